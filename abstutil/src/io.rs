@@ -6,11 +6,11 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json;
 use std;
 use std::cmp::Ord;
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{stdout, BufReader, BufWriter, Error, ErrorKind, Read, Write};
 use std::path::Path;
-use std::time::Instant;
+use std::time::{Instant, SystemTime};
 
 pub fn to_json<T: Serialize>(obj: &T) -> String {
     serde_json::to_string_pretty(obj).unwrap()
@@ -123,28 +123,10 @@ pub fn deserialize_multimap<
 // Just list all things from a directory, return sorted by name, with file extension removed.
 // Pretty hacky that we return a (String, String). Also hacky that map_name can be blank. ;)
 pub fn list_all_objects(dir: &str, map_name: &str) -> Vec<(String, String)> {
-    let mut results: BTreeSet<(String, String)> = BTreeSet::new();
-    match std::fs::read_dir(format!("../data/{}/{}", dir, map_name)) {
-        Ok(iter) => {
-            for entry in iter {
-                let filename = entry.unwrap().file_name();
-                let path = Path::new(&filename);
-                if path.to_string_lossy().ends_with(".swp") {
-                    continue;
-                }
-                let name = path
-                    .file_stem()
-                    .unwrap()
-                    .to_os_string()
-                    .into_string()
-                    .unwrap();
-                results.insert((name.clone(), name));
-            }
-        }
-        Err(ref e) if e.kind() == ErrorKind::NotFound => {}
-        Err(e) => panic!(e),
-    };
-    results.into_iter().collect()
+    list_dir_objects(&format!("../data/{}/{}", dir, map_name))
+        .into_iter()
+        .map(|(name, _)| (name.clone(), name))
+        .collect()
 }
 
 // Load all serialized things from a directory, return sorted by name, with file extension removed.
@@ -154,43 +136,52 @@ pub fn load_all_objects<T: DeserializeOwned>(dir: &str, map_name: &str) -> Vec<(
         "load_all_objects from ../data/{}/{}/",
         dir, map_name
     ));
-    let mut tree: BTreeMap<String, T> = BTreeMap::new();
-    match std::fs::read_dir(format!("../data/{}/{}/", dir, map_name)) {
+    let mut results = Vec::new();
+    for (name, filename) in list_dir_objects(&format!("../data/{}/{}", dir, map_name)) {
+        let path = format!("../data/{}/{}/{}", dir, map_name, filename);
+        let load: T = if filename.ends_with(".json") {
+            read_json(&path).unwrap()
+        } else if filename.ends_with(".bin") {
+            read_binary(&path, &mut timer).unwrap()
+        } else {
+            panic!("Don't know what {} is", path);
+        };
+        results.push((name, load));
+    }
+    results
+}
+
+// Returns (name with the extension removed, filename) for everything in a directory, sorted by
+// name. When the same name exists in multiple formats, the JSON version wins, since that's the one
+// people edit by hand.
+pub fn list_dir_objects(path: &str) -> Vec<(String, String)> {
+    let mut results: BTreeMap<String, String> = BTreeMap::new();
+    match std::fs::read_dir(path) {
         Ok(iter) => {
             for entry in iter {
-                let filename = entry.unwrap().file_name();
-                let path = Path::new(&filename);
-                let path_str = path.to_string_lossy();
-                if path_str.ends_with(".swp") {
+                let filename = entry.unwrap().file_name().into_string().unwrap();
+                if filename.ends_with(".swp") {
                     continue;
                 }
-                let name = path
+                let name = Path::new(&filename)
                     .file_stem()
                     .unwrap()
                     .to_os_string()
                     .into_string()
                     .unwrap();
-                let load: T = if path_str.ends_with(".json") {
-                    read_json(&format!("../data/{}/{}/{}.json", dir, map_name, name)).unwrap()
-                } else if path_str.ends_with(".bin") {
-                    read_binary(
-                        &format!("../data/{}/{}/{}.bin", dir, map_name, name),
-                        &mut timer,
-                    )
-                    .unwrap()
-                } else {
-                    panic!(
-                        "Don't know what ../data/{}/{}/{} is",
-                        dir, map_name, path_str
-                    );
+                let replace = match results.get(&name) {
+                    Some(existing) => !existing.ends_with(".json") && filename.ends_with(".json"),
+                    None => true,
                 };
-                tree.insert(name, load);
+                if replace {
+                    results.insert(name, filename);
+                }
             }
         }
         Err(ref e) if e.kind() == ErrorKind::NotFound => {}
         Err(e) => panic!(e),
     };
-    tree.into_iter().collect()
+    results.into_iter().collect()
 }
 
 pub fn save_json_object<T: Serialize>(dir: &str, map_name: &str, obj_name: &str, obj: &T) {
@@ -326,3 +317,40 @@ pub fn basename(path: &str) -> String {
         .into_string()
         .unwrap()
 }
+
+// Notices when a file is changed by something else, like a text editor. Polling the metadata is
+// cheap enough to do every frame, so no need for OS-specific notifications.
+pub struct FileWatcher {
+    path: String,
+    last_seen: Option<(SystemTime, u64)>,
+}
+
+impl FileWatcher {
+    pub fn new(path: &str) -> FileWatcher {
+        FileWatcher {
+            path: path.to_string(),
+            last_seen: FileWatcher::stat(path),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // True if the file was modified, created, or deleted since the last call (or since creation).
+    pub fn changed(&mut self) -> bool {
+        let now = FileWatcher::stat(&self.path);
+        if now == self.last_seen {
+            return false;
+        }
+        self.last_seen = now;
+        true
+    }
+
+    // Some editors save by truncating and then writing, so the length catches rapid successive
+    // changes within the filesystem's mtime granularity.
+    fn stat(path: &str) -> Option<(SystemTime, u64)> {
+        let md = std::fs::metadata(path).ok()?;
+        Some((md.modified().ok()?, md.len()))
+    }
+}
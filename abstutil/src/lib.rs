@@ -12,15 +12,16 @@ pub use crate::collections::{contains_duplicates, retain_btreemap, wraparound_ge
 pub use crate::error::Error;
 pub use crate::io::{
     basename, deserialize_btreemap, deserialize_multimap, find_next_file, find_prev_file,
-    list_all_objects, load_all_objects, read_binary, read_json, save_binary_object,
-    save_json_object, serialize_btreemap, serialize_multimap, to_json, write_binary, write_json,
-    FileWithProgress,
+    list_all_objects, list_dir_objects, load_all_objects, read_binary, read_json,
+    save_binary_object, save_json_object, serialize_btreemap, serialize_multimap, to_json,
+    write_binary, write_json, FileWatcher, FileWithProgress,
 };
 pub use crate::logs::Warn;
 pub use crate::notes::note;
 pub use crate::random::{fork_rng, WeightedUsizeChoice};
 pub use crate::time::{
-    elapsed_seconds, prettyprint_usize, MeasureMemory, Profiler, Timer, TimerSink,
+    elapsed_seconds, prettyprint_usize, MeasureMemory, Profiler, Timer, TimerRecord,
+    TimerRecordKind, TimerSink, TimerWarning,
 };
 
 const PROGRESS_FREQUENCY_SECONDS: f64 = 0.2;
@@ -1,4 +1,5 @@
-use crate::{notes, PROGRESS_FREQUENCY_SECONDS};
+use crate::{notes, to_json, PROGRESS_FREQUENCY_SECONDS};
+use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{stdout, BufReader, Error, Read, Write};
@@ -91,11 +92,111 @@ enum StackEntry {
     File(TimedFileReader),
 }
 
+// A warning, along with the names of the spans and loops the Timer was in when it happened,
+// outermost first.
+#[derive(Clone, Debug)]
+pub struct TimerWarning {
+    pub context: Vec<String>,
+    pub message: String,
+}
+
 pub trait TimerSink {
     fn println(&mut self, line: String);
     fn reprintln(&mut self, line: String);
 }
 
+// The machine-readable version of one span, start_iter loop, or file read. Times are wall-clock
+// seconds, matching what's printed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimerRecord {
+    pub name: String,
+    pub kind: TimerRecordKind,
+    // How many spans, loops, or files this is nested in
+    pub depth: usize,
+    // For something still in progress, the time so far
+    pub seconds: f64,
+    // Items for a loop, bytes for a file, and always 1 for a span
+    pub count: usize,
+    // Anything noted or warned about directly inside of this, not in children
+    pub notes: Vec<String>,
+    pub warnings: Vec<String>,
+    pub children: Vec<TimerRecord>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TimerRecordKind {
+    Span,
+    Loop,
+    File,
+}
+
+impl TimerRecord {
+    // Depth-first, starting with this record
+    pub fn find(&self, name: &str) -> Option<&TimerRecord> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter().filter_map(|c| c.find(name)).next()
+    }
+
+    fn shift_depth(&mut self, by: usize) {
+        self.depth += by;
+        for c in self.children.iter_mut() {
+            c.shift_depth(by);
+        }
+    }
+}
+
+// Mirrors the Timer's stack with the records that haven't finished yet.
+struct TimerRecords {
+    finished: Vec<TimerRecord>,
+    open: Vec<(Instant, TimerRecord)>,
+}
+
+impl TimerRecords {
+    fn push(&mut self, name: &str, kind: TimerRecordKind, count: usize, started_at: Instant) {
+        let depth = self.open.len();
+        self.open.push((
+            started_at,
+            TimerRecord {
+                name: name.to_string(),
+                kind,
+                depth,
+                seconds: 0.0,
+                count,
+                notes: Vec::new(),
+                warnings: Vec::new(),
+                children: Vec::new(),
+            },
+        ));
+    }
+
+    fn pop(&mut self, seconds: f64) {
+        let (_, mut record) = self.open.pop().unwrap();
+        record.seconds = seconds;
+        match self.open.last_mut() {
+            Some((_, parent)) => parent.children.push(record),
+            None => self.finished.push(record),
+        }
+    }
+
+    // Things still open are closed off with the time so far.
+    fn snapshot(&self) -> Vec<TimerRecord> {
+        let mut results = self.finished.clone();
+        let mut child: Option<TimerRecord> = None;
+        for (started_at, record) in self.open.iter().rev() {
+            let mut record = record.clone();
+            record.seconds = elapsed_seconds(*started_at);
+            if let Some(c) = child {
+                record.children.push(c);
+            }
+            child = Some(record);
+        }
+        results.extend(child);
+        results
+    }
+}
+
 // Hierarchial magic
 pub struct Timer<'a> {
     results: Vec<String>,
@@ -105,6 +206,12 @@ pub struct Timer<'a> {
 
     notes: Vec<String>,
     pub(crate) warnings: Vec<String>,
+    // Only filled out after collect_warnings()
+    collected_warnings: Option<Vec<TimerWarning>>,
+    // Only filled out after record_timings()
+    records: Option<TimerRecords>,
+    // One side of run_pair. It's stopped and merged into its parent instead of printing results.
+    is_child: bool,
 
     sink: Option<Box<TimerSink + 'a>>,
 }
@@ -124,6 +231,9 @@ impl<'a> Timer<'a> {
             outermost_name: name.to_string(),
             notes: Vec::new(),
             warnings: Vec::new(),
+            collected_warnings: None,
+            records: None,
+            is_child: false,
             sink: None,
         };
         t.start(name);
@@ -162,21 +272,116 @@ impl<'a> Timer<'a> {
         }
 
         self.println(line.clone());
+        if let Some(ref mut records) = self.records {
+            if let Some((_, r)) = records.open.last_mut() {
+                r.notes.push(line.clone());
+            }
+        }
         self.notes.push(line);
     }
 
     pub fn warn(&mut self, line: String) {
+        if let Some(ref mut collected) = self.collected_warnings {
+            collected.push(TimerWarning {
+                context: Timer::context(&self.stack),
+                message: line.clone(),
+            });
+        }
+        if let Some(ref mut records) = self.records {
+            if let Some((_, r)) = records.open.last_mut() {
+                r.warnings.push(line.clone());
+            }
+        }
         self.warnings.push(line);
     }
 
+    // Workaround for borrow checker
+    fn context(stack: &[StackEntry]) -> Vec<String> {
+        stack
+            .iter()
+            .map(|entry| match entry {
+                StackEntry::TimerSpan(s) => s.name.clone(),
+                StackEntry::Progress(p) => p.label.clone(),
+                StackEntry::File(f) => f.path.clone(),
+            })
+            .collect()
+    }
+
+    // Warnings are still printed when the Timer is dropped, but from now on, they're also kept
+    // with their context until take_warnings().
+    pub fn collect_warnings(&mut self) {
+        if self.collected_warnings.is_none() {
+            self.collected_warnings = Some(Vec::new());
+        }
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<TimerWarning> {
+        match self.collected_warnings {
+            Some(ref mut collected) => std::mem::replace(collected, Vec::new()),
+            None => Vec::new(),
+        }
+    }
+
+    // From now on, also keep a tree of TimerRecords, for tools and tests that want timing data
+    // without parsing the printed output. Whatever is already in progress is included.
+    pub fn record_timings(&mut self) {
+        if self.records.is_some() {
+            return;
+        }
+        let mut records = TimerRecords {
+            finished: Vec::new(),
+            open: Vec::new(),
+        };
+        for entry in &self.stack {
+            match entry {
+                StackEntry::TimerSpan(s) => {
+                    records.push(&s.name, TimerRecordKind::Span, 1, s.started_at)
+                }
+                StackEntry::Progress(p) => {
+                    records.push(&p.label, TimerRecordKind::Loop, p.total_items, p.started_at)
+                }
+                StackEntry::File(f) => {
+                    records.push(&f.path, TimerRecordKind::File, f.total_bytes, f.started_at)
+                }
+            }
+        }
+        self.records = Some(records);
+    }
+
+    // Empty unless record_timings() was called. The outermost span is still going until the Timer
+    // is dropped, so its time is just what's elapsed so far.
+    pub fn timing_records(&self) -> Vec<TimerRecord> {
+        match self.records {
+            Some(ref records) => records.snapshot(),
+            None => Vec::new(),
+        }
+    }
+
+    // The first span, loop, or file with this name, searching depth-first
+    pub fn find_timing(&self, name: &str) -> Option<TimerRecord> {
+        self.timing_records()
+            .iter()
+            .filter_map(|r| r.find(name))
+            .next()
+            .cloned()
+    }
+
+    pub fn done_json(&self) -> String {
+        to_json(&self.timing_records())
+    }
+
     // Used to end the scope of a timer early.
     pub fn done(self) {}
 
     pub fn start(&mut self, name: &str) {
         self.println(format!("{}...", name));
+        let started_at = Instant::now();
+        if let Some(ref mut records) = self.records {
+            records.push(name, TimerRecordKind::Span, 1, started_at);
+        }
         self.stack.push(StackEntry::TimerSpan(TimerSpan {
             name: name.to_string(),
-            started_at: Instant::now(),
+            started_at,
             nested_results: Vec::new(),
             nested_time: 0.0,
         }));
@@ -189,6 +394,9 @@ impl<'a> Timer<'a> {
         };
         assert_eq!(span.name, name);
         let elapsed = elapsed_seconds(span.started_at);
+        if let Some(ref mut records) = self.records {
+            records.pop(elapsed);
+        }
         let line = format!("{} took {}", name, prettyprint_time(elapsed));
 
         let padding = "  ".repeat(self.stack.len());
@@ -246,8 +454,16 @@ impl<'a> Timer<'a> {
             );
         }
 
-        self.stack
-            .push(StackEntry::Progress(Progress::new(name, total_items)));
+        let progress = Progress::new(name, total_items);
+        if let Some(ref mut records) = self.records {
+            records.push(
+                name,
+                TimerRecordKind::Loop,
+                total_items,
+                progress.started_at,
+            );
+        }
+        self.stack.push(StackEntry::Progress(progress));
     }
 
     pub fn next(&mut self) {
@@ -259,6 +475,9 @@ impl<'a> Timer<'a> {
             };
         if let Some((elapsed, result)) = maybe_result {
             self.stack.pop();
+            if let Some(ref mut records) = self.records {
+                records.pop(elapsed);
+            }
             self.add_result(elapsed, result);
         }
     }
@@ -322,16 +541,117 @@ impl<'a> Timer<'a> {
         })
     }
 
+    // Runs two independent pieces of work and returns both results. Without parallel, they just
+    // run one after the other, as spans called name1 and name2. With parallel, each one gets its
+    // own Timer on another thread, and whatever those measure, note, and warn about is merged
+    // back afterwards, so the results and records have the same shape either way.
+    pub fn run_pair<A, B, F1, F2>(
+        &mut self,
+        parallel: bool,
+        name1: &str,
+        f1: F1,
+        name2: &str,
+        f2: F2,
+    ) -> (A, B)
+    where
+        A: Send,
+        B: Send,
+        F1: Send + FnOnce(&mut Timer) -> A,
+        F2: Send + FnOnce(&mut Timer) -> B,
+    {
+        if !parallel {
+            self.start(name1);
+            let a = f1(self);
+            self.stop(name1);
+            self.start(name2);
+            let b = f2(self);
+            self.stop(name2);
+            return (a, b);
+        }
+
+        let record = self.records.is_some();
+        let collect = self.collected_warnings.is_some();
+        let started_at = Instant::now();
+        let mut result1 = None;
+        let mut result2 = None;
+        scoped_threadpool::Pool::new(2).scoped(|scope| {
+            let (result1, result2) = (&mut result1, &mut result2);
+            scope.execute(move || {
+                *result1 = Some(ChildTimer::run(name1, record, collect, f1));
+            });
+            scope.execute(move || {
+                *result2 = Some(ChildTimer::run(name2, record, collect, f2));
+            });
+        });
+        let (a, child1) = result1.unwrap();
+        let (b, child2) = result2.unwrap();
+        self.merge(child1);
+        self.merge(child2);
+        // The children overlapped, so only count the wall-clock time they took together.
+        if let Some(StackEntry::TimerSpan(ref mut s)) = self.stack.last_mut() {
+            s.nested_time += elapsed_seconds(started_at);
+        }
+        (a, b)
+    }
+
+    fn merge(&mut self, child: ChildTimer) {
+        let padding = "  ".repeat(self.stack.len());
+        let lines = child
+            .results
+            .into_iter()
+            .map(|line| format!("{}{}", padding, line));
+        match self.stack.last_mut() {
+            Some(StackEntry::TimerSpan(ref mut s)) => s.nested_results.extend(lines),
+            Some(_) => panic!("Can't merge a child Timer while a loop or file is top of the stack"),
+            None => self.results.extend(lines),
+        }
+
+        self.notes.extend(child.notes);
+        self.warnings.extend(child.warnings);
+        if let (Some(ref mut collected), Some(warnings)) =
+            (&mut self.collected_warnings, child.collected_warnings)
+        {
+            let context = Timer::context(&self.stack);
+            for mut w in warnings {
+                let mut full_context = context.clone();
+                full_context.extend(w.context);
+                w.context = full_context;
+                collected.push(w);
+            }
+        }
+        if let Some(ref mut records) = self.records {
+            let depth = records.open.len();
+            for mut r in child.records {
+                r.shift_depth(depth);
+                match records.open.last_mut() {
+                    Some((_, parent)) => parent.children.push(r),
+                    None => records.finished.push(r),
+                }
+            }
+        }
+    }
+
     // Then the caller passes this in as a reader
     pub fn read_file(&mut self, path: &str) -> Result<(), Error> {
-        self.stack
-            .push(StackEntry::File(TimedFileReader::new(path)?));
+        let file = TimedFileReader::new(path)?;
+        if let Some(ref mut records) = self.records {
+            records.push(
+                path,
+                TimerRecordKind::File,
+                file.total_bytes,
+                file.started_at,
+            );
+        }
+        self.stack.push(StackEntry::File(file));
         Ok(())
     }
 }
 
 impl<'a> std::ops::Drop for Timer<'a> {
     fn drop(&mut self) {
+        if self.is_child {
+            return;
+        }
         let stop_name = self.outermost_name.clone();
 
         // If we're in the middle of unwinding a panic, don't further blow up.
@@ -376,6 +696,44 @@ impl<'a> std::ops::Drop for Timer<'a> {
     }
 }
 
+// Everything one side of run_pair measured, noted, and warned about, sent back from its thread
+struct ChildTimer {
+    results: Vec<String>,
+    notes: Vec<String>,
+    warnings: Vec<String>,
+    collected_warnings: Option<Vec<TimerWarning>>,
+    records: Vec<TimerRecord>,
+}
+
+impl ChildTimer {
+    fn run<T, F: FnOnce(&mut Timer) -> T>(
+        name: &str,
+        record: bool,
+        collect: bool,
+        f: F,
+    ) -> (T, ChildTimer) {
+        let mut timer = Timer::new(name);
+        timer.is_child = true;
+        if record {
+            timer.record_timings();
+        }
+        if collect {
+            timer.collect_warnings();
+        }
+        let result = f(&mut timer);
+        timer.stop(name);
+
+        let child = ChildTimer {
+            results: std::mem::replace(&mut timer.results, Vec::new()),
+            notes: std::mem::replace(&mut timer.notes, Vec::new()),
+            warnings: std::mem::replace(&mut timer.warnings, Vec::new()),
+            collected_warnings: timer.collected_warnings.take(),
+            records: timer.timing_records(),
+        };
+        (result, child)
+    }
+}
+
 // For repeated things
 // TODO Why does the PartialEq derivation in sim require this?
 pub struct Profiler {
@@ -594,6 +952,9 @@ impl<'a> Read for Timer<'a> {
                 }
             }
             self.stack.pop();
+            if let Some(ref mut records) = self.records {
+                records.pop(elapsed);
+            }
             self.add_result(elapsed, line);
         } else if file.last_printed_at.is_none()
             || elapsed_seconds(file.last_printed_at.unwrap()) >= PROGRESS_FREQUENCY_SECONDS
@@ -0,0 +1,19 @@
+use abstutil::Timer;
+use map_model::{Map, MapEdits};
+use std::io::Error;
+
+// Loads a map and applies the named edits to it, the same way the editor does when saving edits.
+pub fn load_edited_map(map_path: &str, edits_name: &str, timer: &mut Timer) -> Result<Map, Error> {
+    let map: Map = abstutil::read_binary(map_path, timer)?;
+    let edits = MapEdits::load(map.get_name(), edits_name);
+    Ok(apply_to_map(map, edits, timer))
+}
+
+// Like load_edited_map, for edits that aren't saved under the map's usual edits directory.
+pub fn apply_to_map(mut map: Map, edits: MapEdits, timer: &mut Timer) -> Map {
+    timer.start("apply edits");
+    map.apply_edits(edits, timer);
+    map.simplify_edits(timer);
+    timer.stop("apply edits");
+    map
+}
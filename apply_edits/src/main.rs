@@ -0,0 +1,64 @@
+use abstutil::Timer;
+use map_model::MapEdits;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "apply_edits")]
+struct Flags {
+    /// Map to edit
+    #[structopt(long = "map")]
+    map: String,
+
+    /// Name of the edits to apply
+    #[structopt(long = "edits")]
+    edits: String,
+
+    /// Where to write the edited map
+    #[structopt(long = "output")]
+    output: Option<String>,
+
+    /// Name of other edits to compare against
+    #[structopt(long = "diff")]
+    diff: Option<String>,
+}
+
+fn main() {
+    let flags = Flags::from_args();
+    let mut timer = Timer::new(&format!("apply {} to {}", flags.edits, flags.map));
+
+    let map = apply_edits::load_edited_map(&flags.map, &flags.edits, &mut timer)
+        .expect(&format!("Couldn't load {}", flags.map));
+
+    if let Some(ref other) = flags.diff {
+        let lines = map
+            .get_edits()
+            .diff(&MapEdits::load(map.get_name(), other))
+            .describe();
+        println!(
+            "Going from {} to {} changes {} things",
+            flags.edits,
+            other,
+            lines.len()
+        );
+        for line in lines {
+            println!("  {}", line);
+        }
+    }
+
+    if let Some(ref path) = flags.output {
+        timer.start("save map");
+        abstutil::write_binary(path, &map).expect(&format!("Saving {} failed", path));
+        timer.stop("save map");
+        println!("Saved {}", path);
+    }
+
+    let report = map.connectivity_audit();
+    println!("{} connectivity problems", report.num_problems());
+    for line in report.describe() {
+        println!("  {}", line);
+    }
+    // Agents can't route through orphaned lanes at all, so refuse to bless the edits.
+    if !report.no_incoming_turns.is_empty() || !report.no_outgoing_turns.is_empty() {
+        std::process::exit(1);
+    }
+}
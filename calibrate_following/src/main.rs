@@ -0,0 +1,204 @@
+use abstutil::Timer;
+use geom::{Distance, Duration, Speed};
+use map_fixtures::{driving_lane, oneway, pt, raw_map, set_boundary};
+use map_model::{IntersectionType, LaneID, LaneType, Map, Position, Traversable};
+use sim::{
+    DrivingGoal, DrivingParams, GetDrawAgents, Sim, TripSpec, VehicleClass, VehicleSpec,
+    VehicleType,
+};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "calibrate_following")]
+struct Flags {
+    /// Comma-separated minimum gaps between cars to try, in meters
+    #[structopt(long = "min_gaps", default_value = "0.5,1,2,4")]
+    min_gaps: String,
+
+    /// Comma-separated following headways to try, in seconds
+    #[structopt(long = "headways", default_value = "0,0.5,1,2")]
+    headways: String,
+
+    /// How many cars to push through the corridor for each combination
+    #[structopt(long = "num_cars", default_value = "50")]
+    num_cars: usize,
+
+    /// Where to write the CSV results
+    #[structopt(long = "output", default_value = "car_following.csv")]
+    output: String,
+}
+
+// Sample the corridor this often
+const DT: Duration = Duration::const_seconds(1.0);
+
+fn main() {
+    let flags = Flags::from_args();
+    let min_gaps = parse_list(&flags.min_gaps);
+    let headways = parse_list(&flags.headways);
+
+    let mut timer = Timer::new("calibrate car following");
+    let (map, corridor) = make_corridor(&mut timer);
+
+    let mut csv =
+        "min_gap_m,headway_s,cars,flow_veh_per_hr,density_veh_per_km,speed_m_per_s\n".to_string();
+    timer.start_iter("run parameter grid", min_gaps.len() * headways.len());
+    for min_gap in &min_gaps {
+        for headway in &headways {
+            timer.next();
+            let params = DrivingParams {
+                min_gap: Distance::meters(*min_gap),
+                headway: Duration::seconds(*headway),
+            };
+            let m = measure(&map, &corridor, params, flags.num_cars);
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                min_gap, headway, m.cars, m.flow, m.density, m.speed
+            ));
+        }
+    }
+
+    std::fs::write(&flags.output, csv).unwrap();
+    println!("Wrote {}", flags.output);
+}
+
+fn parse_list(list: &str) -> Vec<f64> {
+    list.split(',')
+        .map(|x| {
+            x.trim()
+                .parse::<f64>()
+                .unwrap_or_else(|_| panic!("Couldn't parse {} as a number", x))
+        })
+        .collect()
+}
+
+// The corridor's start, middle, and end lanes. Only the middle lane is measured, so cars have
+// already settled into a queue when they enter it and aren't held up by the border when they leave.
+struct Corridor {
+    start: LaneID,
+    middle: LaneID,
+    end: LaneID,
+}
+
+// A straight one-way, one-lane street split into three pieces, with a border at each end.
+fn make_corridor(timer: &mut Timer) -> (Map, Corridor) {
+    let roads = vec![
+        oneway(1, vec![pt(0.0, 0.0), pt(0.004, 0.0)]),
+        oneway(2, vec![pt(0.004, 0.0), pt(0.008, 0.0)]),
+        oneway(3, vec![pt(0.008, 0.0), pt(0.012, 0.0)]),
+    ];
+
+    let mut raw = raw_map(roads, Vec::new());
+    for i in raw.intersections.values_mut() {
+        i.intersection_type = if i.point == pt(0.0, 0.0) || i.point == pt(0.012, 0.0) {
+            IntersectionType::Border
+        } else {
+            // Only one road in and out, so the stop sign never actually stops anybody
+            IntersectionType::StopSign
+        };
+    }
+    set_boundary(&mut raw, pt(-0.001, -0.001), pt(0.013, 0.001));
+    let map = Map::create_from_raw("calibrate_following".to_string(), raw, timer);
+
+    let corridor = Corridor {
+        start: driving_lane(&map, 1),
+        middle: driving_lane(&map, 2),
+        end: driving_lane(&map, 3),
+    };
+    (map, corridor)
+}
+
+struct Measurement {
+    // How many cars made it all the way through the middle lane
+    cars: usize,
+    // Cars per hour leaving the middle lane, from the first to the last
+    flow: f64,
+    // Average cars per km on the middle lane, while anybody is on it
+    density: f64,
+    // Average speed across the middle lane, in m/s
+    speed: f64,
+}
+
+// Release a saturated queue of identical cars into the corridor, then watch the middle lane.
+fn measure(map: &Map, corridor: &Corridor, params: DrivingParams, num_cars: usize) -> Measurement {
+    let mut sim = Sim::new(map, "calibrate_following".to_string(), None);
+    sim.set_driving_params(params);
+    let vehicle = VehicleSpec {
+        vehicle_type: VehicleType::Car,
+        class: VehicleClass::Car,
+        length: Distance::meters(5.0),
+        max_speed: None,
+        occupancy: 1,
+    };
+    for _ in 0..num_cars {
+        sim.schedule_trip(
+            Duration::ZERO,
+            TripSpec::CarAppearing {
+                start_pos: Position::new(corridor.start, vehicle.length),
+                goal: DrivingGoal::end_at_border(
+                    map.get_l(corridor.end).dst_i,
+                    vec![LaneType::Driving],
+                    map,
+                )
+                .unwrap(),
+                vehicle_spec: vehicle.clone(),
+                ped_speed: Speed::miles_per_hour(3.0),
+            },
+            map,
+        );
+    }
+    // Cars that don't fit yet keep retrying to spawn
+    sim.spawn_all_trips(map, &mut Timer::throwaway(), true);
+
+    let lane_len = map.get_l(corridor.middle).length();
+    let mut exits = 0;
+    let mut first_exit: Option<Duration> = None;
+    let mut last_exit = Duration::ZERO;
+    let mut occupied_samples = 0;
+    let mut occupied_total = 0;
+    while !sim.is_done() {
+        sim.step(map, DT);
+
+        let count = sim
+            .get_draw_cars(Traversable::Lane(corridor.middle), map)
+            .len();
+        if count > 0 {
+            occupied_samples += 1;
+            occupied_total += count;
+        }
+
+        let now_exited = sim
+            .lane_travel_times()
+            .get(&corridor.middle)
+            .map(|(n, _)| *n)
+            .unwrap_or(0);
+        if now_exited > exits {
+            exits = now_exited;
+            if first_exit.is_none() {
+                first_exit = Some(sim.time());
+            }
+            last_exit = sim.time();
+        }
+    }
+
+    let flow = match first_exit {
+        Some(t) if last_exit > t && exits > 1 => {
+            ((exits - 1) as f64) / (last_exit - t).inner_seconds() * 3600.0
+        }
+        _ => 0.0,
+    };
+    let density = if occupied_samples == 0 {
+        0.0
+    } else {
+        (occupied_total as f64) / (occupied_samples as f64) / (lane_len.inner_meters() / 1000.0)
+    };
+    let speed = match sim.lane_travel_times().get(&corridor.middle) {
+        Some((_, avg)) => lane_len.inner_meters() / avg.inner_seconds(),
+        None => 0.0,
+    };
+    Measurement {
+        cars: exits,
+        flow,
+        density,
+        speed,
+    }
+}
@@ -1,6 +1,6 @@
 use abstutil::{retain_btreemap, Timer};
 use clipping::CPolygon;
-use geom::{GPSBounds, PolyLine, Polygon, Pt2D};
+use geom::{GPSBounds, LonLat, PolyLine, Polygon, Pt2D};
 use map_model::{raw_data, IntersectionType};
 
 pub fn clip_map(map: &mut raw_data::Map, timer: &mut Timer) {
@@ -99,6 +99,18 @@ pub fn clip_map(map: &mut raw_data::Map, timer: &mut Timer) {
             .all(|pt| boundary_poly.contains_pt(pt))
     });
 
+    map.bike_racks.retain(|rack| {
+        Pt2D::from_gps(rack.point, &bounds)
+            .map(|pt| boundary_poly.contains_pt(pt))
+            .unwrap_or(false)
+    });
+
+    map.parking_lots.retain(|lot| {
+        Pt2D::from_gps(LonLat::center(&lot.points), &bounds)
+            .map(|pt| boundary_poly.contains_pt(pt))
+            .unwrap_or(false)
+    });
+
     let mut result_areas = Vec::new();
     for orig_area in map.areas.drain(..) {
         let mut boundary_pts = CPolygon::from_vec(
@@ -2,18 +2,27 @@ mod clip;
 mod neighborhoods;
 mod osm;
 mod remove_disconnected;
+mod road_names;
 mod split_ways;
 
+pub use crate::osm::osm_to_raw_roads;
+pub use crate::road_names::{normalize_name, normalize_road_names, RAW_NAME_TAG};
+pub use crate::split_ways::split_up_roads;
 use abstutil::Timer;
-use geom::{Distance, FindClosest, LonLat, PolyLine, Pt2D};
+use geom::{Distance, Duration, FindClosest, LonLat, PolyLine, Polygon, Pt2D};
 use kml::ExtraShapes;
 use map_model::{raw_data, IntersectionType, LANE_THICKNESS};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use structopt::StructOpt;
 
 const MAX_DIST_BTWN_INTERSECTION_AND_SIGNAL: Distance = Distance::const_meters(50.0);
 const MAX_DIST_BTWN_BLDG_PERMIT_AND_BLDG: Distance = Distance::const_meters(10.0);
+// Placeholder buildings for permits get a square footprint this big per unit, within some limits.
+const SYNTHETIC_BLDG_AREA_PER_UNIT_SQ_METERS: f64 = 50.0;
+const MIN_SYNTHETIC_BLDG_SIDE: Distance = Distance::const_meters(8.0);
+const MAX_SYNTHETIC_BLDG_SIDE: Distance = Distance::const_meters(30.0);
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "convert_osm")]
@@ -53,10 +62,42 @@ pub struct Flags {
     /// Disable blockface
     #[structopt(long = "fast_dev")]
     pub fast_dev: bool,
+
+    /// Keep roundabouts as circulating one-way roads, instead of collapsing each into a single
+    /// intersection. Experimental.
+    #[structopt(long = "circulating_roundabouts")]
+    pub circulating_roundabouts: bool,
+
+    /// Import OSM steps as pedestrian-only roads. Experimental.
+    #[structopt(long = "import_steps")]
+    pub import_steps: bool,
+
+    /// Create placeholder buildings for residential permits that don't match any OSM building.
+    /// Experimental.
+    #[structopt(long = "synthesize_buildings")]
+    pub synthesize_buildings: bool,
+
+    /// Spell out abbreviations in road names, so "Main St" and "Main Street" match. The original
+    /// name is kept in another tag.
+    #[structopt(long = "normalize_road_names")]
+    pub normalize_road_names: bool,
 }
 
 pub fn convert(flags: &Flags, timer: &mut abstutil::Timer) -> raw_data::Map {
-    let mut map = split_ways::split_up_roads(osm::osm_to_raw_roads(&flags.osm, timer), timer);
+    let (roads, buildings, areas, traffic_signals, bike_racks, parking_lots) =
+        osm::osm_to_raw_roads(&flags.osm, flags.import_steps, timer);
+    // Node tags are precise, so only fall back to matching the KML when OSM has no signals.
+    let have_signal_tags = !traffic_signals.is_empty();
+    let mut map = split_ways::split_up_roads(
+        (roads, buildings, areas, traffic_signals),
+        flags.circulating_roundabouts,
+        timer,
+    );
+    map.bike_racks = bike_racks;
+    map.parking_lots = parking_lots;
+    if flags.normalize_road_names {
+        road_names::normalize_road_names(&mut map, timer);
+    }
     map.boundary_polygon = read_osmosis_polygon(&flags.clip);
     clip::clip_map(&mut map, timer);
     remove_disconnected::remove_disconnected_roads(&mut map, timer);
@@ -68,12 +109,17 @@ pub fn convert(flags: &Flags, timer: &mut abstutil::Timer) -> raw_data::Map {
     map.compute_gps_bounds();
 
     if !flags.residential_buildings.is_empty() {
-        handle_residences(&mut map, &flags.residential_buildings, timer);
+        handle_residences(
+            &mut map,
+            &flags.residential_buildings,
+            flags.synthesize_buildings,
+            timer,
+        );
     }
     if !flags.parking_shapes.is_empty() {
         use_parking_hints(&mut map, &flags.parking_shapes, timer);
     }
-    if !flags.traffic_signals.is_empty() {
+    if !flags.traffic_signals.is_empty() && !have_signal_tags {
         handle_traffic_signals(&mut map, &flags.traffic_signals, timer);
     }
     if !flags.gtfs.is_empty() {
@@ -127,15 +173,39 @@ fn use_parking_hints(map: &mut raw_data::Map, path: &str, timer: &mut Timer) {
             // TODO Long blockfaces sometimes cover two roads. Should maybe find ALL matches within
             // the threshold distance?
             let middle = PolyLine::new(pts).middle();
-            if let Some(((r, fwds), _)) = closest.closest_pt(middle, LANE_THICKNESS * 5.0) {
+            let candidates: Vec<((raw_data::StableRoadID, bool), isize)> = closest
+                .all_close_pts(middle, LANE_THICKNESS * 5.0)
+                .into_iter()
+                .map(|((r, fwds), _, _)| ((r, fwds), map.roads[&r].get_zorder()))
+                .collect();
+            let shape_layer = s
+                .attributes
+                .get("layer")
+                .and_then(|l| l.parse::<isize>().ok());
+            if let Some(((r, fwds), ambiguous)) =
+                raw_data::pick_layered_match(&candidates, shape_layer)
+            {
+                if ambiguous {
+                    timer.warn(format!(
+                        "Blockface near {} matches roads on different levels; picked {} (way {})",
+                        middle, r, map.roads[&r].osm_way_id
+                    ));
+                }
                 let category = s.attributes.get("PARKING_CATEGORY");
                 let has_parking = category != Some(&"None".to_string())
                     && category != Some(&"No Parking Allowed".to_string());
+                let hours = s
+                    .attributes
+                    .get("PEAK_HOUR")
+                    .and_then(|restriction| parse_parking_hours(restriction));
                 // Blindly override prior values.
+                let road = map.roads.get_mut(&r).unwrap();
                 if fwds {
-                    map.roads.get_mut(&r).unwrap().parking_lane_fwd = has_parking;
+                    road.parking_lane_fwd = has_parking;
+                    road.parking_hours_fwd = hours;
                 } else {
-                    map.roads.get_mut(&r).unwrap().parking_lane_back = has_parking;
+                    road.parking_lane_back = has_parking;
+                    road.parking_hours_back = hours;
                 }
             }
         }
@@ -143,6 +213,73 @@ fn use_parking_hints(map: &mut raw_data::Map, path: &str, timer: &mut Timer) {
     timer.stop("apply parking hints");
 }
 
+// Blockface describes peak-hour restrictions like "NO PARKING 7AM-9AM, 4-6PM". Returns the windows
+// when parking IS allowed -- the gaps between restrictions -- or None if nothing was understood.
+fn parse_parking_hours(restriction: &str) -> Option<Vec<(Duration, Duration)>> {
+    let mut disallowed: Vec<(Duration, Duration)> = restriction
+        .split(|c| c == ',' || c == '&' || c == ';')
+        .filter_map(parse_time_range)
+        .collect();
+    if disallowed.is_empty() {
+        return None;
+    }
+    disallowed.sort_by_key(|(start, _)| *start);
+
+    let mut allowed = Vec::new();
+    for (idx, (_, end)) in disallowed.iter().enumerate() {
+        let next_start = disallowed[(idx + 1) % disallowed.len()].0;
+        if *end != next_start {
+            allowed.push((*end, next_start));
+        }
+    }
+    Some(allowed)
+}
+
+// Handles "7AM-9AM", "7-9AM", and "3:30-6:30PM", ignoring any text before the first digit.
+fn parse_time_range(text: &str) -> Option<(Duration, Duration)> {
+    let text: String = text
+        .to_uppercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let text = &text[text.find(|c: char| c.is_ascii_digit())?..];
+    let parts: Vec<&str> = text.split('-').collect();
+    if parts.len() != 2 {
+        return None;
+    }
+    let (end, end_pm) = parse_time_of_day(parts[1], None)?;
+    let (start, _) = parse_time_of_day(parts[0], Some(end_pm))?;
+    Some((start, end))
+}
+
+// Returns the time since midnight and whether it was PM. Without an AM/PM suffix, uses the
+// default.
+fn parse_time_of_day(text: &str, default_pm: Option<bool>) -> Option<(Duration, bool)> {
+    let (text, pm) = if text.ends_with("AM") {
+        (&text[..text.len() - 2], false)
+    } else if text.ends_with("PM") {
+        (&text[..text.len() - 2], true)
+    } else {
+        (text, default_pm?)
+    };
+    let mut parts = text.split(':');
+    let hours = parts.next()?.parse::<usize>().ok()?;
+    let mins = match parts.next() {
+        Some(m) => m.parse::<usize>().ok()?,
+        None => 0,
+    };
+    if hours == 0 || hours > 12 || mins >= 60 || parts.next().is_some() {
+        return None;
+    }
+    let hours = match (hours, pm) {
+        (12, false) => 0,
+        (12, true) => 12,
+        (h, false) => h,
+        (h, true) => h + 12,
+    };
+    Some((Duration::minutes(60 * hours + mins), pm))
+}
+
 fn handle_traffic_signals(map: &mut raw_data::Map, path: &str, timer: &mut Timer) {
     timer.start("handle traffic signals");
     for shape in kml::load(path, &map.gps_bounds, timer)
@@ -158,12 +295,15 @@ fn handle_traffic_signals(map: &mut raw_data::Map, path: &str, timer: &mut Timer
         if map.gps_bounds.contains(pt) {
             // TODO use a quadtree or some better way to match signals to the closest
             // intersection
-            let closest_intersection = map
+            let (closest_intersection, dist) = map
                 .intersections
                 .values_mut()
-                .min_by_key(|i| pt.gps_dist_meters(i.point))
+                .map(|i| {
+                    let dist = pt.haversine_dist(i.point);
+                    (i, dist)
+                })
+                .min_by_key(|(_, dist)| *dist)
                 .unwrap();
-            let dist = pt.gps_dist_meters(closest_intersection.point);
             if dist <= MAX_DIST_BTWN_INTERSECTION_AND_SIGNAL {
                 if closest_intersection.intersection_type == IntersectionType::TrafficSignal {
                     println!("WARNING: {:?} already has a traffic signal, but there's another one that's {} from it", closest_intersection, dist);
@@ -175,7 +315,7 @@ fn handle_traffic_signals(map: &mut raw_data::Map, path: &str, timer: &mut Timer
     timer.stop("handle traffic signals");
 }
 
-fn handle_residences(map: &mut raw_data::Map, path: &str, timer: &mut Timer) {
+fn handle_residences(map: &mut raw_data::Map, path: &str, synthesize: bool, timer: &mut Timer) {
     timer.start("match residential permits with buildings");
 
     let mut closest: FindClosest<usize> = FindClosest::new(&map.gps_bounds.to_bounds());
@@ -183,10 +323,24 @@ fn handle_residences(map: &mut raw_data::Map, path: &str, timer: &mut Timer) {
         // TODO Ew, have to massage into Pt2D.
         closest.add_gps(idx, &b.points, &map.gps_bounds);
     }
+    // Only needed to keep synthetic buildings off of roads
+    let mut closest_road: FindClosest<raw_data::StableRoadID> =
+        FindClosest::new(&map.gps_bounds.to_bounds());
+    if synthesize {
+        for (id, r) in &map.roads {
+            closest_road.add_gps(*id, &r.points, &map.gps_bounds);
+        }
+    }
+
+    let boundary = Polygon::new(&map.gps_bounds.must_convert(&map.boundary_polygon));
 
     let shapes = kml::load(path, &map.gps_bounds, timer)
         .expect("loading residential buildings failed")
         .shapes;
+    let mut matched = 0;
+    let mut synthesized = 0;
+    let mut out_of_bounds = 0;
+    let mut no_room = 0;
     timer.start_iter("handle residential permits", shapes.len());
     for shape in shapes.into_iter() {
         timer.next();
@@ -198,6 +352,12 @@ fn handle_residences(map: &mut raw_data::Map, path: &str, timer: &mut Timer) {
         }
         let pt = shape.points[0];
         if !map.gps_bounds.contains(pt) {
+            out_of_bounds += 1;
+            continue;
+        }
+        let center = Pt2D::from_gps(pt, &map.gps_bounds).unwrap();
+        if !boundary.contains_pt(center) {
+            out_of_bounds += 1;
             continue;
         }
         if let Some(num) = shape
@@ -205,19 +365,89 @@ fn handle_residences(map: &mut raw_data::Map, path: &str, timer: &mut Timer) {
             .get("net_units")
             .and_then(|n| usize::from_str_radix(n, 10).ok())
         {
-            if let Some((idx, _)) = closest.closest_pt(
-                Pt2D::from_gps(pt, &map.gps_bounds).unwrap(),
-                MAX_DIST_BTWN_BLDG_PERMIT_AND_BLDG,
-            ) {
+            if let Some((idx, _)) = closest.closest_pt(center, MAX_DIST_BTWN_BLDG_PERMIT_AND_BLDG) {
                 // Just blindly override with the latest point. The dataset says multiple permits
                 // per building might exist.
                 map.buildings[idx].num_residential_units = Some(num);
+                matched += 1;
+            } else if synthesize {
+                // Negative IDs aren't used by OSM, and each synthetic building needs its own.
+                let osm_way_id = -(synthesized as i64 + 1);
+                if let Some(bldg) =
+                    synthesize_building(map, &closest_road, &boundary, center, num, osm_way_id)
+                {
+                    // Later permits for the same spot should match this.
+                    closest.add_gps(map.buildings.len(), &bldg.points, &map.gps_bounds);
+                    map.buildings.push(bldg);
+                    synthesized += 1;
+                } else {
+                    no_room += 1;
+                }
             }
         }
     }
+    timer.note(format!(
+        "Residential permits: {} matched buildings, {} made new buildings, {} dropped for being \
+         out of bounds, {} dropped for lack of room",
+        matched, synthesized, out_of_bounds, no_room
+    ));
     timer.stop("match residential permits with buildings");
 }
 
+// A square footprint around the permit, shrunk to stay clear of the closest road. None if there's
+// no room at all, between roads and the edge of the map.
+fn synthesize_building(
+    map: &raw_data::Map,
+    closest_road: &FindClosest<raw_data::StableRoadID>,
+    boundary: &Polygon,
+    center: Pt2D,
+    num_units: usize,
+    osm_way_id: i64,
+) -> Option<raw_data::Building> {
+    let mut half_side =
+        Distance::meters((num_units as f64 * SYNTHETIC_BLDG_AREA_PER_UNIT_SQ_METERS).sqrt())
+            .max(MIN_SYNTHETIC_BLDG_SIDE)
+            .min(MAX_SYNTHETIC_BLDG_SIDE)
+            / 2.0;
+    // Wide enough to find roads that a full-size building could hit
+    let search_dist = MAX_SYNTHETIC_BLDG_SIDE + LANE_THICKNESS * 10.0;
+    if let Some((id, road_pt)) = closest_road.closest_pt(center, search_dist) {
+        let spec = map.roads[&id].get_spec();
+        let road_half_width = LANE_THICKNESS * (spec.fwd.len().max(spec.back.len()) as f64);
+        // The corners of the square are furthest out.
+        let max_half_side = (center.dist_to(road_pt) - road_half_width) / 2.0_f64.sqrt();
+        if max_half_side * 2.0 < MIN_SYNTHETIC_BLDG_SIDE {
+            return None;
+        }
+        half_side = half_side.min(max_half_side);
+    }
+
+    let pts = vec![
+        center.offset(-half_side, -half_side),
+        center.offset(half_side, -half_side),
+        center.offset(half_side, half_side),
+        center.offset(-half_side, half_side),
+        center.offset(-half_side, -half_side),
+    ];
+    let mut osm_tags = BTreeMap::new();
+    osm_tags.insert("building".to_string(), "residential".to_string());
+    osm_tags.insert("synthetic".to_string(), "true".to_string());
+    // Near the edge of the map, the footprint might not fit.
+    if !pts.iter().all(|pt| boundary.contains_pt(*pt)) {
+        return None;
+    }
+    let points = pts
+        .into_iter()
+        .map(|pt| pt.to_gps(&map.gps_bounds))
+        .collect::<Option<Vec<LonLat>>>()?;
+    Some(raw_data::Building {
+        points,
+        osm_tags,
+        osm_way_id,
+        num_residential_units: Some(num_units),
+    })
+}
+
 fn read_osmosis_polygon(path: &str) -> Vec<LonLat> {
     let mut pts: Vec<LonLat> = Vec::new();
     for (idx, maybe_line) in BufReader::new(File::open(path).unwrap())
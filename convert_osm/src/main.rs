@@ -1,12 +1,32 @@
 use convert_osm::{convert, Flags};
 use structopt::StructOpt;
 
+#[derive(StructOpt, Debug)]
+#[structopt(name = "convert_osm")]
+struct Args {
+    #[structopt(flatten)]
+    flags: Flags,
+
+    /// Also write how long each step took to this JSON file
+    #[structopt(long = "output_timings")]
+    output_timings: Option<String>,
+}
+
 fn main() {
-    let flags = Flags::from_args();
+    let args = Args::from_args();
+    let flags = args.flags;
     let mut timer = abstutil::Timer::new(&format!("generate {}", flags.output));
+    if args.output_timings.is_some() {
+        timer.record_timings();
+    }
     let map = convert(&flags, &mut timer);
     println!("writing to {}", flags.output);
     timer.start("saving map");
     abstutil::write_binary(&flags.output, &map).expect("serializing map failed");
     timer.stop("saving map");
+
+    if let Some(ref path) = args.output_timings {
+        abstutil::write_json(path, &timer.timing_records()).unwrap();
+        println!("Wrote {}", path);
+    }
 }
@@ -1,16 +1,23 @@
 use abstutil::{FileWithProgress, Timer};
-use geom::LonLat;
+use geom::{LonLat, Speed};
 use map_model::{raw_data, AreaType};
 use osm_xml;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Most racks without a capacity tag are a single staple, holding a bike on each side.
+const DEFAULT_BIKE_RACK_CAPACITY: usize = 2;
 
 pub fn osm_to_raw_roads(
     osm_path: &str,
+    import_steps: bool,
     timer: &mut Timer,
 ) -> (
     Vec<raw_data::Road>,
     Vec<raw_data::Building>,
     Vec<raw_data::Area>,
+    HashSet<i64>,
+    Vec<raw_data::BikeRack>,
+    Vec<raw_data::ParkingLot>,
 ) {
     let (reader, done) = FileWithProgress::new(osm_path).unwrap();
     let doc = osm_xml::OSM::parse(reader).expect("OSM parsing failed");
@@ -22,6 +29,56 @@ pub fn osm_to_raw_roads(
     );
     done(timer);
 
+    // Signals just for pedestrians crossing mid-block aren't intersections.
+    let traffic_signals: HashSet<i64> = doc
+        .nodes
+        .values()
+        .filter(|node| {
+            let tags = tags_to_map(&node.tags);
+            tags.get("highway") == Some(&"traffic_signals".to_string())
+                && !tags.contains_key("crossing")
+        })
+        .map(|node| node.id)
+        .collect();
+
+    let mut bike_racks: Vec<raw_data::BikeRack> = doc
+        .nodes
+        .values()
+        .filter_map(|node| {
+            let tags = tags_to_map(&node.tags);
+            if tags.get("amenity") != Some(&"bicycle_parking".to_string()) {
+                return None;
+            }
+            Some(raw_data::BikeRack {
+                point: LonLat::new(node.lon, node.lat),
+                capacity: tags
+                    .get("capacity")
+                    .and_then(|c| c.parse::<usize>().ok())
+                    .filter(|c| *c > 0)
+                    .unwrap_or(DEFAULT_BIKE_RACK_CAPACITY),
+                osm_node_id: node.id,
+            })
+        })
+        .collect();
+    // Nodes aren't stored in any particular order.
+    bike_racks.sort_by_key(|rack| rack.osm_node_id);
+
+    let mut parking_lots: Vec<raw_data::ParkingLot> = doc
+        .nodes
+        .values()
+        .filter_map(|node| {
+            let tags = tags_to_map(&node.tags);
+            if !is_parking_lot(&tags) {
+                return None;
+            }
+            Some(raw_data::ParkingLot {
+                points: vec![LonLat::new(node.lon, node.lat)],
+                capacity: parse_capacity(&tags),
+                osm_id: node.id,
+            })
+        })
+        .collect();
+
     let mut id_to_way: HashMap<i64, Vec<LonLat>> = HashMap::new();
     let mut roads: Vec<raw_data::Road> = Vec::new();
     let mut buildings: Vec<raw_data::Building> = Vec::new();
@@ -32,10 +89,12 @@ pub fn osm_to_raw_roads(
 
         let mut valid = true;
         let mut pts = Vec::new();
+        let mut node_ids = Vec::new();
         for node_ref in &way.nodes {
             match doc.resolve_reference(node_ref) {
                 osm_xml::Reference::Node(node) => {
                     pts.push(LonLat::new(node.lon, node.lat));
+                    node_ids.push(node.id);
                 }
                 // Don't handle nested ways/relations yet
                 _ => {
@@ -47,16 +106,38 @@ pub fn osm_to_raw_roads(
             continue;
         }
         let tags = tags_to_map(&way.tags);
-        if is_road(&tags) {
+        // Garages are often buildings too, so this doesn't stop the way from being used below.
+        if is_parking_lot(&tags) && pts.len() >= 4 && pts[0] == *pts.last().unwrap() {
+            parking_lots.push(raw_data::ParkingLot {
+                points: pts.clone(),
+                capacity: parse_capacity(&tags),
+                osm_id: way.id,
+            });
+        }
+        if is_road(&tags, import_steps) {
+            let speed_limit = tags.get("maxspeed").and_then(|raw| {
+                let speed = parse_maxspeed(raw);
+                if speed.is_none() {
+                    timer.warn(format!(
+                        "Way {} has unparseable maxspeed {}; using a default",
+                        way.id, raw
+                    ));
+                }
+                speed
+            });
             roads.push(raw_data::Road {
                 osm_way_id: way.id,
                 points: pts,
+                osm_node_ids: node_ids,
                 osm_tags: tags,
                 // We'll fill this out later
                 i1: raw_data::StableIntersectionID(0),
                 i2: raw_data::StableIntersectionID(0),
                 parking_lane_fwd: false,
                 parking_lane_back: false,
+                parking_hours_fwd: None,
+                parking_hours_back: None,
+                speed_limit,
             });
         } else if is_bldg(&tags) {
             buildings.push(raw_data::Building {
@@ -126,7 +207,26 @@ pub fn osm_to_raw_roads(
         }
     }
 
-    (roads, buildings, areas)
+    parking_lots.sort_by_key(|lot| lot.osm_id);
+
+    (
+        roads,
+        buildings,
+        areas,
+        traffic_signals,
+        bike_racks,
+        parking_lots,
+    )
+}
+
+fn is_parking_lot(tags: &BTreeMap<String, String>) -> bool {
+    tags.get("amenity") == Some(&"parking".to_string())
+}
+
+fn parse_capacity(tags: &BTreeMap<String, String>) -> Option<usize> {
+    tags.get("capacity")
+        .and_then(|c| c.parse::<usize>().ok())
+        .filter(|c| *c > 0)
 }
 
 fn tags_to_map(raw_tags: &[osm_xml::Tag]) -> BTreeMap<String, String> {
@@ -136,10 +236,38 @@ fn tags_to_map(raw_tags: &[osm_xml::Tag]) -> BTreeMap<String, String> {
         .collect()
 }
 
-fn is_road(tags: &BTreeMap<String, String>) -> bool {
+// Handles "30 mph", "50 km/h", and plain numbers, which are km/h according to
+// https://wiki.openstreetmap.org/wiki/Key:maxspeed
+fn parse_maxspeed(raw: &str) -> Option<Speed> {
+    let raw = raw.trim();
+    let (number, mph) = if raw.ends_with("mph") {
+        (&raw[0..raw.len() - "mph".len()], true)
+    } else if raw.ends_with("km/h") {
+        (&raw[0..raw.len() - "km/h".len()], false)
+    } else if raw.ends_with("kmh") {
+        (&raw[0..raw.len() - "kmh".len()], false)
+    } else {
+        (raw, false)
+    };
+    let value = number.trim().parse::<f64>().ok()?;
+    if !value.is_finite() || value <= 0.0 {
+        return None;
+    }
+    if mph {
+        Some(Speed::miles_per_hour(value))
+    } else {
+        Some(Speed::km_per_hour(value))
+    }
+}
+
+fn is_road(tags: &BTreeMap<String, String>, import_steps: bool) -> bool {
     if !tags.contains_key("highway") {
         return false;
     }
+    // These only get sidewalks, so nothing with wheels will try to use them.
+    if import_steps && tags.get("highway") == Some(&"steps".to_string()) {
+        return true;
+    }
 
     // https://github.com/Project-OSRM/osrm-backend/blob/master/profiles/car.lua is another
     // potential reference
@@ -0,0 +1,79 @@
+use abstutil::Timer;
+use map_model::raw_data;
+
+// Where the original OSM value winds up, when normalizing changes it
+pub const RAW_NAME_TAG: &str = "name:raw";
+
+// Tags that Road::get_name might use for a name
+const NAME_TAGS: [&str; 2] = ["name", "destination:street"];
+
+// OSM isn't consistent about spelling these out, but edits match roads by name, so make every
+// segment of a street agree.
+pub fn normalize_road_names(map: &mut raw_data::Map, timer: &mut Timer) {
+    timer.start("normalize road names");
+    let mut changed = 0;
+    for r in map.roads.values_mut() {
+        for key in NAME_TAGS.iter() {
+            let orig = match r.osm_tags.get(*key) {
+                Some(name) => name.clone(),
+                None => {
+                    continue;
+                }
+            };
+            let name = normalize_name(&orig);
+            if name != orig {
+                if *key == "name" {
+                    r.osm_tags.insert(RAW_NAME_TAG.to_string(), orig);
+                }
+                r.osm_tags.insert(key.to_string(), name);
+                changed += 1;
+            }
+        }
+    }
+    timer.note(format!("Normalized {} road names", changed));
+    timer.stop("normalize road names");
+}
+
+// Collapses whitespace and spells out abbreviations, so "Main St." and " Main  Street" both become
+// "Main Street".
+pub fn normalize_name(name: &str) -> String {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    let mut result: Vec<String> = Vec::new();
+    for (idx, word) in words.iter().enumerate() {
+        let bare = word.trim_end_matches('.');
+        let expanded = match bare {
+            // At the beginning, this is probably Saint
+            "St" if idx == 0 => None,
+            "St" => Some("Street"),
+            "Ave" | "Av" => Some("Avenue"),
+            "Blvd" => Some("Boulevard"),
+            "Rd" => Some("Road"),
+            "Dr" => Some("Drive"),
+            "Ln" => Some("Lane"),
+            "Pl" => Some("Place"),
+            "Ct" => Some("Court"),
+            "Ter" => Some("Terrace"),
+            "Cir" => Some("Circle"),
+            "Sq" => Some("Square"),
+            "Pkwy" => Some("Parkway"),
+            "Hwy" => Some("Highway"),
+            "Expy" => Some("Expressway"),
+            "Trl" => Some("Trail"),
+            // Directions only if there's something else in the name; "E" alone could be a ref.
+            "N" if words.len() > 1 => Some("North"),
+            "S" if words.len() > 1 => Some("South"),
+            "E" if words.len() > 1 => Some("East"),
+            "W" if words.len() > 1 => Some("West"),
+            "NE" if words.len() > 1 => Some("Northeast"),
+            "NW" if words.len() > 1 => Some("Northwest"),
+            "SE" if words.len() > 1 => Some("Southeast"),
+            "SW" if words.len() > 1 => Some("Southwest"),
+            _ => None,
+        };
+        result.push(match expanded {
+            Some(full) => full.to_string(),
+            None => word.to_string(),
+        });
+    }
+    result.join(" ")
+}
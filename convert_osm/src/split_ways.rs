@@ -1,20 +1,36 @@
 use abstutil::Timer;
-use geom::{HashablePt2D, LonLat};
+use geom::{Distance, HashablePt2D, LonLat};
 use map_model::{raw_data, IntersectionType};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+// Even when keeping circulating roundabouts, mini-roundabouts smaller than this still collapse to
+// one intersection.
+const MIN_CIRCULATING_ROUNDABOUT_DIAMETER: Distance = Distance::const_meters(20.0);
+// OSM sometimes tags the signal on each approach, just before the intersection node.
+const MAX_DIST_BTWN_INTERSECTION_AND_SIGNAL_NODE: Distance = Distance::const_meters(20.0);
 
 pub fn split_up_roads(
-    (mut roads, buildings, areas): (
+    (mut roads, buildings, areas, traffic_signals): (
         Vec<raw_data::Road>,
         Vec<raw_data::Building>,
         Vec<raw_data::Area>,
+        HashSet<i64>,
     ),
+    circulating_roundabouts: bool,
     timer: &mut Timer,
 ) -> raw_data::Map {
     timer.start("splitting up roads");
 
     let mut next_intersection_id = 0;
 
+    // Roundabouts kept as one-way roads around the circle get split wherever approaches touch
+    // them, just like any other road.
+    let circulating: HashSet<i64> = if circulating_roundabouts {
+        find_circulating_roundabouts(&roads, timer)
+    } else {
+        HashSet::new()
+    };
+
     // Normally one point to one intersection, but all points on a roundabout map to a single
     // point.
     let mut roundabout_centers: HashMap<raw_data::StableIntersectionID, LonLat> = HashMap::new();
@@ -22,7 +38,7 @@ pub fn split_up_roads(
         HashMap::new();
 
     roads.retain(|r| {
-        if r.osm_tags.get("junction") == Some(&"roundabout".to_string()) {
+        if is_roundabout(r) && !circulating.contains(&r.osm_way_id) {
             let id = raw_data::StableIntersectionID(next_intersection_id);
             next_intersection_id += 1;
 
@@ -89,10 +105,14 @@ pub fn split_up_roads(
         timer.next();
         let mut r = orig_road.clone();
         r.points.clear();
+        r.osm_node_ids.clear();
         r.i1 = pt_to_intersection[&orig_road.points[0].to_hashable()];
 
         for (idx, pt) in orig_road.points.iter().enumerate() {
             r.points.push(pt.clone());
+            if let Some(node) = orig_road.osm_node_ids.get(idx) {
+                r.osm_node_ids.push(*node);
+            }
             if r.points.len() > 1 {
                 if let Some(i2) = pt_to_intersection.get(&pt.to_hashable()) {
                     if roundabout_centers.contains_key(i2) && idx != orig_road.points.len() - 1 {
@@ -109,14 +129,101 @@ pub fn split_up_roads(
                     map.roads
                         .insert(raw_data::StableRoadID(map.roads.len()), r.clone());
                     r.points.clear();
+                    r.osm_node_ids.clear();
                     r.i1 = *i2;
                     r.points.push(pt.clone());
+                    if let Some(node) = orig_road.osm_node_ids.get(idx) {
+                        r.osm_node_ids.push(*node);
+                    }
                 }
             }
         }
         assert!(r.points.len() == 1);
     }
 
+    if !traffic_signals.is_empty() {
+        mark_traffic_signals(&mut map, &traffic_signals, timer);
+    }
+
     timer.stop("splitting up roads");
     map
 }
+
+fn mark_traffic_signals(
+    map: &mut raw_data::Map,
+    traffic_signals: &HashSet<i64>,
+    timer: &mut Timer,
+) {
+    let mut signals: HashSet<raw_data::StableIntersectionID> = HashSet::new();
+    for r in map.roads.values() {
+        for (idx, node) in r.osm_node_ids.iter().enumerate() {
+            if !traffic_signals.contains(node) {
+                continue;
+            }
+            let dist_to_i1 = r.points[idx].haversine_dist(r.points[0]);
+            let dist_to_i2 = r.points[idx].haversine_dist(*r.points.last().unwrap());
+            let (i, dist) = if dist_to_i1 <= dist_to_i2 {
+                (r.i1, dist_to_i1)
+            } else {
+                (r.i2, dist_to_i2)
+            };
+            if dist <= MAX_DIST_BTWN_INTERSECTION_AND_SIGNAL_NODE {
+                signals.insert(i);
+            } else {
+                timer.warn(format!(
+                    "OSM node {} is tagged as a traffic signal, but it's {} from any intersection",
+                    node, dist
+                ));
+            }
+        }
+    }
+    timer.note(format!(
+        "{} intersections have traffic signals from OSM node tags",
+        signals.len()
+    ));
+    for id in signals {
+        map.intersections.get_mut(&id).unwrap().intersection_type = IntersectionType::TrafficSignal;
+    }
+}
+
+fn is_roundabout(r: &raw_data::Road) -> bool {
+    r.osm_tags.get("junction") == Some(&"roundabout".to_string())
+}
+
+// Returns the OSM way IDs of roundabouts big enough to keep their geometry. They also need at least
+// two approaches; otherwise splitting would produce a road looping back to the same intersection.
+fn find_circulating_roundabouts(roads: &Vec<raw_data::Road>, timer: &mut Timer) -> HashSet<i64> {
+    let approach_pts: HashSet<HashablePt2D> = roads
+        .iter()
+        .filter(|r| !is_roundabout(r))
+        .flat_map(|r| r.points.iter().map(|pt| pt.to_hashable()))
+        .collect();
+
+    let mut results = HashSet::new();
+    for r in roads.iter().filter(|r| is_roundabout(r)) {
+        let mut diameter = Distance::ZERO;
+        for pt1 in &r.points {
+            for pt2 in &r.points {
+                diameter = diameter.max(pt1.haversine_dist(*pt2));
+            }
+        }
+        let approaches: HashSet<HashablePt2D> = r
+            .points
+            .iter()
+            .map(|pt| pt.to_hashable())
+            .filter(|pt| approach_pts.contains(pt))
+            .collect();
+
+        if diameter >= MIN_CIRCULATING_ROUNDABOUT_DIAMETER && approaches.len() >= 2 {
+            results.insert(r.osm_way_id);
+        } else {
+            timer.note(format!(
+                "Collapsing roundabout {} (diameter {}, {} approaches) to one intersection",
+                r.osm_way_id,
+                diameter,
+                approaches.len()
+            ));
+        }
+    }
+    results
+}
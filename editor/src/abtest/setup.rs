@@ -132,6 +132,16 @@ fn launch_test(test: &ABTest, ui: &mut UI, ctx: &mut EventCtx) -> Mode {
                             load,
                             rng_seed: current_flags.sim_flags.rng_seed,
                             run_name: Some(format!("{} with {}", test.test_name, test.edits2_name)),
+                            midblock_crossings: current_flags.sim_flags.midblock_crossings,
+                            record_trip_details: current_flags.sim_flags.record_trip_details,
+                            conditions: current_flags.sim_flags.conditions,
+                            gridlock_window: current_flags.sim_flags.gridlock_window,
+                            abort_gridlocked_trips: current_flags.sim_flags.abort_gridlocked_trips,
+                            min_following_gap: current_flags.sim_flags.min_following_gap,
+                            following_headway: current_flags.sim_flags.following_headway,
+                            warm_start: current_flags.sim_flags.warm_start,
+                            right_turn_on_red: current_flags.sim_flags.right_turn_on_red,
+                            record_replay: current_flags.sim_flags.record_replay,
                         },
                         ..current_flags.clone()
                     },
@@ -0,0 +1,202 @@
+use crate::ui::UI;
+use ezgui::{Color, EventCtx, EventLoopMode, GfxCtx, InputResult, Key, ModalMenu, Text, TextBox};
+use geom::{Circle, Distance, GPSBounds, LonLat, Pt2D};
+use serde_derive::{Deserialize, Serialize};
+
+// Markers are drawn this big, and hovering anywhere on one shows its note.
+const MARKER_RADIUS: Distance = Distance::const_meters(5.0);
+
+// A note left on the map while reviewing it. The position is GPS, so it survives the map being
+// rebuilt.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub pt: LonLat,
+    pub note: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Annotations {
+    pub map_name: String,
+    pub annotations: Vec<Annotation>,
+}
+
+impl Annotations {
+    fn path(map_name: &str) -> String {
+        format!("../data/player/annotations/{}.json", map_name)
+    }
+
+    // Having nothing saved yet is fine. A corrupt file gets noted and ignored.
+    pub fn load(map_name: &str) -> Annotations {
+        let path = Annotations::path(map_name);
+        match abstutil::read_json(&path) {
+            Ok(a) => a,
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    println!("Ignoring annotations in {}: {}", path, e);
+                }
+                Annotations {
+                    map_name: map_name.to_string(),
+                    annotations: Vec::new(),
+                }
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let path = Annotations::path(&self.map_name);
+        abstutil::write_json(&path, self).expect("Saving annotations failed");
+        println!("Saved {}", path);
+    }
+
+    // One line per note, for sharing outside of the editor
+    pub fn report(&self) -> String {
+        let mut lines = vec![format!(
+            "{} notes on {}",
+            self.annotations.len(),
+            self.map_name
+        )];
+        for a in &self.annotations {
+            lines.push(format!(
+                "{:.6}, {:.6}: {}",
+                a.pt.longitude, a.pt.latitude, a.note
+            ));
+        }
+        lines.join("\n")
+    }
+
+    // The first note containing the query, ignoring case
+    pub fn search(&self, query: &str) -> Option<&Annotation> {
+        let query = query.to_lowercase();
+        self.annotations
+            .iter()
+            .find(|a| a.note.to_lowercase().contains(&query))
+    }
+
+    fn hovering(&self, cursor: Pt2D, gps_bounds: &GPSBounds) -> Option<usize> {
+        self.annotations.iter().position(|a| {
+            Pt2D::from_gps(a.pt, gps_bounds)
+                .map(|pt| pt.dist_to(cursor) <= MARKER_RADIUS)
+                .unwrap_or(false)
+        })
+    }
+}
+
+pub struct AnnotationsState {
+    // Index of the note under the cursor
+    hovering: Option<usize>,
+    // Writing a new note at some point, or editing an existing one
+    editing: Option<(LonLat, Option<usize>, TextBox)>,
+}
+
+impl AnnotationsState {
+    pub fn new() -> AnnotationsState {
+        AnnotationsState {
+            hovering: None,
+            editing: None,
+        }
+    }
+
+    pub fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        ui: &mut UI,
+        menu: &mut ModalMenu,
+    ) -> Option<EventLoopMode> {
+        let annotations = &mut ui.primary.annotations;
+        if let Some((pt, idx, ref mut tb)) = self.editing {
+            match tb.event(ctx.input) {
+                InputResult::Canceled => {}
+                InputResult::Done(note, _) => {
+                    if !note.is_empty() {
+                        match idx {
+                            Some(idx) => {
+                                annotations.annotations[idx].note = note;
+                            }
+                            None => {
+                                annotations.annotations.push(Annotation { pt, note });
+                            }
+                        }
+                        annotations.save();
+                    }
+                }
+                InputResult::StillActive => {
+                    return Some(EventLoopMode::InputOnly);
+                }
+            }
+            self.editing = None;
+            return Some(EventLoopMode::InputOnly);
+        }
+
+        let gps_bounds = ui.primary.map.get_gps_bounds();
+        self.hovering = ctx
+            .canvas
+            .get_cursor_in_map_space()
+            .and_then(|pt| annotations.hovering(pt, gps_bounds));
+        if let Some(idx) = self.hovering {
+            if ctx.input.contextual_action(Key::Enter, "edit this note") {
+                let a = &annotations.annotations[idx];
+                self.editing = Some((
+                    a.pt,
+                    Some(idx),
+                    TextBox::new("Edit the note", Some(a.note.clone()), Vec::new()),
+                ));
+                return Some(EventLoopMode::InputOnly);
+            }
+            // Not Backspace; that clears the selection while editing the map.
+            if ctx.input.contextual_action(Key::Delete, "delete this note") {
+                annotations.annotations.remove(idx);
+                annotations.save();
+                self.hovering = None;
+            }
+        }
+
+        if menu.action("export notes") {
+            let path = format!("../data/player/annotations/{}.txt", annotations.map_name);
+            std::fs::create_dir_all("../data/player/annotations")
+                .expect("Creating parent dir failed");
+            std::fs::write(&path, annotations.report()).expect("Exporting notes failed");
+            println!(
+                "Exported {} notes to {}",
+                annotations.annotations.len(),
+                path
+            );
+        }
+        if menu.action("add a note here") {
+            if let Some(pt) = ctx
+                .canvas
+                .get_cursor_in_map_space()
+                .and_then(|pt| pt.to_gps(gps_bounds))
+            {
+                self.editing = Some((pt, None, TextBox::new("Note", None, Vec::new())));
+                return Some(EventLoopMode::InputOnly);
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, ui: &UI) {
+        let gps_bounds = ui.primary.map.get_gps_bounds();
+        let color = ui.cs.get_def("annotation marker", Color::PURPLE);
+        let hovering_color = ui.cs.get_def("hovered annotation marker", Color::PINK);
+        for (idx, a) in ui.primary.annotations.annotations.iter().enumerate() {
+            if let Some(pt) = Pt2D::from_gps(a.pt, gps_bounds) {
+                g.draw_circle(
+                    if Some(idx) == self.hovering {
+                        hovering_color
+                    } else {
+                        color
+                    },
+                    &Circle::new(pt, MARKER_RADIUS),
+                );
+            }
+        }
+
+        if let Some((_, _, ref tb)) = self.editing {
+            tb.draw(g);
+        } else if let Some(idx) = self.hovering {
+            g.draw_mouse_tooltip(&Text::from_line(
+                ui.primary.annotations.annotations[idx].note.clone(),
+            ));
+        }
+    }
+}
@@ -1,34 +1,59 @@
+mod annotations;
 mod associated;
 mod navigate;
+mod overlays;
 mod turn_cycler;
 mod warp;
 
+pub use self::annotations::{Annotation, Annotations};
+pub use self::overlays::{Overlay, Overlays};
+
 use crate::helpers::ID;
 use crate::render::DrawOptions;
 use crate::ui::UI;
 use abstutil::elapsed_seconds;
 use ezgui::{
-    hotkey, Color, EventCtx, EventLoopMode, GfxCtx, HorizontalAlignment, Key, ModalMenu, MultiKey,
-    ScreenPt, Slider, Text, VerticalAlignment,
+    hotkey, Color, EventCtx, EventLoopMode, GfxCtx, HorizontalAlignment, Key, Minimap, ModalMenu,
+    MultiKey, ScreenPt, Slider, Text, VerticalAlignment,
 };
-use geom::Duration;
+use geom::{Circle, Distance, Duration};
+use map_model::RoadID;
 use std::collections::BTreeSet;
 use std::time::Instant;
 
+// The minimap isn't worth the screen space for maps smaller than this in both dimensions
+const MINIMAP_MIN_MAP_SIZE: f64 = 5000.0;
+
 pub struct CommonState {
+    annotations: annotations::AnnotationsState,
     associated: associated::ShowAssociatedState,
     turn_cycler: turn_cycler::TurnCyclerState,
     warp: Option<warp::WarpState>,
     navigate: Option<navigate::Navigator>,
+    // Keep showing info about this object, even when the mouse moves elsewhere
+    pinned: Option<ID>,
+    // When on, selecting one road of a street highlights the whole thing
+    highlight_streets: bool,
+    // The name and roads of the street that's selected, if highlight_streets is on
+    street: Option<(String, Vec<RoadID>)>,
+    minimap: Minimap,
+    // None until toggled; until then, only big maps show the minimap
+    show_minimap: Option<bool>,
 }
 
 impl CommonState {
     pub fn new() -> CommonState {
         CommonState {
+            annotations: annotations::AnnotationsState::new(),
             associated: associated::ShowAssociatedState::Inactive,
             turn_cycler: turn_cycler::TurnCyclerState::new(),
             warp: None,
             navigate: None,
+            pinned: None,
+            highlight_streets: false,
+            street: None,
+            minimap: Minimap::new(),
+            show_minimap: None,
         }
     }
 
@@ -38,6 +63,10 @@ impl CommonState {
             // TODO This definitely conflicts with some modes.
             (hotkey(Key::K), "navigate"),
             (hotkey(Key::F1), "take a screenshot"),
+            (None, "toggle highlighting whole streets"),
+            (None, "toggle minimap"),
+            (hotkey(Key::Semicolon), "add a note here"),
+            (None, "export notes"),
         ]
     }
 
@@ -67,9 +96,23 @@ impl CommonState {
         if menu.action("navigate") {
             self.navigate = Some(navigate::Navigator::new(ui));
         }
+        if let Some(evmode) = self.annotations.event(ctx, ui, menu) {
+            return Some(evmode);
+        }
+        if menu.action("toggle minimap") {
+            self.show_minimap = Some(!self.minimap_visible(ui));
+        }
+        if self.minimap_visible(ui) && self.minimap.event(ctx, ui.primary.map.get_bounds()) {
+            return Some(EventLoopMode::InputOnly);
+        }
 
         self.associated.event(ui);
         self.turn_cycler.event(ctx, ui);
+        self.pin_event(ctx, ui);
+        if menu.action("toggle highlighting whole streets") {
+            self.highlight_streets = !self.highlight_streets;
+        }
+        self.street_event(ui);
         if menu.action("take a screenshot") {
             return Some(EventLoopMode::ScreenCaptureCurrentShot);
         }
@@ -84,15 +127,103 @@ impl CommonState {
             navigate.draw(g);
         }
         self.turn_cycler.draw(g, ui);
+        self.annotations.draw(g, ui);
+        if self.minimap_visible(ui) {
+            let draw_map = &ui.primary.draw_map;
+            self.minimap.draw(
+                g,
+                ui.primary.map.get_bounds(),
+                vec![
+                    &draw_map.boundary_polygon,
+                    &draw_map.draw_all_areas,
+                    &draw_map.draw_all_thick_roads,
+                    &draw_map.draw_all_unzoomed_intersections,
+                ],
+            );
+        }
 
-        CommonState::draw_osd(g, ui, ui.primary.current_selection);
+        if let Some(id) = self.pinned {
+            if let Some(pt) = id.canonical_point(&ui.primary) {
+                g.draw_circle(
+                    ui.cs.get_def("pinned object", Color::ORANGE.alpha(0.8)),
+                    &Circle::new(pt, Distance::meters(3.0)),
+                );
+            }
+
+            let mut osd = Text::new();
+            osd.append("Pinned: ".to_string(), None);
+            CommonState::describe(&mut osd, ui, Some(id));
+            osd.add_line(String::new());
+            CommonState::describe(&mut osd, ui, ui.primary.current_selection);
+            CommonState::draw_custom_osd(g, osd);
+        } else {
+            CommonState::draw_osd(g, ui, ui.primary.current_selection);
+        }
+    }
+
+    fn minimap_visible(&self, ui: &UI) -> bool {
+        self.show_minimap.unwrap_or_else(|| {
+            let bounds = ui.primary.map.get_bounds();
+            bounds.max_x - bounds.min_x > MINIMAP_MIN_MAP_SIZE
+                || bounds.max_y - bounds.min_y > MINIMAP_MIN_MAP_SIZE
+        })
+    }
+
+    fn pin_event(&mut self, ctx: &mut EventCtx, ui: &UI) {
+        // Agents vanish when they finish
+        if let Some(id) = self.pinned {
+            if id.canonical_point(&ui.primary).is_none() {
+                self.pinned = None;
+            }
+        }
+
+        match ui.primary.current_selection {
+            Some(id) if Some(id) != self.pinned => {
+                if ctx.input.contextual_action(Key::I, "pin info") {
+                    self.pinned = Some(id);
+                }
+            }
+            _ => {
+                if self.pinned.is_some() && ctx.input.unimportant_key_pressed(Key::I, "unpin info")
+                {
+                    self.pinned = None;
+                }
+            }
+        }
+    }
+
+    fn street_event(&mut self, ui: &UI) {
+        let map = &ui.primary.map;
+        let (name, r) = match ui.primary.current_selection {
+            Some(ID::Lane(l)) if self.highlight_streets => {
+                let r = map.get_parent(l);
+                (r.get_name(), r.id)
+            }
+            _ => {
+                self.street = None;
+                return;
+            }
+        };
+        let stale = match self.street {
+            Some((ref n, ref roads)) => *n != name || !roads.contains(&r),
+            None => true,
+        };
+        if stale {
+            let roads = map.street_roads(r);
+            self.street = Some((name, roads));
+        }
     }
 
     pub fn draw_osd(g: &mut GfxCtx, ui: &UI, id: Option<ID>) {
+        let mut osd = Text::new();
+        CommonState::describe(&mut osd, ui, id);
+        CommonState::draw_custom_osd(g, osd);
+    }
+
+    fn describe(osd: &mut Text, ui: &UI, id: Option<ID>) {
         let map = &ui.primary.map;
         let id_color = ui.cs.get_def("OSD ID color", Color::RED);
         let name_color = ui.cs.get_def("OSD name color", Color::CYAN);
-        let mut osd = Text::new();
         match id {
             None => {
                 osd.append("...".to_string(), None);
@@ -101,6 +232,13 @@ impl CommonState {
                 osd.append(format!("{}", l), Some(id_color));
                 osd.append(" is ".to_string(), None);
                 osd.append(map.get_parent(l).get_name(), Some(name_color));
+                if let Some(ref windows) = map.get_l(l).parking_hours {
+                    let hours: Vec<String> = windows
+                        .iter()
+                        .map(|(start, end)| format!("{}-{}", start, end))
+                        .collect();
+                    osd.append(format!(" (parking only {})", hours.join(", ")), None);
+                }
             }
             Some(ID::Building(b)) => {
                 osd.append(format!("{}", b), Some(id_color));
@@ -157,7 +295,6 @@ impl CommonState {
                 osd.append(format!("{:?}", id), Some(id_color));
             }
         }
-        CommonState::draw_custom_osd(g, osd);
     }
 
     pub fn draw_custom_osd(g: &mut GfxCtx, mut osd: Text) {
@@ -182,6 +319,16 @@ impl CommonState {
         let mut opts = DrawOptions::new();
         self.associated
             .override_colors(&mut opts.override_colors, ui);
+        if let Some((_, ref roads)) = self.street {
+            let color = ui.cs.get_def("selected street", Color::BLUE.alpha(0.6));
+            for r in roads {
+                for l in ui.primary.map.get_r(*r).all_lanes() {
+                    if ui.primary.current_selection != Some(ID::Lane(l)) {
+                        opts.override_colors.insert(ID::Lane(l), color);
+                    }
+                }
+            }
+        }
         opts.suppress_traffic_signal_details = self
             .turn_cycler
             .suppress_traffic_signal_details(&ui.primary.map);
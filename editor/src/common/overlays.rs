@@ -0,0 +1,162 @@
+use crate::ui::UI;
+use abstutil::{elapsed_seconds, Timer};
+use ezgui::{
+    Drawable, EventCtx, EventLoopMode, GeomBatch, GfxCtx, HorizontalAlignment, ModalMenu, Text,
+    VerticalAlignment, Wizard,
+};
+use geom::Duration;
+use map_model::MapEdits;
+use std::time::Instant;
+
+// While the sim is running, the time changes every frame. Don't recompute that often.
+const MIN_SECONDS_BETWEEN_REFRESHES: f64 = 1.0;
+
+// Something that colors the whole map by some value, like how many agents have entered each road.
+//
+// To add a new one:
+// 1) Make a struct (usually with no fields) and implement this trait. recompute() pushes colored
+//    polygons -- road thick polygons, lane polygons from ui.primary.draw_map, building polygons --
+//    into a GeomBatch, and builds a legend explaining the colors.
+// 2) List it with a name in the Overlays::new call of whichever mode should offer it.
+//
+// Choosing the overlay from the menu, drawing it, and recomputing it when the sim time or map
+// edits change are all handled by Overlays. sandbox/travel_volume.rs is a short example.
+pub trait Overlay {
+    fn recompute(&self, ui: &UI, timer: &mut Timer) -> (GeomBatch, Text);
+}
+
+// Shows at most one of the available overlays at a time.
+pub struct Overlays {
+    available: Vec<(&'static str, Box<Overlay>)>,
+    state: State,
+}
+
+enum State {
+    Inactive,
+    Picking(Wizard),
+    Active {
+        idx: usize,
+        draw: Drawable,
+        legend: Text,
+        // What the overlay was computed from
+        time: Duration,
+        edits: MapEdits,
+        computed_at: Instant,
+    },
+}
+
+impl Overlays {
+    pub fn new(available: Vec<(&'static str, Box<Overlay>)>) -> Overlays {
+        Overlays {
+            available,
+            state: State::Inactive,
+        }
+    }
+
+    // The caller's menu needs a "choose overlay" entry. If this returns something, then other
+    // things shouldn't happen.
+    pub fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        ui: &UI,
+        menu: &mut ModalMenu,
+    ) -> Option<EventLoopMode> {
+        match self.state {
+            State::Picking(ref mut wizard) => {
+                ctx.canvas.handle_event(ctx.input);
+                let mut choices: Vec<&str> = self.available.iter().map(|(n, _)| *n).collect();
+                choices.push("none");
+                if let Some(name) = wizard
+                    .wrap(ctx)
+                    .choose_string("Show which overlay?", choices)
+                {
+                    match self.available.iter().position(|(n, _)| *n == name) {
+                        Some(idx) => {
+                            self.state = ctx.loading_screen(
+                                &format!("compute {} overlay", name),
+                                |ctx, mut timer| {
+                                    compute(
+                                        idx,
+                                        self.available[idx].1.as_ref(),
+                                        ctx,
+                                        ui,
+                                        &mut timer,
+                                    )
+                                },
+                            );
+                        }
+                        None => {
+                            self.state = State::Inactive;
+                        }
+                    }
+                } else if wizard.aborted() {
+                    self.state = State::Inactive;
+                }
+                return Some(EventLoopMode::InputOnly);
+            }
+            State::Active {
+                idx,
+                time,
+                ref edits,
+                computed_at,
+                ..
+            } => {
+                let edits_changed = !edits.diff(ui.primary.map.get_edits()).is_empty();
+                let time_changed = time != ui.primary.sim.time()
+                    && elapsed_seconds(computed_at) >= MIN_SECONDS_BETWEEN_REFRESHES;
+                if edits_changed || time_changed {
+                    self.state = compute(
+                        idx,
+                        self.available[idx].1.as_ref(),
+                        ctx,
+                        ui,
+                        &mut Timer::throwaway(),
+                    );
+                }
+            }
+            State::Inactive => {}
+        }
+
+        if menu.action("choose overlay") {
+            self.state = State::Picking(Wizard::new());
+            return Some(EventLoopMode::InputOnly);
+        }
+        None
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        match self.state {
+            State::Inactive => {}
+            State::Picking(ref wizard) => {
+                wizard.draw(g);
+            }
+            State::Active {
+                ref draw,
+                ref legend,
+                ..
+            } => {
+                g.redraw(draw);
+                g.draw_blocking_text(legend, (HorizontalAlignment::Left, VerticalAlignment::Top));
+            }
+        }
+    }
+
+    pub fn active_name(&self) -> Option<&'static str> {
+        match self.state {
+            State::Active { idx, .. } => Some(self.available[idx].0),
+            _ => None,
+        }
+    }
+}
+
+fn compute(idx: usize, overlay: &Overlay, ctx: &EventCtx, ui: &UI, timer: &mut Timer) -> State {
+    let (batch, legend) = overlay.recompute(ui, timer);
+    State::Active {
+        idx,
+        draw: ctx.prerender.upload(batch),
+        legend,
+        time: ui.primary.sim.time(),
+        edits: ui.primary.map.get_edits().clone(),
+        computed_at: Instant::now(),
+    }
+}
@@ -89,8 +89,14 @@ impl TurnCyclerState {
             State::ShowIntersection(i) => {
                 if self.shift_key_held {
                     if let Some(signal) = ui.primary.map.maybe_get_traffic_signal(i) {
+                        // TODO Show something during the all-red clearance.
                         let (cycle, mut time_left) =
-                            signal.current_cycle_and_remaining_time(ui.primary.sim.time());
+                            match ui.primary.sim.current_signal_cycle(signal) {
+                                (Some(cycle), time_left) => (cycle, time_left),
+                                (None, _) => {
+                                    return;
+                                }
+                            };
                         if ui.primary.sim.is_in_overtime(i, &ui.primary.map) {
                             // TODO Hacky way of indicating overtime. Should make a 3-case enum.
                             time_left = Duration::seconds(-1.0);
@@ -127,5 +133,6 @@ fn color_turn_type(t: TurnType, ui: &UI) -> Color {
         TurnType::LaneChangeRight => ui.cs.get_def("change lanes right turn", Color::PURPLE),
         TurnType::Right => ui.cs.get_def("right turn", Color::GREEN),
         TurnType::Left => ui.cs.get_def("left turn", Color::RED),
+        TurnType::UTurn => ui.cs.get_def("u-turn", Color::PINK),
     }
 }
@@ -8,12 +8,13 @@ use std::usize;
 
 pub enum WarpState {
     EnteringSearch(TextBox),
-    Warping(Warper, ID),
+    // Notes don't have an ID to select
+    Warping(Warper, Option<ID>),
 }
 
 impl WarpState {
     pub fn new() -> WarpState {
-        WarpState::EnteringSearch(TextBox::new("Warp to what?", None))
+        WarpState::EnteringSearch(TextBox::new("Warp to what?", None, Vec::new()))
     }
 
     // When None, this is done.
@@ -33,8 +34,8 @@ impl WarpState {
             },
             WarpState::Warping(ref warper, id) => {
                 let result = warper.event(ctx);
-                if result.is_none() {
-                    ui.primary.current_selection = Some(*id);
+                if result.is_none() && id.is_some() {
+                    ui.primary.current_selection = *id;
                 }
                 result
             }
@@ -48,7 +49,8 @@ impl WarpState {
     }
 }
 
-fn warp_point(line: String, primary: &PerMapUI) -> Option<(ID, Pt2D)> {
+// Anything that isn't an ID is looked up in the notes left on the map.
+fn warp_point(line: String, primary: &PerMapUI) -> Option<(Option<ID>, Pt2D)> {
     if line.is_empty() {
         return None;
     }
@@ -121,12 +123,21 @@ fn warp_point(line: String, primary: &PerMapUI) -> Option<(ID, Pt2D)> {
             }
         },
         Err(_) => {
-            return None;
+            let a = match primary.annotations.search(&line) {
+                Some(a) => a,
+                None => {
+                    println!("No note mentions {}", line);
+                    return None;
+                }
+            };
+            let pt = Pt2D::from_gps(a.pt, primary.map.get_gps_bounds())?;
+            println!("Warping to note {}", a.note);
+            return Some((None, pt));
         }
     };
     if let Some(pt) = id.canonical_point(primary) {
         println!("Warping to {:?}", id);
-        Some((id, pt))
+        Some((Some(id), pt))
     } else {
         println!("{:?} doesn't exist", id);
         None
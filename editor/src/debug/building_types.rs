@@ -0,0 +1,54 @@
+use crate::helpers::ID;
+use crate::render::DrawOptions;
+use crate::ui::UI;
+use ezgui::{Color, ModalMenu};
+use map_model::BuildingType;
+use std::collections::HashMap;
+
+// Buildings are only drawn individually when zoomed in, so that's the only time this shows up.
+pub struct BuildingTypeColors {
+    pub active: bool,
+}
+
+impl BuildingTypeColors {
+    pub fn new() -> BuildingTypeColors {
+        BuildingTypeColors { active: false }
+    }
+
+    pub fn event(&mut self, menu: &mut ModalMenu) {
+        if menu.action("show/hide building types") {
+            self.active = !self.active;
+        }
+    }
+
+    pub fn override_colors(&self, opts: &mut DrawOptions, ui: &UI) {
+        if !self.active {
+            return;
+        }
+        let colors: HashMap<BuildingType, Color> = BuildingType::all()
+            .into_iter()
+            .map(|t| (t, color(t, ui)))
+            .collect();
+        for b in ui.primary.map.all_buildings() {
+            opts.override_colors
+                .insert(ID::Building(b.id), colors[&b.building_type]);
+        }
+    }
+}
+
+fn color(bldg_type: BuildingType, ui: &UI) -> Color {
+    match bldg_type {
+        BuildingType::Residence => ui
+            .cs
+            .get_def("residential building", Color::rgb(218, 165, 32)),
+        BuildingType::Retail => ui.cs.get_def("retail building", Color::rgb(210, 105, 30)),
+        BuildingType::Office => ui.cs.get_def("office building", Color::rgb(70, 130, 180)),
+        BuildingType::School => ui.cs.get_def("school building", Color::rgb(154, 205, 50)),
+        BuildingType::Industrial => ui
+            .cs
+            .get_def("industrial building", Color::rgb(128, 0, 128)),
+        BuildingType::Unknown => ui
+            .cs
+            .get_def("unknown building", Color::rgb_f(0.7, 0.7, 0.7)),
+    }
+}
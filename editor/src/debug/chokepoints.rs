@@ -28,6 +28,7 @@ impl ChokepointsFinder {
                         PathStep::Turn(t) => {
                             count_per_intersection.update(vec![t.parent]);
                         }
+                        PathStep::MidblockCrossing(_) => {}
                     }
                 }
             }
@@ -0,0 +1,161 @@
+use crate::ui::UI;
+use ezgui::{hotkey, Color, EventCtx, GfxCtx, Key, ModalMenu, Text};
+use geom::{Circle, Distance, Line, Pt2D};
+use map_model::{LaneType, Map, PathRequest, Position};
+use std::collections::BTreeSet;
+
+pub struct MeasureTool {
+    menu: ModalMenu,
+    anchor: Option<Pt2D>,
+    // Once the second point is placed, the line stops following the cursor.
+    result: Option<Measurement>,
+}
+
+struct Measurement {
+    to: Pt2D,
+    straight: Distance,
+    walking: Option<Distance>,
+    driving: Option<Distance>,
+}
+
+impl MeasureTool {
+    pub fn new(ctx: &EventCtx) -> MeasureTool {
+        MeasureTool {
+            menu: ModalMenu::new(
+                "Measure Tool",
+                vec![
+                    (hotkey(Key::Escape), "reset"),
+                    (hotkey(Key::Space), "place point"),
+                ],
+                ctx,
+            ),
+            anchor: None,
+            result: None,
+        }
+    }
+
+    // True when done
+    pub fn event(&mut self, ctx: &mut EventCtx, ui: &UI) -> bool {
+        let mut txt = Text::prompt("Measure Tool");
+        match (self.anchor, &self.result) {
+            (None, _) => {
+                txt.add_line("Place the first point".to_string());
+            }
+            (Some(_), None) => {
+                txt.add_line("Place the second point".to_string());
+            }
+            (Some(_), Some(ref m)) => {
+                for line in m.describe() {
+                    txt.add_line(line);
+                }
+            }
+        }
+        self.menu.handle_event(ctx, Some(txt));
+        ctx.canvas.handle_event(ctx.input);
+
+        if self.menu.action("reset") {
+            // Escape with nothing measured leaves the tool.
+            if self.anchor.is_none() {
+                return true;
+            }
+            self.anchor = None;
+            self.result = None;
+        }
+
+        if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+            if self.menu.action("place point") {
+                match self.anchor {
+                    Some(from) if self.result.is_none() => {
+                        self.result = Some(Measurement::new(from, pt, ui));
+                    }
+                    // Start over from here
+                    _ => {
+                        self.anchor = Some(pt);
+                        self.result = None;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, ui: &UI) {
+        if let Some(from) = self.anchor {
+            let color = ui.cs.get_def("measure tool", Color::PURPLE);
+            let radius = Distance::meters(5.0) / g.canvas.cam_zoom;
+            g.draw_circle(color, &Circle::new(from, radius));
+
+            let to = if let Some(ref m) = self.result {
+                Some(m.to)
+            } else {
+                g.canvas.get_cursor_in_map_space()
+            };
+            if let Some(to) = to {
+                g.draw_circle(color, &Circle::new(to, radius));
+                if let Some(line) = Line::maybe_new(from, to) {
+                    g.draw_line(color, Distance::meters(2.0) / g.canvas.cam_zoom, &line);
+
+                    let mut txt = Text::new();
+                    if let Some(ref m) = self.result {
+                        for l in m.describe() {
+                            txt.add_line(l);
+                        }
+                    } else {
+                        txt.add_line(format!("{}", line.length()));
+                    }
+                    g.draw_text_at(&txt, line.dist_along(line.length() / 2.0));
+                }
+            }
+        }
+
+        self.menu.draw(g);
+    }
+}
+
+impl Measurement {
+    fn new(from: Pt2D, to: Pt2D, ui: &UI) -> Measurement {
+        Measurement {
+            to,
+            straight: from.dist_to(to),
+            walking: routed_dist(from, to, LaneType::Sidewalk, ui),
+            driving: routed_dist(from, to, LaneType::Driving, ui),
+        }
+    }
+
+    fn describe(&self) -> Vec<String> {
+        let mut lines = vec![format!("Straight-line distance: {}", self.straight)];
+        if let Some(d) = self.walking {
+            lines.push(format!("Walking distance: {}", d));
+        }
+        if let Some(d) = self.driving {
+            lines.push(format!("Driving distance: {}", d));
+        }
+        lines
+    }
+}
+
+// None if either point isn't near a lane of this type, or there's no path between them.
+fn routed_dist(from: Pt2D, to: Pt2D, lane_type: LaneType, ui: &UI) -> Option<Distance> {
+    let map = &ui.primary.map;
+    let draw_map = &ui.primary.draw_map;
+    let start = draw_map.closest_lane_of_type(from, &[lane_type], map)?;
+    let end = draw_map.closest_lane_of_type(to, &[lane_type], map)?;
+    if start == end {
+        return Some(Distance::ZERO);
+    }
+    path_length(start, end, map)
+}
+
+fn path_length(start: Position, end: Position, map: &Map) -> Option<Distance> {
+    let path = map.pathfind(PathRequest {
+        start,
+        end,
+        can_use_bike_lanes: false,
+        can_use_bus_lanes: false,
+        can_use_hov_lanes: false,
+        can_use_midblock_crossings: false,
+        avoid_lanes: BTreeSet::new(),
+    })?;
+    Some(path.trace(map, start.dist_along(), None)?.length())
+}
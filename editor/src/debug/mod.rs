@@ -1,15 +1,19 @@
+mod building_types;
 mod bus_explorer;
 mod chokepoints;
 mod color_picker;
 mod connected_roads;
+mod measure;
+mod neighborhood_colors;
 mod neighborhood_summary;
 mod objects;
 mod polygons;
+mod warnings;
 
 use crate::common::CommonState;
 use crate::edit::EditMode;
 use crate::game::{GameState, Mode};
-use crate::helpers::ID;
+use crate::helpers::{Palette, ID};
 use crate::render::DrawOptions;
 use crate::sandbox::SandboxMode;
 use crate::ui::{ShowLayers, ShowObject, UI};
@@ -17,8 +21,8 @@ use abstutil::wraparound_get;
 use abstutil::Timer;
 use clipping::CPolygon;
 use ezgui::{
-    hotkey, lctrl, Color, EventCtx, EventLoopMode, GfxCtx, InputResult, Key, ModalMenu,
-    ScrollingMenu, Text, TextBox, Wizard,
+    hotkey, lctrl, Color, EventCtx, EventLoopMode, GfxCtx, InputResult, Key, LogScroller,
+    ModalMenu, ScrollingMenu, Text, TextBox, Wizard,
 };
 use geom::{Distance, PolyLine, Polygon, Pt2D};
 use map_model::{IntersectionID, Map, RoadID};
@@ -36,6 +40,8 @@ pub struct DebugMode {
     layers: ShowLayers,
     search_results: Option<(String, HashSet<ID>)>,
     neighborhood_summary: neighborhood_summary::NeighborhoodSummary,
+    neighborhood_colors: neighborhood_colors::NeighborhoodColors,
+    building_types: building_types::BuildingTypeColors,
 }
 
 enum State {
@@ -44,6 +50,9 @@ enum State {
     SearchOSM(TextBox),
     Colors(color_picker::ColorPicker),
     BusRoute(bus_explorer::BusRouteExplorer),
+    Warnings(warnings::WarningsPanel),
+    BusRouteProblems(LogScroller),
+    Measure(measure::MeasureTool),
 }
 
 impl DebugMode {
@@ -65,6 +74,8 @@ impl DebugMode {
                 ctx.prerender,
                 &mut Timer::new("set up DebugMode"),
             ),
+            neighborhood_colors: neighborhood_colors::NeighborhoodColors::new(),
+            building_types: building_types::BuildingTypeColors::new(),
         }
     }
 
@@ -84,11 +95,18 @@ impl DebugMode {
                     (hotkey(Key::Num4), "show/hide areas"),
                     (hotkey(Key::Num5), "show/hide extra shapes"),
                     (hotkey(Key::Num6), "show/hide geometry debug mode"),
+                    (hotkey(Key::Num7), "show/hide lanes by neighborhood"),
+                    (hotkey(Key::Num8), "show/hide building types"),
                     (None, "screenshot everything"),
                     (hotkey(Key::Slash), "search OSM metadata"),
                     (hotkey(Key::M), "clear OSM search results"),
                     (hotkey(Key::S), "configure colors"),
+                    (None, "toggle colorblind palette"),
                     (hotkey(Key::N), "show/hide neighborhood summaries"),
+                    (hotkey(Key::W), "show warnings from loading"),
+                    (hotkey(Key::B), "show problems with bus routes"),
+                    (hotkey(Key::F5), "reload assets"),
+                    (hotkey(Key::R), "measure distances"),
                     (lctrl(Key::S), "sandbox mode"),
                     (lctrl(Key::E), "edit mode"),
                 ],
@@ -143,6 +161,12 @@ impl DebugMode {
                         if mode.neighborhood_summary.active {
                             txt.add_line("Showing neighborhood summaries".to_string());
                         }
+                        if mode.neighborhood_colors.active {
+                            txt.add_line("Coloring lanes by neighborhood".to_string());
+                        }
+                        if mode.building_types.active {
+                            txt.add_line("Coloring buildings by type".to_string());
+                        }
                         menu.handle_event(ctx, Some(txt));
 
                         ctx.canvas.handle_event(ctx.input);
@@ -233,6 +257,8 @@ impl DebugMode {
                         mode.connected_roads.event(ctx, &state.ui);
                         mode.objects.event(ctx, &state.ui);
                         mode.neighborhood_summary.event(&state.ui, menu);
+                        mode.neighborhood_colors.event(&state.ui, menu);
+                        mode.building_types.event(menu);
 
                         if let Some(debugger) = polygons::PolygonDebugger::new(ctx, &state.ui) {
                             mode.state = State::Polygons(debugger);
@@ -263,6 +289,20 @@ impl DebugMode {
                             }
                         }
 
+                        if menu.action("reload assets") {
+                            state.ui.reload_assets(ctx);
+                            mode.neighborhood_colors.reload();
+                        }
+
+                        if menu.action("toggle colorblind palette") {
+                            let palette = match state.ui.cs.get_palette() {
+                                Palette::Standard => Palette::Colorblind,
+                                Palette::Colorblind => Palette::Standard,
+                            };
+                            state.ui.cs.set_palette(palette);
+                            state.ui.primary.regenerate_draw_map(&state.ui.cs, ctx);
+                        }
+
                         if menu.action("screenshot everything") {
                             let bounds = state.ui.primary.map.get_bounds();
                             assert!(bounds.min_x == 0.0 && bounds.min_y == 0.0);
@@ -282,7 +322,18 @@ impl DebugMode {
                                 mode.search_results = None;
                             }
                         } else if menu.action("search OSM metadata") {
-                            mode.state = State::SearchOSM(TextBox::new("Search for what?", None));
+                            mode.state = State::SearchOSM(TextBox::new(
+                                "Search for what?",
+                                None,
+                                Vec::new(),
+                            ));
+                        } else if menu.action("show warnings from loading") {
+                            mode.state = State::Warnings(warnings::WarningsPanel::new());
+                        } else if menu.action("show problems with bus routes") {
+                            mode.state =
+                                State::BusRouteProblems(bus_route_problems(&state.ui.primary.map));
+                        } else if menu.action("measure distances") {
+                            mode.state = State::Measure(measure::MeasureTool::new(ctx));
                         } else if menu.action("configure colors") {
                             mode.state = State::Colors(color_picker::ColorPicker::Choosing(
                                 ScrollingMenu::new(
@@ -353,6 +404,26 @@ impl DebugMode {
                             EventLoopMode::InputOnly
                         }
                     }
+                    State::Warnings(ref mut panel) => {
+                        if let Some(evmode) = panel.event(ctx, &mut state.ui) {
+                            evmode
+                        } else {
+                            mode.state = DebugMode::exploring_state(ctx);
+                            EventLoopMode::InputOnly
+                        }
+                    }
+                    State::BusRouteProblems(ref mut scroller) => {
+                        if scroller.event(ctx.input) {
+                            mode.state = DebugMode::exploring_state(ctx);
+                        }
+                        EventLoopMode::InputOnly
+                    }
+                    State::Measure(ref mut tool) => {
+                        if tool.event(ctx, &state.ui) {
+                            mode.state = DebugMode::exploring_state(ctx);
+                        }
+                        EventLoopMode::InputOnly
+                    }
                 }
             }
             _ => unreachable!(),
@@ -365,6 +436,8 @@ impl DebugMode {
                 State::Exploring(ref menu) => {
                     let mut opts = mode.common.draw_options(&state.ui);
                     opts.geom_debug_mode = mode.layers.geom_debug_mode;
+                    mode.neighborhood_colors.override_colors(&mut opts);
+                    mode.building_types.override_colors(&mut opts, &state.ui);
                     if let Some(ref chokepoints) = mode.chokepoints {
                         let color = state.ui.cs.get_def("chokepoint", Color::RED);
                         for l in &chokepoints.lanes {
@@ -447,6 +520,24 @@ impl DebugMode {
                     state.ui.draw(g, opts, &state.ui.primary.sim, mode);
                     explorer.draw(g, &state.ui);
                 }
+                State::Warnings(ref panel) => {
+                    let mut opts = DrawOptions::new();
+                    opts.geom_debug_mode = mode.layers.geom_debug_mode;
+                    state.ui.draw(g, opts, &state.ui.primary.sim, mode);
+                    panel.draw(g);
+                }
+                State::BusRouteProblems(ref scroller) => {
+                    let mut opts = DrawOptions::new();
+                    opts.geom_debug_mode = mode.layers.geom_debug_mode;
+                    state.ui.draw(g, opts, &state.ui.primary.sim, mode);
+                    scroller.draw(g);
+                }
+                State::Measure(ref tool) => {
+                    let mut opts = DrawOptions::new();
+                    opts.geom_debug_mode = mode.layers.geom_debug_mode;
+                    state.ui.draw(g, opts, &state.ui.primary.sim, mode);
+                    tool.draw(g, &state.ui);
+                }
             },
             _ => unreachable!(),
         }
@@ -474,6 +565,26 @@ impl ShowObject for DebugMode {
     }
 }
 
+fn bus_route_problems(map: &Map) -> LogScroller {
+    let reports = map.get_bus_route_reports();
+    let mut lines = Vec::new();
+    for report in reports {
+        lines.push(format!(
+            "{} ({})",
+            report.route_name,
+            if report.kept {
+                "missing some stops"
+            } else {
+                "dropped"
+            }
+        ));
+        for problem in &report.problems {
+            lines.push(format!("  {}", problem));
+        }
+    }
+    LogScroller::new(format!("{} bus routes with problems", reports.len()), lines)
+}
+
 fn recalc_intersection_geom(id: IntersectionID, map: &Map, g: &mut GfxCtx) {
     let mut all_polys = Vec::new();
 
@@ -559,7 +670,7 @@ fn recalc_intersection_geom(id: IntersectionID, map: &Map, g: &mut GfxCtx) {
         for (idx1, p1) in all_polys.iter().enumerate() {
             for (idx2, p2) in all_polys.iter().enumerate() {
                 if idx1 != idx2 {
-                    all_pieces.extend(intersection(p1, p2));
+                    all_pieces.extend(p1.intersection(p2));
                 }
             }
         }
@@ -593,15 +704,6 @@ fn cpoly_to_poly(raw_pts: Vec<[f64; 2]>) -> Polygon {
     Polygon::new(&pts)
 }
 
-fn intersection(p1: &Polygon, p2: &Polygon) -> Vec<Polygon> {
-    let mut cp1 = poly_to_cpoly(p1);
-    let mut cp2 = poly_to_cpoly(p2);
-    cp1.intersection(&mut cp2)
-        .into_iter()
-        .map(cpoly_to_poly)
-        .collect()
-}
-
 fn union(polys: &Vec<Polygon>) -> Option<Polygon> {
     let mut result = poly_to_cpoly(&polys[0]);
     for p in polys.iter().skip(1) {
@@ -0,0 +1,75 @@
+use crate::helpers::{rotating_color, ID};
+use crate::render::DrawOptions;
+use crate::ui::UI;
+use ezgui::{Color, ModalMenu};
+use map_model::{LaneID, Neighborhood};
+use std::collections::HashMap;
+
+pub struct NeighborhoodColors {
+    pub active: bool,
+    // Computed the first time this is shown. Reloading assets throws it away, in case the
+    // neighborhoods changed on disk.
+    colors: Option<HashMap<LaneID, Color>>,
+}
+
+impl NeighborhoodColors {
+    pub fn new() -> NeighborhoodColors {
+        NeighborhoodColors {
+            active: false,
+            colors: None,
+        }
+    }
+
+    pub fn event(&mut self, ui: &UI, menu: &mut ModalMenu) {
+        if menu.action("show/hide lanes by neighborhood") {
+            self.active = !self.active;
+        }
+        if self.active && self.colors.is_none() {
+            self.colors = Some(calculate(ui));
+        }
+    }
+
+    pub fn reload(&mut self) {
+        self.colors = None;
+    }
+
+    pub fn override_colors(&self, opts: &mut DrawOptions) {
+        if !self.active {
+            return;
+        }
+        if let Some(ref colors) = self.colors {
+            for (l, color) in colors {
+                opts.override_colors.insert(ID::Lane(*l), *color);
+            }
+        }
+    }
+}
+
+fn calculate(ui: &UI) -> HashMap<LaneID, Color> {
+    let map = &ui.primary.map;
+    let neighborhoods: Vec<Neighborhood> =
+        Neighborhood::load_all(map.get_name(), map.get_gps_bounds())
+            .into_iter()
+            .map(|(_, n)| n)
+            .collect();
+    let color_per_name: HashMap<String, Color> = neighborhoods
+        .iter()
+        .enumerate()
+        .map(|(idx, n)| (n.name.clone(), rotating_color(idx)))
+        .collect();
+    let outside = ui
+        .cs
+        .get_def("lane outside all neighborhoods", Color::grey(0.6));
+
+    map.all_lanes()
+        .iter()
+        .map(|l| {
+            let middle = l.dist_along(l.length() / 2.0).0;
+            let color = match Neighborhood::neighborhood_containing(middle, &neighborhoods) {
+                Some(name) => color_per_name[&name],
+                None => outside,
+            };
+            (l.id, color)
+        })
+        .collect()
+}
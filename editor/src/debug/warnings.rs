@@ -0,0 +1,112 @@
+use crate::helpers::ID;
+use crate::ui::{PerMapUI, UI};
+use ezgui::{
+    EventCtx, EventLoopMode, GfxCtx, InputResult, LogScroller, ScrollingMenu, TextBox, Warper,
+};
+use map_model::find_mentioned_ids;
+
+// LogScroller can't scroll yet, so don't flood the screen.
+const MAX_LINES: usize = 50;
+
+pub enum WarningsPanel {
+    Filtering(TextBox),
+    // Also remembers everything the matching warnings mention, to offer warping there next.
+    Listing(LogScroller, Vec<(String, ID)>),
+    Choosing(ScrollingMenu<ID>),
+    Warping(Warper, ID),
+}
+
+impl WarningsPanel {
+    pub fn new() -> WarningsPanel {
+        WarningsPanel::Filtering(TextBox::new(
+            "Show warnings containing what? (empty for all)",
+            None,
+            Vec::new(),
+        ))
+    }
+
+    // When None, this is done.
+    pub fn event(&mut self, ctx: &mut EventCtx, ui: &mut UI) -> Option<EventLoopMode> {
+        match self {
+            WarningsPanel::Filtering(tb) => match tb.event(ctx.input) {
+                InputResult::Canceled => None,
+                InputResult::Done(filter, _) => {
+                    *self = list_warnings(&filter, &ui.primary);
+                    Some(EventLoopMode::InputOnly)
+                }
+                InputResult::StillActive => Some(EventLoopMode::InputOnly),
+            },
+            WarningsPanel::Listing(ref mut scroller, ref mut mentions) => {
+                if scroller.event(ctx.input) {
+                    if mentions.is_empty() {
+                        return None;
+                    }
+                    *self = WarningsPanel::Choosing(ScrollingMenu::new(
+                        "Warp to something mentioned? (escape to close)",
+                        std::mem::replace(mentions, Vec::new()),
+                    ));
+                }
+                Some(EventLoopMode::InputOnly)
+            }
+            WarningsPanel::Choosing(ref mut menu) => match menu.event(ctx.input) {
+                InputResult::Canceled => None,
+                InputResult::Done(_, id) => {
+                    let pt = id.canonical_point(&ui.primary)?;
+                    *self = WarningsPanel::Warping(Warper::new(ctx, pt), id);
+                    Some(EventLoopMode::Animation)
+                }
+                InputResult::StillActive => Some(EventLoopMode::InputOnly),
+            },
+            WarningsPanel::Warping(ref warper, id) => {
+                let result = warper.event(ctx);
+                if result.is_none() {
+                    ui.primary.current_selection = Some(*id);
+                }
+                result
+            }
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        match self {
+            WarningsPanel::Filtering(tb) => tb.draw(g),
+            WarningsPanel::Listing(scroller, _) => scroller.draw(g),
+            WarningsPanel::Choosing(menu) => menu.draw(g),
+            WarningsPanel::Warping(_, _) => {}
+        }
+    }
+}
+
+fn list_warnings(filter: &str, primary: &PerMapUI) -> WarningsPanel {
+    let mut lines = Vec::new();
+    let mut matches = 0;
+    let mut mentions = Vec::new();
+    for w in &primary.warnings {
+        let context = w.context.join(" > ");
+        if !w.message.contains(filter) && !context.contains(filter) {
+            continue;
+        }
+        matches += 1;
+        if lines.len() < MAX_LINES {
+            lines.push(format!("[{}] {}", context, w.message));
+        }
+        for m in find_mentioned_ids(&w.message) {
+            if let Some(id) = ID::from_mention(m, &primary.map) {
+                let choice = format!("{:?}", id);
+                if !mentions.iter().any(|(name, _)| name == &choice) {
+                    mentions.push((choice, id));
+                }
+            }
+        }
+    }
+    if matches > MAX_LINES {
+        lines.push(format!("... and {} more", matches - MAX_LINES));
+    }
+
+    let title = if filter.is_empty() {
+        format!("{} warnings from loading", matches)
+    } else {
+        format!("{} warnings containing \"{}\"", matches, filter)
+    };
+    WarningsPanel::Listing(LogScroller::new(title, lines), mentions)
+}
@@ -0,0 +1,180 @@
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+use crate::render::DrawOptions;
+use crate::ui::{PerMapUI, ShowEverything, UI};
+use ezgui::{
+    hotkey, Canvas, Color, EventCtx, GfxCtx, Key, ModalMenu, ScreenPt, ScreenRectangle, Text,
+};
+use geom::{Distance, Line, Pt2D};
+use map_model::MapEdits;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+// The original map on the left half of the window, the edited one on the right, with the cameras
+// locked together. Whichever half the cursor is over gets swapped into ui.primary, so mouseover
+// and the OSD work like usual. The canvas camera always belongs to that half too; the other half
+// is drawn shifted by half the window.
+pub struct CompareView {
+    menu: ModalMenu,
+    // The half the cursor isn't over
+    other: PerMapUI,
+    // The edited map is always on the right.
+    hovering: Side,
+}
+
+impl CompareView {
+    pub fn new(ctx: &mut EventCtx, ui: &mut UI) -> CompareView {
+        let mut original = ctx.loading_screen("load the original map", |ctx, mut timer| {
+            PerMapUI::new(ui.primary.current_flags.clone(), &ui.cs, ctx, &mut timer)
+        });
+        // Savestates come with their own edits
+        let no_edits = MapEdits::new(original.map.get_name().to_string());
+        apply_map_edits(&mut original, &ui.cs, ctx, no_edits);
+
+        // Keep whatever's in the middle of the screen in the middle of the right half.
+        ctx.canvas.cam_x -= ctx.canvas.window_width / 4.0;
+        ui.primary.current_selection = None;
+
+        CompareView {
+            menu: ModalMenu::new(
+                "Compare Edits",
+                vec![(hotkey(Key::Escape), "stop comparing")],
+                ctx,
+            ),
+            other: original,
+            hovering: Side::Right,
+        }
+    }
+
+    // True when done
+    pub fn event(&mut self, ctx: &mut EventCtx, ui: &mut UI) -> bool {
+        let mut txt = Text::prompt("Compare Edits");
+        txt.add_line("Left: original map".to_string());
+        txt.add_line(format!(
+            "Right: {}",
+            self.edited(ui).map.get_edits().edits_name
+        ));
+        self.menu.handle_event(ctx, Some(txt));
+        ctx.canvas.handle_event(ctx.input);
+
+        let side = if ctx.canvas.get_cursor_in_screen_space().x < ctx.canvas.window_width / 2.0 {
+            Side::Left
+        } else {
+            Side::Right
+        };
+        if side != self.hovering {
+            self.hover(side, ui, ctx.canvas);
+        }
+
+        if ctx.redo_mouseover() {
+            ui.primary.current_selection = ui.recalculate_current_selection(
+                ctx,
+                &ui.primary.sim,
+                &ShowEverything::new(),
+                false,
+            );
+        }
+
+        if self.menu.action("stop comparing") {
+            self.hover(Side::Right, ui, ctx.canvas);
+            ctx.canvas.cam_x += ctx.canvas.window_width / 4.0;
+            return true;
+        }
+        false
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, ui: &UI) {
+        let width = g.canvas.window_width;
+        let height = g.canvas.window_height;
+        let left = ScreenRectangle {
+            x1: 0.0,
+            y1: 0.0,
+            x2: width / 2.0,
+            y2: height,
+        };
+        let right = ScreenRectangle {
+            x1: width / 2.0,
+            y1: 0.0,
+            x2: width,
+            y2: height,
+        };
+        let (primary_rect, other_rect, other_offset) = match self.hovering {
+            Side::Left => (left, right, ScreenPt::new(width / 2.0, 0.0)),
+            Side::Right => (right, left, ScreenPt::new(-width / 2.0, 0.0)),
+        };
+
+        g.enable_viewport(&primary_rect, ScreenPt::new(0.0, 0.0));
+        ui.draw(
+            g,
+            DrawOptions::new(),
+            &ui.primary.sim,
+            &ShowEverything::new(),
+        );
+        g.enable_viewport(&other_rect, other_offset);
+        ui.draw_per_map(
+            g,
+            &self.other,
+            DrawOptions::new(),
+            &self.other.sim,
+            &ShowEverything::new(),
+        );
+        g.disable_viewport();
+
+        g.fork_screenspace();
+        g.draw_line(
+            ui.cs.get_def("compare view divider", Color::WHITE),
+            Distance::meters(3.0),
+            &Line::new(Pt2D::new(width / 2.0, 0.0), Pt2D::new(width / 2.0, height)),
+        );
+        g.unfork();
+        g.draw_text_at_screenspace_topleft(
+            &Text::from_line("Original".to_string()),
+            ScreenPt::new(0.0, 0.0),
+        );
+        g.draw_text_at_screenspace_topleft(
+            &Text::from_line(self.edited(ui).map.get_edits().edits_name.clone()),
+            ScreenPt::new(width / 2.0, 0.0),
+        );
+
+        self.menu.draw(g);
+        CommonState::draw_osd(g, ui, ui.primary.current_selection);
+    }
+
+    pub fn describe_state(&self) -> Vec<String> {
+        let mut lines = self.menu.describe_state();
+        lines.push(match self.hovering {
+            Side::Left => "Hovering over the original map".to_string(),
+            Side::Right => "Hovering over the edited map".to_string(),
+        });
+        lines
+    }
+
+    fn edited<'a>(&'a self, ui: &'a UI) -> &'a PerMapUI {
+        match self.hovering {
+            Side::Left => &self.other,
+            Side::Right => &ui.primary,
+        }
+    }
+
+    fn hover(&mut self, side: Side, ui: &mut UI, canvas: &mut Canvas) {
+        if side == self.hovering {
+            return;
+        }
+        // Moving the camera to the other half means shifting by half the window.
+        match side {
+            Side::Left => {
+                canvas.cam_x += canvas.window_width / 2.0;
+            }
+            Side::Right => {
+                canvas.cam_x -= canvas.window_width / 2.0;
+            }
+        }
+        ui.primary.current_selection = None;
+        std::mem::swap(&mut ui.primary, &mut self.other);
+        self.hovering = side;
+    }
+}
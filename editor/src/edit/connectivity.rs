@@ -0,0 +1,56 @@
+use crate::helpers::ID;
+use crate::ui::{PerMapUI, UI};
+use ezgui::{EventCtx, EventLoopMode, GfxCtx, InputResult, ScrollingMenu, Warper};
+
+// Lists everything the connectivity audit flagged, offering to warp to one.
+pub enum ConnectivityPanel {
+    Choosing(ScrollingMenu<ID>),
+    Warping(Warper, ID),
+}
+
+impl ConnectivityPanel {
+    // The report must not be empty.
+    pub fn new(primary: &PerMapUI) -> ConnectivityPanel {
+        let choices: Vec<(String, ID)> = primary
+            .connectivity
+            .problems()
+            .into_iter()
+            .filter_map(|(line, m)| Some((line, ID::from_mention(m, &primary.map)?)))
+            .collect();
+        ConnectivityPanel::Choosing(ScrollingMenu::new(
+            &format!(
+                "{} connectivity problems. Warp to one? (escape to close)",
+                choices.len()
+            ),
+            choices,
+        ))
+    }
+
+    // When None, this is done.
+    pub fn event(&mut self, ctx: &mut EventCtx, ui: &mut UI) -> Option<EventLoopMode> {
+        match self {
+            ConnectivityPanel::Choosing(ref mut menu) => match menu.event(ctx.input) {
+                InputResult::Canceled => None,
+                InputResult::Done(_, id) => {
+                    let pt = id.canonical_point(&ui.primary)?;
+                    *self = ConnectivityPanel::Warping(Warper::new(ctx, pt), id);
+                    Some(EventLoopMode::Animation)
+                }
+                InputResult::StillActive => Some(EventLoopMode::InputOnly),
+            },
+            ConnectivityPanel::Warping(ref warper, id) => {
+                let result = warper.event(ctx);
+                if result.is_none() {
+                    ui.primary.current_selection = Some(*id);
+                }
+                result
+            }
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        if let ConnectivityPanel::Choosing(menu) = self {
+            menu.draw(g);
+        }
+    }
+}
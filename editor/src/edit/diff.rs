@@ -0,0 +1,162 @@
+use crate::common::CommonState;
+use crate::edit::load_edits;
+use crate::helpers::ID;
+use crate::render::{DrawOptions, MIN_ZOOM_FOR_DETAIL};
+use crate::ui::{ShowEverything, UI};
+use ezgui::{hotkey, Color, EventCtx, EventLoopMode, GfxCtx, Key, ModalMenu, Text, Wizard};
+use map_model::{DiffType, EditDiff};
+
+// Highlights everything that changes when switching from the current edits to another set.
+pub enum EditDiffView {
+    Picking(Wizard),
+    Viewing {
+        menu: ModalMenu,
+        other_edits: String,
+        diff: EditDiff,
+    },
+}
+
+impl EditDiffView {
+    pub fn new() -> EditDiffView {
+        EditDiffView::Picking(Wizard::new())
+    }
+
+    // When None, this is done.
+    pub fn event(&mut self, ctx: &mut EventCtx, ui: &mut UI) -> Option<EventLoopMode> {
+        match self {
+            EditDiffView::Picking(ref mut wizard) => {
+                ctx.canvas.handle_event(ctx.input);
+                if let Some(other) = load_edits(
+                    &ui.primary.map,
+                    &mut wizard.wrap(ctx),
+                    "Compare against which map edits?",
+                ) {
+                    *self = EditDiffView::Viewing {
+                        menu: ModalMenu::new(
+                            "Diff Edits",
+                            vec![(hotkey(Key::Escape), "stop diffing")],
+                            ctx,
+                        ),
+                        diff: ui.primary.map.get_edits().diff(&other),
+                        other_edits: other.edits_name,
+                    };
+                } else if wizard.aborted() {
+                    return None;
+                }
+            }
+            EditDiffView::Viewing {
+                ref mut menu,
+                ref other_edits,
+                ref diff,
+            } => {
+                let mut txt = Text::prompt("Diff Edits");
+                txt.add_line(format!(
+                    "{} to {}",
+                    ui.primary.map.get_edits().edits_name,
+                    other_edits
+                ));
+                for (diff_type, name) in vec![
+                    (DiffType::Added, "added"),
+                    (DiffType::Removed, "removed"),
+                    (DiffType::Changed, "changed"),
+                ] {
+                    txt.add_line(format!(
+                        "{} lanes, {} roads, {} intersections {}",
+                        diff.lanes.values().filter(|d| **d == diff_type).count(),
+                        diff.roads.values().filter(|d| **d == diff_type).count(),
+                        diff.intersections
+                            .values()
+                            .filter(|d| **d == diff_type)
+                            .count(),
+                        name
+                    ));
+                }
+                menu.handle_event(ctx, Some(txt));
+                ctx.canvas.handle_event(ctx.input);
+
+                if ctx.redo_mouseover() {
+                    ui.primary.current_selection = ui.recalculate_current_selection(
+                        ctx,
+                        &ui.primary.sim,
+                        &ShowEverything::new(),
+                        false,
+                    );
+                }
+
+                if menu.action("stop diffing") {
+                    return None;
+                }
+            }
+        }
+        Some(EventLoopMode::InputOnly)
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, ui: &UI) {
+        match self {
+            EditDiffView::Picking(ref wizard) => {
+                ui.draw(
+                    g,
+                    DrawOptions::new(),
+                    &ui.primary.sim,
+                    &ShowEverything::new(),
+                );
+                wizard.draw(g);
+            }
+            EditDiffView::Viewing {
+                ref menu, ref diff, ..
+            } => {
+                let color = |diff_type: &DiffType| match diff_type {
+                    DiffType::Added => ui.cs.get_def("edits diff added", Color::GREEN),
+                    DiffType::Removed => ui.cs.get_def("edits diff removed", Color::RED),
+                    DiffType::Changed => ui.cs.get_def("edits diff changed", Color::YELLOW),
+                };
+
+                let mut opts = DrawOptions::new();
+                for (l, diff_type) in &diff.lanes {
+                    opts.override_colors.insert(ID::Lane(*l), color(diff_type));
+                }
+                // A road changing direction affects all of its lanes.
+                for (r, diff_type) in &diff.roads {
+                    for l in ui.primary.map.get_r(*r).all_lanes() {
+                        opts.override_colors.insert(ID::Lane(l), color(diff_type));
+                    }
+                }
+                for (i, diff_type) in &diff.intersections {
+                    opts.override_colors
+                        .insert(ID::Intersection(*i), color(diff_type));
+                }
+                ui.draw(g, opts, &ui.primary.sim, &ShowEverything::new());
+
+                // Lanes aren't drawn individually when zoomed out, so fill in the whole road.
+                if g.canvas.cam_zoom < MIN_ZOOM_FOR_DETAIL {
+                    for (l, diff_type) in &diff.lanes {
+                        g.draw_polygon(
+                            color(diff_type),
+                            &ui.primary.map.get_parent(*l).get_thick_polygon().unwrap(),
+                        );
+                    }
+                    for (r, diff_type) in &diff.roads {
+                        g.draw_polygon(
+                            color(diff_type),
+                            &ui.primary.map.get_r(*r).get_thick_polygon().unwrap(),
+                        );
+                    }
+                }
+
+                menu.draw(g);
+                CommonState::draw_osd(g, ui, ui.primary.current_selection);
+            }
+        }
+    }
+
+    pub fn describe_state(&self) -> Vec<String> {
+        match self {
+            EditDiffView::Picking(ref wizard) => {
+                let mut lines = vec!["Picking edits to diff against".to_string()];
+                lines.extend(wizard.describe_state());
+                lines
+            }
+            EditDiffView::Viewing { ref menu, .. } => menu.describe_state(),
+        }
+    }
+}
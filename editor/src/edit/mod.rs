@@ -1,13 +1,19 @@
+mod compare;
+mod connectivity;
+mod diff;
+mod multi_select;
 mod stop_signs;
 mod traffic_signals;
 
+pub use self::multi_select::select_within;
+
 use crate::common::CommonState;
 use crate::debug::DebugMode;
 use crate::game::{GameState, Mode};
 use crate::helpers::{ColorScheme, ID};
 use crate::render::{
-    DrawCtx, DrawIntersection, DrawLane, DrawMap, DrawOptions, DrawTurn, Renderable,
-    MIN_ZOOM_FOR_DETAIL,
+    DrawCtx, DrawIntersection, DrawLane, DrawMap, DrawOptions, DrawRoad, DrawTurn,
+    LaneMarkingsCache, Renderable, MIN_ZOOM_FOR_DETAIL,
 };
 use crate::sandbox::SandboxMode;
 use crate::ui::{PerMapUI, ShowEverything, UI};
@@ -17,17 +23,21 @@ use ezgui::{
     WrappedWizard,
 };
 use map_model::{
-    IntersectionID, Lane, LaneID, LaneType, Map, MapEdits, Road, RoadID, TurnID, TurnType,
+    IntersectionID, Lane, LaneID, LaneType, Map, MapEdits, Road, RoadDirectionChange, RoadID,
+    TurnID, TurnType,
 };
 use std::collections::{BTreeSet, HashMap};
 
 pub enum EditMode {
-    ViewingDiffs(CommonState, ModalMenu),
+    ViewingDiffs(CommonState, ModalMenu, multi_select::MultiSelect),
     Saving(Wizard),
     Loading(Wizard),
     EditingStopSign(stop_signs::StopSignEditor),
     EditingTrafficSignal(traffic_signals::TrafficSignalEditor),
     BulkEditLanes(RoadID, Wizard),
+    ConnectivityProblems(connectivity::ConnectivityPanel),
+    Comparing(compare::CompareView),
+    DiffingEdits(diff::EditDiffView),
 }
 
 impl EditMode {
@@ -37,27 +47,46 @@ impl EditMode {
 
         EditMode::ViewingDiffs(
             CommonState::new(),
-            ModalMenu::new(
+            ModalMenu::new_with_help(
                 "Map Edit Mode",
                 vec![
                     vec![
-                        (hotkey(Key::Escape), "quit"),
-                        (hotkey(Key::S), "save edits"),
-                        (hotkey(Key::L), "load different edits"),
-                        (lctrl(Key::S), "sandbox mode"),
-                        (lctrl(Key::D), "debug mode"),
+                        (hotkey(Key::Escape), "quit", None),
+                        (hotkey(Key::S), "save edits", None),
+                        (hotkey(Key::L), "load different edits", None),
+                        (
+                            hotkey(Key::C),
+                            "show connectivity problems",
+                            Some("Highlight lanes that can't reach the rest of the map"),
+                        ),
+                        (
+                            hotkey(Key::V),
+                            "compare with the original map",
+                            Some("Show the map without these edits side-by-side"),
+                        ),
+                        (
+                            hotkey(Key::X),
+                            "diff with other edits",
+                            Some("Compare against another set of saved edits"),
+                        ),
+                        (lctrl(Key::S), "sandbox mode", None),
+                        (lctrl(Key::D), "debug mode", None),
                     ],
-                    CommonState::modal_menu_entries(),
+                    CommonState::modal_menu_entries()
+                        .into_iter()
+                        .map(|(key, action)| (key, action, None))
+                        .collect(),
                 ]
                 .concat(),
                 ctx,
             ),
+            multi_select::MultiSelect::new(),
         )
     }
 
     pub fn event(state: &mut GameState, ctx: &mut EventCtx) -> EventLoopMode {
         match state.mode {
-            Mode::Edit(EditMode::ViewingDiffs(ref mut common, ref mut menu)) => {
+            Mode::Edit(EditMode::ViewingDiffs(ref mut common, ref mut menu, ref mut selection)) => {
                 // The .clone() is probably not that expensive, and it makes later code a bit
                 // easier to read. :)
                 let orig_edits = state.ui.primary.map.get_edits().clone();
@@ -65,6 +94,10 @@ impl EditMode {
                 {
                     txt.add_line(orig_edits.edits_name.clone());
                     txt.add_line(format!("{} lanes", orig_edits.lane_overrides.len()));
+                    txt.add_line(format!(
+                        "{} roads changed direction",
+                        orig_edits.direction_overrides.len()
+                    ));
                     txt.add_line(format!(
                         "{} stop signs ",
                         orig_edits.stop_sign_overrides.len()
@@ -73,7 +106,19 @@ impl EditMode {
                         "{} traffic signals",
                         orig_edits.traffic_signal_overrides.len()
                     ));
+                    txt.add_line(format!(
+                        "{} intersections allowing u-turns",
+                        orig_edits.allow_uturns.len()
+                    ));
+                    let problems = state.ui.primary.connectivity.num_problems();
+                    if problems > 0 {
+                        txt.push(format!("[red:{}] connectivity problems", problems));
+                    }
+                    if !selection.selected.is_empty() {
+                        txt.add_line(format!("{} objects selected", selection.selected.len()));
+                    }
                     txt.add_line("Right-click a lane or intersection to start editing".to_string());
+                    txt.add_line("Control-drag to select many lanes at once".to_string());
                 }
                 menu.handle_event(ctx, Some(txt));
 
@@ -94,6 +139,7 @@ impl EditMode {
                 if let Some(evmode) = common.event(ctx, &mut state.ui, menu) {
                     return evmode;
                 }
+                selection.event(ctx, &state.ui);
 
                 if menu.action("quit") {
                     // TODO Warn about unsaved edits
@@ -117,6 +163,41 @@ impl EditMode {
                     state.mode = Mode::Edit(EditMode::Loading(Wizard::new()));
                     return EventLoopMode::InputOnly;
                 }
+                if !state.ui.primary.connectivity.is_empty()
+                    && menu.action("show connectivity problems")
+                {
+                    state.mode = Mode::Edit(EditMode::ConnectivityProblems(
+                        connectivity::ConnectivityPanel::new(&state.ui.primary),
+                    ));
+                    return EventLoopMode::InputOnly;
+                }
+                if menu.action("compare with the original map") {
+                    state.mode = Mode::Edit(EditMode::Comparing(compare::CompareView::new(
+                        ctx,
+                        &mut state.ui,
+                    )));
+                    return EventLoopMode::InputOnly;
+                }
+                if menu.action("diff with other edits") {
+                    state.mode = Mode::Edit(EditMode::DiffingEdits(diff::EditDiffView::new()));
+                    return EventLoopMode::InputOnly;
+                }
+
+                let selected_lanes = selection.selected_lanes();
+                if !selected_lanes.is_empty() {
+                    for (lt, name, key) in editable_lane_types() {
+                        if ctx.input.unimportant_key_pressed(
+                            key,
+                            &format!("change {} selected lanes to {}", selected_lanes.len(), name),
+                        ) {
+                            let new_edits =
+                                change_lane_types(&selected_lanes, lt, &state.ui.primary.map);
+                            apply_map_edits(&mut state.ui.primary, &state.ui.cs, ctx, new_edits);
+                            selection.selected.clear();
+                            break;
+                        }
+                    }
+                }
 
                 if let Some(ID::Lane(id)) = state.ui.primary.current_selection {
                     // TODO Urgh, borrow checker.
@@ -146,21 +227,17 @@ impl EditMode {
                     {
                         let lane = state.ui.primary.map.get_l(id);
                         let road = state.ui.primary.map.get_r(lane.parent);
-                        if lane.lane_type != LaneType::Sidewalk {
-                            for (lt, name, key) in &[
-                                (LaneType::Driving, "driving", Key::D),
-                                (LaneType::Parking, "parking", Key::P),
-                                (LaneType::Biking, "biking", Key::B),
-                                (LaneType::Bus, "bus", Key::T),
-                            ] {
-                                if can_change_lane_type(road, lane, *lt, &state.ui.primary.map)
+                        // While lanes are selected, these keys change all of them instead.
+                        if lane.lane_type != LaneType::Sidewalk && selected_lanes.is_empty() {
+                            for (lt, name, key) in editable_lane_types() {
+                                if can_change_lane_type(road, lane, lt, &state.ui.primary.map)
                                     && ctx.input.contextual_action(
-                                        *key,
+                                        key,
                                         &format!("change to {} lane", name),
                                     )
                                 {
                                     let mut new_edits = orig_edits.clone();
-                                    new_edits.lane_overrides.insert(lane.id, *lt);
+                                    new_edits.lane_overrides.insert(lane.id, lt);
                                     apply_map_edits(
                                         &mut state.ui.primary,
                                         &state.ui.cs,
@@ -173,10 +250,46 @@ impl EditMode {
                         }
                     }
 
-                    if ctx
-                        .input
-                        .contextual_action(Key::U, "bulk edit lanes on this road")
                     {
+                        let map = &state.ui.primary.map;
+                        let lane = map.get_l(id);
+                        let r = lane.parent;
+                        let one_way = if map.get_r(r).is_forwards(id) {
+                            RoadDirectionChange::MakeOneWayForward
+                        } else {
+                            RoadDirectionChange::MakeOneWayBackward
+                        };
+                        let two_way = RoadDirectionChange::MakeTwoWay(
+                            (map.num_reversible_lanes(r) / 2).max(1),
+                        );
+                        let change = if lane.lane_type.is_for_moving_vehicles()
+                            && map.can_change_direction(r, one_way)
+                            && ctx
+                                .input
+                                .contextual_action(Key::O, "make this road one-way this direction")
+                        {
+                            Some(one_way)
+                        } else if map.can_change_direction(r, two_way)
+                            && ctx
+                                .input
+                                .contextual_action(Key::W, "make this road two-way")
+                        {
+                            Some(two_way)
+                        } else {
+                            None
+                        };
+                        if let Some(change) = change {
+                            let mut new_edits = orig_edits.clone();
+                            new_edits.direction_overrides.insert(r, change);
+                            apply_map_edits(&mut state.ui.primary, &state.ui.cs, ctx, new_edits);
+                        }
+                    }
+
+                    if ctx.input.contextual_action_with_help(
+                        Key::U,
+                        "bulk edit lanes on this road",
+                        Some("Change every lane of one type on this road to another type"),
+                    ) {
                         state.mode = Mode::Edit(EditMode::BulkEditLanes(
                             state.ui.primary.map.get_l(id).parent,
                             Wizard::new(),
@@ -198,11 +311,13 @@ impl EditMode {
                             state.mode = Mode::Edit(EditMode::EditingStopSign(
                                 stop_signs::StopSignEditor::new(id, ctx, &mut state.ui),
                             ));
-                        } else if orig_edits.stop_sign_overrides.contains_key(&id)
+                        } else if (orig_edits.stop_sign_overrides.contains_key(&id)
+                            || orig_edits.allow_uturns.contains(&id))
                             && ctx.input.contextual_action(Key::R, "revert")
                         {
                             let mut new_edits = orig_edits.clone();
                             new_edits.stop_sign_overrides.remove(&id);
+                            new_edits.allow_uturns.remove(&id);
                             apply_map_edits(&mut state.ui.primary, &state.ui.cs, ctx, new_edits);
                         }
                     }
@@ -214,14 +329,29 @@ impl EditMode {
                             state.mode = Mode::Edit(EditMode::EditingTrafficSignal(
                                 traffic_signals::TrafficSignalEditor::new(id, ctx, &mut state.ui),
                             ));
-                        } else if orig_edits.traffic_signal_overrides.contains_key(&id)
+                        } else if (orig_edits.traffic_signal_overrides.contains_key(&id)
+                            || orig_edits.allow_uturns.contains(&id))
                             && ctx.input.contextual_action(Key::R, "revert")
                         {
                             let mut new_edits = orig_edits.clone();
                             new_edits.traffic_signal_overrides.remove(&id);
+                            new_edits.allow_uturns.remove(&id);
                             apply_map_edits(&mut state.ui.primary, &state.ui.cs, ctx, new_edits);
                         }
                     }
+                    if !state.ui.primary.map.are_uturns_allowed(id) {
+                        if ctx.input.contextual_action(Key::T, "allow u-turns here") {
+                            let mut new_edits = orig_edits.clone();
+                            new_edits.allow_uturns.insert(id);
+                            apply_map_edits(&mut state.ui.primary, &state.ui.cs, ctx, new_edits);
+                        }
+                    } else if orig_edits.allow_uturns.contains(&id)
+                        && ctx.input.contextual_action(Key::T, "ban u-turns here")
+                    {
+                        let mut new_edits = orig_edits.clone();
+                        new_edits.allow_uturns.remove(&id);
+                        apply_map_edits(&mut state.ui.primary, &state.ui.cs, ctx, new_edits);
+                    }
                 }
             }
             Mode::Edit(EditMode::Saving(ref mut wizard)) => {
@@ -264,15 +394,58 @@ impl EditMode {
                     state.mode = Mode::Edit(EditMode::new(ctx, &mut state.ui));
                 }
             }
+            Mode::Edit(EditMode::ConnectivityProblems(ref mut panel)) => {
+                if let Some(evmode) = panel.event(ctx, &mut state.ui) {
+                    return evmode;
+                }
+                state.mode = Mode::Edit(EditMode::new(ctx, &mut state.ui));
+            }
+            Mode::Edit(EditMode::Comparing(ref mut view)) => {
+                if view.event(ctx, &mut state.ui) {
+                    state.mode = Mode::Edit(EditMode::new(ctx, &mut state.ui));
+                }
+            }
+            Mode::Edit(EditMode::DiffingEdits(ref mut view)) => {
+                if let Some(evmode) = view.event(ctx, &mut state.ui) {
+                    return evmode;
+                }
+                state.mode = Mode::Edit(EditMode::new(ctx, &mut state.ui));
+            }
             _ => unreachable!(),
         }
 
         EventLoopMode::InputOnly
     }
 
+    pub fn describe_state(&self) -> Vec<String> {
+        match *self {
+            EditMode::ViewingDiffs(_, ref menu, _) => menu.describe_state(),
+            EditMode::Saving(ref wizard) => {
+                let mut lines = vec!["Saving edits".to_string()];
+                lines.extend(wizard.describe_state());
+                lines
+            }
+            EditMode::Loading(ref wizard) => {
+                let mut lines = vec!["Loading edits".to_string()];
+                lines.extend(wizard.describe_state());
+                lines
+            }
+            EditMode::EditingStopSign(_) => vec!["Editing a stop sign".to_string()],
+            EditMode::EditingTrafficSignal(_) => vec!["Editing a traffic signal".to_string()],
+            EditMode::BulkEditLanes(r, ref wizard) => {
+                let mut lines = vec![format!("Bulk editing lanes on {}", r)];
+                lines.extend(wizard.describe_state());
+                lines
+            }
+            EditMode::ConnectivityProblems(_) => vec!["Showing connectivity problems".to_string()],
+            EditMode::Comparing(ref view) => view.describe_state(),
+            EditMode::DiffingEdits(ref view) => view.describe_state(),
+        }
+    }
+
     pub fn draw(state: &GameState, g: &mut GfxCtx) {
         match state.mode {
-            Mode::Edit(EditMode::ViewingDiffs(ref common, ref menu)) => {
+            Mode::Edit(EditMode::ViewingDiffs(ref common, ref menu, ref selection)) => {
                 state.ui.draw(
                     g,
                     common.draw_options(&state.ui),
@@ -280,8 +453,8 @@ impl EditMode {
                     &ShowEverything::new(),
                 );
 
-                // More generally we might want to show the diff between two edits, but for now,
-                // just show diff relative to basemap.
+                // Show the diff relative to the basemap. EditDiffView handles diffing against
+                // another set of edits.
                 let edits = state.ui.primary.map.get_edits();
 
                 let ctx = DrawCtx {
@@ -301,10 +474,16 @@ impl EditMode {
                     for l in edits.lane_overrides.keys() {
                         ctx.draw_map.get_l(*l).draw(g, &opts, &ctx);
                     }
+                    for r in edits.direction_overrides.keys() {
+                        for l in ctx.map.get_r(*r).all_lanes() {
+                            ctx.draw_map.get_l(l).draw(g, &opts, &ctx);
+                        }
+                    }
                     for i in edits
                         .stop_sign_overrides
                         .keys()
                         .chain(edits.traffic_signal_overrides.keys())
+                        .chain(edits.allow_uturns.iter())
                     {
                         ctx.draw_map.get_i(*i).draw(g, &opts, &ctx);
                     }
@@ -332,17 +511,22 @@ impl EditMode {
                     for l in edits.lane_overrides.keys() {
                         g.draw_polygon(color, &ctx.map.get_parent(*l).get_thick_polygon().unwrap());
                     }
+                    for r in edits.direction_overrides.keys() {
+                        g.draw_polygon(color, &ctx.map.get_r(*r).get_thick_polygon().unwrap());
+                    }
 
                     for i in edits
                         .stop_sign_overrides
                         .keys()
                         .chain(edits.traffic_signal_overrides.keys())
+                        .chain(edits.allow_uturns.iter())
                     {
                         opts.override_colors.insert(ID::Intersection(*i), color);
                         ctx.draw_map.get_i(*i).draw(g, &opts, &ctx);
                     }
                 }
 
+                selection.draw(g, &state.ui);
                 common.draw(g, &state.ui);
                 menu.draw(g);
             }
@@ -365,6 +549,21 @@ impl EditMode {
             Mode::Edit(EditMode::EditingTrafficSignal(ref editor)) => {
                 editor.draw(g, state);
             }
+            Mode::Edit(EditMode::ConnectivityProblems(ref panel)) => {
+                state.ui.draw(
+                    g,
+                    DrawOptions::new(),
+                    &state.ui.primary.sim,
+                    &ShowEverything::new(),
+                );
+                panel.draw(g);
+            }
+            Mode::Edit(EditMode::Comparing(ref view)) => {
+                view.draw(g, &state.ui);
+            }
+            Mode::Edit(EditMode::DiffingEdits(ref view)) => {
+                view.draw(g, &state.ui);
+            }
             _ => unreachable!(),
         }
     }
@@ -397,26 +596,33 @@ fn save_edits(mut wizard: WrappedWizard, map: &mut Map) -> Option<()> {
 
 // For lane editing
 
+// The lane editor cycles through these in order. Sidewalks can't be edited, so they're absent.
+pub fn editable_lane_types() -> Vec<(LaneType, &'static str, Key)> {
+    vec![
+        (LaneType::Driving, "driving", Key::D),
+        (LaneType::Parking, "parking", Key::P),
+        (LaneType::Biking, "biking", Key::B),
+        (LaneType::Bus, "bus", Key::T),
+        (LaneType::HOV, "HOV", Key::H),
+    ]
+}
+
 fn next_valid_type(r: &Road, l: &Lane, map: &Map) -> Option<LaneType> {
-    let mut new_type = next_type(l.lane_type);
+    let mut new_type = next_type(l.lane_type)?;
     while new_type != l.lane_type {
         if can_change_lane_type(r, l, new_type, map) {
             return Some(new_type);
         }
-        new_type = next_type(new_type);
+        new_type = next_type(new_type)?;
     }
     None
 }
 
-fn next_type(lt: LaneType) -> LaneType {
-    match lt {
-        LaneType::Driving => LaneType::Parking,
-        LaneType::Parking => LaneType::Biking,
-        LaneType::Biking => LaneType::Bus,
-        LaneType::Bus => LaneType::Driving,
-
-        LaneType::Sidewalk => unreachable!(),
-    }
+// None for types that can't be edited
+pub fn next_type(lt: LaneType) -> Option<LaneType> {
+    let types = editable_lane_types();
+    let idx = types.iter().position(|(x, _, _)| *x == lt)?;
+    Some(types[(idx + 1) % types.len()].0)
 }
 
 fn can_change_lane_type(r: &Road, l: &Lane, lt: LaneType, map: &Map) -> bool {
@@ -425,6 +631,10 @@ fn can_change_lane_type(r: &Road, l: &Lane, lt: LaneType, map: &Map) -> bool {
     if l.lane_type == lt {
         return false;
     }
+    let editable = |x: LaneType| editable_lane_types().iter().any(|(y, _, _)| *y == x);
+    if !editable(l.lane_type) || !editable(lt) {
+        return false;
+    }
 
     // Only one parking lane per side.
     if lt == LaneType::Parking {
@@ -460,7 +670,10 @@ fn can_change_lane_type(r: &Road, l: &Lane, lt: LaneType, map: &Map) -> bool {
         for id in r.all_lanes() {
             if l.id != id {
                 let other_lt = map.get_l(id).lane_type;
-                if other_lt == LaneType::Driving || other_lt == LaneType::Bus {
+                if other_lt == LaneType::Driving
+                    || other_lt == LaneType::Bus
+                    || other_lt == LaneType::HOV
+                {
                     other_bus_lane = true;
                     break;
                 }
@@ -484,15 +697,33 @@ pub fn apply_map_edits(
 
     let (lanes_changed, turns_deleted, turns_added) = bundle.map.apply_edits(edits, &mut timer);
 
+    let mut markings_cache = LaneMarkingsCache::new();
+    // Roads changing direction move their center line.
+    let roads_changed: BTreeSet<RoadID> = lanes_changed
+        .iter()
+        .map(|l| bundle.map.get_l(*l).parent)
+        .collect();
+    // Changing a road's direction or width changes its outline too, so mouseover has to find
+    // every regenerated object in its new place.
+    for r in roads_changed {
+        let draw = DrawRoad::new(bundle.map.get_r(r), cs, ctx.prerender);
+        let bounds = draw.get_outline(&bundle.map).get_bounds();
+        bundle.draw_map.roads[r.0] = draw;
+        bundle.draw_map.update_object(ID::Road(r), bounds);
+    }
     for l in lanes_changed {
-        bundle.draw_map.lanes[l.0] = DrawLane::new(
+        let draw = DrawLane::new(
             bundle.map.get_l(l),
             &bundle.map,
             !bundle.current_flags.dont_draw_lane_markings,
             cs,
             ctx.prerender,
+            &mut markings_cache,
             &mut timer,
         );
+        let bounds = draw.get_outline(&bundle.map).get_bounds();
+        bundle.draw_map.lanes[l.0] = draw;
+        bundle.draw_map.update_object(ID::Lane(l), bounds);
     }
     let mut modified_intersections: BTreeSet<IntersectionID> = BTreeSet::new();
     let mut lanes_of_modified_turns: BTreeSet<LaneID> = BTreeSet::new();
@@ -525,17 +756,28 @@ pub fn apply_map_edits(
     }
 
     for i in modified_intersections {
-        bundle.draw_map.intersections[i.0] = DrawIntersection::new(
+        let draw = DrawIntersection::new(
             bundle.map.get_i(i),
             &bundle.map,
             cs,
             ctx.prerender,
             &mut timer,
         );
+        let bounds = draw.get_outline(&bundle.map).get_bounds();
+        bundle.draw_map.intersections[i.0] = draw;
+        bundle.draw_map.update_object(ID::Intersection(i), bounds);
     }
 
     // Do this after fixing up all the state above.
     bundle.map.simplify_edits(&mut timer);
+
+    bundle.connectivity = bundle.map.connectivity_audit();
+    if !bundle.connectivity.is_empty() {
+        timer.note(format!(
+            "These edits leave {} connectivity problems",
+            bundle.connectivity.num_problems()
+        ));
+    }
 }
 
 fn load_edits(map: &Map, wizard: &mut WrappedWizard, query: &str) -> Option<MapEdits> {
@@ -558,12 +800,10 @@ fn bulk_edit(r: RoadID, wizard: &mut WrappedWizard, map: &Map) -> Option<MapEdit
         .choose_something(
             "Change all lanes of type...",
             Box::new(|| {
-                vec![
-                    (None, "driving".to_string(), LaneType::Driving),
-                    (None, "parking".to_string(), LaneType::Parking),
-                    (None, "biking".to_string(), LaneType::Biking),
-                    (None, "bus".to_string(), LaneType::Bus),
-                ]
+                editable_lane_types()
+                    .into_iter()
+                    .map(|(lt, name, _)| (None, name.to_string(), lt))
+                    .collect()
             }),
         )?
         .1;
@@ -571,35 +811,35 @@ fn bulk_edit(r: RoadID, wizard: &mut WrappedWizard, map: &Map) -> Option<MapEdit
         .choose_something(
             "Change to all lanes of type...",
             Box::new(move || {
-                vec![
-                    (None, "driving".to_string(), LaneType::Driving),
-                    (None, "parking".to_string(), LaneType::Parking),
-                    (None, "biking".to_string(), LaneType::Biking),
-                    (None, "bus".to_string(), LaneType::Bus),
-                ]
-                .into_iter()
-                .filter(|(_, _, lt)| *lt != from)
-                .collect()
+                editable_lane_types()
+                    .into_iter()
+                    .filter(|(lt, _, _)| *lt != from)
+                    .map(|(lt, name, _)| (None, name.to_string(), lt))
+                    .collect()
             }),
         )?
         .1;
+    Some(bulk_edit_lanes(r, from, to, map))
+}
 
-    // Do the dirty deed. Match by road name; OSM way ID changes a fair bit.
+// Change every lane of one type along the whole street containing this road.
+pub fn bulk_edit_lanes(r: RoadID, from: LaneType, to: LaneType, map: &Map) -> MapEdits {
     let road_name = map.get_r(r).get_name();
     let mut edits = map.get_edits().clone();
     let mut cnt = 0;
-    for l in map.all_lanes() {
-        if l.lane_type != from {
-            continue;
-        }
-        let parent = map.get_parent(l.id);
-        if parent.get_name() != road_name {
-            continue;
-        }
-        // TODO This looks at the original state of the map, not with all the edits applied so far!
-        if can_change_lane_type(parent, l, to, map) {
-            edits.lane_overrides.insert(l.id, to);
-            cnt += 1;
+    for parent in map.street_roads(r) {
+        let parent = map.get_r(parent);
+        for l in parent.all_lanes() {
+            let l = map.get_l(l);
+            if l.lane_type != from {
+                continue;
+            }
+            // TODO This looks at the original state of the map, not with all the edits applied so
+            // far!
+            if can_change_lane_type(parent, l, to, map) {
+                edits.lane_overrides.insert(l.id, to);
+                cnt += 1;
+            }
         }
     }
     // TODO pop this up. warn about road names changing and being weird. :)
@@ -607,5 +847,28 @@ fn bulk_edit(r: RoadID, wizard: &mut WrappedWizard, map: &Map) -> Option<MapEdit
         "Changed {} {:?} lanes to {:?} lanes on {}",
         cnt, from, to, road_name
     );
-    Some(edits)
+    edits
+}
+
+// Lanes that can't be changed are left alone. Like bulk_edit, this checks each lane against the
+// map before any of these changes, so only change one lane per side of a road to parking or
+// biking; otherwise a road could wind up with two parking lanes on one side.
+fn change_lane_types(lanes: &Vec<LaneID>, lt: LaneType, map: &Map) -> MapEdits {
+    let mut edits = map.get_edits().clone();
+    let mut sides_changed: BTreeSet<(RoadID, bool)> = BTreeSet::new();
+    for id in lanes {
+        let lane = map.get_l(*id);
+        let road = map.get_r(lane.parent);
+        if !can_change_lane_type(road, lane, lt, map) {
+            continue;
+        }
+        if lt == LaneType::Parking || lt == LaneType::Biking {
+            let side = (road.id, road.dir_and_offset(lane.id).0);
+            if !sides_changed.insert(side) {
+                continue;
+            }
+        }
+        edits.lane_overrides.insert(*id, lt);
+    }
+    edits
 }
@@ -0,0 +1,114 @@
+use crate::helpers::ID;
+use crate::render::Renderable;
+use crate::ui::UI;
+use ezgui::{Color, EventCtx, GfxCtx, Key};
+use geom::{Bounds, Distance, Polygon, Pt2D};
+use map_model::LaneID;
+use std::collections::BTreeSet;
+
+// Hold control and drag the mouse to select every lane and intersection inside a rectangle.
+pub struct MultiSelect {
+    // Where the rectangle started, while the mouse is held down
+    dragging_from: Option<Pt2D>,
+    pub selected: BTreeSet<ID>,
+}
+
+impl MultiSelect {
+    pub fn new() -> MultiSelect {
+        MultiSelect {
+            dragging_from: None,
+            selected: BTreeSet::new(),
+        }
+    }
+
+    pub fn event(&mut self, ctx: &mut EventCtx, ui: &UI) {
+        if let Some(from) = self.dragging_from {
+            if ctx.input.left_mouse_button_released() {
+                self.dragging_from = None;
+                let to = match ctx.canvas.get_cursor_in_map_space() {
+                    Some(pt) => pt,
+                    None => {
+                        return;
+                    }
+                };
+                let rect = map_bounds(from, to);
+
+                let map = &ui.primary.map;
+                let draw_map = &ui.primary.draw_map;
+                let candidates = draw_map
+                    .get_matching_objects(rect.clone())
+                    .into_iter()
+                    .filter_map(|id| match id {
+                        ID::Lane(l) => Some((id, draw_map.get_l(l).get_outline(map).get_bounds())),
+                        ID::Intersection(i) => {
+                            Some((id, draw_map.get_i(i).get_outline(map).get_bounds()))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                self.selected = select_within(&rect, candidates);
+            }
+        } else if ctx.canvas.lctrl_held && ctx.input.left_mouse_button_pressed() {
+            self.dragging_from = ctx.canvas.get_cursor_in_map_space();
+        } else if !self.selected.is_empty()
+            && ctx
+                .input
+                .unimportant_key_pressed(Key::Backspace, "clear selection")
+        {
+            self.selected.clear();
+        }
+    }
+
+    pub fn selected_lanes(&self) -> Vec<LaneID> {
+        self.selected
+            .iter()
+            .filter_map(|id| match id {
+                ID::Lane(l) => Some(*l),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, ui: &UI) {
+        let color = ui.cs.get_def("multi-selected", Color::CYAN.alpha(0.5));
+        for id in &self.selected {
+            let outline = match id {
+                ID::Lane(l) => ui.primary.draw_map.get_l(*l).get_outline(&ui.primary.map),
+                ID::Intersection(i) => ui.primary.draw_map.get_i(*i).get_outline(&ui.primary.map),
+                _ => continue,
+            };
+            g.draw_polygon(color, &outline);
+        }
+
+        if let (Some(from), Some(to)) = (self.dragging_from, g.canvas.get_cursor_in_map_space()) {
+            let rect = map_bounds(from, to);
+            if rect.max_x > rect.min_x && rect.max_y > rect.min_y {
+                g.draw_polygon(
+                    ui.cs.get_def("selection rectangle", Color::BLUE.alpha(0.3)),
+                    &Polygon::rectangle_topleft(
+                        Pt2D::new(rect.min_x, rect.min_y),
+                        Distance::meters(rect.max_x - rect.min_x),
+                        Distance::meters(rect.max_y - rect.min_y),
+                    ),
+                );
+            }
+        }
+    }
+}
+
+fn map_bounds(pt1: Pt2D, pt2: Pt2D) -> Bounds {
+    let mut b = Bounds::new();
+    b.update(pt1);
+    b.update(pt2);
+    b
+}
+
+// Only objects lying entirely inside the rectangle count; the quadtree also returns anything
+// merely overlapping it.
+pub fn select_within(rect: &Bounds, candidates: Vec<(ID, Bounds)>) -> BTreeSet<ID> {
+    candidates
+        .into_iter()
+        .filter(|(_, b)| b.get_corners().into_iter().all(|pt| rect.contains(pt)))
+        .map(|(id, _)| id)
+        .collect()
+}
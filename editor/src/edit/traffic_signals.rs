@@ -6,10 +6,13 @@ use crate::render::{draw_signal_cycle, draw_signal_diagram, DrawCtx, DrawOptions
 use crate::ui::{ShowEverything, UI};
 use abstutil::Timer;
 use ezgui::{
-    hotkey, Color, EventCtx, GeomBatch, GfxCtx, Key, ModalMenu, MultiKey, Wizard, WrappedWizard,
+    hotkey, Color, EventCtx, GeomBatch, GfxCtx, Key, ModalMenu, MultiKey, Text, Wizard,
+    WrappedWizard,
 };
 use geom::Duration;
-use map_model::{ControlTrafficSignal, Cycle, IntersectionID, Map, TurnID, TurnPriority, TurnType};
+use map_model::{
+    ControlTrafficSignal, Cycle, IntersectionID, Map, SignalControl, TurnID, TurnPriority, TurnType,
+};
 
 // TODO Warn if there are empty cycles or if some turn is completely absent from the signal.
 pub struct TrafficSignalEditor {
@@ -20,6 +23,7 @@ pub struct TrafficSignalEditor {
     // drawing stuff. Better way to represent nested states?
     cycle_duration_wizard: Option<Wizard>,
     preset_wizard: Option<Wizard>,
+    actuated_wizard: Option<Wizard>,
     icon_selected: Option<TurnID>,
 }
 
@@ -40,6 +44,8 @@ impl TrafficSignalEditor {
                 (hotkey(Key::Backspace), "delete current cycle"),
                 (hotkey(Key::N), "add a new empty cycle"),
                 (hotkey(Key::M), "add a new pedestrian scramble cycle"),
+                (hotkey(Key::A), "toggle actuated control"),
+                (hotkey(Key::G), "change actuated timing"),
             ],
             ctx,
         );
@@ -49,13 +55,26 @@ impl TrafficSignalEditor {
             current_cycle: 0,
             cycle_duration_wizard: None,
             preset_wizard: None,
+            actuated_wizard: None,
             icon_selected: None,
         }
     }
 
     // Returns true if the editor is done and we should go back to main edit mode.
     pub fn event(&mut self, ctx: &mut EventCtx, ui: &mut UI) -> bool {
-        self.menu.handle_event(ctx, None);
+        let mut txt = Text::prompt(&format!("Traffic Signal Editor for {}", self.i));
+        match ui.primary.map.get_traffic_signal(self.i).control {
+            SignalControl::FixedTime => {
+                txt.add_line("Fixed-time control".to_string());
+            }
+            SignalControl::Actuated { gap_out } => {
+                txt.add_line(format!(
+                    "Actuated control, gapping out after {}",
+                    gap_out.to_string_hms()
+                ));
+            }
+        }
+        self.menu.handle_event(ctx, Some(txt));
         ctx.canvas.handle_event(ctx.input);
 
         if ctx.redo_mouseover() {
@@ -106,6 +125,20 @@ impl TrafficSignalEditor {
             } else if self.preset_wizard.as_ref().unwrap().aborted() {
                 self.preset_wizard = None;
             }
+        } else if self.actuated_wizard.is_some() {
+            if let Some((min_green, max_green, gap_out)) = edit_actuated_timing(
+                &signal,
+                self.current_cycle,
+                self.actuated_wizard.as_mut().unwrap().wrap(ctx),
+            ) {
+                signal.cycles[self.current_cycle].min_green = min_green;
+                signal.cycles[self.current_cycle].max_green = max_green;
+                signal.control = SignalControl::Actuated { gap_out };
+                changed = true;
+                self.actuated_wizard = None;
+            } else if self.actuated_wizard.as_ref().unwrap().aborted() {
+                self.actuated_wizard = None;
+            }
         } else if let Some(id) = self.icon_selected {
             let cycle = &mut signal.cycles[self.current_cycle];
             // Just one key to toggle between the 3 states
@@ -160,6 +193,16 @@ impl TrafficSignalEditor {
                 self.cycle_duration_wizard = Some(Wizard::new());
             } else if self.menu.action("choose a preset signal") {
                 self.preset_wizard = Some(Wizard::new());
+            } else if self.menu.action("toggle actuated control") {
+                signal.control = match signal.control {
+                    SignalControl::FixedTime => SignalControl::default_actuated(),
+                    SignalControl::Actuated { .. } => SignalControl::FixedTime,
+                };
+                changed = true;
+            } else if signal.control != SignalControl::FixedTime
+                && self.menu.action("change actuated timing")
+            {
+                self.actuated_wizard = Some(Wizard::new());
             } else if self.menu.action("reset to original") {
                 signal = ControlTrafficSignal::get_possible_policies(&ui.primary.map, self.i)
                     .remove(0)
@@ -279,6 +322,8 @@ impl TrafficSignalEditor {
             wizard.draw(g);
         } else if let Some(ref wizard) = self.preset_wizard {
             wizard.draw(g);
+        } else if let Some(ref wizard) = self.actuated_wizard {
+            wizard.draw(g);
         }
 
         self.menu.draw(g);
@@ -310,3 +355,33 @@ fn choose_preset(
         )
         .map(|(_, ts)| ts)
 }
+
+// Returns the current cycle's min and max green time and the signal's gap-out time.
+fn edit_actuated_timing(
+    signal: &ControlTrafficSignal,
+    current_cycle: usize,
+    mut wizard: WrappedWizard,
+) -> Option<(Duration, Duration, Duration)> {
+    let cycle = &signal.cycles[current_cycle];
+    let gap_out = match signal.control {
+        SignalControl::Actuated { gap_out } => gap_out,
+        SignalControl::FixedTime => unreachable!(),
+    };
+
+    let min_green = wizard.input_something(
+        "Minimum green time for this cycle? (like 1m 30s)",
+        Some(cycle.min_green.to_string_hms()),
+        Box::new(|line| Duration::parse_hms(&line).filter(|d| *d > Duration::ZERO)),
+    )?;
+    let max_green = wizard.input_something(
+        "Maximum green time for this cycle?",
+        Some(cycle.max_green.to_string_hms()),
+        Box::new(move |line| Duration::parse_hms(&line).filter(|d| *d >= min_green)),
+    )?;
+    let gap_out = wizard.input_something(
+        "End the cycle early when nobody new arrives for how long?",
+        Some(gap_out.to_string_hms()),
+        Box::new(|line| Duration::parse_hms(&line).filter(|d| *d > Duration::ZERO)),
+    )?;
+    Some((min_green, max_green, gap_out))
+}
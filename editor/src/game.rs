@@ -1,16 +1,16 @@
 use crate::abtest::ABTestMode;
 use crate::debug::DebugMode;
-use crate::edit::EditMode;
+use crate::edit::{apply_map_edits, EditMode};
 use crate::helpers::ID;
 use crate::mission::MissionEditMode;
 use crate::render::DrawOptions;
 use crate::sandbox::SandboxMode;
 use crate::tutorial::TutorialMode;
-use crate::ui::{EditorState, Flags, ShowEverything, UI};
+use crate::ui::{EditorState, Flags, Session, SessionMode, ShowEverything, SESSION_PATH, UI};
 use abstutil::elapsed_seconds;
 use ezgui::{hotkey, Canvas, EventCtx, EventLoopMode, GfxCtx, Key, UserInput, Wizard, GUI};
 use geom::{Duration, Line, Pt2D, Speed};
-use map_model::Map;
+use map_model::{Map, MapEdits};
 use rand::seq::SliceRandom;
 use rand::Rng;
 use rand_xorshift::XorShiftRng;
@@ -22,6 +22,8 @@ use std::time::Instant;
 pub struct GameState {
     pub mode: Mode,
     pub ui: UI,
+    // Offered on the splash screen until something else is picked
+    last_session: Option<Session>,
 }
 
 // TODO Need to reset_sim() when entering Edit, Tutorial, Mission, or ABTest and when leaving
@@ -47,6 +49,7 @@ impl GameState {
         let mut game = GameState {
             mode: Mode::Sandbox(SandboxMode::new(ctx)),
             ui: UI::new(flags, ctx),
+            last_session: None,
         };
 
         let rand_focus_pt = game
@@ -67,6 +70,7 @@ impl GameState {
             .expect("Can't get canonical_point of a random building or lane");
 
         if splash {
+            game.last_session = Session::load(SESSION_PATH);
             ctx.canvas.center_on_map_pt(rand_focus_pt);
             game.mode = Mode::SplashScreen(
                 Wizard::new(),
@@ -93,6 +97,28 @@ impl GameState {
         game
     }
 
+    // What's going on, in words instead of pixels, so tests can check it
+    pub fn describe_state(&self) -> Vec<String> {
+        let mut lines = match self.mode {
+            Mode::SplashScreen(ref wizard, _) => {
+                let mut lines = vec!["Splash screen".to_string()];
+                lines.extend(wizard.describe_state());
+                lines
+            }
+            Mode::Edit(ref mode) => mode.describe_state(),
+            Mode::Tutorial(_) => vec!["Tutorial mode".to_string()],
+            Mode::Sandbox(ref mode) => mode.describe_state(),
+            Mode::Debug(_) => vec!["Debug mode".to_string()],
+            Mode::Mission(_) => vec!["Mission edit mode".to_string()],
+            Mode::ABTest(_) => vec!["A/B test mode".to_string()],
+        };
+        lines.push(match self.ui.primary.current_selection {
+            Some(id) => format!("Selected {:?}", id),
+            None => "Nothing selected".to_string(),
+        });
+        lines
+    }
+
     fn save_editor_state(&self, canvas: &Canvas) {
         let state = EditorState {
             map_name: self.ui.primary.map.get_name().clone(),
@@ -104,11 +130,34 @@ impl GameState {
         abstutil::write_json("../editor_state.json", &state)
             .expect("Saving editor_state.json failed");
         println!("Saved editor_state.json");
+
+        Session {
+            map_name: state.map_name,
+            edits_name: self.ui.primary.map.get_edits().edits_name.clone(),
+            mode: match self.mode {
+                Mode::Edit(_) => SessionMode::Edit,
+                Mode::Debug(_) => SessionMode::Debug,
+                _ => SessionMode::Sandbox,
+            },
+            cam_x: state.cam_x,
+            cam_y: state.cam_y,
+            cam_zoom: state.cam_zoom,
+        }
+        .save(SESSION_PATH);
     }
 }
 
 impl GUI for GameState {
     fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
+        // Cheap enough to check every event, and lets colors be tweaked without a restart.
+        if self.ui.reload_assets_if_changed(ctx) {
+            if let Mode::ABTest(ref mut abtest) = self.mode {
+                if let Some(ref mut secondary) = abtest.secondary {
+                    secondary.regenerate_draw_map(&self.ui.cs, ctx);
+                }
+            }
+        }
+
         match self.mode {
             Mode::SplashScreen(ref mut wizard, ref mut maybe_screensaver) => {
                 let anim = maybe_screensaver.is_some();
@@ -116,8 +165,14 @@ impl GUI for GameState {
                     screensaver.update(rng, ctx.input, ctx.canvas, &self.ui.primary.map);
                 }
 
-                if let Some(new_mode) = splash_screen(wizard, ctx, &mut self.ui, maybe_screensaver)
-                {
+                if let Some(new_mode) = splash_screen(
+                    wizard,
+                    ctx,
+                    &mut self.ui,
+                    maybe_screensaver,
+                    &mut self.last_session,
+                ) {
+                    self.last_session = None;
                     self.mode = new_mode;
                 } else if wizard.aborted() {
                     self.before_quit(ctx.canvas);
@@ -163,6 +218,15 @@ impl GUI for GameState {
         );*/
     }
 
+    fn draw_secondary(&self, g: &mut GfxCtx) {
+        g.clear(self.ui.cs.get("true background"));
+        match self.mode {
+            Mode::Sandbox(_) => SandboxMode::draw_secondary(self, g),
+            Mode::Mission(_) => MissionEditMode::draw_secondary(self, g),
+            _ => {}
+        }
+    }
+
     fn dump_before_abort(&self, canvas: &Canvas) {
         println!(
             "********************************************************************************"
@@ -239,8 +303,10 @@ fn splash_screen(
     ctx: &mut EventCtx,
     ui: &mut UI,
     maybe_screensaver: &mut Option<(Screensaver, XorShiftRng)>,
+    last_session: &mut Option<Session>,
 ) -> Option<Mode> {
     let mut wizard = raw_wizard.wrap(ctx);
+    let resume = "Resume last session";
     let sandbox = "Sandbox mode";
     let load_map = "Load another map";
     let edit = "Edit map";
@@ -253,24 +319,26 @@ fn splash_screen(
 
     // Loop because we might go from About -> top-level menu repeatedly, and recursion is scary.
     loop {
-        // TODO No hotkey for quit because it's just the normal menu escape?
+        let mut choices = vec![
+            (hotkey(Key::S), sandbox),
+            (hotkey(Key::L), load_map),
+            (hotkey(Key::E), edit),
+            (hotkey(Key::T), tutorial),
+            (hotkey(Key::D), debug),
+            (hotkey(Key::M), mission),
+            (hotkey(Key::A), abtest),
+            // TODO No hotkey for quit because it's just the normal menu escape?
+            (None, about),
+            (None, quit),
+        ];
+        if last_session.is_some() {
+            choices.insert(0, (hotkey(Key::R), resume));
+        }
         match wizard
-            .choose_string_hotkeys(
-                "Welcome to A/B Street!",
-                vec![
-                    (hotkey(Key::S), sandbox),
-                    (hotkey(Key::L), load_map),
-                    (hotkey(Key::E), edit),
-                    (hotkey(Key::T), tutorial),
-                    (hotkey(Key::D), debug),
-                    (hotkey(Key::M), mission),
-                    (hotkey(Key::A), abtest),
-                    (None, about),
-                    (None, quit),
-                ],
-            )?
+            .choose_string_hotkeys("Welcome to A/B Street!", choices)?
             .as_str()
         {
+            x if x == resume => break Some(resume_session(last_session.take().unwrap(), ctx, ui)),
             x if x == sandbox => break Some(Mode::Sandbox(SandboxMode::new(ctx))),
             x if x == load_map => {
                 let current_map = ui.primary.map.get_name().to_string();
@@ -324,3 +392,29 @@ fn splash_screen(
         }
     }
 }
+
+fn resume_session(session: Session, ctx: &mut EventCtx, ui: &mut UI) -> Mode {
+    if ui.primary.map.get_name() != &session.map_name {
+        let mut flags = ui.primary.current_flags.clone();
+        flags.sim_flags.load = PathBuf::from(format!("../data/maps/{}.bin", session.map_name));
+        *ui = UI::new(flags, ctx);
+    }
+    if ui.primary.map.get_edits().edits_name != session.edits_name {
+        apply_map_edits(
+            &mut ui.primary,
+            &ui.cs,
+            ctx,
+            MapEdits::load(&session.map_name, &session.edits_name),
+        );
+        ui.primary.reset_sim();
+    }
+    ctx.canvas.cam_x = session.cam_x;
+    ctx.canvas.cam_y = session.cam_y;
+    ctx.canvas.cam_zoom = session.cam_zoom;
+
+    match session.mode {
+        SessionMode::Sandbox => Mode::Sandbox(SandboxMode::new(ctx)),
+        SessionMode::Edit => Mode::Edit(EditMode::new(ctx, ui)),
+        SessionMode::Debug => Mode::Debug(DebugMode::new(ctx, ui)),
+    }
+}
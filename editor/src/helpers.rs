@@ -4,7 +4,9 @@ use abstutil;
 use ezgui::{Color, GfxCtx, Text};
 use geom::Pt2D;
 use map_model::raw_data::StableRoadID;
-use map_model::{AreaID, BuildingID, BusStopID, IntersectionID, LaneID, Map, RoadID, TurnID};
+use map_model::{
+    AreaID, BuildingID, BusStopID, IntersectionID, LaneID, Map, MentionedID, RoadID, TurnID,
+};
 use serde_derive::{Deserialize, Serialize};
 use sim::{AgentID, CarID, GetDrawAgents, PedestrianID, Sim, TripID};
 use std::collections::{BTreeMap, HashMap};
@@ -33,6 +35,26 @@ impl ID {
         }
     }
 
+    // None if the object isn't in this map anymore. Roads are selected through their first lane.
+    pub fn from_mention(m: MentionedID, map: &Map) -> Option<ID> {
+        match m {
+            MentionedID::Road(r) => map.maybe_get_r(r).map(|r| ID::Lane(r.all_lanes()[0])),
+            MentionedID::Lane(l) => map.maybe_get_l(l).map(|l| ID::Lane(l.id)),
+            MentionedID::Intersection(i) => map.maybe_get_i(i).map(|i| ID::Intersection(i.id)),
+            MentionedID::Building(b) => map.maybe_get_b(b).map(|b| ID::Building(b.id)),
+            MentionedID::StableRoad(id) => map
+                .all_roads()
+                .iter()
+                .find(|r| r.stable_id == id)
+                .map(|r| ID::Lane(r.all_lanes()[0])),
+            MentionedID::StableIntersection(id) => map
+                .all_intersections()
+                .iter()
+                .find(|i| i.stable_id == id)
+                .map(|i| ID::Intersection(i.id)),
+        }
+    }
+
     pub fn agent_id(&self) -> Option<AgentID> {
         match *self {
             ID::Car(id) => Some(AgentID::Car(id)),
@@ -223,11 +245,48 @@ fn styled_kv(txt: &mut Text, tags: &BTreeMap<String, String>) {
     }
 }
 
+pub const COLOR_SCHEME_PATH: &str = "../color_scheme.json";
+
 pub struct ColorScheme {
     map: HashMap<String, Color>,
 
     // A subset of map
     modified: ModifiedColors,
+
+    palette: Palette,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Palette {
+    Standard,
+    // Swaps the red/green pairs used to mean bad/good for orange/blue, from the Okabe-Ito palette.
+    // Only affects colors that haven't been modified.
+    Colorblind,
+}
+
+impl Palette {
+    fn remap(self, name: &str) -> Option<Color> {
+        if self == Palette::Standard {
+            return None;
+        }
+        let orange = Color::rgb(213, 94, 0);
+        let blue = Color::rgb(0, 114, 178);
+        match name {
+            "occupied parking spot" | "full parking lot" => Some(orange.alpha(0.5)),
+            "free parking spot" => Some(blue.alpha(0.5)),
+            "full bike rack" | "stuck bike" | "stop turn" | "left turn" | "edits diff removed"
+            | "trip leg driving" => Some(orange),
+            "moving bike"
+            | "priority stop sign turn"
+            | "right turn"
+            | "edits diff added"
+            | "trip leg walking"
+            | "unzoomed map diffs" => Some(blue),
+            "unzoomed car" => Some(orange.alpha(0.5)),
+            "unzoomed bike" => Some(blue.alpha(0.5)),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -237,30 +296,48 @@ struct ModifiedColors {
 
 impl ColorScheme {
     pub fn load() -> Result<ColorScheme, Error> {
-        let modified: ModifiedColors = abstutil::read_json("../color_scheme.json")?;
+        let modified: ModifiedColors = abstutil::read_json(COLOR_SCHEME_PATH)?;
         let mut map: HashMap<String, Color> = default_colors();
         for (name, c) in &modified.map {
             map.insert(name.clone(), *c);
         }
 
-        Ok(ColorScheme { map, modified })
+        Ok(ColorScheme {
+            map,
+            modified,
+            palette: Palette::Standard,
+        })
     }
 
     pub fn save(&self) {
-        abstutil::write_json("../color_scheme.json", &self.modified)
+        abstutil::write_json(COLOR_SCHEME_PATH, &self.modified)
             .expect("Saving color_scheme.json failed");
     }
 
     // Get, but specify the default inline. The default is extracted before compilation by a script
     // and used to generate default_colors().
     pub fn get_def(&self, name: &str, _default: Color) -> Color {
-        self.map[name]
+        self.get(name)
     }
 
     pub fn get(&self, name: &str) -> Color {
+        if !self.modified.map.contains_key(name) {
+            if let Some(c) = self.palette.remap(name) {
+                return c;
+            }
+        }
         self.map[name]
     }
 
+    // Anything already drawn with the old palette has to be redrawn.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn get_palette(&self) -> Palette {
+        self.palette
+    }
+
     // Just for the color picker plugin, that's why the funky return value
     pub fn color_names(&self) -> Vec<(String, ())> {
         let mut names: Vec<(String, ())> = self.map.keys().map(|n| (n.clone(), ())).collect();
@@ -0,0 +1,23 @@
+mod abtest;
+mod common;
+mod debug;
+mod edit;
+mod game;
+mod helpers;
+mod mission;
+mod render;
+mod sandbox;
+mod tutorial;
+mod ui;
+
+pub use crate::common::{Annotation, Annotations};
+pub use crate::edit::{bulk_edit_lanes, editable_lane_types, next_type, select_within};
+pub use crate::game::GameState;
+pub use crate::helpers::{ColorScheme, Palette, ID};
+pub use crate::mission::{Trip, TripEndpt};
+pub use crate::render::{
+    calculate_corners, parking_spot_occupancy, parking_spot_polygons, LaneMarkingsCache,
+    ObjectQuadtree,
+};
+pub use crate::sandbox::Throughput;
+pub use crate::ui::{Flags, Session, SessionMode, SESSION_PATH};
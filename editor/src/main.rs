@@ -1,19 +1,22 @@
-mod abtest;
-mod common;
-mod debug;
-mod edit;
-mod game;
-mod helpers;
-mod mission;
-mod render;
-mod sandbox;
-mod tutorial;
-mod ui;
-
+use editor::{Flags, GameState};
 use structopt::StructOpt;
 
 fn main() {
-    ezgui::run("A/B Street", 1800.0, 800.0, |ctx| {
-        game::GameState::new(ui::Flags::from_args(), ctx)
-    });
+    let flags = Flags::from_args();
+    if flags.secondary_window {
+        ezgui::run_with_secondary_window(
+            "A/B Street",
+            1800.0,
+            800.0,
+            "A/B Street panels",
+            800.0,
+            800.0,
+            flags.ignore_hidpi,
+            |ctx| GameState::new(flags, ctx),
+        );
+    } else {
+        ezgui::run("A/B Street", 1800.0, 800.0, flags.ignore_hidpi, |ctx| {
+            GameState::new(flags, ctx)
+        });
+    }
 }
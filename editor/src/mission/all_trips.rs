@@ -30,15 +30,11 @@ impl TripsVisualizer {
             let map = &ui.primary.map;
             let maybe_trips =
                 timer.parallelize("calculate paths with geometry", all_trips, |mut trip| {
-                    let req = trip.path_req(map);
-                    if let Some(route) = map
-                        .pathfind(req.clone())
-                        .and_then(|path| path.trace(map, req.start.dist_along(), None))
-                    {
+                    if let Some(route) = trip.calculate_route(map) {
                         trip.route = Some(route);
                         MaybeTrip::Success(trip)
                     } else {
-                        MaybeTrip::Failure(req)
+                        MaybeTrip::Failure(trip.path_req(map))
                     }
                 });
             let mut final_trips = Vec::new();
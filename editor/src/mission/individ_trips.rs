@@ -1,17 +1,24 @@
 use crate::common::CommonState;
 use crate::helpers::ID;
-use crate::mission::trips::{clip_trips, Trip, TripEndpt};
+use crate::mission::trips::{
+    clip_trips, expected_building_types, purpose_mismatches, Trip, TripEndpt,
+};
 use crate::ui::{ShowEverything, UI};
 use abstutil::prettyprint_usize;
-use ezgui::{hotkey, Color, EventCtx, GfxCtx, ItemSlider, Key, Text};
-use geom::{Circle, Distance, Line, Speed};
-use map_model::BuildingID;
+use ezgui::{hotkey, Color, EventCtx, GfxCtx, ItemSlider, Key, LogScroller, Text};
+use geom::{Circle, Distance, Line, PolyLine, Speed};
+use map_model::{BuildingID, Map, LANE_THICKNESS};
 use popdat::psrc;
 use std::collections::HashMap;
 
 pub struct TripsVisualizer {
     slider: ItemSlider<Trip>,
     bldgs: HashMap<BuildingID, psrc::Parcel>,
+    // Indexed like the slider. Calculated as each trip is shown; None if there's no path.
+    routes: HashMap<usize, Option<PolyLine>>,
+    // Calculated up-front, since the slider owns the trips
+    mismatches: Vec<String>,
+    showing_mismatches: Option<LogScroller>,
 }
 
 impl TripsVisualizer {
@@ -20,20 +27,35 @@ impl TripsVisualizer {
             // TODO We'll break if there are no matching trips
             clip_trips(ui, &mut timer)
         });
+        let mismatches = purpose_mismatches(&trips, &ui.primary.map);
         TripsVisualizer {
             slider: ItemSlider::new(
                 trips,
                 "Trips Visualizer",
                 "trip",
-                vec![(hotkey(Key::Escape), "quit")],
+                vec![
+                    (hotkey(Key::Escape), "quit"),
+                    (hotkey(Key::M), "show purpose mismatches"),
+                ],
                 ctx,
             ),
             bldgs,
+            routes: HashMap::new(),
+            mismatches,
+            showing_mismatches: None,
         }
     }
 
     // Returns true if the we're done
     pub fn event(&mut self, ctx: &mut EventCtx, ui: &mut UI) -> bool {
+        if let Some(ref mut scroller) = self.showing_mismatches {
+            if scroller.event(ctx.input) {
+                self.showing_mismatches = None;
+            }
+            return false;
+        }
+
+        self.calculate_current_route(&ui.primary.map);
         let (idx, trip) = self.slider.get();
         let mut txt = Text::prompt("Trips Visualizer");
         txt.add_line(format!(
@@ -46,6 +68,17 @@ impl TripsVisualizer {
             "Purpose: {:?} -> {:?}",
             trip.purpose.0, trip.purpose.1
         ));
+        if let TripEndpt::Building(b) = trip.to {
+            let bldg_type = ui.primary.map.get_b(b).building_type;
+            let unexpected = expected_building_types(trip.purpose.1)
+                .map(|expected| !expected.contains(&bldg_type))
+                .unwrap_or(false);
+            txt.add_line(format!(
+                "Ends at a {} building{}",
+                bldg_type,
+                if unexpected { " (unexpected)" } else { "" }
+            ));
+        }
         txt.add_line(format!("Mode: {:?}", trip.mode));
         txt.add_line(format!("Trip time: {}", trip.trip_time));
         txt.add_line(format!("Trip distance: {}", trip.trip_dist));
@@ -53,8 +86,15 @@ impl TripsVisualizer {
             "Average speed {}",
             Speed::from_dist_time(trip.trip_dist, trip.trip_time)
         ));
+        if let Some(ref route) = self.routes[&idx] {
+            txt.add_line(format!("Route length: {}", route.length()));
+        } else {
+            txt.add_line("No path found; showing a straight line".to_string());
+        }
 
         self.slider.event(ctx, Some(txt));
+        // The slider may have moved to a different trip
+        self.calculate_current_route(&ui.primary.map);
         ctx.canvas.handle_event(ctx.input);
 
         if ctx.redo_mouseover() {
@@ -69,14 +109,41 @@ impl TripsVisualizer {
         if self.slider.action("quit") {
             return true;
         }
+        if self.slider.action("show purpose mismatches") {
+            self.showing_mismatches = Some(LogScroller::new(
+                "Trip purposes vs. destination building types".to_string(),
+                self.mismatches.clone(),
+            ));
+        }
         false
     }
 
+    fn calculate_current_route(&mut self, map: &Map) {
+        let (idx, trip) = self.slider.get();
+        if !self.routes.contains_key(&idx) {
+            self.routes.insert(idx, trip.calculate_route(map));
+        }
+    }
+
     pub fn draw(&self, g: &mut GfxCtx, ui: &UI) {
-        let (_, trip) = self.slider.get();
+        let (idx, trip) = self.slider.get();
         let from = trip.from.polygon(&ui.primary.map);
         let to = trip.to.polygon(&ui.primary.map);
 
+        if let Some(Some(ref route)) = self.routes.get(&idx) {
+            g.draw_polygon(
+                ui.cs.get_def("trip route", Color::PURPLE.alpha(0.8)),
+                &route.make_polygons(LANE_THICKNESS),
+            );
+        } else if let Some(line) = Line::maybe_new(from.center(), to.center()) {
+            g.draw_line(
+                ui.cs
+                    .get_def("trip without route", Color::ORANGE.alpha(0.8)),
+                Distance::meters(5.0),
+                &line,
+            );
+        }
+
         g.draw_polygon(Color::RED, from);
         g.draw_polygon(Color::BLUE, to);
 
@@ -109,6 +176,10 @@ impl TripsVisualizer {
         }
 
         self.slider.draw(g);
+        if let Some(ref scroller) = self.showing_mismatches {
+            scroller.draw(g);
+            return;
+        }
         if let Some(ID::Building(b)) = ui.primary.current_selection {
             let mut osd = Text::new();
             osd.append(format!("{}", b), Some(ui.cs.get("OSD ID color")));
@@ -6,6 +6,7 @@ mod scenario;
 mod trips;
 
 use self::trips::{pick_time_range, trips_to_scenario};
+pub use self::trips::{Trip, TripEndpt};
 use crate::game::{GameState, Mode};
 use crate::render::DrawOptions;
 use crate::sandbox::SandboxMode;
@@ -181,6 +182,17 @@ impl MissionEditMode {
             _ => unreachable!(),
         }
     }
+
+    pub fn draw_secondary(state: &GameState, g: &mut GfxCtx) {
+        match state.mode {
+            Mode::Mission(ref mode) => {
+                if let State::Scenario(ref editor) = mode.state {
+                    editor.draw_secondary(g);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
 }
 
 pub fn input_time(wizard: &mut WrappedWizard, query: &str) -> Option<Duration> {
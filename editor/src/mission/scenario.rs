@@ -5,8 +5,11 @@ use crate::ui::UI;
 use abstutil::{Timer, WeightedUsizeChoice};
 use ezgui::{hotkey, EventCtx, GfxCtx, Key, LogScroller, ModalMenu, Wizard, WrappedWizard};
 use geom::Duration;
-use map_model::{IntersectionID, Map, Neighborhood};
-use sim::{BorderSpawnOverTime, OriginDestination, Scenario, SeedParkedCars, SpawnOverTime};
+use map_model::{BuildingType, IntersectionID, Map, Neighborhood};
+use sim::{
+    BorderSpawnOverTime, ODMatrix, OriginDestination, Scenario, SeedParkedCars, SpawnOverTime,
+};
+use std::collections::{BTreeMap, HashSet};
 
 pub enum ScenarioEditor {
     PickScenario(Wizard),
@@ -31,9 +34,11 @@ impl ScenarioEditor {
     pub fn event(&mut self, ctx: &mut EventCtx, ui: &mut UI) -> Option<Mode> {
         match self {
             ScenarioEditor::PickScenario(ref mut wizard) => {
-                if let Some(scenario) = pick_scenario(&ui.primary.map, wizard.wrap(ctx)) {
-                    let scroller =
-                        LogScroller::new(scenario.scenario_name.clone(), scenario.describe());
+                if let Some(scenario) = pick_scenario(ui, wizard.wrap(ctx)) {
+                    let scroller = LogScroller::new(
+                        scenario.scenario_name.clone(),
+                        scenario.describe(&ui.primary.map),
+                    );
                     *self = ScenarioEditor::ManageScenario(
                         ScenarioEditor::modal_menu(&scenario.scenario_name, ctx),
                         scenario,
@@ -67,8 +72,10 @@ impl ScenarioEditor {
             }
             ScenarioEditor::EditScenario(ref mut scenario, ref mut wizard) => {
                 if let Some(()) = edit_scenario(&ui.primary.map, scenario, wizard.wrap(ctx)) {
-                    let scroller =
-                        LogScroller::new(scenario.scenario_name.clone(), scenario.describe());
+                    let scroller = LogScroller::new(
+                        scenario.scenario_name.clone(),
+                        scenario.describe(&ui.primary.map),
+                    );
                     // TODO autosave, or at least make it clear there are unsaved edits
                     *self = ScenarioEditor::ManageScenario(
                         ScenarioEditor::modal_menu(&scenario.scenario_name, ctx),
@@ -76,8 +83,10 @@ impl ScenarioEditor {
                         scroller,
                     );
                 } else if wizard.aborted() {
-                    let scroller =
-                        LogScroller::new(scenario.scenario_name.clone(), scenario.describe());
+                    let scroller = LogScroller::new(
+                        scenario.scenario_name.clone(),
+                        scenario.describe(&ui.primary.map),
+                    );
                     *self = ScenarioEditor::ManageScenario(
                         ScenarioEditor::modal_menu(&scenario.scenario_name, ctx),
                         scenario.clone(),
@@ -95,7 +104,9 @@ impl ScenarioEditor {
                 wizard.draw(g);
             }
             ScenarioEditor::ManageScenario(ref menu, _, scroller) => {
-                scroller.draw(g);
+                if !g.canvas.has_secondary_window() {
+                    scroller.draw(g);
+                }
                 menu.draw(g);
             }
             ScenarioEditor::EditScenario(_, wizard) => {
@@ -106,15 +117,73 @@ impl ScenarioEditor {
             }
         }
     }
+
+    pub fn draw_secondary(&self, g: &mut GfxCtx) {
+        if let ScenarioEditor::ManageScenario(_, _, ref scroller) = self {
+            scroller.draw(g);
+        }
+    }
 }
 
-fn pick_scenario(map: &Map, mut wizard: WrappedWizard) -> Option<Scenario> {
+fn pick_scenario(ui: &UI, mut wizard: WrappedWizard) -> Option<Scenario> {
+    let map = &ui.primary.map;
     let load_existing = "Load existing scenario";
+    let clone_existing = "Clone existing scenario";
     let create_new = "Create new scenario";
-    if wizard.choose_string("What scenario to edit?", vec![load_existing, create_new])?
-        == load_existing
-    {
+    let import_od = "Import OD matrix...";
+    let choice = wizard.choose_string(
+        "What scenario to edit?",
+        vec![load_existing, clone_existing, create_new, import_od],
+    )?;
+    if choice == load_existing {
         load_scenario(map, &mut wizard, "Load which scenario?")
+    } else if choice == clone_existing {
+        let original = load_scenario(map, &mut wizard, "Clone which scenario?")?;
+        // Don't let the copy clobber an existing scenario
+        let existing: HashSet<String> = abstutil::list_all_objects("scenarios", map.get_name())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let scenario_name = wizard.input_something(
+            "Name the copy",
+            None,
+            Box::new(move |line| {
+                if line.is_empty() || existing.contains(&line) {
+                    None
+                } else {
+                    Some(line)
+                }
+            }),
+        )?;
+        Some(original.clone_as(scenario_name))
+    } else if choice == import_od {
+        let csv_path = wizard.input_string("Path to the CSV of zone-to-zone trip counts?")?;
+        let zones_path = wizard.input_something(
+            "Path to the GeoJSON of zones?",
+            None,
+            Box::new(|line| {
+                if line.ends_with(".geojson") || line.ends_with(".json") {
+                    Some(line)
+                } else {
+                    None
+                }
+            }),
+        )?;
+        let scenario_name = wizard.input_string("Name the scenario")?;
+        match ODMatrix::load(&csv_path, &zones_path) {
+            Ok(matrix) => Some(matrix.to_scenario(
+                scenario_name,
+                map,
+                &mut ui.primary.current_flags.sim_flags.make_rng(),
+                &mut Timer::new("import OD matrix"),
+            )),
+            Err(err) => {
+                if wizard.acknowledge("Couldn't import OD matrix", vec![&err.to_string()]) {
+                    wizard.abort();
+                }
+                None
+            }
+        }
     } else {
         let scenario_name = wizard.input_string("Name the scenario")?;
         Some(Scenario {
@@ -124,6 +193,8 @@ fn pick_scenario(map: &Map, mut wizard: WrappedWizard) -> Option<Scenario> {
             spawn_over_time: Vec::new(),
             border_spawn_over_time: Vec::new(),
             individ_trips: Vec::new(),
+            trip_metadata: BTreeMap::new(),
+            lane_closures: Vec::new(),
         })
     }
 }
@@ -170,6 +241,9 @@ fn edit_scenario(map: &Map, scenario: &mut Scenario, mut wizard: WrappedWizard)
                 percent_use_transit: wizard.input_percent(
                     "What percent of the walking trips will consider taking transit?",
                 )?,
+                percent_trucks: wizard.input_percent(
+                    "What percent of the trips without a parked car will be trucks?",
+                )?,
             });
         }
         x if x == spawn_border => {
@@ -189,6 +263,7 @@ fn edit_scenario(map: &Map, scenario: &mut Scenario, mut wizard: WrappedWizard)
                 percent_use_transit: wizard.input_percent(
                     "What percent of the walking trips will consider taking transit?",
                 )?,
+                percent_trucks: wizard.input_percent("What percent of the cars will be trucks?")?,
             });
         }
         x if x == randomize => {
@@ -203,6 +278,7 @@ fn edit_scenario(map: &Map, scenario: &mut Scenario, mut wizard: WrappedWizard)
                         goal: OriginDestination::Neighborhood(dst.to_string()),
                         percent_biking: 0.1,
                         percent_use_transit: 0.2,
+                        percent_trucks: 0.0,
                     });
                 }
             }
@@ -263,9 +339,23 @@ fn choose_origin_destination(
     query: &str,
 ) -> Option<OriginDestination> {
     let neighborhood = "Neighborhood";
+    let bldg_type = "Buildings of one type in a neighborhood";
     let border = "Border intersection";
-    if wizard.choose_string(query, vec![neighborhood, border])? == neighborhood {
+    let choice = wizard.choose_string(query, vec![neighborhood, bldg_type, border])?;
+    if choice == neighborhood {
         choose_neighborhood(map, wizard, query).map(OriginDestination::Neighborhood)
+    } else if choice == bldg_type {
+        let n = choose_neighborhood(map, wizard, query)?;
+        let (_, t) = wizard.choose_something_no_keys::<BuildingType>(
+            "What type of building?",
+            Box::new(|| {
+                BuildingType::all()
+                    .into_iter()
+                    .map(|t| (t.to_string(), t))
+                    .collect()
+            }),
+        )?;
+        Some(OriginDestination::BuildingsOfType(n, t))
     } else {
         choose_intersection(wizard, query).map(OriginDestination::Border)
     }
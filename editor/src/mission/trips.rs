@@ -1,11 +1,11 @@
 use crate::mission::input_time;
 use crate::ui::UI;
-use abstutil::Timer;
+use abstutil::{prettyprint_usize, Timer};
 use ezgui::{EventCtx, WrappedWizard};
 use geom::{Distance, Duration, LonLat, PolyLine, Polygon, Pt2D};
-use map_model::{BuildingID, IntersectionID, LaneType, Map, PathRequest, Position};
-use sim::{DrivingGoal, Scenario, SidewalkSpot, SpawnTrip, TripSpec};
-use std::collections::HashMap;
+use map_model::{BuildingID, BuildingType, IntersectionID, LaneType, Map, PathRequest, Position};
+use sim::{DrivingGoal, Scenario, SidewalkSpot, SpawnTrip, TripMetadata, TripPurpose, TripSpec};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 #[derive(Debug)]
 pub struct Trip {
@@ -13,6 +13,8 @@ pub struct Trip {
     pub to: TripEndpt,
     pub depart_at: Duration,
     pub purpose: (popdat::psrc::Purpose, popdat::psrc::Purpose),
+    // The PSRC parcels where the trip starts and ends
+    pub parcels: (i64, i64),
     pub mode: popdat::psrc::Mode,
     // These are an upper bound when TripEndpt::Border is involved.
     pub trip_time: Duration,
@@ -34,15 +36,25 @@ impl Trip {
         self.depart_at + self.trip_time
     }
 
+    // The path this trip takes with its mode, or None if there isn't one.
+    pub fn calculate_route(&self, map: &Map) -> Option<PolyLine> {
+        let req = self.path_req(map);
+        map.pathfind(req.clone())
+            .and_then(|path| path.trace(map, req.start.dist_along(), None))
+    }
+
     pub fn path_req(&self, map: &Map) -> PathRequest {
         use popdat::psrc::Mode;
 
         match self.mode {
             Mode::Walk => PathRequest {
                 start: self.from.start_sidewalk_spot(map).sidewalk_pos,
-                end: self.from.end_sidewalk_spot(map).sidewalk_pos,
+                end: self.to.end_sidewalk_spot(map).sidewalk_pos,
                 can_use_bike_lanes: false,
                 can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: false,
+                avoid_lanes: BTreeSet::new(),
             },
             Mode::Bike => PathRequest {
                 start: self.from.start_pos_driving(map),
@@ -52,6 +64,9 @@ impl Trip {
                     .goal_pos(map),
                 can_use_bike_lanes: true,
                 can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: false,
+                avoid_lanes: BTreeSet::new(),
             },
             Mode::Drive => PathRequest {
                 start: self.from.start_pos_driving(map),
@@ -61,6 +76,9 @@ impl Trip {
                     .goal_pos(map),
                 can_use_bike_lanes: false,
                 can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: false,
+                avoid_lanes: BTreeSet::new(),
             },
             Mode::Transit => {
                 let start = self.from.start_sidewalk_spot(map).sidewalk_pos;
@@ -71,6 +89,9 @@ impl Trip {
                         end: SidewalkSpot::bus_stop(stop1, map).sidewalk_pos,
                         can_use_bike_lanes: false,
                         can_use_bus_lanes: false,
+                        can_use_hov_lanes: false,
+                        can_use_midblock_crossings: false,
+                        avoid_lanes: BTreeSet::new(),
                     }
                 } else {
                     // Just fall back to walking. :\
@@ -79,6 +100,9 @@ impl Trip {
                         end,
                         can_use_bike_lanes: false,
                         can_use_bus_lanes: false,
+                        can_use_hov_lanes: false,
+                        can_use_midblock_crossings: false,
+                        avoid_lanes: BTreeSet::new(),
                     }
                 }
             }
@@ -215,6 +239,7 @@ pub fn clip_trips(
             to,
             depart_at: trip.depart_at,
             purpose: trip.purpose,
+            parcels: (trip.from.parcel_id, trip.to.parcel_id),
             mode: trip.mode,
             trip_time: trip.trip_time,
             trip_dist: trip.trip_dist,
@@ -270,18 +295,19 @@ pub fn trips_to_scenario(ctx: &mut EventCtx, ui: &UI, t1: Duration, t2: Duration
     use popdat::psrc::Mode;
     let map = &ui.primary.map;
 
-    let individ_trips = ctx.loading_screen("convert PSRC trips to scenario", |_, mut timer| {
+    let trips = ctx.loading_screen("convert PSRC trips to scenario", |_, mut timer| {
         let (trips, _) = clip_trips(ui, &mut timer);
         timer
             .parallelize("turn PSRC trips into SpawnTrips", trips, |trip| {
                 if trip.depart_at < t1 || trip.depart_at > t2 {
                     return None;
                 }
+                let metadata = trip_metadata(&trip);
 
-                match trip.mode {
+                let spawn = match trip.mode {
                     Mode::Drive => {
                         // TODO Use a parked car, but first have to figure out what cars to seed.
-                        if let Some(start) =
+                        if let Ok(start) =
                             TripSpec::spawn_car_at(trip.from.start_pos_driving(map), map)
                         {
                             Some(SpawnTrip::CarAppearing {
@@ -289,6 +315,8 @@ pub fn trips_to_scenario(ctx: &mut EventCtx, ui: &UI, t1: Duration, t2: Duration
                                 start,
                                 goal: trip.to.driving_goal(vec![LaneType::Driving], map),
                                 is_bike: false,
+                                occupancy: 1,
+                                vehicle_spec: None,
                             })
                         } else {
                             // TODO need to be able to emit warnings from parallelize
@@ -297,14 +325,16 @@ pub fn trips_to_scenario(ctx: &mut EventCtx, ui: &UI, t1: Duration, t2: Duration
                         }
                     }
                     Mode::Bike => match trip.from {
-                        TripEndpt::Building(b) => Some(SpawnTrip::UsingBike(
-                            trip.depart_at,
-                            SidewalkSpot::building(b, map),
-                            trip.to
+                        TripEndpt::Building(b) => Some(SpawnTrip::UsingBike {
+                            depart: trip.depart_at,
+                            start: SidewalkSpot::building(b, map),
+                            goal: trip
+                                .to
                                 .driving_goal(vec![LaneType::Biking, LaneType::Driving], map),
-                        )),
+                            vehicle: None,
+                        }),
                         TripEndpt::Border(_, _) => {
-                            if let Some(start) =
+                            if let Ok(start) =
                                 TripSpec::spawn_car_at(trip.from.start_pos_driving(map), map)
                             {
                                 Some(SpawnTrip::CarAppearing {
@@ -315,6 +345,8 @@ pub fn trips_to_scenario(ctx: &mut EventCtx, ui: &UI, t1: Duration, t2: Duration
                                         map,
                                     ),
                                     is_bike: true,
+                                    occupancy: 1,
+                                    vehicle_spec: None,
                                 })
                             } else {
                                 //timer.warn(format!("No room for bike to appear at {:?}", trip.from));
@@ -346,12 +378,19 @@ pub fn trips_to_scenario(ctx: &mut EventCtx, ui: &UI, t1: Duration, t2: Duration
                             Some(SpawnTrip::JustWalking(trip.depart_at, start, goal))
                         }
                     }
-                }
+                };
+                spawn.map(|s| (s, metadata))
             })
             .into_iter()
             .flatten()
-            .collect()
+            .collect::<Vec<_>>()
     });
+    let mut individ_trips = Vec::new();
+    let mut trip_metadata = BTreeMap::new();
+    for (trip, metadata) in trips {
+        trip_metadata.insert(individ_trips.len(), metadata);
+        individ_trips.push(trip);
+    }
 
     Scenario {
         scenario_name: format!("psrc {} to {}", t1, t2),
@@ -360,7 +399,100 @@ pub fn trips_to_scenario(ctx: &mut EventCtx, ui: &UI, t1: Duration, t2: Duration
         spawn_over_time: Vec::new(),
         border_spawn_over_time: Vec::new(),
         individ_trips,
+        trip_metadata,
+        lane_closures: Vec::new(),
+    }
+}
+
+fn trip_metadata(trip: &Trip) -> TripMetadata {
+    TripMetadata {
+        purpose: (
+            convert_purpose(trip.purpose.0),
+            convert_purpose(trip.purpose.1),
+        ),
+        from_parcel: Some(trip.parcels.0),
+        to_parcel: Some(trip.parcels.1),
+        // PSRC only records departure to the minute
+        departure_window: (trip.depart_at, trip.depart_at + Duration::minutes(1)),
+    }
+}
+
+fn convert_purpose(purpose: popdat::psrc::Purpose) -> TripPurpose {
+    use popdat::psrc::Purpose;
+
+    match purpose {
+        Purpose::Home => TripPurpose::Home,
+        Purpose::Work => TripPurpose::Work,
+        Purpose::School => TripPurpose::School,
+        Purpose::Escort => TripPurpose::Escort,
+        Purpose::PersonalBusiness => TripPurpose::PersonalBusiness,
+        Purpose::Shopping => TripPurpose::Shopping,
+        Purpose::Meal => TripPurpose::Meal,
+        Purpose::Social => TripPurpose::Social,
+        Purpose::Recreation => TripPurpose::Recreation,
+        Purpose::Medical => TripPurpose::Medical,
+        Purpose::ParkAndRideTransfer => TripPurpose::ParkAndRideTransfer,
+    }
+}
+
+// The types of buildings that trips for some purpose should end at, or None if anything goes
+pub fn expected_building_types(purpose: popdat::psrc::Purpose) -> Option<Vec<BuildingType>> {
+    use popdat::psrc::Purpose;
+
+    match purpose {
+        Purpose::Home => Some(vec![BuildingType::Residence]),
+        Purpose::School => Some(vec![BuildingType::School]),
+        Purpose::Shopping | Purpose::Meal => Some(vec![BuildingType::Retail]),
+        // Everybody works somewhere, but probably not at a house
+        Purpose::Work => Some(vec![
+            BuildingType::Retail,
+            BuildingType::Office,
+            BuildingType::School,
+            BuildingType::Industrial,
+        ]),
+        _ => None,
+    }
+}
+
+// Compares why each trip happens with the type of building it ends at, to sanity check how
+// buildings are classified. Trips ending at borders are skipped.
+pub fn purpose_mismatches(trips: &Vec<Trip>, map: &Map) -> Vec<String> {
+    // Per purpose, the number of trips, and the types of the unexpected destinations
+    let mut per_purpose: BTreeMap<String, (usize, BTreeMap<BuildingType, usize>)> = BTreeMap::new();
+    for trip in trips {
+        let b = match trip.to {
+            TripEndpt::Building(b) => b,
+            TripEndpt::Border(_, _) => continue,
+        };
+        if let Some(expected) = expected_building_types(trip.purpose.1) {
+            let actual = map.get_b(b).building_type;
+            let entry = per_purpose
+                .entry(format!("{:?}", trip.purpose.1))
+                .or_insert_with(|| (0, BTreeMap::new()));
+            entry.0 += 1;
+            if !expected.contains(&actual) {
+                *entry.1.entry(actual).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    for (purpose, (total, unexpected)) in per_purpose {
+        lines.push(format!(
+            "{}: {} of {} trips end at an unexpected type of building",
+            purpose,
+            prettyprint_usize(unexpected.values().sum()),
+            prettyprint_usize(total)
+        ));
+        for (bldg_type, count) in unexpected {
+            lines.push(format!(
+                "  {} at {} buildings",
+                prettyprint_usize(count),
+                bldg_type
+            ));
+        }
     }
+    lines
 }
 
 pub fn pick_time_range(mut wizard: WrappedWizard) -> Option<(Duration, Duration)> {
@@ -0,0 +1,26 @@
+use crate::render::DrawCtx;
+use ezgui::{Color, GfxCtx, Text};
+use geom::{Distance, Polygon};
+
+const RACK_SIZE: Distance = Distance::const_meters(1.5);
+
+// Racks aren't selectable, so they don't live in the quadtree; just draw the ones onscreen.
+pub fn draw_bike_racks(g: &mut GfxCtx, ctx: &DrawCtx) {
+    let screen = g.get_screen_bounds();
+    for rack in ctx.map.all_bike_racks() {
+        if !screen.contains(rack.point) {
+            continue;
+        }
+        let occupancy = ctx.sim.get_bike_rack_occupancy(rack.id);
+        let color = if occupancy >= rack.capacity {
+            ctx.cs.get_def("full bike rack", Color::RED)
+        } else {
+            ctx.cs.get_def("bike rack", Color::grey(0.6))
+        };
+        g.draw_polygon(color, &Polygon::rectangle(rack.point, RACK_SIZE, RACK_SIZE));
+
+        let mut txt = Text::new();
+        txt.add_line(format!("{}/{}", occupancy, rack.capacity));
+        g.draw_text_at(&txt, rack.point);
+    }
+}
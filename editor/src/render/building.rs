@@ -23,12 +23,18 @@ impl DrawBuilding {
         }
         let front_path = front_path_line.make_polygons(Distance::meters(1.0));
 
+        // Debug mode can tell apart the different kinds of businesses too.
         batch.push(
             match bldg.building_type {
                 BuildingType::Residence => {
                     cs.get_def("residential building", Color::rgb(218, 165, 32))
                 }
-                BuildingType::Business => cs.get_def("business building", Color::rgb(210, 105, 30)),
+                BuildingType::Retail
+                | BuildingType::Office
+                | BuildingType::School
+                | BuildingType::Industrial => {
+                    cs.get_def("business building", Color::rgb(210, 105, 30))
+                }
                 BuildingType::Unknown => {
                     cs.get_def("unknown building", Color::rgb_f(0.7, 0.7, 0.7))
                 }
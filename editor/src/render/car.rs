@@ -3,7 +3,7 @@ use crate::render::{DrawCtx, DrawOptions, Renderable, OUTLINE_THICKNESS};
 use ezgui::{Color, Drawable, GeomBatch, GfxCtx, Prerender};
 use geom::{Angle, Circle, Distance, PolyLine, Polygon, Pt2D};
 use map_model::{Map, TurnType};
-use sim::{CarID, CarStatus, DrawCarInput};
+use sim::{CarID, CarStatus, DrawCarInput, VehicleClass};
 
 const CAR_WIDTH: Distance = Distance::const_meters(2.0);
 
@@ -25,6 +25,9 @@ impl DrawCar {
             // TODO if it's a bus, color it differently -- but how? :\
             match input.status {
                 CarStatus::Debug => cs.get_def("debug car", Color::BLUE.alpha(0.8)),
+                CarStatus::Moving if input.class == VehicleClass::Truck => {
+                    cs.get_def("moving truck", Color::rgb(255, 140, 0))
+                }
                 CarStatus::Moving => cs.get_def("moving car", Color::CYAN),
                 CarStatus::Stuck => cs.get_def("stuck car", Color::rgb(222, 184, 135)),
                 CarStatus::Parked => cs.get_def("parked car", Color::rgb(180, 233, 76)),
@@ -102,7 +105,7 @@ impl DrawCar {
                 let turn = map.get_t(t);
                 let angle = turn.angle();
                 match turn.turn_type {
-                    TurnType::Left | TurnType::LaneChangeLeft => {
+                    TurnType::Left | TurnType::LaneChangeLeft | TurnType::UTurn => {
                         for circle in vec![front_left, back_left] {
                             draw_default.push(
                                 arrow_color,
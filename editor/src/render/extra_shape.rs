@@ -2,10 +2,11 @@ use crate::helpers::{rotating_color, ID};
 use crate::render::{
     DrawCtx, DrawOptions, Renderable, EXTRA_SHAPE_POINT_RADIUS, EXTRA_SHAPE_THICKNESS,
 };
+use abstutil::Timer;
 use ezgui::GfxCtx;
-use geom::{Circle, FindClosest, GPSBounds, PolyLine, Polygon, Pt2D};
+use geom::{Circle, FindClosest, PolyLine, Polygon, Pt2D};
 use kml::ExtraShape;
-use map_model::{DirectedRoadID, Map, LANE_THICKNESS};
+use map_model::{raw_data, DirectedRoadID, Map, LANE_THICKNESS};
 use std::collections::BTreeMap;
 use std::fmt;
 
@@ -29,12 +30,13 @@ impl DrawExtraShape {
     pub fn new(
         id: ExtraShapeID,
         s: ExtraShape,
-        gps_bounds: &GPSBounds,
+        map: &Map,
         closest: &FindClosest<DirectedRoadID>,
+        timer: &mut Timer,
     ) -> Option<DrawExtraShape> {
         let mut pts: Vec<Pt2D> = Vec::new();
         for pt in s.points.into_iter() {
-            pts.push(Pt2D::from_gps(pt, gps_bounds)?);
+            pts.push(Pt2D::from_gps(pt, map.get_gps_bounds())?);
         }
 
         if pts.len() == 1 {
@@ -57,9 +59,25 @@ impl DrawExtraShape {
             // middle of the blockface.
             // TODO Long blockfaces sometimes cover two roads. Should maybe find ALL matches within
             // the threshold distance?
-            let road = closest
-                .closest_pt(pl.middle(), LANE_THICKNESS * 5.0)
-                .map(|(r, _)| r);
+            let candidates: Vec<(DirectedRoadID, isize)> = closest
+                .all_close_pts(pl.middle(), LANE_THICKNESS * 5.0)
+                .into_iter()
+                .map(|(r, _, _)| (r, map.get_r(r.id).get_zorder()))
+                .collect();
+            let shape_layer = s
+                .attributes
+                .get("layer")
+                .and_then(|l| l.parse::<isize>().ok());
+            let road =
+                raw_data::pick_layered_match(&candidates, shape_layer).map(|(r, ambiguous)| {
+                    if ambiguous {
+                        timer.warn(format!(
+                            "{} matches roads on different levels; picked {}",
+                            id, r.id
+                        ));
+                    }
+                    r
+                });
             Some(DrawExtraShape {
                 id,
                 polygon: pl.make_polygons(EXTRA_SHAPE_THICKNESS),
@@ -2,12 +2,12 @@ use crate::helpers::{ColorScheme, ID};
 use crate::render::{
     DrawCtx, DrawOptions, DrawTurn, Renderable, CROSSWALK_LINE_THICKNESS, OUTLINE_THICKNESS,
 };
-use abstutil::Timer;
+use abstutil::{Timer, Warn};
 use ezgui::{Color, Drawable, GeomBatch, GfxCtx, Prerender, ScreenPt, Text};
 use geom::{Angle, Circle, Distance, Duration, Line, PolyLine, Polygon, Pt2D, EPSILON_DIST};
 use map_model::{
-    Cycle, Intersection, IntersectionID, IntersectionType, Map, Road, RoadWithStopSign, Turn,
-    TurnID, TurnPriority, TurnType, LANE_THICKNESS,
+    Cycle, Intersection, IntersectionID, IntersectionType, LaneID, Map, Road, RoadWithStopSign,
+    SignalControl, Turn, TurnID, TurnPriority, TurnType, LANE_THICKNESS,
 };
 use ordered_float::NotNan;
 use std::cell::RefCell;
@@ -150,9 +150,11 @@ impl Renderable for DrawIntersection {
                         .map(|(_, t)| *t != ctx.sim.time())
                         .unwrap_or(true);
                     if recalc {
-                        let (cycle, t) = signal.current_cycle_and_remaining_time(ctx.sim.time());
                         let mut batch = GeomBatch::new();
-                        draw_signal_cycle(cycle, Some(t), &mut batch, ctx);
+                        // Nothing's allowed during the all-red clearance.
+                        if let (Some(cycle), t) = ctx.sim.current_signal_cycle(signal) {
+                            draw_signal_cycle(cycle, Some(t), &mut batch, ctx);
+                        }
                         *maybe_redraw = Some((g.prerender.upload(batch), ctx.sim.time()));
                     }
                     g.redraw(&maybe_redraw.as_ref().unwrap().0);
@@ -182,6 +184,22 @@ impl Renderable for DrawIntersection {
 pub fn calculate_corners(i: &Intersection, map: &Map, timer: &mut Timer) -> Vec<Polygon> {
     let mut corners = Vec::new();
 
+    let lane_polygons: Vec<(LaneID, Polygon)> = i
+        .incoming_lanes
+        .iter()
+        .chain(i.outgoing_lanes.iter())
+        .map(|l| {
+            (
+                *l,
+                Polygon::new(
+                    &map.get_l(*l)
+                        .lane_center_pts
+                        .to_thick_boundary_pts(LANE_THICKNESS),
+                ),
+            )
+        })
+        .collect();
+
     for turn in &map.get_turns_in_intersection(i.id) {
         if turn.turn_type == TurnType::SharedSidewalkCorner {
             // Avoid double-rendering
@@ -192,36 +210,25 @@ pub fn calculate_corners(i: &Intersection, map: &Map, timer: &mut Timer) -> Vec<
             let l1 = map.get_l(turn.id.src);
             let l2 = map.get_l(turn.id.dst);
 
-            let src_line = l1.last_line().shift_left(LANE_THICKNESS / 2.0);
-            let dst_line = l2.first_line().shift_left(LANE_THICKNESS / 2.0);
-
-            let pt_maybe_in_intersection = src_line.infinite().intersection(&dst_line.infinite());
-            // Now find all of the points on the intersection polygon between the two sidewalks.
-            let corner1 = l1.last_line().shift_right(LANE_THICKNESS / 2.0).pt2();
-            let corner2 = l2.first_line().shift_right(LANE_THICKNESS / 2.0).pt1();
-            // Intersection polygons are constructed in clockwise order, so do corner2 to corner1.
-            // TODO This threshold is higher than the 0.1 intersection polygons use to dedupe
-            // because of jagged lane teeth from bad polyline shifting. Seemingly.
-            if let Some(mut pts_between) =
-                Pt2D::find_pts_between(&i.polygon.points(), corner2, corner1, Distance::meters(0.5))
-            {
-                pts_between.push(src_line.pt2());
-                // If the intersection of the two lines isn't actually inside, then just exclude
-                // this point. Or if src_line and dst_line were parallel (actually, colinear), then
-                // skip it.
-                if let Some(pt) = pt_maybe_in_intersection {
-                    if i.polygon.contains_pt(pt) {
-                        pts_between.push(pt);
-                    }
-                }
-                pts_between.push(dst_line.pt1());
-                corners.push(Polygon::new(&pts_between));
+            // Corners must not cover any lanes meeting here, besides the sidewalks they connect.
+            let other_lanes: Vec<&Polygon> = lane_polygons
+                .iter()
+                .filter(|(l, _)| *l != l1.id && *l != l2.id)
+                .map(|(_, poly)| poly)
+                .collect();
+            if let Some(pieces) = sidewalk_corner(
+                &i.polygon,
+                &l1.lane_center_pts,
+                &l2.lane_center_pts,
+                other_lanes,
+            ) {
+                corners.extend(pieces.with_context(timer, turn.id.to_string()));
             } else {
                 timer.warn(format!(
                     "Couldn't make geometry for {}. look for {} to {} in {:?}",
                     turn.id,
-                    corner2,
-                    corner1,
+                    l2.first_line().shift_right(LANE_THICKNESS / 2.0).pt1(),
+                    l1.last_line().shift_right(LANE_THICKNESS / 2.0).pt2(),
                     i.polygon.points()
                 ));
             }
@@ -231,6 +238,75 @@ pub fn calculate_corners(i: &Intersection, map: &Map, timer: &mut Timer) -> Vec<
     corners
 }
 
+// The corner between a sidewalk ending at an intersection and the next one starting there. The
+// shape starts from the points on the intersection polygon between the two sidewalks, then gets
+// clipped to the intersection polygon and cut away from the other lanes, so that at skewed
+// intersections, it doesn't cover them.
+fn sidewalk_corner(
+    intersection: &Polygon,
+    src_sidewalk: &PolyLine,
+    dst_sidewalk: &PolyLine,
+    other_lanes: Vec<&Polygon>,
+) -> Option<Warn<Vec<Polygon>>> {
+    let src_line = src_sidewalk.last_line().shift_left(LANE_THICKNESS / 2.0);
+    let dst_line = dst_sidewalk.first_line().shift_left(LANE_THICKNESS / 2.0);
+
+    let pt_maybe_in_intersection = src_line.infinite().intersection(&dst_line.infinite());
+    // Now find all of the points on the intersection polygon between the two sidewalks.
+    let corner1 = src_sidewalk
+        .last_line()
+        .shift_right(LANE_THICKNESS / 2.0)
+        .pt2();
+    let corner2 = dst_sidewalk
+        .first_line()
+        .shift_right(LANE_THICKNESS / 2.0)
+        .pt1();
+    // Intersection polygons are constructed in clockwise order, so do corner2 to corner1.
+    // TODO This threshold is higher than the 0.1 intersection polygons use to dedupe
+    // because of jagged lane teeth from bad polyline shifting. Seemingly.
+    let mut pts_between = Pt2D::find_pts_between(
+        &intersection.points(),
+        corner2,
+        corner1,
+        Distance::meters(0.5),
+    )?;
+    pts_between.push(src_line.pt2());
+    // If the intersection of the two lines isn't actually inside, then just exclude
+    // this point. Or if src_line and dst_line were parallel (actually, colinear), then
+    // skip it.
+    if let Some(pt) = pt_maybe_in_intersection {
+        if intersection.contains_pt(pt) {
+            pts_between.push(pt);
+        }
+    }
+    pts_between.push(dst_line.pt1());
+    let corner = Polygon::new(&pts_between);
+
+    let mut pieces = corner.intersection(intersection);
+    for lane in other_lanes {
+        pieces = pieces
+            .into_iter()
+            .flat_map(|piece| piece.difference(lane))
+            .collect();
+    }
+    // Clipping can fall apart on degenerate input, like the corner sharing edges with the
+    // intersection polygon. Clipping only removes area, so if it didn't, fall back to the original
+    // corner.
+    let clipped_area: f64 = pieces.iter().map(|p| p.area()).sum();
+    if pieces.is_empty() || clipped_area > corner.area() + 0.01 {
+        return Some(Warn::warn(
+            vec![corner],
+            format!(
+                "Couldn't clip the sidewalk corner (clipped area {}, original {}), so it might \
+                 cover other lanes",
+                clipped_area,
+                corner.area()
+            ),
+        ));
+    }
+    Some(Warn::ok(pieces))
+}
+
 // Only draws a box when time_left is present
 pub fn draw_signal_cycle(
     cycle: &Cycle,
@@ -331,7 +407,7 @@ fn draw_signal_cycle_with_icons(cycle: &Cycle, batch: &mut GeomBatch, ctx: &Draw
                         straight_green = false;
                     }
                 }
-                TurnType::Left => {
+                TurnType::Left | TurnType::UTurn => {
                     left_priority = Some(cycle.get_priority(turn.id));
                 }
             };
@@ -394,12 +470,26 @@ pub fn draw_signal_diagram(
             b.max_y - b.min_y,
         )
     };
-    let cycles = &ctx.map.get_traffic_signal(i).cycles;
+    let signal = ctx.map.get_traffic_signal(i);
+    let cycles = &signal.cycles;
+    let actuated = signal.control != SignalControl::FixedTime;
 
     // Precalculate maximum text width.
     let mut labels = Vec::new();
     for (idx, cycle) in cycles.iter().enumerate() {
-        if idx == current_cycle && time_left.is_some() {
+        if actuated {
+            // Actuated cycles don't have a set length, so just show the bounds.
+            let mut txt = Text::from_line(format!(
+                "Cycle {}: {} to {} green",
+                idx + 1,
+                cycle.min_green.to_string_hms(),
+                cycle.max_green.to_string_hms()
+            ));
+            if idx == current_cycle && time_left.is_some() {
+                txt.append(" (current)".to_string(), None);
+            }
+            labels.push(txt);
+        } else if idx == current_cycle && time_left.is_some() {
             // TODO Hacky way of indicating overtime
             if time_left.unwrap() < Duration::ZERO {
                 let mut txt = Text::from_line(format!("Cycle {}: ", idx + 1));
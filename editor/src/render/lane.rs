@@ -1,9 +1,11 @@
 use crate::helpers::{ColorScheme, ID};
-use crate::render::{DrawCtx, DrawOptions, Renderable, OUTLINE_THICKNESS};
+use crate::render::{DrawCtx, DrawOptions, Renderable, MIN_ZOOM_FOR_DETAIL, OUTLINE_THICKNESS};
 use abstutil::Timer;
 use ezgui::{Color, Drawable, GeomBatch, GfxCtx, Prerender};
-use geom::{Circle, Distance, Line, PolyLine, Polygon, Pt2D};
-use map_model::{Lane, LaneID, LaneType, Map, Road, TurnType, LANE_THICKNESS, PARKING_SPOT_LENGTH};
+use geom::{Angle, Circle, Distance, Line, PolyLine, Polygon, Pt2D};
+use map_model::{Lane, LaneID, LaneType, Map, Road, TurnType, LANE_THICKNESS};
+use sim::{ParkingSpot, Sim};
+use std::collections::HashMap;
 
 pub struct DrawLane {
     pub id: LaneID,
@@ -11,6 +13,8 @@ pub struct DrawLane {
     zorder: isize,
 
     draw_default: Drawable,
+    // Only the colors change as cars come and go
+    parking_spots: Vec<Polygon>,
 }
 
 impl DrawLane {
@@ -20,6 +24,7 @@ impl DrawLane {
         draw_lane_markings: bool,
         cs: &ColorScheme,
         prerender: &Prerender,
+        cache: &mut LaneMarkingsCache,
         timer: &mut Timer,
     ) -> DrawLane {
         let road = map.get_r(lane.parent);
@@ -30,6 +35,7 @@ impl DrawLane {
             match lane.lane_type {
                 LaneType::Driving => cs.get_def("driving lane", Color::BLACK),
                 LaneType::Bus => cs.get_def("bus lane", Color::rgb(190, 74, 76)),
+                LaneType::HOV => cs.get_def("HOV lane", Color::rgb(120, 80, 160)),
                 LaneType::Parking => cs.get_def("parking lane", Color::grey(0.2)),
                 LaneType::Sidewalk => cs.get_def("sidewalk", Color::grey(0.8)),
                 LaneType::Biking => cs.get_def("bike lane", Color::rgb(15, 125, 75)),
@@ -39,18 +45,16 @@ impl DrawLane {
         if draw_lane_markings {
             match lane.lane_type {
                 LaneType::Sidewalk => {
-                    draw.extend(
+                    cache.draw(
+                        &mut draw,
+                        lane,
                         cs.get_def("sidewalk lines", Color::grey(0.7)),
-                        calculate_sidewalk_lines(lane),
                     );
                 }
                 LaneType::Parking => {
-                    draw.extend(
-                        cs.get_def("parking lines", Color::WHITE),
-                        calculate_parking_lines(lane),
-                    );
+                    cache.draw(&mut draw, lane, cs.get_def("parking lines", Color::WHITE));
                 }
-                LaneType::Driving | LaneType::Bus => {
+                LaneType::Driving | LaneType::Bus | LaneType::HOV => {
                     draw.extend(
                         cs.get_def("dashed lane line", Color::WHITE),
                         calculate_driving_lines(lane, road, timer),
@@ -74,6 +78,11 @@ impl DrawLane {
             polygon,
             zorder: road.get_zorder(),
             draw_default: prerender.upload(draw),
+            parking_spots: if lane.is_parking() {
+                parking_spot_polygons(lane)
+            } else {
+                Vec::new()
+            },
         }
     }
 
@@ -92,6 +101,21 @@ impl DrawLane {
             g.draw_circle(circle_color, &Circle::new(l.pt2(), Distance::meters(0.8)));
         }
     }
+
+    fn draw_parking_occupancy(&self, g: &mut GfxCtx, ctx: &DrawCtx) {
+        let occupied_color = ctx
+            .cs
+            .get_def("occupied parking spot", Color::RED.alpha(0.5));
+        let free_color = ctx.cs.get_def("free parking spot", Color::GREEN.alpha(0.5));
+        g.draw_polygon_batch(
+            parking_spot_occupancy(&self.parking_spots, self.id, ctx.sim)
+                .into_iter()
+                .map(|(polygon, occupied)| {
+                    (if occupied { occupied_color } else { free_color }, polygon)
+                })
+                .collect(),
+        );
+    }
 }
 
 impl Renderable for DrawLane {
@@ -104,6 +128,9 @@ impl Renderable for DrawLane {
             g.draw_polygon(color, &self.polygon);
         } else {
             g.redraw(&self.draw_default);
+            if g.canvas.cam_zoom >= MIN_ZOOM_FOR_DETAIL && ctx.map.get_l(self.id).is_parking() {
+                self.draw_parking_occupancy(g, ctx);
+            }
         }
 
         if opts.geom_debug_mode {
@@ -127,6 +154,34 @@ impl Renderable for DrawLane {
     }
 }
 
+// The outline of each parking spot along a parking lane, in the same order as Lane::parking_spots
+pub fn parking_spot_polygons(lane: &Lane) -> Vec<Polygon> {
+    lane.parking_spots()
+        .into_iter()
+        .map(|(start, end)| {
+            lane.lane_center_pts
+                .exact_slice(start, end)
+                .make_polygons(LANE_THICKNESS * 0.5)
+        })
+        .collect()
+}
+
+// Each parking spot along the lane, and whether the sim has a car there. This changes constantly,
+// so it's drawn fresh every frame instead of baked into the lane's Drawable.
+pub fn parking_spot_occupancy<'a>(
+    spots: &'a [Polygon],
+    lane: LaneID,
+    sim: &Sim,
+) -> Vec<(&'a Polygon, bool)> {
+    sim.get_parking_spot_occupancy(lane)
+        .into_iter()
+        .filter_map(|(spot, occupied)| match spot {
+            ParkingSpot::Onstreet(_, idx) => Some((&spots[idx], occupied)),
+            ParkingSpot::Offstreet(_, _) => None,
+        })
+        .collect()
+}
+
 // TODO this always does it at pt1
 fn perp_line(l: Line, length: Distance) -> Line {
     let pt1 = l.shift_right(length / 2.0).pt1();
@@ -134,55 +189,110 @@ fn perp_line(l: Line, length: Distance) -> Line {
     Line::new(pt1, pt2)
 }
 
-fn calculate_sidewalk_lines(lane: &Lane) -> Vec<Polygon> {
-    let tile_every = LANE_THICKNESS;
-
-    let length = lane.length();
+// Sidewalk hatches and parking dividers are the same few polygons repeated all along a lane, just
+// moved and rotated. Calculate each shape once, relative to the origin and pointing at 0 degrees,
+// then transform copies into place. Only the shapes are cached; colors can change, like when the
+// color scheme is edited.
+pub struct LaneMarkingsCache {
+    templates: HashMap<LaneType, Vec<Polygon>>,
+    // How many shapes were transformed into place, instead of calculated from scratch
+    pub reused: usize,
+}
 
-    let mut result = Vec::new();
-    // Start away from the intersections
-    let mut dist_along = tile_every;
-    while dist_along < length - tile_every {
-        let (pt, angle) = lane.dist_along(dist_along);
-        // Reuse perp_line. Project away an arbitrary amount
-        let pt2 = pt.project_away(Distance::meters(1.0), angle);
-        result.push(
-            perp_line(Line::new(pt, pt2), LANE_THICKNESS).make_polygons(Distance::meters(0.25)),
-        );
-        dist_along += tile_every;
+impl LaneMarkingsCache {
+    pub fn new() -> LaneMarkingsCache {
+        LaneMarkingsCache {
+            templates: HashMap::new(),
+            reused: 0,
+        }
     }
 
-    result
+    // Only sidewalks and parking lanes have repetitive markings.
+    pub fn draw(&mut self, batch: &mut GeomBatch, lane: &Lane, color: Color) {
+        let positions = match lane.lane_type {
+            LaneType::Sidewalk => sidewalk_line_positions(lane),
+            LaneType::Parking => parking_line_positions(lane),
+            _ => unreachable!(),
+        };
+        let template = self
+            .templates
+            .entry(lane.lane_type)
+            .or_insert_with(|| marking_shape(lane.lane_type));
+        for (pt, angle) in &positions {
+            for p in template.iter() {
+                batch.push(color, p.rotate_and_translate(*angle, *pt));
+            }
+        }
+        self.reused += positions.len();
+    }
 }
 
-fn calculate_parking_lines(lane: &Lane) -> Vec<Polygon> {
-    // meters, but the dims get annoying below to remove
-    let leg_length = Distance::meters(1.0);
+// The polygons for one sidewalk hatch or parking divider, at the origin on a lane pointing at 0
+// degrees
+fn marking_shape(lane_type: LaneType) -> Vec<Polygon> {
+    let pt = Pt2D::new(0.0, 0.0);
+    let lane_angle = Angle::new_degs(0.0);
+    match lane_type {
+        LaneType::Sidewalk => {
+            // Reuse perp_line. Project away an arbitrary amount
+            let pt2 = pt.project_away(Distance::meters(1.0), lane_angle);
+            vec![perp_line(Line::new(pt, pt2), LANE_THICKNESS).make_polygons(Distance::meters(0.25))]
+        }
+        LaneType::Parking => {
+            // meters, but the dims get annoying below to remove
+            let leg_length = Distance::meters(1.0);
 
-    let mut result = Vec::new();
-    let num_spots = lane.number_parking_spots();
-    if num_spots > 0 {
-        for idx in 0..=num_spots {
-            let (pt, lane_angle) = lane.dist_along(PARKING_SPOT_LENGTH * (1.0 + idx as f64));
             let perp_angle = lane_angle.rotate_degs(270.0);
             // Find the outside of the lane. Actually, shift inside a little bit, since the line will
             // have thickness, but shouldn't really intersect the adjacent line when drawn.
             let t_pt = pt.project_away(LANE_THICKNESS * 0.4, perp_angle);
             // The perp leg
             let p1 = t_pt.project_away(leg_length, perp_angle.opposite());
-            result.push(Line::new(t_pt, p1).make_polygons(Distance::meters(0.25)));
             // Upper leg
             let p2 = t_pt.project_away(leg_length, lane_angle);
-            result.push(Line::new(t_pt, p2).make_polygons(Distance::meters(0.25)));
             // Lower leg
             let p3 = t_pt.project_away(leg_length, lane_angle.opposite());
-            result.push(Line::new(t_pt, p3).make_polygons(Distance::meters(0.25)));
+            vec![
+                Line::new(t_pt, p1).make_polygons(Distance::meters(0.25)),
+                Line::new(t_pt, p2).make_polygons(Distance::meters(0.25)),
+                Line::new(t_pt, p3).make_polygons(Distance::meters(0.25)),
+            ]
         }
+        _ => unreachable!(),
+    }
+}
+
+fn sidewalk_line_positions(lane: &Lane) -> Vec<(Pt2D, Angle)> {
+    let tile_every = LANE_THICKNESS;
+
+    let length = lane.length();
+
+    let mut result = Vec::new();
+    // Start away from the intersections
+    let mut dist_along = tile_every;
+    while dist_along < length - tile_every {
+        result.push(lane.dist_along(dist_along));
+        dist_along += tile_every;
     }
 
     result
 }
 
+fn parking_line_positions(lane: &Lane) -> Vec<(Pt2D, Angle)> {
+    let spots = lane.parking_spots();
+    if let Some((_, last_end)) = spots.last() {
+        // A divider at the start of every spot, and one closing off the last spot.
+        spots
+            .iter()
+            .map(|(start, _)| *start)
+            .chain(std::iter::once(*last_end))
+            .map(|dist| lane.dist_along(dist))
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
 fn calculate_driving_lines(lane: &Lane, parent: &Road, timer: &mut Timer) -> Vec<Polygon> {
     // The leftmost lanes don't have dashed white lines.
     if parent.dir_and_offset(lane.id).1 == 0 {
@@ -4,18 +4,18 @@ use crate::render::building::DrawBuilding;
 use crate::render::bus_stop::DrawBusStop;
 use crate::render::extra_shape::{DrawExtraShape, ExtraShapeID};
 use crate::render::intersection::DrawIntersection;
-use crate::render::lane::DrawLane;
+use crate::render::lane::{DrawLane, LaneMarkingsCache};
+use crate::render::quadtree::ObjectQuadtree;
 use crate::render::road::DrawRoad;
 use crate::render::turn::DrawTurn;
 use crate::render::Renderable;
 use crate::ui::Flags;
-use aabb_quadtree::QuadTree;
 use abstutil::Timer;
 use ezgui::{Color, Drawable, GeomBatch, Prerender};
-use geom::{Bounds, Duration, FindClosest};
+use geom::{Bounds, Circle, Distance, Duration, FindClosest, Pt2D};
 use map_model::{
     AreaID, BuildingID, BusStopID, DirectedRoadID, IntersectionID, IntersectionType, Lane, LaneID,
-    Map, RoadID, Traversable, Turn, TurnID, TurnType, LANE_THICKNESS,
+    LaneType, Map, Position, RoadID, Traversable, Turn, TurnID, TurnType, LANE_THICKNESS,
 };
 use std::borrow::Borrow;
 use std::cell::RefCell;
@@ -40,7 +40,7 @@ pub struct DrawMap {
     pub draw_all_buildings: Drawable,
     pub draw_all_areas: Drawable,
 
-    quadtree: QuadTree<ID>,
+    quadtree: ObjectQuadtree,
 }
 
 impl DrawMap {
@@ -71,6 +71,7 @@ impl DrawMap {
 
         timer.start_iter("make DrawLanes", map.all_lanes().len());
         let mut lanes: Vec<DrawLane> = Vec::new();
+        let mut markings_cache = LaneMarkingsCache::new();
         for l in map.all_lanes() {
             timer.next();
             lanes.push(DrawLane::new(
@@ -79,9 +80,14 @@ impl DrawMap {
                 !flags.dont_draw_lane_markings,
                 cs,
                 prerender,
+                &mut markings_cache,
                 timer,
             ));
         }
+        timer.note(format!(
+            "Reused {} lane marking shapes",
+            markings_cache.reused
+        ));
 
         let mut turn_to_lane_offset: HashMap<TurnID, usize> = HashMap::new();
         for l in map.all_lanes() {
@@ -151,10 +157,9 @@ impl DrawMap {
                 );
             }
 
-            let gps_bounds = map.get_gps_bounds();
             for s in raw_shapes.into_iter() {
                 if let Some(es) =
-                    DrawExtraShape::new(ExtraShapeID(extra_shapes.len()), s, gps_bounds, &closest)
+                    DrawExtraShape::new(ExtraShapeID(extra_shapes.len()), s, map, &closest, timer)
                 {
                     extra_shapes.push(es);
                 }
@@ -181,26 +186,26 @@ impl DrawMap {
         )]);
 
         timer.start("create quadtree");
-        let mut quadtree = QuadTree::default(map.get_bounds().as_bbox());
+        let mut quadtree = ObjectQuadtree::new(map.get_bounds());
         // TODO use iter chain if everything was boxed as a renderable...
         for obj in &roads {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         for obj in &lanes {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         for obj in &intersections {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         for obj in &buildings {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         for obj in &extra_shapes {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         // Don't put BusStops in the quadtree
         for obj in &areas {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         timer.stop("create quadtree");
 
@@ -300,11 +305,37 @@ impl DrawMap {
 
     // Unsorted, unexpanded, raw result.
     pub fn get_matching_objects(&self, bounds: Bounds) -> Vec<ID> {
-        let mut results: Vec<ID> = Vec::new();
-        for &(id, _, _) in &self.quadtree.query(bounds.as_bbox()) {
-            results.push(*id);
+        self.quadtree.query(&bounds)
+    }
+
+    // Call after regenerating an object whose outline might've changed, so mouseover finds it.
+    pub fn update_object(&mut self, id: ID, new_outline_bounds: Bounds) {
+        self.quadtree.insert(id, new_outline_bounds);
+    }
+
+    // Snaps to the nearest lane of one of the given types, if there's one close to the point.
+    pub fn closest_lane_of_type(
+        &self,
+        pt: Pt2D,
+        types: &[LaneType],
+        map: &Map,
+    ) -> Option<Position> {
+        let max_dist = Distance::meters(10.0);
+        let mut closest: FindClosest<LaneID> = FindClosest::new(&map.get_bounds());
+        for id in self.get_matching_objects(Circle::new(pt, max_dist).get_bounds()) {
+            if let ID::Lane(l) = id {
+                let lane = map.get_l(l);
+                if types.contains(&lane.lane_type) {
+                    closest.add(l, lane.lane_center_pts.points());
+                }
+            }
         }
-        results
+        let (l, snapped_pt) = closest.closest_pt(pt, max_dist)?;
+        let (dist, _) = map
+            .get_l(l)
+            .lane_center_pts
+            .dist_along_of_point(snapped_pt)?;
+        Some(Position::new(l, dist))
     }
 }
 
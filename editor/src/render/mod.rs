@@ -1,5 +1,6 @@
 mod area;
 mod bike;
+mod bike_rack;
 mod building;
 mod bus_stop;
 mod car;
@@ -7,21 +8,28 @@ mod extra_shape;
 mod intersection;
 mod lane;
 mod map;
+mod parking_lot;
 mod pedestrian;
+mod quadtree;
 mod road;
 mod turn;
 
 use crate::helpers::{ColorScheme, ID};
 pub use crate::render::area::DrawArea;
 use crate::render::bike::DrawBike;
+pub use crate::render::bike_rack::draw_bike_racks;
 use crate::render::car::DrawCar;
 pub use crate::render::extra_shape::ExtraShapeID;
 pub use crate::render::intersection::{
     calculate_corners, draw_signal_cycle, draw_signal_diagram, DrawIntersection,
 };
-pub use crate::render::lane::DrawLane;
+pub use crate::render::lane::{
+    parking_spot_occupancy, parking_spot_polygons, DrawLane, LaneMarkingsCache,
+};
 pub use crate::render::map::{AgentCache, DrawMap};
+pub use crate::render::parking_lot::draw_parking_lots;
 pub use crate::render::pedestrian::DrawPedestrian;
+pub use crate::render::quadtree::ObjectQuadtree;
 pub use crate::render::road::DrawRoad;
 pub use crate::render::turn::DrawTurn;
 use ezgui::{Color, GfxCtx, Prerender};
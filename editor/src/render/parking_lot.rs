@@ -0,0 +1,24 @@
+use crate::render::DrawCtx;
+use ezgui::{Color, GfxCtx, Text};
+
+// Like bike racks, lots aren't selectable. The cars inside aren't drawn individually, so just show
+// how full each lot is.
+pub fn draw_parking_lots(g: &mut GfxCtx, ctx: &DrawCtx) {
+    let screen = g.get_screen_bounds();
+    for lot in ctx.map.all_parking_lots() {
+        if !lot.polygon.points().iter().any(|pt| screen.contains(*pt)) {
+            continue;
+        }
+        let occupancy = ctx.sim.get_parking_lot_occupancy(lot.id);
+        let color = if occupancy >= lot.capacity {
+            ctx.cs.get_def("full parking lot", Color::RED.alpha(0.5))
+        } else {
+            ctx.cs.get_def("parking lot", Color::grey(0.4).alpha(0.5))
+        };
+        g.draw_polygon(color, &lot.polygon);
+
+        let mut txt = Text::new();
+        txt.add_line(format!("{}/{}", occupancy, lot.capacity));
+        g.draw_text_at(&txt, lot.polygon.center());
+    }
+}
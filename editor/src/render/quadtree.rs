@@ -0,0 +1,79 @@
+use crate::helpers::ID;
+use aabb_quadtree::geom::Rect;
+use aabb_quadtree::{ItemId, QuadTree};
+use geom::Bounds;
+use std::collections::HashMap;
+
+// In debug builds, maps with at most this many objects double-check every query against a
+// brute-force scan.
+const MAX_OBJECTS_TO_CROSS_CHECK: usize = 5_000;
+
+// For mouseover hit-testing. Remembers where each object went, so map edits that change an
+// object's shape can move it.
+pub struct ObjectQuadtree {
+    tree: QuadTree<ID>,
+    items: HashMap<ID, (ItemId, Rect)>,
+}
+
+impl ObjectQuadtree {
+    pub fn new(map_bounds: &Bounds) -> ObjectQuadtree {
+        ObjectQuadtree {
+            tree: QuadTree::default(map_bounds.as_bbox()),
+            items: HashMap::new(),
+        }
+    }
+
+    // Replaces the object's old box, if it had one.
+    pub fn insert(&mut self, id: ID, outline_bounds: Bounds) {
+        if let Some((item, _)) = self.items.remove(&id) {
+            self.tree.remove(item);
+        }
+        let rect = outline_bounds.as_bbox();
+        // Objects that don't fit in the map's bounds can't be found anyway.
+        if let Some(item) = self.tree.insert_with_box(id, rect) {
+            self.items.insert(id, (item, rect));
+        }
+    }
+
+    // Unsorted, unexpanded, raw result.
+    pub fn query(&self, bounds: &Bounds) -> Vec<ID> {
+        let query = bounds.as_bbox();
+        let results: Vec<ID> = self
+            .tree
+            .query(query)
+            .into_iter()
+            .map(|(id, _, _)| *id)
+            .collect();
+        if cfg!(debug_assertions) && self.items.len() <= MAX_OBJECTS_TO_CROSS_CHECK {
+            self.cross_check(query, &results);
+        }
+        results
+    }
+
+    // Boxes that just touch the query's edge might go either way.
+    fn cross_check(&self, query: Rect, results: &[ID]) {
+        for (id, (_, rect)) in &self.items {
+            if overlaps(rect, &query, false) && !results.contains(id) {
+                panic!("Quadtree query {:?} missed {:?} at {:?}", query, id, rect);
+            }
+        }
+        for id in results {
+            match self.items.get(id) {
+                Some((_, rect)) => {
+                    if !overlaps(rect, &query, true) {
+                        panic!("Quadtree query {:?} found {:?} at {:?}", query, id, rect);
+                    }
+                }
+                None => panic!("Quadtree query {:?} found removed {:?}", query, id),
+            }
+        }
+    }
+}
+
+fn overlaps(r1: &Rect, r2: &Rect, include_edges: bool) -> bool {
+    let before = |a: f32, b: f32| if include_edges { a <= b } else { a < b };
+    before(r1.top_left.x, r2.bottom_right.x)
+        && before(r2.top_left.x, r1.bottom_right.x)
+        && before(r1.top_left.y, r2.bottom_right.y)
+        && before(r2.top_left.y, r1.bottom_right.y)
+}
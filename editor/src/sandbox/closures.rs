@@ -0,0 +1,70 @@
+use crate::helpers::ID;
+use crate::ui::UI;
+use ezgui::{Color, EventCtx, GfxCtx, Key};
+use map_model::{LaneID, LANE_THICKNESS};
+
+// Close lanes or whole roads in the running sim, for a quick what-if without editing the map and
+// resetting. Sidewalks are never closed, since pedestrians ignore closures anyway.
+pub fn event(ctx: &mut EventCtx, ui: &mut UI) {
+    let lane = match ui.primary.current_selection {
+        Some(ID::Lane(l)) => l,
+        _ => {
+            return;
+        }
+    };
+    let map = &ui.primary.map;
+    if map.get_l(lane).is_sidewalk() {
+        return;
+    }
+
+    if ui.primary.sim.get_closed_lanes().contains(&lane) {
+        if ctx.input.contextual_action(Key::C, "reopen this lane") {
+            ui.primary.sim.reopen_lanes(vec![lane]);
+        }
+    } else if ctx
+        .input
+        .contextual_action(Key::C, "temporarily close this lane")
+    {
+        let rerouted = ui.primary.sim.close_lanes(vec![lane], map);
+        println!("Closed {}, rerouting {} cars", lane, rerouted.len());
+    }
+
+    let road = map.get_parent(lane);
+    let lanes: Vec<LaneID> = road
+        .all_lanes()
+        .into_iter()
+        .filter(|l| !map.get_l(*l).is_sidewalk())
+        .collect();
+    let closed = ui.primary.sim.get_closed_lanes();
+    if lanes.iter().all(|l| closed.contains(l)) {
+        if ctx.input.contextual_action(Key::G, "reopen this road") {
+            ui.primary.sim.reopen_lanes(lanes);
+        }
+    } else if ctx
+        .input
+        .contextual_action(Key::G, "temporarily close this road")
+    {
+        let rerouted = ui.primary.sim.close_lanes(lanes, map);
+        println!("Closed {}, rerouting {} cars", road.id, rerouted.len());
+    }
+}
+
+pub fn draw(g: &mut GfxCtx, ui: &UI) {
+    let closed = ui.primary.sim.get_closed_lanes();
+    if closed.is_empty() {
+        return;
+    }
+    let color = ui.cs.get_def("temporarily closed lane", Color::RED);
+    g.enable_hatching();
+    for l in closed {
+        g.draw_polygon(
+            color,
+            &ui.primary
+                .map
+                .get_l(*l)
+                .lane_center_pts
+                .make_polygons(LANE_THICKNESS),
+        );
+    }
+    g.disable_hatching();
+}
@@ -0,0 +1,200 @@
+use crate::helpers::ID;
+use crate::ui::UI;
+use ezgui::{EventCtx, EventLoopMode, GfxCtx, InputResult, LogScroller, ScrollingMenu, Warper};
+use geom::Duration;
+use map_model::Traversable;
+use sim::{AgentID, Event, ParkingSpot, Sim};
+use std::collections::{BTreeSet, VecDeque};
+
+// Only remember this many of the most recent events.
+const MAX_EVENTS: usize = 1000;
+// LogScroller can't scroll yet, so don't flood the screen.
+const MAX_LINES: usize = 50;
+
+// Recent events from the sim. This sticks around while the panel is closed, and so do the filters.
+pub struct EventsFeed {
+    // Oldest first
+    recent: VecDeque<(Duration, Event)>,
+    kind: Option<&'static str>,
+    agent: Option<AgentID>,
+}
+
+impl EventsFeed {
+    pub fn new() -> EventsFeed {
+        EventsFeed {
+            recent: VecDeque::new(),
+            kind: None,
+            agent: None,
+        }
+    }
+
+    // Call after every step of the sim. Stepping a long time at once only keeps the events from
+    // the last chunk.
+    pub fn record(&mut self, sim: &Sim) {
+        for ev in sim.get_events_since_last_step() {
+            if self.recent.len() == MAX_EVENTS {
+                self.recent.pop_front();
+            }
+            self.recent.push_back((sim.time(), ev.clone()));
+        }
+    }
+
+    // The filters stay the same.
+    pub fn clear(&mut self) {
+        self.recent.clear();
+    }
+
+    // None shows everybody again.
+    pub fn filter_by_agent(&mut self, agent: Option<AgentID>) {
+        self.agent = agent;
+    }
+
+    fn matches(&self, ev: &Event) -> bool {
+        if let Some(kind) = self.kind {
+            if ev.kind() != kind {
+                return false;
+            }
+        }
+        if let Some(agent) = self.agent {
+            if !ev.agents().contains(&agent) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub enum EventsPanel {
+    ChoosingKind(ScrollingMenu<Option<&'static str>>),
+    // Also remembers where the listed events happened, to offer warping there next.
+    Listing(LogScroller, Vec<(String, ID)>),
+    Choosing(ScrollingMenu<ID>),
+    Warping(Warper, ID),
+}
+
+impl EventsPanel {
+    pub fn new(feed: &EventsFeed) -> EventsPanel {
+        let mut lines = Vec::new();
+        let mut matches = 0;
+        let mut locations = Vec::new();
+        // Newest first
+        for (time, ev) in feed.recent.iter().rev() {
+            if !feed.matches(ev) {
+                continue;
+            }
+            matches += 1;
+            if lines.len() == MAX_LINES {
+                continue;
+            }
+            let line = format!("[{}] {}", time, ev);
+            if let Some(id) = event_location(ev) {
+                locations.push((line.clone(), id));
+            }
+            lines.push(line);
+        }
+        if matches > MAX_LINES {
+            lines.push(format!("... and {} older", matches - MAX_LINES));
+        }
+
+        let mut title = format!("{} recent events", matches);
+        if let Some(kind) = feed.kind {
+            title = format!("{} of type {}", title, kind);
+        }
+        if let Some(agent) = feed.agent {
+            title = format!("{} involving {}", title, agent);
+        }
+        EventsPanel::Listing(LogScroller::new(title, lines), locations)
+    }
+
+    // Only offers the kinds that have happened recently
+    pub fn choose_kind(feed: &EventsFeed) -> EventsPanel {
+        let kinds: BTreeSet<&'static str> = feed.recent.iter().map(|(_, ev)| ev.kind()).collect();
+        let mut choices = vec![("all events".to_string(), None)];
+        for kind in kinds {
+            choices.push((kind.to_string(), Some(kind)));
+        }
+        EventsPanel::ChoosingKind(ScrollingMenu::new("Show which events?", choices))
+    }
+
+    // When None, this is done.
+    pub fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        ui: &mut UI,
+        feed: &mut EventsFeed,
+    ) -> Option<EventLoopMode> {
+        match self {
+            EventsPanel::ChoosingKind(ref mut menu) => match menu.event(ctx.input) {
+                InputResult::Canceled => None,
+                InputResult::Done(_, kind) => {
+                    feed.kind = kind;
+                    *self = EventsPanel::new(feed);
+                    Some(EventLoopMode::InputOnly)
+                }
+                InputResult::StillActive => Some(EventLoopMode::InputOnly),
+            },
+            EventsPanel::Listing(ref mut scroller, ref mut locations) => {
+                if scroller.event(ctx.input) {
+                    if locations.is_empty() {
+                        return None;
+                    }
+                    *self = EventsPanel::Choosing(ScrollingMenu::new(
+                        "Warp to where something happened? (escape to close)",
+                        std::mem::replace(locations, Vec::new()),
+                    ));
+                }
+                Some(EventLoopMode::InputOnly)
+            }
+            EventsPanel::Choosing(ref mut menu) => match menu.event(ctx.input) {
+                InputResult::Canceled => None,
+                InputResult::Done(_, id) => {
+                    let pt = id.canonical_point(&ui.primary)?;
+                    *self = EventsPanel::Warping(Warper::new(ctx, pt), id);
+                    Some(EventLoopMode::Animation)
+                }
+                InputResult::StillActive => Some(EventLoopMode::InputOnly),
+            },
+            EventsPanel::Warping(ref warper, id) => {
+                let result = warper.event(ctx);
+                if result.is_none() {
+                    ui.primary.current_selection = Some(*id);
+                }
+                result
+            }
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        match self {
+            EventsPanel::ChoosingKind(menu) => menu.draw(g),
+            EventsPanel::Listing(scroller, _) => scroller.draw(g),
+            EventsPanel::Choosing(menu) => menu.draw(g),
+            EventsPanel::Warping(_, _) => {}
+        }
+    }
+}
+
+fn event_location(ev: &Event) -> Option<ID> {
+    match ev {
+        Event::CarReachedParkingSpot(_, spot)
+        | Event::CarParkedIllegally(_, spot)
+        | Event::PedReachedParkingSpot(_, spot) => match spot {
+            ParkingSpot::Onstreet(l, _) => Some(ID::Lane(*l)),
+            ParkingSpot::Offstreet(_, _) => None,
+        },
+        Event::CarOrBikeReachedBorder(_, i)
+        | Event::PedReachedBorder(_, i)
+        | Event::BorderSpawnDelayed(_, i, _) => Some(ID::Intersection(*i)),
+        Event::BusArrivedAtStop(_, _, stop, _, _)
+        | Event::BusDepartedFromStop(_, stop, _, _)
+        | Event::PedReachedBusStop(_, stop) => Some(ID::BusStop(*stop)),
+        Event::PedReachedBuilding(_, b) => Some(ID::Building(*b)),
+        Event::BikeStoppedAtSidewalk(_, l) => Some(ID::Lane(*l)),
+        Event::AgentEntersTraversable(_, Traversable::Lane(l)) => Some(ID::Lane(*l)),
+        Event::AgentEntersTraversable(_, Traversable::Turn(t)) => Some(ID::Turn(*t)),
+        Event::AgentEntersTraversable(_, Traversable::MidblockCrossing(_))
+        | Event::PedEntersBus(_, _)
+        | Event::PedLeavesBus(_, _)
+        | Event::GridlockDetected(_) => None,
+    }
+}
@@ -1,11 +1,20 @@
+mod closures;
+mod events;
+mod playback;
 mod route_explorer;
 mod route_viewer;
 mod score;
 mod show_activity;
 mod spawner;
+mod throughput;
 mod time_travel;
+mod travel_times;
+mod travel_volume;
+mod trip_explorer;
 
-use crate::common::{CommonState, SpeedControls};
+pub use self::throughput::Throughput;
+
+use crate::common::{CommonState, Overlays, SpeedControls};
 use crate::debug::DebugMode;
 use crate::edit::EditMode;
 use crate::game::{GameState, Mode};
@@ -14,14 +23,17 @@ use crate::render::DrawOptions;
 use crate::ui::ShowEverything;
 use ezgui::{hotkey, lctrl, EventCtx, EventLoopMode, GfxCtx, Key, ModalMenu, Text, Wizard};
 use geom::Duration;
-use sim::{Sim, TripID};
+use sim::{Conditions, Sim, TripID};
 
 pub struct SandboxMode {
     speed: SpeedControls,
     following: Option<TripID>,
     route_viewer: route_viewer::RouteViewer,
     show_activity: show_activity::ShowActivity,
+    throughput: throughput::Throughput,
+    overlays: Overlays,
     time_travel: time_travel::TimeTravel,
+    events: events::EventsFeed,
     state: State,
     // TODO Not while Spawning or TimeTraveling or ExploringRoute...
     common: CommonState,
@@ -35,6 +47,9 @@ enum State {
     ExploringRoute(route_explorer::RouteExplorer),
     JumpingToTime(Wizard),
     Scoreboard(score::Scoreboard),
+    ChoosingConditions(Wizard),
+    Replaying(playback::Playback),
+    ShowingEvents(events::EventsPanel),
 }
 
 impl SandboxMode {
@@ -45,7 +60,13 @@ impl SandboxMode {
             following: None,
             route_viewer: route_viewer::RouteViewer::Inactive,
             show_activity: show_activity::ShowActivity::Inactive,
+            throughput: throughput::Throughput::new(),
+            overlays: Overlays::new(vec![
+                ("traffic volume", Box::new(travel_volume::TravelVolume)),
+                ("lane travel times", Box::new(travel_times::TravelTimes)),
+            ]),
             time_travel: time_travel::TimeTravel::new(),
+            events: events::EventsFeed::new(),
             common: CommonState::new(),
             menu: ModalMenu::new(
                 "Sandbox Mode",
@@ -63,14 +84,20 @@ impl SandboxMode {
                         (hotkey(Key::B), "jump to specific time"),
                         (hotkey(Key::X), "reset sim"),
                         (hotkey(Key::S), "seed the sim with agents"),
+                        (hotkey(Key::W), "change weather"),
+                        (hotkey(Key::P), "play back a replay"),
                         // TODO Strange to always have this. Really it's a case of stacked modal?
                         (hotkey(Key::F), "stop following agent"),
                         (hotkey(Key::R), "stop showing agent's route"),
                         // TODO This should probably be a debug thing instead
                         (hotkey(Key::L), "show/hide route for all agents"),
                         (hotkey(Key::A), "show/hide active traffic"),
+                        (hotkey(Key::V), "choose overlay"),
                         (hotkey(Key::T), "start time traveling"),
                         (hotkey(Key::Q), "scoreboard"),
+                        (hotkey(Key::H), "show recent events"),
+                        (None, "filter events by type"),
+                        (None, "filter events by selected agent"),
                         (lctrl(Key::D), "debug mode"),
                         (lctrl(Key::E), "edit mode"),
                     ],
@@ -149,19 +176,74 @@ impl SandboxMode {
                     }
                     EventLoopMode::InputOnly
                 }
-                State::Scoreboard(ref mut s) => {
-                    if s.event(ctx, &state.ui) {
+                State::ChoosingConditions(ref mut wizard) => {
+                    ctx.canvas.handle_event(ctx.input);
+                    if let Some((_, conditions)) =
+                        wizard.wrap(ctx).choose_something_no_keys::<Conditions>(
+                            "What's the weather like?",
+                            Box::new(|| {
+                                vec![
+                                    ("Dry".to_string(), Conditions::Dry),
+                                    ("Rain".to_string(), Conditions::Rain),
+                                    ("Snow".to_string(), Conditions::Snow),
+                                ]
+                            }),
+                        )
+                    {
+                        state.ui.primary.sim.set_conditions(conditions);
+                        mode.state = State::Playing;
+                        mode.speed.pause();
+                    } else if wizard.aborted() {
                         mode.state = State::Playing;
                         mode.speed.pause();
                     }
                     EventLoopMode::InputOnly
                 }
+                State::Scoreboard(ref mut s) => {
+                    if let Some(evmode) = s.event(ctx, &state.ui) {
+                        evmode
+                    } else {
+                        mode.state = State::Playing;
+                        mode.speed.pause();
+                        EventLoopMode::InputOnly
+                    }
+                }
+                State::Replaying(ref mut playback) => {
+                    if let Some(evmode) = playback.event(ctx, &state.ui) {
+                        evmode
+                    } else {
+                        mode.state = State::Playing;
+                        mode.speed.pause();
+                        EventLoopMode::InputOnly
+                    }
+                }
+                State::ShowingEvents(ref mut panel) => {
+                    if let Some(evmode) = panel.event(ctx, &mut state.ui, &mut mode.events) {
+                        evmode
+                    } else {
+                        mode.state = State::Playing;
+                        mode.speed.pause();
+                        EventLoopMode::InputOnly
+                    }
+                }
                 State::Playing => {
                     mode.time_travel.record(&state.ui);
 
                     let mut txt = Text::prompt("Sandbox Mode");
                     txt.add_line(state.ui.primary.sim.summary());
                     txt.add_line(mode.speed.modal_status_line());
+                    if state.ui.primary.sim.get_conditions() != Conditions::Dry {
+                        txt.add_line(format!(
+                            "Weather: {:?}",
+                            state.ui.primary.sim.get_conditions()
+                        ));
+                    }
+                    if !state.ui.primary.sim.get_closed_lanes().is_empty() {
+                        txt.add_line(format!(
+                            "{} lanes temporarily closed",
+                            state.ui.primary.sim.get_closed_lanes().len()
+                        ));
+                    }
                     if let Some(trip) = mode.following {
                         txt.add_line(format!("Following {}", trip));
                     }
@@ -180,6 +262,9 @@ impl SandboxMode {
                             txt.add_line("Showing active traffic".to_string());
                         }
                     }
+                    if let Some(name) = mode.overlays.active_name() {
+                        txt.add_line(format!("Showing {}", name));
+                    }
                     mode.menu.handle_event(ctx, Some(txt));
 
                     ctx.canvas.handle_event(ctx.input);
@@ -195,6 +280,9 @@ impl SandboxMode {
                     if let Some(evmode) = mode.common.event(ctx, &mut state.ui, &mut mode.menu) {
                         return evmode;
                     }
+                    if let Some(evmode) = mode.overlays.event(ctx, &state.ui, &mut mode.menu) {
+                        return evmode;
+                    }
 
                     if let Some(spawner) =
                         spawner::AgentSpawner::new(ctx, &mut state.ui, &mut mode.menu)
@@ -243,6 +331,8 @@ impl SandboxMode {
                     }
                     mode.route_viewer.event(ctx, &mut state.ui, &mut mode.menu);
                     mode.show_activity.event(ctx, &mut state.ui, &mut mode.menu);
+                    mode.throughput.event(ctx, &state.ui);
+                    closures::event(ctx, &mut state.ui);
                     if mode.menu.action("start time traveling") {
                         mode.state = State::TimeTraveling;
                         mode.time_travel.start(ctx, &state.ui);
@@ -252,6 +342,24 @@ impl SandboxMode {
                         mode.state = State::Scoreboard(score::Scoreboard::new(ctx, &state.ui));
                         return EventLoopMode::InputOnly;
                     }
+                    if mode.menu.action("show recent events") {
+                        mode.state = State::ShowingEvents(events::EventsPanel::new(&mode.events));
+                        return EventLoopMode::InputOnly;
+                    }
+                    if mode.menu.action("filter events by type") {
+                        mode.state =
+                            State::ShowingEvents(events::EventsPanel::choose_kind(&mode.events));
+                        return EventLoopMode::InputOnly;
+                    }
+                    if mode.menu.action("filter events by selected agent") {
+                        mode.events.filter_by_agent(
+                            state
+                                .ui
+                                .primary
+                                .current_selection
+                                .and_then(|id| id.agent_id()),
+                        );
+                    }
 
                     if mode.menu.action("quit") {
                         state.mode = Mode::SplashScreen(Wizard::new(), None);
@@ -272,11 +380,15 @@ impl SandboxMode {
                     {
                         // If speed is too high, don't be unresponsive for too long.
                         // TODO This should probably match the ezgui framerate.
-                        state.ui.primary.sim.time_limited_step(
-                            &state.ui.primary.map,
-                            dt,
-                            Duration::seconds(0.1),
-                        );
+                        {
+                            let _span = ctx.profiler.span("sim step");
+                            state.ui.primary.sim.time_limited_step(
+                                &state.ui.primary.map,
+                                dt,
+                                Duration::seconds(0.1),
+                            );
+                        }
+                        mode.events.record(&state.ui.primary.sim);
                         state.ui.primary.current_selection =
                             state.ui.recalculate_current_selection(
                                 ctx,
@@ -293,9 +405,12 @@ impl SandboxMode {
                             mode.following = None;
                             mode.route_viewer = route_viewer::RouteViewer::Inactive;
                             mode.show_activity = show_activity::ShowActivity::Inactive;
+                            mode.throughput = throughput::Throughput::new();
+                            mode.events.clear();
                         }
                         if mode.menu.action("save sim state") {
                             state.ui.primary.sim.save();
+                            state.ui.primary.sim.save_replay_log();
                         }
                         if mode.menu.action("load previous sim state") {
                             let prev_state = state
@@ -352,6 +467,7 @@ impl SandboxMode {
                                 .primary
                                 .sim
                                 .step(&state.ui.primary.map, Duration::seconds(0.1));
+                            mode.events.record(&state.ui.primary.sim);
                             state.ui.primary.current_selection =
                                 state.ui.recalculate_current_selection(
                                     ctx,
@@ -367,6 +483,7 @@ impl SandboxMode {
                                     &mut timer,
                                 );
                             });
+                            mode.events.record(&state.ui.primary.sim);
                             state.ui.primary.current_selection =
                                 state.ui.recalculate_current_selection(
                                     ctx,
@@ -376,6 +493,14 @@ impl SandboxMode {
                                 );
                         } else if mode.menu.action("jump to specific time") {
                             mode.state = State::JumpingToTime(Wizard::new());
+                        } else if state.ui.primary.sim.is_empty()
+                            && mode.menu.action("change weather")
+                        {
+                            // Pedestrians keep the speed they started with, so only allow this
+                            // before anybody's moving.
+                            mode.state = State::ChoosingConditions(Wizard::new());
+                        } else if mode.menu.action("play back a replay") {
+                            mode.state = State::Replaying(playback::Playback::new());
                         }
                         EventLoopMode::InputOnly
                     } else {
@@ -387,6 +512,20 @@ impl SandboxMode {
         }
     }
 
+    pub fn describe_state(&self) -> Vec<String> {
+        match self.state {
+            State::Playing => self.menu.describe_state(),
+            State::Spawning(ref spawner) => spawner.describe_state(),
+            State::TimeTraveling => vec!["Time traveling".to_string()],
+            State::ExploringRoute(_) => vec!["Exploring a route".to_string()],
+            State::JumpingToTime(ref wizard) => wizard.describe_state(),
+            State::Scoreboard(_) => vec!["Scoreboard".to_string()],
+            State::ChoosingConditions(ref wizard) => wizard.describe_state(),
+            State::Replaying(ref playback) => playback.describe_state(),
+            State::ShowingEvents(_) => vec!["Showing recent events".to_string()],
+        }
+    }
+
     pub fn draw(state: &GameState, g: &mut GfxCtx) {
         match state.mode {
             Mode::Sandbox(ref mode) => match mode.state {
@@ -411,7 +550,7 @@ impl SandboxMode {
                     );
                     explorer.draw(g, &state.ui);
                 }
-                State::JumpingToTime(ref wizard) => {
+                State::JumpingToTime(ref wizard) | State::ChoosingConditions(ref wizard) => {
                     state.ui.draw(
                         g,
                         DrawOptions::new(),
@@ -427,7 +566,31 @@ impl SandboxMode {
                         &state.ui.primary.sim,
                         &ShowEverything::new(),
                     );
-                    s.draw(g);
+                    s.draw(g, &state.ui);
+                }
+                State::Replaying(ref playback) => {
+                    if let Some(replay) = playback.get_replay() {
+                        state
+                            .ui
+                            .draw(g, DrawOptions::new(), replay, &ShowEverything::new());
+                    } else {
+                        state.ui.draw(
+                            g,
+                            DrawOptions::new(),
+                            &state.ui.primary.sim,
+                            &ShowEverything::new(),
+                        );
+                    }
+                    playback.draw(g);
+                }
+                State::ShowingEvents(ref panel) => {
+                    state.ui.draw(
+                        g,
+                        DrawOptions::new(),
+                        &state.ui.primary.sim,
+                        &ShowEverything::new(),
+                    );
+                    panel.draw(g);
                 }
                 _ => {
                     state.ui.draw(
@@ -436,9 +599,12 @@ impl SandboxMode {
                         &state.ui.primary.sim,
                         &ShowEverything::new(),
                     );
+                    closures::draw(g, &state.ui);
                     mode.common.draw(g, &state.ui);
                     mode.route_viewer.draw(g, &state.ui);
                     mode.show_activity.draw(g, &state.ui);
+                    mode.overlays.draw(g);
+                    mode.throughput.draw(g);
                     mode.menu.draw(g);
                     mode.speed.draw(g);
                 }
@@ -446,4 +612,15 @@ impl SandboxMode {
             _ => unreachable!(),
         }
     }
+
+    pub fn draw_secondary(state: &GameState, g: &mut GfxCtx) {
+        match state.mode {
+            Mode::Sandbox(ref mode) => {
+                if let State::Scoreboard(ref s) = mode.state {
+                    s.draw_secondary(g);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
 }
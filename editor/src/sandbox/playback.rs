@@ -0,0 +1,171 @@
+use crate::common::SpeedControls;
+use crate::ui::UI;
+use abstutil::Timer;
+use ezgui::{
+    hotkey, EventCtx, EventLoopMode, GfxCtx, Key, ModalMenu, ScreenPt, Slider, Text, Wizard,
+};
+use sim::{GetDrawAgents, Replay, ReplayLog};
+
+// Below the speed slider
+const TIME_SLIDER_TOP: f64 = 150.0;
+
+// Animates a previously recorded run from its ReplayLog, without touching the Sim.
+pub enum Playback {
+    Picking(Wizard),
+    // The log couldn't be read, or was recorded with different map edits, so its lanes and turns
+    // may not exist here.
+    CantPlay(String, Wizard),
+    Playing {
+        replay: Replay,
+        speed: SpeedControls,
+        time_slider: Slider,
+        menu: ModalMenu,
+    },
+}
+
+impl Playback {
+    pub fn new() -> Playback {
+        Playback::Picking(Wizard::new())
+    }
+
+    // None means done.
+    pub fn event(&mut self, ctx: &mut EventCtx, ui: &UI) -> Option<EventLoopMode> {
+        match self {
+            Playback::Picking(ref mut wizard) => {
+                let map_name = ui.primary.map.get_name().to_string();
+                if let Some((_, name)) = wizard.wrap(ctx).choose_something_no_keys::<String>(
+                    "Play back which replay?",
+                    Box::new(move || abstutil::list_all_objects("replays", &map_name)),
+                ) {
+                    let log: ReplayLog = match abstutil::read_binary(
+                        &format!("../data/replays/{}/{}.bin", ui.primary.map.get_name(), name),
+                        &mut Timer::throwaway(),
+                    ) {
+                        Ok(log) => log,
+                        Err(err) => {
+                            *self = Playback::CantPlay(
+                                format!("{} couldn't be read: {}", name, err),
+                                Wizard::new(),
+                            );
+                            return Some(EventLoopMode::InputOnly);
+                        }
+                    };
+                    let edits_name = &ui.primary.map.get_edits().edits_name;
+                    if &log.edits_name != edits_name {
+                        *self = Playback::CantPlay(
+                            format!(
+                                "{} was recorded with edits {}, but {} are loaded now",
+                                name, log.edits_name, edits_name
+                            ),
+                            Wizard::new(),
+                        );
+                        return Some(EventLoopMode::InputOnly);
+                    }
+                    *self = Playback::Playing {
+                        replay: Replay::new(log, &ui.primary.map),
+                        speed: SpeedControls::new(ctx, None),
+                        time_slider: Slider::new(Some(ScreenPt::new(0.0, TIME_SLIDER_TOP))),
+                        menu: ModalMenu::new(
+                            "Replay",
+                            vec![vec![
+                                (hotkey(Key::Escape), "quit"),
+                                (hotkey(Key::RightBracket), "speed up"),
+                                (hotkey(Key::LeftBracket), "slow down"),
+                                (hotkey(Key::Space), "pause/resume"),
+                            ]],
+                            ctx,
+                        ),
+                    };
+                } else if wizard.aborted() {
+                    return None;
+                }
+                Some(EventLoopMode::InputOnly)
+            }
+            Playback::CantPlay(ref reason, ref mut wizard) => {
+                if wizard
+                    .wrap(ctx)
+                    .acknowledge("Can't play back this replay", vec![reason.as_str()])
+                {
+                    return None;
+                }
+                Some(EventLoopMode::InputOnly)
+            }
+            Playback::Playing {
+                ref mut replay,
+                ref mut speed,
+                ref mut time_slider,
+                ref mut menu,
+            } => {
+                let start = replay.get_log().start;
+                let end = replay.get_log().end();
+
+                let mut txt = Text::prompt("Replay");
+                txt.add_line(format!("{} (until {})", replay.time(), end));
+                txt.add_line(speed.modal_status_line());
+                menu.handle_event(ctx, Some(txt));
+                ctx.canvas.handle_event(ctx.input);
+
+                if menu.action("quit") {
+                    return None;
+                }
+
+                if time_slider.event(ctx) {
+                    replay.set_time(
+                        start + (end - start) * time_slider.get_percent(),
+                        &ui.primary.map,
+                    );
+                } else if let Some(dt) = speed.event(ctx, menu, replay.time()) {
+                    let time = (replay.time() + dt).min(end);
+                    replay.set_time(time, &ui.primary.map);
+                    if end > start {
+                        time_slider.set_percent(ctx, (time - start) / (end - start));
+                    }
+                    if time == end {
+                        speed.pause();
+                    }
+                }
+
+                if speed.is_paused() {
+                    Some(EventLoopMode::InputOnly)
+                } else {
+                    Some(EventLoopMode::Animation)
+                }
+            }
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        match self {
+            Playback::Picking(ref wizard) | Playback::CantPlay(_, ref wizard) => {
+                wizard.draw(g);
+            }
+            Playback::Playing {
+                ref speed,
+                ref time_slider,
+                ref menu,
+                ..
+            } => {
+                menu.draw(g);
+                speed.draw(g);
+                time_slider.draw(g);
+            }
+        }
+    }
+
+    pub fn describe_state(&self) -> Vec<String> {
+        match self {
+            Playback::Picking(ref wizard) | Playback::CantPlay(_, ref wizard) => {
+                wizard.describe_state()
+            }
+            Playback::Playing { ref menu, .. } => menu.describe_state(),
+        }
+    }
+
+    // None until a replay is playing
+    pub fn get_replay(&self) -> Option<&Replay> {
+        match self {
+            Playback::Picking(_) | Playback::CantPlay(_, _) => None,
+            Playback::Playing { ref replay, .. } => Some(replay),
+        }
+    }
+}
@@ -5,6 +5,7 @@ use ezgui::{Color, EventCtx, EventLoopMode, GfxCtx, Key, Text, WarpingItemSlider
 use geom::{Distance, Polygon, Pt2D};
 use map_model::{Traversable, LANE_THICKNESS};
 use sim::AgentID;
+use std::collections::BTreeSet;
 
 pub struct RouteExplorer {
     slider: WarpingItemSlider<Traversable>,
@@ -27,6 +28,9 @@ impl RouteExplorer {
                 end: Position::new(LaneID(8188), Distance::meters(82.4241)),
                 can_use_bike_lanes: false,
                 can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: false,
+                avoid_lanes: BTreeSet::new(),
             });
             (agent, path?)
         };
@@ -96,6 +100,12 @@ impl RouteExplorer {
             Traversable::Turn(t) => {
                 DrawTurn::draw_full(ui.primary.map.get_t(*t), g, color);
             }
+            Traversable::MidblockCrossing(c) => {
+                g.draw_polygon(
+                    color,
+                    &ui.primary.map.get_mc(*c).geom.make_polygons(LANE_THICKNESS),
+                );
+            }
         }
         self.slider.draw(g);
         CommonState::draw_osd(g, ui, ui.primary.current_selection);
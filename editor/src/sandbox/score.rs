@@ -1,24 +1,33 @@
+use crate::sandbox::trip_explorer::TripExplorer;
 use crate::ui::UI;
 use ezgui::{
-    hotkey, EventCtx, GfxCtx, HorizontalAlignment, Key, ModalMenu, Text, VerticalAlignment, Wizard,
-    WrappedWizard,
+    hotkey, EventCtx, EventLoopMode, GfxCtx, HorizontalAlignment, Key, ModalMenu, Text,
+    VerticalAlignment, Wizard, WrappedWizard,
 };
-use geom::{Duration, DurationHistogram};
-use itertools::Itertools;
-use sim::{FinishedTrips, TripID, TripMode};
+use geom::Duration;
+use sim::{FinishedTrips, TripID, TripMode, TripPurpose};
+use std::collections::BTreeSet;
 
 pub enum Scoreboard {
-    Summary(ModalMenu, Text),
+    // The bool is true when grouping by trip purpose instead of mode
+    Summary(ModalMenu, Text, bool),
     BrowseTrips(FinishedTrips, Wizard),
+    ExploreTrip(TripExplorer),
+    NoTripDetails(TripID, Wizard),
 }
 
 impl Scoreboard {
     pub fn new(ctx: &mut EventCtx, ui: &UI) -> Scoreboard {
+        Scoreboard::summary(ctx, ui, false)
+    }
+
+    fn summary(ctx: &mut EventCtx, ui: &UI, by_purpose: bool) -> Scoreboard {
         let menu = ModalMenu::new(
             "Scoreboard",
             vec![
                 (hotkey(Key::Escape), "quit"),
                 (hotkey(Key::B), "browse trips"),
+                (hotkey(Key::P), "toggle grouping by purpose"),
             ],
             ctx,
         );
@@ -28,89 +37,152 @@ impl Scoreboard {
         summary.push(format!("Score at [red:{}]", ui.primary.sim.time()));
         summary.push(format!("[cyan:{}] unfinished trips", t.unfinished_trips));
 
-        for (mode, trips) in &t
-            .finished_trips
-            .into_iter()
-            .sorted_by_key(|(_, m, _)| *m)
-            .group_by(|(_, m, _)| *m)
-        {
-            let mut distrib: DurationHistogram = std::default::Default::default();
-            for (_, _, dt) in trips {
-                distrib.add(dt);
+        if by_purpose {
+            for (purpose, distrib) in t.per_purpose() {
+                summary.push(format!(
+                    "[cyan:{:?}] trips: {}",
+                    purpose,
+                    distrib.describe()
+                ));
+            }
+            summary.push(format!(
+                "[cyan:{}] finished trips without a purpose",
+                t.finished_trips.len() - t.metadata.len()
+            ));
+        } else {
+            for (mode, distrib) in t.per_mode() {
+                summary.push(format!("[cyan:{:?}] trips: {}", mode, distrib.describe()));
             }
-            summary.push(format!("[cyan:{:?}] trips: {}", mode, distrib.describe()));
         }
 
-        Scoreboard::Summary(menu, summary)
+        Scoreboard::Summary(menu, summary, by_purpose)
     }
 
-    // Returns true if done and we should go back to main sandbox mode.
-    pub fn event(&mut self, ctx: &mut EventCtx, ui: &UI) -> bool {
+    // Returns None if done and we should go back to main sandbox mode.
+    pub fn event(&mut self, ctx: &mut EventCtx, ui: &UI) -> Option<EventLoopMode> {
         match self {
-            Scoreboard::Summary(ref mut menu, _) => {
+            Scoreboard::Summary(ref mut menu, _, by_purpose) => {
                 menu.handle_event(ctx, None);
                 if menu.action("quit") {
-                    return true;
+                    return None;
                 }
-                if menu.action("browse trips") {
+                if menu.action("toggle grouping by purpose") {
+                    *self = Scoreboard::summary(ctx, ui, !*by_purpose);
+                } else if menu.action("browse trips") {
                     *self =
                         Scoreboard::BrowseTrips(ui.primary.sim.get_finished_trips(), Wizard::new());
                 }
             }
             Scoreboard::BrowseTrips(ref trips, ref mut wizard) => {
-                if let Some(_) = pick_trip(trips, &mut wizard.wrap(ctx)) {
-                    // TODO show trip departure, where it started and ended
-                    *self = Scoreboard::new(ctx, ui);
+                if let Some(trip) = pick_trip(trips, &mut wizard.wrap(ctx)) {
+                    *self = match TripExplorer::new(trip, ctx, ui) {
+                        Some(explorer) => Scoreboard::ExploreTrip(explorer),
+                        None => Scoreboard::NoTripDetails(trip, Wizard::new()),
+                    };
                 } else if wizard.aborted() {
                     *self = Scoreboard::new(ctx, ui);
                 }
             }
+            Scoreboard::ExploreTrip(ref mut explorer) => {
+                if let Some(evmode) = explorer.event(ctx) {
+                    return Some(evmode);
+                }
+                *self = Scoreboard::new(ctx, ui);
+            }
+            Scoreboard::NoTripDetails(trip, ref mut wizard) => {
+                let reason = if ui.primary.sim.is_recording_trip_details() {
+                    "None of its legs have a path to show"
+                } else {
+                    "Trip details weren't recorded; run with --record_trip_details"
+                };
+                if wizard
+                    .wrap(ctx)
+                    .acknowledge(&format!("Can't explore {}", trip), vec![reason])
+                {
+                    *self = Scoreboard::new(ctx, ui);
+                }
+            }
         }
-        false
+        Some(EventLoopMode::InputOnly)
     }
 
-    pub fn draw(&self, g: &mut GfxCtx) {
+    pub fn draw(&self, g: &mut GfxCtx, ui: &UI) {
         match self {
-            Scoreboard::Summary(ref menu, ref txt) => {
-                g.draw_blocking_text(
-                    txt,
-                    (HorizontalAlignment::Center, VerticalAlignment::Center),
-                );
+            Scoreboard::Summary(ref menu, ref txt, _) => {
+                if !g.canvas.has_secondary_window() {
+                    g.draw_blocking_text(
+                        txt,
+                        (HorizontalAlignment::Center, VerticalAlignment::Center),
+                    );
+                }
                 menu.draw(g);
             }
-            Scoreboard::BrowseTrips(_, ref wizard) => {
+            Scoreboard::BrowseTrips(_, ref wizard) | Scoreboard::NoTripDetails(_, ref wizard) => {
                 wizard.draw(g);
             }
+            Scoreboard::ExploreTrip(ref explorer) => {
+                explorer.draw(g, ui);
+            }
+        }
+    }
+
+    pub fn draw_secondary(&self, g: &mut GfxCtx) {
+        if let Scoreboard::Summary(_, ref txt, _) = self {
+            g.draw_blocking_text(
+                txt,
+                (HorizontalAlignment::Center, VerticalAlignment::Center),
+            );
         }
     }
 }
 
+enum TripFilter {
+    Mode(TripMode),
+    Purpose(TripPurpose),
+}
+
 fn pick_trip(trips: &FinishedTrips, wizard: &mut WrappedWizard) -> Option<TripID> {
-    let mode = wizard
-        .choose_something_no_keys::<TripMode>(
-            "Browse which trips?",
-            Box::new(|| {
-                vec![
-                    ("walk".to_string(), TripMode::Walk),
-                    ("bike".to_string(), TripMode::Bike),
-                    ("transit".to_string(), TripMode::Transit),
-                    ("drive".to_string(), TripMode::Drive),
-                ]
-            }),
-        )?
+    let mut filters = vec![
+        ("walk".to_string(), TripFilter::Mode(TripMode::Walk)),
+        ("bike".to_string(), TripFilter::Mode(TripMode::Bike)),
+        ("transit".to_string(), TripFilter::Mode(TripMode::Transit)),
+        ("drive".to_string(), TripFilter::Mode(TripMode::Drive)),
+    ];
+    let purposes: BTreeSet<TripPurpose> =
+        trips.metadata.values().map(|m| m.main_purpose()).collect();
+    for purpose in purposes {
+        filters.push((
+            format!("purpose: {:?}", purpose),
+            TripFilter::Purpose(purpose),
+        ));
+    }
+    let names: Vec<(String, usize)> = filters
+        .iter()
+        .enumerate()
+        .map(|(idx, (name, _))| (name.clone(), idx))
+        .collect();
+    let idx = wizard
+        .choose_something_no_keys::<usize>("Browse which trips?", Box::new(move || names.clone()))?
         .1;
     // TODO Ewwww. Can't do this inside choices_generator because trips isn't &'a static.
     let mut filtered: Vec<&(TripID, TripMode, Duration)> = trips
         .finished_trips
         .iter()
-        .filter(|(_, m, _)| *m == mode)
+        .filter(|(id, m, _)| match filters[idx].1 {
+            TripFilter::Mode(mode) => *m == mode,
+            TripFilter::Purpose(purpose) => trips
+                .metadata
+                .get(id)
+                .map(|md| md.main_purpose() == purpose)
+                .unwrap_or(false),
+        })
         .collect();
     filtered.sort_by_key(|(_, _, dt)| *dt);
     filtered.reverse();
     let choices: Vec<(String, TripID)> = filtered
         .into_iter()
         // TODO Show percentile for time
-        .map(|(id, _, dt)| (format!("{} taking {}", id, dt), *id))
+        .map(|(id, _, dt)| (format!("{} taking {}", id, dt.to_string_hms()), *id))
         .collect();
     wizard
         .choose_something_no_keys::<TripID>(
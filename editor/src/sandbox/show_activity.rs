@@ -156,6 +156,7 @@ impl RoadHeatmap {
                 Traversable::Lane(l) => map.get_l(l).parent,
                 // Count the destination
                 Traversable::Turn(t) => map.get_l(t.dst).parent,
+                Traversable::MidblockCrossing(c) => map.get_mc(c).parent,
             };
             h.count_per_road.entry(r).or_insert(0);
             let count = h.count_per_road[&r] + 1;
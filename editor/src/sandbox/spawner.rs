@@ -3,14 +3,16 @@ use crate::helpers::ID;
 use crate::render::DrawOptions;
 use crate::ui::{ShowEverything, UI};
 use abstutil::Timer;
-use ezgui::{hotkey, EventCtx, GfxCtx, Key, ModalMenu};
+use ezgui::{hotkey, Color, EventCtx, GfxCtx, Key, ModalMenu, Text};
 use geom::{Duration, PolyLine};
 use map_model::{
-    BuildingID, IntersectionID, IntersectionType, LaneType, PathRequest, Position, LANE_THICKNESS,
+    BuildingID, IntersectionID, IntersectionType, LaneType, PathRequest, Position, SpawnError,
+    LANE_THICKNESS,
 };
 use rand::seq::SliceRandom;
 use rand::Rng;
 use sim::{DrivingGoal, Scenario, SidewalkSpot, TripSpec};
+use std::collections::BTreeSet;
 
 const SMALL_DT: Duration = Duration::const_seconds(0.1);
 
@@ -18,6 +20,8 @@ pub struct AgentSpawner {
     menu: ModalMenu,
     from: Source,
     maybe_goal: Option<(Goal, Option<PolyLine>)>,
+    // Why the last attempt to spawn didn't work
+    error: Option<SpawnError>,
 }
 
 #[derive(Clone)]
@@ -50,9 +54,10 @@ impl AgentSpawner {
                         menu,
                         from: Source::Walking(id),
                         maybe_goal: None,
+                        error: None,
                     });
                 }
-                if let Some(pos) = Position::bldg_via_driving(id, map) {
+                if let Ok(pos) = Position::bldg_via_driving(id, map) {
                     if ctx
                         .input
                         .contextual_action(Key::F4, "spawn a car starting here")
@@ -61,6 +66,7 @@ impl AgentSpawner {
                             menu,
                             from: Source::Driving(pos),
                             maybe_goal: None,
+                            error: None,
                         });
                     }
                 }
@@ -75,6 +81,7 @@ impl AgentSpawner {
                         menu,
                         from: Source::Driving(Position::new(id, map.get_l(id).length() / 2.0)),
                         maybe_goal: None,
+                        error: None,
                     });
                 }
             }
@@ -152,6 +159,7 @@ impl AgentSpawner {
         };
 
         if recalculate {
+            self.error = None;
             let start = match self.from {
                 Source::Walking(b) => Position::bldg_via_walking(b, map),
                 Source::Driving(pos) => pos,
@@ -187,6 +195,9 @@ impl AgentSpawner {
                     end,
                     can_use_bike_lanes: false,
                     can_use_bus_lanes: false,
+                    can_use_hov_lanes: false,
+                    can_use_midblock_crossings: false,
+                    avoid_lanes: BTreeSet::new(),
                 }) {
                     self.maybe_goal = Some((new_goal, path.trace(map, start.dist_along(), None)));
                 } else {
@@ -198,7 +209,7 @@ impl AgentSpawner {
         if self.maybe_goal.is_some() && ctx.input.contextual_action(Key::F3, "end the agent here") {
             let mut rng = ui.primary.current_flags.sim_flags.make_rng();
             let sim = &mut ui.primary.sim;
-            match (self.from.clone(), self.maybe_goal.take().unwrap().0) {
+            let result = match (self.from.clone(), self.maybe_goal.take().unwrap().0) {
                 (Source::Walking(from), Goal::Building(to)) => {
                     sim.schedule_trip(
                         sim.time(),
@@ -209,9 +220,10 @@ impl AgentSpawner {
                         },
                         map,
                     );
+                    Ok(())
                 }
-                (Source::Walking(from), Goal::Border(to)) => {
-                    if let Some(goal) = SidewalkSpot::end_at_border(to, map) {
+                (Source::Walking(from), Goal::Border(to)) => SidewalkSpot::end_at_border(to, map)
+                    .map(|goal| {
                         sim.schedule_trip(
                             sim.time(),
                             TripSpec::JustWalking {
@@ -221,12 +233,9 @@ impl AgentSpawner {
                             },
                             map,
                         );
-                    } else {
-                        println!("Can't end a walking trip at {}; no sidewalks", to);
-                    }
-                }
-                (Source::Driving(from), Goal::Building(to)) => {
-                    if let Some(start_pos) = TripSpec::spawn_car_at(from, map) {
+                    }),
+                (Source::Driving(from), Goal::Building(to)) => TripSpec::spawn_car_at(from, map)
+                    .map(|start_pos| {
                         sim.schedule_trip(
                             sim.time(),
                             TripSpec::CarAppearing {
@@ -237,13 +246,9 @@ impl AgentSpawner {
                             },
                             map,
                         );
-                    } else {
-                        println!("Can't make a car appear at {:?}", from);
-                    }
-                }
+                    }),
                 (Source::Driving(from), Goal::Border(to)) => {
-                    if let Some(goal) = DrivingGoal::end_at_border(to, vec![LaneType::Driving], map)
-                    {
+                    DrivingGoal::end_at_border(to, vec![LaneType::Driving], map).map(|goal| {
                         sim.schedule_trip(
                             sim.time(),
                             TripSpec::CarAppearing {
@@ -254,11 +259,14 @@ impl AgentSpawner {
                             },
                             map,
                         );
-                    } else {
-                        println!("Can't end a car trip at {}; no driving lanes", to);
-                    }
+                    })
                 }
             };
+            if let Err(err) = result {
+                // Stay here, so the OSD can explain what went wrong.
+                self.error = Some(err);
+                return false;
+            }
             sim.spawn_all_trips(map, &mut Timer::new("spawn trip"), false);
             sim.step(map, SMALL_DT);
             ui.primary.current_selection = ui.recalculate_current_selection(
@@ -273,6 +281,23 @@ impl AgentSpawner {
         false
     }
 
+    pub fn describe_state(&self) -> Vec<String> {
+        let mut lines = self.menu.describe_state();
+        lines.push(match self.from {
+            Source::Walking(b) => format!("Spawning a pedestrian from {}", b),
+            Source::Driving(pos) => format!("Spawning a car from {}", pos.lane()),
+        });
+        match self.maybe_goal {
+            Some((Goal::Building(b), _)) => lines.push(format!("Ending at {}", b)),
+            Some((Goal::Border(i), _)) => lines.push(format!("Ending at {}", i)),
+            None => {}
+        }
+        if let Some(ref err) = self.error {
+            lines.push(format!("Can't spawn that agent: {}", err));
+        }
+        lines
+    }
+
     pub fn draw(&self, g: &mut GfxCtx, ui: &UI) {
         let src = match self.from {
             Source::Walking(b1) => ID::Building(b1),
@@ -287,7 +312,16 @@ impl AgentSpawner {
         }
 
         self.menu.draw(g);
-        CommonState::draw_osd(g, ui, ui.primary.current_selection);
+        if let Some(ref err) = self.error {
+            let mut osd = Text::new();
+            osd.append(
+                format!("Can't spawn that agent: {}", err),
+                Some(ui.cs.get_def("spawn error", Color::RED)),
+            );
+            CommonState::draw_custom_osd(g, osd);
+        } else {
+            CommonState::draw_osd(g, ui, ui.primary.current_selection);
+        }
     }
 }
 
@@ -0,0 +1,105 @@
+use crate::ui::UI;
+use ezgui::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, ScreenPt};
+use geom::{Distance, Duration, Polygon, Pt2D};
+
+const CHART_WIDTH: f64 = 300.0;
+const CHART_HEIGHT: f64 = 100.0;
+const MARGIN: f64 = 10.0;
+
+// A small bar chart in the bottom-left of the screen, showing how many trips finished during each
+// minute of the simulation.
+pub struct Throughput {
+    per_minute: Vec<usize>,
+    total: usize,
+    draw: Option<Drawable>,
+}
+
+impl Throughput {
+    pub fn new() -> Throughput {
+        Throughput {
+            per_minute: Vec::new(),
+            total: 0,
+            draw: None,
+        }
+    }
+
+    pub fn event(&mut self, ctx: &mut EventCtx, ui: &UI) {
+        let minutes = (ui.primary.sim.time() / Duration::minutes(1)) as usize;
+        // The sim was reset or an earlier savestate was loaded.
+        if minutes < self.per_minute.len() {
+            *self = Throughput::new();
+        }
+        // get_finished_trips is expensive, so only look once per sim-minute.
+        if minutes == self.per_minute.len() {
+            return;
+        }
+
+        let finished = ui.primary.sim.get_finished_trips().finished_trips.len();
+        if finished < self.total {
+            *self = Throughput::new();
+        }
+        self.record(minutes, finished);
+        self.draw = Some(ctx.prerender.upload(self.make_chart()));
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        if let Some(ref draw) = self.draw {
+            g.fork(
+                Pt2D::new(0.0, 0.0),
+                ScreenPt::new(MARGIN, g.canvas.window_height - CHART_HEIGHT - MARGIN),
+                1.0,
+            );
+            g.redraw(draw);
+            g.unfork();
+        }
+    }
+
+    // Called after `minutes` full minutes of the sim have elapsed, when `finished` trips are done
+    // in total. If several minutes passed since the last call, everything new is attributed to the
+    // most recent minute.
+    pub fn record(&mut self, minutes: usize, finished: usize) {
+        if minutes == 0 {
+            return;
+        }
+        self.per_minute.resize(minutes, 0);
+        self.per_minute[minutes - 1] += finished - self.total;
+        self.total = finished;
+    }
+
+    pub fn per_minute(&self) -> &Vec<usize> {
+        &self.per_minute
+    }
+
+    fn make_chart(&self) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        batch.push(
+            Color::grey(0.2).alpha(0.8),
+            Polygon::rectangle_topleft(
+                Pt2D::new(0.0, 0.0),
+                Distance::meters(CHART_WIDTH),
+                Distance::meters(CHART_HEIGHT),
+            ),
+        );
+
+        let max = *self.per_minute.iter().max().unwrap_or(&0);
+        if max == 0 {
+            return batch;
+        }
+        let bar_width = CHART_WIDTH / (self.per_minute.len() as f64);
+        for (idx, cnt) in self.per_minute.iter().enumerate() {
+            if *cnt == 0 {
+                continue;
+            }
+            let height = CHART_HEIGHT * (*cnt as f64) / (max as f64);
+            batch.push(
+                Color::CYAN,
+                Polygon::rectangle_topleft(
+                    Pt2D::new((idx as f64) * bar_width, CHART_HEIGHT - height),
+                    Distance::meters(bar_width),
+                    Distance::meters(height),
+                ),
+            );
+        }
+        batch
+    }
+}
@@ -0,0 +1,59 @@
+use crate::common::Overlay;
+use crate::ui::UI;
+use abstutil::Timer;
+use ezgui::{Color, GeomBatch, Text};
+use geom::{Duration, Polygon};
+
+// Fastest to slowest
+const COLORS: [(usize, usize, usize); 4] = [
+    (26, 150, 65),
+    (166, 217, 106),
+    (253, 174, 97),
+    (215, 25, 28),
+];
+
+// Colors lanes by how long agents take to cross them, on average.
+pub struct TravelTimes;
+
+impl Overlay for TravelTimes {
+    fn recompute(&self, ui: &UI, _: &mut Timer) -> (GeomBatch, Text) {
+        let times = ui.primary.sim.lane_travel_times();
+        let max = times
+            .values()
+            .map(|(_, avg)| *avg)
+            .max()
+            .unwrap_or(Duration::ZERO);
+
+        let mut buckets: Vec<(Option<(Duration, Duration)>, Vec<Polygon>)> =
+            COLORS.iter().map(|_| (None, Vec::new())).collect();
+        for (l, (_, avg)) in times {
+            let idx = if max == Duration::ZERO {
+                0
+            } else {
+                (((avg / max) * (COLORS.len() as f64)) as usize).min(COLORS.len() - 1)
+            };
+            let bucket = &mut buckets[idx];
+            bucket.0 = Some(match bucket.0 {
+                Some((low, high)) => (low.min(avg), high.max(avg)),
+                None => (avg, avg),
+            });
+            bucket.1.push(ui.primary.draw_map.get_l(l).polygon.clone());
+        }
+
+        let mut batch = GeomBatch::new();
+        let mut legend = Text::prompt("Average time to cross each lane");
+        for ((red, green, blue), (range, polygons)) in COLORS.iter().zip(buckets) {
+            if let Some((low, high)) = range {
+                let color = Color::rgb(*red, *green, *blue);
+                batch.extend(color.alpha(0.8), polygons);
+                legend.add_styled_line(
+                    format!("{} - {}", low.to_string_hms(), high.to_string_hms()),
+                    Some(color),
+                    None,
+                    None,
+                );
+            }
+        }
+        (batch, legend)
+    }
+}
@@ -0,0 +1,57 @@
+use crate::common::Overlay;
+use crate::ui::UI;
+use abstutil::Timer;
+use ezgui::{Color, GeomBatch, Text};
+use geom::Polygon;
+use map_model::Road;
+
+// Lightest to heaviest traffic
+const COLORS: [(usize, usize, usize); 4] = [
+    (255, 255, 178),
+    (254, 204, 92),
+    (253, 141, 60),
+    (227, 26, 28),
+];
+
+// Colors roads by how many agents have entered them so far.
+pub struct TravelVolume;
+
+impl Overlay for TravelVolume {
+    fn recompute(&self, ui: &UI, _: &mut Timer) -> (GeomBatch, Text) {
+        let map = &ui.primary.map;
+        let stats = ui.primary.sim.get_travel_stats();
+        let counts: Vec<(&Road, usize)> = map
+            .all_roads()
+            .iter()
+            .map(|r| (r, stats.road_count(r)))
+            .filter(|(_, cnt)| *cnt > 0)
+            .collect();
+        let max = counts.iter().map(|(_, cnt)| *cnt).max().unwrap_or(0);
+
+        // Log scale, so a few busy arterials don't wash out everything else
+        let max_log = ((max + 1) as f64).ln();
+        let mut buckets: Vec<(Option<(usize, usize)>, Vec<Polygon>)> =
+            COLORS.iter().map(|_| (None, Vec::new())).collect();
+        for (r, cnt) in counts {
+            let percent = ((cnt + 1) as f64).ln() / max_log;
+            let idx = ((percent * (COLORS.len() as f64)) as usize).min(COLORS.len() - 1);
+            let bucket = &mut buckets[idx];
+            bucket.0 = Some(match bucket.0 {
+                Some((low, high)) => (low.min(cnt), high.max(cnt)),
+                None => (cnt, cnt),
+            });
+            bucket.1.push(r.get_thick_polygon().unwrap());
+        }
+
+        let mut batch = GeomBatch::new();
+        let mut legend = Text::prompt("Agents entering each road");
+        for ((red, green, blue), (range, polygons)) in COLORS.iter().zip(buckets) {
+            if let Some((low, high)) = range {
+                let color = Color::rgb(*red, *green, *blue);
+                batch.extend(color.alpha(0.8), polygons);
+                legend.add_styled_line(format!("{} - {}", low, high), Some(color), None, None);
+            }
+        }
+        (batch, legend)
+    }
+}
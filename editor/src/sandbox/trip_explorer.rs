@@ -0,0 +1,106 @@
+use crate::ui::UI;
+use ezgui::{Color, EventCtx, EventLoopMode, GfxCtx, Text, WarpingItemSlider};
+use geom::{Circle, Distance, Duration, PolyLine};
+use map_model::LANE_THICKNESS;
+use sim::{TripID, TripMode};
+
+struct Leg {
+    mode: TripMode,
+    // None if the leg isn't done yet
+    duration: Option<Duration>,
+    trace: PolyLine,
+}
+
+pub struct TripExplorer {
+    trip: TripID,
+    legs: Vec<Leg>,
+    // Indexes into legs
+    slider: WarpingItemSlider<usize>,
+}
+
+impl TripExplorer {
+    // None if the trip's legs weren't recorded.
+    pub fn new(trip: TripID, ctx: &mut EventCtx, ui: &UI) -> Option<TripExplorer> {
+        let map = &ui.primary.map;
+        let mut legs = Vec::new();
+        for leg in ui.primary.sim.get_trip_details(trip) {
+            // Legs without a path, like a bus ride between stops that couldn't be routed, can't
+            // be shown.
+            if let Some(trace) = leg
+                .path
+                .as_ref()
+                .and_then(|(path, start_dist)| path.trace(map, *start_dist, None))
+            {
+                legs.push(Leg {
+                    mode: leg.mode,
+                    duration: leg.finished_at.map(|t| t - leg.started_at),
+                    trace,
+                });
+            }
+        }
+        if legs.is_empty() {
+            return None;
+        }
+
+        let items = legs
+            .iter()
+            .enumerate()
+            .map(|(idx, leg)| (leg.trace.middle(), idx))
+            .collect();
+        Some(TripExplorer {
+            trip,
+            legs,
+            slider: WarpingItemSlider::new(items, "Trip Explorer", "leg", ctx),
+        })
+    }
+
+    // Done when None
+    pub fn event(&mut self, ctx: &mut EventCtx) -> Option<EventLoopMode> {
+        ctx.canvas.handle_event(ctx.input);
+
+        let (current, _) = self.slider.get();
+        let mut txt = Text::prompt(&format!("Trip Explorer for {}", self.trip));
+        txt.add_line(format!("Leg {}/{}", current + 1, self.legs.len()));
+        for (idx, leg) in self.legs.iter().enumerate() {
+            let line = format!(
+                "{:?} for {}",
+                leg.mode,
+                leg.duration
+                    .map(|dt| dt.to_string())
+                    .unwrap_or_else(|| "unfinished".to_string())
+            );
+            if idx == current {
+                txt.add_styled_line(line, None, Some(Color::BLUE), None);
+            } else {
+                txt.add_line(line);
+            }
+        }
+
+        self.slider.event(ctx, Some(txt)).map(|(evmode, _)| evmode)
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, ui: &UI) {
+        let (current, _) = self.slider.get();
+        let boundary_color = ui.cs.get_def("trip leg boundary", Color::BLACK);
+        for (idx, leg) in self.legs.iter().enumerate() {
+            let color = match leg.mode {
+                TripMode::Walk => ui.cs.get_def("trip leg walking", Color::GREEN),
+                TripMode::Bike => ui.cs.get_def("trip leg biking", Color::CYAN),
+                TripMode::Transit => ui.cs.get_def("trip leg riding bus", Color::BLUE),
+                TripMode::Drive => ui.cs.get_def("trip leg driving", Color::RED),
+            };
+            g.draw_polygon(
+                if idx == current {
+                    color
+                } else {
+                    color.alpha(0.5)
+                },
+                &leg.trace.make_polygons(LANE_THICKNESS),
+            );
+            for pt in vec![leg.trace.first_pt(), leg.trace.last_pt()] {
+                g.draw_circle(boundary_color, &Circle::new(pt, Distance::meters(5.0)));
+            }
+        }
+        self.slider.draw(g);
+    }
+}
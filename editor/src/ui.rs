@@ -1,13 +1,14 @@
-use crate::helpers::{ColorScheme, ID};
+use crate::common::Annotations;
+use crate::helpers::{ColorScheme, COLOR_SCHEME_PATH, ID};
 use crate::render::{
-    draw_vehicle, AgentCache, DrawCtx, DrawMap, DrawOptions, DrawPedestrian, Renderable,
-    MIN_ZOOM_FOR_DETAIL,
+    draw_bike_racks, draw_parking_lots, draw_vehicle, AgentCache, DrawCtx, DrawMap, DrawOptions,
+    DrawPedestrian, Renderable, MIN_ZOOM_FOR_DETAIL,
 };
 use abstutil;
-use abstutil::{MeasureMemory, Timer};
+use abstutil::{FileWatcher, MeasureMemory, Timer, TimerWarning};
 use ezgui::{Color, EventCtx, GeomBatch, GfxCtx, Prerender};
 use geom::{Bounds, Circle, Distance, Duration};
-use map_model::{Map, Traversable};
+use map_model::{ConnectivityReport, Map, Traversable};
 use serde_derive::{Deserialize, Serialize};
 use sim::{GetDrawAgents, Sim, SimFlags};
 use structopt::StructOpt;
@@ -16,6 +17,7 @@ use structopt::StructOpt;
 pub struct UI {
     pub primary: PerMapUI,
     pub cs: ColorScheme,
+    cs_watcher: FileWatcher,
 }
 
 impl UI {
@@ -24,7 +26,41 @@ impl UI {
         let primary = ctx.loading_screen("load map", |ctx, mut timer| {
             PerMapUI::new(flags, &cs, ctx, &mut timer)
         });
-        UI { primary, cs }
+        UI {
+            primary,
+            cs,
+            cs_watcher: FileWatcher::new(COLOR_SCHEME_PATH),
+        }
+    }
+
+    // True if the color scheme was edited on disk since the last call and reloaded. The caller
+    // must regenerate any other PerMapUI it owns.
+    pub fn reload_assets_if_changed(&mut self, ctx: &mut EventCtx) -> bool {
+        if !self.cs_watcher.changed() {
+            return false;
+        }
+        println!("{} changed, reloading", self.cs_watcher.path());
+        self.reload_assets(ctx)
+    }
+
+    // Colors are baked into everything DrawMap prerenders, so that has to be rebuilt too. Returns
+    // false if the color scheme couldn't be loaded, in which case nothing changes.
+    pub fn reload_assets(&mut self, ctx: &mut EventCtx) -> bool {
+        match ColorScheme::load() {
+            Ok(mut cs) => {
+                cs.set_palette(self.cs.get_palette());
+                self.cs = cs;
+            }
+            Err(err) => {
+                println!(
+                    "Couldn't reload {}, keeping old colors: {}",
+                    COLOR_SCHEME_PATH, err
+                );
+                return false;
+            }
+        }
+        self.primary.regenerate_draw_map(&self.cs, ctx);
+        true
     }
 
     pub fn draw(
@@ -33,36 +69,48 @@ impl UI {
         opts: DrawOptions,
         source: &GetDrawAgents,
         show_objs: &ShowObject,
+    ) {
+        self.draw_per_map(g, &self.primary, opts, source, show_objs);
+    }
+
+    // Like draw(), but for any map, not just the primary one.
+    pub fn draw_per_map(
+        &self,
+        g: &mut GfxCtx,
+        per_map: &PerMapUI,
+        opts: DrawOptions,
+        source: &GetDrawAgents,
+        show_objs: &ShowObject,
     ) {
         let ctx = DrawCtx {
             cs: &self.cs,
-            map: &self.primary.map,
-            draw_map: &self.primary.draw_map,
-            sim: &self.primary.sim,
+            map: &per_map.map,
+            draw_map: &per_map.draw_map,
+            sim: &per_map.sim,
         };
         let mut sample_intersection: Option<String> = None;
 
         g.clear(self.cs.get_def("true background", Color::BLACK));
-        g.redraw(&self.primary.draw_map.boundary_polygon);
+        g.redraw(&per_map.draw_map.boundary_polygon);
 
         if g.canvas.cam_zoom < MIN_ZOOM_FOR_DETAIL && !g.is_screencap() {
             // Unzoomed mode
             let layers = show_objs.layers();
             if layers.show_areas {
-                g.redraw(&self.primary.draw_map.draw_all_areas);
+                g.redraw(&per_map.draw_map.draw_all_areas);
             }
             if layers.show_lanes {
-                g.redraw(&self.primary.draw_map.draw_all_thick_roads);
+                g.redraw(&per_map.draw_map.draw_all_thick_roads);
             }
             if layers.show_intersections {
-                g.redraw(&self.primary.draw_map.draw_all_unzoomed_intersections);
+                g.redraw(&per_map.draw_map.draw_all_unzoomed_intersections);
             }
             if layers.show_buildings {
-                g.redraw(&self.primary.draw_map.draw_all_buildings);
+                g.redraw(&per_map.draw_map.draw_all_buildings);
             }
 
             if layers.show_extra_shapes {
-                for es in &self.primary.draw_map.extra_shapes {
+                for es in &per_map.draw_map.extra_shapes {
                     if show_objs.show(es.get_id()) {
                         es.draw(g, &opts, &ctx);
                     }
@@ -70,20 +118,19 @@ impl UI {
             }
 
             // Still show area/extra shape selection when zoomed out.
-            if let Some(ID::Area(id)) = self.primary.current_selection {
+            if let Some(ID::Area(id)) = per_map.current_selection {
                 g.draw_polygon(
                     self.cs.get("selected"),
                     &ctx.draw_map.get_a(id).get_outline(&ctx.map),
                 );
-            } else if let Some(ID::ExtraShape(id)) = self.primary.current_selection {
+            } else if let Some(ID::ExtraShape(id)) = per_map.current_selection {
                 g.draw_polygon(
                     self.cs.get("selected"),
                     &ctx.draw_map.get_es(id).get_outline(&ctx.map),
                 );
             }
 
-            let (cars, bikes, buses, peds) =
-                self.primary.sim.get_unzoomed_agents(&self.primary.map);
+            let (cars, bikes, buses, peds) = per_map.sim.get_unzoomed_agents(&per_map.map);
             let mut batch = GeomBatch::new();
             let radius = Distance::meters(10.0) / g.canvas.cam_zoom;
             for (color, agents) in vec![
@@ -108,8 +155,9 @@ impl UI {
             }
             batch.draw(g);
         } else {
-            let mut cache = self.primary.draw_map.agents.borrow_mut();
+            let mut cache = per_map.draw_map.agents.borrow_mut();
             let objects = self.get_renderables_back_to_front(
+                per_map,
                 g.get_screen_bounds(),
                 &g.prerender,
                 &mut cache,
@@ -124,13 +172,13 @@ impl UI {
                 match obj.get_id() {
                     ID::Building(_) => {
                         if !drawn_all_buildings {
-                            g.redraw(&self.primary.draw_map.draw_all_buildings);
+                            g.redraw(&per_map.draw_map.draw_all_buildings);
                             drawn_all_buildings = true;
                         }
                     }
                     ID::Area(_) => {
                         if !drawn_all_areas {
-                            g.redraw(&self.primary.draw_map.draw_all_areas);
+                            g.redraw(&per_map.draw_map.draw_all_areas);
                             drawn_all_areas = true;
                         }
                     }
@@ -138,7 +186,7 @@ impl UI {
                 };
                 obj.draw(g, &opts, &ctx);
 
-                if self.primary.current_selection == Some(obj.get_id()) {
+                if per_map.current_selection == Some(obj.get_id()) {
                     g.draw_polygon(
                         self.cs.get_def("selected", Color::RED.alpha(0.7)),
                         &obj.get_outline(&ctx.map),
@@ -151,6 +199,9 @@ impl UI {
                     }
                 }
             }
+
+            draw_parking_lots(g, &ctx);
+            draw_bike_racks(g, &ctx);
         }
 
         if let Some(i) = sample_intersection {
@@ -168,6 +219,8 @@ impl UI {
         show_objs: &ShowObject,
         debug_mode: bool,
     ) -> Option<ID> {
+        let _span = ctx.profiler.span("recalculate_current_selection");
+
         // Unzoomed mode. Ignore when debugging areas and extra shapes.
         if ctx.canvas.cam_zoom < MIN_ZOOM_FOR_DETAIL && !debug_mode {
             return None;
@@ -177,6 +230,7 @@ impl UI {
 
         let mut cache = self.primary.draw_map.agents.borrow_mut();
         let mut objects = self.get_renderables_back_to_front(
+            &self.primary,
             Circle::new(pt, Distance::meters(3.0)).get_bounds(),
             ctx.prerender,
             &mut cache,
@@ -214,14 +268,15 @@ impl UI {
     // State does, like show_icons_for() and show().
     fn get_renderables_back_to_front<'a>(
         &'a self,
+        per_map: &'a PerMapUI,
         bounds: Bounds,
         prerender: &Prerender,
         agents: &'a mut AgentCache,
         source: &GetDrawAgents,
         show_objs: &ShowObject,
     ) -> Vec<&'a (dyn Renderable + 'a)> {
-        let map = &self.primary.map;
-        let draw_map = &self.primary.draw_map;
+        let map = &per_map.map;
+        let draw_map = &per_map.draw_map;
 
         let mut areas: Vec<&dyn Renderable> = Vec::new();
         let mut lanes: Vec<&dyn Renderable> = Vec::new();
@@ -241,6 +296,9 @@ impl UI {
                 ID::Lane(id) => {
                     lanes.push(draw_map.get_l(id));
                     agents_on.push(Traversable::Lane(id));
+                    for c in &map.get_l(id).midblock_crossings {
+                        agents_on.push(Traversable::MidblockCrossing(*c));
+                    }
                     for bs in &map.get_l(id).bus_stops {
                         bus_stops.push(draw_map.get_bs(*bs));
                     }
@@ -318,6 +376,70 @@ pub struct EditorState {
     pub cam_zoom: f64,
 }
 
+pub const SESSION_PATH: &str = "../data/player/session.json";
+
+// Where the player left off, so the splash screen can offer to pick up from there.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Session {
+    pub map_name: String,
+    pub edits_name: String,
+    pub mode: SessionMode,
+    pub cam_x: f64,
+    pub cam_y: f64,
+    pub cam_zoom: f64,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum SessionMode {
+    Sandbox,
+    Edit,
+    Debug,
+}
+
+impl Session {
+    pub fn save(&self, path: &str) {
+        abstutil::write_json(path, self).expect("Saving session failed");
+        println!("Saved {}", path);
+    }
+
+    // None if there's nothing to resume. A corrupt session, or one for a map or edits that don't
+    // exist anymore, gets noted and skipped.
+    pub fn load(path: &str) -> Option<Session> {
+        let session: Session = match abstutil::read_json(path) {
+            Ok(s) => s,
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return None;
+            }
+            Err(e) => {
+                println!("Ignoring the last session: {}", e);
+                return None;
+            }
+        };
+        let map_path = format!("../data/maps/{}.bin", session.map_name);
+        if !std::path::Path::new(&map_path).exists() {
+            println!(
+                "Ignoring the last session, since {} doesn't exist anymore",
+                map_path
+            );
+            return None;
+        }
+        if session.edits_name != "no_edits" {
+            let edits_path = format!(
+                "../data/edits/{}/{}.json",
+                session.map_name, session.edits_name
+            );
+            if !std::path::Path::new(&edits_path).exists() {
+                println!(
+                    "Ignoring the last session, since {} doesn't exist anymore",
+                    edits_path
+                );
+                return None;
+            }
+        }
+        Some(session)
+    }
+}
+
 pub struct ShowLayers {
     pub show_buildings: bool,
     pub show_intersections: bool,
@@ -394,6 +516,14 @@ pub struct Flags {
     /// Don't start with the splash screen and menu
     #[structopt(long = "no_splash")]
     pub no_splash: bool,
+
+    /// Show panels like the scenario description and scoreboard in a second window
+    #[structopt(long = "secondary_window")]
+    pub secondary_window: bool,
+
+    /// Draw as if the display had a hidpi factor of 1, ignoring what the window system reports
+    #[structopt(long = "ignore_hidpi")]
+    pub ignore_hidpi: bool,
 }
 
 // All of the state that's bound to a specific map+edit has to live here.
@@ -404,29 +534,65 @@ pub struct PerMapUI {
 
     pub current_selection: Option<ID>,
     pub current_flags: Flags,
+    // Everything the Timer warned about while loading
+    pub warnings: Vec<TimerWarning>,
+    // Kept up-to-date as map edits are applied
+    pub connectivity: ConnectivityReport,
+    // Notes left on this map, persisted separately from edits
+    pub annotations: Annotations,
 }
 
 impl PerMapUI {
-    pub fn new(flags: Flags, cs: &ColorScheme, ctx: &mut EventCtx, timer: &mut Timer) -> PerMapUI {
+    pub fn new(
+        mut flags: Flags,
+        cs: &ColorScheme,
+        ctx: &mut EventCtx,
+        timer: &mut Timer,
+    ) -> PerMapUI {
+        timer.collect_warnings();
         let mut mem = MeasureMemory::new();
         let (map, sim, _) = flags.sim_flags.load(Some(Duration::minutes(30)), timer);
         mem.reset("Map and Sim", timer);
+        // Remember the seed that was picked, so everything spawned later is reproducible too.
+        if flags.sim_flags.rng_seed.is_none() {
+            flags.sim_flags.rng_seed = sim.rng_seed();
+        }
 
         timer.start("draw_map");
         let draw_map = DrawMap::new(&map, &flags, cs, ctx.prerender, timer);
         timer.stop("draw_map");
         mem.reset("DrawMap", timer);
 
+        let connectivity = map.connectivity_audit();
+        let annotations = Annotations::load(map.get_name());
         PerMapUI {
             map,
             draw_map,
             sim,
             current_selection: None,
-            current_flags: flags.clone(),
+            current_flags: flags,
+            warnings: timer.take_warnings(),
+            connectivity,
+            annotations,
         }
     }
 
+    pub fn regenerate_draw_map(&mut self, cs: &ColorScheme, ctx: &mut EventCtx) {
+        let map = &self.map;
+        let flags = &self.current_flags;
+        self.draw_map = ctx.loading_screen("regenerate DrawMap", |ctx, mut timer| {
+            DrawMap::new(map, flags, cs, ctx.prerender, &mut timer)
+        });
+    }
+
     pub fn reset_sim(&mut self) {
+        // Keep using the seed the old sim was picked with, if the flags didn't specify one.
+        let seed = self
+            .current_flags
+            .sim_flags
+            .rng_seed
+            .or_else(|| self.sim.rng_seed())
+            .unwrap_or(0);
         // TODO savestate_every gets lost
         self.sim = Sim::new(
             &self.map,
@@ -437,5 +603,6 @@ impl PerMapUI {
                 .unwrap_or_else(|| "unnamed".to_string()),
             None,
         );
+        self.current_flags.sim_flags.configure(&mut self.sim, seed);
     }
 }
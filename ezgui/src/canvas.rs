@@ -1,10 +1,12 @@
 use crate::screen_geom::ScreenRectangle;
+use crate::text::default_font;
 use crate::{ScreenPt, Text, UserInput};
 use geom::{Bounds, Pt2D};
-use glium_glyph::glyph_brush::rusttype::Scale;
+use glium_glyph::glyph_brush::rusttype::{Font, Scale};
 use glium_glyph::GlyphBrush;
-use std::cell::RefCell;
+use std::cell::{RefCell, RefMut};
 use std::collections::HashMap;
+use std::time::Instant;
 
 const ZOOM_SPEED: f64 = 0.1;
 
@@ -22,10 +24,14 @@ pub struct Canvas {
 
     left_mouse_drag_from: Option<ScreenPt>,
 
+    // Physical pixels, like everything else in screen-space.
     pub window_width: f64,
     pub window_height: f64,
+    // Physical pixels per logical pixel. Sizes meant for a normal display, like widget
+    // dimensions and font sizes, get multiplied by this.
+    pub(crate) hidpi_factor: f64,
 
-    pub(crate) glyphs: RefCell<GlyphBrush<'static, 'static>>,
+    pub(crate) glyphs: Glyphs,
     line_height_per_font_size: RefCell<HashMap<usize, f64>>,
 
     // TODO Bit weird and hacky to mutate inside of draw() calls.
@@ -33,7 +39,17 @@ pub struct Canvas {
 
     // Kind of just ezgui state awkwardly stuck here...
     pub(crate) hide_modal_menus: bool,
-    pub(crate) lctrl_held: bool,
+    pub lctrl_held: bool,
+    // Only set on the primary window's canvas, while the secondary window is open.
+    pub(crate) secondary_window: bool,
+    // Something onscreen changes at this time, even if no input arrives.
+    redraw_at: Option<Instant>,
+}
+
+// A GlyphBrush needs a GL context. Without one, text can still be measured, just not drawn.
+pub(crate) enum Glyphs {
+    Gpu(RefCell<GlyphBrush<'static, 'static>>),
+    Headless(Font<'static>),
 }
 
 impl Canvas {
@@ -42,6 +58,22 @@ impl Canvas {
         initial_height: f64,
         glyphs: GlyphBrush<'static, 'static>,
     ) -> Canvas {
+        Canvas::with_glyphs(
+            initial_width,
+            initial_height,
+            Glyphs::Gpu(RefCell::new(glyphs)),
+        )
+    }
+
+    pub(crate) fn new_headless(initial_width: f64, initial_height: f64) -> Canvas {
+        Canvas::with_glyphs(
+            initial_width,
+            initial_height,
+            Glyphs::Headless(default_font()),
+        )
+    }
+
+    fn with_glyphs(initial_width: f64, initial_height: f64, glyphs: Glyphs) -> Canvas {
         Canvas {
             cam_x: 0.0,
             cam_y: 0.0,
@@ -54,16 +86,54 @@ impl Canvas {
             left_mouse_drag_from: None,
             window_width: initial_width,
             window_height: initial_height,
+            hidpi_factor: 1.0,
 
-            glyphs: RefCell::new(glyphs),
+            glyphs,
             line_height_per_font_size: RefCell::new(HashMap::new()),
             covered_areas: RefCell::new(Vec::new()),
 
             hide_modal_menus: false,
             lctrl_held: false,
+            secondary_window: false,
+            redraw_at: None,
+        }
+    }
+
+    pub(crate) fn request_redraw_at(&mut self, at: Instant) {
+        if self.redraw_at.map(|t| at < t).unwrap_or(true) {
+            self.redraw_at = Some(at);
+        }
+    }
+
+    // True once a requested redraw is due, then forgets about the request.
+    pub(crate) fn redraw_due(&mut self) -> bool {
+        match self.redraw_at {
+            Some(t) if Instant::now() >= t => {
+                self.redraw_at = None;
+                true
+            }
+            _ => false,
         }
     }
 
+    // If true, the GUI should draw panels into the secondary window instead of overlaying them
+    // here.
+    pub fn has_secondary_window(&self) -> bool {
+        self.secondary_window
+    }
+
+    pub fn hidpi_factor(&self) -> f64 {
+        self.hidpi_factor
+    }
+
+    // Normally the window system reports this. The window keeps its logical size, so its physical
+    // dimensions change.
+    pub fn set_hidpi_factor(&mut self, factor: f64) {
+        self.window_width *= factor / self.hidpi_factor;
+        self.window_height *= factor / self.hidpi_factor;
+        self.hidpi_factor = factor;
+    }
+
     pub(crate) fn is_dragging(&self) -> bool {
         self.left_mouse_drag_from.is_some()
     }
@@ -79,8 +149,12 @@ impl Canvas {
                 self.left_mouse_drag_from = Some(pt);
             }
         }
-        // Can't start dragging on top of covered area
-        if input.left_mouse_button_pressed() && self.get_cursor_in_map_space().is_some() {
+        // Can't start dragging on top of covered area. Dragging while holding control is left for
+        // the GUI, like selecting a rectangle.
+        if input.left_mouse_button_pressed()
+            && !self.lctrl_held
+            && self.get_cursor_in_map_space().is_some()
+        {
             self.left_mouse_drag_from = Some(self.get_cursor_in_screen_space());
         }
         if input.left_mouse_button_released() {
@@ -119,7 +193,9 @@ impl Canvas {
         self.cam_y = ((self.cam_zoom / old_zoom) * (self.cursor_y + self.cam_y)) - self.cursor_y;
     }
 
-    pub(crate) fn get_cursor_in_screen_space(&self) -> ScreenPt {
+    // Physical pixels. Events from the window system are converted before they get here, so this
+    // agrees with screen_to_map and everything drawn in screen-space.
+    pub fn get_cursor_in_screen_space(&self) -> ScreenPt {
         ScreenPt::new(self.cursor_x, self.cursor_y)
     }
 
@@ -177,18 +253,34 @@ impl Canvas {
         txt.dims(self)
     }
 
-    // Don't call this while glyphs are mutably borrowed.
+    // In physical pixels, so already scaled by the hidpi factor. Don't call this while glyphs are
+    // mutably borrowed.
     pub(crate) fn line_height(&self, font_size: usize) -> f64 {
+        self.hidpi_factor * self.unscaled_line_height(font_size)
+    }
+
+    fn unscaled_line_height(&self, font_size: usize) -> f64 {
         let mut hash = self.line_height_per_font_size.borrow_mut();
         if hash.contains_key(&font_size) {
             return hash[&font_size];
         }
-        let vmetrics = self.glyphs.borrow().fonts()[0].v_metrics(Scale::uniform(font_size as f32));
+        let scale = Scale::uniform(font_size as f32);
+        let vmetrics = match self.glyphs {
+            Glyphs::Gpu(ref brush) => brush.borrow().fonts()[0].v_metrics(scale),
+            Glyphs::Headless(ref font) => font.v_metrics(scale),
+        };
         // TODO This works for this font, but could be more paranoid with abs()
         let line_height = f64::from(vmetrics.ascent - vmetrics.descent + vmetrics.line_gap);
         hash.insert(font_size, line_height);
         line_height
     }
+
+    pub(crate) fn glyph_brush(&self) -> RefMut<GlyphBrush<'static, 'static>> {
+        match self.glyphs {
+            Glyphs::Gpu(ref brush) => brush.borrow_mut(),
+            Glyphs::Headless(_) => panic!("Can't draw text on a headless Canvas"),
+        }
+    }
 }
 
 pub enum HorizontalAlignment {
@@ -1,7 +1,7 @@
 use crate::input::ContextMenu;
 use crate::{
-    text, Canvas, Color, Drawable, HorizontalAlignment, Key, Prerender, ScreenPt, Text,
-    VerticalAlignment,
+    text, Canvas, Color, Drawable, HorizontalAlignment, Key, Prerender, ScreenPt, ScreenRectangle,
+    Text, VerticalAlignment,
 };
 use geom::{Bounds, Circle, Distance, Line, Polygon, Pt2D};
 use glium::{uniform, Surface};
@@ -34,6 +34,8 @@ pub struct GfxCtx<'a> {
 
     pub num_draw_calls: usize,
     hatching: f32,
+    // Shifts the camera while drawing into a viewport
+    viewport_offset: ScreenPt,
 }
 
 impl<'a> GfxCtx<'a> {
@@ -69,6 +71,7 @@ impl<'a> GfxCtx<'a> {
             naming_hint: None,
             context_menu,
             hatching: NO_HATCHING,
+            viewport_offset: ScreenPt::new(0.0, 0.0),
         }
     }
 
@@ -94,16 +97,48 @@ impl<'a> GfxCtx<'a> {
     }
 
     pub fn unfork(&mut self) {
+        let cam_x = self.canvas.cam_x - self.viewport_offset.x;
+        let cam_y = self.canvas.cam_y - self.viewport_offset.y;
         self.uniforms = uniform! {
-            transform: [self.canvas.cam_x as f32, self.canvas.cam_y as f32, self.canvas.cam_zoom as f32],
+            transform: [cam_x as f32, cam_y as f32, self.canvas.cam_zoom as f32],
             window: [self.canvas.window_width as f32, self.canvas.window_height as f32, self.hatching],
         };
     }
 
+    // Until disable_viewport(), only draw inside this part of the screen, with the camera shifted
+    // by some screen-space offset. This lets different halves of the window show different
+    // things.
+    // TODO Text is neither clipped nor shifted, and Canvas doesn't know about the offset either.
+    pub fn enable_viewport(&mut self, rect: &ScreenRectangle, offset: ScreenPt) {
+        // The scissor rectangle is in physical pixels, starting from the bottom-left.
+        let (width, height) = self.target.get_dimensions();
+        let scale = f64::from(width) / self.canvas.window_width;
+        self.params.scissor = Some(glium::Rect {
+            left: (rect.x1 * scale).max(0.0) as u32,
+            bottom: (f64::from(height) - rect.y2 * scale).max(0.0) as u32,
+            width: ((rect.x2 - rect.x1) * scale).max(0.0) as u32,
+            height: ((rect.y2 - rect.y1) * scale).max(0.0) as u32,
+        });
+        self.viewport_offset = offset;
+        self.unfork();
+    }
+
+    pub fn disable_viewport(&mut self) {
+        self.params.scissor = None;
+        self.viewport_offset = ScreenPt::new(0.0, 0.0);
+        self.unfork();
+    }
+
+    // Only clears the current viewport, if there is one.
     pub fn clear(&mut self, color: Color) {
         // Without this, SRGB gets enabled and post-processes the color from the fragment shader.
-        self.target
-            .clear_color_srgb(color.0[0], color.0[1], color.0[2], color.0[3]);
+        self.target.clear(
+            self.params.scissor.as_ref(),
+            Some((color.0[0], color.0[1], color.0[2], color.0[3])),
+            true,
+            None,
+            None,
+        );
     }
 
     pub fn draw_line(&mut self, color: Color, thickness: Distance, line: &Line) {
@@ -141,11 +176,21 @@ impl<'a> GfxCtx<'a> {
         self.redraw(&obj);
     }
 
+    // Like GeomBatch::draw, for polygons that are kept around between frames
+    pub fn draw_polygon_batch(&mut self, list: Vec<(Color, &Polygon)>) {
+        let obj = self.prerender.upload_temporary(list);
+        self.redraw(&obj);
+    }
+
     pub fn redraw(&mut self, obj: &Drawable) {
+        let (vertex_buffer, index_buffer) = obj
+            .buffers
+            .as_ref()
+            .expect("Drawable was uploaded headless");
         self.target
             .draw(
-                &obj.vertex_buffer,
-                &obj.index_buffer,
+                vertex_buffer,
+                index_buffer,
                 &self.program,
                 &self.uniforms,
                 &self.params,
@@ -223,6 +268,10 @@ impl<'a> GfxCtx<'a> {
 
     pub fn draw_text_at_mapspace(&mut self, txt: &Text, map_pt: Pt2D) {
         let (width, height) = self.text_dims(&txt);
+        let (width, height) = (
+            width / self.canvas.hidpi_factor,
+            height / self.canvas.hidpi_factor,
+        );
         text::draw_text_bubble_mapspace(
             self,
             Pt2D::new(map_pt.x() - (width / 2.0), map_pt.y() - (height / 2.0)),
@@ -304,6 +353,10 @@ impl GeomBatch {
         self.list.extend(other.list.clone());
     }
 
+    pub fn consume(self) -> Vec<(Color, Polygon)> {
+        self.list
+    }
+
     pub fn draw(self, g: &mut GfxCtx) {
         let refs = self.list.iter().map(|(color, p)| (*color, p)).collect();
         let obj = g.prerender.upload_temporary(refs);
@@ -25,7 +25,8 @@ pub enum Event {
 }
 
 impl Event {
-    pub fn from_glutin_event(ev: glutin::WindowEvent) -> Option<Event> {
+    // glutin reports positions and sizes in logical pixels, but screen-space is physical.
+    pub fn from_glutin_event(ev: glutin::WindowEvent, hidpi_factor: f64) -> Option<Event> {
         match ev {
             glutin::WindowEvent::MouseInput { state, button, .. } => match (button, state) {
                 (glutin::MouseButton::Left, glutin::ElementState::Pressed) => {
@@ -54,7 +55,8 @@ impl Event {
                 }
             }
             glutin::WindowEvent::CursorMoved { position, .. } => {
-                Some(Event::MouseMovedTo(ScreenPt::new(position.x, position.y)))
+                let pos = position.to_physical(hidpi_factor);
+                Some(Event::MouseMovedTo(ScreenPt::new(pos.x, pos.y)))
             }
             glutin::WindowEvent::MouseWheel { delta, .. } => match delta {
                 glutin::MouseScrollDelta::LineDelta(_, dy) => {
@@ -68,6 +70,7 @@ impl Event {
                 }
             },
             glutin::WindowEvent::Resized(size) => {
+                let size = size.to_physical(hidpi_factor);
                 Some(Event::WindowResized(size.width, size.height))
             }
             glutin::WindowEvent::Focused(gained) => Some(if gained {
@@ -138,6 +141,7 @@ pub enum Key {
     Enter,
     Tab,
     Backspace,
+    Delete,
     LeftShift,
     LeftControl,
     RightControl,
@@ -147,6 +151,8 @@ pub enum Key {
     RightArrow,
     UpArrow,
     DownArrow,
+    PageUp,
+    PageDown,
     F1,
     F2,
     F3,
@@ -213,6 +219,7 @@ impl Key {
             | Key::Enter
             | Key::Tab
             | Key::Backspace
+            | Key::Delete
             | Key::LeftShift
             | Key::LeftControl
             | Key::RightControl
@@ -222,6 +229,8 @@ impl Key {
             | Key::RightArrow
             | Key::UpArrow
             | Key::DownArrow
+            | Key::PageUp
+            | Key::PageDown
             | Key::F1
             | Key::F2
             | Key::F3
@@ -243,6 +252,7 @@ impl Key {
             Key::Enter => "Enter".to_string(),
             Key::Tab => "Tab".to_string(),
             Key::Backspace => "Backspace".to_string(),
+            Key::Delete => "Delete".to_string(),
             Key::LeftShift => "Shift".to_string(),
             Key::LeftControl => "left Control".to_string(),
             Key::RightControl => "right Control".to_string(),
@@ -252,6 +262,8 @@ impl Key {
             Key::RightArrow => "→ arrow".to_string(),
             Key::UpArrow => "↑".to_string(),
             Key::DownArrow => "↓".to_string(),
+            Key::PageUp => "Page Up".to_string(),
+            Key::PageDown => "Page Down".to_string(),
             Key::F1 => "F1".to_string(),
             Key::F2 => "F2".to_string(),
             Key::F3 => "F3".to_string(),
@@ -322,6 +334,7 @@ impl Key {
             glutin::VirtualKeyCode::Return => Key::Enter,
             glutin::VirtualKeyCode::Tab => Key::Tab,
             glutin::VirtualKeyCode::Back => Key::Backspace,
+            glutin::VirtualKeyCode::Delete => Key::Delete,
             glutin::VirtualKeyCode::LShift => Key::LeftShift,
             glutin::VirtualKeyCode::LControl => Key::LeftControl,
             glutin::VirtualKeyCode::RControl => Key::RightControl,
@@ -331,6 +344,8 @@ impl Key {
             glutin::VirtualKeyCode::Right => Key::RightArrow,
             glutin::VirtualKeyCode::Up => Key::UpArrow,
             glutin::VirtualKeyCode::Down => Key::DownArrow,
+            glutin::VirtualKeyCode::PageUp => Key::PageUp,
+            glutin::VirtualKeyCode::PageDown => Key::PageDown,
             glutin::VirtualKeyCode::F1 => Key::F1,
             glutin::VirtualKeyCode::F2 => Key::F2,
             glutin::VirtualKeyCode::F3 => Key::F3,
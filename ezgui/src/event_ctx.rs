@@ -1,22 +1,20 @@
 use crate::input::ContextMenu;
-use crate::text::FONT_SIZE;
+use crate::profiler::Profiler;
+use crate::text::{default_font, FONT_SIZE};
 use crate::{
     Canvas, Color, GeomBatch, GfxCtx, HorizontalAlignment, Text, UserInput, VerticalAlignment,
 };
 use abstutil::{elapsed_seconds, Timer, TimerSink};
 use geom::Polygon;
 use glium::implement_vertex;
-use glium_glyph::glyph_brush::rusttype::Font;
-use glium_glyph::glyph_brush::rusttype::Scale;
 use glium_glyph::GlyphBrush;
 use std::cell::Cell;
 use std::collections::VecDeque;
 use std::time::Instant;
 
-// Something that's been sent to the GPU already.
+// Something that's been sent to the GPU already. Headless, there's no GPU, so there's nothing.
 pub struct Drawable {
-    pub(crate) vertex_buffer: glium::VertexBuffer<Vertex>,
-    pub(crate) index_buffer: glium::IndexBuffer<u32>,
+    pub(crate) buffers: Option<(glium::VertexBuffer<Vertex>, glium::IndexBuffer<u32>)>,
 }
 
 #[derive(Copy, Clone)]
@@ -31,7 +29,8 @@ implement_vertex!(Vertex, position, color);
 
 // TODO Don't expose this directly
 pub struct Prerender<'a> {
-    pub(crate) display: &'a glium::Display,
+    // None when running headless
+    pub(crate) display: Option<&'a glium::Display>,
     pub(crate) num_uploads: Cell<usize>,
     // TODO Prerender doesn't know what things are temporary and permanent. Could make the API more
     // detailed (and use the corresponding persistent glium types).
@@ -52,12 +51,22 @@ impl<'a> Prerender<'a> {
         self.total_bytes_uploaded.get()
     }
 
+    pub(crate) fn display(&self) -> &'a glium::Display {
+        self.display.expect("Headless Prerender has no display")
+    }
+
     pub(crate) fn upload_temporary(&self, list: Vec<(Color, &Polygon)>) -> Drawable {
         self.actually_upload(false, list)
     }
 
     fn actually_upload(&self, permanent: bool, list: Vec<(Color, &Polygon)>) -> Drawable {
         self.num_uploads.set(self.num_uploads.get() + 1);
+        let display = match self.display {
+            Some(d) => d,
+            None => {
+                return Drawable { buffers: None };
+            }
+        };
 
         let mut vertices: Vec<Vertex> = Vec::new();
         let mut indices: Vec<u32> = Vec::new();
@@ -82,20 +91,20 @@ impl<'a> Prerender<'a> {
         }
 
         let vertex_buffer = if permanent {
-            glium::VertexBuffer::immutable(self.display, &vertices).unwrap()
+            glium::VertexBuffer::immutable(display, &vertices).unwrap()
         } else {
-            glium::VertexBuffer::new(self.display, &vertices).unwrap()
+            glium::VertexBuffer::new(display, &vertices).unwrap()
         };
         let index_buffer = if permanent {
             glium::IndexBuffer::immutable(
-                self.display,
+                display,
                 glium::index::PrimitiveType::TrianglesList,
                 &indices,
             )
             .unwrap()
         } else {
             glium::IndexBuffer::new(
-                self.display,
+                display,
                 glium::index::PrimitiveType::TrianglesList,
                 &indices,
             )
@@ -111,8 +120,7 @@ impl<'a> Prerender<'a> {
         }
 
         Drawable {
-            vertex_buffer,
-            index_buffer,
+            buffers: Some((vertex_buffer, index_buffer)),
         }
     }
 }
@@ -123,7 +131,11 @@ pub struct EventCtx<'a> {
     pub canvas: &'a mut Canvas,
     pub prerender: &'a Prerender<'a>,
 
-    pub(crate) program: &'a glium::Program,
+    // For timing custom spans in the profiling overlay
+    pub profiler: &'a Profiler,
+
+    // None when running headless
+    pub(crate) program: Option<&'a glium::Program>,
 }
 
 impl<'a> EventCtx<'a> {
@@ -132,16 +144,21 @@ impl<'a> EventCtx<'a> {
         timer_name: &str,
         f: F,
     ) -> O {
-        let mut timer = Timer::new_with_sink(
-            timer_name,
-            Box::new(LoadingScreen::new(
-                self.prerender,
-                self.program,
-                self.canvas.window_width,
-                self.canvas.window_height,
-                timer_name.to_string(),
-            )),
-        );
+        let mut timer = if let Some(program) = self.program {
+            Timer::new_with_sink(
+                timer_name,
+                Box::new(LoadingScreen::new(
+                    self.prerender,
+                    program,
+                    self.canvas.window_width,
+                    self.canvas.window_height,
+                    self.canvas.hidpi_factor,
+                    timer_name.to_string(),
+                )),
+            )
+        } else {
+            Timer::new(timer_name)
+        };
         f(self, &mut timer)
     }
 
@@ -167,16 +184,14 @@ impl<'a> LoadingScreen<'a> {
         program: &'a glium::Program,
         initial_width: f64,
         initial_height: f64,
+        hidpi_factor: f64,
         title: String,
     ) -> LoadingScreen<'a> {
         // TODO Ew! Expensive and wacky. Fix by not storing GlyphBrush in Canvas at all.
-        let dejavu: &[u8] = include_bytes!("assets/DejaVuSans.ttf");
-        let glyphs = GlyphBrush::new(prerender.display, vec![Font::from_bytes(dejavu).unwrap()]);
-        let canvas = Canvas::new(initial_width, initial_height, glyphs);
-        // TODO Dupe code
-        let vmetrics =
-            canvas.glyphs.borrow().fonts()[0].v_metrics(Scale::uniform(FONT_SIZE as f32));
-        let line_height = f64::from(vmetrics.ascent - vmetrics.descent + vmetrics.line_gap);
+        let glyphs = GlyphBrush::new(prerender.display(), vec![default_font()]);
+        let mut canvas = Canvas::new(initial_width, initial_height, glyphs);
+        canvas.hidpi_factor = hidpi_factor;
+        let line_height = canvas.line_height(FONT_SIZE);
 
         LoadingScreen {
             canvas,
@@ -203,12 +218,12 @@ impl<'a> LoadingScreen<'a> {
             txt.add_line(l.to_string());
         }
 
-        let mut target = self.prerender.display.draw();
+        let mut target = self.prerender.display().draw();
         let context_menu = ContextMenu::new();
         let mut g = GfxCtx::new(
             &self.canvas,
             self.prerender,
-            self.prerender.display,
+            self.prerender.display(),
             &mut target,
             self.program,
             &context_menu,
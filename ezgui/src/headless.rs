@@ -0,0 +1,94 @@
+use crate::input::ContextMenu;
+use crate::profiler::Profiler;
+use crate::runner::State;
+use crate::{Canvas, Event, EventCtx, EventLoopMode, Key, Prerender, UserInput, GUI};
+use geom::Pt2D;
+use std::cell::Cell;
+
+// Drives a GUI with synthetic events and no window, so tests can exercise event handling. Nothing
+// is ever drawn; that needs a GL context.
+pub struct Headless<G: GUI> {
+    // Only None in the middle of an event
+    state: Option<State<G>>,
+    prerender: Prerender<'static>,
+}
+
+impl<G: GUI> Headless<G> {
+    pub fn new<F: FnOnce(&mut EventCtx) -> G>(
+        initial_width: f64,
+        initial_height: f64,
+        make_gui: F,
+    ) -> Headless<G> {
+        let mut canvas = Canvas::new_headless(initial_width, initial_height);
+        let prerender = Prerender {
+            display: None,
+            num_uploads: Cell::new(0),
+            total_bytes_uploaded: Cell::new(0),
+        };
+        let profiler = Profiler::new();
+        let gui = make_gui(&mut EventCtx {
+            input: &mut UserInput::new(Event::NoOp, ContextMenu::new(), &mut canvas),
+            canvas: &mut canvas,
+            prerender: &prerender,
+            program: None,
+            profiler: &profiler,
+        });
+
+        let mut headless = Headless {
+            state: Some(State {
+                gui,
+                canvas,
+                context_menu: ContextMenu::new(),
+                profiler,
+            }),
+            prerender,
+        };
+        // Like the first frame of the real event loop, so menus learn what actions are possible
+        headless.event(Event::NoOp);
+        headless
+    }
+
+    pub fn event(&mut self, ev: Event) -> EventLoopMode {
+        let (mut state, mode, input_used) =
+            self.state.take().unwrap().event(ev, &self.prerender, None);
+        // The runner does the same; menus need to recalculate what's active after a change.
+        if input_used {
+            state = state.event(Event::NoOp, &self.prerender, None).0;
+        }
+        self.state = Some(state);
+        mode
+    }
+
+    pub fn press_key(&mut self, key: Key) {
+        self.event(Event::KeyPress(key));
+        self.event(Event::KeyRelease(key));
+    }
+
+    pub fn press_lctrl(&mut self, key: Key) {
+        self.event(Event::KeyPress(Key::LeftControl));
+        self.press_key(key);
+        self.event(Event::KeyRelease(Key::LeftControl));
+    }
+
+    // Move the mouse to wherever this point currently is onscreen.
+    pub fn hover(&mut self, pt: Pt2D) {
+        let screen_pt = self.canvas().map_to_screen(pt);
+        self.event(Event::MouseMovedTo(screen_pt));
+    }
+
+    pub fn gui(&self) -> &G {
+        &self.state.as_ref().unwrap().gui
+    }
+
+    pub fn gui_mut(&mut self) -> &mut G {
+        &mut self.state.as_mut().unwrap().gui
+    }
+
+    pub fn canvas(&self) -> &Canvas {
+        &self.state.as_ref().unwrap().canvas
+    }
+
+    pub fn canvas_mut(&mut self) -> &mut Canvas {
+        &mut self.state.as_mut().unwrap().canvas
+    }
+}
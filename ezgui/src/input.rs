@@ -21,7 +21,8 @@ pub struct UserInput {
 
 pub enum ContextMenu {
     Inactive(BTreeSet<Key>),
-    Building(ScreenPt, BTreeMap<Key, String>),
+    // Each action might have some help text
+    Building(ScreenPt, BTreeMap<Key, (String, Option<String>)>),
     Displaying(Menu<Key>),
     Clicked(Key),
 }
@@ -37,17 +38,20 @@ impl ContextMenu {
                 if actions.is_empty() {
                     ContextMenu::new()
                 } else {
-                    ContextMenu::Displaying(Menu::new(
+                    let tooltips = actions.values().map(|(_, help)| help.clone()).collect();
+                    let mut menu = Menu::new(
                         Text::new(),
                         actions
                             .into_iter()
-                            .map(|(key, action)| (hotkey(key), action, key))
+                            .map(|(key, (action, _))| (hotkey(key), action, key))
                             .collect(),
                         false,
                         false,
                         Position::SomeCornerAt(origin),
                         canvas,
-                    ))
+                    );
+                    menu.set_tooltips(tooltips);
+                    ContextMenu::Displaying(menu)
                 }
             }
             _ => self,
@@ -132,6 +136,16 @@ impl UserInput {
     }
 
     pub fn contextual_action(&mut self, hotkey: Key, action: &str) -> bool {
+        self.contextual_action_with_help(hotkey, action, None)
+    }
+
+    // The help text shows up when the mouse rests on the action in the context menu.
+    pub fn contextual_action_with_help(
+        &mut self,
+        hotkey: Key,
+        action: &str,
+        help: Option<&str>,
+    ) -> bool {
         match self.context_menu {
             ContextMenu::Inactive(ref mut keys) => {
                 // If the menu's not active (the user hasn't right-clicked yet), then still allow the
@@ -142,7 +156,7 @@ impl UserInput {
             ContextMenu::Building(_, ref mut actions) => {
                 // The event this round was the right click, so don't check if the right keypress
                 // happened.
-                if let Some(prev_action) = actions.get(&hotkey) {
+                if let Some((prev_action, _)) = actions.get(&hotkey) {
                     if prev_action != action {
                         panic!(
                             "Context menu uses hotkey {:?} for both {} and {}",
@@ -150,7 +164,7 @@ impl UserInput {
                         );
                     }
                 } else {
-                    actions.insert(hotkey, action.to_string());
+                    actions.insert(hotkey, (action.to_string(), help.map(|h| h.to_string())));
                 }
             }
             ContextMenu::Displaying(_) => {
@@ -204,13 +218,13 @@ impl UserInput {
     }
 
     // No consuming for these?
-    pub(crate) fn left_mouse_button_pressed(&mut self) -> bool {
+    pub fn left_mouse_button_pressed(&mut self) -> bool {
         if self.context_menu_active() {
             return false;
         }
         self.event == Event::LeftMouseButtonDown
     }
-    pub(crate) fn left_mouse_button_released(&mut self) -> bool {
+    pub fn left_mouse_button_released(&mut self) -> bool {
         if self.context_menu_active() {
             return false;
         }
@@ -3,7 +3,9 @@ mod color;
 mod drawing;
 mod event;
 mod event_ctx;
+mod headless;
 mod input;
+mod profiler;
 mod runner;
 mod screen_geom;
 mod text;
@@ -14,13 +16,15 @@ pub use crate::color::Color;
 pub use crate::drawing::{GeomBatch, GfxCtx};
 pub use crate::event::{hotkey, lctrl, Event, Key, MultiKey};
 pub use crate::event_ctx::{Drawable, EventCtx, Prerender};
+pub use crate::headless::Headless;
 pub use crate::input::UserInput;
-pub use crate::runner::{run, EventLoopMode, GUI};
-pub use crate::screen_geom::ScreenPt;
+pub use crate::profiler::{Profiler, Span};
+pub use crate::runner::{run, run_with_secondary_window, EventLoopMode, GUI};
+pub use crate::screen_geom::{ScreenPt, ScreenRectangle};
 pub use crate::text::{Text, HOTKEY_COLOR};
 pub use crate::widgets::{
-    Autocomplete, ItemSlider, LogScroller, ModalMenu, ScrollingMenu, Slider, TextBox, Warper,
-    WarpingItemSlider, Wizard, WrappedWizard,
+    Autocomplete, ItemSlider, LogScroller, Minimap, ModalMenu, ScrollingMenu, Slider, TextBox,
+    Warper, WarpingItemSlider, Wizard, WrappedWizard,
 };
 
 pub enum InputResult<T: Clone> {
@@ -0,0 +1,187 @@
+use crate::{Color, GfxCtx, HorizontalAlignment, Text, VerticalAlignment};
+use abstutil::{elapsed_seconds, prettyprint_usize};
+use geom::{Distance, Polygon, Pt2D};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+// About 4 seconds at the normal framerate
+const NUM_FRAMES: usize = 120;
+// Frames taking longer than this are drawn in red. Matches the runner's sleep between frames.
+const FRAME_BUDGET: f64 = 0.033;
+const BAR_WIDTH: f64 = 2.0;
+// A bar this tall means twice the frame budget; anything slower gets clipped.
+const GRAPH_HEIGHT: f64 = 100.0;
+const PADDING: f64 = 10.0;
+
+// Everything is in seconds of wall-clock time.
+#[derive(Default)]
+struct Frame {
+    // Time since the previous frame was drawn, including sleeping
+    total: f64,
+    // Summed over every GUI::event call in the frame
+    event: f64,
+    draw: f64,
+    bytes_uploaded: usize,
+    spans: Vec<(&'static str, f64)>,
+}
+
+// Nothing is measured while the overlay is hidden. The GUI gets a shared reference through
+// EventCtx, so recording uses interior mutability.
+pub struct Profiler {
+    enabled: Cell<bool>,
+    current: RefCell<Frame>,
+    history: RefCell<VecDeque<Frame>>,
+}
+
+impl Profiler {
+    pub(crate) fn new() -> Profiler {
+        Profiler {
+            enabled: Cell::new(false),
+            current: RefCell::new(Frame::default()),
+            history: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    pub(crate) fn toggle(&self) {
+        self.enabled.set(!self.enabled.get());
+        *self.current.borrow_mut() = Frame::default();
+        self.history.borrow_mut().clear();
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    // Times everything until the returned guard is dropped. Usage:
+    //     let _span = ctx.profiler.span("recalculate_current_selection");
+    pub fn span(&self, name: &'static str) -> Span {
+        Span {
+            profiler: self,
+            name,
+            started: if self.enabled.get() {
+                Some(Instant::now())
+            } else {
+                None
+            },
+        }
+    }
+
+    pub(crate) fn record_event(&self, seconds: f64) {
+        if self.enabled.get() {
+            self.current.borrow_mut().event += seconds;
+        }
+    }
+
+    pub(crate) fn record_draw(&self, seconds: f64) {
+        if self.enabled.get() {
+            self.current.borrow_mut().draw += seconds;
+        }
+    }
+
+    pub(crate) fn end_frame(&self, total_seconds: f64, bytes_uploaded: usize) {
+        if !self.enabled.get() {
+            return;
+        }
+        let mut frame = self.current.replace(Frame::default());
+        frame.total = total_seconds;
+        frame.bytes_uploaded = bytes_uploaded;
+
+        let mut history = self.history.borrow_mut();
+        if history.len() == NUM_FRAMES {
+            history.pop_front();
+        }
+        history.push_back(frame);
+    }
+
+    pub(crate) fn draw(&self, g: &mut GfxCtx) {
+        let history = self.history.borrow();
+        if history.is_empty() {
+            return;
+        }
+
+        let total: f64 = history.iter().map(|f| f.total).sum();
+        let mut txt = Text::new();
+        if total > 0.0 {
+            txt.add_line(format!("{:.1} FPS", (history.len() as f64) / total));
+        }
+        let last = history.back().unwrap();
+        describe_frame(&mut txt, "Last frame", last);
+        let worst = history
+            .iter()
+            .max_by(|a, b| a.total.partial_cmp(&b.total).unwrap())
+            .unwrap();
+        describe_frame(&mut txt, "Worst frame", worst);
+        g.draw_blocking_text(&txt, (HorizontalAlignment::Right, VerticalAlignment::Top));
+
+        // One bar per frame, oldest on the left, along the bottom-right corner
+        g.fork_screenspace();
+        let scale = g.canvas.hidpi_factor();
+        let (bar_width, graph_height) = (scale * BAR_WIDTH, scale * GRAPH_HEIGHT);
+        let left = g.canvas.window_width - scale * PADDING - (NUM_FRAMES as f64) * bar_width;
+        let bottom = g.canvas.window_height - scale * PADDING;
+        g.draw_polygon(
+            Color::grey(0.2).alpha(0.8),
+            &Polygon::rectangle_topleft(
+                Pt2D::new(left, bottom - graph_height),
+                Distance::meters((NUM_FRAMES as f64) * bar_width),
+                Distance::meters(graph_height),
+            ),
+        );
+        let mut fast_bars = Vec::new();
+        let mut slow_bars = Vec::new();
+        for (idx, frame) in history.iter().enumerate() {
+            let height = (frame.total / (2.0 * FRAME_BUDGET)).min(1.0) * graph_height;
+            if height <= 0.0 {
+                continue;
+            }
+            let bar = Polygon::rectangle_topleft(
+                Pt2D::new(left + (idx as f64) * bar_width, bottom - height),
+                Distance::meters(bar_width),
+                Distance::meters(height),
+            );
+            if frame.total > FRAME_BUDGET {
+                slow_bars.push(bar);
+            } else {
+                fast_bars.push(bar);
+            }
+        }
+        for (color, bars) in vec![(Color::GREEN, fast_bars), (Color::RED, slow_bars)] {
+            if !bars.is_empty() {
+                g.draw_polygons(color, &bars);
+            }
+        }
+        g.unfork();
+    }
+}
+
+fn describe_frame(txt: &mut Text, label: &str, frame: &Frame) {
+    txt.add_line(format!("{}: {:.1}ms", label, frame.total * 1000.0));
+    txt.add_line(format!(
+        "  event: {:.1}ms, draw: {:.1}ms, uploaded {} bytes",
+        frame.event * 1000.0,
+        frame.draw * 1000.0,
+        prettyprint_usize(frame.bytes_uploaded)
+    ));
+    for (name, seconds) in &frame.spans {
+        txt.add_line(format!("  {}: {:.1}ms", name, seconds * 1000.0));
+    }
+}
+
+pub struct Span<'a> {
+    profiler: &'a Profiler,
+    name: &'static str,
+    started: Option<Instant>,
+}
+
+impl<'a> Drop for Span<'a> {
+    fn drop(&mut self) {
+        if let Some(started) = self.started {
+            self.profiler
+                .current
+                .borrow_mut()
+                .spans
+                .push((self.name, elapsed_seconds(started)));
+        }
+    }
+}
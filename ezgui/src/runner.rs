@@ -1,7 +1,9 @@
 use crate::input::ContextMenu;
-use crate::{widgets, Canvas, Event, EventCtx, GfxCtx, Prerender, UserInput};
+use crate::profiler::Profiler;
+use crate::text::default_font;
+use crate::{widgets, Canvas, Event, EventCtx, GfxCtx, Key, Prerender, UserInput};
+use abstutil::elapsed_seconds;
 use glium::glutin;
-use glium_glyph::glyph_brush::rusttype::Font;
 use glium_glyph::GlyphBrush;
 use std::cell::Cell;
 use std::time::{Duration, Instant};
@@ -21,6 +23,12 @@ pub trait GUI {
     fn profiling_enabled(&self) -> bool {
         false
     }
+
+    // Only called when running with a secondary window, for events while it's focused.
+    fn secondary_event(&mut self, _ctx: &mut EventCtx) {}
+    // Only called when running with a secondary window. Anything drawn here has to be uploaded
+    // through this GfxCtx's prerender; the windows don't share GPU state.
+    fn draw_secondary(&self, _g: &mut GfxCtx) {}
 }
 
 #[derive(Clone, PartialEq)]
@@ -36,19 +44,28 @@ pub enum EventLoopMode {
     ScreenCaptureCurrentShot,
 }
 
+// A second OS window sharing the event loop. It has its own GL context, so it needs its own
+// program and glyphs.
+struct SecondaryWindow<'a> {
+    canvas: Canvas,
+    program: glium::Program,
+    prerender: Prerender<'a>,
+}
+
 pub(crate) struct State<G: GUI> {
     pub(crate) gui: G,
     pub(crate) canvas: Canvas,
-    context_menu: ContextMenu,
+    pub(crate) context_menu: ContextMenu,
+    pub(crate) profiler: Profiler,
 }
 
 impl<G: GUI> State<G> {
     // The bool indicates if the input was actually used.
-    fn event(
+    pub(crate) fn event(
         mut self,
         ev: Event,
         prerender: &Prerender,
-        program: &glium::Program,
+        program: Option<&glium::Program>,
     ) -> (State<G>, EventLoopMode, bool) {
         // Clear out the possible keys
         if let ContextMenu::Inactive(_) = self.context_menu {
@@ -60,12 +77,15 @@ impl<G: GUI> State<G> {
         let mut input = UserInput::new(ev, self.context_menu, &mut self.canvas);
         let mut gui = self.gui;
         let mut canvas = self.canvas;
+        let profiler = &self.profiler;
+        let started_event = Instant::now();
         let event_mode = match panic::catch_unwind(panic::AssertUnwindSafe(|| {
             gui.event(&mut EventCtx {
                 input: &mut input,
                 canvas: &mut canvas,
                 prerender,
                 program,
+                profiler,
             })
         })) {
             Ok(pair) => pair,
@@ -74,6 +94,7 @@ impl<G: GUI> State<G> {
                 panic::resume_unwind(err);
             }
         };
+        self.profiler.record_event(elapsed_seconds(started_event));
         self.gui = gui;
         self.canvas = canvas;
         // TODO We should always do has_been_consumed, but various hacks prevent this from being
@@ -109,14 +130,20 @@ impl<G: GUI> State<G> {
 
         self.canvas.start_drawing();
 
+        let started_draw = Instant::now();
         if let Err(err) = panic::catch_unwind(panic::AssertUnwindSafe(|| {
             self.gui.draw(&mut g);
         })) {
             self.gui.dump_before_abort(&self.canvas);
             panic::resume_unwind(err);
         }
+        self.profiler.record_draw(elapsed_seconds(started_draw));
         let naming_hint = g.naming_hint.take();
 
+        if self.profiler.is_enabled() && !screenshot {
+            self.profiler.draw(&mut g);
+        }
+
         // Always draw the menus last.
         if let ContextMenu::Displaying(ref menu) = self.context_menu {
             menu.draw(&mut g);
@@ -125,25 +152,183 @@ impl<G: GUI> State<G> {
         target.finish().unwrap();
         naming_hint
     }
+
+    // The bool indicates if the input was actually used.
+    fn secondary_event(&mut self, ev: Event, secondary: &mut SecondaryWindow) -> bool {
+        let mut input = UserInput::new(ev, ContextMenu::new(), &mut secondary.canvas);
+        let gui = &mut self.gui;
+        let profiler = &self.profiler;
+        if let Err(err) = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            gui.secondary_event(&mut EventCtx {
+                input: &mut input,
+                canvas: &mut secondary.canvas,
+                prerender: &secondary.prerender,
+                program: Some(&secondary.program),
+                profiler,
+            });
+        })) {
+            self.gui.dump_before_abort(&self.canvas);
+            panic::resume_unwind(err);
+        }
+        match ev {
+            Event::KeyRelease(_) => input.has_been_consumed(),
+            _ => true,
+        }
+    }
+
+    fn draw_secondary(&self, secondary: &SecondaryWindow) {
+        let display = secondary.prerender.display();
+        let mut target = display.draw();
+        let context_menu = ContextMenu::new();
+        let mut g = GfxCtx::new(
+            &secondary.canvas,
+            &secondary.prerender,
+            display,
+            &mut target,
+            &secondary.program,
+            &context_menu,
+            false,
+        );
+
+        secondary.canvas.start_drawing();
+        if let Err(err) = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            self.gui.draw_secondary(&mut g);
+        })) {
+            self.gui.dump_before_abort(&self.canvas);
+            panic::resume_unwind(err);
+        }
+
+        target.finish().unwrap();
+        secondary.prerender.num_uploads.set(0);
+    }
 }
 
+// If ignore_hidpi is true, pretend the display has a hidpi factor of 1, like before hidpi was
+// supported. Some systems report the wrong factor.
 pub fn run<G: GUI, F: FnOnce(&mut EventCtx) -> G>(
     window_title: &str,
     initial_width: f64,
     initial_height: f64,
+    ignore_hidpi: bool,
+    make_gui: F,
+) {
+    run_windows(
+        window_title,
+        initial_width,
+        initial_height,
+        None,
+        ignore_hidpi,
+        make_gui,
+    );
+}
+
+// Like run, but also opens a second window, drawn by GUI::draw_secondary. Closing the secondary
+// window just hides it; the GUI should check Canvas::has_secondary_window and fall back to
+// drawing everything in the main window.
+pub fn run_with_secondary_window<G: GUI, F: FnOnce(&mut EventCtx) -> G>(
+    window_title: &str,
+    initial_width: f64,
+    initial_height: f64,
+    secondary_title: &str,
+    secondary_width: f64,
+    secondary_height: f64,
+    ignore_hidpi: bool,
     make_gui: F,
 ) {
-    // DPI is broken on my system; force the old behavior.
-    env::set_var("WINIT_HIDPI_FACTOR", "1.0");
+    run_windows(
+        window_title,
+        initial_width,
+        initial_height,
+        Some((secondary_title, secondary_width, secondary_height)),
+        ignore_hidpi,
+        make_gui,
+    );
+}
 
+fn run_windows<G: GUI, F: FnOnce(&mut EventCtx) -> G>(
+    window_title: &str,
+    initial_width: f64,
+    initial_height: f64,
+    secondary: Option<(&str, f64, f64)>,
+    ignore_hidpi: bool,
+    make_gui: F,
+) {
+    // Otherwise, set WINIT_HIDPI_FACTOR to override what the window system reports.
+    if ignore_hidpi {
+        env::set_var("WINIT_HIDPI_FACTOR", "1.0");
+    }
     let events_loop = glutin::EventsLoop::new();
+    let display = make_display(window_title, initial_width, initial_height, &events_loop);
+    let program = make_program(&display);
+
+    let mut canvas = Canvas::new(initial_width, initial_height, make_glyphs(&display));
+    canvas.set_hidpi_factor(display.gl_window().get_hidpi_factor());
+    let prerender = Prerender {
+        display: Some(&display),
+        num_uploads: Cell::new(0),
+        total_bytes_uploaded: Cell::new(0),
+    };
+
+    let secondary_display = secondary.map(|(title, width, height)| {
+        (
+            make_display(title, width, height, &events_loop),
+            width,
+            height,
+        )
+    });
+    let secondary = secondary_display.as_ref().map(|(display, width, height)| {
+        let mut canvas = Canvas::new(*width, *height, make_glyphs(display));
+        canvas.set_hidpi_factor(display.gl_window().get_hidpi_factor());
+        SecondaryWindow {
+            canvas,
+            program: make_program(display),
+            prerender: Prerender {
+                display: Some(display),
+                num_uploads: Cell::new(0),
+                total_bytes_uploaded: Cell::new(0),
+            },
+        }
+    });
+    canvas.secondary_window = secondary.is_some();
+
+    let profiler = Profiler::new();
+    let gui = make_gui(&mut EventCtx {
+        input: &mut UserInput::new(Event::NoOp, ContextMenu::new(), &mut canvas),
+        canvas: &mut canvas,
+        prerender: &prerender,
+        program: Some(&program),
+        profiler: &profiler,
+    });
+
+    let state = State {
+        canvas,
+        context_menu: ContextMenu::new(),
+        profiler,
+        gui,
+    };
+
+    loop_forever(state, events_loop, program, prerender, secondary);
+}
+
+fn make_display(
+    title: &str,
+    width: f64,
+    height: f64,
+    events_loop: &glutin::EventsLoop,
+) -> glium::Display {
     let window = glutin::WindowBuilder::new()
-        .with_title(window_title)
-        .with_dimensions(glutin::dpi::LogicalSize::new(initial_width, initial_height));
+        .with_title(title)
+        .with_dimensions(glutin::dpi::LogicalSize::new(width, height));
     // 2 looks bad, 4 looks fine
     let context = glutin::ContextBuilder::new().with_multisampling(4);
-    let display = glium::Display::new(window, context, &events_loop).unwrap();
+    glium::Display::new(window, context, events_loop).unwrap()
+}
 
+fn make_glyphs(display: &glium::Display) -> GlyphBrush<'static, 'static> {
+    GlyphBrush::new(display, vec![default_font()])
+}
+
+fn make_program(display: &glium::Display) -> glium::Program {
     let (vertex_shader, fragment_shader) =
         if display.is_glsl_version_supported(&glium::Version(glium::Api::Gl, 1, 4)) {
             (
@@ -178,8 +363,8 @@ pub fn run<G: GUI, F: FnOnce(&mut EventCtx) -> G>(
         (&vert, &frag)
     };*/
 
-    let program = glium::Program::new(
-        &display,
+    glium::Program::new(
+        display,
         glium::program::ProgramCreationInput::SourceCode {
             vertex_shader,
             tessellation_control_shader: None,
@@ -193,32 +378,7 @@ pub fn run<G: GUI, F: FnOnce(&mut EventCtx) -> G>(
             uses_point_size: false,
         },
     )
-    .unwrap();
-
-    let dejavu: &[u8] = include_bytes!("assets/DejaVuSans.ttf");
-    let glyphs = GlyphBrush::new(&display, vec![Font::from_bytes(dejavu).unwrap()]);
-
-    let mut canvas = Canvas::new(initial_width, initial_height, glyphs);
-    let prerender = Prerender {
-        display: &display,
-        num_uploads: Cell::new(0),
-        total_bytes_uploaded: Cell::new(0),
-    };
-
-    let gui = make_gui(&mut EventCtx {
-        input: &mut UserInput::new(Event::NoOp, ContextMenu::new(), &mut canvas),
-        canvas: &mut canvas,
-        prerender: &prerender,
-        program: &program,
-    });
-
-    let state = State {
-        canvas,
-        context_menu: ContextMenu::new(),
-        gui,
-    };
-
-    loop_forever(state, events_loop, program, prerender);
+    .unwrap()
 }
 
 fn loop_forever<G: GUI>(
@@ -226,6 +386,7 @@ fn loop_forever<G: GUI>(
     mut events_loop: glutin::EventsLoop,
     program: glium::Program,
     prerender: Prerender,
+    mut secondary: Option<SecondaryWindow>,
 ) {
     if state.gui.profiling_enabled() {
         #[cfg(target_os = "linux")]
@@ -239,13 +400,37 @@ fn loop_forever<G: GUI>(
     }
 
     let mut wait_for_events = false;
+    let mut last_drawn = Instant::now();
+    let mut last_bytes_uploaded = prerender.get_total_bytes_uploaded();
 
     loop {
         let start_frame = Instant::now();
 
         let mut new_events: Vec<Event> = Vec::new();
+        let mut secondary_events: Vec<Event> = Vec::new();
+        let mut close_secondary = false;
+        let secondary_id = secondary
+            .as_ref()
+            .map(|s| s.prerender.display().gl_window().id());
+        let mut secondary_hidpi_factor = secondary.as_ref().map(|s| s.canvas.hidpi_factor());
         events_loop.poll_events(|event| {
-            if let glutin::Event::WindowEvent { event, .. } = event {
+            if let glutin::Event::WindowEvent { window_id, event } = event {
+                if Some(window_id) == secondary_id {
+                    if event == glutin::WindowEvent::CloseRequested {
+                        close_secondary = true;
+                    } else if let glutin::WindowEvent::HiDpiFactorChanged(factor) = event {
+                        secondary_hidpi_factor = Some(factor);
+                    } else if let Some(ev) =
+                        Event::from_glutin_event(event, secondary_hidpi_factor.unwrap())
+                    {
+                        secondary_events.push(ev);
+                    }
+                    return;
+                }
+                if let glutin::WindowEvent::HiDpiFactorChanged(factor) = event {
+                    state.canvas.set_hidpi_factor(factor);
+                    return;
+                }
                 if event == glutin::WindowEvent::CloseRequested {
                     if state.gui.profiling_enabled() {
                         #[cfg(target_os = "linux")]
@@ -256,7 +441,7 @@ fn loop_forever<G: GUI>(
                     state.gui.before_quit(&state.canvas);
                     process::exit(0);
                 }
-                if let Some(ev) = Event::from_glutin_event(event) {
+                if let Some(ev) = Event::from_glutin_event(event, state.canvas.hidpi_factor()) {
                     new_events.push(ev);
                 }
             }
@@ -267,8 +452,39 @@ fn loop_forever<G: GUI>(
 
         let mut any_input_used = false;
 
+        if close_secondary {
+            // The Display lives as long as the runner, so just hide the window.
+            if let Some(s) = secondary.take() {
+                s.prerender.display().gl_window().hide();
+            }
+            state.canvas.secondary_window = false;
+            any_input_used = true;
+        }
+        let mut redraw_secondary = false;
+        if let Some(ref mut s) = secondary {
+            if let Some(factor) = secondary_hidpi_factor {
+                if factor != s.canvas.hidpi_factor() {
+                    s.canvas.set_hidpi_factor(factor);
+                    redraw_secondary = true;
+                }
+            }
+            for event in secondary_events {
+                redraw_secondary = true;
+                if state.secondary_event(event, s) {
+                    any_input_used = true;
+                }
+            }
+        }
+
         for event in new_events {
-            let (new_state, mode, input_used) = state.event(event, &prerender, &program);
+            // The runner owns this debug overlay, so no GUI has to plumb it through.
+            if event == Event::KeyPress(Key::F12) {
+                state.profiler.toggle();
+                any_input_used = true;
+                continue;
+            }
+
+            let (new_state, mode, input_used) = state.event(event, &prerender, Some(&program));
             if input_used {
                 any_input_used = true;
             }
@@ -284,7 +500,7 @@ fn loop_forever<G: GUI>(
                     state = widgets::screenshot_everything(
                         &dir,
                         state,
-                        &prerender.display,
+                        prerender.display(),
                         &program,
                         &prerender,
                         zoom,
@@ -295,7 +511,7 @@ fn loop_forever<G: GUI>(
                 EventLoopMode::ScreenCaptureCurrentShot => {
                     widgets::screenshot_current(
                         &mut state,
-                        &prerender.display,
+                        prerender.display(),
                         &program,
                         &prerender,
                     );
@@ -306,17 +522,31 @@ fn loop_forever<G: GUI>(
 
         // Don't draw if an event was ignored and we're not in Animation mode. Every keypress also
         // fires a release event, most of which are ignored.
-        if any_input_used || !wait_for_events {
+        let redraw_due = state.canvas.redraw_due();
+        if any_input_used || !wait_for_events || redraw_due {
             if any_input_used {
                 // But if the event caused a state-change, the drawing state might be different
                 // too. Need to recalculate what menu entries and such are valid. So send through
                 // a no-op event.
-                let (new_state, _, _) = state.event(Event::NoOp, &prerender, &program);
+                let (new_state, _, _) = state.event(Event::NoOp, &prerender, Some(&program));
                 state = new_state;
             }
 
-            state.draw(&prerender.display, &program, &prerender, false);
+            state.draw(prerender.display(), &program, &prerender, false);
             prerender.num_uploads.set(0);
+            redraw_secondary = true;
+
+            let bytes_uploaded = prerender.get_total_bytes_uploaded();
+            state
+                .profiler
+                .end_frame(elapsed_seconds(last_drawn), bytes_uploaded - last_bytes_uploaded);
+            last_drawn = Instant::now();
+            last_bytes_uploaded = bytes_uploaded;
+        }
+        if redraw_secondary {
+            if let Some(ref s) = secondary {
+                state.draw_secondary(s);
+            }
         }
 
         // Primitive event loop.
@@ -1,7 +1,8 @@
+use crate::canvas::Glyphs;
 use crate::screen_geom::ScreenRectangle;
 use crate::{Canvas, Color, GfxCtx, ScreenPt};
 use geom::{Distance, Polygon, Pt2D};
-use glium_glyph::glyph_brush::rusttype::Scale;
+use glium_glyph::glyph_brush::rusttype::{point, Font, Scale};
 use glium_glyph::glyph_brush::GlyphCruncher;
 use glium_glyph::glyph_brush::{Section, SectionText, VariedSection};
 use nom::types::CompleteStr;
@@ -144,6 +145,15 @@ impl Text {
         self.lines.is_empty()
     }
 
+    // Just the words, without any colors or sizes. Handy for tests.
+    pub fn to_plain_lines(&self) -> Vec<String> {
+        self.lines
+            .iter()
+            .map(|(_, line)| line.iter().map(|span| span.text.as_str()).collect())
+            .collect()
+    }
+
+    // In physical pixels
     pub(crate) fn dims(&self, canvas: &Canvas) -> (f64, f64) {
         let mut max_width = 0;
         let mut height = 0.0;
@@ -155,17 +165,20 @@ impl Text {
                 full_line.push_str(&span.text);
                 max_size = max_size.max(span.size);
             }
+            let scale = Scale::uniform(scaled_font_size(canvas, max_size));
             // Empty lines or whitespace-only lines effectively have 0 width.
-            let width = canvas
-                .glyphs
-                .borrow_mut()
-                .pixel_bounds(Section {
-                    text: &full_line,
-                    scale: Scale::uniform(max_size as f32),
-                    ..Section::default()
-                })
-                .map(|rect| rect.width())
-                .unwrap_or(0);
+            let width = match canvas.glyphs {
+                Glyphs::Gpu(ref brush) => brush
+                    .borrow_mut()
+                    .pixel_bounds(Section {
+                        text: &full_line,
+                        scale,
+                        ..Section::default()
+                    })
+                    .map(|rect| rect.width())
+                    .unwrap_or(0),
+                Glyphs::Headless(ref font) => width_without_gpu(font, &full_line, scale),
+            };
             max_width = max_width.max(width);
             height += canvas.line_height(max_size);
         }
@@ -173,6 +186,30 @@ impl Text {
     }
 }
 
+pub(crate) fn default_font() -> Font<'static> {
+    let dejavu: &[u8] = include_bytes!("assets/DejaVuSans.ttf");
+    Font::from_bytes(dejavu).unwrap()
+}
+
+fn scaled_font_size(canvas: &Canvas, font_size: usize) -> f32 {
+    ((font_size as f64) * canvas.hidpi_factor) as f32
+}
+
+// Should match what GlyphBrush's pixel_bounds would say.
+fn width_without_gpu(font: &Font, line: &str, scale: Scale) -> i32 {
+    let bounds: Vec<_> = font
+        .layout(line, scale, point(0.0, 0.0))
+        .filter_map(|glyph| glyph.pixel_bounding_box())
+        .collect();
+    match (
+        bounds.iter().map(|rect| rect.min.x).min(),
+        bounds.iter().map(|rect| rect.max.x).max(),
+    ) {
+        (Some(min), Some(max)) => max - min,
+        _ => 0,
+    }
+}
+
 pub fn draw_text_bubble(
     g: &mut GfxCtx,
     top_left: ScreenPt,
@@ -206,7 +243,7 @@ pub fn draw_text_bubble(
                     SectionText {
                         text: &span.text,
                         color: span.fg_color.0,
-                        scale: Scale::uniform(span.size as f32),
+                        scale: Scale::uniform(scaled_font_size(g.canvas, span.size)),
                         ..SectionText::default()
                     }
                 })
@@ -227,12 +264,9 @@ pub fn draw_text_bubble(
         }
 
         y += height;
-        g.canvas.glyphs.borrow_mut().queue(section);
+        g.canvas.glyph_brush().queue(section);
     }
-    g.canvas
-        .glyphs
-        .borrow_mut()
-        .draw_queued(g.display, g.target);
+    g.canvas.glyph_brush().draw_queued(g.display, g.target);
 
     g.unfork();
 
@@ -250,7 +284,7 @@ pub fn draw_text_bubble_mapspace(
     g: &mut GfxCtx,
     top_left: Pt2D,
     txt: &Text,
-    // Callers almost always calculate this anyway
+    // Callers almost always calculate this anyway. In map-space, so not scaled by the hidpi factor.
     (total_width, total_height): (f64, f64),
 ) {
     if let Some(c) = txt.bg_color {
@@ -286,7 +320,7 @@ pub fn draw_text_bubble_mapspace(
                 .collect(),
             ..VariedSection::default()
         };
-        let height = g.canvas.line_height(max_size);
+        let height = g.canvas.line_height(max_size) / g.canvas.hidpi_factor;
 
         if let Some(c) = line_color {
             g.draw_polygon(
@@ -300,12 +334,9 @@ pub fn draw_text_bubble_mapspace(
         }
 
         y += height * g.canvas.cam_zoom;
-        g.canvas.glyphs.borrow_mut().queue(section);
+        g.canvas.glyph_brush().queue(section);
     }
-    g.canvas
-        .glyphs
-        .borrow_mut()
-        .draw_queued(g.display, g.target);
+    g.canvas.glyph_brush().draw_queued(g.display, g.target);
 }
 
 #[derive(Debug)]
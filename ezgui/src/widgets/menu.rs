@@ -3,6 +3,11 @@ use crate::{
     hotkey, lctrl, text, Canvas, Event, GfxCtx, InputResult, Key, MultiKey, ScreenPt, Text,
 };
 use std::collections::HashSet;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+// How long the mouse has to rest on a choice before its help text appears
+const TOOLTIP_DELAY: Duration = Duration::from_millis(500);
 
 // Stores some associated data with each choice
 pub struct Menu<T: Clone> {
@@ -16,6 +21,10 @@ pub struct Menu<T: Clone> {
     hidden: bool,
     pos: Position,
     geom: Geometry,
+    // Indexed like choices; empty if there's no help text at all
+    tooltips: Vec<Option<String>>,
+    // Which choice the mouse is resting on, and since when
+    hovering: Option<(usize, Instant)>,
 }
 
 struct Geometry {
@@ -23,6 +32,50 @@ struct Geometry {
     top_left: ScreenPt,
     first_choice_row: ScreenRectangle,
     total_height: f64,
+    paging: Paging,
+    page: usize,
+}
+
+// Which choices are onscreen. Usually they all fit on one page; when they don't, Page Up and Page
+// Down flip through the rest. Hotkeys work no matter what page is showing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Paging {
+    num_choices: usize,
+    per_page: usize,
+}
+
+impl Paging {
+    fn new(num_choices: usize, rows_that_fit: usize) -> Paging {
+        Paging {
+            num_choices,
+            per_page: rows_that_fit.min(num_choices).max(1),
+        }
+    }
+
+    fn num_pages(self) -> usize {
+        ((self.num_choices + self.per_page - 1) / self.per_page).max(1)
+    }
+
+    fn is_paginated(self) -> bool {
+        self.num_pages() > 1
+    }
+
+    fn visible(self, page: usize) -> Range<usize> {
+        let start = page * self.per_page;
+        start..(start + self.per_page).min(self.num_choices)
+    }
+
+    fn page_of(self, idx: usize) -> usize {
+        idx / self.per_page
+    }
+
+    fn describe(self, page: usize) -> String {
+        format!(
+            "Page {}/{} (Page Up/Page Down for more)",
+            page + 1,
+            self.num_pages()
+        )
+    }
 }
 
 #[derive(Clone)]
@@ -38,18 +91,36 @@ impl Position {
         canvas: &Canvas,
         prompt: Text,
         choices: &Vec<(Option<MultiKey>, String, bool, T)>,
+        page: usize,
     ) -> Geometry {
         // This is actually a constant, effectively...
         let row_height = canvas.line_height(text::FONT_SIZE);
 
-        let mut txt = prompt;
-        let (_, prompt_height) = canvas.text_dims(&txt);
-        for (hotkey, choice, _, _) in choices {
-            if let Some(key) = hotkey {
-                txt.add_line(format!("{} - {}", key.describe(), choice));
-            } else {
-                txt.add_line(choice.to_string());
+        let describe_choices = |range: Range<usize>| {
+            let mut txt = prompt.clone();
+            for (hotkey, choice, _, _) in &choices[range] {
+                if let Some(key) = hotkey {
+                    txt.add_line(format!("{} - {}", key.describe(), choice));
+                } else {
+                    txt.add_line(choice.to_string());
+                }
             }
+            txt
+        };
+        let (_, prompt_height) = canvas.text_dims(&prompt);
+        let (_, unpaged_height) = canvas.text_dims(&describe_choices(0..choices.len()));
+        let paging = if unpaged_height <= canvas.window_height {
+            Paging::new(choices.len(), choices.len())
+        } else {
+            // Leave room for the line about paging.
+            let rows = (canvas.window_height - prompt_height - row_height) / row_height;
+            Paging::new(choices.len(), rows.max(0.0).floor() as usize)
+        };
+        let page = page.min(paging.num_pages() - 1);
+
+        let mut txt = describe_choices(paging.visible(page));
+        if paging.is_paginated() {
+            txt.add_line(paging.describe(page));
         }
         let (total_width, total_height) = canvas.text_dims(&txt);
 
@@ -94,6 +165,8 @@ impl Position {
                 y2: top_left.y + prompt_height + row_height,
             },
             total_height,
+            paging,
+            page,
         }
     }
 }
@@ -131,7 +204,7 @@ impl<T: Clone> Menu<T> {
             .into_iter()
             .map(|(key, choice, data)| (key, choice, true, data))
             .collect();
-        let geom = pos.geometry(canvas, prompt.clone(), &choices);
+        let geom = pos.geometry(canvas, prompt.clone(), &choices, 0);
 
         Menu {
             prompt,
@@ -144,10 +217,38 @@ impl<T: Clone> Menu<T> {
             hideable,
             hidden: false,
             geom,
+            tooltips: Vec::new(),
+            hovering: None,
         }
     }
 
+    // The help text shows up when the mouse rests on that choice for a moment.
+    pub(crate) fn set_tooltips(&mut self, tooltips: Vec<Option<String>>) {
+        assert_eq!(tooltips.len(), self.choices.len());
+        self.tooltips = tooltips;
+    }
+
     pub fn event(&mut self, ev: Event, canvas: &mut Canvas) -> InputResult<T> {
+        let result = self.handle_event(ev, canvas);
+
+        let hovered = self.hovered_choice().map(|(idx, _)| idx);
+        self.hovering = match (hovered, self.hovering) {
+            (Some(idx), Some((old_idx, since))) if idx == old_idx => Some((idx, since)),
+            (Some(idx), _) => {
+                let since = Instant::now();
+                // If the mouse stays put, no input arrives to trigger drawing the tooltip.
+                if self.tooltip_for(idx).is_some() {
+                    canvas.request_redraw_at(since + TOOLTIP_DELAY);
+                }
+                Some((idx, since))
+            }
+            (None, _) => None,
+        };
+
+        result
+    }
+
+    fn handle_event(&mut self, ev: Event, canvas: &mut Canvas) -> InputResult<T> {
         if !self.hidden {
             // Handle the mouse
             if ev == Event::LeftMouseButtonDown {
@@ -165,12 +266,14 @@ impl<T: Clone> Menu<T> {
                 return InputResult::Canceled;
             } else if let Event::MouseMovedTo(pt) = ev {
                 if !canvas.is_dragging() {
-                    for i in 0..self.choices.len() {
+                    let visible = self.geom.paging.visible(self.geom.page);
+                    let first = visible.start;
+                    for i in visible {
                         if self.choices[i].2
                             && self
                                 .geom
                                 .first_choice_row
-                                .translate(0.0, (i as f64) * self.geom.row_height)
+                                .translate(0.0, ((i - first) as f64) * self.geom.row_height)
                                 .contains(pt)
                         {
                             self.current_idx = Some(i);
@@ -199,13 +302,32 @@ impl<T: Clone> Menu<T> {
                 } else if ev == Event::KeyPress(Key::UpArrow) {
                     if idx > 0 {
                         self.current_idx = Some(idx - 1);
+                        self.show_page_of(idx - 1, canvas);
                     }
                 } else if ev == Event::KeyPress(Key::DownArrow) {
                     if idx < self.choices.len() - 1 {
                         self.current_idx = Some(idx + 1);
+                        self.show_page_of(idx + 1, canvas);
                     }
                 }
             }
+
+            if self.geom.paging.is_paginated() {
+                let page = self.geom.page;
+                let new_page = if ev == Event::KeyPress(Key::PageDown)
+                    && page + 1 < self.geom.paging.num_pages()
+                {
+                    Some(page + 1)
+                } else if ev == Event::KeyPress(Key::PageUp) && page > 0 {
+                    Some(page - 1)
+                } else {
+                    None
+                };
+                if let Some(p) = new_page {
+                    self.set_page(p, canvas);
+                    return InputResult::StillActive;
+                }
+            }
         }
 
         if self.hideable {
@@ -227,10 +349,9 @@ impl<T: Clone> Menu<T> {
             } else {
                 hotkey(key)
             };
-            for (maybe_key, choice, active, data) in &self.choices {
-                if *active && pressed == *maybe_key {
-                    return InputResult::Done(choice.to_string(), data.clone());
-                }
+            if let Some(idx) = find_hotkey(&self.choices, pressed) {
+                let (_, choice, _, data) = self.choices[idx].clone();
+                return InputResult::Done(choice, data);
             }
         }
 
@@ -249,7 +370,9 @@ impl<T: Clone> Menu<T> {
     pub fn draw(&self, g: &mut GfxCtx) {
         let mut txt = self.prompt.clone();
         if !self.hidden {
-            for (idx, (hotkey, choice, active, _)) in self.choices.iter().enumerate() {
+            let visible = self.geom.paging.visible(self.geom.page);
+            for idx in visible {
+                let (hotkey, choice, active, _) = &self.choices[idx];
                 let bg = if Some(idx) == self.current_idx {
                     Some(text::SELECTED_COLOR)
                 } else {
@@ -280,6 +403,14 @@ impl<T: Clone> Menu<T> {
                     }
                 }
             }
+            if self.geom.paging.is_paginated() {
+                txt.add_styled_line(
+                    self.geom.paging.describe(self.geom.page),
+                    Some(text::INACTIVE_CHOICE_COLOR),
+                    None,
+                    None,
+                );
+            }
         }
         g.canvas.mark_covered_area(ScreenRectangle {
             x1: self.geom.top_left.x,
@@ -288,6 +419,75 @@ impl<T: Clone> Menu<T> {
             y2: self.geom.top_left.y + self.geom.total_height,
         });
         g.draw_text_at_screenspace_topleft(&txt, self.geom.top_left);
+
+        if let Some((idx, since)) = self.hovering {
+            if since.elapsed() < TOOLTIP_DELAY {
+                return;
+            }
+            if let (Some(help), Some((_, row))) = (self.tooltip_for(idx), self.hovered_choice()) {
+                let txt = Text::from_line(help.to_string());
+                let (width, _) = g.text_dims(&txt);
+                // Off to the left of the choice, unless the menu is already against that edge
+                let pt = if row.x1 - width >= 0.0 {
+                    ScreenPt::new(row.x1 - width, row.y1)
+                } else {
+                    ScreenPt::new(row.x1, row.y2)
+                };
+                g.draw_text_at_screenspace_topleft(&txt, pt);
+            }
+        }
+    }
+
+    // The prompt, then every choice (even ones on other pages), marking inactive choices and the one
+    // the keyboard points at.
+    pub fn describe_state(&self) -> Vec<String> {
+        let mut lines = self.prompt.to_plain_lines();
+        if self.hidden {
+            lines.push("(hidden)".to_string());
+            return lines;
+        }
+        for (idx, (hotkey, choice, active, _)) in self.choices.iter().enumerate() {
+            let mut line = if let Some(key) = hotkey {
+                format!("{} - {}", key.describe(), choice)
+            } else {
+                choice.to_string()
+            };
+            if !active {
+                line = format!("{} (inactive)", line);
+            }
+            if Some(idx) == self.current_idx {
+                line = format!("> {}", line);
+            }
+            lines.push(line);
+        }
+        if self.geom.paging.is_paginated() {
+            lines.push(self.geom.paging.describe(self.geom.page));
+        }
+        lines
+    }
+
+    // The choice under the mouse and where it's drawn
+    pub(crate) fn hovered_choice(&self) -> Option<(usize, ScreenRectangle)> {
+        if self.hidden || !self.mouse_in_bounds {
+            return None;
+        }
+        let idx = self.current_idx?;
+        let first = self.geom.paging.visible(self.geom.page).start;
+        Some((
+            idx,
+            self.geom
+                .first_choice_row
+                .translate(0.0, ((idx - first) as f64) * self.geom.row_height),
+        ))
+    }
+
+    // The help text for whatever choice the mouse is on, even if it isn't showing yet
+    pub(crate) fn current_tooltip(&self) -> Option<&str> {
+        self.tooltip_for(self.hovered_choice()?.0)
+    }
+
+    fn tooltip_for(&self, idx: usize) -> Option<&str> {
+        self.tooltips.get(idx)?.as_ref().map(|s| s.as_str())
     }
 
     pub fn current_choice(&self) -> Option<&T> {
@@ -302,6 +502,21 @@ impl<T: Clone> Menu<T> {
             .collect()
     }
 
+    // Point the keyboard cursor at a choice again. If there's no matching active choice, be silent.
+    pub(crate) fn select_choice(&mut self, choice: &str, canvas: &Canvas) {
+        if !self.keys_enabled {
+            return;
+        }
+        if let Some(idx) = self
+            .choices
+            .iter()
+            .position(|(_, name, active, _)| *active && name == choice)
+        {
+            self.current_idx = Some(idx);
+            self.show_page_of(idx, canvas);
+        }
+    }
+
     // If there's no matching choice, be silent. The two callers don't care.
     pub fn mark_active(&mut self, choice: &str) {
         for (_, action, ref mut active, _) in self.choices.iter_mut() {
@@ -333,15 +548,44 @@ impl<T: Clone> Menu<T> {
         self.recalculate_geom(canvas);
     }
 
+    fn show_page_of(&mut self, idx: usize, canvas: &Canvas) {
+        let page = self.geom.paging.page_of(idx);
+        if page != self.geom.page {
+            self.set_page(page, canvas);
+            self.current_idx = Some(idx);
+        }
+    }
+
+    fn set_page(&mut self, page: usize, canvas: &Canvas) {
+        self.geom.page = page;
+        self.recalculate_geom(canvas);
+        self.current_idx = if self.keys_enabled {
+            Some(self.geom.paging.visible(self.geom.page).start)
+        } else {
+            None
+        };
+    }
+
     fn recalculate_geom(&mut self, canvas: &Canvas) {
         if self.hidden {
             self.geom = self
                 .pos
-                .geometry::<()>(canvas, self.prompt.clone(), &Vec::new());
+                .geometry::<()>(canvas, self.prompt.clone(), &Vec::new(), 0);
         } else {
+            let page = self.geom.page;
             self.geom = self
                 .pos
-                .geometry(canvas, self.prompt.clone(), &self.choices);
+                .geometry(canvas, self.prompt.clone(), &self.choices, page);
         }
     }
 }
+
+// Hotkeys match any active choice, even ones not on the current page.
+fn find_hotkey<T>(
+    choices: &Vec<(Option<MultiKey>, String, bool, T)>,
+    pressed: Option<MultiKey>,
+) -> Option<usize> {
+    choices
+        .iter()
+        .position(|(maybe_key, _, active, _)| *active && pressed == *maybe_key)
+}
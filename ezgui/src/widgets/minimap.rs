@@ -0,0 +1,145 @@
+use crate::screen_geom::ScreenRectangle;
+use crate::{Color, Drawable, EventCtx, GfxCtx, ScreenPt};
+use geom::{Bounds, Distance, Line, Pt2D};
+
+// Logical pixels
+const PANEL_SIZE: f64 = 250.0;
+const MARGIN: f64 = 10.0;
+// Leave room for the OSD along the bottom of the screen
+const BOTTOM_CLEARANCE: f64 = 60.0;
+
+// A small overview of the whole map in the bottom-right corner, showing where the camera is.
+// Clicking or dragging inside it moves the camera.
+pub struct Minimap {
+    dragging: bool,
+}
+
+impl Minimap {
+    pub fn new() -> Minimap {
+        Minimap { dragging: false }
+    }
+
+    // Returns true if the camera moved.
+    pub fn event(&mut self, ctx: &mut EventCtx, map_bounds: &Bounds) -> bool {
+        let layout = Layout::new(
+            ctx.canvas.window_width,
+            ctx.canvas.window_height,
+            ctx.canvas.hidpi_factor(),
+            map_bounds,
+        );
+        let cursor = ctx.canvas.get_cursor_in_screen_space();
+        if self.dragging {
+            if ctx.input.left_mouse_button_released() {
+                self.dragging = false;
+                return false;
+            }
+            if ctx.input.get_moved_mouse().is_none() {
+                return false;
+            }
+        } else if ctx.input.left_mouse_button_pressed() && layout.panel.contains(cursor) {
+            self.dragging = true;
+        } else {
+            return false;
+        }
+        ctx.canvas.center_on_map_pt(layout.to_map(cursor));
+        true
+    }
+
+    // Draws the prerendered layers, in order, scaled down to fit the panel.
+    pub fn draw(&self, g: &mut GfxCtx, map_bounds: &Bounds, layers: Vec<&Drawable>) {
+        let layout = Layout::new(
+            g.canvas.window_width,
+            g.canvas.window_height,
+            g.canvas.hidpi_factor(),
+            map_bounds,
+        );
+        g.canvas.mark_covered_area(ScreenRectangle {
+            x1: layout.panel.x1,
+            y1: layout.panel.y1,
+            x2: layout.panel.x2,
+            y2: layout.panel.y2,
+        });
+
+        g.enable_viewport(&layout.panel, ScreenPt::new(0.0, 0.0));
+        g.clear(Color::grey(0.3));
+        g.fork(
+            Pt2D::new(map_bounds.min_x, map_bounds.min_y),
+            ScreenPt::new(layout.panel.x1, layout.panel.y1),
+            layout.zoom,
+        );
+        for layer in layers {
+            g.redraw(layer);
+        }
+
+        // Just a few lines, so this is cheap to redo every frame. Anything outside the panel gets
+        // clipped.
+        g.fork_screenspace();
+        let screen = g.canvas.get_screen_bounds();
+        let corners: Vec<Pt2D> = vec![
+            Pt2D::new(screen.min_x, screen.min_y),
+            Pt2D::new(screen.max_x, screen.min_y),
+            Pt2D::new(screen.max_x, screen.max_y),
+            Pt2D::new(screen.min_x, screen.max_y),
+        ]
+        .into_iter()
+        .map(|pt| {
+            let screen_pt = layout.to_screen(pt);
+            Pt2D::new(screen_pt.x, screen_pt.y)
+        })
+        .collect();
+        for (pt1, pt2) in corners.iter().zip(corners.iter().cycle().skip(1)) {
+            g.draw_line(Color::RED, Distance::meters(2.0), &Line::new(*pt1, *pt2));
+        }
+        g.disable_viewport();
+    }
+}
+
+// Where the panel is and how the map fits inside of it
+struct Layout {
+    panel: ScreenRectangle,
+    top_left_map: Pt2D,
+    zoom: f64,
+}
+
+impl Layout {
+    fn new(
+        window_width: f64,
+        window_height: f64,
+        hidpi_factor: f64,
+        map_bounds: &Bounds,
+    ) -> Layout {
+        let panel_size = hidpi_factor * PANEL_SIZE;
+        let x2 = window_width - hidpi_factor * MARGIN;
+        let y2 = window_height - hidpi_factor * BOTTOM_CLEARANCE;
+        let width = map_bounds.max_x - map_bounds.min_x;
+        let height = map_bounds.max_y - map_bounds.min_y;
+        Layout {
+            panel: ScreenRectangle {
+                x1: x2 - panel_size,
+                y1: y2 - panel_size,
+                x2,
+                y2,
+            },
+            top_left_map: Pt2D::new(map_bounds.min_x, map_bounds.min_y),
+            zoom: (panel_size / width).min(panel_size / height),
+        }
+    }
+
+    fn to_screen(&self, pt: Pt2D) -> ScreenPt {
+        ScreenPt::new(
+            self.panel.x1 + (pt.x() - self.top_left_map.x()) * self.zoom,
+            self.panel.y1 + (pt.y() - self.top_left_map.y()) * self.zoom,
+        )
+    }
+
+    // Points outside the panel are clamped to its edge first, so dragging past the border still
+    // works.
+    fn to_map(&self, pt: ScreenPt) -> Pt2D {
+        let x = pt.x.max(self.panel.x1).min(self.panel.x2);
+        let y = pt.y.max(self.panel.y1).min(self.panel.y2);
+        Pt2D::new(
+            self.top_left_map.x() + (x - self.panel.x1) / self.zoom,
+            self.top_left_map.y() + (y - self.panel.y1) / self.zoom,
+        )
+    }
+}
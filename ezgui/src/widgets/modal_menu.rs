@@ -12,17 +12,38 @@ impl ModalMenu {
         choices: Vec<(Option<MultiKey>, &str)>,
         ctx: &EventCtx,
     ) -> ModalMenu {
+        ModalMenu::new_with_help(
+            prompt_line,
+            choices
+                .into_iter()
+                .map(|(multikey, action)| (multikey, action, None))
+                .collect(),
+            ctx,
+        )
+    }
+
+    // The help text shows up when the mouse hovers over that entry for a moment.
+    pub fn new_with_help(
+        prompt_line: &str,
+        choices: Vec<(Option<MultiKey>, &str, Option<&str>)>,
+        ctx: &EventCtx,
+    ) -> ModalMenu {
+        let tooltips = choices
+            .iter()
+            .map(|(_, _, help)| help.map(|h| h.to_string()))
+            .collect();
         let mut menu = Menu::new(
             Text::prompt(prompt_line),
             choices
                 .into_iter()
-                .map(|(multikey, action)| (multikey, action.to_string(), ()))
+                .map(|(multikey, action, _)| (multikey, action.to_string(), ()))
                 .collect(),
             false,
             true,
             Position::TopRightOfScreen,
             ctx.canvas,
         );
+        menu.set_tooltips(tooltips);
         menu.mark_all_inactive();
         if ctx.canvas.hide_modal_menus {
             menu.make_hidden(ctx.canvas);
@@ -70,4 +91,13 @@ impl ModalMenu {
     pub fn draw(&self, g: &mut GfxCtx) {
         self.menu.draw(g);
     }
+
+    // The help text for whatever entry the mouse is on, even if it isn't showing yet
+    pub fn current_tooltip(&self) -> Option<&str> {
+        self.menu.current_tooltip()
+    }
+
+    pub fn describe_state(&self) -> Vec<String> {
+        self.menu.describe_state()
+    }
 }
@@ -5,7 +5,7 @@ use crate::{
 };
 use geom::{Distance, Polygon, Pt2D};
 
-// Pixels
+// Logical pixels
 const BAR_WIDTH: f64 = 300.0;
 const BAR_HEIGHT: f64 = 100.0;
 const SLIDER_WIDTH: f64 = 50.0;
@@ -46,7 +46,10 @@ impl Slider {
         // Just reset dragging, to prevent chaos
         self.dragging = false;
         let pt = ctx.canvas.get_cursor_in_screen_space();
-        self.mouse_on_slider = self.slider_geom().contains_pt(Pt2D::new(pt.x, pt.y));
+        self.mouse_on_slider = self
+            .layout(ctx.canvas.hidpi_factor())
+            .slider(self.current_percent)
+            .contains_pt(Pt2D::new(pt.x, pt.y));
     }
 
     pub fn set_value(&mut self, ctx: &mut EventCtx, idx: usize, num_items: usize) {
@@ -55,12 +58,10 @@ impl Slider {
 
     // Returns true if the percentage changed.
     pub fn event(&mut self, ctx: &mut EventCtx) -> bool {
+        let layout = self.layout(ctx.canvas.hidpi_factor());
         if self.dragging {
             if ctx.input.get_moved_mouse().is_some() {
-                let percent =
-                    (ctx.canvas.get_cursor_in_screen_space().x - HORIZ_PADDING - self.top_left.x)
-                        / BAR_WIDTH;
-                self.current_percent = percent.min(1.0).max(0.0);
+                self.current_percent = layout.percent_at(ctx.canvas.get_cursor_in_screen_space());
                 return true;
             }
             if ctx.input.left_mouse_button_released() {
@@ -69,7 +70,9 @@ impl Slider {
         } else {
             if ctx.redo_mouseover() {
                 let pt = ctx.canvas.get_cursor_in_screen_space();
-                self.mouse_on_slider = self.slider_geom().contains_pt(Pt2D::new(pt.x, pt.y));
+                self.mouse_on_slider = layout
+                    .slider(self.current_percent)
+                    .contains_pt(Pt2D::new(pt.x, pt.y));
             }
             if ctx.input.left_mouse_button_pressed() {
                 if self.mouse_on_slider {
@@ -77,18 +80,8 @@ impl Slider {
                 } else {
                     // Did we click somewhere else on the bar?
                     let pt = ctx.canvas.get_cursor_in_screen_space();
-                    if Polygon::rectangle_topleft(
-                        Pt2D::new(
-                            HORIZ_PADDING + self.top_left.x,
-                            VERT_PADDING + self.top_left.y,
-                        ),
-                        Distance::meters(BAR_WIDTH),
-                        Distance::meters(BAR_HEIGHT),
-                    )
-                    .contains_pt(Pt2D::new(pt.x, pt.y))
-                    {
-                        let percent = (pt.x - HORIZ_PADDING - self.top_left.x) / BAR_WIDTH;
-                        self.current_percent = percent.min(1.0).max(0.0);
+                    if layout.bar(1.0).contains_pt(Pt2D::new(pt.x, pt.y)) {
+                        self.current_percent = layout.percent_at(pt);
                         self.mouse_on_slider = true;
                         self.dragging = true;
                         return true;
@@ -100,50 +93,27 @@ impl Slider {
     }
 
     pub fn draw(&self, g: &mut GfxCtx) {
+        let layout = self.layout(g.canvas.hidpi_factor());
         g.fork_screenspace();
 
         // A nice background for the entire thing
+        let background = layout.background();
         g.draw_polygon(
             Color::grey(0.3),
             &Polygon::rectangle_topleft(
-                Pt2D::new(self.top_left.x, self.top_left.y),
-                Distance::meters(BAR_WIDTH + 2.0 * HORIZ_PADDING),
-                Distance::meters(BAR_HEIGHT + 2.0 * VERT_PADDING),
+                Pt2D::new(background.x1, background.y1),
+                Distance::meters(background.x2 - background.x1),
+                Distance::meters(background.y2 - background.y1),
             ),
         );
-        g.canvas.mark_covered_area(ScreenRectangle {
-            x1: self.top_left.x,
-            y1: self.top_left.y,
-            x2: self.top_left.x + BAR_WIDTH + 2.0 * HORIZ_PADDING,
-            y2: self.top_left.y + BAR_HEIGHT + 2.0 * VERT_PADDING,
-        });
+        g.canvas.mark_covered_area(background);
 
         // The bar
-        g.draw_polygon(
-            Color::WHITE,
-            &Polygon::rectangle_topleft(
-                Pt2D::new(
-                    self.top_left.x + HORIZ_PADDING,
-                    self.top_left.y + VERT_PADDING,
-                ),
-                Distance::meters(BAR_WIDTH),
-                Distance::meters(BAR_HEIGHT),
-            ),
-        );
+        g.draw_polygon(Color::WHITE, &layout.bar(1.0));
 
         // Show the progress
         if self.current_percent != 0.0 {
-            g.draw_polygon(
-                Color::GREEN,
-                &Polygon::rectangle_topleft(
-                    Pt2D::new(
-                        self.top_left.x + HORIZ_PADDING,
-                        self.top_left.y + VERT_PADDING,
-                    ),
-                    Distance::meters(self.current_percent * BAR_WIDTH),
-                    Distance::meters(BAR_HEIGHT),
-                ),
-            );
+            g.draw_polygon(Color::GREEN, &layout.bar(self.current_percent));
         }
 
         // The actual slider
@@ -153,21 +123,64 @@ impl Slider {
             } else {
                 Color::grey(0.7)
             },
-            &self.slider_geom(),
+            &layout.slider(self.current_percent),
         );
     }
 
-    fn slider_geom(&self) -> Polygon {
+    fn layout(&self, hidpi_factor: f64) -> Layout {
+        Layout {
+            top_left: self.top_left,
+            scale: hidpi_factor,
+        }
+    }
+}
+
+// Where everything is drawn, in physical pixels. The top-left corner doesn't get scaled.
+struct Layout {
+    top_left: ScreenPt,
+    scale: f64,
+}
+
+impl Layout {
+    fn background(&self) -> ScreenRectangle {
+        ScreenRectangle {
+            x1: self.top_left.x,
+            y1: self.top_left.y,
+            x2: self.top_left.x + self.scale * (BAR_WIDTH + 2.0 * HORIZ_PADDING),
+            y2: self.top_left.y + self.scale * (BAR_HEIGHT + 2.0 * VERT_PADDING),
+        }
+    }
+
+    // The bar filled up to some percent
+    fn bar(&self, percent: f64) -> Polygon {
         Polygon::rectangle_topleft(
             Pt2D::new(
-                self.top_left.x + HORIZ_PADDING + self.current_percent * BAR_WIDTH
-                    - (SLIDER_WIDTH / 2.0),
-                self.top_left.y + VERT_PADDING - (SLIDER_HEIGHT - BAR_HEIGHT) / 2.0,
+                self.top_left.x + self.scale * HORIZ_PADDING,
+                self.top_left.y + self.scale * VERT_PADDING,
             ),
-            Distance::meters(SLIDER_WIDTH),
-            Distance::meters(SLIDER_HEIGHT),
+            Distance::meters(self.scale * percent * BAR_WIDTH),
+            Distance::meters(self.scale * BAR_HEIGHT),
         )
     }
+
+    fn slider(&self, percent: f64) -> Polygon {
+        Polygon::rectangle_topleft(
+            Pt2D::new(
+                self.top_left.x
+                    + self.scale * (HORIZ_PADDING + percent * BAR_WIDTH - (SLIDER_WIDTH / 2.0)),
+                self.top_left.y + self.scale * (VERT_PADDING - (SLIDER_HEIGHT - BAR_HEIGHT) / 2.0),
+            ),
+            Distance::meters(self.scale * SLIDER_WIDTH),
+            Distance::meters(self.scale * SLIDER_HEIGHT),
+        )
+    }
+
+    // Clamped to [0, 1]
+    fn percent_at(&self, pt: ScreenPt) -> f64 {
+        let percent =
+            (pt.x - self.top_left.x - self.scale * HORIZ_PADDING) / (self.scale * BAR_WIDTH);
+        percent.min(1.0).max(0.0)
+    }
 }
 
 pub struct ItemSlider<T> {
@@ -2,25 +2,43 @@ use crate::{text, Event, GfxCtx, InputResult, Key, Text, UserInput, CENTERED};
 
 // TODO right now, only a single line
 
+// Older entries get forgotten past this
+const MAX_HISTORY: usize = 100;
+
 pub struct TextBox {
     prompt: String,
     // TODO A rope would be cool.
     line: String,
     cursor_x: usize,
     shift_pressed: bool,
+
+    // Previously submitted lines, oldest first. Up and Down arrows recall them.
+    history: Vec<String>,
+    // Which entry is being shown, or None when editing a new line
+    history_idx: Option<usize>,
+    // What was typed before browsing the history, to get back to with Down
+    draft: String,
 }
 
 impl TextBox {
-    pub fn new(prompt: &str, prefilled: Option<String>) -> TextBox {
+    pub fn new(prompt: &str, prefilled: Option<String>, history: Vec<String>) -> TextBox {
         let line = prefilled.unwrap_or_else(String::new);
         TextBox {
             prompt: prompt.to_string(),
             cursor_x: line.len(),
             line,
             shift_pressed: false,
+            history,
+            history_idx: None,
+            draft: String::new(),
         }
     }
 
+    // Includes anything submitted from this TextBox, so the next one can be seeded with it.
+    pub fn get_history(&self) -> &Vec<String> {
+        &self.history
+    }
+
     pub fn draw(&self, g: &mut GfxCtx) {
         let mut txt = Text::prompt(&self.prompt);
 
@@ -37,17 +55,46 @@ impl TextBox {
         g.draw_blocking_text(&txt, CENTERED);
     }
 
+    pub fn describe_state(&self) -> Vec<String> {
+        vec![self.prompt.clone(), self.line.clone()]
+    }
+
     pub fn event(&mut self, input: &mut UserInput) -> InputResult<()> {
-        let maybe_ev = input.use_event_directly();
-        if maybe_ev.is_none() {
-            return InputResult::StillActive;
+        match input.use_event_directly() {
+            Some(ev) => self.handle_event(ev),
+            None => InputResult::StillActive,
         }
-        let ev = maybe_ev.unwrap();
+    }
 
+    fn handle_event(&mut self, ev: Event) -> InputResult<()> {
         if ev == Event::KeyPress(Key::Escape) {
             return InputResult::Canceled;
         } else if ev == Event::KeyPress(Key::Enter) {
+            self.remember(self.line.clone());
             return InputResult::Done(self.line.clone(), ());
+        } else if ev == Event::KeyPress(Key::UpArrow) {
+            let idx = match self.history_idx {
+                Some(idx) => idx.saturating_sub(1),
+                None => {
+                    if self.history.is_empty() {
+                        return InputResult::StillActive;
+                    }
+                    self.draft = self.line.clone();
+                    self.history.len() - 1
+                }
+            };
+            self.history_idx = Some(idx);
+            self.set_line(self.history[idx].clone());
+        } else if ev == Event::KeyPress(Key::DownArrow) {
+            if let Some(idx) = self.history_idx {
+                if idx + 1 < self.history.len() {
+                    self.history_idx = Some(idx + 1);
+                    self.set_line(self.history[idx + 1].clone());
+                } else {
+                    self.history_idx = None;
+                    self.set_line(self.draft.clone());
+                }
+            }
         } else if ev == Event::KeyPress(Key::LeftShift) {
             self.shift_pressed = true;
         } else if ev == Event::KeyRelease(Key::LeftShift) {
@@ -71,4 +118,19 @@ impl TextBox {
         };
         InputResult::StillActive
     }
+    fn set_line(&mut self, line: String) {
+        self.cursor_x = line.len();
+        self.line = line;
+    }
+
+    fn remember(&mut self, line: String) {
+        self.history_idx = None;
+        if line.is_empty() || self.history.last() == Some(&line) {
+            return;
+        }
+        self.history.push(line);
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+    }
 }
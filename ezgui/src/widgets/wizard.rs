@@ -1,9 +1,17 @@
 use crate::widgets::{Menu, Position};
 use crate::{
-    Canvas, EventCtx, GfxCtx, InputResult, LogScroller, MultiKey, Text, TextBox, UserInput,
+    Canvas, Event, EventCtx, GfxCtx, HorizontalAlignment, InputResult, Key, LogScroller, MultiKey,
+    Text, TextBox, UserInput, VerticalAlignment,
 };
 use abstutil::Cloneable;
-use std::collections::VecDeque;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+thread_local! {
+    // What's been typed into each question before, keyed by the query. Wizards are usually
+    // recreated every time they're opened, so this lives outside of them.
+    static TEXT_BOX_HISTORY: RefCell<HashMap<String, Vec<String>>> = RefCell::new(HashMap::new());
+}
 
 pub struct Wizard {
     alive: bool,
@@ -12,7 +20,17 @@ pub struct Wizard {
     log_scroller: Option<LogScroller>,
 
     // In the order of queries made
-    confirmed_state: Vec<Box<Cloneable>>,
+    confirmed_state: Vec<ConfirmedStep>,
+    // After stepping back, the previous answer to show again for the question being re-asked
+    restore: Option<String>,
+}
+
+// One answered question, kept so the flow can be replayed every frame and rewound.
+struct ConfirmedStep {
+    query: String,
+    // The line typed or the menu choice. None for acknowledgements, which aren't really answers.
+    answer: Option<String>,
+    result: Box<Cloneable>,
 }
 
 impl Wizard {
@@ -23,10 +41,31 @@ impl Wizard {
             menu: None,
             log_scroller: None,
             confirmed_state: Vec::new(),
+            restore: None,
         }
     }
 
+    // Whatever question is being asked right now
+    pub fn describe_state(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(ref menu) = self.menu {
+            lines.extend(menu.describe_state());
+        }
+        if let Some(ref tb) = self.tb {
+            lines.extend(tb.describe_state());
+        }
+        lines
+    }
+
     pub fn draw(&self, g: &mut GfxCtx) {
+        let breadcrumbs = self.breadcrumbs();
+        if !breadcrumbs.is_empty() {
+            let mut txt = Text::prompt("Answered so far (Escape to go back)");
+            for (query, answer) in breadcrumbs {
+                txt.add_line(format!("{}: {}", query, answer));
+            }
+            g.draw_blocking_text(&txt, (HorizontalAlignment::Left, VerticalAlignment::Top));
+        }
         if let Some(ref menu) = self.menu {
             menu.draw(g);
         }
@@ -41,7 +80,11 @@ impl Wizard {
     pub fn wrap<'a>(&'a mut self, ctx: &'a mut EventCtx) -> WrappedWizard<'a> {
         assert!(self.alive);
 
-        let ready_results = VecDeque::from(self.confirmed_state.clone());
+        let ready_results = self
+            .confirmed_state
+            .iter()
+            .map(|step| step.result.clone())
+            .collect();
         WrappedWizard {
             wizard: self,
             input: ctx.input,
@@ -63,6 +106,43 @@ impl Wizard {
         None
     }
 
+    // The (question, answer) pairs for the current flow, in order
+    pub fn breadcrumbs(&self) -> Vec<(&str, &str)> {
+        self.confirmed_state
+            .iter()
+            .filter_map(|step| {
+                step.answer
+                    .as_ref()
+                    .map(|answer| (step.query.as_str(), answer.as_str()))
+            })
+            .collect()
+    }
+
+    fn confirm(&mut self, query: &str, answer: Option<String>, result: Box<Cloneable>) {
+        self.restore = None;
+        self.confirmed_state.push(ConfirmedStep {
+            query: query.to_string(),
+            answer,
+            result,
+        });
+    }
+
+    // Forget the last answer, so the next frame asks that question again. Only aborts when there's
+    // nothing left to step back to.
+    fn step_back(&mut self) {
+        self.tb = None;
+        self.menu = None;
+        self.restore = None;
+        // Acknowledgements can't be canceled, so skip straight past them.
+        while let Some(step) = self.confirmed_state.pop() {
+            if step.answer.is_some() {
+                self.restore = step.answer;
+                return;
+            }
+        }
+        self.alive = false;
+    }
+
     fn input_with_text_box<R: Cloneable>(
         &mut self,
         query: &str,
@@ -78,18 +158,26 @@ impl Wizard {
         }
 
         if self.tb.is_none() {
-            self.tb = Some(TextBox::new(query, prefilled));
+            let prefilled = self.restore.take().or(prefilled);
+            let history = TEXT_BOX_HISTORY.with(|h| h.borrow().get(query).cloned());
+            self.tb = Some(TextBox::new(
+                query,
+                prefilled,
+                history.unwrap_or_else(Vec::new),
+            ));
         }
 
         match self.tb.as_mut().unwrap().event(input) {
             InputResult::StillActive => None,
             InputResult::Canceled => {
-                self.alive = false;
+                self.step_back();
                 None
             }
             InputResult::Done(line, _) => {
-                self.tb = None;
+                let history = self.tb.take().unwrap().get_history().clone();
+                TEXT_BOX_HISTORY.with(|h| h.borrow_mut().insert(query.to_string(), history));
                 if let Some(result) = parser(line.clone()) {
+                    self.confirm(query, Some(line), result.clone_box());
                     Some(result)
                 } else {
                     println!("Invalid input {}", line);
@@ -123,15 +211,8 @@ impl<'a> WrappedWizard<'a> {
             let item: &R = first.as_any().downcast_ref::<R>().unwrap();
             return Some(item.clone());
         }
-        if let Some(obj) = self
-            .wizard
+        self.wizard
             .input_with_text_box(query, prefilled, self.input, parser)
-        {
-            self.wizard.confirmed_state.push(Box::new(obj.clone()));
-            Some(obj)
-        } else {
-            None
-        }
     }
 
     pub fn input_string(&mut self, query: &str) -> Option<String> {
@@ -209,14 +290,18 @@ impl<'a> WrappedWizard<'a> {
                 .into_iter()
                 .map(|(multikey, s, item)| (multikey, s, item.clone_box()))
                 .collect();
-            self.wizard.menu = Some(Menu::new(
+            let mut menu = Menu::new(
                 Text::prompt(query),
                 boxed_choices,
                 true,
                 false,
                 Position::ScreenCenter,
                 self.canvas,
-            ));
+            );
+            if let Some(choice) = self.wizard.restore.take() {
+                menu.select_choice(&choice, self.canvas);
+            }
+            self.wizard.menu = Some(menu);
         }
 
         assert!(self.wizard.alive);
@@ -229,16 +314,23 @@ impl<'a> WrappedWizard<'a> {
         let ev = self.input.use_event_directly().unwrap();
         match self.wizard.menu.as_mut().unwrap().event(ev, self.canvas) {
             InputResult::Canceled => {
-                self.wizard.menu = None;
-                self.wizard.alive = false;
+                self.wizard.step_back();
+                None
+            }
+            InputResult::StillActive => {
+                // Menus don't use Backspace for anything else, unless it's a hotkey for a choice.
+                if ev == Event::KeyPress(Key::Backspace) {
+                    self.wizard.step_back();
+                }
                 None
             }
-            InputResult::StillActive => None,
             InputResult::Done(choice, item) => {
                 self.wizard.menu = None;
-                self.wizard
-                    .confirmed_state
-                    .push(Box::new((choice.to_string(), item.clone())));
+                self.wizard.confirm(
+                    query,
+                    Some(choice.clone()),
+                    Box::new((choice.to_string(), item.clone())),
+                );
                 let downcasted_item: &R = item.as_any().downcast_ref::<R>().unwrap();
                 Some((choice, downcasted_item.clone()))
             }
@@ -307,7 +399,7 @@ impl<'a> WrappedWizard<'a> {
             ));
         }
         if self.wizard.log_scroller.as_mut().unwrap().event(self.input) {
-            self.wizard.confirmed_state.push(Box::new(()));
+            self.wizard.confirm(title, None, Box::new(()));
             self.wizard.log_scroller = None;
             true
         } else {
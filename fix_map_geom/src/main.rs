@@ -1,8 +1,8 @@
-use abstutil::Timer;
+use abstutil::{FileWatcher, Timer};
 use ezgui::{
     hotkey, Color, EventCtx, EventLoopMode, GfxCtx, Key, ModalMenu, Text, WarpingItemSlider, GUI,
 };
-use geom::{Circle, Distance, PolyLine, Polygon, Pt2D};
+use geom::{Circle, Distance, PolyLine, Pt2D};
 use map_model::raw_data::{Hint, Hints, InitialMap, Map, StableIntersectionID, StableRoadID};
 use map_model::LANE_THICKNESS;
 use std::collections::HashSet;
@@ -11,12 +11,16 @@ use viewer::World;
 
 // Bit bigger than buses
 const MIN_ROAD_LENGTH: Distance = Distance::const_meters(13.0);
+const HINTS_PATH: &str = "../data/hints.json";
 
 struct UI {
     world: World<ID>,
     data: InitialMap,
     raw: Map,
     hints: Hints,
+    // Somebody might edit or check out a different hints.json while this is running
+    hints_watcher: FileWatcher,
+    hints_changed_on_disk: bool,
     state: State,
 }
 
@@ -41,6 +45,7 @@ impl State {
                     (hotkey(Key::R), "reset hints"),
                     (hotkey(Key::U), "undo last hint"),
                     (hotkey(Key::B), "browse hints"),
+                    (hotkey(Key::L), "re-apply hints from disk"),
                 ],
                 ctx,
             ),
@@ -73,6 +78,8 @@ impl UI {
                 data,
                 raw,
                 hints,
+                hints_watcher: FileWatcher::new(HINTS_PATH),
+                hints_changed_on_disk: false,
                 state: State::main(ctx),
             }
         })
@@ -81,6 +88,10 @@ impl UI {
 
 impl GUI for UI {
     fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
+        if self.hints_watcher.changed() {
+            self.hints_changed_on_disk = true;
+        }
+
         match self.state {
             State::Main {
                 ref mut menu,
@@ -91,6 +102,9 @@ impl GUI for UI {
                     let len = self.hints.hints.len();
                     let mut txt = Text::prompt("Fix Map Geometry");
                     txt.push(format!("[cyan:{}] hints", len));
+                    if self.hints_changed_on_disk {
+                        txt.push("[red:hints.json changed on disk]".to_string());
+                    }
                     for i in (1..=5).rev() {
                         if len >= i {
                             txt.add_line(describe(&self.hints.hints[len - i]));
@@ -146,10 +160,19 @@ impl GUI for UI {
                 if menu.action("quit") {
                     process::exit(0);
                 }
+                let mut recalc = false;
+                if self.hints_changed_on_disk && menu.action("re-apply hints from disk") {
+                    self.hints = Hints::load();
+                    self.hints_changed_on_disk = false;
+                    recalc = true;
+                }
                 if !self.hints.hints.is_empty() {
                     if menu.action("save") {
-                        abstutil::write_json("../data/hints.json", &self.hints).unwrap();
+                        abstutil::write_json(HINTS_PATH, &self.hints).unwrap();
                         println!("Saved hints.json");
+                        // Don't offer to reload our own changes
+                        self.hints_watcher.changed();
+                        self.hints_changed_on_disk = false;
                     }
 
                     if menu.action("browse hints") {
@@ -178,65 +201,68 @@ impl GUI for UI {
                         return EventLoopMode::InputOnly;
                     }
 
-                    let recalc = if menu.action("undo last hint") {
+                    if menu.action("undo last hint") {
                         self.hints.hints.pop();
-                        true
+                        *selected = None;
+                        // Only the hints applied since the last full rebuild can be undone cheaply
+                        if let Some((roads, intersections)) = self.data.undo_last_hint() {
+                            for r in roads {
+                                self.world.delete_obj(ID::Road(r));
+                                add_road(&mut self.world, &self.data, r, ctx);
+                            }
+                            for i in intersections {
+                                self.world.delete_obj(ID::Intersection(i));
+                                add_intersection(&mut self.world, &self.data, i, ctx);
+                            }
+                            return EventLoopMode::InputOnly;
+                        }
+                        recalc = true;
                     } else if menu.action("reset hints") {
                         self.hints.hints.clear();
-                        true
-                    } else {
-                        false
-                    };
-                    if recalc {
-                        *selected = None;
-                        ctx.loading_screen("recalculate map from hints", |ctx, mut timer| {
-                            let gps_bounds = &self.raw.gps_bounds;
-                            self.data = InitialMap::new(
-                                self.data.name.clone(),
-                                &self.raw,
-                                gps_bounds,
-                                &gps_bounds.to_bounds(),
-                                &mut timer,
-                            );
-                            self.data.apply_hints(&self.hints, &self.raw, &mut timer);
-                            self.world = initial_map_to_world(&self.data, ctx);
-                        });
-                        return EventLoopMode::InputOnly;
+                        recalc = true;
                     }
                 }
+                if recalc {
+                    *selected = None;
+                    ctx.loading_screen("recalculate map from hints", |ctx, mut timer| {
+                        let gps_bounds = &self.raw.gps_bounds;
+                        self.data = InitialMap::new(
+                            self.data.name.clone(),
+                            &self.raw,
+                            gps_bounds,
+                            &gps_bounds.to_bounds(),
+                            &mut timer,
+                        );
+                        self.data.apply_hints(&self.hints, &self.raw, &mut timer);
+                        self.world = initial_map_to_world(&self.data, ctx);
+                    });
+                    return EventLoopMode::InputOnly;
+                }
 
+                let mut new_hint = None;
                 if let Some(ID::Road(r)) = selected {
                     if ctx.input.key_pressed(Key::M, "merge") {
-                        self.hints
-                            .hints
-                            .push(Hint::MergeRoad(self.raw.roads[&r].orig_id()));
-                        self.data.merge_road(*r, &mut Timer::new("merge road"));
-                        self.world = initial_map_to_world(&self.data, ctx);
-                        *selected = None;
+                        new_hint = Some(Hint::MergeRoad(self.raw.roads[&r].orig_id()));
                     } else if ctx.input.key_pressed(Key::D, "delete") {
-                        self.hints
-                            .hints
-                            .push(Hint::DeleteRoad(self.raw.roads[r].orig_id()));
-                        self.data.delete_road(*r, &mut Timer::new("delete road"));
-                        self.world = initial_map_to_world(&self.data, ctx);
-                        *selected = None;
+                        new_hint = Some(Hint::DeleteRoad(self.raw.roads[r].orig_id()));
                     }
                 }
                 if let Some(ID::Intersection(i)) = selected {
                     if self.data.intersections[i].roads.len() == 2
                         && ctx.input.key_pressed(Key::M, "merge")
                     {
-                        self.hints.hints.push(Hint::MergeDegenerateIntersection(
+                        new_hint = Some(Hint::MergeDegenerateIntersection(
                             self.raw.intersections[i].orig_id(),
                         ));
-                        self.data.merge_degenerate_intersection(
-                            *i,
-                            &mut Timer::new("merge intersection"),
-                        );
-                        self.world = initial_map_to_world(&self.data, ctx);
-                        *selected = None;
                     }
                 }
+                if let Some(hint) = new_hint {
+                    self.data
+                        .apply_hint(&hint, &self.raw, &mut Timer::new("apply hint"));
+                    self.hints.hints.push(hint);
+                    self.world = initial_map_to_world(&self.data, ctx);
+                    *selected = None;
+                }
 
                 *osd = Text::new();
                 ctx.input.populate_osd(osd);
@@ -308,7 +334,7 @@ impl GUI for UI {
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    ezgui::run("InitialMap debugger", 1800.0, 800.0, |ctx| {
+    ezgui::run("InitialMap debugger", 1800.0, 800.0, false, |ctx| {
         ctx.canvas.cam_zoom = 4.0;
         UI::new(&args[1], ctx)
     });
@@ -332,45 +358,60 @@ impl viewer::ObjectID for ID {
 fn initial_map_to_world(data: &InitialMap, ctx: &mut EventCtx) -> World<ID> {
     let mut w = World::new(&data.bounds);
 
-    for r in data.roads.values() {
-        w.add_obj(
-            ctx.prerender,
-            ID::Road(r.id),
-            (if r.fwd_width >= r.back_width {
-                r.trimmed_center_pts
-                    .shift_right((r.fwd_width - r.back_width) / 2.0)
-            } else {
-                r.trimmed_center_pts
-                    .shift_left((r.back_width - r.fwd_width) / 2.0)
-            })
-            .unwrap()
-            .make_polygons(r.fwd_width + r.back_width),
-            if r.trimmed_center_pts.length() < MIN_ROAD_LENGTH {
-                Color::CYAN
-            } else {
-                Color::grey(0.8)
-            },
-            Text::from_line(r.id.to_string()),
-        );
+    for r in data.roads.keys() {
+        add_road(&mut w, data, *r, ctx);
     }
 
-    for i in data.intersections.values() {
-        w.add_obj(
-            ctx.prerender,
-            ID::Intersection(i.id),
-            Polygon::new(&i.polygon),
-            if i.roads.len() == 2 {
-                Color::RED
-            } else {
-                Color::BLACK
-            },
-            Text::from_line(format!("{}", i.id)),
-        );
+    for i in data.intersections.keys() {
+        add_intersection(&mut w, data, *i, ctx);
     }
 
     w
 }
 
+fn add_road(w: &mut World<ID>, data: &InitialMap, id: StableRoadID, ctx: &mut EventCtx) {
+    let r = &data.roads[&id];
+    w.add_obj(
+        ctx.prerender,
+        ID::Road(r.id),
+        (if r.fwd_width >= r.back_width {
+            r.trimmed_center_pts
+                .shift_right((r.fwd_width - r.back_width) / 2.0)
+        } else {
+            r.trimmed_center_pts
+                .shift_left((r.back_width - r.fwd_width) / 2.0)
+        })
+        .unwrap()
+        .make_polygons(r.fwd_width + r.back_width),
+        if r.trimmed_center_pts.length() < MIN_ROAD_LENGTH {
+            Color::CYAN
+        } else {
+            Color::grey(0.8)
+        },
+        Text::from_line(r.id.to_string()),
+    );
+}
+
+fn add_intersection(
+    w: &mut World<ID>,
+    data: &InitialMap,
+    id: StableIntersectionID,
+    ctx: &mut EventCtx,
+) {
+    let i = &data.intersections[&id];
+    w.add_obj(
+        ctx.prerender,
+        ID::Intersection(i.id),
+        i.polygon.to_polygon(),
+        if i.roads.len() == 2 {
+            Color::RED
+        } else {
+            Color::BLACK
+        },
+        Text::from_line(format!("{}", i.id)),
+    );
+}
+
 fn describe(hint: &Hint) -> String {
     match hint {
         Hint::MergeRoad(_) => "MergeRoad(...)".to_string(),
@@ -137,9 +137,9 @@ impl GPSBounds {
     // TODO cache this
     pub fn get_max_world_pt(&self) -> Pt2D {
         let width = LonLat::new(self.min_lon, self.min_lat)
-            .gps_dist_meters(LonLat::new(self.max_lon, self.min_lat));
+            .haversine_dist(LonLat::new(self.max_lon, self.min_lat));
         let height = LonLat::new(self.min_lon, self.min_lat)
-            .gps_dist_meters(LonLat::new(self.min_lon, self.max_lat));
+            .haversine_dist(LonLat::new(self.min_lon, self.max_lat));
         Pt2D::new(width.inner_meters(), height.inner_meters())
     }
 
@@ -108,30 +108,91 @@ impl Duration {
         Some(Duration::seconds(hours + minutes + seconds + ms))
     }*/
 
-    // (hours, minutes, seconds, centiseconds)
+    // Like "1h 23m 45s", leaving off leading units that are zero. Tenths of a second only show up
+    // when they're non-zero, like "3.5s".
+    pub fn to_string_hms(self) -> String {
+        let (hours, minutes, seconds, tenths) = self.get_parts();
+        let mut result = self.sign().to_string();
+        if hours > 0 {
+            result.push_str(&format!("{}h ", hours));
+        }
+        if hours > 0 || minutes > 0 {
+            result.push_str(&format!("{}m ", minutes));
+        }
+        if tenths > 0 {
+            result.push_str(&format!("{}.{}s", seconds, tenths));
+        } else {
+            result.push_str(&format!("{}s", seconds));
+        }
+        result
+    }
+
+    // The inverse of to_string_hms. Units must appear in order (hours, minutes, seconds), but any
+    // of them can be omitted.
+    pub fn parse_hms(string: &str) -> Option<Duration> {
+        let (negative, string) = if string.starts_with('-') {
+            (true, &string[1..])
+        } else {
+            (false, string)
+        };
+
+        // Popped from the end, so the biggest unit comes first.
+        let mut units = vec![('s', 1.0), ('m', 60.0), ('h', 3600.0)];
+        let mut seconds = 0.0;
+        let mut any_parts = false;
+        for part in string.split_whitespace() {
+            let unit = part.chars().last()?;
+            let value = part[..part.len() - unit.len_utf8()].parse::<f64>().ok()?;
+            if !value.is_finite() || value < 0.0 {
+                return None;
+            }
+            loop {
+                let (u, multiplier) = units.pop()?;
+                if u == unit {
+                    seconds += multiplier * value;
+                    break;
+                }
+            }
+            any_parts = true;
+        }
+        if !any_parts {
+            return None;
+        }
+
+        Some(Duration::seconds(if negative { -seconds } else { seconds }))
+    }
+
+    // (hours, minutes, seconds, tenths of a second) of the absolute value
     fn get_parts(self) -> (usize, usize, usize, usize) {
-        let mut remainder = self.inner_seconds();
-        let hours = (remainder / 3600.0).floor();
-        remainder -= hours * 3600.0;
-        let minutes = (remainder / 60.0).floor();
-        remainder -= minutes * 60.0;
-        let seconds = remainder.floor();
-        remainder -= seconds;
-        let centis = (remainder / 0.1).floor();
-
-        (
-            hours as usize,
-            minutes as usize,
-            seconds as usize,
-            centis as usize,
-        )
+        // Round once up front, so that float error doesn't turn 2.3s into 2.2s.
+        let mut remainder = (self.0.abs() * 10.0).round() as usize;
+        let tenths = remainder % 10;
+        remainder /= 10;
+        let seconds = remainder % 60;
+        remainder /= 60;
+        let minutes = remainder % 60;
+        let hours = remainder / 60;
+
+        (hours, minutes, seconds, tenths)
+    }
+
+    fn sign(self) -> &'static str {
+        if self.0 < 0.0 {
+            "-"
+        } else {
+            ""
+        }
     }
 
     pub fn as_filename(self) -> String {
         let (hours, minutes, seconds, remainder) = self.get_parts();
         format!(
-            "{0:02}h{1:02}m{2:02}.{3:01}s",
-            hours, minutes, seconds, remainder
+            "{0}{1:02}h{2:02}m{3:02}.{4:01}s",
+            self.sign(),
+            hours,
+            minutes,
+            seconds,
+            remainder
         )
     }
 }
@@ -141,8 +202,12 @@ impl std::fmt::Display for Duration {
         let (hours, minutes, seconds, remainder) = self.get_parts();
         write!(
             f,
-            "{0:02}:{1:02}:{2:02}.{3:01}",
-            hours, minutes, seconds, remainder
+            "{0}{1:02}:{2:02}:{3:02}.{4:01}",
+            self.sign(),
+            hours,
+            minutes,
+            seconds,
+            remainder
         )
     }
 }
@@ -237,9 +302,9 @@ impl DurationHistogram {
         format!(
             "{} count, 50%ile {}, 90%ile {}, 99%ile {}",
             abstutil::prettyprint_usize(self.count),
-            Duration::from_u64(self.histogram.percentile(50.0).unwrap()),
-            Duration::from_u64(self.histogram.percentile(90.0).unwrap()),
-            Duration::from_u64(self.histogram.percentile(99.0).unwrap()),
+            Duration::from_u64(self.histogram.percentile(50.0).unwrap()).to_string_hms(),
+            Duration::from_u64(self.histogram.percentile(90.0).unwrap()).to_string_hms(),
+            Duration::from_u64(self.histogram.percentile(99.0).unwrap()).to_string_hms(),
         )
     }
 }
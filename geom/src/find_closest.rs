@@ -38,6 +38,18 @@ where
 
     // Finds the closest point on the existing geometry to the query pt.
     pub fn closest_pt(&self, query_pt: Pt2D, max_dist_away: Distance) -> Option<(K, Pt2D)> {
+        self.all_close_pts(query_pt, max_dist_away)
+            .into_iter()
+            .next()
+            .map(|(key, pt, _)| (key, pt))
+    }
+
+    // Like closest_pt, but returns every geometry within the threshold, closest first.
+    pub fn all_close_pts(
+        &self,
+        query_pt: Pt2D,
+        max_dist_away: Distance,
+    ) -> Vec<(K, Pt2D, Distance)> {
         let query_geom = geo::Point::new(query_pt.x(), query_pt.y());
         let query_bbox = Rect {
             top_left: Point {
@@ -50,7 +62,8 @@ where
             },
         };
 
-        self.quadtree
+        let mut results: Vec<(K, Pt2D, Distance)> = self
+            .quadtree
             .query(query_bbox)
             .into_iter()
             .filter_map(|(key, _, _)| {
@@ -59,7 +72,7 @@ where
                 {
                     let dist = Distance::meters(pt.euclidean_distance(&query_geom));
                     if dist <= max_dist_away {
-                        Some((key, pt, dist))
+                        Some((key.clone(), Pt2D::new(pt.x(), pt.y()), dist))
                     } else {
                         None
                     }
@@ -67,8 +80,9 @@ where
                     None
                 }
             })
-            .min_by_key(|(_, _, dist)| *dist)
-            .map(|(key, pt, _)| (key.clone(), Pt2D::new(pt.x(), pt.y())))
+            .collect();
+        results.sort_by_key(|(_, _, dist)| *dist);
+        results
     }
 }
 
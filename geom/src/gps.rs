@@ -1,9 +1,11 @@
-use crate::{Distance, HashablePt2D};
+use crate::{Angle, Distance, HashablePt2D};
 use ordered_float::NotNan;
 use serde_derive::{Deserialize, Serialize};
 use std::f64;
 use std::fmt;
 
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
 // longitude is x, latitude is y
 #[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct LonLat {
@@ -19,9 +21,8 @@ impl LonLat {
         }
     }
 
-    pub fn gps_dist_meters(&self, other: LonLat) -> Distance {
-        // Haversine distance
-        let earth_radius_m = 6_371_000.0;
+    // Great-circle distance, no projection needed
+    pub fn haversine_dist(&self, other: LonLat) -> Distance {
         let lon1 = self.longitude.to_radians();
         let lon2 = other.longitude.to_radians();
         let lat1 = self.latitude.to_radians();
@@ -33,7 +34,21 @@ impl LonLat {
         let a = (delta_lat / 2.0).sin().powi(2)
             + (delta_lon / 2.0).sin().powi(2) * lat1.cos() * lat2.cos();
         let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
-        Distance::meters(earth_radius_m * c)
+        Distance::meters(EARTH_RADIUS_M * c)
+    }
+
+    // The initial compass bearing along the great circle towards the other point. Unlike most
+    // angles, 0 is north and it increases clockwise, so 90 degrees is east.
+    pub fn bearing(&self, other: LonLat) -> Angle {
+        let lon1 = self.longitude.to_radians();
+        let lon2 = other.longitude.to_radians();
+        let lat1 = self.latitude.to_radians();
+        let lat2 = other.latitude.to_radians();
+
+        let delta_lon = lon2 - lon1;
+        let y = delta_lon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * delta_lon.cos();
+        Angle::new(y.atan2(x))
     }
 
     // Pretty meaningless units, for comparing distances very roughly
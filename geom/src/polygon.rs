@@ -1,4 +1,5 @@
-use crate::{Bounds, Distance, HashablePt2D, Pt2D};
+use crate::{Angle, Bounds, Distance, HashablePt2D, Pt2D};
+use clipping::CPolygon;
 use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 
@@ -110,6 +111,30 @@ impl Polygon {
         self.triangles().into_iter().any(|tri| tri.contains_pt(pt))
     }
 
+    // In square meters. Just sums up the triangles, so polygons with overlapping triangles (like
+    // thickened PolyLines) get overcounted a bit.
+    pub fn area(&self) -> f64 {
+        self.triangles().into_iter().map(|tri| tri.area()).sum()
+    }
+
+    // True if any two triangles overlap, beyond just sharing an edge or a point. Slow; meant for
+    // tests.
+    pub fn is_self_intersecting(&self) -> bool {
+        let triangles: Vec<Triangle> = self
+            .triangles()
+            .into_iter()
+            .filter(|tri| tri.area() > 0.0)
+            .collect();
+        for (idx, tri1) in triangles.iter().enumerate() {
+            for tri2 in &triangles[idx + 1..] {
+                if tri1.overlaps(tri2) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
     pub fn get_bounds(&self) -> Bounds {
         Bounds::from(&self.points)
     }
@@ -121,6 +146,24 @@ impl Polygon {
         }
     }
 
+    // Rotates around the origin, then moves the origin to the given point.
+    pub fn rotate_and_translate(&self, rotation: Angle, translation: Pt2D) -> Polygon {
+        let (sin, cos) = rotation.normalized_radians().sin_cos();
+        Polygon {
+            points: self
+                .points
+                .iter()
+                .map(|pt| {
+                    Pt2D::new(
+                        translation.x() + pt.x() * cos - pt.y() * sin,
+                        translation.y() + pt.x() * sin + pt.y() * cos,
+                    )
+                })
+                .collect(),
+            indices: self.indices.clone(),
+        }
+    }
+
     // The order of these points depends on the constructor! The first and last point may or may
     // not match. Polygons constructed from PolyLines will have a very weird order.
     pub fn points(&self) -> &Vec<Pt2D> {
@@ -161,6 +204,27 @@ impl Polygon {
         }
         Polygon::precomputed(points, indices)
     }
+
+    // The boolean operations only work when both polygons have their points in order around the
+    // boundary, like the ones from Polygon::new. Polygons from thickened PolyLines don't. The
+    // clipping can also go wrong on degenerate input, like two polygons sharing an edge, so
+    // callers should sanity check the results.
+    pub fn intersection(&self, other: &Polygon) -> Vec<Polygon> {
+        to_cpoly(self)
+            .intersection(&mut to_cpoly(other))
+            .into_iter()
+            .filter_map(from_cpoly)
+            .collect()
+    }
+
+    // The parts of self not covered by other.
+    pub fn difference(&self, other: &Polygon) -> Vec<Polygon> {
+        to_cpoly(self)
+            .difference(&mut to_cpoly(other))
+            .into_iter()
+            .filter_map(from_cpoly)
+            .collect()
+    }
 }
 
 impl fmt::Display for Polygon {
@@ -182,6 +246,28 @@ impl fmt::Display for Polygon {
     }
 }
 
+fn to_cpoly(poly: &Polygon) -> CPolygon {
+    let mut pts: Vec<[f64; 2]> = poly.points.iter().map(|pt| [pt.x(), pt.y()]).collect();
+    if pts[0] == *pts.last().unwrap() {
+        pts.pop();
+    }
+    CPolygon::from_vec(&pts)
+}
+
+fn from_cpoly(raw_pts: Vec<[f64; 2]>) -> Option<Polygon> {
+    if raw_pts.len() < 3 {
+        return None;
+    }
+    let mut pts: Vec<Pt2D> = raw_pts
+        .into_iter()
+        .map(|pt| Pt2D::new(pt[0], pt[1]))
+        .collect();
+    if pts[0] != *pts.last().unwrap() {
+        pts.push(pts[0]);
+    }
+    Some(Polygon::new(&pts))
+}
+
 #[derive(Clone, Debug)]
 pub struct Triangle {
     pub pt1: Pt2D,
@@ -194,6 +280,50 @@ impl Triangle {
         Triangle { pt1, pt2, pt3 }
     }
 
+    // In square meters
+    pub fn area(&self) -> f64 {
+        let x1 = self.pt1.x();
+        let y1 = self.pt1.y();
+        let x2 = self.pt2.x();
+        let y2 = self.pt2.y();
+        let x3 = self.pt3.x();
+        let y3 = self.pt3.y();
+
+        let cross_product = (x2 - x1) * (y3 - y1) - (y2 - y1) * (x3 - x1);
+        cross_product.abs() / 2.0
+    }
+
+    // Separating axis test. Touching along an edge or at a point doesn't count.
+    fn overlaps(&self, other: &Triangle) -> bool {
+        let project = |tri: &Triangle, axis: (f64, f64)| {
+            let dots: Vec<f64> = vec![tri.pt1, tri.pt2, tri.pt3]
+                .into_iter()
+                .map(|pt| pt.x() * axis.0 + pt.y() * axis.1)
+                .collect();
+            (
+                dots.iter().cloned().fold(std::f64::MAX, f64::min),
+                dots.iter().cloned().fold(std::f64::MIN, f64::max),
+            )
+        };
+        let epsilon = 0.000_000_1;
+
+        for tri in &[self, other] {
+            for (pt1, pt2) in &[(tri.pt1, tri.pt2), (tri.pt2, tri.pt3), (tri.pt3, tri.pt1)] {
+                let len = pt1.raw_dist_to(*pt2);
+                if len == 0.0 {
+                    continue;
+                }
+                let normal = ((pt1.y() - pt2.y()) / len, (pt2.x() - pt1.x()) / len);
+                let (min1, max1) = project(self, normal);
+                let (min2, max2) = project(other, normal);
+                if max1 <= min2 + epsilon || max2 <= min1 + epsilon {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
     fn is_convex(&self) -> bool {
         let x1 = self.pt1.x();
         let y1 = self.pt1.y();
@@ -6,6 +6,10 @@ use serde_derive::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fmt;
 
+// When a miter at a bend would stick out more than this many widths from the original point, bevel
+// the corner instead.
+const MITER_LIMIT: f64 = 2.0;
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PolyLine {
     pts: Vec<Pt2D>,
@@ -91,6 +95,16 @@ impl PolyLine {
         PolyLine::new(pts)
     }
 
+    // Pointwise, so the two must have the same number of points.
+    pub fn approx_eq(&self, other: &PolyLine, threshold: Distance) -> bool {
+        self.pts.len() == other.pts.len()
+            && self
+                .pts
+                .iter()
+                .zip(other.pts.iter())
+                .all(|(pt1, pt2)| pt1.approx_eq(*pt2, threshold))
+    }
+
     pub fn extend(self, other: PolyLine) -> PolyLine {
         assert_eq!(*self.pts.last().unwrap(), other.pts[0]);
 
@@ -247,6 +261,50 @@ impl PolyLine {
         self.exact_slice(self.length() / 2.0, self.length())
     }
 
+    // Panics if either half would be too short to be a PolyLine.
+    pub fn split_at_dist(&self, dist: Distance) -> (PolyLine, PolyLine) {
+        if dist < EPSILON_DIST || dist > self.length() - EPSILON_DIST {
+            panic!(
+                "Can't split a PL of length {} at {}; one half would be empty",
+                self.length(),
+                dist
+            );
+        }
+        (
+            self.exact_slice(Distance::ZERO, dist),
+            self.exact_slice(dist, self.length()),
+        )
+    }
+
+    // The point doesn't have to be on the polyline; it's projected onto the closest spot first.
+    // Returns None if that lands too close to either end.
+    pub fn split_at_pt(&self, pt: Pt2D) -> Option<(PolyLine, PolyLine)> {
+        let dist = self.project_pt(pt);
+        if dist < EPSILON_DIST || dist > self.length() - EPSILON_DIST {
+            return None;
+        }
+        Some(self.split_at_dist(dist))
+    }
+
+    // Returns the distance along the polyline of the closest point to pt.
+    fn project_pt(&self, pt: Pt2D) -> Distance {
+        let mut best: Option<(Distance, Distance)> = None;
+        let mut dist_so_far = Distance::ZERO;
+        for l in self.lines() {
+            let (dx, dy) = (l.pt2().x() - l.pt1().x(), l.pt2().y() - l.pt1().y());
+            let percent = (((pt.x() - l.pt1().x()) * dx + (pt.y() - l.pt1().y()) * dy)
+                / (dx * dx + dy * dy))
+                .max(0.0)
+                .min(1.0);
+            let dist_away = l.percent_along(percent).dist_to(pt);
+            if best.map(|(d, _)| dist_away < d).unwrap_or(true) {
+                best = Some((dist_away, dist_so_far + percent * l.length()));
+            }
+            dist_so_far += l.length();
+        }
+        best.unwrap().1
+    }
+
     // TODO return result with an error message
     pub fn safe_dist_along(&self, dist_along: Distance) -> Option<(Pt2D, Angle)> {
         if dist_along < Distance::ZERO || dist_along > self.length() {
@@ -379,18 +437,87 @@ impl PolyLine {
         result
     }
 
+    // Both edges of the thickened line, with the same number of points. Unlike
+    // shift_with_sharp_angles, very sharp bends don't get a miter that shoots far away or folds
+    // back over the other side. Instead, the outside of the bend is beveled. The inside still meets
+    // at a miter, unless the segments are too short for that, in which case it pinches to the
+    // original point right around the bend.
+    fn thick_sides(&self, half_width: Distance) -> (Vec<Pt2D>, Vec<Pt2D>) {
+        let first = Line::new(self.pts[0], self.pts[1]);
+        let mut side1 = vec![first.shift_either_direction(half_width).pt1()];
+        let mut side2 = vec![first.shift_either_direction(-half_width).pt1()];
+
+        for triple in self.pts.windows(3) {
+            let (pt1, pt2, pt3) = (triple[0], triple[1], triple[2]);
+            let (l1a, l2a, miter_a) = shift_bend(pt1, pt2, pt3, half_width);
+            let (l1b, l2b, miter_b) = shift_bend(pt1, pt2, pt3, -half_width);
+            let too_sharp = match miter_a {
+                Some(pt) => pt.raw_dist_to(pt2) > MITER_LIMIT * 2.0 * half_width.inner_meters(),
+                // Parallel lines either continue straight ahead, or the line doubles back.
+                None => !l1a.pt2().approx_eq(l2a.pt1(), EPSILON_DIST),
+            };
+            if !too_sharp {
+                side1.push(miter_a.unwrap_or_else(|| l1a.pt2()));
+                side2.push(miter_b.unwrap_or_else(|| l1b.pt2()));
+                continue;
+            }
+
+            // The inside of the bend is on the side the line turns towards.
+            let turn = cross(pt1, pt2, pt3);
+            let side1_inside = cross(pt1, pt2, l1a.pt2()) * turn > 0.0;
+            let (out1, out2, in1, in2, inner_miter) = if side1_inside {
+                (l1b, l2b, l1a, l2a, miter_a)
+            } else {
+                (l1a, l2a, l1b, l2b, miter_b)
+            };
+            let (outside, inside) = match inner_miter {
+                // The inner edges meet before either segment ends, so the inside is a plain miter.
+                Some(pt) if in1.contains_pt(pt) && in2.contains_pt(pt) => {
+                    (vec![out1.pt2(), out2.pt1()], vec![pt, pt])
+                }
+                // Otherwise pinch the inside to the original point, but only right around the
+                // bend.
+                _ => {
+                    let taper = |l: &Line| {
+                        Distance::meters(
+                            (2.0 * half_width.inner_meters()).min(l.length().inner_meters() / 2.0),
+                        )
+                    };
+                    (
+                        vec![out1.pt2(), out1.pt2(), out2.pt1()],
+                        vec![
+                            in1.dist_along(in1.length() - taper(&in1)),
+                            pt2,
+                            in2.dist_along(taper(&in2)),
+                        ],
+                    )
+                }
+            };
+            if side1_inside {
+                side1.extend(inside);
+                side2.extend(outside);
+            } else {
+                side1.extend(outside);
+                side2.extend(inside);
+            }
+        }
+
+        let last = Line::new(self.pts[self.pts.len() - 2], self.pts[self.pts.len() - 1]);
+        side1.push(last.shift_either_direction(half_width).pt2());
+        side2.push(last.shift_either_direction(-half_width).pt2());
+        (side1, side2)
+    }
+
     pub fn make_polygons(&self, width: Distance) -> Polygon {
         // TODO Don't use the angle corrections yet -- they seem to do weird things.
-        let side1 = self.shift_with_sharp_angles(width / 2.0);
-        let side2 = self.shift_with_sharp_angles(-width / 2.0);
-        assert_eq!(side1.len(), side2.len());
+        let (side1, side2) = self.thick_sides(width / 2.0);
 
         let side2_offset = side1.len();
         let mut points = side1;
         points.extend(side2);
         let mut indices = Vec::new();
 
-        for high_idx in 1..self.pts.len() {
+        for high_idx in 1..side2_offset {
             // Duplicate first point, since that's what graphics layer expects
             indices.extend(vec![high_idx, high_idx - 1, side2_offset + high_idx - 1]);
             indices.extend(vec![
@@ -677,3 +804,16 @@ fn check_angles(orig: &PolyLine, fixed: PolyLine) -> Warn<PolyLine> {
 fn to_set(pts: &[Pt2D]) -> HashSet<HashablePt2D> {
     pts.iter().map(|pt| HashablePt2D::from(*pt)).collect()
 }
+
+// Both lines of a bend shifted to one side, and where they'd meet.
+fn shift_bend(pt1: Pt2D, pt2: Pt2D, pt3: Pt2D, width: Distance) -> (Line, Line, Option<Pt2D>) {
+    let l1 = Line::new(pt1, pt2).shift_either_direction(width);
+    let l2 = Line::new(pt2, pt3).shift_either_direction(width);
+    let miter = l1.infinite().intersection(&l2.infinite());
+    (l1, l2, miter)
+}
+
+// Positive when pt1 -> pt2 -> pt3 turns one way, negative the other, 0 when straight.
+fn cross(pt1: Pt2D, pt2: Pt2D, pt3: Pt2D) -> f64 {
+    (pt2.x() - pt1.x()) * (pt3.y() - pt2.y()) - (pt2.y() - pt1.y()) * (pt3.x() - pt2.x())
+}
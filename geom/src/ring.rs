@@ -0,0 +1,88 @@
+use crate::{Distance, HashablePt2D, Line, Polygon, Pt2D};
+use abstutil::Error;
+use geo::algorithm::convexhull::ConvexHull;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// A closed loop of points: the last point is always the first one again. Rings are oriented
+// clockwise as drawn (remember y points down) and never cross themselves.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Ring {
+    pts: Vec<Pt2D>,
+}
+
+impl Ring {
+    // Closes off the points if needed -- it's fine if the input already repeats the first point a
+    // few times at the end -- and reverses counter-clockwise input.
+    pub fn new(mut pts: Vec<Pt2D>) -> Result<Ring, Error> {
+        while pts.len() > 1 && pts.last().unwrap().approx_eq(pts[0], Distance::meters(0.1)) {
+            pts.pop();
+        }
+        if pts.len() < 3 {
+            return Err(Error::new(format!(
+                "Ring needs at least 3 distinct points, not {:?}",
+                pts
+            )));
+        }
+        let distinct: HashSet<HashablePt2D> =
+            pts.iter().map(|pt| HashablePt2D::from(*pt)).collect();
+        if distinct.len() != pts.len() {
+            return Err(Error::new(format!("Ring has repeat points: {:?}", pts)));
+        }
+        pts.push(pts[0]);
+
+        // Shoelace formula. Since y points down, positive area means clockwise.
+        let area: f64 = pts
+            .windows(2)
+            .map(|pair| pair[0].x() * pair[1].y() - pair[1].x() * pair[0].y())
+            .sum();
+        if area < 0.0 {
+            pts.reverse();
+        }
+
+        let mut lines: Vec<Line> = Vec::new();
+        for pair in pts.windows(2) {
+            if let Some(l) = Line::maybe_new(pair[0], pair[1]) {
+                lines.push(l);
+            } else {
+                return Err(Error::new(format!("Ring has ~dupe pts: {:?}", pts)));
+            }
+        }
+        // Neighboring lines always touch, including the last and first.
+        for (idx1, l1) in lines.iter().enumerate() {
+            for (idx2, l2) in lines.iter().enumerate().skip(idx1 + 2) {
+                if idx1 == 0 && idx2 == lines.len() - 1 {
+                    continue;
+                }
+                if let Some(hit) = l1.intersection(l2) {
+                    return Err(Error::new(format!("Ring crosses itself at {}", hit)));
+                }
+            }
+        }
+
+        Ok(Ring { pts })
+    }
+
+    // The smallest convex ring around all of the points. Still fails if they're all on one line.
+    pub fn convex_hull(pts: &Vec<Pt2D>) -> Result<Ring, Error> {
+        let mp: geo::MultiPoint<f64> = pts
+            .iter()
+            .map(|pt| geo::Point::new(pt.x(), pt.y()))
+            .collect();
+        let hull: geo::Polygon<f64> = mp.convex_hull();
+        Ring::new(
+            hull.exterior()
+                .points_iter()
+                .map(|pt| Pt2D::new(pt.x(), pt.y()))
+                .collect(),
+        )
+    }
+
+    pub fn points(&self) -> &Vec<Pt2D> {
+        &self.pts
+    }
+
+    pub fn to_polygon(&self) -> Polygon {
+        Polygon::new(&self.pts)
+    }
+}
@@ -13,6 +13,50 @@ pub struct Route {
     pub stops: Vec<LonLat>,
 }
 
+// A route with only the stops that lie inside some boundary.
+#[derive(Debug, PartialEq)]
+pub struct ClippedRoute {
+    pub stops: Vec<LonLat>,
+    // True if the original route had stops out of bounds before or after the ones kept. Buses
+    // should enter or exit the map there.
+    pub enters_from_border: bool,
+    pub exits_to_border: bool,
+}
+
+impl Route {
+    // Keeps the longest run of consecutive in-bounds stops. A route that leaves the boundary and
+    // comes back would otherwise get stitched together across the gap. Returns None if fewer than
+    // two stops survive.
+    pub fn clip<F: Fn(LonLat) -> bool>(&self, in_bounds: F) -> Option<ClippedRoute> {
+        let mut best: Option<(usize, usize)> = None;
+        let mut current_start: Option<usize> = None;
+        for (idx, pt) in self.stops.iter().enumerate() {
+            if in_bounds(*pt) {
+                let start = *current_start.get_or_insert(idx);
+                let longer = match best {
+                    Some((s, e)) => idx - start > e - s,
+                    None => true,
+                };
+                if longer {
+                    best = Some((start, idx));
+                }
+            } else {
+                current_start = None;
+            }
+        }
+
+        let (start, end) = best?;
+        if start == end {
+            return None;
+        }
+        Some(ClippedRoute {
+            stops: self.stops[start..=end].to_vec(),
+            enters_from_border: start != 0,
+            exits_to_border: end != self.stops.len() - 1,
+        })
+    }
+}
+
 pub fn load(dir_path: &str) -> Result<Vec<Route>, Error> {
     println!("Loading GTFS from {}", dir_path);
     let timer = Instant::now();
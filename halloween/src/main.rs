@@ -65,7 +65,7 @@ impl GUI for UI {
 
 fn main() {
     let flags = Flags::from_args();
-    ezgui::run("Halloween tech demo", 1024.0, 768.0, |ctx| {
+    ezgui::run("Halloween tech demo", 1024.0, 768.0, false, |ctx| {
         UI::new(flags, ctx)
     });
 }
@@ -1,6 +1,6 @@
 use abstutil::Timer;
 use geom::Duration;
-use sim::{GetDrawAgents, Scenario, SimFlags};
+use sim::{GetDrawAgents, ODMatrix, Scenario, SimFlags};
 use std::path::Path;
 use structopt::StructOpt;
 
@@ -18,6 +18,14 @@ struct Flags {
     #[structopt(long = "num_agents")]
     num_agents: Option<usize>,
 
+    /// Generate trips from this CSV of zone-to-zone counts instead. Needs --od_zones.
+    #[structopt(long = "od_matrix")]
+    od_matrix: Option<String>,
+
+    /// GeoJSON file with the polygons of the zones in --od_matrix
+    #[structopt(long = "od_zones")]
+    od_zones: Option<String>,
+
     /// Enable cpuprofiler?
     #[structopt(long = "enable_profiler")]
     enable_profiler: bool,
@@ -25,6 +33,23 @@ struct Flags {
     /// Every 0.1s, pretend to draw everything to make sure there are no bugs.
     #[structopt(long = "paranoia")]
     paranoia: bool,
+
+    /// Stop at this time, even if some trips haven't finished.
+    #[structopt(long = "deadline")]
+    deadline: Option<String>,
+
+    /// At the end, write how many agents entered each lane to this JSON file.
+    #[structopt(long = "output_json")]
+    output_json: Option<String>,
+
+    /// At the end, write the time of each finished trip to this CSV file. With
+    /// --record_trip_details, there's one row per leg of each trip.
+    #[structopt(long = "output_csv")]
+    output_csv: Option<String>,
+
+    /// At the end, write how long setup and running the sim took to this JSON file.
+    #[structopt(long = "output_timings")]
+    output_timings: Option<String>,
 }
 
 fn main() {
@@ -39,22 +64,37 @@ fn main() {
     } else {
         None
     };
+    let deadline = flags.deadline.as_ref().map(|time_str| {
+        Duration::parse(time_str).unwrap_or_else(|| panic!("Couldn't parse time {}", time_str))
+    });
 
     // TODO not the ideal way to distinguish what thing we loaded
     let load = flags.sim_flags.load.clone();
     let mut timer = Timer::new("setup headless");
+    if flags.output_timings.is_some() {
+        timer.record_timings();
+    }
     let (map, mut sim, mut rng) = flags.sim_flags.load(None, &mut timer);
 
     if load.starts_with(Path::new("../data/raw_maps/"))
         || load.starts_with(Path::new("../data/maps/"))
     {
-        let s = if let Some(n) = flags.num_agents {
+        let s = if let Some(ref csv_path) = flags.od_matrix {
+            let zones_path = flags
+                .od_zones
+                .as_ref()
+                .expect("--od_matrix needs --od_zones");
+            ODMatrix::load(csv_path, zones_path)
+                .unwrap_or_else(|err| panic!("Couldn't import {}: {}", csv_path, err))
+                .to_scenario("od matrix".to_string(), &map, &mut rng, &mut timer)
+        } else if let Some(n) = flags.num_agents {
             Scenario::scaled_run(&map, n)
         } else {
             Scenario::small_run(&map)
         };
         s.instantiate(&mut sim, &map, &mut rng, &mut timer);
     }
+    let mut timings = timer.timing_records();
     timer.done();
 
     if flags.enable_profiler {
@@ -66,26 +106,63 @@ fn main() {
     }
     let enable_profiler = flags.enable_profiler;
     let paranoia = flags.paranoia;
-    let timer = Timer::new("run sim until done");
-    sim.run_until_done(
-        &map,
-        move |sim, map| {
-            // TODO We want to savestate at the end of this time; this'll happen at the beginning.
-            if Some(sim.time()) == save_at {
-                sim.save();
-                // Some simulations run for a really long time, just do this.
-                if enable_profiler {
-                    cpuprofiler::PROFILER.lock().unwrap().stop().unwrap();
+    let mut timer = Timer::new("run sim until done");
+    if flags.output_timings.is_some() {
+        timer.record_timings();
+    }
+    if let Some(t) = deadline {
+        if !sim.run_until_done_or_deadline(&map, t) {
+            println!("Deadline {} hit before all trips finished", t);
+        }
+    } else {
+        sim.run_until_done(
+            &map,
+            move |sim, map| {
+                // TODO We want to savestate at the end of this time; this'll happen at the
+                // beginning.
+                if Some(sim.time()) == save_at {
+                    sim.save();
+                    // Some simulations run for a really long time, just do this.
+                    if enable_profiler {
+                        cpuprofiler::PROFILER.lock().unwrap().stop().unwrap();
+                    }
                 }
-            }
-            if paranoia {
-                sim.get_all_draw_cars(map);
-            }
-        },
-        None,
-    );
+                if paranoia {
+                    sim.get_all_draw_cars(map);
+                }
+            },
+            None,
+        );
+    }
+    timings.extend(timer.timing_records());
     timer.done();
     println!("Done at {}", sim.time());
+
+    let finished = sim.get_finished_trips();
+    println!("{} unfinished trips", finished.unfinished_trips);
+    for (mode, distrib) in finished.per_mode() {
+        println!("{:?} trips: {}", mode, distrib.describe());
+    }
+    for (i, (count, vehicle_hours)) in sim.get_travel_stats().border_spawn_delays() {
+        println!(
+            "{} cars waited to appear at {}, {:.2} vehicle-hours total",
+            count, i, vehicle_hours
+        );
+    }
+    if let Some(ref path) = flags.output_json {
+        abstutil::write_json(path, sim.get_travel_stats()).unwrap();
+        println!("Wrote {}", path);
+    }
+    if let Some(ref path) = flags.output_csv {
+        sim.save_trips_csv(path, &map).unwrap();
+        println!("Wrote {}", path);
+    }
+    if let Some(ref path) = flags.output_timings {
+        abstutil::write_json(path, &timings).unwrap();
+        println!("Wrote {}", path);
+    }
+    sim.save_replay_log();
+
     if flags.enable_profiler && save_at.is_none() {
         cpuprofiler::PROFILER.lock().unwrap().stop().unwrap();
     }
@@ -0,0 +1,100 @@
+// Tiny maps built from a handful of straight roads, for tests and experiments that need some
+// particular shape that isn't worth drawing in the synthetic map editor. All coordinates are small
+// offsets (in degrees) from somewhere in Seattle.
+
+use abstutil::Timer;
+use geom::LonLat;
+use map_model::{raw_data, LaneID, LaneType, Map, Road};
+use std::collections::{BTreeMap, HashSet};
+
+pub fn pt(lon: f64, lat: f64) -> LonLat {
+    LonLat::new(-122.3 + lon, 47.6 + lat)
+}
+
+// No parking or speed limit. Tests that need those (or more tags) modify the result.
+pub fn road(osm_way_id: i64, highway: &str, points: Vec<LonLat>) -> raw_data::Road {
+    let mut osm_tags = BTreeMap::new();
+    osm_tags.insert("highway".to_string(), highway.to_string());
+    raw_data::Road {
+        i1: raw_data::StableIntersectionID(0),
+        i2: raw_data::StableIntersectionID(0),
+        points,
+        osm_node_ids: Vec::new(),
+        osm_tags,
+        osm_way_id,
+        parking_lane_fwd: false,
+        parking_lane_back: false,
+        parking_hours_fwd: None,
+        parking_hours_back: None,
+        speed_limit: None,
+    }
+}
+
+// A residential road with one lane, one way
+pub fn oneway(osm_way_id: i64, points: Vec<LonLat>) -> raw_data::Road {
+    let mut r = road(osm_way_id, "residential", points);
+    r.osm_tags.insert("oneway".to_string(), "yes".to_string());
+    r
+}
+
+// The closed ring around the box with these corners
+pub fn rectangle(min: LonLat, max: LonLat) -> Vec<LonLat> {
+    vec![
+        min,
+        LonLat::new(max.longitude, min.latitude),
+        max,
+        LonLat::new(min.longitude, max.latitude),
+        min,
+    ]
+}
+
+// About 15m by 10m, centered on the point
+pub fn building(osm_way_id: i64, lon: f64, lat: f64) -> raw_data::Building {
+    raw_data::Building {
+        points: rectangle(
+            pt(lon - 0.0001, lat - 0.00005),
+            pt(lon + 0.0001, lat + 0.00005),
+        ),
+        osm_tags: BTreeMap::new(),
+        osm_way_id,
+        num_residential_units: None,
+    }
+}
+
+// Split up the roads the same way as real OSM input, without any roundabouts.
+pub fn raw_map(roads: Vec<raw_data::Road>, buildings: Vec<raw_data::Building>) -> raw_data::Map {
+    convert_osm::split_up_roads(
+        (roads, buildings, Vec::new(), HashSet::new()),
+        false,
+        &mut Timer::throwaway(),
+    )
+}
+
+// Only call this after everything else has been added to the raw map, since it fixes the GPS
+// bounds.
+pub fn set_boundary(raw: &mut raw_data::Map, min: LonLat, max: LonLat) {
+    raw.boundary_polygon = rectangle(min, max);
+    raw.compute_gps_bounds();
+}
+
+pub fn build_map(name: &str, mut raw: raw_data::Map, min: LonLat, max: LonLat) -> Map {
+    set_boundary(&mut raw, min, max);
+    Map::create_from_raw(name.to_string(), raw, &mut Timer::throwaway())
+}
+
+// The first road made from this OSM way. Ways get split up at intersections, so there may be more.
+pub fn road_by_osm_id(map: &Map, osm_way_id: i64) -> &Road {
+    map.all_roads()
+        .iter()
+        .find(|r| r.osm_way_id == osm_way_id)
+        .unwrap()
+}
+
+// The first driving lane of road_by_osm_id, pointing forwards if there is one
+pub fn driving_lane(map: &Map, osm_way_id: i64) -> LaneID {
+    *road_by_osm_id(map, osm_way_id)
+        .all_lanes()
+        .iter()
+        .find(|l| map.get_l(**l).lane_type == LaneType::Driving)
+        .unwrap()
+}
@@ -0,0 +1,35 @@
+use crate::{Map, Position};
+use geom::Pt2D;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BikeRackID(pub usize);
+
+impl fmt::Display for BikeRackID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BikeRackID({0})", self.0)
+    }
+}
+
+// Somewhere to leave a bike, from OSM's amenity=bicycle_parking
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BikeRack {
+    pub id: BikeRackID,
+    pub osm_node_id: i64,
+    pub point: Pt2D,
+    pub capacity: usize,
+    pub sidewalk_pos: Position,
+}
+
+impl BikeRack {
+    // Where a bike pulls over to use this rack. Lane edits can change this, so it isn't cached.
+    pub fn driving_pos(&self, map: &Map) -> Option<Position> {
+        let road = map.get_parent(self.sidewalk_pos.lane());
+        let lane = road.sidewalk_to_bike(self.sidewalk_pos.lane())?;
+        if !map.is_bikeable(lane) {
+            return None;
+        }
+        Some(self.sidewalk_pos.equiv_pos(lane, map))
+    }
+}
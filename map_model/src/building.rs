@@ -23,13 +23,90 @@ pub struct FrontPath {
     pub line: Line,
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum BuildingType {
     Residence,
-    Business,
+    Retail,
+    Office,
+    School,
+    Industrial,
     Unknown,
 }
 
+// Checked in order, so the first matching tag wins. A value of "*" matches anything.
+const TAG_TO_BUILDING_TYPE: &[(&str, &str, BuildingType)] = &[
+    ("amenity", "school", BuildingType::School),
+    ("amenity", "kindergarten", BuildingType::School),
+    ("amenity", "college", BuildingType::School),
+    ("amenity", "university", BuildingType::School),
+    ("building", "school", BuildingType::School),
+    ("building", "kindergarten", BuildingType::School),
+    ("building", "college", BuildingType::School),
+    ("building", "university", BuildingType::School),
+    ("building", "apartments", BuildingType::Residence),
+    ("building", "residential", BuildingType::Residence),
+    ("building", "house", BuildingType::Residence),
+    ("building", "detached", BuildingType::Residence),
+    ("building", "terrace", BuildingType::Residence),
+    ("building", "dormitory", BuildingType::Residence),
+    ("shop", "*", BuildingType::Retail),
+    ("office", "*", BuildingType::Office),
+    // Restaurants, banks, and such. The amenities that aren't businesses are mostly mapped as
+    // points or areas, not buildings.
+    ("amenity", "*", BuildingType::Retail),
+    ("building", "retail", BuildingType::Retail),
+    ("building", "supermarket", BuildingType::Retail),
+    ("building", "commercial", BuildingType::Office),
+    ("building", "office", BuildingType::Office),
+    ("building", "industrial", BuildingType::Industrial),
+    ("building", "warehouse", BuildingType::Industrial),
+];
+
+impl BuildingType {
+    pub fn all() -> Vec<BuildingType> {
+        vec![
+            BuildingType::Residence,
+            BuildingType::Retail,
+            BuildingType::Office,
+            BuildingType::School,
+            BuildingType::Industrial,
+            BuildingType::Unknown,
+        ]
+    }
+
+    // Permit data about residential units trumps the OSM tags.
+    pub fn classify(
+        num_residential_units: Option<usize>,
+        tags: &BTreeMap<String, String>,
+    ) -> BuildingType {
+        if num_residential_units.is_some() {
+            return BuildingType::Residence;
+        }
+        for (key, value, bldg_type) in TAG_TO_BUILDING_TYPE {
+            if let Some(v) = tags.get(*key) {
+                if *value == "*" || v == value {
+                    return *bldg_type;
+                }
+            }
+        }
+        BuildingType::Unknown
+    }
+}
+
+impl fmt::Display for BuildingType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            BuildingType::Residence => "residence",
+            BuildingType::Retail => "retail",
+            BuildingType::Office => "office",
+            BuildingType::School => "school",
+            BuildingType::Industrial => "industrial",
+            BuildingType::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Building {
     pub id: BuildingID,
@@ -1,5 +1,6 @@
-use crate::{LaneID, Position};
+use crate::{IntersectionID, LaneID, Position};
 use abstutil;
+use geom::Pt2D;
 use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 
@@ -44,4 +45,60 @@ pub struct BusRoute {
     pub id: BusRouteID,
     pub name: String,
     pub stops: Vec<BusStopID>,
+    // Set when the route was truncated by the map boundary. Buses spawn at the start border; they
+    // still loop back to the first stop, so the end border is just a marker for now.
+    pub start_border: Option<IntersectionID>,
+    pub end_border: Option<IntersectionID>,
+}
+
+// Something wrong with a bus route from the input data. Map authors can use these to fix transit
+// data.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BusRouteProblem {
+    // The stop wasn't close enough to any sidewalk, so it was skipped.
+    StopFarFromSidewalk(Pt2D),
+    // The stop's sidewalk has no lane next to it that buses can use, so it was skipped.
+    StopNotOnTransitLane(LaneID),
+    // After skipping stops, not enough were left to make a route.
+    TooFewStops(usize),
+    // Consecutive stops on the same lane, probably in the wrong order
+    StopsOnSameLane(BusStopID, BusStopID),
+    // Buses can't get from one stop to the next
+    UnreachableStop(BusStopID, BusStopID),
+}
+
+impl fmt::Display for BusRouteProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BusRouteProblem::StopFarFromSidewalk(pt) => {
+                write!(f, "stop at {} isn't near a sidewalk", pt)
+            }
+            BusRouteProblem::StopNotOnTransitLane(sidewalk) => {
+                write!(f, "stop on {} has no lane for buses next to it", sidewalk)
+            }
+            BusRouteProblem::TooFewStops(n) => write!(f, "only {} stops left", n),
+            BusRouteProblem::StopsOnSameLane(bs1, bs2) => {
+                write!(f, "{} and {} are on the same lane", bs1, bs2)
+            }
+            BusRouteProblem::UnreachableStop(bs1, bs2) => {
+                write!(f, "can't reach {} from {}", bs2, bs1)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BusRouteReport {
+    pub route_name: String,
+    // False if the problems kept the route out of the map entirely
+    pub kept: bool,
+    pub problems: Vec<BusRouteProblem>,
+}
+
+// A stop from the input data that never made it onto the map
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DroppedBusStop {
+    pub pt: Pt2D,
+    // Either StopFarFromSidewalk or StopNotOnTransitLane
+    pub problem: BusRouteProblem,
 }
@@ -0,0 +1,240 @@
+use crate::{
+    BuildingID, Intersection, IntersectionID, IntersectionType, Lane, LaneID, LaneType, Map,
+    MentionedID, Position, RoadID, TurnID,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Lanes and buildings that agents can't get to or away from. Aggressive lane edits can cause
+// these, and so can bugs in map conversion.
+#[derive(Debug, PartialEq)]
+pub struct ConnectivityReport {
+    // Driving, biking, bus, and HOV lanes that no allowed turn leads into. Lanes starting at a
+    // border are fine; agents appear there.
+    pub no_incoming_turns: Vec<LaneID>,
+    // Same, but nothing leads out of them. Lanes ending at a border are fine.
+    pub no_outgoing_turns: Vec<LaneID>,
+    // Sidewalks not connected to the largest group of sidewalks
+    pub disconnected_sidewalks: Vec<LaneID>,
+    // Their sidewalk is disconnected, or the driving lane in front of them is orphaned
+    pub unreachable_buildings: Vec<BuildingID>,
+    // Movements through a merged short road that no turn in the merged intersection covers
+    pub lost_movements: Vec<(IntersectionID, RoadID, RoadID)>,
+}
+
+impl ConnectivityReport {
+    pub fn new(map: &Map) -> ConnectivityReport {
+        let (no_incoming_turns, no_outgoing_turns) =
+            find_orphaned_lanes(map.all_lanes(), map.all_intersections(), |t| {
+                map.is_turn_allowed(t)
+            });
+
+        let disconnected_sidewalks = find_disconnected_sidewalks(map);
+
+        let bad_sidewalks: BTreeSet<LaneID> = disconnected_sidewalks.iter().cloned().collect();
+        let orphaned_lanes: BTreeSet<LaneID> = no_incoming_turns
+            .iter()
+            .chain(no_outgoing_turns.iter())
+            .cloned()
+            .collect();
+        let mut unreachable_buildings = Vec::new();
+        for b in map.all_buildings() {
+            let walkable = !bad_sidewalks.contains(&b.sidewalk());
+            let drivable = match Position::bldg_via_driving(b.id, map) {
+                Ok(pos) => !orphaned_lanes.contains(&pos.lane()),
+                // Never had driving access, so nothing broke
+                Err(_) => true,
+            };
+            if !walkable || !drivable {
+                unreachable_buildings.push(b.id);
+            }
+        }
+
+        ConnectivityReport {
+            no_incoming_turns,
+            no_outgoing_turns,
+            disconnected_sidewalks,
+            unreachable_buildings,
+            lost_movements: find_lost_movements(map),
+        }
+    }
+
+    pub fn num_problems(&self) -> usize {
+        self.no_incoming_turns.len()
+            + self.no_outgoing_turns.len()
+            + self.disconnected_sidewalks.len()
+            + self.unreachable_buildings.len()
+            + self.lost_movements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_problems() == 0
+    }
+
+    // One line per problem, mentioning IDs in their Display format, along with where to look
+    pub fn problems(&self) -> Vec<(String, MentionedID)> {
+        let mut problems = Vec::new();
+        for l in &self.no_incoming_turns {
+            problems.push((
+                format!("No allowed turns lead into {}", l),
+                MentionedID::Lane(*l),
+            ));
+        }
+        for l in &self.no_outgoing_turns {
+            problems.push((
+                format!("No allowed turns lead out of {}", l),
+                MentionedID::Lane(*l),
+            ));
+        }
+        for l in &self.disconnected_sidewalks {
+            problems.push((
+                format!("{} is disconnected from most sidewalks", l),
+                MentionedID::Lane(*l),
+            ));
+        }
+        for b in &self.unreachable_buildings {
+            problems.push((format!("{} can't be reached", b), MentionedID::Building(*b)));
+        }
+        for (i, r1, r2) in &self.lost_movements {
+            problems.push((
+                format!(
+                    "Merging roads into {} lost the movement from {} to {}",
+                    i, r1, r2
+                ),
+                MentionedID::Intersection(*i),
+            ));
+        }
+        problems
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        self.problems().into_iter().map(|(line, _)| line).collect()
+    }
+}
+
+// Vehicle lanes that no allowed turn leads (into, out of). Takes the pieces instead of a Map, so
+// that map conversion can check before there is one.
+pub(crate) fn find_orphaned_lanes<F: Fn(TurnID) -> bool>(
+    lanes: &Vec<Lane>,
+    intersections: &Vec<Intersection>,
+    is_turn_allowed: F,
+) -> (Vec<LaneID>, Vec<LaneID>) {
+    let mut no_incoming_turns = Vec::new();
+    let mut no_outgoing_turns = Vec::new();
+    for l in lanes {
+        if !l.lane_type.is_for_moving_vehicles() {
+            continue;
+        }
+        let src_i = &intersections[l.src_i.0];
+        if src_i.intersection_type != IntersectionType::Border
+            && !src_i
+                .turns
+                .iter()
+                .any(|t| t.dst == l.id && is_turn_allowed(*t))
+        {
+            no_incoming_turns.push(l.id);
+        }
+        let dst_i = &intersections[l.dst_i.0];
+        if dst_i.intersection_type != IntersectionType::Border
+            && !dst_i
+                .turns
+                .iter()
+                .any(|t| t.src == l.id && is_turn_allowed(*t))
+        {
+            no_outgoing_turns.push(l.id);
+        }
+    }
+    (no_incoming_turns, no_outgoing_turns)
+}
+
+// Only checks that some turn exists, not that it's allowed. Banning a turn is a deliberate choice,
+// but a missing turn means map conversion dropped the movement. If edits take away all of the
+// vehicle lanes on either road, there's nothing to connect anymore.
+fn find_lost_movements(map: &Map) -> Vec<(IntersectionID, RoadID, RoadID)> {
+    let mut lost = Vec::new();
+    for i in map.all_intersections() {
+        for (r1, r2) in &i.merged_movements {
+            let from: BTreeSet<LaneID> = map
+                .get_r(*r1)
+                .incoming_lanes(i.id)
+                .iter()
+                .filter(|(_, lt)| lt.is_for_moving_vehicles())
+                .map(|(l, _)| *l)
+                .collect();
+            let to: BTreeSet<LaneID> = map
+                .get_r(*r2)
+                .outgoing_lanes(i.id)
+                .iter()
+                .filter(|(_, lt)| lt.is_for_moving_vehicles())
+                .map(|(l, _)| *l)
+                .collect();
+            if from.is_empty() || to.is_empty() {
+                continue;
+            }
+            if !i
+                .turns
+                .iter()
+                .any(|t| from.contains(&t.src) && to.contains(&t.dst))
+            {
+                lost.push((i.id, *r1, *r2));
+            }
+        }
+    }
+    lost
+}
+
+// Walking is bidirectional, so just find connected components over allowed sidewalk turns.
+fn find_disconnected_sidewalks(map: &Map) -> Vec<LaneID> {
+    let mut neighbors: BTreeMap<LaneID, Vec<LaneID>> = BTreeMap::new();
+    for l in map.all_lanes() {
+        if l.lane_type == LaneType::Sidewalk {
+            neighbors.insert(l.id, Vec::new());
+        }
+    }
+    for t in map.all_turns().values() {
+        if neighbors.contains_key(&t.id.src)
+            && neighbors.contains_key(&t.id.dst)
+            && map.is_turn_allowed(t.id)
+        {
+            neighbors.get_mut(&t.id.src).unwrap().push(t.id.dst);
+            neighbors.get_mut(&t.id.dst).unwrap().push(t.id.src);
+        }
+    }
+
+    let mut components: Vec<BTreeSet<LaneID>> = Vec::new();
+    let mut visited: BTreeSet<LaneID> = BTreeSet::new();
+    for start in neighbors.keys() {
+        if visited.contains(start) {
+            continue;
+        }
+        let mut component = BTreeSet::new();
+        let mut queue = vec![*start];
+        while let Some(l) = queue.pop() {
+            if !visited.insert(l) {
+                continue;
+            }
+            component.insert(l);
+            queue.extend(neighbors[&l].iter().filter(|next| !visited.contains(*next)));
+        }
+        components.push(component);
+    }
+
+    // Ties go to whichever group was found first, which is deterministic.
+    let biggest = match components
+        .iter()
+        .enumerate()
+        .max_by_key(|(idx, c)| (c.len(), std::cmp::Reverse(*idx)))
+    {
+        Some((idx, _)) => idx,
+        None => {
+            return Vec::new();
+        }
+    };
+    let mut disconnected: Vec<LaneID> = components
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != biggest)
+        .flat_map(|(_, c)| c.into_iter())
+        .collect();
+    disconnected.sort();
+    disconnected
+}
@@ -1,6 +1,6 @@
-use crate::{ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, LaneType};
+use crate::{ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, LaneType, RoadID};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MapEdits {
@@ -10,6 +10,23 @@ pub struct MapEdits {
     // TODO Storing the entire thing is maybe a bit dramatic, but works for now.
     pub stop_sign_overrides: BTreeMap<IntersectionID, ControlStopSign>,
     pub traffic_signal_overrides: BTreeMap<IntersectionID, ControlTrafficSignal>,
+    #[serde(default)]
+    pub direction_overrides: BTreeMap<RoadID, RoadDirectionChange>,
+    // U-turns are banned everywhere except dead-ends, unless they're allowed here
+    #[serde(default)]
+    pub allow_uturns: BTreeSet<IntersectionID>,
+}
+
+// Which way the vehicle lanes in the middle of a road point. Sidewalks and parking never switch
+// sides; only the run of driving, bus, bike, and HOV lanes closest to the road's original center
+// line does. Each lane keeps its physical position across the road.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum RoadDirectionChange {
+    MakeOneWayForward,
+    MakeOneWayBackward,
+    // How many of those lanes point backwards; the rest point forwards. Both directions need at
+    // least one lane.
+    MakeTwoWay(usize),
 }
 
 impl MapEdits {
@@ -21,6 +38,8 @@ impl MapEdits {
             lane_overrides: BTreeMap::new(),
             stop_sign_overrides: BTreeMap::new(),
             traffic_signal_overrides: BTreeMap::new(),
+            direction_overrides: BTreeMap::new(),
+            allow_uturns: BTreeSet::new(),
         }
     }
 
@@ -34,4 +53,102 @@ impl MapEdits {
     pub fn save(&self) {
         abstutil::save_json_object("edits", &self.map_name, &self.edits_name, self);
     }
+
+    // What changes when switching from these edits to the other ones.
+    pub fn diff(&self, other: &MapEdits) -> EditDiff {
+        let mut diff = EditDiff {
+            lanes: BTreeMap::new(),
+            roads: BTreeMap::new(),
+            intersections: BTreeMap::new(),
+        };
+        diff_overrides(&mut diff.lanes, &self.lane_overrides, &other.lane_overrides);
+        diff_overrides(
+            &mut diff.roads,
+            &self.direction_overrides,
+            &other.direction_overrides,
+        );
+        diff_overrides(
+            &mut diff.intersections,
+            &self.stop_sign_overrides,
+            &other.stop_sign_overrides,
+        );
+        diff_overrides(
+            &mut diff.intersections,
+            &self.traffic_signal_overrides,
+            &other.traffic_signal_overrides,
+        );
+        let uturns_before: BTreeMap<IntersectionID, ()> =
+            self.allow_uturns.iter().map(|i| (*i, ())).collect();
+        let uturns_after: BTreeMap<IntersectionID, ()> =
+            other.allow_uturns.iter().map(|i| (*i, ())).collect();
+        diff_overrides(&mut diff.intersections, &uturns_before, &uturns_after);
+        diff
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiffType {
+    // Only the newer edits override this
+    Added,
+    // Only the older edits override this
+    Removed,
+    // Both override this, but differently
+    Changed,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct EditDiff {
+    pub lanes: BTreeMap<LaneID, DiffType>,
+    // Roads whose direction changes
+    pub roads: BTreeMap<RoadID, DiffType>,
+    pub intersections: BTreeMap<IntersectionID, DiffType>,
+}
+
+impl EditDiff {
+    pub fn is_empty(&self) -> bool {
+        self.lanes.is_empty() && self.roads.is_empty() && self.intersections.is_empty()
+    }
+
+    // One line per lane, road, or intersection
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (l, diff) in &self.lanes {
+            lines.push(format!("{} {:?}", l, diff));
+        }
+        for (r, diff) in &self.roads {
+            lines.push(format!("{} {:?}", r, diff));
+        }
+        for (i, diff) in &self.intersections {
+            lines.push(format!("{} {:?}", i, diff));
+        }
+        lines
+    }
+}
+
+fn diff_overrides<K: Ord + Copy, V: PartialEq>(
+    results: &mut BTreeMap<K, DiffType>,
+    before: &BTreeMap<K, V>,
+    after: &BTreeMap<K, V>,
+) {
+    let ids: BTreeSet<K> = before.keys().chain(after.keys()).cloned().collect();
+    for id in ids {
+        let diff = match (before.get(&id), after.get(&id)) {
+            (Some(v1), Some(v2)) => {
+                if v1 == v2 {
+                    continue;
+                }
+                DiffType::Changed
+            }
+            (None, Some(_)) => DiffType::Added,
+            (Some(_), None) => DiffType::Removed,
+            (None, None) => unreachable!(),
+        };
+        // An intersection might've been a stop sign in one set of edits and a traffic signal in
+        // the other.
+        if results.contains_key(&id) {
+            results.insert(id, DiffType::Changed);
+        } else {
+            results.insert(id, diff);
+        }
+    }
 }
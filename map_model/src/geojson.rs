@@ -0,0 +1,142 @@
+use crate::{LaneType, Map};
+use abstutil;
+use geom::{GPSBounds, LonLat, Pt2D};
+use std::fs::File;
+use std::io::{BufWriter, Error, Write};
+
+impl Map {
+    // Writes roads (LineStrings), intersections (Points), and buildings (Polygons) in WGS84, so
+    // the map can be inspected in QGIS and friends. Each feature is written as it's produced,
+    // instead of building up the whole document in memory. Objects with points that can't be
+    // expressed in GPS coordinates are skipped.
+    pub fn export_geojson(&self, path: &str) -> Result<(), Error> {
+        println!("Exporting {} to {}...", self.get_name(), path);
+        let gps_bounds = self.get_gps_bounds();
+        let mut out = BufWriter::new(File::create(path)?);
+        let mut first = true;
+        let mut skipped = 0;
+        writeln!(out, "{{\"type\": \"FeatureCollection\", \"features\": [")?;
+
+        for r in self.all_roads() {
+            let coords = match to_gps(r.center_pts.points(), gps_bounds) {
+                Some(c) => c,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let (fwd, back) = r.get_lane_types();
+            let properties = format!(
+                "\"id\": {}, \"osm_way_id\": {}, \"name\": {}, \"lanes_forward\": {}, \
+                 \"lanes_backward\": {}, \"lane_types_forward\": {}, \"lane_types_backward\": {}",
+                r.id.0,
+                r.osm_way_id,
+                abstutil::to_json(&r.get_name()),
+                fwd.len(),
+                back.len(),
+                abstutil::to_json(&lane_types(&fwd)),
+                abstutil::to_json(&lane_types(&back)),
+            );
+            write_feature(
+                &mut out,
+                &mut first,
+                "LineString",
+                &coords_to_json(&coords),
+                &properties,
+            )?;
+        }
+
+        for i in self.all_intersections() {
+            let coords = match to_gps(&vec![i.polygon.center()], gps_bounds) {
+                Some(c) => c,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let properties = format!(
+                "\"id\": {}, \"intersection_type\": {}",
+                i.id.0,
+                abstutil::to_json(&format!("{:?}", i.intersection_type)),
+            );
+            write_feature(
+                &mut out,
+                &mut first,
+                "Point",
+                &format!("[{}, {}]", coords[0].longitude, coords[0].latitude),
+                &properties,
+            )?;
+        }
+
+        for b in self.all_buildings() {
+            let mut coords = match to_gps(b.polygon.points(), gps_bounds) {
+                Some(c) => c,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            // GeoJSON rings have to be closed.
+            if coords[0] != *coords.last().unwrap() {
+                coords.push(coords[0]);
+            }
+            let properties = format!(
+                "\"id\": {}, \"osm_way_id\": {}, \"building_type\": {}",
+                b.id.0,
+                b.osm_way_id,
+                abstutil::to_json(&format!("{:?}", b.building_type)),
+            );
+            write_feature(
+                &mut out,
+                &mut first,
+                "Polygon",
+                &format!("[{}]", coords_to_json(&coords)),
+                &properties,
+            )?;
+        }
+
+        writeln!(out, "]}}")?;
+        out.flush()?;
+        if skipped > 0 {
+            println!("Skipped {} objects that don't fit in the GPS bounds", skipped);
+        }
+        println!("Exported {}", path);
+        Ok(())
+    }
+}
+
+fn write_feature<W: Write>(
+    out: &mut W,
+    first: &mut bool,
+    geometry_type: &str,
+    coordinates: &str,
+    properties: &str,
+) -> Result<(), Error> {
+    if !*first {
+        writeln!(out, ",")?;
+    }
+    *first = false;
+    write!(
+        out,
+        "{{\"type\": \"Feature\", \"geometry\": {{\"type\": \"{}\", \"coordinates\": {}}}, \
+         \"properties\": {{{}}}}}",
+        geometry_type, coordinates, properties
+    )
+}
+
+fn to_gps(pts: &Vec<Pt2D>, gps_bounds: &GPSBounds) -> Option<Vec<LonLat>> {
+    pts.iter().map(|pt| pt.to_gps(gps_bounds)).collect()
+}
+
+fn coords_to_json(pts: &Vec<LonLat>) -> String {
+    let coords: Vec<String> = pts
+        .iter()
+        .map(|gps| format!("[{}, {}]", gps.longitude, gps.latitude))
+        .collect();
+    format!("[{}]", coords.join(", "))
+}
+
+fn lane_types(types: &Vec<LaneType>) -> String {
+    let names: Vec<String> = types.iter().map(|lt| format!("{:?}", lt)).collect();
+    names.join(",")
+}
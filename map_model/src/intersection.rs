@@ -25,7 +25,8 @@ pub enum IntersectionType {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Intersection {
     pub id: IntersectionID,
-    // This needs to be in clockwise orientation, or later rendering of sidewalk corners breaks.
+    // This comes from a Ring, so it's in clockwise orientation. Later rendering of sidewalk
+    // corners breaks otherwise.
     pub polygon: Polygon,
     pub turns: Vec<TurnID>,
 
@@ -39,6 +40,9 @@ pub struct Intersection {
     pub outgoing_lanes: Vec<LaneID>,
 
     pub roads: BTreeSet<RoadID>,
+    // Movements between roads that used to go through a short road merged into this
+    // intersection. There must be at least one turn for each.
+    pub merged_movements: Vec<(RoadID, RoadID)>,
 }
 
 impl Intersection {
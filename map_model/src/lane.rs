@@ -1,12 +1,19 @@
-use crate::{BuildingID, BusStopID, DirectedRoadID, IntersectionID, Map, RoadID};
+use crate::{
+    BuildingID, BusStopID, DirectedRoadID, IntersectionID, Map, MidblockCrossingID, RoadID,
+};
 use abstutil;
-use geom::{Angle, Distance, Line, PolyLine, Pt2D};
+use geom::{Angle, Distance, Duration, Line, PolyLine, Pt2D};
 use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 
 // Bit longer than the longest car.
 pub const PARKING_SPOT_LENGTH: Distance = Distance::const_meters(8.0);
 
+// Vehicles carrying at least this many people can use HOV lanes.
+pub const HOV_MIN_OCCUPANCY: usize = 2;
+
+const SECONDS_PER_DAY: f64 = 24.0 * 3600.0;
+
 // TODO reconsider pub usize. maybe outside world shouldnt know.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct LaneID(pub usize);
@@ -24,6 +31,8 @@ pub enum LaneType {
     Sidewalk,
     Biking,
     Bus,
+    // Only for vehicles carrying at least HOV_MIN_OCCUPANCY people, or buses
+    HOV,
 }
 
 impl LaneType {
@@ -32,6 +41,7 @@ impl LaneType {
             LaneType::Driving => true,
             LaneType::Biking => true,
             LaneType::Bus => true,
+            LaneType::HOV => true,
             _ => false,
         }
     }
@@ -50,6 +60,11 @@ pub struct Lane {
     // Sorted by distance of the front path
     pub building_paths: Vec<BuildingID>,
     pub bus_stops: Vec<BusStopID>,
+    // Starting from this sidewalk
+    pub midblock_crossings: Vec<MidblockCrossingID>,
+    // Only for parking lanes. When parking is allowed, as times since midnight; None means any
+    // time.
+    pub parking_hours: Option<Vec<(Duration, Duration)>>,
 }
 
 impl Lane {
@@ -121,15 +136,65 @@ impl Lane {
 
     // TODO different types for each lane type might be reasonable
 
-    pub fn number_parking_spots(&self) -> usize {
+    // Sim time is treated as the time of day, wrapping around every 24 hours.
+    pub fn parking_allowed_at(&self, time: Duration) -> bool {
+        assert_eq!(self.lane_type, LaneType::Parking);
+        let windows = match self.parking_hours {
+            Some(ref windows) => windows,
+            None => {
+                return true;
+            }
+        };
+        let time_of_day = Duration::seconds(time.inner_seconds() % SECONDS_PER_DAY);
+        windows.iter().any(|(start, end)| {
+            if start <= end {
+                *start <= time_of_day && time_of_day < *end
+            } else {
+                time_of_day >= *start || time_of_day < *end
+            }
+        })
+    }
+
+    // The first time strictly after the given one when parking_allowed_at might change.
+    pub fn next_parking_hours_change(&self, after: Duration) -> Option<Duration> {
+        let windows = self.parking_hours.as_ref()?;
+        let midnight =
+            Duration::seconds((after.inner_seconds() / SECONDS_PER_DAY).floor() * SECONDS_PER_DAY);
+        windows
+            .iter()
+            .flat_map(|(start, end)| vec![*start, *end])
+            .map(|t| {
+                if midnight + t > after {
+                    midnight + t
+                } else {
+                    midnight + t + Duration::seconds(SECONDS_PER_DAY)
+                }
+            })
+            .min()
+    }
+
+    // The start and end of every parking spot, in order along the lane. This is the only place
+    // that decides where spots are; rendering and the sim both consume this. Any remainder at the
+    // end of the lane shorter than a full spot isn't a spot.
+    pub fn parking_spots(&self) -> Vec<(Distance, Distance)> {
         assert_eq!(self.lane_type, LaneType::Parking);
         // No spots next to intersections
         let spots = (self.length() / PARKING_SPOT_LENGTH).floor() - 2.0;
-        if spots >= 1.0 {
-            spots as usize
-        } else {
-            0
+        if spots < 1.0 {
+            return Vec::new();
         }
+        (0..spots as usize)
+            .map(|idx| {
+                (
+                    PARKING_SPOT_LENGTH * (1.0 + idx as f64),
+                    PARKING_SPOT_LENGTH * (2.0 + idx as f64),
+                )
+            })
+            .collect()
+    }
+
+    pub fn number_parking_spots(&self) -> usize {
+        self.parking_spots().len()
     }
 
     pub fn is_driving(&self) -> bool {
@@ -1,12 +1,18 @@
 mod area;
+mod bike_rack;
 mod building;
 mod bus_stop;
+mod connectivity;
 mod edits;
+mod geojson;
 mod intersection;
 mod lane;
 mod make;
 mod map;
+mod mentions;
+mod midblock_crossing;
 mod neighborhood;
+mod parking_lot;
 mod pathfind;
 pub mod raw_data;
 mod road;
@@ -14,27 +20,37 @@ mod stop_signs;
 mod traffic_signals;
 mod traversable;
 mod turn;
+mod turn_graph;
 
 pub use crate::area::{Area, AreaID, AreaType};
+pub use crate::bike_rack::{BikeRack, BikeRackID};
 pub use crate::building::{Building, BuildingID, BuildingType, FrontPath};
-pub use crate::bus_stop::{BusRoute, BusRouteID, BusStop, BusStopID};
-pub use crate::edits::MapEdits;
+pub use crate::bus_stop::{
+    BusRoute, BusRouteID, BusRouteProblem, BusRouteReport, BusStop, BusStopID, DroppedBusStop,
+};
+pub use crate::connectivity::ConnectivityReport;
+pub use crate::edits::{DiffType, EditDiff, MapEdits, RoadDirectionChange};
 pub use crate::intersection::{Intersection, IntersectionID, IntersectionType};
-pub use crate::lane::{Lane, LaneID, LaneType, PARKING_SPOT_LENGTH};
+pub use crate::lane::{Lane, LaneID, LaneType, HOV_MIN_OCCUPANCY, PARKING_SPOT_LENGTH};
 pub use crate::make::RoadSpec;
 pub use crate::map::Map;
+pub use crate::mentions::{find_mentioned_ids, MentionedID};
+pub use crate::midblock_crossing::{MidblockCrossing, MidblockCrossingID};
 pub use crate::neighborhood::{FullNeighborhoodInfo, Neighborhood, NeighborhoodBuilder};
+pub use crate::parking_lot::{ParkingLot, ParkingLotID};
 pub use crate::pathfind::{Path, PathRequest, PathStep};
 pub use crate::road::{DirectedRoadID, Road, RoadID};
 pub use crate::stop_signs::{ControlStopSign, RoadWithStopSign};
-pub use crate::traffic_signals::{ControlTrafficSignal, Cycle};
-pub use crate::traversable::{Position, Traversable};
+pub use crate::traffic_signals::{ControlTrafficSignal, Cycle, SignalControl};
+pub use crate::traversable::{Position, SpawnError, Traversable};
 pub use crate::turn::{Turn, TurnID, TurnPriority, TurnType};
+pub use crate::turn_graph::{TurnGraph, TurnGraphLink, TurnGraphNode};
 use abstutil::Cloneable;
 use geom::Distance;
 
 pub const LANE_THICKNESS: Distance = Distance::const_meters(2.5);
 
+impl Cloneable for BuildingType {}
 impl Cloneable for ControlTrafficSignal {}
 impl Cloneable for IntersectionID {}
 impl Cloneable for LaneType {}
@@ -0,0 +1,59 @@
+use crate::make::sidewalk_finder::find_sidewalk_points;
+use crate::{raw_data, BikeRack, BikeRackID, Map};
+use abstutil::Timer;
+use geom::{Bounds, Distance, GPSBounds, HashablePt2D, Pt2D};
+use std::collections::HashSet;
+
+pub fn make_bike_racks(
+    map: &Map,
+    input: &Vec<raw_data::BikeRack>,
+    gps_bounds: &GPSBounds,
+    bounds: &Bounds,
+    timer: &mut Timer,
+) -> Vec<BikeRack> {
+    let mut query: HashSet<HashablePt2D> = HashSet::new();
+    for rack in input {
+        if let Some(pt) = Pt2D::from_gps(rack.point, gps_bounds) {
+            query.insert(pt.into());
+        }
+    }
+    let sidewalk_pts = find_sidewalk_points(
+        bounds,
+        query,
+        map.all_lanes(),
+        Distance::meters(20.0),
+        timer,
+    );
+
+    let mut results: Vec<BikeRack> = Vec::new();
+    for rack in input {
+        let pt = match Pt2D::from_gps(rack.point, gps_bounds) {
+            Some(pt) => pt,
+            None => {
+                continue;
+            }
+        };
+        let hash_pt: HashablePt2D = pt.into();
+        if let Some(sidewalk_pos) = sidewalk_pts.get(&hash_pt) {
+            let candidate = BikeRack {
+                id: BikeRackID(results.len()),
+                osm_node_id: rack.osm_node_id,
+                point: pt,
+                capacity: rack.capacity,
+                sidewalk_pos: *sidewalk_pos,
+            };
+            // Bikes have to be able to pull up alongside the rack.
+            if candidate.driving_pos(map).is_some() {
+                results.push(candidate);
+            }
+        }
+    }
+    let dropped = input.len() - results.len();
+    if dropped > 0 {
+        timer.note(format!(
+            "Dropped {} bike racks not near a sidewalk with a bikeable lane alongside",
+            dropped
+        ));
+    }
+    results
+}
@@ -1,8 +1,15 @@
-use crate::make::sidewalk_finder::find_sidewalk_points;
-use crate::{raw_data, Building, BuildingID, BuildingType, FrontPath, Lane};
+use crate::make::sidewalk_finder::SidewalkFinder;
+use crate::{
+    raw_data, Building, BuildingID, BuildingType, FrontPath, Lane, LaneID, Position, Road,
+};
 use abstutil::Timer;
-use geom::{Bounds, Distance, GPSBounds, HashablePt2D, Line, Polygon, Pt2D};
-use std::collections::{BTreeMap, HashSet};
+use geom::{Bounds, Distance, GPSBounds, Line, Polygon, Pt2D};
+use std::collections::{BTreeMap, HashMap};
+
+// Skip buildings that're too far away from their sidewalk
+const MAX_DIST_TO_SIDEWALK: Distance = Distance::const_meters(100.0);
+// How far a building's wall can be turned from a sidewalk and still count as facing it
+const FRONTAGE_ANGLE_DEGREES: f64 = 30.0;
 
 pub fn make_all_buildings(
     results: &mut Vec<Building>,
@@ -10,54 +17,45 @@ pub fn make_all_buildings(
     gps_bounds: &GPSBounds,
     bounds: &Bounds,
     lanes: &Vec<Lane>,
+    roads: &Vec<Road>,
     timer: &mut Timer,
 ) {
     timer.start("convert buildings");
-    let mut pts_per_bldg: Vec<Vec<Pt2D>> = Vec::new();
-    let mut center_per_bldg: Vec<HashablePt2D> = Vec::new();
-    let mut query: HashSet<HashablePt2D> = HashSet::new();
-    timer.start_iter("get building center points", input.len());
-    for b in input {
-        timer.next();
-        let pts = Pt2D::approx_dedupe(gps_bounds.must_convert(&b.points), geom::EPSILON_DIST);
-        let center: HashablePt2D = Pt2D::center(&pts).into();
-        pts_per_bldg.push(pts);
-        center_per_bldg.push(center);
-        query.insert(center);
-    }
-
-    // Skip buildings that're too far away from their sidewalk
-    let sidewalk_pts = find_sidewalk_points(bounds, query, lanes, Distance::meters(100.0), timer);
+    let finder = SidewalkFinder::new(bounds, lanes, timer);
 
-    timer.start_iter("create building front paths", pts_per_bldg.len());
-    for (idx, points) in pts_per_bldg.into_iter().enumerate() {
+    timer.start_iter("create building front paths", input.len());
+    for b in input {
         timer.next();
-        let bldg_center = center_per_bldg[idx];
-        if let Some(sidewalk_pos) = sidewalk_pts.get(&bldg_center) {
-            let sidewalk_pt = lanes[sidewalk_pos.lane().0]
-                .dist_along(sidewalk_pos.dist_along())
-                .0;
-            if sidewalk_pt.epsilon_eq(bldg_center.into()) {
-                timer.warn("Skipping a building because front path has 0 length".to_string());
-                continue;
-            }
-            let line = trim_front_path(&points, Line::new(bldg_center.into(), sidewalk_pt));
-
-            let id = BuildingID(results.len());
-            results.push(Building {
-                id,
-                building_type: classify(input[idx].num_residential_units, &input[idx].osm_tags),
-                polygon: Polygon::new(&points),
-                osm_tags: input[idx].osm_tags.clone(),
-                osm_way_id: input[idx].osm_way_id,
-                front_path: FrontPath {
-                    bldg: id,
-                    sidewalk: *sidewalk_pos,
-                    line,
-                },
-                num_residential_units: input[idx].num_residential_units,
-            });
+        let points = Pt2D::approx_dedupe(gps_bounds.must_convert(&b.points), geom::EPSILON_DIST);
+        let bldg_center = Pt2D::center(&points);
+        let candidates = finder.all_close(bldg_center, MAX_DIST_TO_SIDEWALK);
+        if candidates.is_empty() {
+            continue;
+        }
+        let sidewalk_pos = pick_sidewalk(&points, &b.osm_tags, &candidates, &finder, lanes, roads);
+        let sidewalk_pt = lanes[sidewalk_pos.lane().0]
+            .dist_along(sidewalk_pos.dist_along())
+            .0;
+        if sidewalk_pt.epsilon_eq(bldg_center) {
+            timer.warn("Skipping a building because front path has 0 length".to_string());
+            continue;
         }
+        let line = trim_front_path(&points, Line::new(bldg_center, sidewalk_pt));
+
+        let id = BuildingID(results.len());
+        results.push(Building {
+            id,
+            building_type: BuildingType::classify(b.num_residential_units, &b.osm_tags),
+            polygon: Polygon::new(&points),
+            osm_tags: b.osm_tags.clone(),
+            osm_way_id: b.osm_way_id,
+            front_path: FrontPath {
+                bldg: id,
+                sidewalk: sidewalk_pos,
+                line,
+            },
+            num_residential_units: b.num_residential_units,
+        });
     }
 
     let discarded = input.len() - results.len();
@@ -70,6 +68,86 @@ pub fn make_all_buildings(
     timer.stop("convert buildings");
 }
 
+// Buildings on corner lots are close to more than one street, and the closest sidewalk isn't
+// always the one with the front door. If the address names a nearby street, use that. Without an
+// address, use the sidewalk that the most wall length faces.
+fn pick_sidewalk(
+    bldg_points: &Vec<Pt2D>,
+    tags: &BTreeMap<String, String>,
+    candidates: &Vec<(Position, Distance)>,
+    finder: &SidewalkFinder,
+    lanes: &Vec<Lane>,
+    roads: &Vec<Road>,
+) -> Position {
+    let closest = candidates[0].0;
+
+    if let Some(street) = tags.get("addr:street") {
+        return candidates
+            .iter()
+            .find(|(pos, _)| road_named(&roads[lanes[pos.lane().0].parent.0], street))
+            .map(|(pos, _)| *pos)
+            .unwrap_or(closest);
+    }
+
+    // Attribute each wall to the sidewalk closest to its middle, if the wall runs alongside it.
+    let mut frontage: HashMap<LaneID, Distance> = HashMap::new();
+    for pair in bldg_points.windows(2) {
+        let wall = match Line::maybe_new(pair[0], pair[1]) {
+            Some(l) => l,
+            None => {
+                continue;
+            }
+        };
+        if let Some((pos, _)) = finder
+            .all_close(wall.percent_along(0.5), MAX_DIST_TO_SIDEWALK)
+            .into_iter()
+            .next()
+        {
+            let sidewalk_angle = lanes[pos.lane().0].dist_along(pos.dist_along()).1;
+            if wall
+                .angle()
+                .approx_eq(sidewalk_angle, FRONTAGE_ANGLE_DEGREES)
+                || wall
+                    .angle()
+                    .approx_eq(sidewalk_angle.opposite(), FRONTAGE_ANGLE_DEGREES)
+            {
+                *frontage.entry(pos.lane()).or_insert(Distance::ZERO) += wall.length();
+            }
+        }
+    }
+
+    // Ties go to the closer sidewalk.
+    let mut best = (closest, Distance::ZERO);
+    for (pos, _) in candidates {
+        if let Some(len) = frontage.get(&pos.lane()) {
+            if *len > best.1 {
+                best = (*pos, *len);
+            }
+        }
+    }
+    best.0
+}
+
+// Addresses and road names are both typed in by hand, so don't be too picky about spacing or
+// capitalization.
+fn road_named(road: &Road, street: &str) -> bool {
+    let normalize = |name: &str| {
+        name.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    let street = normalize(street);
+    // When road names are normalized, the original is kept around, and the address might match
+    // either spelling.
+    ["name", "name:raw"].iter().any(|key| {
+        road.osm_tags
+            .get(*key)
+            .map(|name| normalize(name) == street)
+            .unwrap_or(false)
+    })
+}
+
 // Adjust the path to start on the building's border, not center
 fn trim_front_path(bldg_points: &Vec<Pt2D>, path: Line) -> Line {
     for bldg_line in bldg_points.windows(2) {
@@ -83,30 +161,3 @@ fn trim_front_path(bldg_points: &Vec<Pt2D>, path: Line) -> Line {
     // Just give up
     path
 }
-
-fn classify(num_residential_units: Option<usize>, tags: &BTreeMap<String, String>) -> BuildingType {
-    if num_residential_units.is_some() {
-        return BuildingType::Residence;
-    }
-    if tags.get("building") == Some(&"apartments".to_string()) {
-        return BuildingType::Residence;
-    }
-    if tags.get("building") == Some(&"residential".to_string()) {
-        return BuildingType::Residence;
-    }
-    if tags.get("building") == Some(&"house".to_string()) {
-        return BuildingType::Residence;
-    }
-
-    if tags.contains_key(&"shop".to_string()) || tags.contains_key(&"amenity".to_string()) {
-        return BuildingType::Business;
-    }
-    if tags.get("building") == Some(&"commercial".to_string()) {
-        return BuildingType::Business;
-    }
-    if tags.get("building") == Some(&"retail".to_string()) {
-        return BuildingType::Business;
-    }
-
-    BuildingType::Unknown
-}
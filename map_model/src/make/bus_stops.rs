@@ -1,56 +1,98 @@
 use crate::make::sidewalk_finder::find_sidewalk_points;
 use crate::{
-    BusRoute, BusRouteID, BusStop, BusStopID, LaneID, LaneType, Map, PathRequest, Position,
+    BusRoute, BusRouteID, BusRouteProblem, BusRouteReport, BusStop, BusStopID, DroppedBusStop,
+    IntersectionID, IntersectionType, LaneID, LaneType, Map, PathRequest, Position,
 };
 use abstutil::{MultiMap, Timer};
 use geom::{Bounds, Distance, GPSBounds, HashablePt2D, Pt2D};
 use gtfs;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::iter;
 
+// Stops further than this from every sidewalk are dropped, rather than snapped somewhere
+// misleading.
+pub const MAX_BUS_STOP_SNAP_DIST: Distance = Distance::const_meters(10.0);
+
+// Also returns every stop from the input that didn't make it onto the map, and why.
 pub fn make_bus_stops(
     map: &Map,
     bus_routes: &Vec<gtfs::Route>,
     gps_bounds: &GPSBounds,
     bounds: &Bounds,
     timer: &mut Timer,
-) -> (BTreeMap<BusStopID, BusStop>, Vec<BusRoute>) {
+) -> (
+    BTreeMap<BusStopID, BusStop>,
+    Vec<BusRoute>,
+    Vec<BusRouteReport>,
+    Vec<DroppedBusStop>,
+) {
     timer.start("make bus stops");
     let mut bus_stop_pts: HashSet<HashablePt2D> = HashSet::new();
-    let mut route_lookups: HashMap<String, Vec<HashablePt2D>> = HashMap::new();
+    // Per route: the in-bounds stops, and whether it enters and exits the map at a border
+    let mut clipped_routes: Vec<(String, Vec<HashablePt2D>, bool, bool)> = Vec::new();
+    let mut dropped_routes = 0;
     for route in bus_routes {
-        for gps in &route.stops {
-            if let Some(pt) = Pt2D::from_gps(*gps, gps_bounds) {
-                let hash_pt: HashablePt2D = pt.into();
+        if let Some(clipped) = route.clip(|gps| gps_bounds.contains(gps)) {
+            let mut pts = Vec::new();
+            for gps in clipped.stops {
+                let hash_pt: HashablePt2D = Pt2D::from_gps(gps, gps_bounds).unwrap().into();
                 bus_stop_pts.insert(hash_pt);
-                route_lookups
-                    .entry(route.name.clone())
-                    .or_insert_with(Vec::new)
-                    .push(hash_pt);
+                pts.push(hash_pt);
             }
+            clipped_routes.push((
+                route.name.clone(),
+                pts,
+                clipped.enters_from_border,
+                clipped.exits_to_border,
+            ));
+        } else {
+            dropped_routes += 1;
         }
     }
+    if dropped_routes > 0 {
+        timer.note(format!(
+            "Dropped {} bus routes with fewer than 2 stops in the slice of the map",
+            dropped_routes
+        ));
+    }
 
     let mut stops_per_sidewalk: MultiMap<LaneID, (Distance, HashablePt2D)> = MultiMap::new();
-    for (pt, pos) in find_sidewalk_points(
+    // Why each stop that didn't make it onto the map was dropped
+    let mut point_to_problem: HashMap<HashablePt2D, BusRouteProblem> = HashMap::new();
+    let mut sidewalk_pts = find_sidewalk_points(
         bounds,
-        bus_stop_pts,
+        bus_stop_pts.clone(),
         map.all_lanes(),
-        Distance::meters(10.0),
+        MAX_BUS_STOP_SNAP_DIST,
         timer,
-    )
-    .into_iter()
-    {
-        stops_per_sidewalk.insert(pos.lane(), (pos.dist_along(), pt));
+    );
+    for pt in bus_stop_pts {
+        // Don't trust the search blindly; a stop snapped to the wrong place is worse than a
+        // missing one.
+        match sidewalk_pts.remove(&pt) {
+            Some(pos)
+                if map.get_l(pos.lane()).is_sidewalk()
+                    && pos.pt(map).dist_to(pt.into()) <= MAX_BUS_STOP_SNAP_DIST =>
+            {
+                stops_per_sidewalk.insert(pos.lane(), (pos.dist_along(), pt));
+            }
+            _ => {
+                point_to_problem.insert(
+                    pt,
+                    BusRouteProblem::StopFarFromSidewalk(Pt2D::new(pt.x(), pt.y())),
+                );
+            }
+        }
     }
     let mut point_to_stop_id: HashMap<HashablePt2D, BusStopID> = HashMap::new();
     let mut bus_stops: BTreeMap<BusStopID, BusStop> = BTreeMap::new();
 
     for (sidewalk_id, dists_set) in stops_per_sidewalk.consume().into_iter() {
         let road = map.get_parent(sidewalk_id);
-        if let Ok(driving_lane) =
-            road.find_closest_lane(sidewalk_id, vec![LaneType::Driving, LaneType::Bus])
-        {
+        if let Ok(driving_lane) = road.find_closest_lane(
+            sidewalk_id,
+            vec![LaneType::Driving, LaneType::Bus, LaneType::HOV],
+        ) {
             let mut dists: Vec<(Distance, HashablePt2D)> = dists_set.into_iter().collect();
             dists.sort_by_key(|(dist, _)| *dist);
             for (idx, (dist_along, orig_pt)) in dists.into_iter().enumerate() {
@@ -71,23 +113,41 @@ pub fn make_bus_stops(
                 );
             }
         } else {
-            timer.warn(format!(
-                "Can't find driving lane next to {}: {:?} and {:?}",
-                sidewalk_id, road.children_forwards, road.children_backwards
-            ));
+            for (_, pt) in dists_set {
+                point_to_problem.insert(pt, BusRouteProblem::StopNotOnTransitLane(sidewalk_id));
+            }
+        }
+    }
+
+    // Report each dropped stop once, in the order the routes visit them.
+    let mut dropped_stops: Vec<DroppedBusStop> = Vec::new();
+    let mut reported: HashSet<HashablePt2D> = HashSet::new();
+    for (_, pts, _, _) in &clipped_routes {
+        for pt in pts {
+            if let Some(problem) = point_to_problem.get(pt) {
+                if reported.insert(*pt) {
+                    timer.warn(format!("Dropped bus stop: {}", problem));
+                    dropped_stops.push(DroppedBusStop {
+                        pt: Pt2D::new(pt.x(), pt.y()),
+                        problem: problem.clone(),
+                    });
+                }
+            }
         }
     }
 
     let mut routes: Vec<BusRoute> = Vec::new();
-    for route in bus_routes {
-        let route_name = route.name.to_string();
-        let stops: Vec<BusStopID> = route_lookups
-            .remove(&route_name)
-            .unwrap_or_else(Vec::new)
-            .into_iter()
-            .filter_map(|pt| point_to_stop_id.get(&pt))
-            .cloned()
-            .collect();
+    let mut reports: Vec<BusRouteReport> = Vec::new();
+    for (route_name, pts, enters_from_border, exits_to_border) in clipped_routes {
+        let mut stops: Vec<BusStopID> = Vec::new();
+        let mut problems: Vec<BusRouteProblem> = Vec::new();
+        for pt in pts {
+            if let Some(stop) = point_to_stop_id.get(&pt) {
+                stops.push(*stop);
+            } else {
+                problems.push(point_to_problem[&pt].clone());
+            }
+        }
         if stops.len() < 2 {
             if !stops.is_empty() {
                 timer.warn(format!(
@@ -96,25 +156,74 @@ pub fn make_bus_stops(
                     stops.len()
                 ));
             }
+            problems.push(BusRouteProblem::TooFewStops(stops.len()));
+            reports.push(BusRouteReport {
+                route_name,
+                kept: false,
+                problems,
+            });
             continue;
         }
+        if !problems.is_empty() {
+            reports.push(BusRouteReport {
+                route_name: route_name.clone(),
+                kept: true,
+                problems,
+            });
+        }
+        let start_border = if enters_from_border {
+            closest_border(map, bus_stops[&stops[0]].driving_pos, true)
+        } else {
+            None
+        };
+        let end_border = if exits_to_border {
+            closest_border(map, bus_stops[stops.last().unwrap()].driving_pos, false)
+        } else {
+            None
+        };
         let id = BusRouteID(routes.len());
         routes.push(BusRoute {
             id,
-            name: route_name.to_string(),
+            name: route_name,
             stops,
+            start_border,
+            end_border,
         });
     }
     timer.stop("make bus stops");
-    (bus_stops, routes)
+    (bus_stops, routes, reports, dropped_stops)
 }
 
-pub fn verify_bus_routes(map: &Map, routes: Vec<BusRoute>, timer: &mut Timer) -> Vec<BusRoute> {
+// The border intersection closest to a stop where buses could enter the map (if incoming) or leave
+// it.
+fn closest_border(map: &Map, pos: Position, incoming: bool) -> Option<IntersectionID> {
+    let pt = pos.pt(map);
+    map.all_intersections()
+        .iter()
+        .filter(|i| i.intersection_type == IntersectionType::Border)
+        .filter(|i| {
+            if incoming {
+                !i.get_outgoing_lanes(map, LaneType::Driving).is_empty()
+            } else {
+                !i.get_incoming_lanes(map, LaneType::Driving).is_empty()
+            }
+        })
+        .min_by_key(|i| i.polygon.center().dist_to(pt))
+        .map(|i| i.id)
+}
+
+// Drops routes that buses can't actually follow, adding to the reports from make_bus_stops.
+pub fn verify_bus_routes(
+    map: &Map,
+    routes: Vec<BusRoute>,
+    mut reports: Vec<BusRouteReport>,
+    timer: &mut Timer,
+) -> (Vec<BusRoute>, Vec<BusRouteReport>) {
     timer.start_iter("verify bus routes are connected", routes.len());
     let mut results = Vec::new();
     for mut r in routes {
         timer.next();
-        let mut ok = true;
+        let mut problems = Vec::new();
         for (stop1, stop2) in r
             .stops
             .iter()
@@ -123,6 +232,12 @@ pub fn verify_bus_routes(map: &Map, routes: Vec<BusRoute>, timer: &mut Timer) ->
         {
             let bs1 = map.get_bs(*stop1);
             let bs2 = map.get_bs(*stop2);
+            if !is_transit_lane(map.get_l(bs1.driving_pos.lane()).lane_type) {
+                problems.push(BusRouteProblem::StopNotOnTransitLane(
+                    bs1.sidewalk_pos.lane(),
+                ));
+                continue;
+            }
             if bs1.driving_pos.lane() == bs2.driving_pos.lane() {
                 // This is coming up because the dist_along's are in a bad order. But why
                 // should this happen at all?
@@ -130,8 +245,8 @@ pub fn verify_bus_routes(map: &Map, routes: Vec<BusRoute>, timer: &mut Timer) ->
                     "Removing route {} since {:?} and {:?} are on the same lane",
                     r.name, bs1, bs2
                 ));
-                ok = false;
-                break;
+                problems.push(BusRouteProblem::StopsOnSameLane(*stop1, *stop2));
+                continue;
             }
 
             if map
@@ -140,6 +255,9 @@ pub fn verify_bus_routes(map: &Map, routes: Vec<BusRoute>, timer: &mut Timer) ->
                     end: bs2.driving_pos,
                     can_use_bike_lanes: false,
                     can_use_bus_lanes: true,
+                    can_use_hov_lanes: true,
+                    can_use_midblock_crossings: false,
+                    avoid_lanes: BTreeSet::new(),
                 })
                 .is_none()
             {
@@ -147,15 +265,35 @@ pub fn verify_bus_routes(map: &Map, routes: Vec<BusRoute>, timer: &mut Timer) ->
                     "Removing route {} since {:?} and {:?} aren't connected",
                     r.name, bs1, bs2
                 ));
-                ok = false;
-                break;
+                problems.push(BusRouteProblem::UnreachableStop(*stop1, *stop2));
             }
         }
 
-        if ok {
+        let kept = problems.is_empty();
+        if !kept {
+            // make_bus_stops might've already found problems with this route.
+            if let Some(report) = reports
+                .iter_mut()
+                .find(|report| report.route_name == r.name)
+            {
+                report.kept = false;
+                report.problems.extend(problems);
+            } else {
+                reports.push(BusRouteReport {
+                    route_name: r.name.clone(),
+                    kept: false,
+                    problems,
+                });
+            }
+        }
+        if kept {
             r.id = BusRouteID(results.len());
             results.push(r);
         }
     }
-    results
+    (results, reports)
+}
+
+fn is_transit_lane(lt: LaneType) -> bool {
+    lt == LaneType::Driving || lt == LaneType::Bus || lt == LaneType::HOV
 }
@@ -1,9 +1,9 @@
 use crate::{
-    make, raw_data, Area, AreaID, Building, Intersection, IntersectionID, IntersectionType, Lane,
-    LaneID, Road, RoadID, Turn, TurnID, LANE_THICKNESS,
+    connectivity, make, raw_data, Area, AreaID, Building, Intersection, IntersectionID,
+    IntersectionType, Lane, LaneID, LaneType, Road, RoadID, Turn, TurnID, LANE_THICKNESS,
 };
 use abstutil::Timer;
-use geom::{Bounds, GPSBounds, Polygon};
+use geom::{Bounds, GPSBounds, Polygon, Speed};
 use std::collections::BTreeMap;
 
 pub struct HalfMap {
@@ -22,6 +22,7 @@ pub fn make_half_map(
     initial_map: make::InitialMap,
     gps_bounds: &GPSBounds,
     bounds: &Bounds,
+    parallel: bool,
     timer: &mut Timer,
 ) -> HalfMap {
     let mut half_map = HalfMap {
@@ -50,7 +51,7 @@ pub fn make_half_map(
             id,
             // IMPORTANT! We're relying on the triangulation algorithm not to mess with the order
             // of the points. Sidewalk corner rendering depends on it later.
-            polygon: Polygon::new(&i.polygon),
+            polygon: i.polygon.to_polygon(),
             turns: Vec::new(),
             // Might change later
             intersection_type: i.intersection_type,
@@ -59,6 +60,11 @@ pub fn make_half_map(
             incoming_lanes: Vec::new(),
             outgoing_lanes: Vec::new(),
             roads: i.roads.iter().map(|id| road_id_mapping[id]).collect(),
+            merged_movements: i
+                .merged_movements
+                .iter()
+                .map(|(r1, r2)| (road_id_mapping[r1], road_id_mapping[r2]))
+                .collect(),
         });
         intersection_id_mapping.insert(i.id, id);
     }
@@ -85,7 +91,10 @@ pub fn make_half_map(
             dst_i: i2,
             parking_lane_fwd: raw_r.parking_lane_fwd,
             parking_lane_back: raw_r.parking_lane_back,
+            // Filled out right below, since the fallback depends on the road's tags
+            speed_limit: Speed::ZERO,
         };
+        road.speed_limit = raw_r.speed_limit.unwrap_or_else(|| road.default_speed_limit());
 
         for lane in &r.lane_specs {
             let id = LaneID(half_map.lanes.len());
@@ -121,9 +130,17 @@ pub fn make_half_map(
                 parent: road_id,
                 building_paths: Vec::new(),
                 bus_stops: Vec::new(),
+                midblock_crossings: Vec::new(),
+                parking_hours: if lane.lane_type != LaneType::Parking {
+                    None
+                } else if lane.reverse_pts {
+                    raw_r.parking_hours_back.clone()
+                } else {
+                    raw_r.parking_hours_fwd.clone()
+                },
             });
         }
-        if road.get_name() == "???" {
+        if road.get_osm_name().is_none() {
             timer.warn(format!(
                 "{} has no name. Tags: {:?}",
                 road.id, road.osm_tags
@@ -132,40 +149,92 @@ pub fn make_half_map(
         half_map.roads.push(road);
     }
 
-    for i in half_map.intersections.iter_mut() {
-        if is_border(i, &half_map.lanes) {
-            i.intersection_type = IntersectionType::Border;
-            continue;
-        }
+    // Turns and buildings only need the lanes, so they can happen at the same time.
+    let (buildings, areas) = {
+        let roads = &half_map.roads;
+        let lanes = &half_map.lanes;
+        let intersections = &mut half_map.intersections;
+        let turns = &mut half_map.turns;
+        let turn_lookup = &mut half_map.turn_lookup;
+        let (_, buildings_and_areas) = timer.run_pair(
+            parallel,
+            "make turns",
+            move |timer| {
+                for i in intersections.iter_mut() {
+                    if is_border(i, lanes) {
+                        i.intersection_type = IntersectionType::Border;
+                        continue;
+                    }
 
-        if i.incoming_lanes.is_empty() || i.outgoing_lanes.is_empty() {
-            timer.warn(format!("{:?} is orphaned!", i));
-            continue;
-        }
+                    if i.incoming_lanes.is_empty() || i.outgoing_lanes.is_empty() {
+                        timer.warn(format!("{:?} is orphaned!", i));
+                        continue;
+                    }
 
-        for t in make::turns::make_all_turns(i, &half_map.roads, &half_map.lanes, timer) {
-            assert!(!half_map.turns.contains_key(&t.id));
-            i.turns.push(t.id);
-            half_map.turns.insert(t.id, t);
-        }
-    }
+                    for t in make::turns::make_all_turns(i, roads, lanes, timer) {
+                        assert!(!turns.contains_key(&t.id));
+                        i.turns.push(t.id);
+                        turns.insert(t.id, t);
+                    }
+                }
 
-    for t in half_map.turns.values_mut() {
-        t.lookup_idx = half_map.turn_lookup.len();
-        half_map.turn_lookup.push(t.id);
-        if t.geom.length() < geom::EPSILON_DIST {
-            timer.warn(format!("u{} is a very short turn", t.lookup_idx));
-        }
-    }
+                for t in turns.values_mut() {
+                    t.lookup_idx = turn_lookup.len();
+                    turn_lookup.push(t.id);
+                    if t.length < geom::EPSILON_DIST {
+                        timer.warn(format!("u{} is a very short turn", t.lookup_idx));
+                    }
+                }
+
+                // Agents stuck on these would never get anywhere, so catch them now instead of in
+                // the sim.
+                let (no_incoming_turns, no_outgoing_turns) =
+                    connectivity::find_orphaned_lanes(lanes, intersections, |_| true);
+                for l in no_incoming_turns {
+                    timer.warn(format!("No turns lead into {}", l));
+                }
+                for l in no_outgoing_turns {
+                    timer.warn(format!("No turns lead out of {}", l));
+                }
+            },
+            "make buildings and areas",
+            move |timer| {
+                let mut buildings = Vec::new();
+                make::make_all_buildings(
+                    &mut buildings,
+                    &data.buildings,
+                    gps_bounds,
+                    bounds,
+                    lanes,
+                    roads,
+                    timer,
+                );
+
+                let mut areas = Vec::new();
+                for (idx, a) in data.areas.iter().enumerate() {
+                    let pts = gps_bounds.must_convert(&a.points);
+                    if pts[0] != *pts.last().unwrap() {
+                        panic!(
+                            "Unclosed Area from OSM {} with tags {:?}",
+                            a.osm_id, a.osm_tags
+                        );
+                    }
+                    areas.push(Area {
+                        id: AreaID(idx),
+                        area_type: a.area_type,
+                        polygon: Polygon::new(&pts),
+                        osm_tags: a.osm_tags.clone(),
+                        osm_id: a.osm_id,
+                    });
+                }
+                (buildings, areas)
+            },
+        );
+        buildings_and_areas
+    };
+    half_map.buildings = buildings;
+    half_map.areas = areas;
 
-    make::make_all_buildings(
-        &mut half_map.buildings,
-        &data.buildings,
-        &gps_bounds,
-        &bounds,
-        &half_map.lanes,
-        timer,
-    );
     for b in &half_map.buildings {
         let lane = b.sidewalk();
 
@@ -176,23 +245,6 @@ pub fn make_half_map(
         half_map.lanes[lane.0].building_paths = bldgs;
     }
 
-    for (idx, a) in data.areas.iter().enumerate() {
-        let pts = gps_bounds.must_convert(&a.points);
-        if pts[0] != *pts.last().unwrap() {
-            panic!(
-                "Unclosed Area from OSM {} with tags {:?}",
-                a.osm_id, a.osm_tags
-            );
-        }
-        half_map.areas.push(Area {
-            id: AreaID(idx),
-            area_type: a.area_type,
-            polygon: Polygon::new(&pts),
-            osm_tags: a.osm_tags.clone(),
-            osm_id: a.osm_id,
-        });
-    }
-
     half_map
 }
 
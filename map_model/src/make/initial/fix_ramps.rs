@@ -16,7 +16,7 @@ pub fn fix_ramps(m: &mut InitialMap, timer: &mut Timer) {
 
     let mut quadtree = QuadTree::default(m.bounds.as_bbox());
     for i in m.intersections.values() {
-        quadtree.insert_with_box(i.id, Bounds::from(&i.polygon).as_bbox());
+        quadtree.insert_with_box(i.id, Bounds::from(i.polygon.points()).as_bbox());
     }
 
     // Look for road center lines that hit an intersection polygon that isn't one of their
@@ -33,7 +33,7 @@ pub fn fix_ramps(m: &mut InitialMap, timer: &mut Timer) {
             if r.src_i == i.id || r.dst_i == i.id {
                 continue;
             }
-            if !r.trimmed_center_pts.crosses_polygon(&i.polygon) {
+            if !r.trimmed_center_pts.crosses_polygon(i.polygon.points()) {
                 continue;
             }
 
@@ -129,7 +129,7 @@ fn fix_ramp(
             return false;
         }
         i.roads.remove(&last_road);
-        i.polygon = geometry::intersection_polygon(i, &mut m.roads, timer);
+        i.polygon = geometry::intersection_polygon(i.id, &i.roads, &mut m.roads, timer);
     } else {
         // TODO Not really sure why, but when there's not a road in between, don't apply the fix.
         return false;
@@ -145,7 +145,7 @@ fn fix_ramp(
         m.roads.get_mut(&ramp).unwrap().src_i = new_src;
         let mut i = m.intersections.get_mut(&new_src).unwrap();
         i.roads.insert(ramp);
-        i.polygon = geometry::intersection_polygon(i, &mut m.roads, timer);
+        i.polygon = geometry::intersection_polygon(i.id, &i.roads, &mut m.roads, timer);
     }
     true
 }
@@ -1,20 +1,23 @@
-use crate::make::initial::{Intersection, Road};
+use crate::make::initial::Road;
 use crate::raw_data::{StableIntersectionID, StableRoadID};
 use abstutil::{wraparound_get, Timer, Warn};
-use geom::{Distance, HashablePt2D, Line, PolyLine, Pt2D};
-use std::collections::{BTreeMap, HashMap};
+use geom::{trim_f64, Distance, HashablePt2D, Line, PolyLine, Pt2D, Ring, EPSILON_DIST};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 const DEGENERATE_INTERSECTION_HALF_LENGTH: Distance = Distance::const_meters(5.0);
+// How far past the end of a too-short dead-end road its intersection pokes out
+const DEADEND_TIP_LENGTH: Distance = Distance::const_meters(1.0);
 
 // The polygon should exist entirely within the thick bands around all original roads -- it just
 // carves up part of that space, doesn't reach past it.
 pub fn intersection_polygon(
-    i: &Intersection,
+    i: StableIntersectionID,
+    incident_roads: &BTreeSet<StableRoadID>,
     roads: &mut BTreeMap<StableRoadID, Road>,
     timer: &mut Timer,
-) -> Vec<Pt2D> {
-    if i.roads.is_empty() {
-        panic!("{} has no roads", i.id);
+) -> Ring {
+    if incident_roads.is_empty() {
+        panic!("{} has no roads", i);
     }
     let mut road_endpts: Vec<Pt2D> = Vec::new();
 
@@ -23,20 +26,19 @@ pub fn intersection_polygon(
     // at the intersection (which may be different points for merged intersections!), and the last
     // segment of the center line.
     // TODO Maybe express the two incoming PolyLines as the "right" and "left"
-    let mut lines: Vec<(StableRoadID, Line, PolyLine, PolyLine)> = i
-        .roads
+    let mut lines: Vec<(StableRoadID, Line, PolyLine, PolyLine)> = incident_roads
         .iter()
         .map(|id| {
             let r = &roads[id];
 
-            let (line, width_normal, width_reverse) = if r.src_i == i.id {
+            let (line, width_normal, width_reverse) = if r.src_i == i {
                 road_endpts.push(r.trimmed_center_pts.first_pt());
                 (r.trimmed_center_pts.reversed(), r.back_width, r.fwd_width)
-            } else if r.dst_i == i.id {
+            } else if r.dst_i == i {
                 road_endpts.push(r.trimmed_center_pts.last_pt());
                 (r.trimmed_center_pts.clone(), r.fwd_width, r.back_width)
             } else {
-                panic!("Incident road {} doesn't have an endpoint at {}", id, i.id);
+                panic!("Incident road {} doesn't have an endpoint at {}", id, i);
             };
 
             let pl_normal = line
@@ -52,19 +54,26 @@ pub fn intersection_polygon(
     // Find the average of all road endpoints at the intersection. This is usually just a single
     // point, except for merged intersections.
     road_endpts.sort_by_key(|pt| HashablePt2D::from(*pt));
-    road_endpts.dedup();
+    let road_endpts = Pt2D::approx_dedupe(road_endpts, EPSILON_DIST);
     let intersection_center = Pt2D::center(&road_endpts);
 
     // Sort the polylines by the angle their last segment makes to the "center". This is normally
     // equivalent to the angle of the last line, except when the intersection has been merged.
-    lines.sort_by_key(|(_, l, _, _)| {
-        l.pt1().angle_to(intersection_center).normalized_degrees() as i64
-    });
+    lines.sort_by_key(|(id, l, _, _)| (angle_sort_key(l.pt1(), intersection_center), *id));
+    // Jitter can move a line from just below 360 degrees to just above 0, so don't let that decide
+    // where the polygon starts. Always start from the same road.
+    let first = lines
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, (id, _, _, _))| *id)
+        .unwrap()
+        .0;
+    lines.rotate_left(first);
 
     if lines.len() == 1 {
-        deadend(roads, i.id, &lines).get(timer)
+        deadend(roads, i, &lines).get(timer)
     } else {
-        generalized_trim_back(roads, i.id, &lines, timer)
+        generalized_trim_back(roads, i, &lines, timer)
     }
 }
 
@@ -73,7 +82,7 @@ fn generalized_trim_back(
     i: StableIntersectionID,
     lines: &Vec<(StableRoadID, Line, PolyLine, PolyLine)>,
     timer: &mut Timer,
-) -> Vec<Pt2D> {
+) -> Ring {
     let mut road_lines: Vec<(StableRoadID, PolyLine, PolyLine)> = Vec::new();
     for (r, _, pl1, pl2) in lines {
         // TODO Argh, just use original lines.
@@ -273,26 +282,44 @@ fn generalized_trim_back(
             timer.warn(format!("Excluding collision between original polylines of {} and something, because stuff's too short", id));
         }
     }
-    let main_result = close_off_polygon(Pt2D::approx_dedupe(endpoints, Distance::meters(0.1)));
+    let endpoints = Pt2D::approx_dedupe(endpoints, Distance::meters(0.1));
 
     // There are bad polygons caused by weird short roads. As a temporary workaround, detect cases
     // where polygons dramatically double back on themselves and force the polygon to proceed
     // around its center.
-    let mut deduped = main_result.clone();
-    deduped.pop();
+    let mut deduped = endpoints.clone();
     deduped.sort_by_key(|pt| HashablePt2D::from(*pt));
     deduped = Pt2D::approx_dedupe(deduped, Distance::meters(0.1));
     let center = Pt2D::center(&deduped);
-    deduped.sort_by_key(|pt| pt.angle_to(center).normalized_degrees() as i64);
-    deduped = close_off_polygon(deduped);
-    if main_result.len() == deduped.len() {
-        main_result
-    } else {
-        timer.warn(format!(
-            "{}'s polygon has weird repeats, forcibly removing points",
-            i
-        ));
-        deduped
+    deduped.sort_by_key(|pt| angle_sort_key(*pt, center));
+    if let Some(first) = (0..deduped.len()).min_by_key(|idx| HashablePt2D::from(deduped[*idx])) {
+        deduped.rotate_left(first);
+    }
+    match Ring::new(endpoints) {
+        Ok(ring) if ring.points().len() == deduped.len() + 1 => {
+            return ring;
+        }
+        Ok(_) => {
+            timer.warn(format!(
+                "{}'s polygon has weird repeats, forcibly removing points",
+                i
+            ));
+        }
+        Err(err) => {
+            timer.warn(format!(
+                "{}'s polygon is broken ({}), forcibly ordering points around the center",
+                i, err
+            ));
+        }
+    }
+    match Ring::new(deduped.clone()) {
+        Ok(ring) => ring,
+        Err(err) => fallback_ring(
+            &deduped,
+            center,
+            format!("{}'s polygon is still broken after ordering ({})", i, err),
+        )
+        .get(timer),
     }
 }
 
@@ -300,8 +327,8 @@ fn deadend(
     roads: &mut BTreeMap<StableRoadID, Road>,
     i: StableIntersectionID,
     lines: &Vec<(StableRoadID, Line, PolyLine, PolyLine)>,
-) -> Warn<Vec<Pt2D>> {
-    let (id, _, pl_a, pl_b) = &lines[0];
+) -> Warn<Ring> {
+    let (id, center_line, pl_a, pl_b) = &lines[0];
     let pt1 = pl_a
         .reversed()
         .safe_dist_along(DEGENERATE_INTERSECTION_HALF_LENGTH * 2.0)
@@ -324,27 +351,67 @@ fn deadend(
             );
         }
 
-        Warn::ok(close_off_polygon(vec![
-            pt1.unwrap(),
-            pt2.unwrap(),
-            pl_b.last_pt(),
-            pl_a.last_pt(),
-        ]))
+        let pts = vec![pt1.unwrap(), pt2.unwrap(), pl_b.last_pt(), pl_a.last_pt()];
+        match Ring::new(pts.clone()) {
+            Ok(ring) => Warn::ok(ring),
+            Err(err) => fallback_ring(
+                &pts,
+                center_line.pt2(),
+                format!("{}'s dead-end polygon is broken ({})", i, err),
+            ),
+        }
     } else {
-        Warn::warn(
-            vec![pl_a.last_pt(), pl_b.last_pt(), pl_a.last_pt()],
-            format!(
-            "{} is a dead-end for {}, which is too short to make degenerate intersection geometry",
-            i, id
-        ),
-        )
+        // Just cap off the end of the road with a sliver.
+        let tip = center_line
+            .pt2()
+            .project_away(DEADEND_TIP_LENGTH, center_line.angle());
+        let pts = vec![pl_a.last_pt(), pl_b.last_pt(), tip];
+        let warning = format!(
+            "{} is a dead-end for {}, which is too short to make degenerate intersection geometry, \
+             so it's capped off with a {} tip",
+            i, id, DEADEND_TIP_LENGTH
+        );
+        match Ring::new(pts.clone()) {
+            Ok(ring) => Warn::warn(ring, warning),
+            Err(err) => fallback_ring(
+                &pts,
+                center_line.pt2(),
+                format!("{}, but that's broken ({})", warning, err),
+            ),
+        }
     }
 }
 
-fn close_off_polygon(mut pts: Vec<Pt2D>) -> Vec<Pt2D> {
-    if pts.last().unwrap().approx_eq(pts[0], Distance::meters(0.1)) {
-        pts.pop();
+// The last resort when an intersection's points don't make a valid polygon: their convex hull, or
+// if even that's degenerate, a small square around the center.
+fn fallback_ring(pts: &Vec<Pt2D>, center: Pt2D, problem: String) -> Warn<Ring> {
+    match Ring::convex_hull(pts) {
+        Ok(ring) => Warn::warn(ring, format!("{}, so using the convex hull", problem)),
+        Err(err) => {
+            let r = Distance::meters(1.0);
+            let square = Ring::new(vec![
+                center.offset(-r, -r),
+                center.offset(r, -r),
+                center.offset(r, r),
+                center.offset(-r, r),
+            ])
+            .unwrap();
+            Warn::warn(
+                square,
+                format!(
+                    "{}, and so is the convex hull ({}), so it's just a square",
+                    problem, err
+                ),
+            )
+        }
     }
-    pts.push(pts[0]);
-    pts
+}
+
+// Ordering points by their angle around a center is sensitive to tiny floating point differences
+// in the input, so quantize the angle the same way Pt2D quantizes coordinates, and break ties by
+// both coordinates of the point. Callers still have to pick a stable starting point, since an
+// angle just below 360 can jitter to just above 0.
+fn angle_sort_key(pt: Pt2D, center: Pt2D) -> (i64, HashablePt2D) {
+    let degrees = trim_f64(pt.angle_to(center).normalized_degrees());
+    ((degrees * 10_000.0).round() as i64, HashablePt2D::from(pt))
 }
@@ -22,7 +22,9 @@ pub fn get_lane_types(
     if tags.get("junction") == Some(&"roundabout".to_string()) {
         return (vec![LaneType::Driving, LaneType::Sidewalk], Vec::new());
     }
-    if tags.get("highway") == Some(&"footway".to_string()) {
+    if tags.get("highway") == Some(&"footway".to_string())
+        || tags.get("highway") == Some(&"steps".to_string())
+    {
         return (vec![LaneType::Sidewalk], Vec::new());
     }
 
@@ -109,7 +111,7 @@ pub fn get_lane_types(
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct LaneSpec {
     pub lane_type: LaneType,
     pub reverse_pts: bool,
@@ -189,6 +191,7 @@ impl RoadSpec {
             LaneType::Sidewalk => 's',
             LaneType::Biking => 'b',
             LaneType::Bus => 'u',
+            LaneType::HOV => 'h',
         }
     }
 
@@ -199,6 +202,7 @@ impl RoadSpec {
             's' => Some(LaneType::Sidewalk),
             'b' => Some(LaneType::Biking),
             'u' => Some(LaneType::Bus),
+            'h' => Some(LaneType::HOV),
             _ => None,
         }
     }
@@ -1,9 +1,9 @@
-use crate::make::initial::{geometry, InitialMap};
+use crate::make::initial::{geometry, InitialMap, Road};
 use crate::raw_data::{StableIntersectionID, StableRoadID};
 use crate::IntersectionType;
 use abstutil::Timer;
 use geom::Distance;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
 pub fn short_roads(map: &mut InitialMap, timer: &mut Timer) {
     if false {
@@ -56,15 +56,16 @@ pub fn merge(
 
         (r.src_i, r.dst_i)
     };
+    let through_movements = through_movements(map, merge_road);
     map.roads.remove(&merge_road);
-    let deleted_intersection_type = map
-        .intersections
-        .remove(&delete_i)
-        .unwrap()
-        .intersection_type;
+    let deleted_intersection = map.intersections.remove(&delete_i).unwrap();
+    let deleted_intersection_type = deleted_intersection.intersection_type;
     {
         let mut i = map.intersections.get_mut(&keep_i).unwrap();
         i.roads.remove(&merge_road);
+        i.merged_movements
+            .extend(deleted_intersection.merged_movements);
+        i.merged_movements.extend(through_movements);
         if deleted_intersection_type == IntersectionType::TrafficSignal
             && i.intersection_type == IntersectionType::StopSign
         {
@@ -100,9 +101,19 @@ pub fn merge(
                 .remove(&r.id);
         }
     }
-    for r in new_loops {
-        map.roads.remove(&r);
+    for r in &new_loops {
+        map.roads.remove(r);
     }
+    map.intersections
+        .get_mut(&keep_i)
+        .unwrap()
+        .merged_movements
+        .retain(|(r1, r2)| {
+            *r1 != merge_road
+                && *r2 != merge_road
+                && !new_loops.contains(r1)
+                && !new_loops.contains(r2)
+        });
 
     // TODO Ah, we can also wind up with multiple roads between the same intersections here. Should
     // probably auto-remove those too.
@@ -131,7 +142,52 @@ pub fn merge(
     }
 
     let mut i = map.intersections.get_mut(&keep_i).unwrap();
-    i.polygon = geometry::intersection_polygon(i, &mut map.roads, timer);
+    i.polygon = geometry::intersection_polygon(i.id, &i.roads, &mut map.roads, timer);
 
     keep_i
 }
+
+// Every movement from one road to another that crossed merge_road. After merging, both roads meet
+// at the same intersection, and the connector's lanes are gone, so these turns have to be made
+// explicitly, as if the connector were internal to the intersection.
+fn through_movements(
+    map: &InitialMap,
+    merge_road: StableRoadID,
+) -> BTreeSet<(StableRoadID, StableRoadID)> {
+    let connector = &map.roads[&merge_road];
+    let mut movements = BTreeSet::new();
+    for (from_i, to_i) in vec![
+        (connector.src_i, connector.dst_i),
+        (connector.dst_i, connector.src_i),
+    ] {
+        if !has_vehicle_lanes(connector, to_i, true) {
+            continue;
+        }
+        for r1 in &map.intersections[&from_i].roads {
+            if *r1 == merge_road || !has_vehicle_lanes(&map.roads[r1], from_i, true) {
+                continue;
+            }
+            for r2 in &map.intersections[&to_i].roads {
+                if *r2 == merge_road || r1 == r2 || !has_vehicle_lanes(&map.roads[r2], to_i, false)
+                {
+                    continue;
+                }
+                movements.insert((*r1, *r2));
+            }
+        }
+    }
+    movements
+}
+
+// Does the road have any lanes for vehicles going into (or if not incoming, out of) i?
+fn has_vehicle_lanes(r: &Road, i: StableIntersectionID, incoming: bool) -> bool {
+    r.lane_specs.iter().any(|spec| {
+        // Lanes that aren't reversed point from src_i to dst_i.
+        let ends_at_i = if spec.reverse_pts {
+            r.src_i == i
+        } else {
+            r.dst_i == i
+        };
+        spec.lane_type.is_for_moving_vehicles() && ends_at_i == incoming
+    })
+}
@@ -6,18 +6,25 @@ mod merge;
 use crate::raw_data::{StableIntersectionID, StableRoadID};
 use crate::{raw_data, IntersectionType, LANE_THICKNESS};
 use abstutil::Timer;
-use geom::{Bounds, Distance, GPSBounds, PolyLine, Pt2D};
+use geom::{Bounds, Distance, GPSBounds, PolyLine, Pt2D, Ring};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 
+#[derive(Serialize, Deserialize)]
 pub struct InitialMap {
     pub roads: BTreeMap<StableRoadID, Road>,
     pub intersections: BTreeMap<StableIntersectionID, Intersection>,
 
     pub name: String,
     pub bounds: Bounds,
+
+    // One entry per call to apply_hint, so the most recent hint can be undone cheaply. None if the
+    // hint didn't apply.
+    #[serde(skip)]
+    undo_log: Vec<Option<Checkpoint>>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Road {
     pub id: StableRoadID,
     pub src_i: StableIntersectionID,
@@ -41,11 +48,15 @@ impl Road {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Intersection {
     pub id: StableIntersectionID,
-    pub polygon: Vec<Pt2D>,
+    pub polygon: Ring,
     pub roads: BTreeSet<StableRoadID>,
     pub intersection_type: IntersectionType,
+    // Pairs of roads whose movement used to go through a short connector road that got merged
+    // into this intersection. Turns between them must survive.
+    pub merged_movements: BTreeSet<(StableRoadID, StableRoadID)>,
 }
 
 impl InitialMap {
@@ -61,19 +72,15 @@ impl InitialMap {
             intersections: BTreeMap::new(),
             name,
             bounds: bounds.clone(),
+            undo_log: Vec::new(),
         };
 
-        for (stable_id, i) in &data.intersections {
-            m.intersections.insert(
-                *stable_id,
-                Intersection {
-                    id: *stable_id,
-                    polygon: Vec::new(),
-                    roads: BTreeSet::new(),
-                    intersection_type: i.intersection_type,
-                },
-            );
-        }
+        // The intersection polygons depend on all the roads, so find those first.
+        let mut incident_roads: BTreeMap<StableIntersectionID, BTreeSet<StableRoadID>> = data
+            .intersections
+            .keys()
+            .map(|id| (*id, BTreeSet::new()))
+            .collect();
 
         for (stable_id, r) in &data.roads {
             if r.i1 == r.i2 {
@@ -83,16 +90,8 @@ impl InitialMap {
                 ));
                 continue;
             }
-            m.intersections
-                .get_mut(&r.i1)
-                .unwrap()
-                .roads
-                .insert(*stable_id);
-            m.intersections
-                .get_mut(&r.i2)
-                .unwrap()
-                .roads
-                .insert(*stable_id);
+            incident_roads.get_mut(&r.i1).unwrap().insert(*stable_id);
+            incident_roads.get_mut(&r.i2).unwrap().insert(*stable_id);
 
             let original_center_pts = PolyLine::new(gps_bounds.must_convert(&r.points));
 
@@ -134,11 +133,22 @@ impl InitialMap {
             );
         }
 
-        timer.start_iter("find each intersection polygon", m.intersections.len());
-        for i in m.intersections.values_mut() {
+        timer.start_iter("find each intersection polygon", data.intersections.len());
+        for (stable_id, i) in &data.intersections {
             timer.next();
 
-            i.polygon = geometry::intersection_polygon(i, &mut m.roads, timer);
+            let roads = incident_roads.remove(stable_id).unwrap();
+            let polygon = geometry::intersection_polygon(*stable_id, &roads, &mut m.roads, timer);
+            m.intersections.insert(
+                *stable_id,
+                Intersection {
+                    id: *stable_id,
+                    polygon,
+                    roads,
+                    intersection_type: i.intersection_type,
+                    merged_movements: BTreeSet::new(),
+                },
+            );
         }
 
         fix_ramps::fix_ramps(&mut m, timer);
@@ -157,12 +167,14 @@ impl InitialMap {
         {
             let mut i = self.intersections.get_mut(&road.src_i).unwrap();
             i.roads.remove(&r);
-            i.polygon = geometry::intersection_polygon(i, &mut self.roads, timer);
+            i.merged_movements.retain(|(r1, r2)| *r1 != r && *r2 != r);
+            i.polygon = geometry::intersection_polygon(i.id, &i.roads, &mut self.roads, timer);
         }
         {
             let mut i = self.intersections.get_mut(&road.dst_i).unwrap();
             i.roads.remove(&r);
-            i.polygon = geometry::intersection_polygon(i, &mut self.roads, timer);
+            i.merged_movements.retain(|(r1, r2)| *r1 != r && *r2 != r);
+            i.polygon = geometry::intersection_polygon(i.id, &i.roads, &mut self.roads, timer);
         }
     }
 
@@ -201,6 +213,12 @@ impl InitialMap {
             let i = self.intersections.get_mut(&new_i1).unwrap();
             i.roads.remove(&r1);
             i.roads.insert(r2);
+            let rename = |r| if r == r1 { r2 } else { r };
+            i.merged_movements = i
+                .merged_movements
+                .iter()
+                .map(|(from, to)| (rename(*from), rename(*to)))
+                .collect();
         }
         // Start at delete_i and go to new_i1.
         let pts_towards_new_i1 = if deleted_road.src_i == delete_i {
@@ -227,11 +245,11 @@ impl InitialMap {
         // And finally the intersection geometry
         {
             let i = self.intersections.get_mut(&new_i1).unwrap();
-            i.polygon = geometry::intersection_polygon(i, &mut self.roads, timer);
+            i.polygon = geometry::intersection_polygon(i.id, &i.roads, &mut self.roads, timer);
         }
         {
             let i = self.intersections.get_mut(&new_i2).unwrap();
-            i.polygon = geometry::intersection_polygon(i, &mut self.roads, timer);
+            i.polygon = geometry::intersection_polygon(i.id, &i.roads, &mut self.roads, timer);
         }
     }
 
@@ -240,29 +258,90 @@ impl InitialMap {
         let mut cnt = 0;
         for h in &hints.hints {
             timer.next();
-            match h {
-                Hint::MergeRoad(orig) => {
-                    if let Some(r) = raw.find_r(*orig) {
-                        cnt += 1;
-                        self.merge_road(r, timer);
-                    }
-                }
-                Hint::DeleteRoad(orig) => {
-                    if let Some(r) = raw.find_r(*orig) {
-                        cnt += 1;
-                        self.delete_road(r, timer);
-                    }
-                }
-                Hint::MergeDegenerateIntersection(orig) => {
-                    if let Some(i) = raw.find_i(*orig) {
-                        cnt += 1;
-                        self.merge_degenerate_intersection(i, timer);
-                    }
-                }
+            if self.apply_hint(h, raw, timer) {
+                cnt += 1;
             }
         }
         timer.note(format!("Applied {} of {} hints", cnt, hints.hints.len()));
     }
+
+    // Returns false if the hint doesn't match anything in this map. Either way, undo_last_hint
+    // will revert this call.
+    pub fn apply_hint(&mut self, hint: &Hint, raw: &raw_data::Map, timer: &mut Timer) -> bool {
+        let checkpoint = match hint {
+            Hint::MergeRoad(orig) => raw.find_r(*orig).map(|r| {
+                let checkpoint = self.checkpoint(vec![self.roads[&r].src_i, self.roads[&r].dst_i]);
+                self.merge_road(r, timer);
+                checkpoint
+            }),
+            Hint::DeleteRoad(orig) => raw.find_r(*orig).map(|r| {
+                let checkpoint = self.checkpoint(vec![self.roads[&r].src_i, self.roads[&r].dst_i]);
+                self.delete_road(r, timer);
+                checkpoint
+            }),
+            Hint::MergeDegenerateIntersection(orig) => raw.find_i(*orig).map(|i| {
+                // The roads on either side get extended to the intersections at their other end,
+                // whose polygons change.
+                let mut affected = vec![i];
+                for r in &self.intersections[&i].roads {
+                    let r = &self.roads[r];
+                    affected.push(if r.src_i == i { r.dst_i } else { r.src_i });
+                }
+                let checkpoint = self.checkpoint(affected);
+                self.merge_degenerate_intersection(i, timer);
+                checkpoint
+            }),
+        };
+        let applied = checkpoint.is_some();
+        self.undo_log.push(checkpoint);
+        applied
+    }
+
+    // Reverts the most recent apply_hint. The result is the same as building from scratch without
+    // that hint. Returns the roads and intersections that changed, or None if there's nothing to
+    // undo.
+    pub fn undo_last_hint(&mut self) -> Option<(Vec<StableRoadID>, Vec<StableIntersectionID>)> {
+        let checkpoint = match self.undo_log.pop()? {
+            Some(c) => c,
+            None => {
+                return Some((Vec::new(), Vec::new()));
+            }
+        };
+        let mut changed_roads = Vec::new();
+        let mut changed_intersections = Vec::new();
+        for r in checkpoint.roads {
+            changed_roads.push(r.id);
+            self.roads.insert(r.id, r);
+        }
+        for i in checkpoint.intersections {
+            changed_intersections.push(i.id);
+            self.intersections.insert(i.id, i);
+        }
+        Some((changed_roads, changed_intersections))
+    }
+
+    // Hints only ever modify or remove the given intersections and the roads touching them;
+    // nothing gets created. So remembering just those is enough to undo.
+    fn checkpoint(&self, intersections: Vec<StableIntersectionID>) -> Checkpoint {
+        let mut roads = BTreeMap::new();
+        let mut saved_intersections = BTreeMap::new();
+        for id in intersections {
+            let i = &self.intersections[&id];
+            for r in &i.roads {
+                roads.insert(*r, self.roads[r].clone());
+            }
+            saved_intersections.insert(id, i.clone());
+        }
+        Checkpoint {
+            roads: roads.into_iter().map(|(_, r)| r).collect(),
+            intersections: saved_intersections.into_iter().map(|(_, i)| i).collect(),
+        }
+    }
+}
+
+struct Checkpoint {
+    roads: Vec<Road>,
+    intersections: Vec<Intersection>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
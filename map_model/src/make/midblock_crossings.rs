@@ -0,0 +1,85 @@
+use crate::{LaneID, LaneType, Map, MidblockCrossing, MidblockCrossingID, Position};
+use abstutil::Timer;
+use geom::{Distance, PolyLine};
+
+// Only residential roads and quieter get mid-block crossings.
+const MAX_RANK: usize = 5;
+const MAX_LANES_TO_CROSS: usize = 2;
+// Close to an intersection, just use the crosswalk there.
+const MIN_DIST_FROM_INTERSECTION: Distance = Distance::const_meters(20.0);
+const SPACING: Distance = Distance::const_meters(50.0);
+
+pub fn make_midblock_crossings(map: &Map, timer: &mut Timer) -> Vec<MidblockCrossing> {
+    timer.start_iter("make mid-block crossings", map.all_roads().len());
+    let mut results: Vec<MidblockCrossing> = Vec::new();
+    for r in map.all_roads() {
+        timer.next();
+        if r.get_rank() > MAX_RANK {
+            continue;
+        }
+        // One-ways with sidewalks on both sides would need both sidewalks in the same direction,
+        // which the sidewalk pathfinder can't tell apart anyway.
+        let fwd_sidewalks = sidewalks(&r.children_forwards);
+        let back_sidewalks = sidewalks(&r.children_backwards);
+        if fwd_sidewalks.len() != 1 || back_sidewalks.len() != 1 {
+            continue;
+        }
+        let vehicle_lanes: Vec<LaneID> = r
+            .all_lanes()
+            .into_iter()
+            .filter(|l| map.get_l(*l).lane_type.is_for_moving_vehicles())
+            .collect();
+        if vehicle_lanes.len() > MAX_LANES_TO_CROSS {
+            continue;
+        }
+
+        let len = r.center_pts.length();
+        if len < 2.0 * MIN_DIST_FROM_INTERSECTION {
+            continue;
+        }
+        // Evenly spread out the crossings, keeping away from both ends.
+        let num_crossings = 1 + ((len - 2.0 * MIN_DIST_FROM_INTERSECTION) / SPACING) as usize;
+        for idx in 0..num_crossings {
+            let percent = (idx as f64 + 1.0) / (num_crossings as f64 + 1.0);
+            let fwd = pos_at_percent(fwd_sidewalks[0], percent, map);
+            let back = pos_at_percent(back_sidewalks[0], percent, map);
+            let crossed_lanes: Vec<Position> = vehicle_lanes
+                .iter()
+                .map(|l| pos_at_percent(*l, percent, map))
+                .collect();
+
+            for (src, dst) in vec![(fwd, back), (back, fwd)] {
+                let id = MidblockCrossingID(results.len());
+                results.push(MidblockCrossing {
+                    id,
+                    parent: r.id,
+                    src,
+                    dst,
+                    geom: PolyLine::new(vec![src.pt(map), dst.pt(map)]),
+                    crossed_lanes: crossed_lanes.clone(),
+                });
+            }
+        }
+    }
+    timer.note(format!("Made {} mid-block crossings", results.len()));
+    results
+}
+
+fn sidewalks(lanes: &Vec<(LaneID, LaneType)>) -> Vec<LaneID> {
+    lanes
+        .iter()
+        .filter(|(_, lt)| *lt == LaneType::Sidewalk)
+        .map(|(id, _)| *id)
+        .collect()
+}
+
+// Lanes on either side of a road point in opposite directions, so measure from the road's start.
+fn pos_at_percent(l: LaneID, percent: f64, map: &Map) -> Position {
+    let lane = map.get_l(l);
+    let len = lane.length();
+    if map.get_parent(l).is_forwards(l) {
+        Position::new(l, percent * len)
+    } else {
+        Position::new(l, (1.0 - percent) * len)
+    }
+}
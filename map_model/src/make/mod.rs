@@ -1,13 +1,19 @@
+mod bike_racks;
 mod buildings;
 mod bus_stops;
 mod half_map;
 mod initial;
+mod midblock_crossings;
+mod parking_lots;
 mod sidewalk_finder;
 mod turns;
 
+pub use self::bike_racks::make_bike_racks;
 pub use self::buildings::make_all_buildings;
 pub use self::bus_stops::{make_bus_stops, verify_bus_routes};
 pub use self::half_map::make_half_map;
 pub use self::initial::lane_specs::{get_lane_types, RoadSpec};
 pub use self::initial::{Hint, Hints, InitialMap};
+pub use self::midblock_crossings::make_midblock_crossings;
+pub use self::parking_lots::make_parking_lots;
 pub use self::turns::make_all_turns;
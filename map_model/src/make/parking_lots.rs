@@ -0,0 +1,86 @@
+use crate::make::sidewalk_finder::find_sidewalk_points;
+use crate::{raw_data, LaneType, Map, ParkingLot, ParkingLotID};
+use abstutil::Timer;
+use geom::{Bounds, Distance, GPSBounds, HashablePt2D, Polygon, Pt2D};
+use std::collections::HashSet;
+
+// Lots tagged as a single node get drawn as a square this wide.
+const NODE_LOT_SIZE: Distance = Distance::const_meters(10.0);
+// Without a capacity tag, a small lot is a reasonable guess for a node.
+const DEFAULT_NODE_LOT_CAPACITY: usize = 10;
+// A spot plus its share of the aisles, for estimating capacity from a lot's area
+const SQ_METERS_PER_SPOT: f64 = 30.0;
+// Lots can be big, and the entrance is measured from the center.
+const MAX_DIST_TO_SIDEWALK: Distance = Distance::const_meters(100.0);
+
+pub fn make_parking_lots(
+    map: &Map,
+    input: &Vec<raw_data::ParkingLot>,
+    gps_bounds: &GPSBounds,
+    bounds: &Bounds,
+    timer: &mut Timer,
+) -> Vec<ParkingLot> {
+    let mut polygons: Vec<Option<Polygon>> = Vec::new();
+    let mut query: HashSet<HashablePt2D> = HashSet::new();
+    for lot in input {
+        let polygon = gps_bounds.try_convert(&lot.points).map(|pts| {
+            if pts.len() < 3 {
+                Polygon::rectangle(pts[0], NODE_LOT_SIZE, NODE_LOT_SIZE)
+            } else {
+                Polygon::new(&pts)
+            }
+        });
+        if let Some(ref p) = polygon {
+            query.insert(p.center().into());
+        }
+        polygons.push(polygon);
+    }
+    let sidewalk_pts =
+        find_sidewalk_points(bounds, query, map.all_lanes(), MAX_DIST_TO_SIDEWALK, timer);
+
+    let mut results: Vec<ParkingLot> = Vec::new();
+    for (lot, polygon) in input.iter().zip(polygons) {
+        let polygon = match polygon {
+            Some(p) => p,
+            None => {
+                continue;
+            }
+        };
+        let sidewalk_pos = match sidewalk_pts.get(&polygon.center().into()) {
+            Some(pos) => *pos,
+            None => {
+                continue;
+            }
+        };
+        let sidewalk = sidewalk_pos.lane();
+        let driving_lane = match map.find_closest_lane(sidewalk, vec![LaneType::Driving]) {
+            Ok(l) => l,
+            Err(_) => {
+                continue;
+            }
+        };
+        let capacity = lot.capacity.unwrap_or_else(|| {
+            if lot.points.len() < 3 {
+                DEFAULT_NODE_LOT_CAPACITY
+            } else {
+                ((polygon.area() / SQ_METERS_PER_SPOT) as usize).max(1)
+            }
+        });
+        results.push(ParkingLot {
+            id: ParkingLotID(results.len()),
+            osm_id: lot.osm_id,
+            capacity,
+            driving_pos: sidewalk_pos.equiv_pos(driving_lane, map),
+            sidewalk_pos,
+            polygon,
+        });
+    }
+    let dropped = input.len() - results.len();
+    if dropped > 0 {
+        timer.note(format!(
+            "Dropped {} parking lots not near a sidewalk with a driving lane alongside",
+            dropped
+        ));
+    }
+    results
+}
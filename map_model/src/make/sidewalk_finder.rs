@@ -1,6 +1,6 @@
 use crate::{Lane, LaneID, Position};
 use abstutil::Timer;
-use geom::{Bounds, Distance, FindClosest, HashablePt2D};
+use geom::{Bounds, Distance, FindClosest, HashablePt2D, Pt2D};
 use std::collections::{HashMap, HashSet};
 
 // If the result doesn't contain a requested point, then there was no matching sidewalk close
@@ -16,14 +16,7 @@ pub fn find_sidewalk_points(
         return HashMap::new();
     }
 
-    let mut closest: FindClosest<LaneID> = FindClosest::new(bounds);
-    timer.start_iter("index lanes", lanes.len());
-    for l in lanes {
-        timer.next();
-        if l.is_sidewalk() {
-            closest.add(l.id, l.lane_center_pts.points());
-        }
-    }
+    let finder = SidewalkFinder::new(bounds, lanes, timer);
 
     // For each point, find the closest point to any sidewalk, using the quadtree to prune the
     // search.
@@ -31,13 +24,49 @@ pub fn find_sidewalk_points(
     timer.start_iter("find closest sidewalk point", pts.len());
     for query_pt in pts {
         timer.next();
-        if let Some((sidewalk, sidewalk_pt)) = closest.closest_pt(query_pt.into(), max_dist_away) {
-            if let Some(dist_along) = lanes[sidewalk.0].dist_along_of_point(sidewalk_pt) {
-                results.insert(query_pt, Position::new(sidewalk, dist_along));
-            } else {
-                panic!("{} isn't on {} according to dist_along_of_point, even though closest_point thinks it is.\n{}", sidewalk_pt, sidewalk, lanes[sidewalk.0].lane_center_pts);
-            }
+        if let Some((pos, _)) = finder
+            .all_close(query_pt.into(), max_dist_away)
+            .into_iter()
+            .next()
+        {
+            results.insert(query_pt, pos);
         }
     }
     results
 }
+
+// An index of every sidewalk, for callers that need more than the single closest one.
+pub struct SidewalkFinder<'a> {
+    lanes: &'a Vec<Lane>,
+    closest: FindClosest<LaneID>,
+}
+
+impl<'a> SidewalkFinder<'a> {
+    pub fn new(bounds: &Bounds, lanes: &'a Vec<Lane>, timer: &mut Timer) -> SidewalkFinder<'a> {
+        let mut closest: FindClosest<LaneID> = FindClosest::new(bounds);
+        timer.start_iter("index lanes", lanes.len());
+        for l in lanes {
+            timer.next();
+            if l.is_sidewalk() {
+                closest.add(l.id, l.lane_center_pts.points());
+            }
+        }
+        SidewalkFinder { lanes, closest }
+    }
+
+    // Every sidewalk within max_dist_away of the point, closest first, with the position on each
+    // sidewalk closest to the point.
+    pub fn all_close(&self, pt: Pt2D, max_dist_away: Distance) -> Vec<(Position, Distance)> {
+        self.closest
+            .all_close_pts(pt, max_dist_away)
+            .into_iter()
+            .map(|(sidewalk, sidewalk_pt, dist)| {
+                if let Some(dist_along) = self.lanes[sidewalk.0].dist_along_of_point(sidewalk_pt) {
+                    (Position::new(sidewalk, dist_along), dist)
+                } else {
+                    panic!("{} isn't on {} according to dist_along_of_point, even though closest_point thinks it is.\n{}", sidewalk_pt, sidewalk, self.lanes[sidewalk.0].lane_center_pts);
+                }
+            })
+            .collect()
+    }
+}
@@ -9,6 +9,10 @@ use std::collections::{BTreeSet, HashSet};
 
 // TODO Add proper warnings when the geometry is too small to handle.
 
+const UTURN_TOLERANCE_DEGREES: f64 = 10.0;
+// The two halves of a divided road are rarely drawn exactly parallel.
+const DIVIDED_UTURN_TOLERANCE_DEGREES: f64 = 30.0;
+
 pub fn make_all_turns(
     i: &Intersection,
     roads: &Vec<Road>,
@@ -19,6 +23,7 @@ pub fn make_all_turns(
 
     let mut turns: Vec<Turn> = Vec::new();
     turns.extend(make_vehicle_turns(i, roads, lanes, timer));
+    turns.extend(make_merged_movement_turns(i, roads, lanes, &turns));
     turns.extend(make_walking_turns(i, roads, lanes, timer));
     let turns = ensure_unique(turns);
 
@@ -204,6 +209,36 @@ fn make_vehicle_turns(
     result.into_iter().filter_map(|x| x).collect()
 }
 
+// Roads that used to meet over a short connector road get turns as if the connector were part of
+// this intersection: every incoming lane to every compatible outgoing lane.
+fn make_merged_movement_turns(
+    i: &Intersection,
+    all_roads: &Vec<Road>,
+    lanes: &Vec<Lane>,
+    existing: &Vec<Turn>,
+) -> Vec<Turn> {
+    let existing: HashSet<TurnID> = existing.iter().map(|t| t.id).collect();
+    let mut result = Vec::new();
+    for (r1, r2) in &i.merged_movements {
+        let r1 = &all_roads[r1.0];
+        let r2 = &all_roads[r2.0];
+        for (l1, lt) in r1.incoming_lanes(i.id) {
+            if !lt.is_for_moving_vehicles() {
+                continue;
+            }
+            for l2 in filter_vehicle_lanes(r2.outgoing_lanes(i.id), *lt) {
+                if existing.contains(&turn_id(i.id, *l1, l2)) {
+                    continue;
+                }
+                if let Some(t) = make_vehicle_turn(lanes, i.id, *l1, l2) {
+                    result.push(t);
+                }
+            }
+        }
+    }
+    result
+}
+
 fn make_vehicle_turns_for_dead_end(
     i: &Intersection,
     roads: &Vec<Road>,
@@ -227,6 +262,24 @@ fn make_vehicle_turns_for_dead_end(
     Warn::ok(result)
 }
 
+// Back onto the same road, or pointing almost exactly the opposite way. Heading back towards the
+// intersection the source lane came from (the other half of a divided road, usually) gets more
+// slack, but loops and frontage roads also lead back there, so the angle still has to match.
+fn is_uturn(src: &Lane, dst: &Lane) -> bool {
+    if src.parent == dst.parent {
+        return true;
+    }
+    let tolerance = if src.src_i == dst.dst_i {
+        DIVIDED_UTURN_TOLERANCE_DEGREES
+    } else {
+        UTURN_TOLERANCE_DEGREES
+    };
+    src.last_line()
+        .angle()
+        .opposite()
+        .approx_eq(dst.first_line().angle(), tolerance)
+}
+
 fn make_walking_turns(
     i: &Intersection,
     all_roads: &Vec<Road>,
@@ -256,16 +309,19 @@ fn make_walking_turns(
                 ) {
                     if !l1.last_pt().epsilon_eq(l2.first_pt()) {
                         let geom = make_shared_sidewalk_corner(i, l1, l2, timer);
+                        let geom_back = geom.reversed();
                         result.push(Turn {
                             id: turn_id(i.id, l1.id, l2.id),
                             turn_type: TurnType::SharedSidewalkCorner,
-                            geom: geom.clone(),
+                            length: geom.length(),
+                            geom,
                             lookup_idx: 0,
                         });
                         result.push(Turn {
                             id: turn_id(i.id, l2.id, l1.id),
                             turn_type: TurnType::SharedSidewalkCorner,
-                            geom: geom.reversed(),
+                            length: geom_back.length(),
+                            geom: geom_back,
                             lookup_idx: 0,
                         });
                     }
@@ -294,18 +350,21 @@ fn make_crosswalks(i: IntersectionID, l1: &Lane, l2: &Lane) -> Vec<Turn> {
     // Jut out a bit into the intersection, cross over, then jut back in.
     let line = Line::new(l1.last_pt(), l2.first_pt()).shift_right(LANE_THICKNESS / 2.0);
     let geom_fwds = PolyLine::new(vec![l1.last_pt(), line.pt1(), line.pt2(), l2.first_pt()]);
+    let geom_back = geom_fwds.reversed();
 
     vec![
         Turn {
             id: turn_id(i, l1.id, l2.id),
             turn_type: TurnType::Crosswalk,
-            geom: geom_fwds.clone(),
+            length: geom_fwds.length(),
+            geom: geom_fwds,
             lookup_idx: 0,
         },
         Turn {
             id: turn_id(i, l2.id, l1.id),
             turn_type: TurnType::Crosswalk,
-            geom: geom_fwds.reversed(),
+            length: geom_back.length(),
+            geom: geom_back,
             lookup_idx: 0,
         },
     ]
@@ -408,7 +467,11 @@ fn filter_lanes(lanes: &Vec<(LaneID, LaneType)>, filter: LaneType) -> Vec<LaneID
 fn make_vehicle_turn(lanes: &Vec<Lane>, i: IntersectionID, l1: LaneID, l2: LaneID) -> Option<Turn> {
     let src = &lanes[l1.0];
     let dst = &lanes[l2.0];
-    let turn_type = TurnType::from_angles(src.last_line().angle(), dst.first_line().angle());
+    let turn_type = if is_uturn(src, dst) {
+        TurnType::UTurn
+    } else {
+        TurnType::from_angles(src.last_line().angle(), dst.first_line().angle())
+    };
 
     if src.last_pt().epsilon_eq(dst.first_pt()) {
         return None;
@@ -447,6 +510,7 @@ fn make_vehicle_turn(lanes: &Vec<Lane>, i: IntersectionID, l1: LaneID, l2: LaneI
     Some(Turn {
         id: turn_id(i, l1, l2),
         turn_type,
+        length: geom.length(),
         geom,
         lookup_idx: 0,
     })
@@ -1,16 +1,18 @@
 use crate::make::get_lane_types;
 use crate::pathfind::Pathfinder;
 use crate::{
-    make, raw_data, Area, AreaID, Building, BuildingID, BusRoute, BusRouteID, BusStop, BusStopID,
-    ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, IntersectionType, Lane,
-    LaneID, LaneType, MapEdits, Path, PathRequest, Position, Road, RoadID, Turn, TurnID,
-    TurnPriority,
+    make, raw_data, Area, AreaID, BikeRack, BikeRackID, Building, BuildingID, BusRoute, BusRouteID,
+    BusRouteReport, BusStop, BusStopID, ConnectivityReport, ControlStopSign, ControlTrafficSignal,
+    DroppedBusStop, Intersection, IntersectionID, IntersectionType, Lane, LaneID, LaneType,
+    MapEdits, MidblockCrossing, MidblockCrossingID, ParkingLot, ParkingLotID, Path, PathRequest,
+    Position, Road, RoadDirectionChange, RoadID, Turn, TurnID, TurnPriority, TurnType,
+    LANE_THICKNESS,
 };
 use abstutil;
 use abstutil::{deserialize_btreemap, serialize_btreemap, Error, Timer};
 use geom::{Bounds, GPSBounds, Polygon};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::io;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -30,7 +32,12 @@ pub struct Map {
     )]
     bus_stops: BTreeMap<BusStopID, BusStop>,
     bus_routes: Vec<BusRoute>,
+    bus_route_reports: Vec<BusRouteReport>,
+    dropped_bus_stops: Vec<DroppedBusStop>,
     areas: Vec<Area>,
+    midblock_crossings: Vec<MidblockCrossing>,
+    bike_racks: Vec<BikeRack>,
+    parking_lots: Vec<ParkingLot>,
     boundary_polygon: Polygon,
 
     // Note that border nodes belong in neither!
@@ -50,22 +57,50 @@ pub struct Map {
 
 impl Map {
     pub fn new(path: &str, timer: &mut Timer) -> Result<Map, io::Error> {
+        Map::new_maybe_parallel(path, false, timer)
+    }
+
+    // With parallel, independent phases of construction run at the same time on a few threads.
+    // The resulting Map is identical either way.
+    pub fn new_maybe_parallel(
+        path: &str,
+        parallel: bool,
+        timer: &mut Timer,
+    ) -> Result<Map, io::Error> {
         let data: raw_data::Map = abstutil::read_binary(path, timer)?;
-        Ok(Map::create_from_raw(abstutil::basename(path), data, timer))
+        let hints = raw_data::Hints::load();
+        Ok(Map::create_from_raw_with_hints(
+            abstutil::basename(path),
+            data,
+            &hints,
+            parallel,
+            timer,
+        ))
     }
 
     pub fn create_from_raw(name: String, data: raw_data::Map, timer: &mut Timer) -> Map {
+        let hints = raw_data::Hints::load();
+        Map::create_from_raw_with_hints(name, data, &hints, false, timer)
+    }
+
+    pub fn create_from_raw_with_hints(
+        name: String,
+        data: raw_data::Map,
+        hints: &raw_data::Hints,
+        parallel: bool,
+        timer: &mut Timer,
+    ) -> Map {
         timer.start("raw_map to InitialMap");
         let gps_bounds = data.gps_bounds.clone();
         let bounds = gps_bounds.to_bounds();
         let mut initial_map =
             make::InitialMap::new(name.clone(), &data, &gps_bounds, &bounds, timer);
-        let hints = raw_data::Hints::load();
-        initial_map.apply_hints(&hints, &data, timer);
+        initial_map.apply_hints(hints, &data, timer);
         timer.stop("raw_map to InitialMap");
 
         timer.start("InitialMap to HalfMap");
-        let half_map = make::make_half_map(&data, initial_map, &gps_bounds, &bounds, timer);
+        let half_map =
+            make::make_half_map(&data, initial_map, &gps_bounds, &bounds, parallel, timer);
         timer.stop("InitialMap to HalfMap");
 
         timer.start("finalize Map");
@@ -77,7 +112,12 @@ impl Map {
             buildings: half_map.buildings,
             bus_stops: BTreeMap::new(),
             bus_routes: Vec::new(),
+            bus_route_reports: Vec::new(),
+            dropped_bus_stops: Vec::new(),
             areas: half_map.areas,
+            midblock_crossings: Vec::new(),
+            bike_racks: Vec::new(),
+            parking_lots: Vec::new(),
             boundary_polygon: Polygon::new(&gps_bounds.must_convert(&data.boundary_polygon)),
             stop_signs: BTreeMap::new(),
             traffic_signals: BTreeMap::new(),
@@ -109,20 +149,55 @@ impl Map {
             m.traffic_signals = traffic_signals;
         }
 
-        timer.start("setup Pathfinder");
-        m.pathfinder = Some(Pathfinder::new(&m));
-        timer.stop("setup Pathfinder");
+        m.make_midblock_crossings(timer);
+
+        // Neither side changes the map until both are done. Note the pathfinder is built before
+        // any bus stops exist.
+        let (pathfinder, (bus_stops, bike_racks, parking_lots)) = {
+            let m = &m;
+            let data = &data;
+            timer.run_pair(
+                parallel,
+                "setup Pathfinder",
+                move |_| Pathfinder::new(m),
+                "make bus stops, bike racks, and parking lots",
+                move |timer| {
+                    (
+                        make::make_bus_stops(m, &data.bus_routes, &m.gps_bounds, &m.bounds, timer),
+                        make::make_bike_racks(m, &data.bike_racks, &m.gps_bounds, &m.bounds, timer),
+                        make::make_parking_lots(
+                            m,
+                            &data.parking_lots,
+                            &m.gps_bounds,
+                            &m.bounds,
+                            timer,
+                        ),
+                    )
+                },
+            )
+        };
+        m.pathfinder = Some(pathfinder);
+        m.bike_racks = bike_racks;
+        m.parking_lots = parking_lots;
 
         {
-            let (stops, routes) =
-                make::make_bus_stops(&m, &data.bus_routes, &m.gps_bounds, &m.bounds, timer);
+            let (stops, routes, reports, dropped_stops) = bus_stops;
             m.bus_stops = stops;
+            m.dropped_bus_stops = dropped_stops;
             // The IDs are sorted in the BTreeMap, so this order winds up correct.
             for id in m.bus_stops.keys() {
                 m.lanes[id.sidewalk.0].bus_stops.push(*id);
             }
 
-            m.bus_routes = make::verify_bus_routes(&m, routes, timer);
+            let (routes, reports) = make::verify_bus_routes(&m, routes, reports, timer);
+            m.bus_routes = routes;
+            m.bus_route_reports = reports;
+        }
+
+        // Lost movements are always a bug in map conversion, unlike the other problems, but the
+        // rest of the map is still usable, so they're just reported along with everything else.
+        for problem in m.connectivity_audit().describe() {
+            timer.warn(problem);
         }
 
         timer.stop("finalize Map");
@@ -153,6 +228,18 @@ impl Map {
         &self.areas
     }
 
+    pub fn all_midblock_crossings(&self) -> &Vec<MidblockCrossing> {
+        &self.midblock_crossings
+    }
+
+    pub fn all_bike_racks(&self) -> &Vec<BikeRack> {
+        &self.bike_racks
+    }
+
+    pub fn all_parking_lots(&self) -> &Vec<ParkingLot> {
+        &self.parking_lots
+    }
+
     pub fn maybe_get_r(&self, id: RoadID) -> Option<&Road> {
         self.roads.get(id.0)
     }
@@ -213,6 +300,18 @@ impl Map {
         &self.areas[id.0]
     }
 
+    pub fn get_mc(&self, id: MidblockCrossingID) -> &MidblockCrossing {
+        &self.midblock_crossings[id.0]
+    }
+
+    pub fn get_bike_rack(&self, id: BikeRackID) -> &BikeRack {
+        &self.bike_racks[id.0]
+    }
+
+    pub fn get_parking_lot(&self, id: ParkingLotID) -> &ParkingLot {
+        &self.parking_lots[id.0]
+    }
+
     pub fn get_stop_sign(&self, id: IntersectionID) -> &ControlStopSign {
         &self.stop_signs[&id]
     }
@@ -243,6 +342,59 @@ impl Map {
             .collect()
     }
 
+    // People think of a street as one thing, but it's usually split into many roads. Match by name,
+    // since OSM way IDs change a fair bit.
+    pub fn roads_with_name(&self, name: &str) -> Vec<RoadID> {
+        self.roads
+            .iter()
+            .filter(|r| r.get_name() == name)
+            .map(|r| r.id)
+            .collect()
+    }
+
+    // The roads making up the same street as this one: the other pieces of its OSM way, and
+    // anything connected to those that shares its OSM name. Unrelated streets elsewhere with the
+    // same name or ref don't match, and roads without a name never match every other unnamed road.
+    pub fn street_roads(&self, r: RoadID) -> Vec<RoadID> {
+        let road = self.get_r(r);
+        let name = road.get_osm_name();
+        let mut found: BTreeSet<RoadID> = self
+            .roads
+            .iter()
+            .filter(|other| other.osm_way_id == road.osm_way_id)
+            .map(|other| other.id)
+            .collect();
+        if name.is_none() {
+            return found.into_iter().collect();
+        }
+
+        let mut queue: Vec<RoadID> = found.iter().cloned().collect();
+        while let Some(current) = queue.pop() {
+            let current = self.get_r(current);
+            for i in vec![current.src_i, current.dst_i] {
+                for next in &self.get_i(i).roads {
+                    if !found.contains(next) && self.get_r(*next).get_osm_name() == name {
+                        found.insert(*next);
+                        queue.push(*next);
+                    }
+                }
+            }
+        }
+        found.into_iter().collect()
+    }
+
+    // Every road, keyed by the name of the street it's part of
+    pub fn group_roads_by_name(&self) -> HashMap<String, Vec<RoadID>> {
+        let mut groups: HashMap<String, Vec<RoadID>> = HashMap::new();
+        for r in &self.roads {
+            groups
+                .entry(r.get_name())
+                .or_insert_with(Vec::new)
+                .push(r.id);
+        }
+        groups
+    }
+
     // TODO Get rid of this, or rewrite it in in terms of get_next_turns_and_lanes
     // The turns may belong to two different intersections!
     pub fn get_turns_from_lane(&self, l: LaneID) -> Vec<&Turn> {
@@ -308,6 +460,13 @@ impl Map {
         self.get_r(l.parent)
     }
 
+    // Bikes can use driving and bike lanes, but never anything imported from OSM steps.
+    pub fn is_bikeable(&self, id: LaneID) -> bool {
+        let l = self.get_l(id);
+        (l.is_driving() || l.is_biking())
+            && self.get_parent(id).osm_tags.get("highway") != Some(&"steps".to_string())
+    }
+
     pub fn get_gps_bounds(&self) -> &GPSBounds {
         &self.gps_bounds
     }
@@ -336,6 +495,16 @@ impl Map {
         &self.bus_routes
     }
 
+    // Routes from the input data that lost stops or were dropped entirely
+    pub fn get_bus_route_reports(&self) -> &Vec<BusRouteReport> {
+        &self.bus_route_reports
+    }
+
+    // Stops from the input data that couldn't be placed on any sidewalk served by buses
+    pub fn get_dropped_bus_stops(&self) -> &Vec<DroppedBusStop> {
+        &self.dropped_bus_stops
+    }
+
     pub fn get_bus_route(&self, name: &str) -> Option<&BusRoute> {
         self.bus_routes.iter().find(|r| r.name == name)
     }
@@ -470,6 +639,9 @@ impl Map {
     }
 
     pub fn is_turn_allowed(&self, t: TurnID) -> bool {
+        if self.get_t(t).turn_type == TurnType::UTurn && !self.are_uturns_allowed(t.parent) {
+            return false;
+        }
         if let Some(ss) = self.stop_signs.get(&t.parent) {
             ss.get_priority(t) != TurnPriority::Banned
         } else if let Some(ts) = self.traffic_signals.get(&t.parent) {
@@ -482,6 +654,13 @@ impl Map {
         }
     }
 
+    pub fn are_uturns_allowed(&self, i: IntersectionID) -> bool {
+        let i = self.get_i(i);
+        i.is_dead_end()
+            || i.intersection_type == IntersectionType::Border
+            || self.edits.allow_uturns.contains(&i.id)
+    }
+
     // When driving towards some goal building, there may not be a driving lane directly outside the
     // building. So BFS out in a deterministic way and find one.
     pub fn find_driving_lane_near_building(&self, b: BuildingID) -> LaneID {
@@ -535,6 +714,10 @@ impl Map {
         self.pathfinder.as_ref().unwrap().pathfind(req, self)
     }
 
+    pub fn connectivity_audit(&self) -> ConnectivityReport {
+        ConnectivityReport::new(self)
+    }
+
     pub fn should_use_transit(
         &self,
         start: Position,
@@ -552,10 +735,12 @@ impl Map {
         &self.edits
     }
 
-    // new_edits assumed to be valid. Returns actual lanes that changed, turns deleted, turns added.
+    // new_edits assumed to be valid, except that direction changes that don't fit the road (maybe
+    // from edits saved against an older map) get skipped with a warning. Returns actual lanes that
+    // changed, turns deleted, turns added.
     pub fn apply_edits(
         &mut self,
-        new_edits: MapEdits,
+        mut new_edits: MapEdits,
         timer: &mut Timer,
     ) -> (BTreeSet<LaneID>, BTreeSet<TurnID>, BTreeSet<TurnID>) {
         // Ignore if there's no change from current
@@ -563,6 +748,8 @@ impl Map {
         let mut all_stop_sign_edits: BTreeMap<IntersectionID, ControlStopSign> = BTreeMap::new();
         let mut all_traffic_signals: BTreeMap<IntersectionID, ControlTrafficSignal> =
             BTreeMap::new();
+        // The number of forwards lanes each road should wind up with
+        let mut all_direction_edits: BTreeMap<RoadID, usize> = BTreeMap::new();
         for (id, lt) in &new_edits.lane_overrides {
             if self.edits.lane_overrides.get(id) != Some(lt) {
                 all_lane_edits.insert(*id, *lt);
@@ -578,6 +765,20 @@ impl Map {
                 all_traffic_signals.insert(*id, ts.clone());
             }
         }
+        let mut impossible_directions = Vec::new();
+        for (id, change) in &new_edits.direction_overrides {
+            if self.edits.direction_overrides.get(id) != Some(change) {
+                if let Some(num_fwd) = self.num_fwd_lanes_after(*id, Some(*change)) {
+                    all_direction_edits.insert(*id, num_fwd);
+                } else {
+                    timer.warn(format!("Can't {:?} on {}, skipping it", change, id));
+                    impossible_directions.push(*id);
+                }
+            }
+        }
+        for id in impossible_directions {
+            new_edits.direction_overrides.remove(&id);
+        }
 
         // May need to revert some previous changes
         for id in self.edits.lane_overrides.keys() {
@@ -595,17 +796,41 @@ impl Map {
                 all_traffic_signals.insert(*id, ControlTrafficSignal::new(self, *id, timer));
             }
         }
+        for id in self.edits.direction_overrides.keys() {
+            if !new_edits.direction_overrides.contains_key(id) {
+                all_direction_edits.insert(*id, self.num_fwd_lanes_after(*id, None).unwrap());
+            }
+        }
+        let all_uturn_edits: BTreeSet<IntersectionID> = self
+            .edits
+            .allow_uturns
+            .symmetric_difference(&new_edits.allow_uturns)
+            .cloned()
+            .collect();
 
         timer.note(format!(
-            "Total diff: {} lanes, {} stop signs, {} traffic signals",
+            "Total diff: {} lanes, {} roads changing direction, {} stop signs, {} traffic signals, {} intersections changing u-turns",
             all_lane_edits.len(),
+            all_direction_edits.len(),
             all_stop_sign_edits.len(),
-            all_traffic_signals.len()
+            all_traffic_signals.len(),
+            all_uturn_edits.len()
         ));
 
         let mut changed_lanes = BTreeSet::new();
         let mut changed_intersections = BTreeSet::new();
         let mut changed_roads = BTreeSet::new();
+        let changed_directions = !all_direction_edits.is_empty();
+        for (id, num_fwd) in all_direction_edits {
+            self.set_num_fwd_lanes(id, num_fwd, timer);
+
+            let r = self.get_r(id);
+            changed_lanes.extend(r.all_lanes());
+            changed_intersections.insert(r.src_i);
+            changed_intersections.insert(r.dst_i);
+            changed_roads.insert(id);
+        }
+
         for (id, lt) in all_lane_edits {
             changed_lanes.insert(id);
 
@@ -625,6 +850,13 @@ impl Map {
             changed_roads.insert(l.parent);
         }
 
+        // Whether a road gets crossings and which lanes they cut across depend on lane types and
+        // directions.
+        let changed_crossings = !changed_roads.is_empty();
+        if changed_crossings {
+            self.make_midblock_crossings(timer);
+        }
+
         for id in changed_roads {
             let stops = self.get_r(id).all_bus_stops(self);
             for s in stops {
@@ -632,7 +864,10 @@ impl Map {
                 // Must exist, because we aren't allowed to orphan a bus stop.
                 let driving_lane = self
                     .get_r(id)
-                    .find_closest_lane(sidewalk_pos.lane(), vec![LaneType::Driving, LaneType::Bus])
+                    .find_closest_lane(
+                        sidewalk_pos.lane(),
+                        vec![LaneType::Driving, LaneType::Bus, LaneType::HOV],
+                    )
                     .unwrap();
                 let driving_pos = sidewalk_pos.equiv_pos(driving_lane, self);
                 self.bus_stops.get_mut(&s).unwrap().driving_pos = driving_pos;
@@ -702,10 +937,31 @@ impl Map {
                 changed_lanes.insert(*l);
             }
         }
+        // is_turn_allowed looks at the current edits, so the pathfinder needs these in place.
+        // U-turns that become banned have to be deleted from the pathfinder explicitly.
+        self.edits.allow_uturns = new_edits.allow_uturns.clone();
+        for id in all_uturn_edits {
+            for t in &self.get_i(id).turns {
+                if self.get_t(*t).turn_type == TurnType::UTurn {
+                    delete_turns.insert(*t);
+                }
+                add_turns.insert(*t);
+            }
+        }
 
-        let mut pathfinder = self.pathfinder.take().unwrap();
-        pathfinder.apply_edits(&delete_turns, &add_turns, self);
-        self.pathfinder = Some(pathfinder);
+        if changed_directions {
+            // The vehicle graphs have one node per direction of a road, and only for directions
+            // that have lanes. Patching that up in place isn't worth it yet; just start over.
+            let pathfinder = Pathfinder::new(self);
+            self.pathfinder = Some(pathfinder);
+        } else {
+            let mut pathfinder = self.pathfinder.take().unwrap();
+            pathfinder.apply_edits(&delete_turns, &add_turns, self);
+            if changed_crossings {
+                pathfinder.recalculate_midblock_crossings(self);
+            }
+            self.pathfinder = Some(pathfinder);
+        }
 
         self.edits = new_edits;
         (changed_lanes, delete_turns, add_turns)
@@ -741,6 +997,43 @@ impl Map {
         for id in delete_signals {
             self.edits.traffic_signal_overrides.remove(&id);
         }
+
+        let mut delete_directions = Vec::new();
+        for (id, change) in &self.edits.direction_overrides {
+            if self.num_fwd_lanes_after(*id, Some(*change)) == self.num_fwd_lanes_after(*id, None) {
+                delete_directions.push(*id);
+            }
+        }
+        for id in delete_directions {
+            self.edits.direction_overrides.remove(&id);
+        }
+
+        let mut delete_uturns = Vec::new();
+        for id in &self.edits.allow_uturns {
+            let i = self.get_i(*id);
+            if i.is_dead_end() || i.intersection_type == IntersectionType::Border {
+                delete_uturns.push(*id);
+            }
+        }
+        for id in delete_uturns {
+            self.edits.allow_uturns.remove(&id);
+        }
+    }
+
+    // True if the change is possible on this road and would point some lanes differently than
+    // they are now.
+    pub fn can_change_direction(&self, r: RoadID, change: RoadDirectionChange) -> bool {
+        match self.num_fwd_lanes_after(r, Some(change)) {
+            Some(num_fwd) => num_fwd != self.get_r(r).children_forwards.len(),
+            None => false,
+        }
+    }
+
+    // How many vehicle lanes in the middle of the road could point either way. These are the
+    // lanes that a RoadDirectionChange moves between sides.
+    pub fn num_reversible_lanes(&self, r: RoadID) -> usize {
+        let (fwd, back) = self.original_reversible_lanes(r);
+        fwd + back
     }
 
     fn get_original_lt(&self, id: LaneID) -> LaneType {
@@ -750,11 +1043,126 @@ impl Map {
             parent.parking_lane_fwd,
             parent.parking_lane_back,
         );
+        // Lanes that changed direction kept their physical position across the road, so count
+        // from the original center line instead of the current one.
+        let shift = (parent.children_forwards.len() as isize) - (side1.len() as isize);
         let (fwds, idx) = parent.dir_and_offset(id);
-        if fwds {
-            side1[idx]
+        let pos = if fwds {
+            idx as isize - shift
+        } else {
+            -(idx as isize) - 1 - shift
+        };
+        if pos >= 0 {
+            side1[pos as usize]
         } else {
-            side2[idx]
+            side2[(-pos - 1) as usize]
+        }
+    }
+
+    // The number of reversible lanes originally pointing (forwards, backwards).
+    fn original_reversible_lanes(&self, r: RoadID) -> (usize, usize) {
+        let road = self.get_r(r);
+        let (side1, side2) = get_lane_types(
+            &road.osm_tags,
+            road.parking_lane_fwd,
+            road.parking_lane_back,
+        );
+        let count = |side: Vec<LaneType>| {
+            side.into_iter()
+                .take_while(|lt| lt.is_for_moving_vehicles())
+                .count()
+        };
+        (count(side1), count(side2))
+    }
+
+    // How many lanes the forwards side of a road has after a change, or None if the change isn't
+    // possible there. No change means the original layout.
+    fn num_fwd_lanes_after(&self, r: RoadID, change: Option<RoadDirectionChange>) -> Option<usize> {
+        let road = self.get_r(r);
+        let orig_fwd = get_lane_types(
+            &road.osm_tags,
+            road.parking_lane_fwd,
+            road.parking_lane_back,
+        )
+        .0
+        .len();
+        let (fwd, back) = self.original_reversible_lanes(r);
+        match change {
+            None => Some(orig_fwd),
+            Some(_) if fwd + back == 0 => None,
+            Some(RoadDirectionChange::MakeOneWayForward) => Some(orig_fwd + back),
+            Some(RoadDirectionChange::MakeOneWayBackward) => Some(orig_fwd - fwd),
+            Some(RoadDirectionChange::MakeTwoWay(num_back)) => {
+                if num_back == 0 || num_back >= fwd + back {
+                    None
+                } else {
+                    Some(orig_fwd + back - num_back)
+                }
+            }
+        }
+    }
+
+    // Every lane keeps its physical position; only the split between the two sides moves. Lanes
+    // that cross it get reversed.
+    // Crossing IDs aren't stable; this starts over from scratch.
+    fn make_midblock_crossings(&mut self, timer: &mut Timer) {
+        for l in self.lanes.iter_mut() {
+            l.midblock_crossings.clear();
+        }
+        self.midblock_crossings = make::make_midblock_crossings(self, timer);
+        for c in &self.midblock_crossings {
+            self.lanes[c.src.lane().0].midblock_crossings.push(c.id);
+        }
+    }
+
+    fn set_num_fwd_lanes(&mut self, r: RoadID, num_fwd: usize, timer: &mut Timer) {
+        let road = &mut self.roads[r.0];
+        // From the outermost backwards lane to the outermost forwards lane
+        let mut all: Vec<(LaneID, LaneType)> = road
+            .children_backwards
+            .iter()
+            .rev()
+            .chain(road.children_forwards.iter())
+            .cloned()
+            .collect();
+        let old_split = road.children_backwards.len();
+        let new_split = all.len() - num_fwd;
+        let flipped: Vec<LaneID> = all[old_split.min(new_split)..old_split.max(new_split)]
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        road.children_forwards = all.split_off(new_split);
+        all.reverse();
+        road.children_backwards = all;
+
+        for id in flipped {
+            let lane = &mut self.lanes[id.0];
+            lane.lane_center_pts = lane.lane_center_pts.reversed();
+            std::mem::swap(&mut lane.src_i, &mut lane.dst_i);
+
+            let src_i = &mut self.intersections[lane.src_i.0];
+            src_i.incoming_lanes.retain(|l| *l != id);
+            src_i.outgoing_lanes.push(id);
+            let dst_i = &mut self.intersections[lane.dst_i.0];
+            dst_i.outgoing_lanes.retain(|l| *l != id);
+            dst_i.incoming_lanes.push(id);
         }
+
+        // The road's center line is the split, running along the left edge of the centermost lane
+        // on either side.
+        let road = &mut self.roads[r.0];
+        road.center_pts = if let Some((l, _)) = road.children_forwards.get(0) {
+            self.lanes[l.0]
+                .lane_center_pts
+                .shift_left(LANE_THICKNESS / 2.0)
+                .get(timer)
+        } else {
+            let (l, _) = road.children_backwards[0];
+            self.lanes[l.0]
+                .lane_center_pts
+                .shift_left(LANE_THICKNESS / 2.0)
+                .get(timer)
+                .reversed()
+        };
     }
 }
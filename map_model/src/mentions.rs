@@ -0,0 +1,58 @@
+use crate::raw_data::{StableIntersectionID, StableRoadID};
+use crate::{BuildingID, IntersectionID, LaneID, RoadID};
+
+// An object mentioned in some free-form text, like a Timer warning.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MentionedID {
+    Road(RoadID),
+    Lane(LaneID),
+    Intersection(IntersectionID),
+    Building(BuildingID),
+    // Warnings from building the initial map only know about these
+    StableRoad(StableRoadID),
+    StableIntersection(StableIntersectionID),
+}
+
+// Looks for the Display (and Debug) formats of IDs, like "RoadID(42)", in the order they appear.
+pub fn find_mentioned_ids(line: &str) -> Vec<MentionedID> {
+    let mut results = Vec::new();
+    let mut search_from = 0;
+    while let Some(offset) = line[search_from..].find("ID(") {
+        let idx = search_from + offset;
+        search_from = idx + "ID(".len();
+
+        let digits: String = line[search_from..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if !line[search_from + digits.len()..].starts_with(')') {
+            continue;
+        }
+        let num = match digits.parse::<usize>() {
+            Ok(n) => n,
+            Err(_) => {
+                continue;
+            }
+        };
+
+        let mut kind: Vec<char> = line[..idx]
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        kind.reverse();
+        let kind: String = kind.into_iter().collect();
+        results.push(match kind.as_str() {
+            "Road" => MentionedID::Road(RoadID(num)),
+            "Lane" => MentionedID::Lane(LaneID(num)),
+            "Intersection" => MentionedID::Intersection(IntersectionID(num)),
+            "Building" => MentionedID::Building(BuildingID(num)),
+            "StableRoad" => MentionedID::StableRoad(StableRoadID(num)),
+            "StableIntersection" => MentionedID::StableIntersection(StableIntersectionID(num)),
+            _ => {
+                continue;
+            }
+        });
+    }
+    results
+}
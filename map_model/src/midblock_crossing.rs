@@ -0,0 +1,28 @@
+use crate::{Position, RoadID};
+use geom::PolyLine;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct MidblockCrossingID(pub usize);
+
+impl fmt::Display for MidblockCrossingID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MidblockCrossingID({0})", self.0)
+    }
+}
+
+// A place where pedestrians can cut straight across a quiet road, instead of detouring to a
+// crosswalk at an intersection. Each one only goes in one direction; there's a twin going back.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MidblockCrossing {
+    pub id: MidblockCrossingID,
+    pub parent: RoadID,
+    // Both are sidewalks
+    pub src: Position,
+    pub dst: Position,
+    // From src to dst
+    pub geom: PolyLine,
+    // Where the crossing cuts across each lane vehicles could be moving along.
+    pub crossed_lanes: Vec<Position>,
+}
@@ -80,7 +80,7 @@ impl Neighborhood {
             .collect()
     }
 
-    fn make_everywhere(map: &Map) -> Neighborhood {
+    pub fn make_everywhere(map: &Map) -> Neighborhood {
         let mut pts = map.get_bounds().get_corners();
         pts.push(pts[0]);
         Neighborhood {
@@ -89,6 +89,15 @@ impl Neighborhood {
             polygon: Polygon::new(&pts),
         }
     }
+
+    // The name of the neighborhood containing the point. If neighborhoods overlap, the first match
+    // in the list wins.
+    pub fn neighborhood_containing(pt: Pt2D, neighborhoods: &[Neighborhood]) -> Option<String> {
+        neighborhoods
+            .iter()
+            .find(|n| n.polygon.contains_pt(pt))
+            .map(|n| n.name.clone())
+    }
 }
 
 pub struct FullNeighborhoodInfo {
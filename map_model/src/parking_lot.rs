@@ -0,0 +1,25 @@
+use crate::Position;
+use geom::Polygon;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ParkingLotID(pub usize);
+
+impl fmt::Display for ParkingLotID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ParkingLotID({0})", self.0)
+    }
+}
+
+// Off-street parking, from OSM's amenity=parking. Cars enter and leave from a spot on the nearest
+// driving lane, and their drivers walk in and out from the sidewalk alongside.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ParkingLot {
+    pub id: ParkingLotID,
+    pub osm_id: i64,
+    pub polygon: Polygon,
+    pub capacity: usize,
+    pub driving_pos: Position,
+    pub sidewalk_pos: Position,
+}
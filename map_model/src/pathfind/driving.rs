@@ -71,8 +71,7 @@ impl VehiclePathfinder {
             let dst = self.get_node(t.id.dst, map);
             // First length arbitrarily wins.
             if self.graph.find_edge(src, dst).is_none() {
-                self.graph
-                    .add_edge(src, dst, src_l.length() + t.geom.length());
+                self.graph.add_edge(src, dst, src_l.length() + t.length);
             }
         }
     }
@@ -125,6 +124,10 @@ impl VehiclePathfinder {
                     _ => unreachable!(),
                 };
                 if let Some(turn) = map.get_turns_from_lane(from_lane).into_iter().find(|t| {
+                    // Don't stitch the path through a lane this graph can't use.
+                    if !self.lane_types.contains(&map.get_l(t.id.dst).lane_type) {
+                        return false;
+                    }
                     // Special case the last step
                     if nodes.is_empty() {
                         t.id.dst == req.end.lane()
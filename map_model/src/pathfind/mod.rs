@@ -4,7 +4,9 @@ mod walking;
 
 use self::driving::{Outcome, VehiclePathfinder};
 use self::walking::SidewalkPathfinder;
-use crate::{BusRouteID, BusStopID, LaneID, LaneType, Map, Position, Traversable, TurnID};
+use crate::{
+    BusRouteID, BusStopID, LaneID, LaneType, Map, MidblockCrossingID, Position, Traversable, TurnID,
+};
 use geom::{Distance, PolyLine};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::{BTreeSet, VecDeque};
@@ -17,6 +19,8 @@ pub enum PathStep {
     // Sidewalks only!
     ContraflowLane(LaneID),
     Turn(TurnID),
+    // Sidewalks only! The steps before and after only go part of the way along their lanes.
+    MidblockCrossing(MidblockCrossingID),
 }
 
 impl PathStep {
@@ -25,6 +29,7 @@ impl PathStep {
             PathStep::Lane(id) => Traversable::Lane(*id),
             PathStep::ContraflowLane(id) => Traversable::Lane(*id),
             PathStep::Turn(id) => Traversable::Turn(*id),
+            PathStep::MidblockCrossing(id) => Traversable::MidblockCrossing(*id),
         }
     }
 
@@ -79,6 +84,14 @@ impl PathStep {
                     pts.slice(start, pts.length())
                 }
             }
+            PathStep::MidblockCrossing(id) => {
+                let pts = &map.get_mc(*id).geom;
+                if let Some(d) = dist_ahead {
+                    pts.slice(start, start + d)
+                } else {
+                    pts.slice(start, pts.length())
+                }
+            }
         }
     }
 }
@@ -139,6 +152,10 @@ impl Path {
         self.steps[self.steps.len() - 1]
     }
 
+    pub fn get_end_dist(&self) -> Distance {
+        self.end_dist
+    }
+
     // dist_ahead is unlimited when None.
     pub fn trace(
         &self,
@@ -165,10 +182,12 @@ impl Path {
         }
 
         // Special case the first step.
-        if let Some((pts, dist)) = self.steps[0].slice(map, start_dist, dist_remaining) {
+        let step_dist =
+            limit_to_crossing(dist_remaining, self.dist_to_crossing(0, start_dist, map));
+        if let Some((pts, dist)) = self.steps[0].slice(map, start_dist, step_dist) {
             pts_so_far = Some(pts);
-            if dist_remaining.is_some() {
-                dist_remaining = Some(dist);
+            if let Some(d) = dist_remaining {
+                dist_remaining = Some(d - (step_dist.unwrap() - dist));
             }
         }
 
@@ -188,14 +207,19 @@ impl Path {
                     return Some(pts_so_far.unwrap());
                 }
             }
+            let start_dist_this_step = match (self.steps[i - 1], self.steps[i]) {
+                (PathStep::MidblockCrossing(id), _) => map.get_mc(id).dst.dist_along(),
+                // TODO Length of a PolyLine can slightly change when points are reversed! That
+                // seems bad.
+                (_, PathStep::ContraflowLane(l)) => {
+                    map.get_l(l).lane_center_pts.reversed().length()
+                }
+                _ => Distance::ZERO,
+            };
+
             // If we made it to the last step, maybe use the end_dist.
             if i == self.steps.len() - 1 {
-                let end_dist = match self.steps[i] {
-                    PathStep::ContraflowLane(l) => {
-                        map.get_l(l).lane_center_pts.reversed().length() - self.end_dist
-                    }
-                    _ => self.end_dist,
-                };
+                let end_dist = (self.end_dist - start_dist_this_step).abs();
                 if let Some(d) = dist_remaining {
                     if end_dist < d {
                         dist_remaining = Some(end_dist);
@@ -205,22 +229,19 @@ impl Path {
                 }
             }
 
-            let start_dist_this_step = match self.steps[i] {
-                // TODO Length of a PolyLine can slightly change when points are reversed! That
-                // seems bad.
-                PathStep::ContraflowLane(l) => map.get_l(l).lane_center_pts.reversed().length(),
-                _ => Distance::ZERO,
-            };
-            if let Some((new_pts, dist)) =
-                self.steps[i].slice(map, start_dist_this_step, dist_remaining)
+            let step_dist = limit_to_crossing(
+                dist_remaining,
+                self.dist_to_crossing(i, start_dist_this_step, map),
+            );
+            if let Some((new_pts, dist)) = self.steps[i].slice(map, start_dist_this_step, step_dist)
             {
                 if pts_so_far.is_some() {
-                    pts_so_far = Some(pts_so_far.unwrap().extend(new_pts));
+                    pts_so_far = Some(glue(pts_so_far.unwrap(), new_pts));
                 } else {
                     pts_so_far = Some(new_pts);
                 }
-                if dist_remaining.is_some() {
-                    dist_remaining = Some(dist);
+                if let Some(d) = dist_remaining {
+                    dist_remaining = Some(d - (step_dist.unwrap() - dist));
                 }
             }
         }
@@ -228,6 +249,17 @@ impl Path {
         Some(pts_so_far.unwrap())
     }
 
+    // If the step after this one is a mid-block crossing, how much farther this step goes before
+    // the crossing starts.
+    fn dist_to_crossing(&self, idx: usize, start: Distance, map: &Map) -> Option<Distance> {
+        match self.steps.get(idx + 1) {
+            Some(PathStep::MidblockCrossing(id)) => {
+                Some((map.get_mc(*id).src.dist_along() - start).abs())
+            }
+            _ => None,
+        }
+    }
+
     pub fn get_steps(&self) -> &VecDeque<PathStep> {
         &self.steps
     }
@@ -246,7 +278,13 @@ pub struct PathRequest {
     pub start: Position,
     pub end: Position,
     pub can_use_bike_lanes: bool,
+    // Buses can always use HOV lanes.
     pub can_use_bus_lanes: bool,
+    pub can_use_hov_lanes: bool,
+    // Only for pedestrians
+    pub can_use_midblock_crossings: bool,
+    // Only for vehicles. The lanes where the path starts and ends are never avoided.
+    pub avoid_lanes: BTreeSet<LaneID>,
 }
 
 impl fmt::Display for PathRequest {
@@ -264,26 +302,62 @@ impl fmt::Display for PathRequest {
             write!(f, ", bike lanes)")
         } else if self.can_use_bus_lanes {
             write!(f, ", bus lanes)")
+        } else if self.can_use_hov_lanes {
+            write!(f, ", HOV lanes)")
+        } else if self.can_use_midblock_crossings {
+            write!(f, ", mid-block crossings)")
         } else {
             write!(f, ")")
         }
     }
 }
 
+// Don't trace past the start of a mid-block crossing.
+fn limit_to_crossing(
+    dist_remaining: Option<Distance>,
+    dist_to_crossing: Option<Distance>,
+) -> Option<Distance> {
+    match (dist_remaining, dist_to_crossing) {
+        (Some(d1), Some(d2)) => Some(d1.min(d2)),
+        (None, Some(d)) => Some(d),
+        (d, None) => d,
+    }
+}
+
+// Mid-block crossings start and end in the middle of sidewalks, where slicing the sidewalk might
+// not land on exactly the same point.
+fn glue(pl: PolyLine, next: PolyLine) -> PolyLine {
+    let mut pts = next.points().clone();
+    if pl.last_pt().epsilon_eq(pts[0]) {
+        pts[0] = pl.last_pt();
+    }
+    pl.extend(PolyLine::new(pts))
+}
+
 fn validate(map: &Map, steps: &Vec<PathStep>) {
     if steps.is_empty() {
         panic!("Empty Path");
     }
     for pair in steps.windows(2) {
-        let from = match pair[0] {
-            PathStep::Lane(id) => map.get_l(id).last_pt(),
-            PathStep::ContraflowLane(id) => map.get_l(id).first_pt(),
-            PathStep::Turn(id) => map.get_t(id).geom.last_pt(),
+        let from = match (pair[0], pair[1]) {
+            (PathStep::Lane(id), PathStep::MidblockCrossing(c))
+            | (PathStep::ContraflowLane(id), PathStep::MidblockCrossing(c)) => {
+                Position::new(id, map.get_mc(c).src.dist_along()).pt(map)
+            }
+            (PathStep::Lane(id), _) => map.get_l(id).last_pt(),
+            (PathStep::ContraflowLane(id), _) => map.get_l(id).first_pt(),
+            (PathStep::Turn(id), _) => map.get_t(id).geom.last_pt(),
+            (PathStep::MidblockCrossing(id), _) => map.get_mc(id).geom.last_pt(),
         };
-        let to = match pair[1] {
-            PathStep::Lane(id) => map.get_l(id).first_pt(),
-            PathStep::ContraflowLane(id) => map.get_l(id).last_pt(),
-            PathStep::Turn(id) => map.get_t(id).geom.first_pt(),
+        let to = match (pair[0], pair[1]) {
+            (PathStep::MidblockCrossing(c), PathStep::Lane(id))
+            | (PathStep::MidblockCrossing(c), PathStep::ContraflowLane(id)) => {
+                Position::new(id, map.get_mc(c).dst.dist_along()).pt(map)
+            }
+            (_, PathStep::Lane(id)) => map.get_l(id).first_pt(),
+            (_, PathStep::ContraflowLane(id)) => map.get_l(id).last_pt(),
+            (_, PathStep::Turn(id)) => map.get_t(id).geom.first_pt(),
+            (_, PathStep::MidblockCrossing(id)) => map.get_mc(id).geom.first_pt(),
         };
         let len = from.dist_to(to);
         if len > Distance::ZERO {
@@ -302,7 +376,7 @@ fn validate(map: &Map, steps: &Vec<PathStep>) {
                         map.get_l(*l).dst_i,
                         map.get_l(*l).src_i
                     ),
-                    PathStep::Turn(_) => println!("  {:?}", s),
+                    PathStep::Turn(_) | PathStep::MidblockCrossing(_) => println!("  {:?}", s),
                 }
             }
             panic!(
@@ -318,8 +392,10 @@ pub struct Pathfinder {
     car_graph: VehiclePathfinder,
     bike_graph: VehiclePathfinder,
     bus_graph: VehiclePathfinder,
+    hov_graph: VehiclePathfinder,
     walking_graph: SidewalkPathfinder,
     walking_with_transit_graph: SidewalkPathfinder,
+    walking_with_crossings_graph: SidewalkPathfinder,
 }
 
 impl Pathfinder {
@@ -327,9 +403,14 @@ impl Pathfinder {
         Pathfinder {
             car_graph: VehiclePathfinder::new(map, vec![LaneType::Driving]),
             bike_graph: VehiclePathfinder::new(map, vec![LaneType::Driving, LaneType::Biking]),
-            bus_graph: VehiclePathfinder::new(map, vec![LaneType::Driving, LaneType::Bus]),
-            walking_graph: SidewalkPathfinder::new(map, false),
-            walking_with_transit_graph: SidewalkPathfinder::new(map, true),
+            bus_graph: VehiclePathfinder::new(
+                map,
+                vec![LaneType::Driving, LaneType::Bus, LaneType::HOV],
+            ),
+            hov_graph: VehiclePathfinder::new(map, vec![LaneType::Driving, LaneType::HOV]),
+            walking_graph: SidewalkPathfinder::new(map, false, false),
+            walking_with_transit_graph: SidewalkPathfinder::new(map, true, false),
+            walking_with_crossings_graph: SidewalkPathfinder::new(map, false, true),
         }
     }
 
@@ -345,14 +426,24 @@ impl Pathfinder {
         }
 
         let outcome = if map.get_l(req.start.lane()).is_sidewalk() {
-            match self.walking_graph.pathfind(&req, map) {
+            let graph = if req.can_use_midblock_crossings {
+                &self.walking_with_crossings_graph
+            } else {
+                &self.walking_graph
+            };
+            match graph.pathfind(&req, map) {
                 Some(path) => Outcome::Success(path),
                 None => Outcome::Failure,
             }
+        } else if !req.avoid_lanes.is_empty() {
+            // The graphs only know about directed roads, so go lane-by-lane instead.
+            Outcome::RetrySlow
         } else if req.can_use_bus_lanes {
             self.bus_graph.pathfind(&req, map)
         } else if req.can_use_bike_lanes {
             self.bike_graph.pathfind(&req, map)
+        } else if req.can_use_hov_lanes {
+            self.hov_graph.pathfind(&req, map)
         } else {
             self.car_graph.pathfind(&req, map)
         };
@@ -382,5 +473,11 @@ impl Pathfinder {
         self.car_graph.apply_edits(delete_turns, add_turns, map);
         self.bike_graph.apply_edits(delete_turns, add_turns, map);
         self.bus_graph.apply_edits(delete_turns, add_turns, map);
+        self.hov_graph.apply_edits(delete_turns, add_turns, map);
+    }
+
+    // Call after the map's mid-block crossings have been rebuilt.
+    pub fn recalculate_midblock_crossings(&mut self, map: &Map) {
+        self.walking_with_crossings_graph = SidewalkPathfinder::new(map, false, true);
     }
 }
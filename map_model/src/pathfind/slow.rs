@@ -1,7 +1,7 @@
-use crate::{LaneType, Map, Path, PathRequest, PathStep, Position, Traversable};
+use crate::{LaneID, LaneType, Map, Path, PathRequest, PathStep, Position, Traversable};
 use geom::{Distance, Pt2D};
 use ordered_float::NotNan;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BTreeSet, BinaryHeap, HashMap};
 
 // Only for vehicle paths, no walking support.
 pub fn shortest_distance(map: &Map, req: PathRequest) -> Option<Path> {
@@ -12,6 +12,9 @@ pub fn shortest_distance(map: &Map, req: PathRequest) -> Option<Path> {
         goal_pt,
         can_use_bike_lanes: req.can_use_bike_lanes,
         can_use_bus_lanes: req.can_use_bus_lanes,
+        can_use_hov_lanes: req.can_use_bus_lanes || req.can_use_hov_lanes,
+        avoid_lanes: req.avoid_lanes,
+        goal_lane: req.end.lane(),
     }
     .pathfind(map, req.start, req.end)?;
     assert_eq!(
@@ -29,6 +32,9 @@ struct SlowPathfinder {
     goal_pt: Pt2D,
     can_use_bike_lanes: bool,
     can_use_bus_lanes: bool,
+    can_use_hov_lanes: bool,
+    avoid_lanes: BTreeSet<LaneID>,
+    goal_lane: LaneID,
 }
 
 impl SlowPathfinder {
@@ -46,6 +52,10 @@ impl SlowPathfinder {
                         // Skip
                     } else if !self.can_use_bus_lanes && next.lane_type == LaneType::Bus {
                         // Skip
+                    } else if !self.can_use_hov_lanes && next.lane_type == LaneType::HOV {
+                        // Skip
+                    } else if self.avoid_lanes.contains(&next.id) && next.id != self.goal_lane {
+                        // Skip
                     } else {
                         results.push(PathStep::Turn(turn.id));
                     }
@@ -54,7 +64,7 @@ impl SlowPathfinder {
             PathStep::Turn(t) => {
                 results.push(PathStep::Lane(t.dst));
             }
-            PathStep::ContraflowLane(_) => unreachable!(),
+            PathStep::ContraflowLane(_) | PathStep::MidblockCrossing(_) => unreachable!(),
         };
         results
     }
@@ -113,8 +123,8 @@ fn dist_to_pri_queue(dist: Distance) -> NotNan<f64> {
 fn cost(step: &PathStep, map: &Map) -> Distance {
     match step {
         PathStep::Lane(l) => map.get_l(*l).length(),
-        PathStep::Turn(t) => map.get_t(*t).geom.length(),
-        PathStep::ContraflowLane(_) => unreachable!(),
+        PathStep::Turn(t) => map.get_t(*t).length,
+        PathStep::ContraflowLane(_) | PathStep::MidblockCrossing(_) => unreachable!(),
     }
 }
 
@@ -122,7 +132,7 @@ fn heuristic(step: &PathStep, goal_pt: Pt2D, map: &Map) -> Distance {
     let pt = match step {
         PathStep::Lane(l) => map.get_l(*l).last_pt(),
         PathStep::Turn(t) => map.get_t(*t).geom.last_pt(),
-        PathStep::ContraflowLane(_) => unreachable!(),
+        PathStep::ContraflowLane(_) | PathStep::MidblockCrossing(_) => unreachable!(),
     };
     pt.dist_to(goal_pt)
 }
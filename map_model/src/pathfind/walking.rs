@@ -8,6 +8,10 @@ use petgraph::graph::{Graph, NodeIndex};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+// Looking for a gap in traffic takes time. Edge costs are distances, so express that delay as extra
+// walking.
+const MIDBLOCK_CROSSING_PENALTY: Distance = Distance::const_meters(15.0);
+
 // TODO Make the graph smaller by considering RoadID, or even (directed?) bundles of roads based on
 // OSM way.
 #[derive(Serialize, Deserialize, Debug)]
@@ -23,11 +27,12 @@ pub struct SidewalkPathfinder {
 #[derive(Serialize, Deserialize, Debug)]
 enum Edge {
     Cross(Distance),
+    CrossMidblock(Distance),
     RideBus(BusStopID, BusStopID, BusRouteID),
 }
 
 impl SidewalkPathfinder {
-    pub fn new(map: &Map, use_transit: bool) -> SidewalkPathfinder {
+    pub fn new(map: &Map, use_transit: bool, use_midblock_crossings: bool) -> SidewalkPathfinder {
         let mut g = SidewalkPathfinder {
             graph: Graph::new(),
             nodes: BTreeMap::new(),
@@ -55,7 +60,22 @@ impl SidewalkPathfinder {
             // First length arbitrarily wins.
             if !g.graph.contains_edge(src, dst) {
                 g.graph
-                    .add_edge(src, dst, Edge::Cross(src_l.length() + t.geom.length()));
+                    .add_edge(src, dst, Edge::Cross(src_l.length() + t.length));
+            }
+        }
+
+        // Cutting across the road beats walking to the end of the block and back, so replace any
+        // crosswalk edge between the two sides. The exact crossing is picked later, based on
+        // where the pedestrian is.
+        if use_midblock_crossings {
+            for c in map.all_midblock_crossings() {
+                let src = g.get_node(c.src.lane(), map);
+                let dst = g.get_node(c.dst.lane(), map);
+                g.graph.update_edge(
+                    src,
+                    dst,
+                    Edge::CrossMidblock(c.geom.length() + MIDBLOCK_CROSSING_PENALTY),
+                );
             }
         }
 
@@ -127,7 +147,7 @@ impl SidewalkPathfinder {
             start_node,
             |n| n == end_node,
             |e| match e.weight() {
-                Edge::Cross(dist) => *dist,
+                Edge::Cross(dist) | Edge::CrossMidblock(dist) => *dist,
                 // Free for now
                 Edge::RideBus(_, _, _) => Distance::ZERO,
             },
@@ -151,6 +171,32 @@ impl SidewalkPathfinder {
             let lane1 = map.get_l(self.get_sidewalk(self.graph[pair[0]], map));
             let l2 = self.get_sidewalk(self.graph[pair[1]], map);
 
+            if let Edge::CrossMidblock(_) =
+                self.graph[self.graph.find_edge(pair[0], pair[1]).unwrap()]
+            {
+                // Where along lane1 are we right now?
+                let dist = match current_i {
+                    Some(i) if i == lane1.src_i => Distance::ZERO,
+                    Some(_) => lane1.length(),
+                    None => req.start.dist_along(),
+                };
+                let crossing = lane1
+                    .midblock_crossings
+                    .iter()
+                    .map(|id| map.get_mc(*id))
+                    .filter(|c| c.dst.lane() == l2)
+                    .min_by_key(|c| (c.src.dist_along() - dist).abs())
+                    .unwrap();
+                if dist <= crossing.src.dist_along() {
+                    steps.push(PathStep::Lane(lane1.id));
+                } else {
+                    steps.push(PathStep::ContraflowLane(lane1.id));
+                }
+                steps.push(PathStep::MidblockCrossing(crossing.id));
+                current_i = None;
+                continue;
+            }
+
             let fwd_t = map.get_turn_between(lane1.id, l2, lane1.dst_i);
             let back_t = map.get_turn_between(lane1.id, l2, lane1.src_i);
             // TODO If both are available, we sort of need to lookahead to pick the better one.
@@ -172,7 +218,13 @@ impl SidewalkPathfinder {
 
         // Don't end a path in a turn; sim layer breaks.
         let last_lane = map.get_l(self.get_sidewalk(self.graph[*raw_nodes.last().unwrap()], map));
-        if Some(last_lane.src_i) == current_i {
+        if let Some(PathStep::MidblockCrossing(id)) = steps.last() {
+            if map.get_mc(*id).dst.dist_along() <= req.end.dist_along() {
+                steps.push(PathStep::Lane(last_lane.id));
+            } else {
+                steps.push(PathStep::ContraflowLane(last_lane.id));
+            }
+        } else if Some(last_lane.src_i) == current_i {
             steps.push(PathStep::Lane(last_lane.id));
         } else if Some(last_lane.dst_i) == current_i {
             steps.push(PathStep::ContraflowLane(last_lane.id));
@@ -199,7 +251,7 @@ impl SidewalkPathfinder {
             start_node,
             |n| n == end_node,
             |e| match e.weight() {
-                Edge::Cross(dist) => *dist,
+                Edge::Cross(dist) | Edge::CrossMidblock(dist) => *dist,
                 // Free for now
                 Edge::RideBus(_, _, _) => Distance::ZERO,
             },
@@ -1,7 +1,7 @@
 use crate::make::get_lane_types;
 pub use crate::make::{Hint, Hints, InitialMap};
 use crate::{AreaType, IntersectionType, RoadSpec};
-use geom::{GPSBounds, LonLat};
+use geom::{Duration, GPSBounds, LonLat, Speed};
 use gtfs::Route;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -32,6 +32,8 @@ pub struct Map {
     pub buildings: Vec<Building>,
     pub bus_routes: Vec<Route>,
     pub areas: Vec<Area>,
+    pub bike_racks: Vec<BikeRack>,
+    pub parking_lots: Vec<ParkingLot>,
 
     pub boundary_polygon: Vec<LonLat>,
     pub gps_bounds: GPSBounds,
@@ -46,6 +48,8 @@ impl Map {
             buildings: Vec::new(),
             bus_routes: Vec::new(),
             areas: Vec::new(),
+            bike_racks: Vec::new(),
+            parking_lots: Vec::new(),
             boundary_polygon: Vec::new(),
             gps_bounds: GPSBounds::new(),
             coordinates_in_world_space: false,
@@ -123,10 +127,19 @@ pub struct Road {
     pub i1: StableIntersectionID,
     pub i2: StableIntersectionID,
     pub points: Vec<LonLat>,
+    // The OSM node behind each point. Empty for roads that didn't come from OSM, and not kept in
+    // sync once clipping trims the points.
+    pub osm_node_ids: Vec<i64>,
     pub osm_tags: BTreeMap<String, String>,
     pub osm_way_id: i64,
     pub parking_lane_fwd: bool,
     pub parking_lane_back: bool,
+    // When parking is allowed, as times since midnight. None means any time. A window may wrap
+    // past midnight.
+    pub parking_hours_fwd: Option<Vec<(Duration, Duration)>>,
+    pub parking_hours_back: Option<Vec<(Duration, Duration)>>,
+    // From OSM's maxspeed, if it was present and understood
+    pub speed_limit: Option<Speed>,
 }
 
 impl Road {
@@ -145,6 +158,48 @@ impl Road {
             pt2: *self.points.last().unwrap(),
         }
     }
+
+    pub fn get_zorder(&self) -> isize {
+        osm_zorder(&self.osm_tags)
+    }
+}
+
+// Ground level is 0. Use the explicit layer if there is one; otherwise OSM implies bridges are one
+// level up and tunnels one level down.
+pub fn osm_zorder(osm_tags: &BTreeMap<String, String>) -> isize {
+    if let Some(layer) = osm_tags.get("layer").and_then(|l| l.parse::<isize>().ok()) {
+        return layer;
+    }
+    let is_set = |key: &str| osm_tags.get(key).map(|v| v != "no").unwrap_or(false);
+    if is_set("bridge") {
+        1
+    } else if is_set("tunnel") {
+        -1
+    } else {
+        0
+    }
+}
+
+// Shapes like blockfaces are matched to nearby roads in 2D, but a bridge might pass right over the
+// street the shape really describes. Given the candidates within range, closest first, with their
+// zorder, prefer the closest one on the shape's own layer if it has one, and ground level
+// otherwise. Also returns true if the candidates were on different levels, so the match is
+// ambiguous.
+pub fn pick_layered_match<K: Clone>(
+    candidates: &[(K, isize)],
+    shape_layer: Option<isize>,
+) -> Option<(K, bool)> {
+    let (nearest, nearest_zorder) = candidates.first()?;
+    if candidates.iter().all(|(_, z)| z == nearest_zorder) {
+        return Some((nearest.clone(), false));
+    }
+    let want = shape_layer.unwrap_or(0);
+    let pick = candidates
+        .iter()
+        .find(|(_, z)| *z == want)
+        .map(|(k, _)| k)
+        .unwrap_or(nearest);
+    Some((pick.clone(), true))
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -180,6 +235,22 @@ pub struct Area {
     pub osm_id: i64,
 }
 
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct BikeRack {
+    pub point: LonLat,
+    pub capacity: usize,
+    pub osm_node_id: i64,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ParkingLot {
+    // Just one point if the lot was tagged as a node
+    pub points: Vec<LonLat>,
+    // None if OSM doesn't say; then it's estimated from the area.
+    pub capacity: Option<usize>,
+    pub osm_id: i64,
+}
+
 // A way to refer to roads across many maps.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct OriginalRoad {
@@ -77,6 +77,8 @@ pub struct Road {
     // Need to retain for map editing.
     pub parking_lane_fwd: bool,
     pub parking_lane_back: bool,
+
+    pub speed_limit: Speed,
 }
 
 impl Road {
@@ -181,31 +183,30 @@ impl Road {
     }
 
     pub fn get_speed_limit(&self) -> Speed {
-        // TODO Should probably cache this
-        if let Some(limit) = self.osm_tags.get("maxspeed") {
-            // TODO handle other units
-            if limit.ends_with(" mph") {
-                if let Ok(mph) = limit[0..limit.len() - 4].parse::<f64>() {
-                    return Speed::miles_per_hour(mph);
-                }
-            }
-        }
+        self.speed_limit
+    }
 
-        if self.osm_tags.get("highway") == Some(&"primary".to_string())
-            || self.osm_tags.get("highway") == Some(&"secondary".to_string())
-        {
-            return Speed::miles_per_hour(40.0);
+    // When OSM doesn't say (or says something unparseable), guess from the type of road.
+    pub(crate) fn default_speed_limit(&self) -> Speed {
+        let rank = self.get_rank();
+        if rank >= 16 {
+            // Motorways and trunks
+            Speed::miles_per_hour(60.0)
+        } else if rank >= 12 {
+            // Primary and secondary
+            Speed::miles_per_hour(40.0)
+        } else {
+            Speed::miles_per_hour(20.0)
         }
-        Speed::miles_per_hour(20.0)
+    }
+
+    pub fn is_roundabout(&self) -> bool {
+        self.osm_tags.get("junction") == Some(&"roundabout".to_string())
     }
 
     pub fn get_zorder(&self) -> isize {
         // TODO Should probably cache this
-        if let Some(layer) = self.osm_tags.get("layer") {
-            layer.parse::<isize>().unwrap()
-        } else {
-            0
-        }
+        raw_data::osm_zorder(&self.osm_tags)
     }
 
     pub fn incoming_lanes(&self, i: IntersectionID) -> &Vec<(LaneID, LaneType)> {
@@ -343,12 +344,26 @@ impl Road {
         }
     }
 
+    // Roads without a name in OSM get one made up from their OSM way, like "service road #123".
+    // That's stable across map rebuilds, and keeps unnamed roads from all looking alike.
     pub fn get_name(&self) -> String {
+        if let Some(name) = self.get_osm_name() {
+            return name;
+        }
+        let kind = match self.osm_tags.get("highway").map(|hwy| hwy.as_str()) {
+            Some("service") => "service road".to_string(),
+            Some(hwy) => hwy.replace('_', " "),
+            None => "road".to_string(),
+        };
+        format!("{} #{}", kind, self.osm_way_id)
+    }
+
+    pub fn get_osm_name(&self) -> Option<String> {
         if let Some(name) = self.osm_tags.get("name") {
-            return name.to_string();
+            return Some(name.to_string());
         }
         if let Some(name) = self.osm_tags.get("ref") {
-            return name.to_string();
+            return Some(name.to_string());
         }
         if self
             .osm_tags
@@ -357,17 +372,17 @@ impl Road {
             .unwrap_or(false)
         {
             if let Some(name) = self.osm_tags.get("destination:street") {
-                return format!("Exit for {}", name);
+                return Some(format!("Exit for {}", name));
             }
             if let Some(name) = self.osm_tags.get("destination:ref") {
-                return format!("Exit for {}", name);
+                return Some(format!("Exit for {}", name));
             }
             if let Some(name) = self.osm_tags.get("destination") {
-                return format!("Exit for {}", name);
+                return Some(format!("Exit for {}", name));
             }
             // Sometimes 'directions' is filled out, but incorrectly...
         }
-        "???".to_string()
+        None
     }
 
     pub fn get_rank(&self) -> usize {
@@ -391,6 +406,7 @@ impl Road {
                 "residential" => 5,
 
                 "footway" => 1,
+                "steps" => 1,
 
                 "unclassified" => 0,
                 "road" => 0,
@@ -207,6 +207,10 @@ impl ControlStopSign {
 }
 
 fn smart_assignment(map: &Map, id: IntersectionID) -> Warn<ControlStopSign> {
+    if map.get_i(id).roads.iter().any(|r| map.get_r(*r).is_roundabout()) {
+        return for_roundabout(map, id);
+    }
+
     // Count the number of roads with incoming lanes to determine degenerate/deadends. Might have
     // one incoming road to two outgoing. Don't count sidewalks as incoming; crosswalks always
     // yield anyway.
@@ -267,6 +271,39 @@ fn smart_assignment(map: &Map, id: IntersectionID) -> Warn<ControlStopSign> {
     Warn::ok(ss)
 }
 
+// Where an approach meets a circulating roundabout, entering traffic yields to traffic already
+// going around.
+fn for_roundabout(map: &Map, id: IntersectionID) -> Warn<ControlStopSign> {
+    let mut ss = ControlStopSign {
+        id,
+        turns: BTreeMap::new(),
+        roads: BTreeMap::new(),
+    };
+    for t in &map.get_i(id).turns {
+        let turn = map.get_t(*t);
+        let priority = match turn.turn_type {
+            TurnType::SharedSidewalkCorner => TurnPriority::Priority,
+            TurnType::Crosswalk => TurnPriority::Stop,
+            _ => {
+                if map.get_parent(t.src).is_roundabout() {
+                    TurnPriority::Priority
+                } else {
+                    TurnPriority::Yield
+                }
+            }
+        };
+        ss.turns.insert(*t, priority);
+    }
+
+    if let Err(err) = ss.validate(map) {
+        return Warn::warn(
+            all_way_stop(map, id),
+            format!("Giving up on for_roundabout({}): {}", id, err),
+        );
+    }
+    Warn::ok(ss)
+}
+
 fn all_way_stop(map: &Map, id: IntersectionID) -> ControlStopSign {
     let mut ss = ControlStopSign {
         id,
@@ -5,11 +5,45 @@ use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 
 const CYCLE_DURATION: Duration = Duration::const_seconds(30.0);
+const MIN_GREEN: Duration = Duration::const_seconds(10.0);
+const MAX_GREEN: Duration = Duration::const_seconds(60.0);
+const DEFAULT_GAP_OUT: Duration = Duration::const_seconds(3.0);
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ControlTrafficSignal {
     pub id: IntersectionID,
     pub cycles: Vec<Cycle>,
+    // Edits saved before signals could be actuated don't have this.
+    #[serde(default)]
+    pub control: SignalControl,
+    // After each cycle, every movement is red for this long, to clear the intersection. Zero means
+    // cycles switch instantly, which is also how edits saved before this existed behave.
+    #[serde(default = "default_all_red")]
+    pub all_red: Duration,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SignalControl {
+    // Cycles take turns in order, each lasting the same amount of time, regardless of demand.
+    FixedTime,
+    // Each cycle lasts between its min_green and max_green. In between, it ends once nobody new
+    // has shown up for one of its turns in gap_out. Cycles that nobody is waiting for get skipped.
+    // The simulation decides when to change cycles.
+    Actuated { gap_out: Duration },
+}
+
+impl SignalControl {
+    pub fn default_actuated() -> SignalControl {
+        SignalControl::Actuated {
+            gap_out: DEFAULT_GAP_OUT,
+        }
+    }
+}
+
+impl Default for SignalControl {
+    fn default() -> SignalControl {
+        SignalControl::FixedTime
+    }
 }
 
 impl ControlTrafficSignal {
@@ -48,12 +82,20 @@ impl ControlTrafficSignal {
         results
     }
 
-    pub fn current_cycle_and_remaining_time(&self, time: Duration) -> (&Cycle, Duration) {
-        let cycle_idx = (time / CYCLE_DURATION).floor() as usize;
+    // Only meaningful for SignalControl::FixedTime; the simulation tracks actuated signals. None
+    // means the all-red clearance after a cycle, when nothing may start, and the time is until
+    // the next cycle begins.
+    pub fn current_cycle_and_remaining_time(&self, time: Duration) -> (Option<&Cycle>, Duration) {
+        let period = CYCLE_DURATION + self.all_red;
+        let cycle_idx = (time / period).floor() as usize;
         let cycle = &self.cycles[cycle_idx % self.cycles.len()];
-        let next_cycle_time = CYCLE_DURATION * (cycle_idx + 1) as f64;
-        let remaining_cycle_time = next_cycle_time - time;
-        (cycle, remaining_cycle_time)
+        let green_ends = period * (cycle_idx as f64) + CYCLE_DURATION;
+        if self.all_red == Duration::ZERO || time < green_ends {
+            (Some(cycle), green_ends - time)
+        } else {
+            let next_cycle_time = period * (cycle_idx + 1) as f64;
+            (None, next_cycle_time - time)
+        }
     }
 
     fn validate(&self, map: &Map) -> Result<(), Error> {
@@ -139,6 +181,8 @@ impl ControlTrafficSignal {
         let ts = ControlTrafficSignal {
             id: intersection,
             cycles,
+            control: SignalControl::FixedTime,
+            all_red: Duration::ZERO,
         };
         // This must succeed
         ts.validate(map).unwrap();
@@ -167,7 +211,12 @@ impl ControlTrafficSignal {
 
         let cycles = make_cycles(map, i, phases);
 
-        let ts = ControlTrafficSignal { id: i, cycles };
+        let ts = ControlTrafficSignal {
+            id: i,
+            cycles,
+            control: SignalControl::FixedTime,
+            all_red: Duration::ZERO,
+        };
         if ts.validate(map).is_ok() {
             Some(ts)
         } else {
@@ -220,7 +269,12 @@ impl ControlTrafficSignal {
             ],
         );
 
-        let ts = ControlTrafficSignal { id: i, cycles };
+        let ts = ControlTrafficSignal {
+            id: i,
+            cycles,
+            control: SignalControl::FixedTime,
+            all_red: Duration::ZERO,
+        };
         if ts.validate(map).is_ok() {
             Some(ts)
         } else {
@@ -266,7 +320,12 @@ impl ControlTrafficSignal {
             ],
         );
 
-        let ts = ControlTrafficSignal { id: i, cycles };
+        let ts = ControlTrafficSignal {
+            id: i,
+            cycles,
+            control: SignalControl::FixedTime,
+            all_red: Duration::ZERO,
+        };
         if ts.validate(map).is_ok() {
             Some(ts)
         } else {
@@ -311,7 +370,12 @@ impl ControlTrafficSignal {
             ],
         );
 
-        let ts = ControlTrafficSignal { id: i, cycles };
+        let ts = ControlTrafficSignal {
+            id: i,
+            cycles,
+            control: SignalControl::FixedTime,
+            all_red: Duration::ZERO,
+        };
         if ts.validate(map).is_ok() {
             Some(ts)
         } else {
@@ -367,7 +431,12 @@ impl ControlTrafficSignal {
             ],
         );
 
-        let ts = ControlTrafficSignal { id: i, cycles };
+        let ts = ControlTrafficSignal {
+            id: i,
+            cycles,
+            control: SignalControl::FixedTime,
+            all_red: Duration::ZERO,
+        };
         if ts.validate(map).is_ok() {
             Some(ts)
         } else {
@@ -383,6 +452,11 @@ pub struct Cycle {
     pub priority_turns: BTreeSet<TurnID>,
     pub yield_turns: BTreeSet<TurnID>,
     pub duration: Duration,
+    // Only used by SignalControl::Actuated
+    #[serde(default = "default_min_green")]
+    pub min_green: Duration,
+    #[serde(default = "default_max_green")]
+    pub max_green: Duration,
 }
 
 impl Cycle {
@@ -393,6 +467,8 @@ impl Cycle {
             priority_turns: BTreeSet::new(),
             yield_turns: BTreeSet::new(),
             duration: CYCLE_DURATION,
+            min_green: MIN_GREEN,
+            max_green: MAX_GREEN,
         }
     }
 
@@ -470,7 +546,13 @@ fn make_cycles(
                     continue;
                 }
 
-                if !roads.contains(&map.get_l(turn.id.src).parent) || turn_type != turn.turn_type {
+                // U-turns go along with the left turns. Most are banned regardless.
+                let actual_type = if turn.turn_type == TurnType::UTurn {
+                    TurnType::Left
+                } else {
+                    turn.turn_type
+                };
+                if !roads.contains(&map.get_l(turn.id.src).parent) || turn_type != actual_type {
                     continue;
                 }
 
@@ -490,3 +572,15 @@ fn make_cycles(
 
     cycles
 }
+
+fn default_min_green() -> Duration {
+    MIN_GREEN
+}
+
+fn default_max_green() -> Duration {
+    MAX_GREEN
+}
+
+fn default_all_red() -> Duration {
+    Duration::ZERO
+}
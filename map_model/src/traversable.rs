@@ -1,4 +1,7 @@
-use crate::{BuildingID, LaneID, LaneType, Map, TurnID};
+use crate::{
+    BikeRackID, BuildingID, BuildingType, IntersectionID, LaneID, LaneType, Map,
+    MidblockCrossingID, TurnID,
+};
 use geom::{Angle, Distance, PolyLine, Pt2D, Speed};
 use serde_derive::{Deserialize, Serialize};
 use std::fmt;
@@ -56,12 +59,58 @@ impl Position {
         map.get_b(b).front_path.sidewalk
     }
 
-    pub fn bldg_via_driving(b: BuildingID, map: &Map) -> Option<Position> {
+    pub fn bldg_via_driving(b: BuildingID, map: &Map) -> Result<Position, SpawnError> {
         let bldg = map.get_b(b);
         let driving_lane = map
             .find_closest_lane(bldg.sidewalk(), vec![LaneType::Driving])
-            .ok()?;
-        Some(bldg.front_path.sidewalk.equiv_pos(driving_lane, map))
+            .map_err(|_| SpawnError::NoDrivingAccess(b))?;
+        Ok(bldg.front_path.sidewalk.equiv_pos(driving_lane, map))
+    }
+}
+
+// Why an agent can't start or end somewhere.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpawnError {
+    NoSidewalkAtBorder(IntersectionID),
+    NoLanesAtBorder(IntersectionID, Vec<LaneType>),
+    NoDrivingAccess(BuildingID),
+    NoBikeAccess(BikeRackID),
+    LaneTooShort(LaneID),
+    NoPath(Position, Position),
+    // The neighborhood name
+    NoBuildingsOfType(String, BuildingType),
+}
+
+impl SpawnError {
+    // For grouping similar errors together in summaries.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SpawnError::NoSidewalkAtBorder(_) => "border without a sidewalk",
+            SpawnError::NoLanesAtBorder(_, _) => "border without lanes for the vehicle",
+            SpawnError::NoDrivingAccess(_) => "building without a nearby driving lane",
+            SpawnError::NoBikeAccess(_) => "bike rack without a nearby biking lane",
+            SpawnError::LaneTooShort(_) => "lane too short to spawn a vehicle",
+            SpawnError::NoPath(_, _) => "no path",
+            SpawnError::NoBuildingsOfType(_, _) => "neighborhood without buildings of the type",
+        }
+    }
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpawnError::NoSidewalkAtBorder(i) => write!(f, "{} has no sidewalk", i),
+            SpawnError::NoLanesAtBorder(i, lane_types) => {
+                write!(f, "{} has no {:?} lanes", i, lane_types)
+            }
+            SpawnError::NoDrivingAccess(b) => write!(f, "{} has no driving lane nearby", b),
+            SpawnError::NoBikeAccess(r) => write!(f, "{} has no biking lane nearby", r),
+            SpawnError::LaneTooShort(l) => write!(f, "{} is too short to spawn a vehicle", l),
+            SpawnError::NoPath(start, end) => write!(f, "no path from {} to {}", start, end),
+            SpawnError::NoBuildingsOfType(n, bldg_type) => {
+                write!(f, "{} has no {} buildings", n, bldg_type)
+            }
+        }
     }
 }
 
@@ -70,6 +119,7 @@ impl Position {
 pub enum Traversable {
     Lane(LaneID),
     Turn(TurnID),
+    MidblockCrossing(MidblockCrossingID),
 }
 
 impl fmt::Display for Traversable {
@@ -81,6 +131,9 @@ impl fmt::Display for Traversable {
                 "Traversable::Turn({}, {}, {})",
                 id.src, id.dst, id.parent
             ),
+            Traversable::MidblockCrossing(id) => {
+                write!(f, "Traversable::MidblockCrossing({})", id.0)
+            }
         }
     }
 }
@@ -89,27 +142,27 @@ impl Traversable {
     pub fn as_lane(&self) -> LaneID {
         match *self {
             Traversable::Lane(id) => id,
-            Traversable::Turn(_) => panic!("not a lane"),
+            Traversable::Turn(_) | Traversable::MidblockCrossing(_) => panic!("not a lane"),
         }
     }
 
     pub fn as_turn(&self) -> TurnID {
         match *self {
             Traversable::Turn(id) => id,
-            Traversable::Lane(_) => panic!("not a turn"),
+            Traversable::Lane(_) | Traversable::MidblockCrossing(_) => panic!("not a turn"),
         }
     }
 
     pub fn maybe_turn(&self) -> Option<TurnID> {
         match *self {
             Traversable::Turn(id) => Some(id),
-            Traversable::Lane(_) => None,
+            Traversable::Lane(_) | Traversable::MidblockCrossing(_) => None,
         }
     }
 
     pub fn maybe_lane(&self) -> Option<LaneID> {
         match *self {
-            Traversable::Turn(_) => None,
+            Traversable::Turn(_) | Traversable::MidblockCrossing(_) => None,
             Traversable::Lane(id) => Some(id),
         }
     }
@@ -118,7 +171,8 @@ impl Traversable {
     pub fn length(&self, map: &Map) -> Distance {
         match *self {
             Traversable::Lane(id) => map.get_l(id).length(),
-            Traversable::Turn(id) => map.get_t(id).geom.length(),
+            Traversable::Turn(id) => map.get_t(id).length,
+            Traversable::MidblockCrossing(id) => map.get_mc(id).geom.length(),
         }
     }
 
@@ -126,6 +180,7 @@ impl Traversable {
         match *self {
             Traversable::Lane(id) => map.get_l(id).dist_along(dist),
             Traversable::Turn(id) => map.get_t(id).geom.dist_along(dist),
+            Traversable::MidblockCrossing(id) => map.get_mc(id).geom.dist_along(dist),
         }
     }
 
@@ -133,6 +188,7 @@ impl Traversable {
         match *self {
             Traversable::Lane(id) => map.get_l(id).lane_center_pts.slice(start, end),
             Traversable::Turn(id) => map.get_t(id).geom.slice(start, end),
+            Traversable::MidblockCrossing(id) => map.get_mc(id).geom.slice(start, end),
         }
     }
 
@@ -140,6 +196,7 @@ impl Traversable {
         match *self {
             Traversable::Lane(id) => map.get_l(id).lane_center_pts.exact_slice(start, end),
             Traversable::Turn(id) => map.get_t(id).geom.exact_slice(start, end),
+            Traversable::MidblockCrossing(id) => map.get_mc(id).geom.exact_slice(start, end),
         }
     }
 
@@ -147,6 +204,7 @@ impl Traversable {
         match *self {
             Traversable::Lane(id) => map.get_parent(id).get_speed_limit(),
             Traversable::Turn(id) => map.get_parent(id.dst).get_speed_limit(),
+            Traversable::MidblockCrossing(id) => map.get_r(map.get_mc(id).parent).get_speed_limit(),
         }
     }
 
@@ -154,6 +212,7 @@ impl Traversable {
         match *self {
             Traversable::Lane(id) => map.get_parent(id).get_zorder(),
             Traversable::Turn(id) => map.get_i(id.parent).get_zorder(map),
+            Traversable::MidblockCrossing(id) => map.get_r(map.get_mc(id).parent).get_zorder(),
         }
     }
 }
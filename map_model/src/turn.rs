@@ -1,6 +1,6 @@
 use crate::{IntersectionID, LaneID};
 use abstutil;
-use geom::{Angle, PolyLine};
+use geom::{Angle, Distance, PolyLine};
 use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 
@@ -31,6 +31,9 @@ pub enum TurnType {
     LaneChangeRight,
     Right,
     Left,
+    // Leaving a road and heading right back the way it came. Banned by Map::is_turn_allowed except
+    // at dead-ends and where MapEdits allow them.
+    UTurn,
 }
 
 impl TurnType {
@@ -70,6 +73,8 @@ pub struct Turn {
     // TODO Some turns might not actually have geometry. Currently encoded by two equal points.
     // Represent more directly?
     pub geom: PolyLine,
+    // Cached geom.length(), since pathfinding and the sim ask for it constantly
+    pub length: Distance,
 
     // Just for convenient debugging lookup.
     pub lookup_idx: usize,
@@ -0,0 +1,76 @@
+use crate::{LaneType, Map, TurnType};
+use abstutil;
+use serde_derive::{Deserialize, Serialize};
+use std::io::Error;
+
+// Lanes and the turns between them, in the node-link layout that networkx's node_link_graph and
+// similar tools read directly.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TurnGraph {
+    pub directed: bool,
+    pub nodes: Vec<TurnGraphNode>,
+    pub links: Vec<TurnGraphLink>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TurnGraphNode {
+    // LaneID
+    pub id: usize,
+    pub road: usize,
+    pub lane_type: LaneType,
+    pub length_meters: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TurnGraphLink {
+    // LaneIDs
+    pub source: usize,
+    pub target: usize,
+    pub intersection: usize,
+    pub turn_type: TurnType,
+    pub length_meters: f64,
+}
+
+impl Map {
+    // Every lane is a node. Only turns that pathfinding could use become links; turns banned
+    // everywhere by the intersection's stop sign or traffic signal are left out.
+    pub fn turn_graph(&self) -> TurnGraph {
+        TurnGraph {
+            directed: true,
+            nodes: self
+                .all_lanes()
+                .iter()
+                .map(|l| TurnGraphNode {
+                    id: l.id.0,
+                    road: l.parent.0,
+                    lane_type: l.lane_type,
+                    length_meters: l.length().inner_meters(),
+                })
+                .collect(),
+            links: self
+                .all_turns()
+                .values()
+                .filter(|t| self.is_turn_allowed(t.id))
+                .map(|t| TurnGraphLink {
+                    source: t.id.src.0,
+                    target: t.id.dst.0,
+                    intersection: t.id.parent.0,
+                    turn_type: t.turn_type,
+                    length_meters: t.length.inner_meters(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn export_turn_graph(&self, path: &str) -> Result<(), Error> {
+        let graph = self.turn_graph();
+        abstutil::write_json(path, &graph)?;
+        println!(
+            "Exported {} lanes and {} turns to {}",
+            graph.nodes.len(),
+            graph.links.len(),
+            path
+        );
+        Ok(())
+    }
+}
@@ -127,5 +127,5 @@ impl GUI for UI {
 }
 
 fn main() {
-    ezgui::run("GUI Playground", 1024.0, 768.0, |ctx| UI::new(ctx));
+    ezgui::run("GUI Playground", 1024.0, 768.0, false, |ctx| UI::new(ctx));
 }
@@ -23,6 +23,7 @@ pub struct Trip {
 pub struct Endpoint {
     pub pos: LonLat,
     pub osm_building: Option<i64>,
+    pub parcel_id: i64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -203,11 +204,13 @@ fn import_parcels(
                     },
                 );
             }
+            let parcel_id = id.trim_end_matches(".0").parse::<i64>()?;
             result.insert(
                 id,
                 Endpoint {
                     pos: pt,
                     osm_building,
+                    parcel_id,
                 },
             );
         }
@@ -8,6 +8,10 @@ struct Flags {
     /// Map
     #[structopt(name = "load")]
     load: String,
+
+    /// Build independent parts of the map at the same time
+    #[structopt(long = "parallel")]
+    parallel: bool,
 }
 
 fn main() {
@@ -22,7 +26,7 @@ fn main() {
         flags.load
     };
 
-    let map = Map::new(&raw_map_path, &mut timer).unwrap();
+    let map = Map::new_maybe_parallel(&raw_map_path, flags.parallel, &mut timer).unwrap();
     timer.start("save map");
     map.save();
     timer.stop("save map");
@@ -1,14 +1,21 @@
 use crate::{AgentID, CarID, ParkingSpot, PedestrianID};
-use map_model::{BuildingID, BusStopID, IntersectionID, LaneID, Traversable};
+use geom::Duration;
+use map_model::{BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, Traversable};
 use serde_derive::{Deserialize, Serialize};
+use std::fmt;
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Event {
     CarReachedParkingSpot(CarID, ParkingSpot),
     CarOrBikeReachedBorder(CarID, IntersectionID),
+    // A restriction on the lane's parking hours started while the car was parked there
+    CarParkedIllegally(CarID, ParkingSpot),
 
-    BusArrivedAtStop(CarID, BusStopID),
-    BusDepartedFromStop(CarID, BusStopID),
+    // The headway is the time since the previous bus of the same route arrived at this stop. None
+    // for the first one. Also has the number of riders aboard when arriving.
+    BusArrivedAtStop(CarID, BusRouteID, BusStopID, Option<Duration>, usize),
+    // How many riders boarded and alighted at this stop
+    BusDepartedFromStop(CarID, BusStopID, usize, usize),
 
     PedReachedParkingSpot(PedestrianID, ParkingSpot),
     PedReachedBuilding(PedestrianID, BuildingID),
@@ -18,7 +25,102 @@ pub enum Event {
     PedLeavesBus(PedestrianID, CarID),
 
     BikeStoppedAtSidewalk(CarID, LaneID),
+    // The first lane from a border was full when this car should've appeared, so it waited this
+    // long at the border
+    BorderSpawnDelayed(CarID, IntersectionID, Duration),
+
+    // Nothing moved for a while, and these cars are all waiting on each other
+    GridlockDetected(Vec<AgentID>),
 
     // TODO Remove this one
     AgentEntersTraversable(AgentID, Traversable),
 }
+
+impl Event {
+    // The name of the variant, for filtering
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::CarReachedParkingSpot(_, _) => "CarReachedParkingSpot",
+            Event::CarOrBikeReachedBorder(_, _) => "CarOrBikeReachedBorder",
+            Event::CarParkedIllegally(_, _) => "CarParkedIllegally",
+            Event::BusArrivedAtStop(_, _, _, _, _) => "BusArrivedAtStop",
+            Event::BusDepartedFromStop(_, _, _, _) => "BusDepartedFromStop",
+            Event::PedReachedParkingSpot(_, _) => "PedReachedParkingSpot",
+            Event::PedReachedBuilding(_, _) => "PedReachedBuilding",
+            Event::PedReachedBorder(_, _) => "PedReachedBorder",
+            Event::PedReachedBusStop(_, _) => "PedReachedBusStop",
+            Event::PedEntersBus(_, _) => "PedEntersBus",
+            Event::PedLeavesBus(_, _) => "PedLeavesBus",
+            Event::BikeStoppedAtSidewalk(_, _) => "BikeStoppedAtSidewalk",
+            Event::BorderSpawnDelayed(_, _, _) => "BorderSpawnDelayed",
+            Event::GridlockDetected(_) => "GridlockDetected",
+            Event::AgentEntersTraversable(_, _) => "AgentEntersTraversable",
+        }
+    }
+
+    // Everybody involved
+    pub fn agents(&self) -> Vec<AgentID> {
+        match self {
+            Event::CarReachedParkingSpot(car, _)
+            | Event::CarOrBikeReachedBorder(car, _)
+            | Event::CarParkedIllegally(car, _)
+            | Event::BusArrivedAtStop(car, _, _, _, _)
+            | Event::BusDepartedFromStop(car, _, _, _)
+            | Event::BikeStoppedAtSidewalk(car, _)
+            | Event::BorderSpawnDelayed(car, _, _) => vec![AgentID::Car(*car)],
+            Event::PedReachedParkingSpot(ped, _)
+            | Event::PedReachedBuilding(ped, _)
+            | Event::PedReachedBorder(ped, _)
+            | Event::PedReachedBusStop(ped, _) => vec![AgentID::Pedestrian(*ped)],
+            Event::PedEntersBus(ped, bus) | Event::PedLeavesBus(ped, bus) => {
+                vec![AgentID::Pedestrian(*ped), AgentID::Car(*bus)]
+            }
+            Event::GridlockDetected(agents) => agents.clone(),
+            Event::AgentEntersTraversable(agent, _) => vec![*agent],
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::CarReachedParkingSpot(car, spot) => write!(f, "{} parked at {}", car, spot),
+            Event::CarOrBikeReachedBorder(car, i) => write!(f, "{} left the map at {}", car, i),
+            Event::CarParkedIllegally(car, spot) => {
+                write!(f, "{} is now parked illegally at {}", car, spot)
+            }
+            Event::BusArrivedAtStop(bus, route, stop, headway, riders) => {
+                write!(
+                    f,
+                    "{} on {} arrived at {} with {} riders",
+                    bus, route, stop, riders
+                )?;
+                if let Some(dt) = headway {
+                    write!(f, ", {} after the previous bus", dt)?;
+                }
+                Ok(())
+            }
+            Event::BusDepartedFromStop(bus, stop, boarded, alighted) => write!(
+                f,
+                "{} left {} ({} boarded, {} alighted)",
+                bus, stop, boarded, alighted
+            ),
+            Event::PedReachedParkingSpot(ped, spot) => {
+                write!(f, "{} reached their car at {}", ped, spot)
+            }
+            Event::PedReachedBuilding(ped, b) => write!(f, "{} reached {}", ped, b),
+            Event::PedReachedBorder(ped, i) => write!(f, "{} left the map at {}", ped, i),
+            Event::PedReachedBusStop(ped, stop) => write!(f, "{} is waiting at {}", ped, stop),
+            Event::PedEntersBus(ped, bus) => write!(f, "{} boarded {}", ped, bus),
+            Event::PedLeavesBus(ped, bus) => write!(f, "{} got off {}", ped, bus),
+            Event::BikeStoppedAtSidewalk(bike, l) => write!(f, "{} stopped at {}", bike, l),
+            Event::BorderSpawnDelayed(car, i, dt) => {
+                write!(f, "{} waited {} to enter the map at {}", car, dt, i)
+            }
+            Event::GridlockDetected(agents) => {
+                write!(f, "Gridlock between {} agents", agents.len())
+            }
+            Event::AgentEntersTraversable(agent, on) => write!(f, "{} entered {}", agent, on),
+        }
+    }
+}
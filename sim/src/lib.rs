@@ -2,30 +2,40 @@ mod events;
 mod make;
 mod mechanics;
 mod render;
+mod replay;
 mod router;
 mod scheduler;
 mod sim;
+mod stats;
 mod transit;
 mod trips;
 
 pub use self::events::Event;
+pub(crate) use self::make::DEMAND_FREQUENCY;
 pub use self::make::{
-    ABTest, BorderSpawnOverTime, OriginDestination, Scenario, SeedParkedCars, SimFlags,
-    SpawnOverTime, SpawnTrip, TripSpawner, TripSpec,
+    ABTest, BorderSpawnOverTime, DemandModel, LaneClosure, ODCell, ODMatrix, OriginDestination,
+    Scenario, SeedParkedCars, SimFlags, SpawnErrors, SpawnOverTime, SpawnTrip, TripSpawner,
+    TripSpec,
 };
 pub(crate) use self::mechanics::{
-    DrivingSimState, IntersectionSimState, ParkingSimState, WalkingSimState,
+    BikeParkingState, DrivingSimState, IntersectionSimState, ParkingSimState, WalkingSimState,
 };
 pub(crate) use self::router::{ActionAtEnd, Router};
 pub(crate) use self::scheduler::{Command, Scheduler};
 pub use self::sim::Sim;
+pub use self::stats::TravelStats;
+pub use self::transit::bus_dwell_time;
 pub(crate) use self::transit::TransitSimState;
-pub use self::trips::{FinishedTrips, TripMode};
+pub use self::trips::{FinishedTrips, LegDetails, TripMetadata, TripMode, TripPurpose};
 pub(crate) use self::trips::{TripLeg, TripManager};
 pub use crate::render::{CarStatus, DrawCarInput, DrawPedestrianInput, GetDrawAgents};
-use abstutil::Cloneable;
+pub use crate::replay::{Replay, ReplayLog};
+use abstutil::{Cloneable, Error};
 use geom::{Distance, Duration, Pt2D, Speed};
-use map_model::{BuildingID, BusStopID, IntersectionID, LaneID, LaneType, Map, Path, Position};
+use map_model::{
+    BikeRackID, BuildingID, BusStopID, IntersectionID, LaneID, LaneType, Map, ParkingLotID, Path,
+    Position, SpawnError, HOV_MIN_OCCUPANCY,
+};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fmt;
@@ -35,13 +45,92 @@ pub const BIKE_LENGTH: Distance = Distance::const_meters(1.8);
 // These two must be < PARKING_SPOT_LENGTH
 pub const MIN_CAR_LENGTH: Distance = Distance::const_meters(4.5);
 pub const MAX_CAR_LENGTH: Distance = Distance::const_meters(6.5);
+// Box trucks, still short enough to park
+pub const MIN_TRUCK_LENGTH: Distance = Distance::const_meters(7.0);
+pub const MAX_TRUCK_LENGTH: Distance = Distance::const_meters(7.8);
 // Note this is more than MAX_CAR_LENGTH
 pub const BUS_LENGTH: Distance = Distance::const_meters(12.5);
 
-// At all speeds (including at rest), cars must be at least this far apart, measured from front of
-// one car to the back of the other.
+// The default DrivingParams::min_gap
 pub const FOLLOWING_DISTANCE: Distance = Distance::const_meters(1.0);
 
+// Tunable knobs for how closely vehicles follow each other. There's no acceleration in the model --
+// vehicles cross each lane and turn at a constant speed -- so there's nothing to say about
+// acceleration or braking here; only the spacing between vehicles can be calibrated.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DrivingParams {
+    // At all speeds (including at rest), cars must be at least this far apart, measured from front
+    // of one car to the back of the other.
+    pub min_gap: Distance,
+    // After a vehicle starts to leave a lane, how much longer the one behind it waits before
+    // following. This is on top of Conditions::following_headway.
+    pub headway: Duration,
+}
+
+impl Default for DrivingParams {
+    fn default() -> DrivingParams {
+        DrivingParams {
+            min_gap: FOLLOWING_DISTANCE,
+            headway: Duration::ZERO,
+        }
+    }
+}
+
+// Weather and road surface. Everything is tuned for Dry; other conditions slow people down and keep
+// some of them off their bikes.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Conditions {
+    Dry,
+    Rain,
+    Snow,
+}
+
+impl Conditions {
+    // Multiplies the speed of pedestrians spawned in these conditions
+    pub fn walking_speed_factor(self) -> f64 {
+        match self {
+            Conditions::Dry => 1.0,
+            Conditions::Rain => 0.85,
+            Conditions::Snow => 0.6,
+        }
+    }
+
+    // After a vehicle starts to leave a lane, how much longer the one behind it waits before
+    // following
+    pub fn following_headway(self) -> Duration {
+        match self {
+            Conditions::Dry => Duration::ZERO,
+            Conditions::Rain => Duration::seconds(1.0),
+            Conditions::Snow => Duration::seconds(2.5),
+        }
+    }
+
+    // Multiplies the chance that somebody who would bike decides to. The rest walk instead.
+    pub fn bike_mode_share_factor(self) -> f64 {
+        match self {
+            Conditions::Dry => 1.0,
+            Conditions::Rain => 0.6,
+            Conditions::Snow => 0.2,
+        }
+    }
+}
+
+impl std::str::FromStr for Conditions {
+    type Err = abstutil::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dry" => Ok(Conditions::Dry),
+            "rain" => Ok(Conditions::Rain),
+            "snow" => Ok(Conditions::Snow),
+            _ => Err(abstutil::Error::new(format!(
+                "{} isn't a valid Conditions; try dry, rain, or snow",
+                s
+            ))),
+        }
+    }
+}
+
 // The VehicleType is only used for convenient debugging. The numeric ID itself must be sufficient.
 // TODO Implement Eq, Hash, Ord manually to guarantee this.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -111,43 +200,119 @@ pub enum VehicleType {
     Bike,
 }
 
+// How big and sluggish a vehicle is. VehicleType decides which lanes it can use; a truck otherwise
+// drives like any car.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Hash, PartialOrd, Ord)]
+pub enum VehicleClass {
+    Car,
+    Truck,
+    Bus,
+    Bike,
+}
+
+impl VehicleClass {
+    // In meters per second squared, when starting from a stop. None means instantly reaching full
+    // speed.
+    pub fn max_accel(self) -> Option<f64> {
+        match self {
+            // TODO Buses should accelerate gradually too, but that changes every transit
+            // schedule.
+            VehicleClass::Car | VehicleClass::Bus | VehicleClass::Bike => None,
+            VehicleClass::Truck => Some(1.0),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Vehicle {
     pub id: CarID,
     pub owner: Option<BuildingID>,
     pub vehicle_type: VehicleType,
+    pub class: VehicleClass,
     pub length: Distance,
     pub max_speed: Option<Speed>,
+    // Including the driver
+    pub occupancy: usize,
+}
+
+impl Vehicle {
+    pub fn can_use_hov_lanes(&self) -> bool {
+        self.occupancy >= HOV_MIN_OCCUPANCY
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct VehicleSpec {
     pub vehicle_type: VehicleType,
+    pub class: VehicleClass,
     pub length: Distance,
     pub max_speed: Option<Speed>,
+    pub occupancy: usize,
 }
 
 impl VehicleSpec {
+    // Scenarios can pin an exact vehicle, so make sure it's one the sim can handle.
+    pub fn validate(&self) -> Result<(), Error> {
+        let max_length = match (self.vehicle_type, self.class) {
+            (VehicleType::Car, VehicleClass::Car) => MAX_CAR_LENGTH,
+            (VehicleType::Car, VehicleClass::Truck) => MAX_TRUCK_LENGTH,
+            (VehicleType::Bus, VehicleClass::Bus) => BUS_LENGTH,
+            (VehicleType::Bike, VehicleClass::Bike) => BIKE_LENGTH,
+            (vehicle_type, class) => {
+                return Err(Error::new(format!(
+                    "A {:?} can't be a {:?}",
+                    vehicle_type, class
+                )));
+            }
+        };
+        if self.length <= Distance::ZERO || self.length > max_length {
+            return Err(Error::new(format!(
+                "A {:?} is {} long, but has to be at most {}",
+                self.class, self.length, max_length
+            )));
+        }
+        if let Some(speed) = self.max_speed {
+            if speed <= Speed::ZERO {
+                return Err(Error::new(format!("A max speed of {} won't move", speed)));
+            }
+        }
+        if self.occupancy == 0 {
+            return Err(Error::new("Nobody is driving".to_string()));
+        }
+        Ok(())
+    }
+
     pub fn make(self, id: CarID, owner: Option<BuildingID>) -> Vehicle {
         Vehicle {
             id,
             owner,
             vehicle_type: self.vehicle_type,
+            class: self.class,
             length: self.length,
             max_speed: self.max_speed,
+            occupancy: self.occupancy,
         }
     }
+
+    pub fn can_use_hov_lanes(&self) -> bool {
+        self.occupancy >= HOV_MIN_OCCUPANCY
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-pub struct ParkingSpot {
-    pub lane: LaneID,
-    pub idx: usize,
+pub enum ParkingSpot {
+    // Parking lane and the index of the spot along it
+    Onstreet(LaneID, usize),
+    // Which lot, and one of its capacity's worth of spots
+    Offstreet(ParkingLotID, usize),
 }
 
-impl ParkingSpot {
-    pub fn new(lane: LaneID, idx: usize) -> ParkingSpot {
-        ParkingSpot { lane, idx }
+impl fmt::Display for ParkingSpot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParkingSpot::Onstreet(l, idx) => write!(f, "spot {} of {}", idx, l),
+            ParkingSpot::Offstreet(lot, idx) => write!(f, "spot {} of {}", idx, lot),
+        }
     }
 }
 
@@ -174,16 +339,16 @@ impl DrivingGoal {
         i: IntersectionID,
         lane_types: Vec<LaneType>,
         map: &Map,
-    ) -> Option<DrivingGoal> {
+    ) -> Result<DrivingGoal, SpawnError> {
         let mut lanes = Vec::new();
-        for lt in lane_types {
-            lanes.extend(map.get_i(i).get_incoming_lanes(map, lt));
+        for lt in &lane_types {
+            lanes.extend(map.get_i(i).get_incoming_lanes(map, *lt));
         }
         if lanes.is_empty() {
-            None
+            Err(SpawnError::NoLanesAtBorder(i, lane_types))
         } else {
             // TODO ideally could use any
-            Some(DrivingGoal::Border(i, lanes[0]))
+            Ok(DrivingGoal::Border(i, lanes[0]))
         }
     }
 
@@ -225,13 +390,9 @@ impl SidewalkSpot {
         map: &Map,
         parking_sim: &ParkingSimState,
     ) -> SidewalkSpot {
-        // TODO Consider precomputing this.
-        let sidewalk = map
-            .find_closest_lane(spot.lane, vec![LaneType::Sidewalk])
-            .unwrap();
         SidewalkSpot {
             connection: SidewalkPOI::ParkingSpot(spot),
-            sidewalk_pos: parking_sim.spot_to_sidewalk_pos(spot, sidewalk, map),
+            sidewalk_pos: parking_sim.spot_to_sidewalk_pos(spot, map),
         }
     }
 
@@ -246,6 +407,9 @@ impl SidewalkSpot {
     pub fn bike_rack(sidewalk: LaneID, map: &Map) -> Option<SidewalkSpot> {
         assert!(map.get_l(sidewalk).is_sidewalk());
         let driving_lane = map.get_parent(sidewalk).sidewalk_to_bike(sidewalk)?;
+        if !map.is_bikeable(driving_lane) {
+            return None;
+        }
         // TODO Arbitrary, but safe
         let sidewalk_pos = Position::new(sidewalk, map.get_l(sidewalk).length() / 2.0);
         let driving_pos = sidewalk_pos.equiv_pos(driving_lane, map);
@@ -255,6 +419,17 @@ impl SidewalkSpot {
         })
     }
 
+    // Fails if lane edits left no way for bikes to reach the rack
+    pub fn at_bike_rack(id: BikeRackID, map: &Map) -> Result<SidewalkSpot, SpawnError> {
+        let rack = map.get_bike_rack(id);
+        Ok(SidewalkSpot {
+            connection: SidewalkPOI::BikeRack(
+                rack.driving_pos(map).ok_or(SpawnError::NoBikeAccess(id))?,
+            ),
+            sidewalk_pos: rack.sidewalk_pos,
+        })
+    }
+
     pub fn bus_stop(stop: BusStopID, map: &Map) -> SidewalkSpot {
         SidewalkSpot {
             sidewalk_pos: map.get_bs(stop).sidewalk_pos,
@@ -263,10 +438,10 @@ impl SidewalkSpot {
     }
 
     // Recall sidewalks are bidirectional.
-    pub fn start_at_border(i: IntersectionID, map: &Map) -> Option<SidewalkSpot> {
+    pub fn start_at_border(i: IntersectionID, map: &Map) -> Result<SidewalkSpot, SpawnError> {
         let lanes = map.get_i(i).get_outgoing_lanes(map, LaneType::Sidewalk);
         if !lanes.is_empty() {
-            return Some(SidewalkSpot {
+            return Ok(SidewalkSpot {
                 sidewalk_pos: Position::new(lanes[0], Distance::ZERO),
                 connection: SidewalkPOI::Border(i),
             });
@@ -274,18 +449,18 @@ impl SidewalkSpot {
 
         let lanes = map.get_i(i).get_incoming_lanes(map, LaneType::Sidewalk);
         if lanes.is_empty() {
-            return None;
+            return Err(SpawnError::NoSidewalkAtBorder(i));
         }
-        Some(SidewalkSpot {
+        Ok(SidewalkSpot {
             sidewalk_pos: Position::new(lanes[0], map.get_l(lanes[0]).length()),
             connection: SidewalkPOI::Border(i),
         })
     }
 
-    pub fn end_at_border(i: IntersectionID, map: &Map) -> Option<SidewalkSpot> {
+    pub fn end_at_border(i: IntersectionID, map: &Map) -> Result<SidewalkSpot, SpawnError> {
         let lanes = map.get_i(i).get_incoming_lanes(map, LaneType::Sidewalk);
         if !lanes.is_empty() {
-            return Some(SidewalkSpot {
+            return Ok(SidewalkSpot {
                 sidewalk_pos: Position::new(lanes[0], map.get_l(lanes[0]).length()),
                 connection: SidewalkPOI::Border(i),
             });
@@ -293,9 +468,9 @@ impl SidewalkSpot {
 
         let lanes = map.get_i(i).get_outgoing_lanes(map, LaneType::Sidewalk);
         if lanes.is_empty() {
-            return None;
+            return Err(SpawnError::NoSidewalkAtBorder(i));
         }
-        Some(SidewalkSpot {
+        Ok(SidewalkSpot {
             sidewalk_pos: Position::new(lanes[0], Distance::ZERO),
             connection: SidewalkPOI::Border(i),
         })
@@ -457,6 +632,7 @@ impl TripPositions {
 // We have to do this in the crate where these types are defined. Bit annoying, since it's really
 // kind of an ezgui concept.
 impl Cloneable for ABTest {}
+impl Cloneable for Conditions {}
 impl Cloneable for Scenario {}
 impl Cloneable for TripID {}
 impl Cloneable for TripMode {}
@@ -0,0 +1,100 @@
+use crate::{OriginDestination, Sim, SpawnErrors, SpawnOverTime};
+use abstutil::Timer;
+use geom::Duration;
+use map_model::{FullNeighborhoodInfo, Map};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::f64::consts::PI;
+
+pub const DEMAND_FREQUENCY: Duration = Duration::const_seconds(60.0);
+// Hours before and after each peak that demand is above the off-peak floor. The two rushes don't
+// overlap, so midday drops back down to the floor.
+const RUSH_HOUR_HALF_WIDTH: f64 = 4.0;
+
+// Background traffic that keeps spawning random trips all over the map while the sim runs,
+// instead of precomputing every trip in a Scenario. Every minute, the sim asks how many trips to
+// start during that minute.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum DemandModel {
+    // Trips per minute
+    Constant(usize),
+    // Trips per minute swing between these, peaking at 8am and 5pm. Midday and all night, demand
+    // stays at the floor of off_peak.
+    PeakHours { off_peak: usize, peak: usize },
+}
+
+impl DemandModel {
+    pub fn trips_per_minute(&self, time: Duration) -> usize {
+        match self {
+            DemandModel::Constant(n) => *n,
+            DemandModel::PeakHours { off_peak, peak } => {
+                // The pattern repeats every day.
+                let hours = (time.inner_seconds() / 3600.0) % 24.0;
+                let intensity = rush_hour(hours - 8.0).max(rush_hour(hours - 17.0));
+                let range = *peak as f64 - *off_peak as f64;
+                (*off_peak as f64 + intensity * range).round() as usize
+            }
+        }
+    }
+
+    // Schedules trips starting during the minute after the sim's current time. Trips are picked
+    // the same way as a SpawnOverTime covering the whole map.
+    pub(crate) fn spawn_trips(&self, sim: &mut Sim, map: &Map, seed: u64) {
+        let num_agents = self.trips_per_minute(sim.time());
+        if num_agents == 0 {
+            return;
+        }
+
+        // Reseed every minute, so savestates don't need to carry the RNG's state.
+        let minute = (sim.time() / DEMAND_FREQUENCY) as u64;
+        let mut rng = XorShiftRng::seed_from_u64(seed.wrapping_add(minute));
+        let mut neighborhoods = HashMap::new();
+        neighborhoods.insert(
+            "_everywhere_".to_string(),
+            FullNeighborhoodInfo {
+                name: "_everywhere_".to_string(),
+                buildings: map.all_buildings().iter().map(|b| b.id).collect(),
+                roads: BTreeSet::new(),
+            },
+        );
+        let spawn = SpawnOverTime {
+            num_agents,
+            start_time: sim.time(),
+            stop_time: sim.time() + DEMAND_FREQUENCY,
+            start_from_neighborhood: "_everywhere_".to_string(),
+            goal: OriginDestination::Neighborhood("_everywhere_".to_string()),
+            percent_biking: 0.5,
+            percent_use_transit: 0.5,
+            percent_trucks: 0.0,
+        };
+
+        let mut timer = Timer::throwaway();
+        let mut errors = SpawnErrors::new();
+        for _ in 0..num_agents {
+            spawn.spawn_agent(
+                &mut rng,
+                sim,
+                // Parked cars might already be reserved for trips scheduled elsewhere, so don't
+                // touch them.
+                None,
+                &neighborhoods,
+                map,
+                &mut errors,
+                &mut timer,
+            );
+        }
+        errors.report(&mut timer);
+        sim.spawn_all_trips(map, &mut timer, true);
+    }
+}
+
+// How busy it is this many hours away from the height of a rush hour, from 1 at the peak down to 0
+// once the rush is over.
+fn rush_hour(hours_from_peak: f64) -> f64 {
+    if hours_from_peak.abs() >= RUSH_HOUR_HALF_WIDTH {
+        return 0.0;
+    }
+    (1.0 + (PI * hours_from_peak / RUSH_HOUR_HALF_WIDTH).cos()) / 2.0
+}
@@ -1,8 +1,8 @@
-use crate::{Scenario, Sim};
+use crate::{Conditions, DrivingParams, Scenario, Sim};
 use abstutil;
-use geom::Duration;
+use geom::{Distance, Duration};
 use map_model::{Map, MapEdits};
-use rand::{FromEntropy, SeedableRng};
+use rand::{FromEntropy, RngCore, SeedableRng};
 use rand_xorshift::XorShiftRng;
 use std::path::{Path, PathBuf};
 use structopt::StructOpt;
@@ -18,13 +18,55 @@ pub struct SimFlags {
     )]
     pub load: PathBuf,
 
-    /// Optional RNG seed
+    /// Optional RNG seed. If omitted, one is picked and printed, so the run can be reproduced.
     #[structopt(long = "rng_seed")]
-    pub rng_seed: Option<u8>,
+    pub rng_seed: Option<u64>,
 
     /// Run name for savestating
     #[structopt(long = "run_name")]
     pub run_name: Option<String>,
+
+    /// Let pedestrians cross quiet roads mid-block, not just at intersections
+    #[structopt(long = "midblock_crossings")]
+    pub midblock_crossings: bool,
+
+    /// Remember the path and timing of every leg of every trip, for later inspection
+    #[structopt(long = "record_trip_details")]
+    pub record_trip_details: bool,
+
+    /// Weather and road surface: dry, rain, or snow
+    #[structopt(long = "conditions", default_value = "dry")]
+    pub conditions: Conditions,
+
+    /// Check for gridlock once no car has moved for this many seconds
+    #[structopt(long = "gridlock_window", default_value = "300")]
+    pub gridlock_window: f64,
+
+    /// Abort the trips of cars stuck in gridlock, instead of savestating and carrying on
+    #[structopt(long = "abort_gridlocked_trips")]
+    pub abort_gridlocked_trips: bool,
+
+    /// Minimum gap in meters between a car's front and the back of the car ahead
+    #[structopt(long = "min_following_gap", default_value = "1")]
+    pub min_following_gap: f64,
+
+    /// Extra seconds a car waits after its leader starts leaving a lane before following
+    #[structopt(long = "following_headway", default_value = "0")]
+    pub following_headway: f64,
+
+    /// Start trips departing before this many seconds partway along their route, as if the
+    /// simulation had already been running
+    #[structopt(long = "warm_start")]
+    pub warm_start: Option<f64>,
+
+    /// Let cars turn right at a red light after stopping, when nothing with a green conflicts
+    #[structopt(long = "right_turn_on_red")]
+    pub right_turn_on_red: bool,
+
+    /// Every this many seconds, log where each agent is, so the run can be played back later.
+    /// The logs get big.
+    #[structopt(long = "record_replay")]
+    pub record_replay: Option<f64>,
 }
 
 impl SimFlags {
@@ -38,24 +80,65 @@ impl SimFlags {
             load: PathBuf::from(format!("../data/maps/{}.bin", map)),
             rng_seed: Some(42),
             run_name: Some(run_name.to_string()),
+            midblock_crossings: false,
+            record_trip_details: false,
+            conditions: Conditions::Dry,
+            gridlock_window: 300.0,
+            abort_gridlocked_trips: false,
+            min_following_gap: 1.0,
+            following_headway: 0.0,
+            warm_start: None,
+            right_turn_on_red: false,
+            record_replay: None,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> SimFlags {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    pub fn driving_params(&self) -> DrivingParams {
+        DrivingParams {
+            min_gap: Distance::meters(self.min_following_gap),
+            headway: Duration::seconds(self.following_headway),
         }
     }
 
     pub fn make_rng(&self) -> XorShiftRng {
         if let Some(seed) = self.rng_seed {
-            XorShiftRng::from_seed([seed; 16])
+            XorShiftRng::seed_from_u64(seed)
         } else {
             XorShiftRng::from_entropy()
         }
     }
 
+    // Apply every flag that changes how a new Sim behaves. Anything that creates a Sim from these
+    // flags should go through here, so no flag gets forgotten.
+    pub fn configure(&self, sim: &mut Sim, seed: u64) {
+        sim.use_midblock_crossings(self.midblock_crossings);
+        sim.record_trip_details(self.record_trip_details);
+        sim.set_conditions(self.conditions);
+        sim.set_gridlock_window(Duration::seconds(self.gridlock_window));
+        sim.abort_gridlocked_trips(self.abort_gridlocked_trips);
+        sim.set_driving_params(self.driving_params());
+        sim.set_warm_start(self.warm_start.map(Duration::seconds));
+        sim.allow_right_turn_on_red(self.right_turn_on_red);
+        sim.record_replay(self.record_replay.map(Duration::seconds));
+        sim.set_rng_seed(seed);
+    }
+
     // Convenience method to setup everything.
     pub fn load(
         &self,
         savestate_every: Option<Duration>,
         timer: &mut abstutil::Timer,
     ) -> (Map, Sim, XorShiftRng) {
-        let mut rng = self.make_rng();
+        let seed = self
+            .rng_seed
+            .unwrap_or_else(|| XorShiftRng::from_entropy().next_u64());
+        timer.note(format!("RNG seed is {}", seed));
+        let mut rng = XorShiftRng::seed_from_u64(seed);
 
         if self.load.starts_with(Path::new("../data/save/")) {
             timer.note(format!("Resuming from {}", self.load.display()));
@@ -91,6 +174,7 @@ impl SimFlags {
                     .unwrap_or_else(|| scenario.scenario_name.clone()),
                 savestate_every,
             );
+            self.configure(&mut sim, seed);
             scenario.instantiate(&mut sim, &map, &mut rng, timer);
 
             (map, sim, rng)
@@ -101,13 +185,14 @@ impl SimFlags {
                 .expect(&format!("Couldn't load map from {}", self.load.display()));
 
             timer.start("create sim");
-            let sim = Sim::new(
+            let mut sim = Sim::new(
                 &map,
                 self.run_name
                     .clone()
                     .unwrap_or_else(|| "unnamed".to_string()),
                 savestate_every,
             );
+            self.configure(&mut sim, seed);
             timer.stop("create sim");
 
             (map, sim, rng)
@@ -118,13 +203,14 @@ impl SimFlags {
                 .expect(&format!("Couldn't load map from {}", self.load.display()));
 
             timer.start("create sim");
-            let sim = Sim::new(
+            let mut sim = Sim::new(
                 &map,
                 self.run_name
                     .clone()
                     .unwrap_or_else(|| "unnamed".to_string()),
                 savestate_every,
             );
+            self.configure(&mut sim, seed);
             timer.stop("create sim");
 
             (map, sim, rng)
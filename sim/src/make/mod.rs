@@ -1,11 +1,18 @@
 mod a_b_test;
+mod demand;
 mod load;
+mod od_matrix;
 mod scenario;
 mod spawner;
+mod warm_start;
 
 pub use self::a_b_test::ABTest;
+pub use self::demand::DemandModel;
+pub(crate) use self::demand::DEMAND_FREQUENCY;
 pub use self::load::SimFlags;
+pub use self::od_matrix::{ODCell, ODMatrix};
 pub use self::scenario::{
-    BorderSpawnOverTime, OriginDestination, Scenario, SeedParkedCars, SpawnOverTime, SpawnTrip,
+    BorderSpawnOverTime, LaneClosure, OriginDestination, Scenario, SeedParkedCars, SpawnOverTime,
+    SpawnTrip,
 };
-pub use self::spawner::{TripSpawner, TripSpec};
+pub use self::spawner::{SpawnErrors, TripSpawner, TripSpec};
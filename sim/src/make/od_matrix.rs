@@ -0,0 +1,409 @@
+use crate::{DrivingGoal, Scenario, SidewalkSpot, SpawnTrip, TripMode, TripSpec};
+use abstutil::{Timer, WeightedUsizeChoice};
+use geojson::{GeoJson, PolygonType, Value};
+use geom::{Distance, Duration, LonLat, Polygon, Pt2D};
+use map_model::{Building, BuildingID, BuildingType, IntersectionID, LaneType, Map, Position};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind};
+
+// Regional models often publish demand as trip counts between zones, instead of individual trips.
+pub struct ODMatrix {
+    // Each zone might have a few separate polygons, in GPS coordinates.
+    pub zones: BTreeMap<String, Vec<Vec<LonLat>>>,
+    pub cells: Vec<ODCell>,
+}
+
+// This many trips go from one zone to another, departing sometime during this hour of the day.
+#[derive(Clone, Debug)]
+pub struct ODCell {
+    pub origin: String,
+    pub destination: String,
+    pub hour: usize,
+    pub mode: TripMode,
+    pub count: usize,
+}
+
+impl ODMatrix {
+    // The CSV has a header, then rows of (origin zone, destination zone, hour, mode, count).
+    // Modes are walk, bike, transit, or drive. The GeoJSON is a FeatureCollection of polygons,
+    // named by their "name" property.
+    pub fn load(csv_path: &str, zones_path: &str) -> Result<ODMatrix, Error> {
+        let mut cells = Vec::new();
+        for (idx, line) in BufReader::new(File::open(csv_path)?)
+            .lines()
+            .enumerate()
+            .skip(1)
+        {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            cells.push(parse_cell(&line).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} line {}: can't parse {}", csv_path, idx + 1, line),
+                )
+            })?);
+        }
+
+        let mut zones = BTreeMap::new();
+        match abstutil::read_json(zones_path)? {
+            GeoJson::FeatureCollection(c) => {
+                for f in c.features {
+                    let name = f
+                        .properties
+                        .as_ref()
+                        .and_then(|props| props.get("name"))
+                        .and_then(|name| name.as_str())
+                        .map(|name| name.to_string())
+                        .ok_or_else(|| {
+                            Error::new(
+                                ErrorKind::InvalidData,
+                                format!("{} has a zone without a name", zones_path),
+                            )
+                        })?;
+                    let polygons: Option<Vec<Vec<LonLat>>> = match f.geometry.map(|g| g.value) {
+                        Some(Value::Polygon(p)) => outer_ring(p).map(|ring| vec![ring]),
+                        Some(Value::MultiPolygon(polygons)) => {
+                            polygons.into_iter().map(outer_ring).collect()
+                        }
+                        x => {
+                            return Err(Error::new(
+                                ErrorKind::InvalidData,
+                                format!("Zone {} isn't a polygon: {:?}", name, x),
+                            ));
+                        }
+                    };
+                    let polygons = polygons.ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Zone {} has a polygon with no rings", name),
+                        )
+                    })?;
+                    zones.insert(name, polygons);
+                }
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} isn't a FeatureCollection", zones_path),
+                ));
+            }
+        }
+
+        for cell in &cells {
+            for zone in &[&cell.origin, &cell.destination] {
+                if !zones.contains_key(*zone) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{} refers to unknown zone {}", csv_path, zone),
+                    ));
+                }
+            }
+        }
+
+        Ok(ODMatrix { zones, cells })
+    }
+
+    // Trips start and end at buildings inside each zone, favoring buildings with more residential
+    // units or that are offices, shops, and schools. Zones without any buildings on this map use
+    // the nearest border instead, like clip_trips does for PSRC trips. Trips that would go from one
+    // border to another are skipped.
+    pub fn to_scenario(
+        &self,
+        scenario_name: String,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
+        let zones: BTreeMap<&String, Zone> = self
+            .zones
+            .iter()
+            .map(|(name, polygons)| (name, Zone::new(polygons, map)))
+            .collect();
+        let borders = Borders::new(map);
+
+        let mut individ_trips = Vec::new();
+        let mut skipped = 0;
+        timer.start_iter("turn OD matrix into trips", self.cells.len());
+        for cell in &self.cells {
+            timer.next();
+            let (from_zone, to_zone) = (&zones[&cell.origin], &zones[&cell.destination]);
+            for _ in 0..cell.count {
+                let depart =
+                    Duration::seconds((cell.hour as f64 + rng.gen_range(0.0, 1.0)) * 3600.0);
+                let from = from_zone.pick(borders.incoming(cell.mode), rng);
+                let to = to_zone.pick(borders.outgoing(cell.mode), rng);
+                match (from, to) {
+                    (Some(from), Some(to)) => match make_trip(depart, from, to, cell.mode, map) {
+                        Some(trip) => individ_trips.push(trip),
+                        None => skipped += 1,
+                    },
+                    _ => skipped += 1,
+                }
+            }
+        }
+        if skipped > 0 {
+            timer.warn(format!(
+                "Skipped {} trips from the OD matrix that couldn't be placed on this map",
+                skipped
+            ));
+        }
+
+        Scenario {
+            scenario_name,
+            map_name: map.get_name().to_string(),
+            seed_parked_cars: Vec::new(),
+            spawn_over_time: Vec::new(),
+            border_spawn_over_time: Vec::new(),
+            individ_trips,
+            trip_metadata: BTreeMap::new(),
+            lane_closures: Vec::new(),
+        }
+    }
+}
+
+fn parse_cell(line: &str) -> Option<ODCell> {
+    let cols: Vec<&str> = line.split(',').map(|col| col.trim()).collect();
+    if cols.len() != 5 {
+        return None;
+    }
+    let mode = match cols[3].to_lowercase().as_str() {
+        "walk" => TripMode::Walk,
+        "bike" => TripMode::Bike,
+        "transit" => TripMode::Transit,
+        "drive" => TripMode::Drive,
+        _ => {
+            return None;
+        }
+    };
+    Some(ODCell {
+        origin: cols[0].to_string(),
+        destination: cols[1].to_string(),
+        hour: cols[2].parse().ok()?,
+        mode,
+        count: cols[4].parse().ok()?,
+    })
+}
+
+// Holes in zones don't matter much for sampling buildings, so ignore them. None if there are no
+// rings at all.
+fn outer_ring(polygon: PolygonType) -> Option<Vec<LonLat>> {
+    Some(
+        polygon
+            .get(0)?
+            .iter()
+            .map(|pt| LonLat::new(pt[0], pt[1]))
+            .collect(),
+    )
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Endpoint {
+    Building(BuildingID),
+    Border(IntersectionID),
+}
+
+struct Zone {
+    bldgs: Vec<BuildingID>,
+    // Only filled out when there are buildings
+    weights: Option<WeightedUsizeChoice>,
+    // Used to find the nearest border when the zone is off the map
+    center: Pt2D,
+}
+
+impl Zone {
+    fn new(polygons: &[Vec<LonLat>], map: &Map) -> Zone {
+        let gps_bounds = map.get_gps_bounds();
+        let polygons: Vec<Polygon> = polygons
+            .iter()
+            .filter(|pts| pts.len() >= 3)
+            .map(|pts| {
+                Polygon::new(
+                    &pts.iter()
+                        .map(|pt| Pt2D::forcibly_from_gps(*pt, gps_bounds))
+                        .collect(),
+                )
+            })
+            .collect();
+        let bldgs: Vec<BuildingID> = map
+            .all_buildings()
+            .iter()
+            .filter(|b| {
+                let pt = b.polygon.center();
+                polygons.iter().any(|p| p.contains_pt(pt))
+            })
+            .map(|b| b.id)
+            .collect();
+        let weights = if bldgs.is_empty() {
+            None
+        } else {
+            Some(WeightedUsizeChoice {
+                weights: bldgs
+                    .iter()
+                    .map(|b| building_weight(map.get_b(*b)))
+                    .collect(),
+            })
+        };
+        let center = Pt2D::center(&polygons.iter().map(|p| p.center()).collect());
+        Zone {
+            bldgs,
+            weights,
+            center,
+        }
+    }
+
+    fn pick(&self, borders: &[(IntersectionID, Pt2D)], rng: &mut XorShiftRng) -> Option<Endpoint> {
+        if let Some(ref weights) = self.weights {
+            return Some(Endpoint::Building(self.bldgs[weights.sample(rng)]));
+        }
+        borders
+            .iter()
+            .min_by_key(|(_, pt)| pt.dist_to(self.center))
+            .map(|(i, _)| Endpoint::Border(*i))
+    }
+}
+
+// How many trips a building attracts, relative to a house. Parcel data about residential units is
+// the best guess; otherwise, go by the building type from OSM.
+fn building_weight(b: &Building) -> usize {
+    if let Some(units) = b.num_residential_units {
+        return units.max(1);
+    }
+    match b.building_type {
+        BuildingType::Residence | BuildingType::Unknown => 1,
+        BuildingType::Industrial => 2,
+        BuildingType::Retail => 5,
+        BuildingType::Office | BuildingType::School => 10,
+    }
+}
+
+// The borders where trips of each mode can enter or leave the map
+struct Borders {
+    incoming_walking: Vec<(IntersectionID, Pt2D)>,
+    incoming_driving: Vec<(IntersectionID, Pt2D)>,
+    outgoing_walking: Vec<(IntersectionID, Pt2D)>,
+    outgoing_driving: Vec<(IntersectionID, Pt2D)>,
+}
+
+impl Borders {
+    fn new(map: &Map) -> Borders {
+        let incoming = |lane_type: LaneType| -> Vec<(IntersectionID, Pt2D)> {
+            map.all_incoming_borders()
+                .into_iter()
+                .filter(|i| !i.get_outgoing_lanes(map, lane_type).is_empty())
+                .map(|i| (i.id, i.polygon.center()))
+                .collect()
+        };
+        let outgoing = |lane_type: LaneType| -> Vec<(IntersectionID, Pt2D)> {
+            map.all_outgoing_borders()
+                .into_iter()
+                .filter(|i| !i.get_incoming_lanes(map, lane_type).is_empty())
+                .map(|i| (i.id, i.polygon.center()))
+                .collect()
+        };
+        Borders {
+            incoming_walking: incoming(LaneType::Sidewalk),
+            incoming_driving: incoming(LaneType::Driving),
+            outgoing_walking: outgoing(LaneType::Sidewalk),
+            outgoing_driving: outgoing(LaneType::Driving),
+        }
+    }
+
+    fn incoming(&self, mode: TripMode) -> &[(IntersectionID, Pt2D)] {
+        match mode {
+            TripMode::Walk | TripMode::Transit => &self.incoming_walking,
+            TripMode::Bike | TripMode::Drive => &self.incoming_driving,
+        }
+    }
+
+    fn outgoing(&self, mode: TripMode) -> &[(IntersectionID, Pt2D)] {
+        match mode {
+            TripMode::Walk | TripMode::Transit => &self.outgoing_walking,
+            TripMode::Bike | TripMode::Drive => &self.outgoing_driving,
+        }
+    }
+}
+
+fn make_trip(
+    depart: Duration,
+    from: Endpoint,
+    to: Endpoint,
+    mode: TripMode,
+    map: &Map,
+) -> Option<SpawnTrip> {
+    if from == to {
+        return None;
+    }
+    // TODO Detect and handle pass-through trips
+    if let (Endpoint::Border(_), Endpoint::Border(_)) = (from, to) {
+        return None;
+    }
+
+    let sidewalk_spot = |endpt: Endpoint, starting: bool| match endpt {
+        Endpoint::Building(b) => Some(SidewalkSpot::building(b, map)),
+        Endpoint::Border(i) => if starting {
+            SidewalkSpot::start_at_border(i, map)
+        } else {
+            SidewalkSpot::end_at_border(i, map)
+        }
+        .ok(),
+    };
+    let driving_goal = |lane_types: Vec<LaneType>| match to {
+        Endpoint::Building(b) => Some(DrivingGoal::ParkNear(b)),
+        Endpoint::Border(i) => DrivingGoal::end_at_border(i, lane_types, map).ok(),
+    };
+    let car_appearing = |is_bike: bool, lane_types: Vec<LaneType>| {
+        let start = match from {
+            Endpoint::Building(b) => Position::bldg_via_driving(b, map).ok()?,
+            Endpoint::Border(i) => Position::new(
+                *map.get_i(i)
+                    .get_outgoing_lanes(map, LaneType::Driving)
+                    .get(0)?,
+                Distance::ZERO,
+            ),
+        };
+        Some(SpawnTrip::CarAppearing {
+            depart,
+            start: TripSpec::spawn_car_at(start, map).ok()?,
+            goal: driving_goal(lane_types)?,
+            is_bike,
+            occupancy: 1,
+            vehicle_spec: None,
+        })
+    };
+
+    match mode {
+        TripMode::Drive => car_appearing(false, vec![LaneType::Driving]),
+        TripMode::Bike => match from {
+            Endpoint::Building(b) => Some(SpawnTrip::UsingBike {
+                depart,
+                start: SidewalkSpot::building(b, map),
+                goal: driving_goal(vec![LaneType::Biking, LaneType::Driving])?,
+                vehicle: None,
+            }),
+            Endpoint::Border(_) => car_appearing(true, vec![LaneType::Biking, LaneType::Driving]),
+        },
+        TripMode::Walk => Some(SpawnTrip::JustWalking(
+            depart,
+            sidewalk_spot(from, true)?,
+            sidewalk_spot(to, false)?,
+        )),
+        TripMode::Transit => {
+            let start = sidewalk_spot(from, true)?;
+            let goal = sidewalk_spot(to, false)?;
+            if let Some((stop1, stop2, route)) =
+                map.should_use_transit(start.sidewalk_pos, goal.sidewalk_pos)
+            {
+                Some(SpawnTrip::UsingTransit(
+                    depart, start, goal, route, stop1, stop2,
+                ))
+            } else {
+                Some(SpawnTrip::JustWalking(depart, start, goal))
+            }
+        }
+    }
+}
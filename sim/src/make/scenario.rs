@@ -1,19 +1,20 @@
 use crate::{
-    CarID, DrivingGoal, ParkingSpot, SidewalkSpot, Sim, TripSpec, VehicleSpec, VehicleType,
-    BIKE_LENGTH, MAX_CAR_LENGTH, MIN_CAR_LENGTH,
+    CarID, DrivingGoal, ParkingSpot, SidewalkPOI, SidewalkSpot, Sim, SpawnErrors, TripMetadata,
+    TripSpec, VehicleClass, VehicleSpec, VehicleType, BIKE_LENGTH, MAX_CAR_LENGTH,
+    MAX_TRUCK_LENGTH, MIN_CAR_LENGTH, MIN_TRUCK_LENGTH,
 };
 use abstutil;
-use abstutil::{fork_rng, Timer, WeightedUsizeChoice};
+use abstutil::{fork_rng, Error, Timer, WeightedUsizeChoice};
 use geom::{Distance, Duration, Speed};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, FullNeighborhoodInfo, IntersectionID, LaneType, Map,
-    Position, RoadID,
+    BuildingID, BuildingType, BusRouteID, BusStopID, FullNeighborhoodInfo, IntersectionID, LaneID,
+    LaneType, Map, Neighborhood, Position, RoadID, SpawnError,
 };
 use rand::seq::SliceRandom;
 use rand::Rng;
 use rand_xorshift::XorShiftRng;
 use serde_derive::{Deserialize, Serialize};
-use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Scenario {
@@ -24,6 +25,11 @@ pub struct Scenario {
     pub spawn_over_time: Vec<SpawnOverTime>,
     pub border_spawn_over_time: Vec<BorderSpawnOverTime>,
     pub individ_trips: Vec<SpawnTrip>,
+    // Imported trips might have some extra info to carry along, keyed by index into individ_trips
+    #[serde(default)]
+    pub trip_metadata: BTreeMap<usize, TripMetadata>,
+    #[serde(default)]
+    pub lane_closures: Vec<LaneClosure>,
 }
 
 // SpawnOverTime and BorderSpawnOverTime should be kept separate. Agents in SpawnOverTime pick
@@ -39,6 +45,9 @@ pub struct SpawnOverTime {
     pub goal: OriginDestination,
     pub percent_biking: f64,
     pub percent_use_transit: f64,
+    // Of the trips that don't use a parked car
+    #[serde(default)]
+    pub percent_trucks: f64,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -53,6 +62,9 @@ pub struct BorderSpawnOverTime {
     pub start_from_border: IntersectionID,
     pub goal: OriginDestination,
     pub percent_use_transit: f64,
+    // Of the cars
+    #[serde(default)]
+    pub percent_trucks: f64,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -61,15 +73,81 @@ pub struct SeedParkedCars {
     pub cars_per_building: WeightedUsizeChoice,
 }
 
+// Construction and such take away a lane for part of the day.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct LaneClosure {
+    pub lane: LaneID,
+    pub start_time: Duration,
+    // Closed for the rest of the simulation if None
+    pub end_time: Option<Duration>,
+}
+
 impl Scenario {
-    pub fn describe(&self) -> Vec<String> {
-        vec![
+    pub fn describe(&self, map: &Map) -> Vec<String> {
+        let mut lines = vec![
             format!("{} for {}", self.scenario_name, self.map_name),
             format!("{} SeedParkedCars", self.seed_parked_cars.len()),
             format!("{} SpawnOverTime", self.spawn_over_time.len()),
             format!("{} BorderSpawnOverTime", self.border_spawn_over_time.len()),
             format!("{} SpawnTrip", self.individ_trips.len()),
-        ]
+            format!("{} LaneClosure", self.lane_closures.len()),
+        ];
+
+        let mut used_neighborhoods: BTreeSet<&String> = BTreeSet::new();
+        for s in &self.seed_parked_cars {
+            used_neighborhoods.insert(&s.neighborhood);
+        }
+        for s in &self.spawn_over_time {
+            used_neighborhoods.insert(&s.start_from_neighborhood);
+            if let Some(n) = s.goal.neighborhood() {
+                used_neighborhoods.insert(n);
+            }
+        }
+        for s in &self.border_spawn_over_time {
+            if let Some(n) = s.goal.neighborhood() {
+                used_neighborhoods.insert(n);
+            }
+        }
+        if used_neighborhoods.is_empty() && self.individ_trips.is_empty() {
+            return lines;
+        }
+        let neighborhoods: Vec<Neighborhood> =
+            Neighborhood::load_all(map.get_name(), map.get_gps_bounds())
+                .into_iter()
+                .map(|(_, n)| n)
+                .collect();
+
+        if !used_neighborhoods.is_empty() {
+            let everywhere = Neighborhood::make_everywhere(map);
+            let mut areas: HashMap<&String, f64> = neighborhoods
+                .iter()
+                .map(|n| (&n.name, n.polygon.area()))
+                .collect();
+            areas.insert(&everywhere.name, everywhere.polygon.area());
+            for name in used_neighborhoods {
+                if let Some(area) = areas.get(name) {
+                    lines.push(format!("{}: {:.2} km^2", name, area / 1_000_000.0));
+                } else {
+                    lines.push(format!("{}: not defined", name));
+                }
+            }
+        }
+
+        // Individual trips between buildings, grouped by the neighborhoods they start and end in
+        let label = |b: BuildingID| {
+            Neighborhood::neighborhood_containing(map.get_b(b).polygon.center(), &neighborhoods)
+                .unwrap_or_else(|| "no neighborhood".to_string())
+        };
+        let mut matrix: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for trip in &self.individ_trips {
+            if let (Some(from), Some(to)) = trip.building_endpoints() {
+                *matrix.entry((label(from), label(to))).or_insert(0) += 1;
+            }
+        }
+        for ((from, to), count) in matrix {
+            lines.push(format!("{} -> {}: {} SpawnTrip", from, to, count));
+        }
+        lines
     }
 
     // TODO may need to fork the RNG a bit more
@@ -104,6 +182,7 @@ impl Scenario {
 
         // Don't let two pedestrians starting from one building use the same car.
         let mut reserved_cars: HashSet<CarID> = HashSet::new();
+        let mut errors = SpawnErrors::new();
 
         for s in &self.spawn_over_time {
             if !neighborhoods.contains_key(&s.start_from_neighborhood) {
@@ -113,67 +192,96 @@ impl Scenario {
             timer.start_iter("SpawnOverTime each agent", s.num_agents);
             for _ in 0..s.num_agents {
                 timer.next();
-                s.spawn_agent(rng, sim, &mut reserved_cars, &neighborhoods, map, timer);
+                s.spawn_agent(
+                    rng,
+                    sim,
+                    Some(&mut reserved_cars),
+                    &neighborhoods,
+                    map,
+                    &mut errors,
+                    timer,
+                );
             }
         }
 
         timer.start_iter("BorderSpawnOverTime", self.border_spawn_over_time.len());
         for s in &self.border_spawn_over_time {
             timer.next();
-            s.spawn_peds(rng, sim, &neighborhoods, map, timer);
-            s.spawn_cars(rng, sim, &neighborhoods, map, timer);
-            s.spawn_bikes(rng, sim, &neighborhoods, map, timer);
+            s.spawn_peds(rng, sim, &neighborhoods, map, &mut errors);
+            s.spawn_cars(rng, sim, &neighborhoods, map, &mut errors);
+            s.spawn_bikes(rng, sim, &neighborhoods, map, &mut errors);
         }
 
         timer.start_iter("SpawnTrip", self.individ_trips.len());
-        for t in &self.individ_trips {
+        for (idx, t) in self.individ_trips.iter().enumerate() {
+            timer.next();
+            if let Err(err) = t.validate_vehicle() {
+                timer.warn(format!("Skipping trip {} in the scenario: {}", idx, err));
+                continue;
+            }
+            let metadata = self.trip_metadata.get(&idx);
             match t.clone() {
                 SpawnTrip::CarAppearing {
                     depart,
                     start,
                     goal,
                     is_bike,
+                    occupancy,
+                    vehicle_spec,
                 } => {
-                    sim.schedule_trip(
+                    sim.schedule_trip_with_metadata(
                         depart,
                         TripSpec::CarAppearing {
                             start_pos: start,
                             goal,
-                            vehicle_spec: if is_bike {
+                            vehicle_spec: if let Some(spec) = vehicle_spec {
+                                spec
+                            } else if is_bike {
                                 Scenario::rand_bike(rng)
                             } else {
-                                Scenario::rand_car(rng)
+                                VehicleSpec {
+                                    occupancy,
+                                    ..Scenario::rand_car(rng)
+                                }
                             },
                             ped_speed: Scenario::rand_ped_speed(rng),
                         },
+                        metadata.cloned(),
                         map,
                     );
                 }
-                SpawnTrip::UsingBike(depart, start, goal) => {
-                    sim.schedule_trip(
+                SpawnTrip::UsingBike {
+                    depart,
+                    start,
+                    goal,
+                    vehicle,
+                } => {
+                    sim.schedule_trip_with_metadata(
                         depart,
                         TripSpec::UsingBike {
                             start,
                             goal,
-                            vehicle: Scenario::rand_bike(rng),
+                            vehicle: vehicle.unwrap_or_else(|| Scenario::rand_bike(rng)),
                             ped_speed: Scenario::rand_ped_speed(rng),
                         },
+                        metadata.cloned(),
                         map,
                     );
                 }
                 SpawnTrip::JustWalking(depart, start, goal) => {
-                    sim.schedule_trip(
+                    sim.schedule_trip_with_metadata(
                         depart,
                         TripSpec::JustWalking {
                             start,
                             goal,
                             ped_speed: Scenario::rand_ped_speed(rng),
                         },
+                        metadata.cloned(),
                         map,
                     );
                 }
                 SpawnTrip::UsingTransit(depart, start, goal, route, stop1, stop2) => {
-                    sim.schedule_trip(
+                    sim.schedule_trip_with_metadata(
                         depart,
                         TripSpec::UsingTransit {
                             start,
@@ -183,17 +291,31 @@ impl Scenario {
                             stop2,
                             ped_speed: Scenario::rand_ped_speed(rng),
                         },
+                        metadata.cloned(),
                         map,
                     );
                 }
             }
-            timer.next();
+        }
+        errors.report(timer);
+
+        for c in &self.lane_closures {
+            if let Err(err) = sim.schedule_lane_closure(c.lane, c.start_time, c.end_time) {
+                timer.warn(format!("Skipping a lane closure: {}", err));
+            }
         }
 
         sim.spawn_all_trips(map, timer, true);
         timer.stop(&format!("Instantiating {}", self.scenario_name));
     }
 
+    // A copy to tweak without touching the original
+    pub fn clone_as(&self, scenario_name: String) -> Scenario {
+        let mut copy = self.clone();
+        copy.scenario_name = scenario_name;
+        copy
+    }
+
     pub fn save(&self) {
         abstutil::save_binary_object("scenarios", &self.map_name, &self.scenario_name, self);
     }
@@ -216,6 +338,7 @@ impl Scenario {
                 goal: OriginDestination::Neighborhood("_everywhere_".to_string()),
                 percent_biking: 0.5,
                 percent_use_transit: 0.5,
+                percent_trucks: 0.0,
             }],
             // If there are no sidewalks/driving lanes at a border, scenario instantiation will
             // just warn and skip them.
@@ -231,9 +354,12 @@ impl Scenario {
                     start_from_border: i.id,
                     goal: OriginDestination::Neighborhood("_everywhere_".to_string()),
                     percent_use_transit: 0.5,
+                    percent_trucks: 0.0,
                 })
                 .collect(),
             individ_trips: Vec::new(),
+            trip_metadata: BTreeMap::new(),
+            lane_closures: Vec::new(),
         };
         for i in map.all_outgoing_borders() {
             s.spawn_over_time.push(SpawnOverTime {
@@ -244,6 +370,7 @@ impl Scenario {
                 goal: OriginDestination::Border(i.id),
                 percent_biking: 0.5,
                 percent_use_transit: 0.5,
+                percent_trucks: 0.0,
             });
         }
         s
@@ -268,9 +395,12 @@ impl Scenario {
                 goal: OriginDestination::Neighborhood("_everywhere_".to_string()),
                 percent_biking: 0.5,
                 percent_use_transit: 0.5,
+                percent_trucks: 0.0,
             }],
             border_spawn_over_time: Vec::new(),
             individ_trips: Vec::new(),
+            trip_metadata: BTreeMap::new(),
+            lane_closures: Vec::new(),
         }
     }
 
@@ -278,8 +408,10 @@ impl Scenario {
         let length = Scenario::rand_dist(rng, MIN_CAR_LENGTH, MAX_CAR_LENGTH);
         VehicleSpec {
             vehicle_type: VehicleType::Car,
+            class: VehicleClass::Car,
             length,
             max_speed: None,
+            occupancy: 1,
         }
     }
 
@@ -291,8 +423,26 @@ impl Scenario {
         ));
         VehicleSpec {
             vehicle_type: VehicleType::Bike,
+            class: VehicleClass::Bike,
             length: BIKE_LENGTH,
             max_speed,
+            occupancy: 1,
+        }
+    }
+
+    pub fn rand_truck(rng: &mut XorShiftRng) -> VehicleSpec {
+        let length = Scenario::rand_dist(rng, MIN_TRUCK_LENGTH, MAX_TRUCK_LENGTH);
+        let max_speed = Some(Scenario::rand_speed(
+            rng,
+            Speed::miles_per_hour(25.0),
+            Speed::miles_per_hour(35.0),
+        ));
+        VehicleSpec {
+            vehicle_type: VehicleType::Car,
+            class: VehicleClass::Truck,
+            length,
+            max_speed,
+            occupancy: 1,
         }
     }
 
@@ -320,13 +470,15 @@ impl Scenario {
 }
 
 impl SpawnOverTime {
-    fn spawn_agent(
+    // If reserved_cars is None, never use parked cars.
+    pub(crate) fn spawn_agent(
         &self,
         rng: &mut XorShiftRng,
         sim: &mut Sim,
-        reserved_cars: &mut HashSet<CarID>,
+        reserved_cars: Option<&mut HashSet<CarID>>,
         neighborhoods: &HashMap<String, FullNeighborhoodInfo>,
         map: &Map,
+        errors: &mut SpawnErrors,
         timer: &mut Timer,
     ) {
         let spawn_time = rand_time(rng, self.start_time, self.stop_time);
@@ -338,19 +490,18 @@ impl SpawnOverTime {
             .unwrap();
 
         // What mode?
-        if let Some(parked_car) = sim
-            .get_parked_cars_by_owner(from_bldg)
-            .into_iter()
-            .find(|p| !reserved_cars.contains(&p.vehicle.id))
-        {
-            if let Some(goal) = self.goal.pick_driving_goal(
+        if let Some(parked_car) = reserved_cars.as_ref().and_then(|reserved| {
+            sim.get_parked_cars_by_owner(from_bldg)
+                .into_iter()
+                .find(|p| !reserved.contains(&p.vehicle.id))
+        }) {
+            if let Some(goal) = errors.check(self.goal.pick_driving_goal(
                 vec![LaneType::Driving],
                 map,
                 &neighborhoods,
                 rng,
-                timer,
-            ) {
-                reserved_cars.insert(parked_car.vehicle.id);
+            )) {
+                reserved_cars.unwrap().insert(parked_car.vehicle.id);
                 let spot = parked_car.spot;
                 sim.schedule_trip(
                     spawn_time,
@@ -366,26 +517,48 @@ impl SpawnOverTime {
             }
         }
 
-        if rng.gen_bool(self.percent_biking) {
-            if let Some(goal) = self.goal.pick_driving_goal(
+        // Deliveries and other freight. The truck appears on the road outside the building.
+        if self.percent_trucks > 0.0 && rng.gen_bool(self.percent_trucks) {
+            if let Some(goal) = errors.check(self.goal.pick_driving_goal(
+                vec![LaneType::Driving],
+                map,
+                &neighborhoods,
+                rng,
+            )) {
+                let vehicle = Scenario::rand_truck(rng);
+                let lane = map.find_driving_lane_near_building(from_bldg);
+                if map.get_l(lane).length() < vehicle.length {
+                    errors.record(SpawnError::LaneTooShort(lane));
+                    return;
+                }
+                sim.schedule_trip(
+                    spawn_time,
+                    TripSpec::CarAppearing {
+                        start_pos: Position::new(lane, vehicle.length),
+                        vehicle_spec: vehicle,
+                        goal,
+                        ped_speed: Scenario::rand_ped_speed(rng),
+                    },
+                    map,
+                );
+                return;
+            }
+        }
+
+        if rng.gen_bool(self.percent_biking * sim.get_conditions().bike_mode_share_factor()) {
+            if let Some(goal) = errors.check(self.goal.pick_driving_goal(
                 vec![LaneType::Driving, LaneType::Biking],
                 map,
                 &neighborhoods,
                 rng,
-                timer,
-            ) {
+            )) {
                 let start_at = map.get_b(from_bldg).sidewalk();
                 // TODO Just start biking on the other side of the street if the sidewalk
                 // is on a one-way. Or at least warn.
-                if map
-                    .get_parent(start_at)
-                    .sidewalk_to_bike(start_at)
-                    .is_some()
-                {
+                if SidewalkSpot::bike_rack(start_at, map).is_some() {
                     let ok = if let DrivingGoal::ParkNear(to_bldg) = goal {
                         let end_at = map.get_b(to_bldg).sidewalk();
-                        map.get_parent(end_at).sidewalk_to_bike(end_at).is_some()
-                            && start_at != end_at
+                        SidewalkSpot::bike_rack(end_at, map).is_some() && start_at != end_at
                     } else {
                         true
                     };
@@ -406,7 +579,7 @@ impl SpawnOverTime {
             }
         }
 
-        if let Some(goal) = self.goal.pick_walking_goal(map, &neighborhoods, rng, timer) {
+        if let Some(goal) = errors.check(self.goal.pick_walking_goal(map, &neighborhoods, rng)) {
             let start_spot = SidewalkSpot::building(from_bldg, map);
             if start_spot == goal {
                 timer.warn("Skipping walking trip between same two buildings".to_string());
@@ -458,25 +631,24 @@ impl BorderSpawnOverTime {
         sim: &mut Sim,
         neighborhoods: &HashMap<String, FullNeighborhoodInfo>,
         map: &Map,
-        timer: &mut Timer,
+        errors: &mut SpawnErrors,
     ) {
         if self.num_peds == 0 {
             return;
         }
 
-        let start = if let Some(s) = SidewalkSpot::start_at_border(self.start_from_border, map) {
-            s
-        } else {
-            timer.warn(format!(
-                "Can't start_at_border for {} without sidewalk",
-                self.start_from_border
-            ));
-            return;
+        let start = match SidewalkSpot::start_at_border(self.start_from_border, map) {
+            Ok(s) => s,
+            Err(err) => {
+                errors.record(err);
+                return;
+            }
         };
 
         for _ in 0..self.num_peds {
             let spawn_time = rand_time(rng, self.start_time, self.stop_time);
-            if let Some(goal) = self.goal.pick_walking_goal(map, &neighborhoods, rng, timer) {
+            if let Some(goal) = errors.check(self.goal.pick_walking_goal(map, &neighborhoods, rng))
+            {
                 if rng.gen_bool(self.percent_use_transit) {
                     // TODO This throws away some work. It also sequentially does expensive
                     // work right here.
@@ -518,7 +690,7 @@ impl BorderSpawnOverTime {
         sim: &mut Sim,
         neighborhoods: &HashMap<String, FullNeighborhoodInfo>,
         map: &Map,
-        timer: &mut Timer,
+        errors: &mut SpawnErrors,
     ) {
         if self.num_cars == 0 {
             return;
@@ -527,31 +699,35 @@ impl BorderSpawnOverTime {
             .get_i(self.start_from_border)
             .get_outgoing_lanes(map, LaneType::Driving);
         if starting_driving_lanes.is_empty() {
-            timer.warn(format!(
-                "Can't start car at border for {}",
-                self.start_from_border
+            errors.record(SpawnError::NoLanesAtBorder(
+                self.start_from_border,
+                vec![LaneType::Driving],
             ));
             return;
         }
 
         let lane_len = map.get_l(starting_driving_lanes[0]).length();
         if lane_len < MAX_CAR_LENGTH {
-            timer.warn(format!(
-                "Skipping {:?} because {} is only {}, too short to spawn cars",
-                self, starting_driving_lanes[0], lane_len
-            ));
+            errors.record(SpawnError::LaneTooShort(starting_driving_lanes[0]));
             return;
         }
         for _ in 0..self.num_cars {
             let spawn_time = rand_time(rng, self.start_time, self.stop_time);
-            if let Some(goal) = self.goal.pick_driving_goal(
+            if let Some(goal) = errors.check(self.goal.pick_driving_goal(
                 vec![LaneType::Driving],
                 map,
                 &neighborhoods,
                 rng,
-                timer,
-            ) {
-                let vehicle = Scenario::rand_car(rng);
+            )) {
+                let vehicle = if self.percent_trucks > 0.0 && rng.gen_bool(self.percent_trucks) {
+                    Scenario::rand_truck(rng)
+                } else {
+                    Scenario::rand_car(rng)
+                };
+                if lane_len < vehicle.length {
+                    errors.record(SpawnError::LaneTooShort(starting_driving_lanes[0]));
+                    continue;
+                }
                 sim.schedule_trip(
                     spawn_time,
                     TripSpec::CarAppearing {
@@ -573,7 +749,7 @@ impl BorderSpawnOverTime {
         sim: &mut Sim,
         neighborhoods: &HashMap<String, FullNeighborhoodInfo>,
         map: &Map,
-        timer: &mut Timer,
+        errors: &mut SpawnErrors,
     ) {
         if self.num_bikes == 0 {
             return;
@@ -589,25 +765,26 @@ impl BorderSpawnOverTime {
                 starting_biking_lanes.push(l);
             }
         }
-        if starting_biking_lanes.is_empty()
-            || map.get_l(starting_biking_lanes[0]).length() < BIKE_LENGTH
-        {
-            timer.warn(format!(
-                "Can't start bike at border for {}",
-                self.start_from_border
+        if starting_biking_lanes.is_empty() {
+            errors.record(SpawnError::NoLanesAtBorder(
+                self.start_from_border,
+                vec![LaneType::Driving, LaneType::Biking],
             ));
             return;
         }
+        if map.get_l(starting_biking_lanes[0]).length() < BIKE_LENGTH {
+            errors.record(SpawnError::LaneTooShort(starting_biking_lanes[0]));
+            return;
+        }
 
         for _ in 0..self.num_bikes {
             let spawn_time = rand_time(rng, self.start_time, self.stop_time);
-            if let Some(goal) = self.goal.pick_driving_goal(
+            if let Some(goal) = errors.check(self.goal.pick_driving_goal(
                 vec![LaneType::Driving, LaneType::Biking],
                 map,
                 &neighborhoods,
                 rng,
-                timer,
-            ) {
+            )) {
                 let bike = Scenario::rand_bike(rng);
                 sim.schedule_trip(
                     spawn_time,
@@ -627,33 +804,36 @@ impl BorderSpawnOverTime {
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum OriginDestination {
     Neighborhood(String),
+    // Only the buildings of one type in the neighborhood
+    BuildingsOfType(String, BuildingType),
     // TODO A serialized Scenario won't last well as the map changes...
     Border(IntersectionID),
 }
 
 impl OriginDestination {
+    pub fn neighborhood(&self) -> Option<&String> {
+        match self {
+            OriginDestination::Neighborhood(ref n)
+            | OriginDestination::BuildingsOfType(ref n, _) => Some(n),
+            OriginDestination::Border(_) => None,
+        }
+    }
+
     fn pick_driving_goal(
         &self,
         lane_types: Vec<LaneType>,
         map: &Map,
         neighborhoods: &HashMap<String, FullNeighborhoodInfo>,
         rng: &mut XorShiftRng,
-        timer: &mut Timer,
-    ) -> Option<DrivingGoal> {
+    ) -> Result<DrivingGoal, SpawnError> {
         match self {
-            OriginDestination::Neighborhood(ref n) => Some(DrivingGoal::ParkNear(
+            OriginDestination::Neighborhood(ref n) => Ok(DrivingGoal::ParkNear(
                 *neighborhoods[n].buildings.choose(rng).unwrap(),
             )),
-            OriginDestination::Border(i) => {
-                let goal = DrivingGoal::end_at_border(*i, lane_types, map);
-                if goal.is_none() {
-                    timer.warn(format!(
-                        "Can't spawn a car ending at border {}; no appropriate lanes there",
-                        i
-                    ));
-                }
-                goal
-            }
+            OriginDestination::BuildingsOfType(ref n, bldg_type) => Ok(DrivingGoal::ParkNear(
+                pick_building_of_type(n, *bldg_type, map, neighborhoods, rng)?,
+            )),
+            OriginDestination::Border(i) => DrivingGoal::end_at_border(*i, lane_types, map),
         }
     }
 
@@ -662,24 +842,40 @@ impl OriginDestination {
         map: &Map,
         neighborhoods: &HashMap<String, FullNeighborhoodInfo>,
         rng: &mut XorShiftRng,
-        timer: &mut Timer,
-    ) -> Option<SidewalkSpot> {
+    ) -> Result<SidewalkSpot, SpawnError> {
         match self {
-            OriginDestination::Neighborhood(ref n) => Some(SidewalkSpot::building(
+            OriginDestination::Neighborhood(ref n) => Ok(SidewalkSpot::building(
                 *neighborhoods[n].buildings.choose(rng).unwrap(),
                 map,
             )),
-            OriginDestination::Border(i) => {
-                let goal = SidewalkSpot::end_at_border(*i, map);
-                if goal.is_none() {
-                    timer.warn(format!("Can't end_at_border for {} without a sidewalk", i));
-                }
-                goal
-            }
+            OriginDestination::BuildingsOfType(ref n, bldg_type) => Ok(SidewalkSpot::building(
+                pick_building_of_type(n, *bldg_type, map, neighborhoods, rng)?,
+                map,
+            )),
+            OriginDestination::Border(i) => SidewalkSpot::end_at_border(*i, map),
         }
     }
 }
 
+fn pick_building_of_type(
+    neighborhood: &str,
+    bldg_type: BuildingType,
+    map: &Map,
+    neighborhoods: &HashMap<String, FullNeighborhoodInfo>,
+    rng: &mut XorShiftRng,
+) -> Result<BuildingID, SpawnError> {
+    let candidates: Vec<BuildingID> = neighborhoods[neighborhood]
+        .buildings
+        .iter()
+        .filter(|b| map.get_b(**b).building_type == bldg_type)
+        .cloned()
+        .collect();
+    candidates
+        .choose(rng)
+        .cloned()
+        .ok_or_else(|| SpawnError::NoBuildingsOfType(neighborhood.to_string(), bldg_type))
+}
+
 fn seed_parked_cars(
     sim: &mut Sim,
     cars_per_building: &WeightedUsizeChoice,
@@ -705,9 +901,19 @@ fn seed_parked_cars(
                 spots.extend(sim.get_free_spots(*lane));
             }
         }
+        open_spots_per_road.insert(r.id, spots);
+    }
+    // Lots belong to the road with their entrance.
+    for lot in map.all_parking_lots() {
+        let r = map.get_parent(lot.driving_pos.lane()).id;
+        if let Some(spots) = open_spots_per_road.get_mut(&r) {
+            spots.extend(sim.get_free_lot_spots(lot.id));
+        }
+    }
+    for id in neighborhoods_roads {
+        let spots = open_spots_per_road.get_mut(id).unwrap();
         total_spots += spots.len();
         spots.shuffle(&mut fork_rng(base_rng));
-        open_spots_per_road.insert(r.id, spots);
     }
 
     let mut new_cars = 0;
@@ -805,8 +1011,21 @@ pub enum SpawnTrip {
         start: Position,
         goal: DrivingGoal,
         is_bike: bool,
+        // Ignored for bikes
+        occupancy: usize,
+        // If this is set, is_bike and occupancy are ignored, and exactly this vehicle is used.
+        // Otherwise one is picked randomly.
+        #[serde(default)]
+        vehicle_spec: Option<VehicleSpec>,
+    },
+    UsingBike {
+        depart: Duration,
+        start: SidewalkSpot,
+        goal: DrivingGoal,
+        // Picked randomly if it's not specified
+        #[serde(default)]
+        vehicle: Option<VehicleSpec>,
     },
-    UsingBike(Duration, SidewalkSpot, DrivingGoal),
     JustWalking(Duration, SidewalkSpot, SidewalkSpot),
     UsingTransit(
         Duration,
@@ -817,3 +1036,52 @@ pub enum SpawnTrip {
         BusStopID,
     ),
 }
+
+impl SpawnTrip {
+    // Only pinned vehicles can be wrong; random ones always fit.
+    fn validate_vehicle(&self) -> Result<(), Error> {
+        match self {
+            SpawnTrip::CarAppearing {
+                vehicle_spec: Some(ref spec),
+                ..
+            } => spec.validate(),
+            SpawnTrip::UsingBike {
+                vehicle: Some(ref spec),
+                ..
+            } => {
+                if spec.vehicle_type != VehicleType::Bike {
+                    return Err(Error::new(format!(
+                        "A bike trip can't use a {:?}",
+                        spec.vehicle_type
+                    )));
+                }
+                spec.validate()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // The buildings where this trip starts and ends, if it does
+    fn building_endpoints(&self) -> (Option<BuildingID>, Option<BuildingID>) {
+        let sidewalk_bldg = |spot: &SidewalkSpot| match spot.connection {
+            SidewalkPOI::Building(b) => Some(b),
+            _ => None,
+        };
+        let driving_bldg = |goal: &DrivingGoal| match goal {
+            DrivingGoal::ParkNear(b) => Some(*b),
+            DrivingGoal::Border(_, _) => None,
+        };
+        match self {
+            SpawnTrip::CarAppearing { ref goal, .. } => (None, driving_bldg(goal)),
+            SpawnTrip::UsingBike {
+                ref start,
+                ref goal,
+                ..
+            } => (sidewalk_bldg(start), driving_bldg(goal)),
+            SpawnTrip::JustWalking(_, ref start, ref goal)
+            | SpawnTrip::UsingTransit(_, ref start, ref goal, _, _, _) => {
+                (sidewalk_bldg(start), sidewalk_bldg(goal))
+            }
+        }
+    }
+}
@@ -1,13 +1,21 @@
+use crate::make::warm_start::WarmStart;
 use crate::{
     CarID, Command, CreateCar, CreatePedestrian, DrivingGoal, ParkingSimState, ParkingSpot,
-    PedestrianID, Scheduler, SidewalkPOI, SidewalkSpot, TripLeg, TripManager, VehicleSpec,
-    VehicleType, MAX_CAR_LENGTH,
+    PedestrianID, Scheduler, SidewalkPOI, SidewalkSpot, TripLeg, TripManager, TripMetadata,
+    VehicleSpec, VehicleType, MAX_CAR_LENGTH,
+};
+use abstutil::{deserialize_btreemap, serialize_btreemap, Timer};
+use geom::{Distance, Duration, Speed, EPSILON_DIST};
+use map_model::{
+    BusRouteID, BusStopID, IntersectionID, IntersectionType, LaneID, Map, PathRequest, Position,
+    SpawnError,
 };
-use abstutil::Timer;
-use geom::{Duration, Speed, EPSILON_DIST};
-use map_model::{BusRouteID, BusStopID, Map, PathRequest, Position};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+// How far past its own length a car can appear and still count as entering from the start of the
+// lane
+const NEAR_LANE_START: Distance = Distance::const_meters(1.0);
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub enum TripSpec {
@@ -48,7 +56,20 @@ pub enum TripSpec {
 #[derive(Serialize, Deserialize, PartialEq)]
 pub struct TripSpawner {
     parked_cars_claimed: BTreeSet<CarID>,
-    trips: Vec<(Duration, Option<PedestrianID>, Option<CarID>, TripSpec)>,
+    trips: Vec<(
+        Duration,
+        Option<PedestrianID>,
+        Option<CarID>,
+        TripSpec,
+        Option<TripMetadata>,
+    )>,
+    // Cars that should've appeared at a border already, but didn't fit on their first lane yet.
+    // They wait in the order they arrived, along with when that was.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    border_queues: BTreeMap<IntersectionID, VecDeque<(Duration, CreateCar)>>,
 }
 
 impl TripSpawner {
@@ -56,6 +77,7 @@ impl TripSpawner {
         TripSpawner {
             parked_cars_claimed: BTreeSet::new(),
             trips: Vec::new(),
+            border_queues: BTreeMap::new(),
         }
     }
 
@@ -65,6 +87,7 @@ impl TripSpawner {
         ped_id: Option<PedestrianID>,
         car_id: Option<CarID>,
         spec: TripSpec,
+        metadata: Option<TripMetadata>,
         map: &Map,
         parking: &ParkingSimState,
     ) {
@@ -143,7 +166,8 @@ impl TripSpawner {
             TripSpec::UsingTransit { .. } => {}
         };
 
-        self.trips.push((start_time, ped_id, car_id, spec));
+        self.trips
+            .push((start_time, ped_id, car_id, spec, metadata));
     }
 
     pub fn spawn_all(
@@ -152,22 +176,28 @@ impl TripSpawner {
         parking: &ParkingSimState,
         trips: &mut TripManager,
         scheduler: &mut Scheduler,
+        min_gap: Distance,
         timer: &mut Timer,
         retry_if_no_room: bool,
     ) {
+        let use_midblock_crossings = trips.use_midblock_crossings;
         let paths = timer.parallelize(
             "calculate paths",
             std::mem::replace(&mut self.trips, Vec::new()),
             |tuple| {
-                let req = tuple.3.get_pathfinding_request(map, parking);
+                let req = tuple
+                    .3
+                    .get_pathfinding_request(map, parking, use_midblock_crossings);
                 (tuple, req.clone(), map.pathfind(req))
             },
         );
+        let mut errors = SpawnErrors::new();
+        let mut warm_start = WarmStart::new(trips.warm_start, min_gap);
         timer.start_iter("spawn trips", paths.len());
-        for ((start_time, ped_id, car_id, spec), req, maybe_path) in paths {
+        for ((start_time, ped_id, car_id, spec, metadata), req, maybe_path) in paths {
             timer.next();
             if maybe_path.is_none() {
-                timer.warn(format!("Some trip couldn't find the first path {}", req));
+                errors.record(SpawnError::NoPath(req.start, req.end));
                 continue;
             }
             let path = maybe_path.unwrap();
@@ -179,6 +209,14 @@ impl TripSpawner {
                     ped_speed,
                 } => {
                     let vehicle = vehicle_spec.make(car_id.unwrap(), None);
+                    let (spawn_at, start_pos, path) = match warm_start
+                        .place_car(start_time, start_pos, path, &vehicle, &req, map)
+                    {
+                        Some(x) => x,
+                        None => {
+                            continue;
+                        }
+                    };
                     let mut legs = vec![TripLeg::Drive(vehicle.clone(), goal.clone())];
                     if let DrivingGoal::ParkNear(b) = goal {
                         legs.push(TripLeg::Walk(
@@ -187,10 +225,10 @@ impl TripSpawner {
                             SidewalkSpot::building(b, map),
                         ));
                     }
-                    let trip = trips.new_trip(start_time, legs);
+                    let trip = trips.new_trip(start_time, legs, metadata);
                     let router = goal.make_router(path, map, vehicle.vehicle_type);
                     scheduler.quick_push(
-                        start_time,
+                        spawn_at,
                         Command::SpawnCar(
                             CreateCar::for_appearing(vehicle, start_pos, router, trip),
                             retry_if_no_room,
@@ -204,13 +242,17 @@ impl TripSpawner {
                     ped_speed,
                 } => {
                     let vehicle = &parking.get_car_at_spot(spot).unwrap().vehicle;
+                    let parking_spot = SidewalkSpot::parking_spot(spot, map, parking);
+                    let (spawn_at, start, path) = warm_start
+                        .place_ped(start_time, start, path, ped_speed, false, &req, map)
+                        .unwrap();
                     match start.connection {
                         SidewalkPOI::Building(b) => assert_eq!(vehicle.owner, Some(b)),
+                        // Warm start put them partway to the car already
+                        SidewalkPOI::SuddenlyAppear => {}
                         _ => unreachable!(),
                     };
 
-                    let parking_spot = SidewalkSpot::parking_spot(spot, map, parking);
-
                     let mut legs = vec![
                         TripLeg::Walk(ped_id.unwrap(), ped_speed, parking_spot.clone()),
                         TripLeg::Drive(vehicle.clone(), goal.clone()),
@@ -225,10 +267,10 @@ impl TripSpawner {
                         }
                         DrivingGoal::Border(_, _) => {}
                     }
-                    let trip = trips.new_trip(start_time, legs);
+                    let trip = trips.new_trip(start_time, legs, metadata);
 
                     scheduler.quick_push(
-                        start_time,
+                        spawn_at,
                         Command::SpawnPed(CreatePedestrian {
                             id: ped_id.unwrap(),
                             speed: ped_speed,
@@ -244,13 +286,22 @@ impl TripSpawner {
                     goal,
                     ped_speed,
                 } => {
+                    let (spawn_at, start, path) = match warm_start
+                        .place_ped(start_time, start, path, ped_speed, true, &req, map)
+                    {
+                        Some(x) => x,
+                        None => {
+                            continue;
+                        }
+                    };
                     let trip = trips.new_trip(
                         start_time,
                         vec![TripLeg::Walk(ped_id.unwrap(), ped_speed, goal.clone())],
+                        metadata,
                     );
 
                     scheduler.quick_push(
-                        start_time,
+                        spawn_at,
                         Command::SpawnPed(CreatePedestrian {
                             id: ped_id.unwrap(),
                             speed: ped_speed,
@@ -268,9 +319,18 @@ impl TripSpawner {
                     ped_speed,
                 } => {
                     let walk_to = SidewalkSpot::bike_rack(start.sidewalk_pos.lane(), map).unwrap();
+                    // Like a parked car, the bike belongs to wherever the rider comes from, so
+                    // they can find it again on the way back.
+                    let owner = match start.connection {
+                        SidewalkPOI::Building(b) => Some(b),
+                        _ => None,
+                    };
+                    let (spawn_at, start, path) = warm_start
+                        .place_ped(start_time, start, path, ped_speed, false, &req, map)
+                        .unwrap();
                     let mut legs = vec![
                         TripLeg::Walk(ped_id.unwrap(), ped_speed, walk_to.clone()),
-                        TripLeg::Drive(vehicle.make(car_id.unwrap(), None), goal.clone()),
+                        TripLeg::Drive(vehicle.make(car_id.unwrap(), owner), goal.clone()),
                     ];
                     match goal {
                         DrivingGoal::ParkNear(b) => {
@@ -282,10 +342,10 @@ impl TripSpawner {
                         }
                         DrivingGoal::Border(_, _) => {}
                     };
-                    let trip = trips.new_trip(start_time, legs);
+                    let trip = trips.new_trip(start_time, legs, metadata);
 
                     scheduler.quick_push(
-                        start_time,
+                        spawn_at,
                         Command::SpawnPed(CreatePedestrian {
                             id: ped_id.unwrap(),
                             speed: ped_speed,
@@ -305,6 +365,9 @@ impl TripSpawner {
                     ped_speed,
                 } => {
                     let walk_to = SidewalkSpot::bus_stop(stop1, map);
+                    let (spawn_at, start, path) = warm_start
+                        .place_ped(start_time, start, path, ped_speed, false, &req, map)
+                        .unwrap();
                     let trip = trips.new_trip(
                         start_time,
                         vec![
@@ -312,10 +375,11 @@ impl TripSpawner {
                             TripLeg::RideBus(ped_id.unwrap(), route, stop2),
                             TripLeg::Walk(ped_id.unwrap(), ped_speed, goal),
                         ],
+                        metadata,
                     );
 
                     scheduler.quick_push(
-                        start_time,
+                        spawn_at,
                         Command::SpawnPed(CreatePedestrian {
                             id: ped_id.unwrap(),
                             speed: ped_speed,
@@ -329,35 +393,95 @@ impl TripSpawner {
             }
         }
 
+        errors.report(timer);
+        if let Some(msg) = warm_start.describe() {
+            timer.note(msg);
+        }
+
         timer.start("finalize spawned trips");
         scheduler.finalize_batch();
         timer.stop("finalize spawned trips");
     }
 
     pub fn is_done(&self) -> bool {
-        self.trips.is_empty()
+        self.trips.is_empty() && self.border_queues.is_empty()
+    }
+
+    // Cars appearing at the start of a lane leading away from a border are subject to
+    // backpressure. Cars appearing further along the lane only need room where they are.
+    pub(crate) fn border_for(create_car: &CreateCar, map: &Map) -> Option<IntersectionID> {
+        if create_car.maybe_parked_car.is_some() {
+            return None;
+        }
+        if create_car.start_dist > create_car.vehicle.length + NEAR_LANE_START {
+            return None;
+        }
+        let i = map.get_l(create_car.router.head().as_lane()).src_i;
+        if map.get_i(i).intersection_type == IntersectionType::Border {
+            Some(i)
+        } else {
+            None
+        }
+    }
+
+    // Anybody still waiting to start on this lane goes first.
+    pub(crate) fn anybody_waiting_for(&self, i: IntersectionID, lane: LaneID) -> bool {
+        self.border_queues
+            .get(&i)
+            .map(|queue| {
+                queue
+                    .iter()
+                    .any(|(_, car)| car.router.head().as_lane() == lane)
+            })
+            .unwrap_or(false)
+    }
+
+    // Returns true if nobody was waiting at this border before, so the caller needs to schedule a
+    // release.
+    pub(crate) fn wait_at_border(
+        &mut self,
+        i: IntersectionID,
+        arrived: Duration,
+        create_car: CreateCar,
+    ) -> bool {
+        let queue = self.border_queues.entry(i).or_insert_with(VecDeque::new);
+        queue.push_back((arrived, create_car));
+        queue.len() == 1
+    }
+
+    // The caller puts back whoever still doesn't fit with wait_at_border, in the same order.
+    pub(crate) fn take_border_queue(
+        &mut self,
+        i: IntersectionID,
+    ) -> VecDeque<(Duration, CreateCar)> {
+        self.border_queues.remove(&i).unwrap_or_else(VecDeque::new)
     }
 }
 
 impl TripSpec {
     // If possible, fixes problems that schedule_trip would hit.
-    pub fn spawn_car_at(pos: Position, map: &Map) -> Option<Position> {
+    pub fn spawn_car_at(pos: Position, map: &Map) -> Result<Position, SpawnError> {
         let len = map.get_l(pos.lane()).length();
         // There's no hope.
         if len <= MAX_CAR_LENGTH {
-            return None;
+            return Err(SpawnError::LaneTooShort(pos.lane()));
         }
 
         if pos.dist_along() < MAX_CAR_LENGTH {
-            Some(Position::new(pos.lane(), MAX_CAR_LENGTH))
+            Ok(Position::new(pos.lane(), MAX_CAR_LENGTH))
         } else if pos.dist_along() == len {
-            Some(Position::new(pos.lane(), pos.dist_along() - EPSILON_DIST))
+            Ok(Position::new(pos.lane(), pos.dist_along() - EPSILON_DIST))
         } else {
-            Some(pos)
+            Ok(pos)
         }
     }
 
-    fn get_pathfinding_request(&self, map: &Map, parking: &ParkingSimState) -> PathRequest {
+    fn get_pathfinding_request(
+        &self,
+        map: &Map,
+        parking: &ParkingSimState,
+        use_midblock_crossings: bool,
+    ) -> PathRequest {
         match self {
             TripSpec::CarAppearing {
                 start_pos,
@@ -368,19 +492,28 @@ impl TripSpec {
                 start: *start_pos,
                 end: goal.goal_pos(map),
                 can_use_bus_lanes: vehicle_spec.vehicle_type == VehicleType::Bus,
+                can_use_hov_lanes: vehicle_spec.can_use_hov_lanes(),
                 can_use_bike_lanes: vehicle_spec.vehicle_type == VehicleType::Bike,
+                can_use_midblock_crossings: false,
+                avoid_lanes: BTreeSet::new(),
             },
             TripSpec::UsingParkedCar { start, spot, .. } => PathRequest {
                 start: start.sidewalk_pos,
                 end: SidewalkSpot::parking_spot(*spot, map, parking).sidewalk_pos,
                 can_use_bike_lanes: false,
                 can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: use_midblock_crossings,
+                avoid_lanes: BTreeSet::new(),
             },
             TripSpec::JustWalking { start, goal, .. } => PathRequest {
                 start: start.sidewalk_pos,
                 end: goal.sidewalk_pos,
                 can_use_bike_lanes: false,
                 can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: use_midblock_crossings,
+                avoid_lanes: BTreeSet::new(),
             },
             TripSpec::UsingBike { start, .. } => PathRequest {
                 start: start.sidewalk_pos,
@@ -389,13 +522,67 @@ impl TripSpec {
                     .sidewalk_pos,
                 can_use_bike_lanes: false,
                 can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: use_midblock_crossings,
+                avoid_lanes: BTreeSet::new(),
             },
             TripSpec::UsingTransit { start, stop1, .. } => PathRequest {
                 start: start.sidewalk_pos,
                 end: SidewalkSpot::bus_stop(*stop1, map).sidewalk_pos,
                 can_use_bike_lanes: false,
                 can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: use_midblock_crossings,
+                avoid_lanes: BTreeSet::new(),
             },
         }
     }
 }
+
+// Instead of warning about every agent that couldn't be spawned, count them up per kind of
+// problem and report a few examples.
+pub struct SpawnErrors {
+    per_kind: BTreeMap<&'static str, (usize, Vec<SpawnError>)>,
+}
+
+impl SpawnErrors {
+    pub fn new() -> SpawnErrors {
+        SpawnErrors {
+            per_kind: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, err: SpawnError) {
+        let (count, examples) = self.per_kind.entry(err.kind()).or_insert((0, Vec::new()));
+        *count += 1;
+        if examples.len() < 3 && !examples.contains(&err) {
+            examples.push(err);
+        }
+    }
+
+    // Records the error, if there is one.
+    pub fn check<T>(&mut self, result: Result<T, SpawnError>) -> Option<T> {
+        match result {
+            Ok(x) => Some(x),
+            Err(err) => {
+                self.record(err);
+                None
+            }
+        }
+    }
+
+    pub fn report(self, timer: &mut Timer) {
+        for (kind, (count, examples)) in self.per_kind {
+            timer.warn(format!(
+                "{} agents couldn't be spawned: {}. For example, {}",
+                count,
+                kind,
+                examples
+                    .into_iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ));
+        }
+    }
+}
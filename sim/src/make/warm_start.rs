@@ -0,0 +1,267 @@
+use crate::{SidewalkSpot, Vehicle};
+use geom::{Distance, Duration, Speed};
+use map_model::{LaneID, Map, Path, PathRequest, PathStep, Position, Traversable};
+use std::collections::BTreeMap;
+
+// Starting a scenario partway through, so the network isn't empty at first. Trips that departed
+// before the warm start time begin wherever free-flow travel would've gotten them by then, instead
+// of at their origins. Only the first leg of a trip is modeled.
+pub struct WarmStart {
+    // When None, nothing changes.
+    at: Option<Duration>,
+    // Copied from DrivingParams
+    min_gap: Distance,
+    // (front, length) of the cars already placed on each lane
+    placed_cars: BTreeMap<LaneID, Vec<(Distance, Distance)>>,
+
+    num_mid_route: usize,
+    num_done: usize,
+    num_from_origin: usize,
+}
+
+enum Placement<T> {
+    // Start at the original spot, just late. Used when nothing better works out.
+    Origin,
+    // Partway along the first leg, with a new path from there
+    MidRoute(T, Path),
+    // The first leg would've already finished
+    Done,
+}
+
+impl WarmStart {
+    pub fn new(at: Option<Duration>, min_gap: Distance) -> WarmStart {
+        WarmStart {
+            at,
+            min_gap,
+            placed_cars: BTreeMap::new(),
+            num_mid_route: 0,
+            num_done: 0,
+            num_from_origin: 0,
+        }
+    }
+
+    // Returns when and where to spawn the car, and its path from there. None if the whole trip
+    // would've already finished.
+    pub fn place_car(
+        &mut self,
+        departed: Duration,
+        start: Position,
+        path: Path,
+        vehicle: &Vehicle,
+        req: &PathRequest,
+        map: &Map,
+    ) -> Option<(Duration, Position, Path)> {
+        let at = match self.at {
+            Some(at) if departed < at => at,
+            _ => {
+                return Some((departed, start, path));
+            }
+        };
+        let placement = match free_flow_progress(
+            &path,
+            req,
+            at - departed,
+            |t| match vehicle.max_speed {
+                Some(s) => t.speed_limit(map).min(s),
+                None => t.speed_limit(map),
+            },
+            map,
+        ) {
+            Some((idx, dist)) => match self.find_room(&path, req, idx, dist, vehicle.length, map) {
+                Some(pos) => match map.pathfind(PathRequest {
+                    start: pos,
+                    ..req.clone()
+                }) {
+                    Some(new_path) => Placement::MidRoute(pos, new_path),
+                    None => Placement::Origin,
+                },
+                None => Placement::Origin,
+            },
+            None => Placement::Done,
+        };
+        self.count(&placement);
+        match placement {
+            Placement::Origin => Some((at, start, path)),
+            Placement::MidRoute(pos, new_path) => Some((at, pos, new_path)),
+            // A car leaving the map would be gone. A car parking would have its driver walking to
+            // a building, but that's short enough to skip.
+            Placement::Done => None,
+        }
+    }
+
+    // Returns when and where to spawn the pedestrian, and their path from there. If this walk is
+    // the whole trip, None means it would've already finished. Otherwise a walk that would've
+    // finished starts right at its end, next to the parked car, bike, or bus stop.
+    pub fn place_ped(
+        &mut self,
+        departed: Duration,
+        start: SidewalkSpot,
+        path: Path,
+        speed: Speed,
+        whole_trip: bool,
+        req: &PathRequest,
+        map: &Map,
+    ) -> Option<(Duration, SidewalkSpot, Path)> {
+        let at = match self.at {
+            Some(at) if departed < at => at,
+            _ => {
+                return Some((departed, start, path));
+            }
+        };
+        let placement = match free_flow_progress(&path, req, at - departed, |_| speed, map) {
+            Some((idx, dist)) => {
+                let steps = path.get_steps();
+                // Sidewalks have room for everybody, but peds can't start on turns or crossings.
+                let pos = match steps[idx] {
+                    PathStep::Lane(l) | PathStep::ContraflowLane(l) => Some(Position::new(l, dist)),
+                    _ => match steps.get(idx + 1) {
+                        Some(PathStep::Lane(l)) => Some(Position::new(*l, Distance::ZERO)),
+                        Some(PathStep::ContraflowLane(l)) => {
+                            Some(Position::new(*l, map.get_l(*l).length()))
+                        }
+                        _ => None,
+                    },
+                };
+                appear_at(pos, req, map)
+            }
+            // Starting everybody who left earlier from their origins at the same moment would be
+            // a burst of pedestrians, so pick up right where the walk ends.
+            None if !whole_trip => appear_at(Some(req.end), req, map),
+            None => Placement::Done,
+        };
+        self.count(&placement);
+        match placement {
+            Placement::MidRoute(spot, new_path) => Some((at, spot, new_path)),
+            Placement::Done => None,
+            Placement::Origin => Some((at, start, path)),
+        }
+    }
+
+    pub fn describe(&self) -> Option<String> {
+        let at = self.at?;
+        Some(format!(
+            "Warm start at {}: {} trips placed partway along, {} already finished, {} starting late \
+             from their origin",
+            at, self.num_mid_route, self.num_done, self.num_from_origin
+        ))
+    }
+
+    fn count<T>(&mut self, placement: &Placement<T>) {
+        match placement {
+            Placement::Origin => self.num_from_origin += 1,
+            Placement::MidRoute(_, _) => self.num_mid_route += 1,
+            Placement::Done => self.num_done += 1,
+        }
+    }
+
+    // Cars can only start on lanes, with their front at least one car length in. Back up behind
+    // any car already placed there. None if there's no room.
+    fn find_room(
+        &mut self,
+        path: &Path,
+        req: &PathRequest,
+        idx: usize,
+        dist: Distance,
+        length: Distance,
+        map: &Map,
+    ) -> Option<Position> {
+        let steps = path.get_steps();
+        let (idx, mut front) = match steps[idx] {
+            PathStep::Lane(_) => (idx, dist),
+            // Just made it onto the next lane
+            _ => (idx + 1, length),
+        };
+        let lane = steps.get(idx)?.as_lane();
+        let end = if idx == steps.len() - 1 {
+            req.end.dist_along()
+        } else {
+            map.get_l(lane).length()
+        };
+        front = front.max(length);
+        if front >= end {
+            return None;
+        }
+
+        let min_gap = self.min_gap;
+        let placed = self.placed_cars.entry(lane).or_insert_with(Vec::new);
+        // Each car takes up its length, plus a following gap behind it.
+        while let Some((other_front, other_length)) = placed
+            .iter()
+            .find(|(f, len)| front - length - min_gap < *f && *f - *len - min_gap < front)
+            .cloned()
+        {
+            front = other_front - other_length - min_gap;
+            if front < length {
+                return None;
+            }
+        }
+        placed.push((front, length));
+        Some(Position::new(lane, front))
+    }
+}
+
+// A pedestrian suddenly appearing here, with a new path from there. Falls back to the origin if
+// there's no spot or no path.
+fn appear_at(pos: Option<Position>, req: &PathRequest, map: &Map) -> Placement<SidewalkSpot> {
+    match pos.and_then(|pos| {
+        map.pathfind(PathRequest {
+            start: pos,
+            ..req.clone()
+        })
+        .map(|new_path| (pos, new_path))
+    }) {
+        Some((pos, new_path)) => Placement::MidRoute(
+            SidewalkSpot::suddenly_appear(pos.lane(), pos.dist_along(), map),
+            new_path,
+        ),
+        None => Placement::Origin,
+    }
+}
+
+// Travel along the path at free-flow speed for some time. Returns the index of the step and the
+// distance along it where that winds up, or None if the path would already be finished.
+fn free_flow_progress<F: Fn(Traversable) -> Speed>(
+    path: &Path,
+    req: &PathRequest,
+    mut time_left: Duration,
+    speed: F,
+    map: &Map,
+) -> Option<(usize, Distance)> {
+    let steps = path.get_steps();
+    for (idx, step) in steps.iter().enumerate() {
+        let t = step.as_traversable();
+        let len = t.length(map);
+        let backwards = match step {
+            PathStep::ContraflowLane(_) => true,
+            _ => false,
+        };
+        let from = if idx == 0 {
+            req.start.dist_along()
+        } else if backwards {
+            len
+        } else {
+            Distance::ZERO
+        };
+        let to = if idx == steps.len() - 1 {
+            req.end.dist_along()
+        } else if backwards {
+            Distance::ZERO
+        } else {
+            len
+        };
+        let dist = if from <= to { to - from } else { from - to };
+
+        let time_needed = dist / speed(t);
+        if time_needed > time_left {
+            let covered = speed(t) * time_left;
+            let dist_along = if from <= to {
+                from + covered
+            } else {
+                from - covered
+            };
+            return Some((idx, dist_along));
+        }
+        time_left -= time_needed;
+    }
+    None
+}
@@ -0,0 +1,112 @@
+use crate::{CarID, BIKE_LENGTH};
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use geom::Distance;
+use map_model::{BikeRackID, BuildingID, Map};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Without any rack this close to their destination, cyclists just stop somewhere nearby instead.
+const MAX_DIST_TO_BIKE_RACK: Distance = Distance::const_meters(300.0);
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct BikeParkingState {
+    // Includes bikes still on their way to the rack
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    occupancy: BTreeMap<BikeRackID, usize>,
+    // Bikes on their way to a rack
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    reserved: BTreeMap<CarID, BikeRackID>,
+    // Where bikes were left, keyed by the building their rider went to and the bike's owner (the
+    // building the rider came from). Only a trip from there back to the owner picks it up again.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    left_near: BTreeMap<(BuildingID, Option<BuildingID>), Vec<BikeRackID>>,
+}
+
+impl BikeParkingState {
+    pub fn new() -> BikeParkingState {
+        BikeParkingState {
+            occupancy: BTreeMap::new(),
+            reserved: BTreeMap::new(),
+            left_near: BTreeMap::new(),
+        }
+    }
+
+    // Claims a space for the bike at the closest rack with room near the building. None when
+    // there's no rack nearby or they're all full.
+    pub fn reserve_rack_near(
+        &mut self,
+        bike: CarID,
+        bldg: BuildingID,
+        map: &Map,
+    ) -> Option<BikeRackID> {
+        let goal = map.get_b(bldg).front_path.sidewalk.pt(map);
+        let rack = map
+            .all_bike_racks()
+            .iter()
+            .filter(|r| r.point.dist_to(goal) <= MAX_DIST_TO_BIKE_RACK)
+            .filter(|r| self.get_occupancy(r.id) < r.capacity)
+            // The bike has to fit on the lane before the place it stops.
+            .filter(|r| match r.driving_pos(map) {
+                Some(pos) => pos.dist_along() >= BIKE_LENGTH,
+                None => false,
+            })
+            .min_by_key(|r| r.point.dist_to(goal))?
+            .id;
+        *self.occupancy.entry(rack).or_insert(0) += 1;
+        self.reserved.insert(bike, rack);
+        Some(rack)
+    }
+
+    // Frees up the space, if the bike had reserved one. The bike won't reach the rack.
+    pub fn cancel_reservation(&mut self, bike: CarID) {
+        if let Some(rack) = self.reserved.remove(&bike) {
+            self.release(rack);
+        }
+    }
+
+    // The bike reached its reserved rack, near this building.
+    pub fn bike_left_near(&mut self, bike: CarID, bldg: BuildingID, owner: Option<BuildingID>) {
+        let rack = self.reserved.remove(&bike).unwrap();
+        self.left_near
+            .entry((bldg, owner))
+            .or_insert_with(Vec::new)
+            .push(rack);
+    }
+
+    // The most recent bike left near this building by this owner, if there is one
+    pub fn parked_bike_near(&self, bldg: BuildingID, owner: BuildingID) -> Option<BikeRackID> {
+        self.left_near.get(&(bldg, Some(owner)))?.last().cloned()
+    }
+
+    // Frees up the space at the rack returned by parked_bike_near.
+    pub fn retrieve_bike_near(&mut self, bldg: BuildingID, owner: BuildingID) {
+        let key = (bldg, Some(owner));
+        let racks = self.left_near.get_mut(&key).unwrap();
+        let rack = racks.pop().unwrap();
+        if racks.is_empty() {
+            self.left_near.remove(&key);
+        }
+        self.release(rack);
+    }
+
+    pub fn get_occupancy(&self, rack: BikeRackID) -> usize {
+        self.occupancy.get(&rack).cloned().unwrap_or(0)
+    }
+
+    fn release(&mut self, rack: BikeRackID) {
+        let cnt = self.occupancy.get_mut(&rack).unwrap();
+        *cnt -= 1;
+        if *cnt == 0 {
+            self.occupancy.remove(&rack);
+        }
+    }
+}
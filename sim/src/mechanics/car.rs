@@ -16,6 +16,9 @@ pub struct Car {
     // In reverse order -- most recently left is first. The sum length of these must be >=
     // vehicle.length.
     pub last_steps: VecDeque<Traversable>,
+    // When the car entered the lane it's on now. None for the lane it started on, or while on a
+    // turn.
+    pub lane_entered_at: Option<Duration>,
 }
 
 impl Car {
@@ -48,10 +51,35 @@ impl Car {
         if let Some(s) = self.vehicle.max_speed {
             speed = speed.min(s);
         }
-        let dt = (dist_int.end - dist_int.start) / speed;
+        let dist = dist_int.end - dist_int.start;
+        let dt = match (self.starting_from_rest(), self.vehicle.class.max_accel()) {
+            (true, Some(accel)) => {
+                // Accelerate at a constant rate until hitting the top speed, then cruise.
+                let v = speed.inner_meters_per_second();
+                let d = dist.inner_meters();
+                if d >= v * v / (2.0 * accel) {
+                    Duration::seconds(d / v + v / (2.0 * accel))
+                } else {
+                    Duration::seconds((2.0 * d / accel).sqrt())
+                }
+            }
+            _ => dist / speed,
+        };
         CarState::Crossing(TimeInterval::new(start_time, start_time + dt), dist_int)
     }
 
+    // Every move between a lane and a turn passes through WaitingToAdvance, so this treats each
+    // intersection like a stop. That's roughly right in the city.
+    fn starting_from_rest(&self) -> bool {
+        match self.state {
+            CarState::Queued
+            | CarState::WaitingToAdvance
+            | CarState::Unparking(_, _)
+            | CarState::Idling(_, _) => true,
+            CarState::Crossing(_, _) | CarState::Parking(_, _, _) => false,
+        }
+    }
+
     pub fn get_draw_car(&self, front: Distance, time: Duration, map: &Map) -> DrawCarInput {
         assert!(front >= Distance::ZERO);
         let raw_body = if front >= self.vehicle.length {
@@ -122,6 +150,7 @@ impl Car {
                 CarState::Idling(_, _) => CarStatus::Parked,
             },
             vehicle_type: self.vehicle.vehicle_type,
+            class: self.vehicle.class,
             on: self.router.head(),
             body,
         }
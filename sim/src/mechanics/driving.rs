@@ -1,23 +1,26 @@
 use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::queue::Queue;
 use crate::{
-    ActionAtEnd, AgentID, CarID, Command, CreateCar, DistanceInterval, DrawCarInput,
-    IntersectionSimState, ParkedCar, ParkingSimState, Scheduler, TimeInterval, TransitSimState,
-    TripManager, TripPositions, VehicleType, WalkingSimState, FOLLOWING_DISTANCE,
+    ActionAtEnd, AgentID, CarID, Command, Conditions, CreateCar, DistanceInterval, DrawCarInput,
+    DrivingParams, Event, IntersectionSimState, ParkedCar, ParkingSimState, Scheduler,
+    TimeInterval, TransitSimState, TravelStats, TripManager, TripPositions, Vehicle, VehicleType,
+    WalkingSimState,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use geom::{Distance, Duration, PolyLine, Pt2D};
-use map_model::{BuildingID, IntersectionID, LaneID, Map, Path, Traversable};
+use map_model::{BuildingID, LaneID, Map, Path, Position, Traversable};
 use petgraph::graph::{Graph, NodeIndex};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 
 const TIME_TO_UNPARK: Duration = Duration::const_seconds(10.0);
 const TIME_TO_PARK: Duration = Duration::const_seconds(15.0);
-const TIME_TO_WAIT_AT_STOP: Duration = Duration::const_seconds(10.0);
 
 // TODO Do something else.
 pub(crate) const BLIND_RETRY_TO_CREEP_FORWARDS: Duration = Duration::const_seconds(0.1);
+// Nothing tells a car waiting for room ahead when that room opens up, so it checks again this
+// often.
+const RETRY_WAITING_FOR_ROOM: Duration = Duration::const_seconds(1.0);
 pub(crate) const BLIND_RETRY_TO_REACH_END_DIST: Duration = Duration::const_seconds(5.0);
 
 #[derive(Serialize, Deserialize, PartialEq)]
@@ -32,6 +35,12 @@ pub struct DrivingSimState {
         deserialize_with = "deserialize_btreemap"
     )]
     queues: BTreeMap<Traversable, Queue>,
+    pub(crate) conditions: Conditions,
+    params: DrivingParams,
+    // The last time any car advanced to a new lane or turn, or finished
+    last_movement: Duration,
+
+    events: Vec<Event>,
 }
 
 impl DrivingSimState {
@@ -39,17 +48,21 @@ impl DrivingSimState {
         let mut sim = DrivingSimState {
             cars: BTreeMap::new(),
             queues: BTreeMap::new(),
+            conditions: Conditions::Dry,
+            params: DrivingParams::default(),
+            last_movement: Duration::ZERO,
+            events: Vec::new(),
         };
 
         for l in map.all_lanes() {
             if l.lane_type.is_for_moving_vehicles() {
-                let q = Queue::new(Traversable::Lane(l.id), map);
+                let q = Queue::new(Traversable::Lane(l.id), map, sim.params.min_gap);
                 sim.queues.insert(q.id, q);
             }
         }
         for t in map.all_turns().values() {
             if !t.between_sidewalks() {
-                let q = Queue::new(Traversable::Turn(t.id), map);
+                let q = Queue::new(Traversable::Turn(t.id), map, sim.params.min_gap);
                 sim.queues.insert(q.id, q);
             }
         }
@@ -57,6 +70,19 @@ impl DrivingSimState {
         sim
     }
 
+    pub fn get_params(&self) -> DrivingParams {
+        self.params
+    }
+
+    // Changing the minimum gap while cars are queued would leave them too close together.
+    pub fn set_params(&mut self, params: DrivingParams) {
+        assert!(self.cars.is_empty());
+        self.params = params;
+        for q in self.queues.values_mut() {
+            q.min_gap = params.min_gap;
+        }
+    }
+
     // True if it worked
     pub fn start_car_on_lane(
         &mut self,
@@ -66,6 +92,7 @@ impl DrivingSimState {
         intersections: &IntersectionSimState,
         parking: &ParkingSimState,
         scheduler: &mut Scheduler,
+        stats: &mut TravelStats,
     ) -> bool {
         let first_lane = params.router.head().as_lane();
 
@@ -86,6 +113,7 @@ impl DrivingSimState {
                 state: CarState::Queued,
                 last_steps: VecDeque::new(),
                 trip: params.trip,
+                lane_entered_at: None,
             };
             if params.maybe_parked_car.is_some() {
                 car.state = CarState::Unparking(
@@ -115,11 +143,57 @@ impl DrivingSimState {
                 .cars
                 .insert(idx, car.vehicle.id);
             self.cars.insert(car.vehicle.id, car);
+            stats.agent_entered(Traversable::Lane(first_lane));
+            self.last_movement = time;
             return true;
         }
         false
     }
 
+    // How much of the beginning of the lane is free, from its start up to the usual following gap
+    // behind the last car on it. A car appearing at the start needs at least its own length.
+    pub fn headroom_at_start(
+        &self,
+        lane: LaneID,
+        time: Duration,
+        intersections: &IntersectionSimState,
+        map: &Map,
+    ) -> Distance {
+        if !intersections.nobody_headed_towards(lane, map.get_l(lane).src_i) {
+            return Distance::ZERO;
+        }
+        self.room_at_start(Traversable::Lane(lane), time)
+            .max(Distance::ZERO)
+    }
+
+    // From the start of the lane or turn up to the usual following gap behind the last car on it.
+    // Negative when that car is still partly hanging off the start.
+    fn room_at_start(&self, on: Traversable, time: Duration) -> Distance {
+        let queue = &self.queues[&on];
+        match queue
+            .get_car_positions(time, &self.cars, &self.queues)
+            .last()
+        {
+            Some((id, front)) => *front - self.cars[id].vehicle.length - queue.min_gap,
+            // Somebody's back is still at the end, and nothing can go in front of them yet.
+            None if queue.laggy_head.is_some() => Distance::ZERO,
+            None => queue.geom_len,
+        }
+    }
+
+    // Can the car move onto the next step of its path right now? Cars don't start a turn unless
+    // they'll fit entirely on the lane after it (or it's empty, when it's too short for them), so
+    // they never block the intersection.
+    fn room_to_advance(&self, vehicle: &Vehicle, next: Traversable, time: Duration) -> bool {
+        match next {
+            Traversable::Turn(t) => {
+                let lane = Traversable::Lane(t.dst);
+                self.room_at_start(lane, time) >= vehicle.length.min(self.queues[&lane].geom_len)
+            }
+            _ => self.room_at_start(next, time) > Distance::ZERO,
+        }
+    }
+
     pub fn update_car(
         &mut self,
         id: CarID,
@@ -131,6 +205,7 @@ impl DrivingSimState {
         scheduler: &mut Scheduler,
         transit: &mut TransitSimState,
         walking: &mut WalkingSimState,
+        stats: &mut TravelStats,
     ) {
         // State transitions for this car:
         //
@@ -174,6 +249,7 @@ impl DrivingSimState {
                 intersections,
                 transit,
                 scheduler,
+                stats,
             );
             self.cars.insert(id, car);
         }
@@ -219,6 +295,7 @@ impl DrivingSimState {
         intersections: &mut IntersectionSimState,
         transit: &mut TransitSimState,
         scheduler: &mut Scheduler,
+        stats: &mut TravelStats,
     ) -> bool {
         match car.state {
             CarState::Crossing(_, _) => {
@@ -246,7 +323,15 @@ impl DrivingSimState {
                 car.state = car.crossing_state(front, time, map);
                 scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
             }
-            CarState::Idling(dist, _) => {
+            CarState::Idling(dist, TimeInterval { start, .. }) => {
+                // More riders may have boarded while the bus was waiting.
+                let depart = start + transit.dwell_time(car.vehicle.id);
+                if depart > time {
+                    car.state = CarState::Idling(dist, TimeInterval::new(start, depart));
+                    scheduler.push(depart, Command::UpdateCar(car.vehicle.id));
+                    return false;
+                }
+
                 car.router = transit.bus_departed_from_stop(car.vehicle.id);
                 car.state = car.crossing_state(dist, time, map);
                 scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
@@ -267,7 +352,7 @@ impl DrivingSimState {
                             if !follower.router.last_step() {
                                 follower.state = follower.crossing_state(
                                     // Since the follower was Queued, this must be where they are.
-                                    dist - car.vehicle.length - FOLLOWING_DISTANCE,
+                                    dist - car.vehicle.length - self.params.min_gap,
                                     time,
                                     map,
                                 );
@@ -294,6 +379,14 @@ impl DrivingSimState {
                 let goto = car.router.next();
                 assert!(from != goto);
 
+                if !self.room_to_advance(&car.vehicle, goto, time) {
+                    scheduler.update(
+                        Command::UpdateCar(car.vehicle.id),
+                        time + RETRY_WAITING_FOR_ROOM,
+                    );
+                    return false;
+                }
+
                 if let Traversable::Turn(t) = goto {
                     if !intersections.maybe_start_turn(
                         AgentID::Car(car.vehicle.id),
@@ -317,22 +410,33 @@ impl DrivingSimState {
                 // way, until laggy_head is None.
 
                 let last_step = car.router.advance(&car.vehicle, parking, map);
+                stats.agent_entered(goto);
+                if let (Traversable::Lane(l), Some(entered_at)) = (from, car.lane_entered_at) {
+                    stats.lane_traversed(l, time - entered_at);
+                }
+                car.lane_entered_at = match goto {
+                    Traversable::Lane(_) => Some(time),
+                    Traversable::Turn(_) => None,
+                };
+                self.last_movement = time;
                 car.state = car.crossing_state(Distance::ZERO, time, map);
                 scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
 
                 car.last_steps.push_front(last_step);
-                if goto.length(map) >= car.vehicle.length + FOLLOWING_DISTANCE {
+                if goto.length(map) >= car.vehicle.length + self.params.min_gap {
                     // Optimistically assume we'll be out of the way ASAP.
                     scheduler.push(
                         car.crossing_state_with_end_dist(
                             DistanceInterval::new_driving(
                                 Distance::ZERO,
-                                car.vehicle.length + FOLLOWING_DISTANCE,
+                                car.vehicle.length + self.params.min_gap,
                             ),
                             time,
                             map,
                         )
-                        .get_end_time(),
+                        .get_end_time()
+                            + self.conditions.following_headway()
+                            + self.params.headway,
                         Command::UpdateLaggyHead(car.vehicle.id),
                     );
                 }
@@ -406,11 +510,18 @@ impl DrivingSimState {
                             .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                         return true;
                     }
-                    Some(ActionAtEnd::StopBiking(bike_rack)) => {
-                        trips.bike_reached_end(time, car.vehicle.id, bike_rack, map, scheduler);
+                    Some(ActionAtEnd::StopBiking(bike_rack, rack_id)) => {
+                        trips.bike_reached_end(
+                            time,
+                            car.vehicle.id,
+                            bike_rack,
+                            rack_id,
+                            map,
+                            scheduler,
+                        );
                     }
                     Some(ActionAtEnd::BusAtStop) => {
-                        transit.bus_arrived_at_stop(
+                        let dwell = transit.bus_arrived_at_stop(
                             time,
                             car.vehicle.id,
                             trips,
@@ -418,10 +529,8 @@ impl DrivingSimState {
                             scheduler,
                             map,
                         );
-                        car.state = CarState::Idling(
-                            our_dist,
-                            TimeInterval::new(time, time + TIME_TO_WAIT_AT_STOP),
-                        );
+                        car.state =
+                            CarState::Idling(our_dist, TimeInterval::new(time, time + dwell));
                         scheduler
                             .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                         return true;
@@ -473,6 +582,7 @@ impl DrivingSimState {
                 .unwrap(),
             car.vehicle.id
         );
+        self.last_movement = time;
 
         // We might be vanishing while partly clipping into other stuff.
         self.clear_last_steps(time, car, intersections, scheduler);
@@ -525,7 +635,7 @@ impl DrivingSimState {
         );
         // This car must be the tail.
         assert_eq!(id, dists.last().unwrap().0);
-        let our_len = self.cars[&id].vehicle.length + FOLLOWING_DISTANCE;
+        let our_len = self.cars[&id].vehicle.length + self.params.min_gap;
 
         // Have we made it far enough yet? Unfortunately, we have some math imprecision issues...
         {
@@ -664,6 +774,26 @@ impl DrivingSimState {
         }
     }
 
+    pub fn populate_agent_positions(
+        &self,
+        time: Duration,
+        positions: &mut BTreeMap<AgentID, (Traversable, Distance)>,
+    ) {
+        for queue in self.queues.values() {
+            if queue.cars.is_empty() {
+                continue;
+            }
+
+            for (car, dist) in queue.get_car_positions(time, &self.cars, &self.queues) {
+                positions.insert(AgentID::Car(car), (queue.id, dist));
+            }
+        }
+    }
+
+    pub fn get_vehicle(&self, id: CarID) -> Option<&Vehicle> {
+        self.cars.get(&id).map(|car| &car.vehicle)
+    }
+
     pub fn get_all_draw_cars(&self, time: Duration, map: &Map) -> Vec<DrawCarInput> {
         let mut result = Vec::new();
         for queue in self.queues.values() {
@@ -693,6 +823,29 @@ impl DrivingSimState {
         }
     }
 
+    // Includes a car whose front has already moved on, but whose back is still here.
+    pub fn get_cars_on(&self, on: Traversable) -> Vec<CarID> {
+        match self.queues.get(&on) {
+            Some(q) => q.cars.iter().cloned().chain(q.laggy_head).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // Is any vehicle on this lane passing the spot, or within the gap before it? Vehicles still on
+    // the previous lane or turn aren't considered.
+    pub fn is_lane_clear_near(&self, time: Duration, pos: Position, gap: Distance) -> bool {
+        match self.queues.get(&Traversable::Lane(pos.lane())) {
+            Some(q) => q
+                .get_car_positions(time, &self.cars, &self.queues)
+                .into_iter()
+                .all(|(car, front)| {
+                    front < pos.dist_along() - gap
+                        || front - self.cars[&car].vehicle.length > pos.dist_along()
+                }),
+            None => true,
+        }
+    }
+
     pub fn debug_car(&self, id: CarID) {
         if let Some(ref car) = self.cars.get(&id) {
             println!("{}", abstutil::to_json(car));
@@ -711,6 +864,17 @@ impl DrivingSimState {
         ])
     }
 
+    // Returns the cars that changed their route
+    pub fn reroute_around(&mut self, closed: &BTreeSet<LaneID>, map: &Map) -> Vec<CarID> {
+        let mut rerouted = Vec::new();
+        for car in self.cars.values_mut() {
+            if car.router.reroute_around(closed, &car.vehicle, map) {
+                rerouted.push(car.vehicle.id);
+            }
+        }
+        rerouted
+    }
+
     pub fn get_path(&self, id: CarID) -> Option<&Path> {
         let car = self.cars.get(&id)?;
         Some(car.router.get_path())
@@ -738,72 +902,124 @@ impl DrivingSimState {
         car.vehicle.owner
     }
 
-    // This ignores capacity, pedestrians, and traffic signal overtime. So it should yield false
-    // positives (thinks there's gridlock, when there isn't) but never false negatives.
-    pub fn detect_gridlock(&self, map: &Map) -> bool {
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-        enum Node {
-            Lane(LaneID),
-            Intersection(IntersectionID),
+    // If any cars are on the road, when did one of them last make progress?
+    pub fn stalled_since(&self) -> Option<Duration> {
+        if self.cars.is_empty() {
+            None
+        } else {
+            Some(self.last_movement)
         }
+    }
 
-        // TODO petgraph wrapper to map nodes -> node index and handle duplicate nodes
-        let mut deps: Graph<Node, ()> = Graph::new();
-        let mut nodes: HashMap<Node, NodeIndex<u32>> = HashMap::new();
+    // Every car waits for its leader in the same queue, and the head of a queue waits for the
+    // last car wherever it's headed next, when there's no room there. If these dependencies form
+    // a cycle, nobody in it can move. Cars waiting at a red light or stop sign aren't stuck on
+    // anybody in particular, so they don't count. This ignores pedestrians, so only trust it
+    // when nothing's moved for a while.
+    pub fn detect_gridlock(&mut self, time: Duration) -> Option<Vec<AgentID>> {
+        let mut deps: Graph<CarID, ()> = Graph::new();
+        let mut nodes: HashMap<CarID, NodeIndex<u32>> = HashMap::new();
+        for id in self.cars.keys() {
+            nodes.insert(*id, deps.add_node(*id));
+        }
 
         for queue in self.queues.values() {
-            if queue.cars.is_empty() {
-                continue;
+            for (leader, follower) in queue.cars.iter().zip(queue.cars.iter().skip(1)) {
+                deps.add_edge(nodes[follower], nodes[leader], ());
             }
-            match queue.id {
-                Traversable::Lane(l) => {
-                    let lane_id = Node::Lane(l);
-                    // Assume lead car will proceed to the intersection
-                    nodes.insert(lane_id, deps.add_node(lane_id));
-
-                    let int_id = Node::Intersection(map.get_l(l).dst_i);
-                    if !nodes.contains_key(&int_id) {
-                        nodes.insert(int_id, deps.add_node(int_id));
-                    }
 
-                    deps.add_edge(nodes[&lane_id], nodes[&int_id], ());
+            let head = match queue.cars.front() {
+                Some(id) => *id,
+                None => {
+                    continue;
                 }
-                Traversable::Turn(t) => {
-                    let int_id = Node::Intersection(t.parent);
-                    if !nodes.contains_key(&int_id) {
-                        nodes.insert(int_id, deps.add_node(int_id));
+            };
+            if let Some(laggy_head) = queue.laggy_head {
+                deps.add_edge(nodes[&head], nodes[&laggy_head], ());
+            } else if let Some(next) = self.cars[&head].router.maybe_next() {
+                if !self.room_to_advance(&self.cars[&head].vehicle, next, time) {
+                    let full = &self.queues[&match next {
+                        Traversable::Turn(t) => Traversable::Lane(t.dst),
+                        other => other,
+                    }];
+                    if let Some(id) = full.cars.back().or(full.laggy_head.as_ref()) {
+                        deps.add_edge(nodes[&head], nodes[id], ());
                     }
+                }
+            }
+        }
 
-                    let target_lane_id = Node::Lane(t.dst);
-                    if !nodes.contains_key(&target_lane_id) {
-                        nodes.insert(target_lane_id, deps.add_node(target_lane_id));
-                    }
+        let cycle = petgraph::algo::tarjan_scc(&deps)
+            .into_iter()
+            .find(|component| component.len() > 1)?;
+        let mut agents: Vec<AgentID> = cycle.into_iter().map(|n| AgentID::Car(deps[n])).collect();
+        agents.sort();
+        self.events.push(Event::GridlockDetected(agents.clone()));
+        Some(agents)
+    }
+
+    // Immediately remove a car, wherever it is. The caller has to deal with its trip.
+    pub fn delete_car(
+        &mut self,
+        id: CarID,
+        time: Duration,
+        map: &Map,
+        intersections: &mut IntersectionSimState,
+        scheduler: &mut Scheduler,
+    ) {
+        let dists = self.queues[&self.cars[&id].router.head()].get_car_positions(
+            time,
+            &self.cars,
+            &self.queues,
+        );
+        let idx = dists.iter().position(|(c, _)| *c == id).unwrap();
 
-                    deps.add_edge(nodes[&int_id], nodes[&target_lane_id], ());
+        let mut car = self.cars.remove(&id).unwrap();
+        assert_eq!(
+            self.queues
+                .get_mut(&car.router.head())
+                .unwrap()
+                .cars
+                .remove(idx)
+                .unwrap(),
+            id
+        );
+        self.last_movement = time;
+        scheduler.cancel(Command::UpdateCar(id));
+        scheduler.cancel(Command::UpdateLaggyHead(id));
+
+        match car.router.head() {
+            Traversable::Turn(t) => {
+                intersections.turn_finished(time, AgentID::Car(id), t, scheduler);
+            }
+            Traversable::Lane(_) => {
+                if let Some(Traversable::Turn(t)) = car.router.maybe_next() {
+                    intersections.cancel_request(time, AgentID::Car(id), t, scheduler);
                 }
             }
+            Traversable::MidblockCrossing(_) => unreachable!(),
         }
+        self.clear_last_steps(time, &mut car, intersections, scheduler);
 
-        if let Err(cycle) = petgraph::algo::toposort(&deps, None) {
-            // Super lame, we only get one node in the cycle, and A* won't attempt to look for
-            // loops.
-            for start in deps.neighbors(cycle.node_id()) {
-                if let Some((_, raw_nodes)) =
-                    petgraph::algo::astar(&deps, start, |n| n == cycle.node_id(), |_| 0, |_| 0)
-                {
-                    println!("Gridlock involving:");
-                    for n in raw_nodes {
-                        println!("- {:?}", deps[n]);
-                    }
-                    return true;
+        // Let the follower move up.
+        if let Some((follower_id, follower_dist)) = dists.get(idx + 1) {
+            let follower = self.cars.get_mut(follower_id).unwrap();
+            match follower.state {
+                CarState::Queued | CarState::Crossing(_, _) => {
+                    follower.state = follower.crossing_state(*follower_dist, time, map);
+                    scheduler.update(
+                        Command::UpdateCar(*follower_id),
+                        follower.state.get_end_time(),
+                    );
                 }
+                CarState::Unparking(_, _) | CarState::Parking(_, _, _) | CarState::Idling(_, _) => {
+                }
+                CarState::WaitingToAdvance => unreachable!(),
             }
-            println!(
-                "Gridlock involving {:?}, but couldn't find the cycle!",
-                cycle.node_id()
-            );
-            return true;
         }
-        false
+    }
+
+    pub fn collect_events(&mut self) -> Vec<Event> {
+        self.events.drain(..).collect()
     }
 }
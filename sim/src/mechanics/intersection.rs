@@ -2,8 +2,8 @@ use crate::{AgentID, Command, Scheduler};
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use geom::Duration;
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, IntersectionID, IntersectionType, LaneID, Map, TurnID,
-    TurnPriority,
+    ControlStopSign, ControlTrafficSignal, Cycle, IntersectionID, IntersectionType, LaneID, Map,
+    SignalControl, TurnID, TurnPriority, TurnType,
 };
 use serde_derive::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashSet};
@@ -13,6 +13,7 @@ const WAIT_AT_STOP_SIGN: Duration = Duration::const_seconds(0.5);
 #[derive(Serialize, Deserialize, PartialEq)]
 pub struct IntersectionSimState {
     state: BTreeMap<IntersectionID, State>,
+    pub(crate) right_turn_on_red: bool,
 }
 
 #[derive(Serialize, Deserialize, PartialEq)]
@@ -25,12 +26,25 @@ struct State {
         deserialize_with = "deserialize_btreemap"
     )]
     waiting: BTreeMap<Request, Duration>,
+    // Every traffic signal has this, since edits can switch a signal to actuated control. Only
+    // used while the signal is actuated.
+    actuated: Option<ActuatedState>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+struct ActuatedState {
+    cycle: usize,
+    // When the cycle's green started. During the all-red clearance before it, this is still to come.
+    cycle_started: Duration,
+    // The last time somebody started waiting for a turn allowed in the current cycle
+    last_arrival: Duration,
 }
 
 impl IntersectionSimState {
     pub fn new(map: &Map, scheduler: &mut Scheduler) -> IntersectionSimState {
         let mut sim = IntersectionSimState {
             state: BTreeMap::new(),
+            right_turn_on_red: false,
         };
         for i in map.all_intersections() {
             sim.state.insert(
@@ -39,6 +53,15 @@ impl IntersectionSimState {
                     id: i.id,
                     accepted: BTreeSet::new(),
                     waiting: BTreeMap::new(),
+                    actuated: if i.intersection_type == IntersectionType::TrafficSignal {
+                        Some(ActuatedState {
+                            cycle: 0,
+                            cycle_started: Duration::ZERO,
+                            last_arrival: Duration::ZERO,
+                        })
+                    } else {
+                        None
+                    },
                 },
             );
             if i.intersection_type == IntersectionType::TrafficSignal {
@@ -77,26 +100,50 @@ impl IntersectionSimState {
         }
     }
 
+    // The agent was waiting to start this turn, but got removed from the simulation instead.
+    pub fn cancel_request(
+        &mut self,
+        now: Duration,
+        agent: AgentID,
+        turn: TurnID,
+        scheduler: &mut Scheduler,
+    ) {
+        let state = self.state.get_mut(&turn.parent).unwrap();
+        if state.waiting.remove(&Request { agent, turn }).is_none() {
+            return;
+        }
+        // At a stop sign, somebody might've been waiting for this agent to go first.
+        for req in state.waiting.keys() {
+            scheduler.update(Command::update_agent(req.agent), now);
+        }
+    }
+
     // This is only triggered for traffic signals.
     pub fn update_intersection(
-        &self,
+        &mut self,
         now: Duration,
         id: IntersectionID,
         map: &Map,
         scheduler: &mut Scheduler,
     ) {
-        let state = &self.state[&id];
-        let (_, remaining) = map
-            .get_traffic_signal(id)
-            .current_cycle_and_remaining_time(now);
+        let state = self.state.get_mut(&id).unwrap();
+        let signal = map.get_traffic_signal(id);
+        let (cycle_changed, next_update) = match signal.control {
+            SignalControl::Actuated { gap_out } if state.actuated.is_some() => {
+                state.update_actuated_signal(signal, gap_out, now)
+            }
+            _ => (true, signal.current_cycle_and_remaining_time(now).1),
+        };
 
-        // TODO Wake up everyone, for now.
-        // TODO Use update in case turn_finished scheduled an event for them already.
-        for req in state.waiting.keys() {
-            scheduler.update(Command::update_agent(req.agent), now);
+        if cycle_changed {
+            // TODO Wake up everyone, for now.
+            // TODO Use update in case turn_finished scheduled an event for them already.
+            for req in state.waiting.keys() {
+                scheduler.update(Command::update_agent(req.agent), now);
+            }
         }
 
-        scheduler.push(now + remaining, Command::UpdateIntersection(id));
+        scheduler.push(now + next_update, Command::UpdateIntersection(id));
     }
 
     // For cars: The head car calls this when they're at the end of the lane WaitingToAdvance. If
@@ -116,10 +163,14 @@ impl IntersectionSimState {
     ) -> bool {
         let req = Request { agent, turn };
         let state = self.state.get_mut(&turn.parent).unwrap();
+        let new_request = !state.waiting.contains_key(&req);
         state.waiting.entry(req.clone()).or_insert(now);
 
         let allowed = if let Some(ref signal) = map.maybe_get_traffic_signal(state.id) {
-            state.traffic_signal_policy(signal, &req, now, map)
+            if new_request {
+                state.note_arrival(signal, turn, now);
+            }
+            state.traffic_signal_policy(signal, &req, now, self.right_turn_on_red, map, scheduler)
         } else if let Some(ref sign) = map.maybe_get_stop_sign(state.id) {
             state.stop_sign_policy(sign, &req, now, map, scheduler)
         } else {
@@ -156,13 +207,23 @@ impl IntersectionSimState {
             .collect()
     }
 
+    // The cycle a traffic signal is in, and how long until it must change. Actuated signals might
+    // change sooner. None means the all-red clearance between cycles.
+    pub fn current_signal_cycle<'a>(
+        &self,
+        time: Duration,
+        signal: &'a ControlTrafficSignal,
+    ) -> (Option<&'a Cycle>, Duration) {
+        self.state[&signal.id].current_signal_cycle(signal, time)
+    }
+
     pub fn is_in_overtime(&self, time: Duration, id: IntersectionID, map: &Map) -> bool {
         if let Some(ref signal) = map.maybe_get_traffic_signal(id) {
-            let (cycle, _) = signal.current_cycle_and_remaining_time(time);
+            let (cycle, _) = self.state[&id].current_signal_cycle(signal, time);
             self.state[&id]
                 .accepted
                 .iter()
-                .any(|req| cycle.get_priority(req.turn) == TurnPriority::Banned)
+                .any(|req| priority_in(cycle, req.turn) == TurnPriority::Banned)
         } else {
             false
         }
@@ -170,6 +231,101 @@ impl IntersectionSimState {
 }
 
 impl State {
+    fn current_signal_cycle<'a>(
+        &self,
+        signal: &'a ControlTrafficSignal,
+        time: Duration,
+    ) -> (Option<&'a Cycle>, Duration) {
+        match (&signal.control, self.actuated) {
+            (SignalControl::Actuated { .. }, Some(actuated)) => {
+                if time < actuated.cycle_started {
+                    return (None, actuated.cycle_started - time);
+                }
+                let cycle = &signal.cycles[actuated.cycle % signal.cycles.len()];
+                let max_end = actuated.cycle_started + cycle.max_green;
+                (
+                    Some(cycle),
+                    if max_end > time {
+                        max_end - time
+                    } else {
+                        Duration::ZERO
+                    },
+                )
+            }
+            _ => signal.current_cycle_and_remaining_time(time),
+        }
+    }
+
+    fn note_arrival(&mut self, signal: &ControlTrafficSignal, turn: TurnID, now: Duration) {
+        let (cycle, _) = self.current_signal_cycle(signal, now);
+        if priority_in(cycle, turn) != TurnPriority::Banned {
+            if let Some(ref mut actuated) = self.actuated {
+                actuated.last_arrival = now;
+            }
+        }
+    }
+
+    // Decides whether an actuated signal stays in its current cycle or moves on. Returns true if
+    // the cycle changed, and how long until the next decision.
+    fn update_actuated_signal(
+        &mut self,
+        signal: &ControlTrafficSignal,
+        gap_out: Duration,
+        now: Duration,
+    ) -> (bool, Duration) {
+        let num_cycles = signal.cycles.len();
+        // Is anybody waiting for a turn allowed in each cycle?
+        let demand: Vec<bool> = signal
+            .cycles
+            .iter()
+            .map(|cycle| {
+                self.waiting
+                    .keys()
+                    .any(|req| cycle.get_priority(req.turn) != TurnPriority::Banned)
+            })
+            .collect();
+        let mut actuated = self.actuated.unwrap();
+        // The cycles might've been edited.
+        actuated.cycle %= num_cycles;
+        let cycle = &signal.cycles[actuated.cycle];
+        let elapsed = now - actuated.cycle_started;
+
+        if elapsed < cycle.min_green {
+            // Whoever waited through the all-red clearance can try to go now.
+            let clearance_ended = signal.all_red > Duration::ZERO && now == actuated.cycle_started;
+            self.actuated = Some(actuated);
+            return (clearance_ended, cycle.min_green - elapsed);
+        }
+
+        let maxed_out = elapsed >= cycle.max_green;
+        let gapped_out = !demand[actuated.cycle] && now - actuated.last_arrival >= gap_out;
+        if maxed_out || gapped_out {
+            // Skip cycles that nobody is waiting for. If nobody is waiting at all, rest here.
+            if let Some(next) = (1..num_cycles)
+                .map(|offset| (actuated.cycle + offset) % num_cycles)
+                .find(|idx| demand[*idx])
+            {
+                self.actuated = Some(ActuatedState {
+                    cycle: next,
+                    cycle_started: now + signal.all_red,
+                    last_arrival: now,
+                });
+                if signal.all_red > Duration::ZERO {
+                    // Nobody can start during the clearance, so only wake people up after.
+                    return (false, signal.all_red);
+                }
+                return (true, signal.cycles[next].min_green);
+            }
+        }
+
+        self.actuated = Some(actuated);
+        if maxed_out {
+            (false, gap_out)
+        } else {
+            (false, gap_out.min(cycle.max_green - elapsed))
+        }
+    }
+
     fn any_accepted_conflict_with(&self, t: TurnID, map: &Map) -> bool {
         let turn = map.get_t(t);
         self.accepted
@@ -233,9 +389,18 @@ impl State {
         signal: &ControlTrafficSignal,
         new_req: &Request,
         time: Duration,
+        right_turn_on_red: bool,
         map: &Map,
+        scheduler: &mut Scheduler,
     ) -> bool {
-        let (cycle, _remaining_cycle_time) = signal.current_cycle_and_remaining_time(time);
+        let cycle = match self.current_signal_cycle(signal, time) {
+            (Some(cycle), _) => cycle,
+            // During the all-red clearance, agents already in the intersection can finish, but
+            // nobody new starts.
+            (None, _) => {
+                return false;
+            }
+        };
 
         // For now, just maintain safety when agents over-run.
         for req in &self.accepted {
@@ -248,8 +413,11 @@ impl State {
             }
         }
 
-        // Can't go at all this cycle.
+        // Can't go at all this cycle, unless it's a right turn on red.
         if cycle.get_priority(new_req.turn) == TurnPriority::Banned {
+            if right_turn_on_red && map.get_t(new_req.turn).turn_type == TurnType::Right {
+                return self.right_turn_on_red_policy(cycle, new_req, time, map, scheduler);
+            }
             return false;
         }
 
@@ -277,6 +445,43 @@ impl State {
 
         true
     }
+
+    // Treat the red light like a stop sign: stop first, then go if nothing that has a green right
+    // now conflicts, whether it's already moving or just waiting.
+    fn right_turn_on_red_policy(
+        &self,
+        cycle: &Cycle,
+        req: &Request,
+        now: Duration,
+        map: &Map,
+        scheduler: &mut Scheduler,
+    ) -> bool {
+        let our_time = self.waiting[req];
+        if now < our_time + WAIT_AT_STOP_SIGN {
+            scheduler.push(
+                our_time + WAIT_AT_STOP_SIGN,
+                Command::update_agent(req.agent),
+            );
+            return false;
+        }
+
+        if self.any_accepted_conflict_with(req.turn, map) {
+            return false;
+        }
+        let turn = map.get_t(req.turn);
+        !self.waiting.keys().any(|r| {
+            cycle.get_priority(r.turn) != TurnPriority::Banned
+                && turn.conflicts_with(map.get_t(r.turn))
+        })
+    }
+}
+
+// Every turn is Banned during the all-red clearance.
+fn priority_in(cycle: Option<&Cycle>, turn: TurnID) -> TurnPriority {
+    match cycle {
+        Some(cycle) => cycle.get_priority(turn),
+        None => TurnPriority::Banned,
+    }
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Debug)]
@@ -1,3 +1,4 @@
+mod bike_parking;
 mod car;
 mod driving;
 mod intersection;
@@ -5,6 +6,7 @@ mod parking;
 mod queue;
 mod walking;
 
+pub use self::bike_parking::BikeParkingState;
 pub use self::driving::DrivingSimState;
 pub use self::intersection::IntersectionSimState;
 pub use self::parking::ParkingSimState;
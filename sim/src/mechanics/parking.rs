@@ -1,10 +1,9 @@
-use crate::{CarID, CarStatus, DrawCarInput, ParkedCar, ParkingSpot, Vehicle, VehicleType};
+use crate::{CarID, CarStatus, DrawCarInput, Event, ParkedCar, ParkingSpot, Vehicle, VehicleType};
 use abstutil::{
     deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap, MultiMap,
 };
-use geom::Distance;
-use map_model;
-use map_model::{BuildingID, Lane, LaneID, LaneType, Map, Position, Traversable};
+use geom::{Distance, Duration};
+use map_model::{BuildingID, Lane, LaneID, LaneType, Map, ParkingLotID, Position, Traversable};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 use std::iter;
@@ -17,14 +16,23 @@ pub struct ParkingSimState {
     )]
     cars: BTreeMap<CarID, ParkedCar>,
     lanes: BTreeMap<LaneID, ParkingLane>,
+    lots: BTreeMap<ParkingLotID, ParkingLot>,
     reserved_spots: BTreeSet<ParkingSpot>,
 
     driving_to_parking_lane: BTreeMap<LaneID, LaneID>,
+    // The lots with an entrance on each driving lane
+    #[serde(
+        serialize_with = "serialize_multimap",
+        deserialize_with = "deserialize_multimap"
+    )]
+    driving_to_lots: MultiMap<LaneID, ParkingLotID>,
     #[serde(
         serialize_with = "serialize_multimap",
         deserialize_with = "deserialize_multimap"
     )]
     cars_per_building: MultiMap<BuildingID, CarID>,
+
+    events: Vec<Event>,
 }
 
 impl ParkingSimState {
@@ -32,9 +40,12 @@ impl ParkingSimState {
         let mut sim = ParkingSimState {
             cars: BTreeMap::new(),
             lanes: BTreeMap::new(),
+            lots: BTreeMap::new(),
             reserved_spots: BTreeSet::new(),
             driving_to_parking_lane: BTreeMap::new(),
+            driving_to_lots: MultiMap::new(),
             cars_per_building: MultiMap::new(),
+            events: Vec::new(),
         };
         for l in map.all_lanes() {
             if let Some(lane) = ParkingLane::new(l, map) {
@@ -43,33 +54,67 @@ impl ParkingSimState {
                 sim.lanes.insert(lane.id, lane);
             }
         }
+        for lot in map.all_parking_lots() {
+            // Lane edits might've taken away the entrance.
+            if map.get_l(lot.driving_pos.lane()).is_driving() {
+                sim.driving_to_lots.insert(lot.driving_pos.lane(), lot.id);
+                sim.lots.insert(
+                    lot.id,
+                    ParkingLot {
+                        occupants: iter::repeat(None).take(lot.capacity).collect(),
+                    },
+                );
+            }
+        }
         sim
     }
 
     pub fn get_free_spots(&self, l: LaneID) -> Vec<ParkingSpot> {
         let lane = &self.lanes[&l];
         let mut spots: Vec<ParkingSpot> = Vec::new();
+        if !lane.allowed_now {
+            return spots;
+        }
         for (idx, maybe_occupant) in lane.occupants.iter().enumerate() {
             if maybe_occupant.is_none() {
-                spots.push(ParkingSpot::new(lane.id, idx));
+                spots.push(ParkingSpot::Onstreet(lane.id, idx));
             }
         }
         spots
     }
 
+    pub fn get_free_lot_spots(&self, id: ParkingLotID) -> Vec<ParkingSpot> {
+        match self.lots.get(&id) {
+            Some(lot) => lot
+                .occupants
+                .iter()
+                .enumerate()
+                .filter(|(_, maybe_occupant)| maybe_occupant.is_none())
+                .map(|(idx, _)| ParkingSpot::Offstreet(id, idx))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // How many cars are in the lot
+    pub fn get_lot_occupancy(&self, id: ParkingLotID) -> usize {
+        self.lots.get(&id).map_or(0, |lot| {
+            lot.occupants.iter().filter(|x| x.is_some()).count()
+        })
+    }
+
     pub fn remove_parked_car(&mut self, p: ParkedCar) {
         self.cars.remove(&p.vehicle.id);
-        self.lanes
-            .get_mut(&p.spot.lane)
-            .unwrap()
-            .remove_parked_car(p.vehicle.id);
+        let occupant = self.occupant_mut(p.spot);
+        assert_eq!(*occupant, Some(p.vehicle.id));
+        *occupant = None;
     }
 
     pub fn add_parked_car(&mut self, p: ParkedCar) {
-        let spot = p.spot;
         assert!(self.reserved_spots.remove(&p.spot));
-        assert_eq!(self.lanes[&spot.lane].occupants[spot.idx], None);
-        self.lanes.get_mut(&spot.lane).unwrap().occupants[spot.idx] = Some(p.vehicle.id);
+        let occupant = self.occupant_mut(p.spot);
+        assert_eq!(*occupant, None);
+        *occupant = Some(p.vehicle.id);
         if let Some(b) = p.vehicle.owner {
             self.cars_per_building.insert(b, p.vehicle.id);
         }
@@ -97,16 +142,23 @@ impl ParkingSimState {
         }
     }
 
+    // Cars in lots aren't drawn; the lot just shows how full it is.
     pub fn get_draw_car(&self, id: CarID, map: &Map) -> Option<DrawCarInput> {
         let p = self.cars.get(&id)?;
-        let lane = p.spot.lane;
+        let (lane, idx) = match p.spot {
+            ParkingSpot::Onstreet(l, idx) => (l, idx),
+            ParkingSpot::Offstreet(_, _) => {
+                return None;
+            }
+        };
 
-        let front_dist = self.lanes[&lane].dist_along_for_car(p.spot.idx, &p.vehicle);
+        let front_dist = self.lanes[&lane].dist_along_for_car(idx, &p.vehicle);
         Some(DrawCarInput {
             id: p.vehicle.id,
             waiting_for_turn: None,
             status: CarStatus::Parked,
             vehicle_type: VehicleType::Car,
+            class: p.vehicle.class,
             on: Traversable::Lane(lane),
 
             body: map
@@ -119,54 +171,113 @@ impl ParkingSimState {
     pub fn get_all_draw_cars(&self, map: &Map) -> Vec<DrawCarInput> {
         self.cars
             .keys()
-            .map(|id| self.get_draw_car(*id, map).unwrap())
+            .filter_map(|id| self.get_draw_car(*id, map))
             .collect()
     }
 
     pub fn is_free(&self, spot: ParkingSpot) -> bool {
-        self.lanes[&spot.lane].occupants[spot.idx].is_none() && !self.reserved_spots.contains(&spot)
+        let allowed_now = match spot {
+            ParkingSpot::Onstreet(l, _) => self.lanes[&l].allowed_now,
+            ParkingSpot::Offstreet(_, _) => true,
+        };
+        allowed_now && self.occupant(spot).is_none() && !self.reserved_spots.contains(&spot)
+    }
+
+    // Every spot on the lane, and whether it's occupied, reserved, or restricted right now.
+    pub fn get_spot_occupancy(&self, l: LaneID) -> Vec<(ParkingSpot, bool)> {
+        if let Some(ref lane) = self.lanes.get(&l) {
+            (0..lane.occupants.len())
+                .map(|idx| {
+                    let spot = ParkingSpot::Onstreet(l, idx);
+                    (spot, !self.is_free(spot))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
     }
 
     pub fn get_car_at_spot(&self, spot: ParkingSpot) -> Option<ParkedCar> {
-        let car = self.lanes[&spot.lane].occupants[spot.idx]?;
+        let car = self.occupant(spot)?;
         Some(self.cars[&car].clone())
     }
 
-    // And the driving position
+    // And the driving position. Picks whichever is reached first: a spot along the curb, or a lot
+    // with its entrance ahead on this lane.
     pub fn get_first_free_spot(
         &self,
         driving_pos: Position,
         vehicle: &Vehicle,
         map: &Map,
     ) -> Option<(ParkingSpot, Position)> {
+        let mut candidates: Vec<(ParkingSpot, Position)> = Vec::new();
+        if let Some(spot) = self.get_first_free_onstreet_spot(driving_pos, vehicle, map) {
+            candidates.push((spot, self.spot_to_driving_pos(spot, vehicle, map)));
+        }
+        for id in self.driving_to_lots.get(driving_pos.lane()) {
+            if let Some(spot) = self
+                .get_free_lot_spots(*id)
+                .into_iter()
+                .find(|spot| !self.reserved_spots.contains(spot))
+            {
+                let pos = self.spot_to_driving_pos(spot, vehicle, map);
+                if driving_pos.dist_along() <= pos.dist_along() {
+                    candidates.push((spot, pos));
+                }
+            }
+        }
+        candidates
+            .into_iter()
+            .min_by_key(|(_, pos)| pos.dist_along())
+    }
+
+    fn get_first_free_onstreet_spot(
+        &self,
+        driving_pos: Position,
+        vehicle: &Vehicle,
+        map: &Map,
+    ) -> Option<ParkingSpot> {
         let l = *self.driving_to_parking_lane.get(&driving_pos.lane())?;
         let parking_dist = driving_pos.equiv_pos(l, map).dist_along();
         let lane = &self.lanes[&l];
+        if !lane.allowed_now {
+            return None;
+        }
         let idx = lane.occupants.iter().enumerate().position(|(idx, x)| {
             x.is_none()
-                && !self.reserved_spots.contains(&ParkingSpot::new(l, idx))
+                && !self.reserved_spots.contains(&ParkingSpot::Onstreet(l, idx))
                 && parking_dist <= lane.dist_along_for_car(idx, vehicle)
         })?;
-        let spot = ParkingSpot::new(l, idx);
-        Some((spot, self.spot_to_driving_pos(spot, vehicle, map)))
+        Some(ParkingSpot::Onstreet(l, idx))
     }
 
     pub fn spot_to_driving_pos(&self, spot: ParkingSpot, vehicle: &Vehicle, map: &Map) -> Position {
-        Position::new(
-            spot.lane,
-            self.lanes[&spot.lane].dist_along_for_car(spot.idx, vehicle),
-        )
-        .equiv_pos(self.lanes[&spot.lane].driving_lane, map)
+        match spot {
+            ParkingSpot::Onstreet(l, idx) => {
+                Position::new(l, self.lanes[&l].dist_along_for_car(idx, vehicle))
+                    .equiv_pos(self.lanes[&l].driving_lane, map)
+            }
+            ParkingSpot::Offstreet(id, _) => {
+                // Every spot in the lot shares the entrance. The whole car has to fit on the lane
+                // when it pulls out.
+                let pos = map.get_parking_lot(id).driving_pos;
+                let len = map.get_l(pos.lane()).length();
+                Position::new(pos.lane(), pos.dist_along().max(vehicle.length).min(len))
+            }
+        }
     }
 
-    pub fn spot_to_sidewalk_pos(&self, spot: ParkingSpot, sidewalk: LaneID, map: &Map) -> Position {
-        // Always centered in the entire parking spot
-        Position::new(
-            spot.lane,
-            self.lanes[&spot.lane].spot_dist_along[spot.idx]
-                - (map_model::PARKING_SPOT_LENGTH / 2.0),
-        )
-        .equiv_pos(sidewalk, map)
+    pub fn spot_to_sidewalk_pos(&self, spot: ParkingSpot, map: &Map) -> Position {
+        match spot {
+            ParkingSpot::Onstreet(l, idx) => {
+                // TODO Consider precomputing this.
+                let sidewalk = map.find_closest_lane(l, vec![LaneType::Sidewalk]).unwrap();
+                // Always centered in the entire parking spot
+                let (start, end) = self.lanes[&l].spots[idx];
+                Position::new(l, (start + end) / 2.0).equiv_pos(sidewalk, map)
+            }
+            ParkingSpot::Offstreet(id, _) => map.get_parking_lot(id).sidewalk_pos,
+        }
     }
 
     pub fn tooltip_lines(&self, id: CarID) -> Option<Vec<String>> {
@@ -188,15 +299,64 @@ impl ParkingSimState {
     pub fn get_owner_of_car(&self, id: CarID) -> Option<BuildingID> {
         self.cars.get(&id).and_then(|p| p.vehicle.owner)
     }
+
+    // Some lanes only allow parking at certain hours. Cars still parked when a restriction begins
+    // stay put, but get flagged. Returns the next time this should be called, if ever.
+    pub fn update_restrictions(&mut self, time: Duration, map: &Map) -> Option<Duration> {
+        let mut next_change: Option<Duration> = None;
+        for lane in self.lanes.values_mut() {
+            let l = map.get_l(lane.id);
+            let allowed = l.parking_allowed_at(time);
+            if lane.allowed_now && !allowed {
+                for (idx, maybe_occupant) in lane.occupants.iter().enumerate() {
+                    if let Some(car) = maybe_occupant {
+                        self.events.push(Event::CarParkedIllegally(
+                            *car,
+                            ParkingSpot::Onstreet(lane.id, idx),
+                        ));
+                    }
+                }
+            }
+            lane.allowed_now = allowed;
+
+            if let Some(t) = l.next_parking_hours_change(time) {
+                next_change = Some(next_change.map_or(t, |prev| prev.min(t)));
+            }
+        }
+        next_change
+    }
+
+    pub fn collect_events(&mut self) -> Vec<Event> {
+        self.events.drain(..).collect()
+    }
+
+    fn occupant(&self, spot: ParkingSpot) -> Option<CarID> {
+        match spot {
+            ParkingSpot::Onstreet(l, idx) => self.lanes[&l].occupants[idx],
+            // Lots without a driving lane entrance aren't tracked at all
+            ParkingSpot::Offstreet(id, idx) => {
+                self.lots.get(&id).and_then(|lot| lot.occupants[idx])
+            }
+        }
+    }
+
+    fn occupant_mut(&mut self, spot: ParkingSpot) -> &mut Option<CarID> {
+        match spot {
+            ParkingSpot::Onstreet(l, idx) => &mut self.lanes.get_mut(&l).unwrap().occupants[idx],
+            ParkingSpot::Offstreet(id, idx) => &mut self.lots.get_mut(&id).unwrap().occupants[idx],
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, PartialEq)]
 struct ParkingLane {
     id: LaneID,
     driving_lane: LaneID,
-    // The front of the parking spot (farthest along the lane)
-    spot_dist_along: Vec<Distance>,
+    // From Lane::parking_spots
+    spots: Vec<(Distance, Distance)>,
     occupants: Vec<Option<CarID>>,
+    // Kept up-to-date by update_restrictions
+    allowed_now: bool,
 }
 
 impl ParkingLane {
@@ -213,23 +373,25 @@ impl ParkingLane {
             return None;
         };
 
+        let spots = l.parking_spots();
         Some(ParkingLane {
             id: l.id,
             driving_lane,
-            occupants: iter::repeat(None).take(l.number_parking_spots()).collect(),
-            spot_dist_along: (0..l.number_parking_spots())
-                .map(|idx| map_model::PARKING_SPOT_LENGTH * (2.0 + idx as f64))
-                .collect(),
+            occupants: iter::repeat(None).take(spots.len()).collect(),
+            spots,
+            allowed_now: l.parking_allowed_at(Duration::ZERO),
         })
     }
 
-    fn remove_parked_car(&mut self, car: CarID) {
-        let idx = self.occupants.iter().position(|x| *x == Some(car)).unwrap();
-        self.occupants[idx] = None;
-    }
-
     fn dist_along_for_car(&self, spot_idx: usize, vehicle: &Vehicle) -> Distance {
         // Find the offset to center this particular car in the parking spot
-        self.spot_dist_along[spot_idx] - (map_model::PARKING_SPOT_LENGTH - vehicle.length) / 2.0
+        let (start, end) = self.spots[spot_idx];
+        end - (end - start - vehicle.length) / 2.0
     }
 }
+
+#[derive(Serialize, Deserialize, PartialEq)]
+struct ParkingLot {
+    // One per unit of the lot's capacity
+    occupants: Vec<Option<CarID>>,
+}
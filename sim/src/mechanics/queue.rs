@@ -1,5 +1,5 @@
 use crate::mechanics::car::{Car, CarState};
-use crate::{CarID, FOLLOWING_DISTANCE};
+use crate::CarID;
 use geom::{Distance, Duration};
 use map_model::{Map, Traversable};
 use serde_derive::{Deserialize, Serialize};
@@ -13,16 +13,19 @@ pub struct Queue {
     pub laggy_head: Option<CarID>,
 
     pub geom_len: Distance,
+    // Copied from DrivingParams
+    pub min_gap: Distance,
 }
 
 impl Queue {
-    pub fn new(id: Traversable, map: &Map) -> Queue {
+    pub fn new(id: Traversable, map: &Map, min_gap: Distance) -> Queue {
         let len = id.length(map);
         Queue {
             id,
             cars: VecDeque::new(),
             laggy_head: None,
             geom_len: len,
+            min_gap,
         }
     }
 
@@ -42,12 +45,12 @@ impl Queue {
         for id in &self.cars {
             let bound = match result.last() {
                 Some((leader, last_dist)) => {
-                    *last_dist - cars[leader].vehicle.length - FOLLOWING_DISTANCE
+                    *last_dist - cars[leader].vehicle.length - self.min_gap
                 }
                 None => match self.laggy_head {
                     Some(id) => {
                         // The simple but broken version:
-                        //self.geom_len - cars[&id].vehicle.length - FOLLOWING_DISTANCE
+                        //self.geom_len - cars[&id].vehicle.length - self.min_gap
 
                         // The expensive case. We need to figure out exactly where the laggy head
                         // is on their queue. No protection against gridlock here!
@@ -67,10 +70,10 @@ impl Queue {
                         }
                         // They might actually be out of the way, but laggy_head hasn't been
                         // updated yet.
-                        if dist_away_from_this_queue < leader.vehicle.length + FOLLOWING_DISTANCE {
+                        if dist_away_from_this_queue < leader.vehicle.length + self.min_gap {
                             self.geom_len
                                 - (cars[&id].vehicle.length - dist_away_from_this_queue)
-                                - FOLLOWING_DISTANCE
+                                - self.min_gap
                         } else {
                             self.geom_len
                         }
@@ -113,7 +116,7 @@ impl Queue {
 
             result.push((*id, front));
         }
-        validate_positions(result, cars, time, self.id)
+        validate_positions(result, cars, time, self.id, self.min_gap)
     }
 
     pub fn get_idx_to_insert_car(
@@ -142,13 +145,12 @@ impl Queue {
 
         // Are we too close to the leader?
         if idx != 0
-            && dists[idx - 1].1 - cars[&dists[idx - 1].0].vehicle.length - FOLLOWING_DISTANCE
-                < start_dist
+            && dists[idx - 1].1 - cars[&dists[idx - 1].0].vehicle.length - self.min_gap < start_dist
         {
             return None;
         }
         // Or the follower?
-        if idx != dists.len() && start_dist - vehicle_len - FOLLOWING_DISTANCE < dists[idx].1 {
+        if idx != dists.len() && start_dist - vehicle_len - self.min_gap < dists[idx].1 {
             return None;
         }
 
@@ -161,9 +163,10 @@ fn validate_positions(
     cars: &BTreeMap<CarID, Car>,
     time: Duration,
     id: Traversable,
+    min_gap: Distance,
 ) -> Vec<(CarID, Distance)> {
     for pair in dists.windows(2) {
-        if pair[0].1 - cars[&pair[0].0].vehicle.length - FOLLOWING_DISTANCE < pair[1].1 {
+        if pair[0].1 - cars[&pair[0].0].vehicle.length - min_gap < pair[1].1 {
             dump_cars(&dists, cars, id, time);
             panic!(
                 "get_car_positions wound up with bad positioning: {} then {}\n{:?}",
@@ -1,7 +1,7 @@
 use crate::{
-    AgentID, Command, CreatePedestrian, DistanceInterval, DrawPedestrianInput,
-    IntersectionSimState, ParkingSimState, PedestrianID, Scheduler, SidewalkPOI, SidewalkSpot,
-    TimeInterval, TransitSimState, TripID, TripManager, TripPositions,
+    AgentID, Command, Conditions, CreatePedestrian, DistanceInterval, DrawPedestrianInput,
+    DrivingSimState, IntersectionSimState, ParkingSimState, PedestrianID, Scheduler, SidewalkPOI,
+    SidewalkSpot, TimeInterval, TransitSimState, TravelStats, TripID, TripManager, TripPositions,
 };
 use abstutil::{deserialize_multimap, serialize_multimap, MultiMap};
 use geom::{Distance, Duration, Line, PolyLine, Pt2D, Speed};
@@ -11,6 +11,10 @@ use std::collections::BTreeMap;
 
 const TIME_TO_START_BIKING: Duration = Duration::const_seconds(30.0);
 const TIME_TO_FINISH_BIKING: Duration = Duration::const_seconds(45.0);
+// Before starting a mid-block crossing, no vehicle can be this close to it.
+const MIDBLOCK_CROSSING_GAP: Distance = Distance::const_meters(30.0);
+// TODO Do something else.
+const BLIND_RETRY_TO_CROSS_MIDBLOCK: Duration = Duration::const_seconds(1.0);
 
 #[derive(Serialize, Deserialize, PartialEq)]
 pub struct WalkingSimState {
@@ -21,6 +25,7 @@ pub struct WalkingSimState {
         deserialize_with = "deserialize_multimap"
     )]
     peds_per_traversable: MultiMap<Traversable, PedestrianID>,
+    pub(crate) conditions: Conditions,
 }
 
 impl WalkingSimState {
@@ -28,6 +33,7 @@ impl WalkingSimState {
         WalkingSimState {
             peds: BTreeMap::new(),
             peds_per_traversable: MultiMap::new(),
+            conditions: Conditions::Dry,
         }
     }
 
@@ -37,6 +43,7 @@ impl WalkingSimState {
         params: CreatePedestrian,
         map: &Map,
         scheduler: &mut Scheduler,
+        stats: &mut TravelStats,
     ) {
         let start_lane = params.start.sidewalk_pos.lane();
         assert_eq!(params.path.current_step().as_lane(), start_lane);
@@ -52,10 +59,11 @@ impl WalkingSimState {
                 DistanceInterval::new_walking(Distance::ZERO, Distance::meters(1.0)),
                 TimeInterval::new(Duration::ZERO, Duration::seconds(1.0)),
             ),
-            speed: params.speed,
+            speed: params.speed * self.conditions.walking_speed_factor(),
             path: params.path,
             goal: params.goal,
             trip: params.trip,
+            lane_entered_at: None,
         };
         ped.state = match params.start.connection {
             SidewalkPOI::Building(b) => PedState::LeavingBuilding(
@@ -76,6 +84,7 @@ impl WalkingSimState {
             Traversable::Lane(params.start.sidewalk_pos.lane()),
             params.id,
         );
+        stats.agent_entered(Traversable::Lane(start_lane));
     }
 
     pub fn get_all_draw_peds(&self, time: Duration, map: &Map) -> Vec<DrawPedestrianInput> {
@@ -98,16 +107,22 @@ impl WalkingSimState {
             .collect()
     }
 
+    pub fn get_peds_on(&self, on: Traversable) -> Vec<PedestrianID> {
+        self.peds_per_traversable.get(on).iter().cloned().collect()
+    }
+
     pub fn update_ped(
         &mut self,
         id: PedestrianID,
         now: Duration,
         map: &Map,
         intersections: &mut IntersectionSimState,
+        driving: &DrivingSimState,
         parking: &ParkingSimState,
         scheduler: &mut Scheduler,
         trips: &mut TripManager,
         transit: &mut TransitSimState,
+        stats: &mut TravelStats,
     ) {
         let mut ped = self.peds.get_mut(&id).unwrap();
         match ped.state {
@@ -133,7 +148,7 @@ impl WalkingSimState {
                             scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
                         }
                         SidewalkPOI::BusStop(stop) => {
-                            if trips.ped_reached_bus_stop(ped.id, stop, map, transit) {
+                            if trips.ped_reached_bus_stop(now, ped.id, stop, map, transit) {
                                 self.peds_per_traversable
                                     .remove(ped.path.current_step().as_traversable(), ped.id);
                                 self.peds.remove(&id);
@@ -169,12 +184,15 @@ impl WalkingSimState {
                         now,
                         map,
                         intersections,
+                        driving,
                         &mut self.peds_per_traversable,
                         scheduler,
+                        stats,
                     ) {
                         scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
                     } else {
-                        // Must've failed because we can't turn yet. Don't schedule a retry here.
+                        // Must've failed because we can't turn or cross yet. Don't schedule a
+                        // retry here; the intersection or maybe_transition will.
                         ped.state = PedState::WaitingToTurn(dist);
                     }
                 }
@@ -184,8 +202,10 @@ impl WalkingSimState {
                     now,
                     map,
                     intersections,
+                    driving,
                     &mut self.peds_per_traversable,
                     scheduler,
+                    stats,
                 ) {
                     scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
                 }
@@ -279,6 +299,23 @@ impl WalkingSimState {
                 .insert(ped.trip, ped.get_draw_ped(trip_positions.time, map).pos);
         }
     }
+
+    pub fn populate_agent_positions(
+        &self,
+        time: Duration,
+        map: &Map,
+        positions: &mut BTreeMap<AgentID, (Traversable, Distance)>,
+    ) {
+        for ped in self.peds.values() {
+            positions.insert(
+                AgentID::Pedestrian(ped.id),
+                (
+                    ped.path.current_step().as_traversable(),
+                    ped.get_dist_along(time, map),
+                ),
+            );
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, PartialEq)]
@@ -290,6 +327,9 @@ struct Pedestrian {
     path: Path,
     goal: SidewalkSpot,
     trip: TripID,
+    // When the pedestrian entered the start of the sidewalk they're on now. None for the first
+    // one, after a mid-block crossing, and while on a turn.
+    lane_entered_at: Option<Duration>,
 }
 
 impl Pedestrian {
@@ -298,10 +338,12 @@ impl Pedestrian {
             self.goal.sidewalk_pos.dist_along()
         } else {
             // TODO PathStep should have a end_dist... or end_pos
-            match self.path.current_step() {
-                PathStep::Lane(l) => map.get_l(l).length(),
-                PathStep::ContraflowLane(_) => Distance::ZERO,
-                PathStep::Turn(t) => map.get_t(t).geom.length(),
+            match (self.path.current_step(), self.path.next_step()) {
+                (_, PathStep::MidblockCrossing(c)) => map.get_mc(c).src.dist_along(),
+                (PathStep::Lane(l), _) => map.get_l(l).length(),
+                (PathStep::ContraflowLane(_), _) => Distance::ZERO,
+                (PathStep::Turn(t), _) => map.get_t(t).length,
+                (PathStep::MidblockCrossing(c), _) => map.get_mc(c).geom.length(),
             }
         };
         let dist_int = DistanceInterval::new_walking(start_dist, end_dist);
@@ -321,6 +363,28 @@ impl Pedestrian {
         }
     }
 
+    // Where along the current step of their path this pedestrian is. While in a building, at a bike
+    // rack, or waiting for a bus, that's the spot on the sidewalk they're using.
+    fn get_dist_along(&self, time: Duration, map: &Map) -> Distance {
+        match self.state {
+            PedState::Crossing(ref dist_int, ref time_int) => {
+                let percent = if time > time_int.end {
+                    1.0
+                } else {
+                    time_int.percent(time)
+                };
+                dist_int.lerp(percent)
+            }
+            PedState::WaitingToTurn(dist) => dist,
+            PedState::LeavingBuilding(b, _) | PedState::EnteringBuilding(b, _) => {
+                map.get_b(b).front_path.sidewalk.dist_along()
+            }
+            PedState::StartingToBike(ref spot, _, _)
+            | PedState::FinishingBiking(ref spot, _, _) => spot.sidewalk_pos.dist_along(),
+            PedState::WaitingForBus => self.goal.sidewalk_pos.dist_along(),
+        }
+    }
+
     fn get_draw_ped(&self, time: Duration, map: &Map) -> DrawPedestrianInput {
         let on = self.path.current_step().as_traversable();
         let (pos, facing) = match self.state {
@@ -398,7 +462,7 @@ impl Pedestrian {
             pos,
             facing,
             waiting_for_turn: match self.state {
-                PedState::WaitingToTurn(_) => Some(self.path.next_step().as_turn()),
+                PedState::WaitingToTurn(_) => self.path.next_step().as_traversable().maybe_turn(),
                 _ => None,
             },
             preparing_bike: match self.state {
@@ -415,25 +479,64 @@ impl Pedestrian {
         now: Duration,
         map: &Map,
         intersections: &mut IntersectionSimState,
+        driving: &DrivingSimState,
         peds_per_traversable: &mut MultiMap<Traversable, PedestrianID>,
         scheduler: &mut Scheduler,
+        stats: &mut TravelStats,
     ) -> bool {
-        if let PathStep::Turn(t) = self.path.next_step() {
-            if !intersections.maybe_start_turn(AgentID::Pedestrian(self.id), t, now, map, scheduler)
-            {
-                return false;
+        match self.path.next_step() {
+            PathStep::Turn(t) => {
+                if !intersections.maybe_start_turn(
+                    AgentID::Pedestrian(self.id),
+                    t,
+                    now,
+                    map,
+                    scheduler,
+                ) {
+                    return false;
+                }
             }
+            PathStep::MidblockCrossing(c) => {
+                // Simple gap acceptance; vehicles don't yield once the pedestrian starts.
+                if !map
+                    .get_mc(c)
+                    .crossed_lanes
+                    .iter()
+                    .all(|pos| driving.is_lane_clear_near(now, *pos, MIDBLOCK_CROSSING_GAP))
+                {
+                    scheduler.push(
+                        now + BLIND_RETRY_TO_CROSS_MIDBLOCK,
+                        Command::UpdatePed(self.id),
+                    );
+                    return false;
+                }
+            }
+            _ => {}
         }
 
         peds_per_traversable.remove(self.path.current_step().as_traversable(), self.id);
-        self.path.shift();
-        let start_dist = match self.path.current_step() {
-            PathStep::Lane(_) => Distance::ZERO,
-            PathStep::ContraflowLane(l) => map.get_l(l).length(),
-            PathStep::Turn(_) => Distance::ZERO,
+        let prev_step = self.path.shift();
+        let start_dist = match (prev_step, self.path.current_step()) {
+            (PathStep::MidblockCrossing(c), _) => map.get_mc(c).dst.dist_along(),
+            (_, PathStep::Lane(_)) => Distance::ZERO,
+            (_, PathStep::ContraflowLane(l)) => map.get_l(l).length(),
+            (_, PathStep::Turn(_)) | (_, PathStep::MidblockCrossing(_)) => Distance::ZERO,
         };
         self.state = self.crossing_state(start_dist, now, map);
         peds_per_traversable.insert(self.path.current_step().as_traversable(), self.id);
+        stats.agent_entered(self.path.current_step().as_traversable());
+        self.lane_entered_at = match (prev_step, self.path.current_step()) {
+            (PathStep::Lane(l), PathStep::Turn(_))
+            | (PathStep::ContraflowLane(l), PathStep::Turn(_)) => {
+                if let Some(entered_at) = self.lane_entered_at {
+                    stats.lane_traversed(l, now - entered_at);
+                }
+                None
+            }
+            (PathStep::Turn(_), PathStep::Lane(_))
+            | (PathStep::Turn(_), PathStep::ContraflowLane(_)) => Some(now),
+            _ => None,
+        };
         true
     }
 }
@@ -441,7 +544,8 @@ impl Pedestrian {
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 enum PedState {
     Crossing(DistanceInterval, TimeInterval),
-    // The Distance is either 0 or the current traversable's length
+    // The Distance is either 0 or the current traversable's length, or where a mid-block crossing
+    // starts
     WaitingToTurn(Distance),
     LeavingBuilding(BuildingID, TimeInterval),
     EnteringBuilding(BuildingID, TimeInterval),
@@ -1,4 +1,4 @@
-use crate::{CarID, PedestrianID, VehicleType};
+use crate::{CarID, PedestrianID, VehicleClass, VehicleType};
 use geom::{Angle, Duration, PolyLine, Pt2D};
 use map_model::{Map, Traversable, TurnID};
 
@@ -20,6 +20,7 @@ pub struct DrawCarInput {
     pub status: CarStatus,
     // TODO This is definitely redundant
     pub vehicle_type: VehicleType,
+    pub class: VehicleClass,
     pub on: Traversable,
 
     // Starts at the BACK of the car. TODO Dedupe unused old stuff.
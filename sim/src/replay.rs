@@ -0,0 +1,294 @@
+use crate::{
+    AgentID, CarID, CarStatus, DrawCarInput, DrawPedestrianInput, GetDrawAgents, PedestrianID,
+    VehicleClass,
+};
+use abstutil::MultiMap;
+use geom::{Distance, Duration};
+use map_model::{Map, Traversable, LANE_THICKNESS};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const CM_PER_METER: f64 = 100.0;
+
+// Where every moving agent was, sampled every interval during a run, so the run can be animated
+// again later without re-simulating. Parked cars aren't included. Positions are kept to the
+// centimeter, and each agent's are stored as deltas from the previous sample, since most of the
+// time an agent is just a little further along the same lane.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReplayLog {
+    pub map_name: String,
+    pub edits_name: String,
+    pub interval: Duration,
+    // When the first sample was taken. The rest follow every interval after that.
+    pub start: Duration,
+    num_samples: usize,
+    vehicles: BTreeMap<CarID, (VehicleClass, Distance)>,
+    tracks: BTreeMap<AgentID, Vec<Track>>,
+
+    // Only needed while recording: (sample index, where, centimeters along) for each agent
+    #[serde(skip_serializing, skip_deserializing)]
+    last_seen: BTreeMap<AgentID, (usize, Traversable, i64)>,
+}
+
+// A stretch of consecutive samples where an agent was present
+#[derive(Serialize, Deserialize, Clone)]
+struct Track {
+    first_sample: usize,
+    steps: Vec<Step>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+enum Step {
+    // Still on the same Traversable, this many centimeters further along. Pedestrians can walk
+    // backwards along sidewalks.
+    Advance(i32),
+    // Moved onto a new Traversable, this many centimeters along it
+    Enter(Traversable, u32),
+}
+
+impl ReplayLog {
+    pub(crate) fn new(
+        map_name: String,
+        edits_name: String,
+        interval: Duration,
+        start: Duration,
+    ) -> ReplayLog {
+        ReplayLog {
+            map_name,
+            edits_name,
+            interval,
+            start,
+            num_samples: 0,
+            vehicles: BTreeMap::new(),
+            tracks: BTreeMap::new(),
+            last_seen: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn record<F: Fn(CarID) -> (VehicleClass, Distance)>(
+        &mut self,
+        positions: BTreeMap<AgentID, (Traversable, Distance)>,
+        lookup_vehicle: F,
+    ) {
+        let idx = self.num_samples;
+        self.num_samples += 1;
+
+        for (agent, (on, dist)) in positions {
+            if let AgentID::Car(car) = agent {
+                self.vehicles
+                    .entry(car)
+                    .or_insert_with(|| lookup_vehicle(car));
+            }
+
+            let cm = (dist.inner_meters() * CM_PER_METER).round() as i64;
+            let (continuing, step) = match self.last_seen.get(&agent) {
+                Some((last_idx, last_on, last_cm)) if *last_idx + 1 == idx => {
+                    if *last_on == on {
+                        (true, Step::Advance((cm - last_cm) as i32))
+                    } else {
+                        (true, Step::Enter(on, cm as u32))
+                    }
+                }
+                _ => (false, Step::Enter(on, cm as u32)),
+            };
+            let tracks = self.tracks.entry(agent).or_insert_with(Vec::new);
+            if !continuing {
+                tracks.push(Track {
+                    first_sample: idx,
+                    steps: Vec::new(),
+                });
+            }
+            tracks.last_mut().unwrap().steps.push(step);
+            self.last_seen.insert(agent, (idx, on, cm));
+        }
+    }
+
+    pub fn end(&self) -> Duration {
+        if self.num_samples == 0 {
+            return self.start;
+        }
+        self.start + self.interval * ((self.num_samples - 1) as f64)
+    }
+
+    // Undo the delta encoding, producing where every agent was at each sample.
+    fn decode(&self) -> Vec<BTreeMap<AgentID, (Traversable, Distance)>> {
+        let mut samples = vec![BTreeMap::new(); self.num_samples];
+        for (agent, tracks) in &self.tracks {
+            for track in tracks {
+                let mut current: Option<(Traversable, i64)> = None;
+                for (i, step) in track.steps.iter().enumerate() {
+                    let (on, cm) = match (step, current) {
+                        (Step::Enter(on, cm), _) => (*on, i64::from(*cm)),
+                        (Step::Advance(delta), Some((on, cm))) => (on, cm + i64::from(*delta)),
+                        (Step::Advance(_), None) => {
+                            panic!("Replay of {:?} doesn't start by entering somewhere", agent)
+                        }
+                    };
+                    current = Some((on, cm));
+                    samples[track.first_sample + i]
+                        .insert(*agent, (on, Distance::meters((cm as f64) / CM_PER_METER)));
+                }
+            }
+        }
+        samples
+    }
+}
+
+// Plays back a ReplayLog at any time, interpolating between samples. Draws agents like a Sim
+// would.
+pub struct Replay {
+    log: ReplayLog,
+    samples: Vec<BTreeMap<AgentID, (Traversable, Distance)>>,
+
+    time: Duration,
+    step_count: usize,
+    cars: BTreeMap<CarID, DrawCarInput>,
+    peds: BTreeMap<PedestrianID, DrawPedestrianInput>,
+    cars_per_traversable: MultiMap<Traversable, CarID>,
+    peds_per_traversable: MultiMap<Traversable, PedestrianID>,
+}
+
+impl Replay {
+    pub fn new(log: ReplayLog, map: &Map) -> Replay {
+        let samples = log.decode();
+        let start = log.start;
+        let mut replay = Replay {
+            log,
+            samples,
+            time: start,
+            step_count: 0,
+            cars: BTreeMap::new(),
+            peds: BTreeMap::new(),
+            cars_per_traversable: MultiMap::new(),
+            peds_per_traversable: MultiMap::new(),
+        };
+        replay.set_time(start, map);
+        replay
+    }
+
+    pub fn get_log(&self) -> &ReplayLog {
+        &self.log
+    }
+
+    // Where the front of every agent was. Between samples, agents staying on the same Traversable
+    // move smoothly; ones moving onto something new jump there at the next sample.
+    pub fn positions_at(&self, time: Duration) -> BTreeMap<AgentID, (Traversable, Distance)> {
+        if self.samples.is_empty() {
+            return BTreeMap::new();
+        }
+        let progress = if time <= self.log.start {
+            0.0
+        } else {
+            (time - self.log.start) / self.log.interval
+        };
+        let idx = progress.floor() as usize;
+        if idx >= self.samples.len() - 1 {
+            return self.samples.last().unwrap().clone();
+        }
+        let percent = progress - (idx as f64);
+
+        let after = &self.samples[idx + 1];
+        self.samples[idx]
+            .iter()
+            .map(|(agent, (on, dist))| match after.get(agent) {
+                Some((next_on, next_dist)) if next_on == on => {
+                    (*agent, (*on, *dist + (*next_dist - *dist) * percent))
+                }
+                _ => (*agent, (*on, *dist)),
+            })
+            .collect()
+    }
+
+    pub fn set_time(&mut self, time: Duration, map: &Map) {
+        self.time = time;
+        self.step_count += 1;
+        self.cars.clear();
+        self.peds.clear();
+        self.cars_per_traversable = MultiMap::new();
+        self.peds_per_traversable = MultiMap::new();
+
+        for (agent, (on, dist)) in self.positions_at(time) {
+            let dist = dist.min(on.length(map));
+            match agent {
+                AgentID::Car(id) => {
+                    let (class, length) = self.log.vehicles[&id];
+                    // Just draw the part of the car on its current Traversable.
+                    if let Some((body, _)) =
+                        on.slice((dist - length).max(Distance::ZERO), dist, map)
+                    {
+                        self.cars_per_traversable.insert(on, id);
+                        self.cars.insert(
+                            id,
+                            DrawCarInput {
+                                id,
+                                waiting_for_turn: None,
+                                status: CarStatus::Moving,
+                                vehicle_type: id.1,
+                                class,
+                                on,
+                                body,
+                            },
+                        );
+                    }
+                }
+                AgentID::Pedestrian(id) => {
+                    let (pt, facing) = on.dist_along(dist, map);
+                    self.peds_per_traversable.insert(on, id);
+                    self.peds.insert(
+                        id,
+                        DrawPedestrianInput {
+                            id,
+                            pos: pt.project_away(LANE_THICKNESS / 4.0, facing.rotate_degs(90.0)),
+                            facing,
+                            waiting_for_turn: None,
+                            preparing_bike: false,
+                            on,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl GetDrawAgents for Replay {
+    fn time(&self) -> Duration {
+        self.time
+    }
+
+    fn step_count(&self) -> usize {
+        self.step_count
+    }
+
+    fn get_draw_car(&self, id: CarID, _map: &Map) -> Option<DrawCarInput> {
+        self.cars.get(&id).cloned()
+    }
+
+    fn get_draw_ped(&self, id: PedestrianID, _map: &Map) -> Option<DrawPedestrianInput> {
+        self.peds.get(&id).cloned()
+    }
+
+    fn get_draw_cars(&self, on: Traversable, _map: &Map) -> Vec<DrawCarInput> {
+        self.cars_per_traversable
+            .get(on)
+            .iter()
+            .map(|id| self.cars[id].clone())
+            .collect()
+    }
+
+    fn get_draw_peds(&self, on: Traversable, _map: &Map) -> Vec<DrawPedestrianInput> {
+        self.peds_per_traversable
+            .get(on)
+            .iter()
+            .map(|id| self.peds[id].clone())
+            .collect()
+    }
+
+    fn get_all_draw_cars(&self, _map: &Map) -> Vec<DrawCarInput> {
+        self.cars.values().cloned().collect()
+    }
+
+    fn get_all_draw_peds(&self, _map: &Map) -> Vec<DrawPedestrianInput> {
+        self.peds.values().cloned().collect()
+    }
+}
@@ -1,7 +1,8 @@
-use crate::{ParkingSimState, ParkingSpot, SidewalkSpot, Vehicle};
+use crate::{ParkingSimState, ParkingSpot, SidewalkSpot, Vehicle, VehicleType};
 use geom::Distance;
 use map_model::{
-    BuildingID, IntersectionID, Map, Path, PathStep, Position, Traversable, Turn, TurnID,
+    BikeRackID, BuildingID, IntersectionID, LaneID, Map, Path, PathRequest, PathStep, Position,
+    Traversable, Turn, TurnID,
 };
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeSet;
@@ -18,7 +19,8 @@ pub enum ActionAtEnd {
     VanishAtBorder(IntersectionID),
     StartParking(ParkingSpot),
     GotoLaneEnd,
-    StopBiking(SidewalkSpot),
+    // Also the rack the bike was left at, if it was going to one
+    StopBiking(SidewalkSpot, Option<BikeRackID>),
     BusAtStop,
 }
 
@@ -37,6 +39,7 @@ enum Goal {
     },
     BikeThenStop {
         end_dist: Distance,
+        rack: Option<BikeRackID>,
     },
     FollowBusRoute {
         end_dist: Distance,
@@ -65,7 +68,21 @@ impl Router {
     pub fn bike_then_stop(path: Path, end_dist: Distance) -> Router {
         Router {
             path,
-            goal: Goal::BikeThenStop { end_dist },
+            goal: Goal::BikeThenStop {
+                end_dist,
+                rack: None,
+            },
+        }
+    }
+
+    // The path must end at the rack's driving position.
+    pub fn bike_to_rack(path: Path, end_dist: Distance, rack: BikeRackID) -> Router {
+        Router {
+            path,
+            goal: Goal::BikeThenStop {
+                end_dist,
+                rack: Some(rack),
+            },
         }
     }
 
@@ -102,7 +119,7 @@ impl Router {
         match self.goal {
             Goal::EndAtBorder { end_dist, .. } => end_dist,
             Goal::ParkNearBuilding { spot, .. } => spot.unwrap().1,
-            Goal::BikeThenStop { end_dist } => end_dist,
+            Goal::BikeThenStop { end_dist, .. } => end_dist,
             Goal::FollowBusRoute { end_dist } => end_dist,
         }
     }
@@ -167,18 +184,29 @@ impl Router {
                     None
                 }
             }
-            Goal::BikeThenStop { end_dist } => {
+            Goal::BikeThenStop { end_dist, rack } => {
                 if end_dist == front {
-                    let last_lane = self.head().as_lane();
-                    Some(ActionAtEnd::StopBiking(
-                        SidewalkSpot::bike_rack(
+                    // Edits since the bike set out might have cut the rack off.
+                    let at_rack = rack.and_then(|r| match SidewalkSpot::at_bike_rack(r, map) {
+                        Ok(spot) => Some((spot, r)),
+                        Err(err) => {
+                            println!("Bike can't use its rack after all: {}", err);
+                            None
+                        }
+                    });
+                    if let Some((spot, r)) = at_rack {
+                        Some(ActionAtEnd::StopBiking(spot, Some(r)))
+                    } else {
+                        let last_lane = self.head().as_lane();
+                        let spot = SidewalkSpot::bike_rack(
                             map.get_parent(last_lane)
                                 .bike_to_sidewalk(last_lane)
                                 .unwrap(),
                             map,
                         )
-                        .unwrap(),
-                    ))
+                        .unwrap();
+                        Some(ActionAtEnd::StopBiking(spot, None))
+                    }
                 } else {
                     None
                 }
@@ -193,6 +221,59 @@ impl Router {
         }
     }
 
+    // If the rest of the path goes through a closed lane, find a way around it. The current step
+    // and the next lane stay the same, since the car might already be committed to them. Returns
+    // false if the path doesn't need to change, or if there's no way around.
+    pub fn reroute_around(
+        &mut self,
+        closed: &BTreeSet<LaneID>,
+        vehicle: &Vehicle,
+        map: &Map,
+    ) -> bool {
+        let steps = self.path.get_steps();
+        let keep = match steps.iter().skip(1).position(|step| match step {
+            PathStep::Lane(_) => true,
+            _ => false,
+        }) {
+            Some(idx) => idx + 2,
+            None => {
+                return false;
+            }
+        };
+        let end_lane = self.path.last_step().as_lane();
+        if !steps.iter().skip(keep).any(|step| match step {
+            PathStep::Lane(l) => *l != end_lane && closed.contains(l),
+            _ => false,
+        }) {
+            return false;
+        }
+
+        let from = steps[keep - 1].as_lane();
+        let new_path = match map.pathfind(PathRequest {
+            start: Position::new(from, map.get_l(from).length()),
+            end: Position::new(end_lane, self.path.get_end_dist()),
+            can_use_bike_lanes: vehicle.vehicle_type == VehicleType::Bike,
+            can_use_bus_lanes: vehicle.vehicle_type == VehicleType::Bus,
+            can_use_hov_lanes: vehicle.can_use_hov_lanes(),
+            can_use_midblock_crossings: false,
+            avoid_lanes: closed.clone(),
+        }) {
+            Some(p) => p,
+            None => {
+                return false;
+            }
+        };
+        // The new path starts with the lane we're keeping.
+        let new_steps: Vec<PathStep> = steps
+            .iter()
+            .take(keep)
+            .chain(new_path.get_steps().iter().skip(1))
+            .cloned()
+            .collect();
+        self.path = Path::new(map, new_steps, self.path.get_end_dist());
+        true
+    }
+
     fn roam_around_for_parking(&mut self, vehicle: &Vehicle, map: &Map) {
         let turns_attempted_while_roaming = match self.goal {
             Goal::ParkNearBuilding {
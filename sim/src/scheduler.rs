@@ -1,7 +1,7 @@
 use crate::{AgentID, CarID, CreateCar, CreatePedestrian, PedestrianID};
 use derivative::Derivative;
 use geom::{Duration, DurationHistogram};
-use map_model::IntersectionID;
+use map_model::{IntersectionID, LaneID};
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, PartialEq)]
@@ -15,7 +15,18 @@ pub enum Command {
     UpdatePed(PedestrianID),
     UpdateIntersection(IntersectionID),
     CheckForGridlock,
+    // Some parking lanes only allow parking at certain hours
+    UpdateParkingRestrictions,
+    // Start the next minute of trips from the DemandModel
+    SpawnDemand,
     Savestate(Duration),
+    // Note where every agent is for the ReplayLog, then again after this long
+    SampleReplay(Duration),
+    // Try again to start cars waiting at this border for room on their first lane
+    ReleaseBorderQueue(IntersectionID),
+    // Scheduled road closures, like for construction
+    CloseLane(LaneID),
+    OpenLane(LaneID),
 }
 
 impl Command {
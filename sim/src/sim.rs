@@ -1,22 +1,28 @@
 use crate::{
-    AgentID, CarID, Command, CreateCar, DrawCarInput, DrawPedestrianInput, DrivingGoal,
-    DrivingSimState, Event, FinishedTrips, GetDrawAgents, IntersectionSimState, ParkedCar,
-    ParkingSimState, ParkingSpot, PedestrianID, Router, Scheduler, TransitSimState, TripID,
-    TripLeg, TripManager, TripPositions, TripSpawner, TripSpec, VehicleSpec, VehicleType,
-    WalkingSimState, BUS_LENGTH,
+    AgentID, CarID, Command, Conditions, CreateCar, DemandModel, DrawCarInput, DrawPedestrianInput,
+    DrivingGoal, DrivingParams, DrivingSimState, Event, FinishedTrips, GetDrawAgents,
+    IntersectionSimState, LegDetails, ParkedCar, ParkingSimState, ParkingSpot, PedestrianID,
+    ReplayLog, Router, Scheduler, TransitSimState, TravelStats, TripID, TripLeg, TripManager,
+    TripMetadata, TripPositions, TripSpawner, TripSpec, Vehicle, VehicleClass, VehicleSpec,
+    VehicleType, WalkingSimState, BUS_LENGTH, DEMAND_FREQUENCY,
 };
-use abstutil::{elapsed_seconds, Timer};
+use abstutil::{elapsed_seconds, Error, Timer};
 use derivative::Derivative;
 use geom::{Distance, Duration, PolyLine, Pt2D};
-use map_model::{BuildingID, BusRoute, BusRouteID, IntersectionID, LaneID, Map, Path, Traversable};
+use map_model::{
+    BikeRackID, BuildingID, BusRoute, BusRouteID, ControlTrafficSignal, Cycle, IntersectionID,
+    LaneID, Map, ParkingLotID, Path, RoadID, Traversable,
+};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::panic;
 use std::time::Instant;
 
-const CHECK_FOR_GRIDLOCK_FREQUENCY: Duration = Duration::const_seconds(5.0 * 60.0);
+const DEFAULT_GRIDLOCK_WINDOW: Duration = Duration::const_seconds(5.0 * 60.0);
 // TODO Do something else.
 const BLIND_RETRY_TO_SPAWN: Duration = Duration::const_seconds(5.0);
+// While cars are waiting at a border, check for room this often
+const RETRY_BORDER_QUEUE: Duration = Duration::const_seconds(1.0);
 
 #[derive(Serialize, Deserialize, Derivative)]
 #[derivative(PartialEq)]
@@ -29,9 +35,17 @@ pub struct Sim {
     trips: TripManager,
     spawner: TripSpawner,
     scheduler: Scheduler,
+    travel_stats: TravelStats,
     time: Duration,
     car_id_counter: usize,
     ped_id_counter: usize,
+    // Keeps spawning background traffic while set
+    demand: Option<DemandModel>,
+    // Look for gridlock this often, once no car has moved for this long
+    gridlock_window: Duration,
+    abort_gridlocked_trips: bool,
+    // Temporary closures for what-if experiments, without touching the map's edits
+    closed_lanes: BTreeSet<LaneID>,
 
     // TODO Reconsider these
     pub(crate) map_name: String,
@@ -41,6 +55,10 @@ pub struct Sim {
     run_name: String,
     #[derivative(PartialEq = "ignore")]
     step_count: usize,
+    // What seeded the RNG used to set up this run, if known. Tests compare runs made with
+    // different seeds.
+    #[derivative(PartialEq = "ignore")]
+    rng_seed: Option<u64>,
 
     // Lazily computed.
     #[derivative(PartialEq = "ignore")]
@@ -50,44 +68,202 @@ pub struct Sim {
     #[derivative(PartialEq = "ignore")]
     #[serde(skip_serializing, skip_deserializing)]
     events_since_last_step: Vec<Event>,
+
+    // Only while recording. Savestates don't carry this along.
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    replay: Option<ReplayLog>,
 }
 
 // Setup
 impl Sim {
     pub fn new(map: &Map, run_name: String, savestate_every: Option<Duration>) -> Sim {
         let mut scheduler = Scheduler::new();
-        scheduler.push(CHECK_FOR_GRIDLOCK_FREQUENCY, Command::CheckForGridlock);
+        scheduler.push(DEFAULT_GRIDLOCK_WINDOW, Command::CheckForGridlock);
         if let Some(d) = savestate_every {
             scheduler.push(d, Command::Savestate(d));
         }
+        let mut parking = ParkingSimState::new(map);
+        if let Some(t) = parking.update_restrictions(Duration::ZERO, map) {
+            scheduler.push(t, Command::UpdateParkingRestrictions);
+        }
         Sim {
             driving: DrivingSimState::new(map),
-            parking: ParkingSimState::new(map),
+            parking,
             walking: WalkingSimState::new(),
             intersections: IntersectionSimState::new(map, &mut scheduler),
             transit: TransitSimState::new(),
             trips: TripManager::new(),
             spawner: TripSpawner::new(),
             scheduler,
+            travel_stats: TravelStats::new(map),
             time: Duration::ZERO,
             car_id_counter: 0,
             ped_id_counter: 0,
+            demand: None,
+            gridlock_window: DEFAULT_GRIDLOCK_WINDOW,
+            abort_gridlocked_trips: false,
+            closed_lanes: BTreeSet::new(),
 
             map_name: map.get_name().to_string(),
             // TODO
             edits_name: "no_edits".to_string(),
             run_name,
             step_count: 0,
+            rng_seed: None,
             trip_positions: None,
             events_since_last_step: Vec::new(),
+            replay: None,
+        }
+    }
+
+    // Off by default. Only affects walking paths calculated after this.
+    pub fn use_midblock_crossings(&mut self, enabled: bool) {
+        self.trips.use_midblock_crossings = enabled;
+    }
+
+    // Off by default. Only affects trip legs starting after this.
+    pub fn record_trip_details(&mut self, enabled: bool) {
+        self.trips.record_trip_details = enabled;
+    }
+
+    // Off by default. Trips scheduled to depart before this time instead start there, partway
+    // along their route. Only affects trips spawned after this, and they're left out of finished
+    // trip stats.
+    pub fn set_warm_start(&mut self, at: Option<Duration>) {
+        self.trips.warm_start = at;
+    }
+
+    pub fn is_recording_trip_details(&self) -> bool {
+        self.trips.record_trip_details
+    }
+
+    // Dry by default. Only affects pedestrians spawned after this.
+    pub fn set_conditions(&mut self, conditions: Conditions) {
+        self.driving.conditions = conditions;
+        self.walking.conditions = conditions;
+    }
+
+    pub fn get_conditions(&self) -> Conditions {
+        self.driving.conditions
+    }
+
+    // Must be called before any cars are spawned. The defaults match the original hardcoded
+    // behavior.
+    pub fn set_driving_params(&mut self, params: DrivingParams) {
+        self.driving.set_params(params);
+    }
+
+    pub fn get_driving_params(&self) -> DrivingParams {
+        self.driving.get_params()
+    }
+
+    // 5 minutes by default. Gridlock is only suspected after no car has moved for this long.
+    pub fn set_gridlock_window(&mut self, window: Duration) {
+        self.gridlock_window = window;
+        self.scheduler
+            .update(Command::CheckForGridlock, self.time + window);
+    }
+
+    // Off by default, meaning the first gridlock detected is savestated, then the sim carries on
+    // without checking again. When on, trips for the cars involved are aborted to break it up.
+    pub fn abort_gridlocked_trips(&mut self, enabled: bool) {
+        self.abort_gridlocked_trips = enabled;
+    }
+
+    // Cars already driving route around newly closed lanes, if there's a way. Cars starting later
+    // avoid all closed lanes. Pedestrians ignore closures. Returns the cars that were rerouted.
+    pub fn close_lanes(&mut self, lanes: Vec<LaneID>, map: &Map) -> Vec<CarID> {
+        self.closed_lanes.extend(lanes);
+        self.driving.reroute_around(&self.closed_lanes, map)
+    }
+
+    // Cars that already rerouted keep their detour.
+    pub fn reopen_lanes(&mut self, lanes: Vec<LaneID>) {
+        for l in lanes {
+            self.closed_lanes.remove(&l);
+        }
+    }
+
+    // Close the lane at some point in the future, then optionally open it again later.
+    pub fn schedule_lane_closure(
+        &mut self,
+        l: LaneID,
+        close_at: Duration,
+        open_at: Option<Duration>,
+    ) -> Result<(), Error> {
+        if let Some(t) = open_at {
+            if t <= close_at {
+                return Err(Error::new(format!(
+                    "{} can't reopen at {} before it closes at {}",
+                    l, t, close_at
+                )));
+            }
+        }
+        self.scheduler.push(close_at, Command::CloseLane(l));
+        if let Some(t) = open_at {
+            self.scheduler.push(t, Command::OpenLane(l));
+        }
+        Ok(())
+    }
+
+    pub fn get_closed_lanes(&self) -> &BTreeSet<LaneID> {
+        &self.closed_lanes
+    }
+
+    // Off by default. Cars stopped at a red light may turn right if no movement with a green
+    // conflicts.
+    pub fn allow_right_turn_on_red(&mut self, enabled: bool) {
+        self.intersections.right_turn_on_red = enabled;
+    }
+
+    // Off by default. Starting now, note where every moving agent is this often, so the run can be
+    // played back later without re-simulating. The logs get big, so use a coarse interval for long
+    // runs. None stops recording and discards the log.
+    pub fn record_replay(&mut self, interval: Option<Duration>) {
+        // Only one sampling chain at a time, at the interval of the current log
+        if let Some(ref replay) = self.replay {
+            self.scheduler
+                .cancel(Command::SampleReplay(replay.interval));
+        }
+        if let Some(d) = interval {
+            self.scheduler.push(self.time, Command::SampleReplay(d));
+            self.replay = Some(ReplayLog::new(
+                self.map_name.clone(),
+                self.edits_name.clone(),
+                d,
+                self.time,
+            ));
+        } else {
+            self.replay = None;
         }
     }
 
+    // Starting now, spawn random trips every minute according to the model. None stops. The sim
+    // won't finish while this is set.
+    pub fn set_demand(&mut self, model: Option<DemandModel>) {
+        if self.demand.is_none() && model.is_some() {
+            self.scheduler.push(self.time, Command::SpawnDemand);
+        }
+        self.demand = model;
+    }
+
     pub fn schedule_trip(
         &mut self,
         start_time: Duration,
         spec: TripSpec,
         map: &Map,
+    ) -> (Option<PedestrianID>, Option<CarID>) {
+        self.schedule_trip_with_metadata(start_time, spec, None, map)
+    }
+
+    // The metadata is carried along for analysis, but doesn't affect the trip at all.
+    pub fn schedule_trip_with_metadata(
+        &mut self,
+        start_time: Duration,
+        spec: TripSpec,
+        metadata: Option<TripMetadata>,
+        map: &Map,
     ) -> (Option<PedestrianID>, Option<CarID>) {
         let (ped_id, car_id) = match spec {
             TripSpec::CarAppearing {
@@ -123,8 +299,15 @@ impl Sim {
             }
         };
 
-        self.spawner
-            .schedule_trip(start_time, ped_id, car_id, spec, map, &self.parking);
+        self.spawner.schedule_trip(
+            start_time,
+            ped_id,
+            car_id,
+            spec,
+            metadata,
+            map,
+            &self.parking,
+        );
         (ped_id, car_id)
     }
 
@@ -134,6 +317,7 @@ impl Sim {
             &self.parking,
             &mut self.trips,
             &mut self.scheduler,
+            self.driving.get_params().min_gap,
             timer,
             retry_if_no_room,
         );
@@ -143,6 +327,18 @@ impl Sim {
         self.parking.get_free_spots(l)
     }
 
+    pub fn get_parking_spot_occupancy(&self, l: LaneID) -> Vec<(ParkingSpot, bool)> {
+        self.parking.get_spot_occupancy(l)
+    }
+
+    pub fn get_free_lot_spots(&self, lot: ParkingLotID) -> Vec<ParkingSpot> {
+        self.parking.get_free_lot_spots(lot)
+    }
+
+    pub fn get_parking_lot_occupancy(&self, lot: ParkingLotID) -> usize {
+        self.parking.get_lot_occupancy(lot)
+    }
+
     pub fn seed_parked_car(
         &mut self,
         vehicle: VehicleSpec,
@@ -175,8 +371,11 @@ impl Sim {
             // changes.
             let vehicle_spec = VehicleSpec {
                 vehicle_type: VehicleType::Bus,
+                class: VehicleClass::Bus,
                 length: BUS_LENGTH,
                 max_speed: None,
+                // Buses can use HOV lanes regardless, and passengers are tracked separately.
+                occupancy: 1,
             };
 
             // TODO Do this validation more up-front in the map layer
@@ -195,9 +394,9 @@ impl Sim {
             // Bypass some layers of abstraction that don't make sense for buses.
 
             // TODO Aww, we create an orphan trip if the bus can't spawn.
-            let trip = self
-                .trips
-                .new_trip(self.time, vec![TripLeg::ServeBusRoute(id, route.id)]);
+            let trip =
+                self.trips
+                    .new_trip(self.time, vec![TripLeg::ServeBusRoute(id, route.id)], None);
             if self.driving.start_car_on_lane(
                 self.time,
                 CreateCar {
@@ -211,6 +410,7 @@ impl Sim {
                 &self.intersections,
                 &self.parking,
                 &mut self.scheduler,
+                &mut self.travel_stats,
             ) {
                 self.trips.agent_starting_trip_leg(AgentID::Car(id), trip);
                 self.transit.bus_created(id, route.id, next_stop_idx);
@@ -228,6 +428,10 @@ impl Sim {
     pub fn set_name(&mut self, name: String) {
         self.run_name = name;
     }
+
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng_seed = Some(seed);
+    }
 }
 
 // Drawing
@@ -294,9 +498,11 @@ impl Sim {
 
         let target_time = self.time + dt;
         let mut savestate_at: Option<Duration> = None;
+        let mut sample_replay_at: Option<Duration> = None;
+        let mut spawn_events: Vec<Event> = Vec::new();
         while let Some((cmd, time)) = self.scheduler.get_next(target_time) {
-            // Many commands might be scheduled for a particular time. Savestate at the END of a
-            // certain time.
+            // Many commands might be scheduled for a particular time. Savestate and sample for the
+            // replay at the END of a certain time.
             if let Some(t) = savestate_at {
                 if time > t {
                     self.time = t;
@@ -304,25 +510,32 @@ impl Sim {
                     savestate_at = None;
                 }
             }
+            if let Some(t) = sample_replay_at {
+                if time > t {
+                    self.time = t;
+                    self.sample_replay(map);
+                    sample_replay_at = None;
+                }
+            }
 
             self.time = time;
             match cmd {
                 Command::SpawnCar(create_car, retry_if_no_room) => {
-                    if self.driving.start_car_on_lane(
-                        self.time,
-                        create_car.clone(),
-                        map,
-                        &self.intersections,
-                        &self.parking,
-                        &mut self.scheduler,
-                    ) {
-                        self.trips.agent_starting_trip_leg(
-                            AgentID::Car(create_car.vehicle.id),
-                            create_car.trip,
-                        );
-                        if let Some(parked_car) = create_car.maybe_parked_car {
-                            self.parking.remove_parked_car(parked_car);
+                    if let Some(i) = TripSpawner::border_for(&create_car, map) {
+                        // Keep the order cars arrive in, and don't drop anybody.
+                        let lane = create_car.router.head().as_lane();
+                        if self.spawner.anybody_waiting_for(i, lane)
+                            || !self.try_start_car(create_car.clone(), map)
+                        {
+                            if self.spawner.wait_at_border(i, self.time, create_car) {
+                                self.scheduler.push(
+                                    self.time + RETRY_BORDER_QUEUE,
+                                    Command::ReleaseBorderQueue(i),
+                                );
+                            }
                         }
+                    } else if self.try_start_car(create_car.clone(), map) {
+                        // Started fine
                     } else if retry_if_no_room {
                         self.scheduler.push(
                             self.time + BLIND_RETRY_TO_SPAWN,
@@ -336,15 +549,27 @@ impl Sim {
                         );
                     }
                 }
-                Command::SpawnPed(create_ped) => {
+                Command::SpawnPed(mut create_ped) => {
+                    self.trips.walk_to_parked_bike(&mut create_ped, map);
                     // Do the order a bit backwards so we don't have to clone the CreatePedestrian.
                     // spawn_ped can't fail.
                     self.trips.agent_starting_trip_leg(
                         AgentID::Pedestrian(create_ped.id),
                         create_ped.trip,
                     );
-                    self.walking
-                        .spawn_ped(self.time, create_ped, map, &mut self.scheduler);
+                    self.trips.record_leg_start(
+                        self.time,
+                        create_ped.trip,
+                        &create_ped.path,
+                        create_ped.start.sidewalk_pos.dist_along(),
+                    );
+                    self.walking.spawn_ped(
+                        self.time,
+                        create_ped,
+                        map,
+                        &mut self.scheduler,
+                        &mut self.travel_stats,
+                    );
                 }
                 Command::UpdateCar(car) => {
                     self.driving.update_car(
@@ -357,6 +582,7 @@ impl Sim {
                         &mut self.scheduler,
                         &mut self.transit,
                         &mut self.walking,
+                        &mut self.travel_stats,
                     );
                 }
                 Command::UpdateLaggyHead(car) => {
@@ -374,10 +600,12 @@ impl Sim {
                         self.time,
                         map,
                         &mut self.intersections,
+                        &self.driving,
                         &self.parking,
                         &mut self.scheduler,
                         &mut self.trips,
                         &mut self.transit,
+                        &mut self.travel_stats,
                     );
                 }
                 Command::UpdateIntersection(i) => {
@@ -385,13 +613,41 @@ impl Sim {
                         .update_intersection(self.time, i, map, &mut self.scheduler);
                 }
                 Command::CheckForGridlock => {
-                    if self.driving.detect_gridlock(map) {
-                        self.save();
+                    let stalled = match self.driving.stalled_since() {
+                        Some(t) => self.time - t >= self.gridlock_window,
+                        None => false,
+                    };
+                    let gridlock = if stalled {
+                        self.driving.detect_gridlock(self.time)
                     } else {
-                        self.scheduler.push(
-                            self.time + CHECK_FOR_GRIDLOCK_FREQUENCY,
-                            Command::CheckForGridlock,
-                        );
+                        None
+                    };
+                    let mut keep_checking = true;
+                    if let Some(agents) = gridlock {
+                        println!("Gridlock at {} involving {:?}", self.time, agents);
+                        if self.abort_gridlocked_trips {
+                            self.abort_gridlocked(agents, map);
+                        } else {
+                            self.save();
+                            keep_checking = false;
+                        }
+                    }
+                    if keep_checking {
+                        self.scheduler
+                            .push(self.time + self.gridlock_window, Command::CheckForGridlock);
+                    }
+                }
+                Command::UpdateParkingRestrictions => {
+                    if let Some(t) = self.parking.update_restrictions(self.time, map) {
+                        self.scheduler.push(t, Command::UpdateParkingRestrictions);
+                    }
+                }
+                Command::SpawnDemand => {
+                    if let Some(model) = self.demand.clone() {
+                        let seed = self.rng_seed.unwrap_or(0);
+                        model.spawn_trips(self, map, seed);
+                        self.scheduler
+                            .push(self.time + DEMAND_FREQUENCY, Command::SpawnDemand);
                     }
                 }
                 Command::Savestate(frequency) => {
@@ -400,21 +656,78 @@ impl Sim {
                     assert_eq!(savestate_at, None);
                     savestate_at = Some(self.time);
                 }
+                Command::SampleReplay(frequency) => {
+                    // If recording stopped or the interval changed, just drop this.
+                    if self.replay.as_ref().map(|r| r.interval) == Some(frequency) {
+                        self.scheduler
+                            .push(self.time + frequency, Command::SampleReplay(frequency));
+                        assert_eq!(sample_replay_at, None);
+                        sample_replay_at = Some(self.time);
+                    }
+                }
+                Command::ReleaseBorderQueue(i) => {
+                    // Once the first car for a lane doesn't fit, everybody behind it for that
+                    // lane keeps waiting too. Other lanes from the same border aren't held up.
+                    let mut blocked: BTreeSet<LaneID> = BTreeSet::new();
+                    let mut still_waiting = false;
+                    for (arrived, create_car) in self.spawner.take_border_queue(i) {
+                        let lane = create_car.router.head().as_lane();
+                        let id = create_car.vehicle.id;
+                        if blocked.contains(&lane)
+                            || self.driving.headroom_at_start(
+                                lane,
+                                self.time,
+                                &self.intersections,
+                                map,
+                            ) < create_car.start_dist
+                            || !self.try_start_car(create_car.clone(), map)
+                        {
+                            blocked.insert(lane);
+                            self.spawner.wait_at_border(i, arrived, create_car);
+                            still_waiting = true;
+                            continue;
+                        }
+                        let delay = self.time - arrived;
+                        self.travel_stats.border_spawn_delayed(i, delay);
+                        spawn_events.push(Event::BorderSpawnDelayed(id, i, delay));
+                    }
+                    if still_waiting {
+                        self.scheduler.push(
+                            self.time + RETRY_BORDER_QUEUE,
+                            Command::ReleaseBorderQueue(i),
+                        );
+                    }
+                }
+                Command::CloseLane(l) => {
+                    self.close_lanes(vec![l], map);
+                }
+                Command::OpenLane(l) => {
+                    self.reopen_lanes(vec![l]);
+                }
             }
         }
         if let Some(t) = savestate_at {
             self.time = t;
             self.save();
         }
+        if let Some(t) = sample_replay_at {
+            self.time = t;
+            self.sample_replay(map);
+        }
         self.time = target_time;
 
         self.trip_positions = None;
 
         self.events_since_last_step.clear();
+        self.events_since_last_step.extend(spawn_events);
         self.events_since_last_step
             .extend(self.trips.collect_events());
         self.events_since_last_step
             .extend(self.transit.collect_events());
+        self.events_since_last_step
+            .extend(self.parking.collect_events());
+        self.events_since_last_step
+            .extend(self.driving.collect_events());
     }
 
     pub fn timed_step(&mut self, map: &Map, dt: Duration, timer: &mut Timer) {
@@ -422,29 +735,34 @@ impl Sim {
         let orig_time = self.time;
         let chunks = (dt / Duration::seconds(10.0)).ceil() as usize;
         timer.start_iter(&format!("advance simulation by {}", dt), chunks);
-        for i in 0..chunks {
-            timer.next();
-            self.step(
-                map,
-                if i == chunks - 1 {
-                    orig_time + dt - self.time
-                } else {
-                    dt * (1.0 / (chunks as f64))
-                },
-            );
-        }
+        self.step_in_chunks(map, dt, None, || timer.next());
         assert_eq!(self.time, orig_time + dt);
     }
 
     pub fn time_limited_step(&mut self, map: &Map, dt: Duration, real_time_limit: Duration) {
+        self.step_in_chunks(map, dt, Some(real_time_limit), || {});
+    }
+
+    // The stepping loop shared by interactive and headless runs. Advances by dt in 10s chunks,
+    // giving up early if real_time_limit elapses.
+    fn step_in_chunks<F: FnMut()>(
+        &mut self,
+        map: &Map,
+        dt: Duration,
+        real_time_limit: Option<Duration>,
+        mut before_chunk: F,
+    ) {
         let started_at = Instant::now();
 
         let orig_time = self.time;
         let chunks = (dt / Duration::seconds(10.0)).ceil() as usize;
         for i in 0..chunks {
-            if Duration::seconds(elapsed_seconds(started_at)) > real_time_limit {
-                break;
+            if let Some(limit) = real_time_limit {
+                if Duration::seconds(elapsed_seconds(started_at)) > limit {
+                    break;
+                }
             }
+            before_chunk();
             self.step(
                 map,
                 if i == chunks - 1 {
@@ -456,6 +774,55 @@ impl Sim {
         }
     }
 
+    // Returns false if there's no room for the car yet.
+    fn try_start_car(&mut self, mut create_car: CreateCar, map: &Map) -> bool {
+        create_car
+            .router
+            .reroute_around(&self.closed_lanes, &create_car.vehicle, map);
+        if !self.driving.start_car_on_lane(
+            self.time,
+            create_car.clone(),
+            map,
+            &self.intersections,
+            &self.parking,
+            &mut self.scheduler,
+            &mut self.travel_stats,
+        ) {
+            return false;
+        }
+        self.trips
+            .agent_starting_trip_leg(AgentID::Car(create_car.vehicle.id), create_car.trip);
+        self.trips.record_leg_start(
+            self.time,
+            create_car.trip,
+            create_car.router.get_path(),
+            create_car.start_dist,
+        );
+        if let Some(parked_car) = create_car.maybe_parked_car {
+            self.parking.remove_parked_car(parked_car);
+        }
+        true
+    }
+
+    fn abort_gridlocked(&mut self, agents: Vec<AgentID>, map: &Map) {
+        for agent in agents {
+            match agent {
+                // Buses don't have a trip to abort.
+                AgentID::Car(car) if car.1 != VehicleType::Bus => {
+                    self.driving.delete_car(
+                        car,
+                        self.time,
+                        map,
+                        &mut self.intersections,
+                        &mut self.scheduler,
+                    );
+                    self.trips.abort_trip_for_car(self.time, car);
+                }
+                _ => {}
+            }
+        }
+    }
+
     pub fn dump_before_abort(&self) {
         println!(
             "********************************************************************************"
@@ -531,6 +898,16 @@ impl Sim {
         }
     }
 
+    // Unlike run_until_done, running out of time isn't a failure. The deadline is absolute.
+    // Returns true if every trip finished.
+    pub fn run_until_done_or_deadline(&mut self, map: &Map, deadline: Duration) -> bool {
+        while !self.is_done() && self.time < deadline {
+            let dt = (deadline - self.time).min(Duration::minutes(1));
+            self.step_in_chunks(map, dt, None, || {});
+        }
+        self.is_done()
+    }
+
     pub fn run_until_expectations_met(
         &mut self,
         map: &Map,
@@ -565,8 +942,8 @@ impl Sim {
 // Savestating
 impl Sim {
     pub fn save(&self) -> String {
-        // If we wanted to be even more reproducible, we'd encode RNG seed, version of code, etc,
-        // but that's overkill right now.
+        // If we wanted to be even more reproducible, we'd encode the version of code, etc, but
+        // that's overkill right now. The RNG seed is part of the savestate.
         let path = format!(
             "../data/save/{}_{}/{}/{}.json",
             self.map_name,
@@ -575,10 +952,38 @@ impl Sim {
             self.time.as_filename()
         );
         abstutil::write_json(&path, &self).expect("Writing sim state failed");
-        println!("Saved to {}", path);
+        if let Some(seed) = self.rng_seed {
+            println!("Saved to {} (RNG seed {})", path, seed);
+        } else {
+            println!("Saved to {}", path);
+        }
         path
     }
 
+    // Returns the path written, or None if this run isn't recording a replay.
+    pub fn save_replay_log(&self) -> Option<String> {
+        let replay = self.replay.as_ref()?;
+        let path = format!("../data/replays/{}/{}.bin", self.map_name, self.run_name);
+        abstutil::write_binary(&path, replay).expect("Writing replay log failed");
+        println!("Saved replay log to {}", path);
+        Some(path)
+    }
+
+    pub fn get_replay_log(&self) -> Option<&ReplayLog> {
+        self.replay.as_ref()
+    }
+
+    fn sample_replay(&mut self, map: &Map) {
+        let positions = self.get_agent_positions(map);
+        let driving = &self.driving;
+        if let Some(ref mut replay) = self.replay {
+            replay.record(positions, |car| {
+                let vehicle = driving.get_vehicle(car).unwrap();
+                (vehicle.class, vehicle.length)
+            });
+        }
+    }
+
     pub fn find_previous_savestate(&self, base_time: Duration) -> Option<String> {
         abstutil::find_prev_file(format!(
             "../data/save/{}_{}/{}/{}.json",
@@ -601,7 +1006,11 @@ impl Sim {
 
     pub fn load_savestate(path: String) -> Result<Sim, std::io::Error> {
         println!("Loading {}", path);
-        abstutil::read_json(&path)
+        let sim: Sim = abstutil::read_json(&path)?;
+        if let Some(seed) = sim.rng_seed {
+            println!("Originally run with RNG seed {}", seed);
+        }
+        Ok(sim)
     }
 }
 
@@ -611,8 +1020,12 @@ impl Sim {
         self.time
     }
 
+    pub fn rng_seed(&self) -> Option<u64> {
+        self.rng_seed
+    }
+
     pub fn is_done(&self) -> bool {
-        self.spawner.is_done() && self.trips.is_done()
+        self.spawner.is_done() && self.trips.is_done() && self.demand.is_none()
     }
 
     pub fn is_empty(&self) -> bool {
@@ -633,6 +1046,22 @@ impl Sim {
         self.trips.get_finished_trips()
     }
 
+    pub fn get_trip_details(&self, id: TripID) -> &Vec<LegDetails> {
+        self.trips.get_trip_details(id)
+    }
+
+    pub fn get_trip_metadata(&self, id: TripID) -> Option<&TripMetadata> {
+        self.trips.get_trip_metadata(id)
+    }
+
+    pub fn finished_trips_csv(&self, map: &Map) -> String {
+        self.trips.finished_trips_csv(map)
+    }
+
+    pub fn save_trips_csv(&self, path: &str, map: &Map) -> std::io::Result<()> {
+        std::fs::write(path, self.finished_trips_csv(map))
+    }
+
     pub fn debug_ped(&self, id: PedestrianID) {
         self.walking.debug_ped(id);
     }
@@ -675,10 +1104,36 @@ impl Sim {
         }
     }
 
+    pub fn get_route_headways(&self, route: BusRouteID) -> Vec<Duration> {
+        self.transit.route_headways(route)
+    }
+
+    // Bikes parked at the rack, plus any on their way there
+    pub fn get_bike_rack_occupancy(&self, rack: BikeRackID) -> usize {
+        self.trips.bike_rack_occupancy(rack)
+    }
+
     pub fn active_agents(&self) -> Vec<AgentID> {
         self.trips.active_agents()
     }
 
+    // Cars, bikes, buses, and pedestrians currently on any lane of the road. Parked cars and agents
+    // still in the intersections at either end don't count.
+    pub fn agents_on_road(&self, r: RoadID, map: &Map) -> Vec<AgentID> {
+        let mut agents = BTreeSet::new();
+        for l in map.get_r(r).all_lanes() {
+            let on = Traversable::Lane(l);
+            agents.extend(self.driving.get_cars_on(on).into_iter().map(AgentID::Car));
+            agents.extend(
+                self.walking
+                    .get_peds_on(on)
+                    .into_iter()
+                    .map(AgentID::Pedestrian),
+            );
+        }
+        agents.into_iter().collect()
+    }
+
     pub fn debug_trip(&self, id: TripID) {
         match self.trips.trip_to_agent(id) {
             Some(AgentID::Car(id)) => self.debug_car(id),
@@ -737,6 +1192,11 @@ impl Sim {
             .or_else(|| self.parking.get_owner_of_car(id))
     }
 
+    // Only for cars currently driving
+    pub fn get_vehicle(&self, id: CarID) -> Option<&Vehicle> {
+        self.driving.get_vehicle(id)
+    }
+
     pub fn get_trip_positions(&mut self, map: &Map) -> &TripPositions {
         if self.trip_positions.is_some() {
             return self.trip_positions.as_ref().unwrap();
@@ -752,6 +1212,26 @@ impl Sim {
         self.trip_positions.as_ref().unwrap()
     }
 
+    // Where the front of every moving agent is right now. Parked cars aren't included.
+    pub fn get_agent_positions(&self, map: &Map) -> BTreeMap<AgentID, (Traversable, Distance)> {
+        let mut positions = BTreeMap::new();
+        self.driving
+            .populate_agent_positions(self.time, &mut positions);
+        self.walking
+            .populate_agent_positions(self.time, map, &mut positions);
+        positions
+    }
+
+    pub fn get_travel_stats(&self) -> &TravelStats {
+        &self.travel_stats
+    }
+
+    // For lanes that somebody crossed from start to end so far, how many agents did and their
+    // average time. Comparing this before and after some map edits shows if a corridor improved.
+    pub fn lane_travel_times(&self) -> HashMap<LaneID, (usize, Duration)> {
+        self.travel_stats.all_lane_travel_times()
+    }
+
     pub fn get_events_since_last_step(&self) -> &Vec<Event> {
         &self.events_since_last_step
     }
@@ -781,6 +1261,14 @@ impl Sim {
         self.intersections.get_accepted_agents(id)
     }
 
+    // None means the signal is in its all-red clearance between cycles.
+    pub fn current_signal_cycle<'a>(
+        &self,
+        signal: &'a ControlTrafficSignal,
+    ) -> (Option<&'a Cycle>, Duration) {
+        self.intersections.current_signal_cycle(self.time, signal)
+    }
+
     pub fn is_in_overtime(&self, id: IntersectionID, map: &Map) -> bool {
         self.intersections.is_in_overtime(self.time, id, map)
     }
@@ -0,0 +1,93 @@
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use geom::Duration;
+use map_model::{IntersectionID, LaneID, Map, Road, Traversable};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+// How many times any car, bike, bus, or pedestrian has entered each lane, including the lane they
+// start on. Turns aren't counted.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct TravelStats {
+    // Indexed by LaneID
+    lane_counts: Vec<usize>,
+    // Indexed by LaneID. How many agents crossed the whole lane, from its start until they began
+    // the next turn, and the sum of the time they took. Agents starting or ending partway along a
+    // lane don't count.
+    lane_times: Vec<(usize, Duration)>,
+    // For each border where cars had to wait for room before appearing, how many waited and the
+    // sum of their waits
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    border_spawn_delays: BTreeMap<IntersectionID, (usize, Duration)>,
+}
+
+impl TravelStats {
+    pub(crate) fn new(map: &Map) -> TravelStats {
+        TravelStats {
+            lane_counts: vec![0; map.all_lanes().len()],
+            lane_times: vec![(0, Duration::ZERO); map.all_lanes().len()],
+            border_spawn_delays: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn agent_entered(&mut self, on: Traversable) {
+        if let Traversable::Lane(l) = on {
+            self.lane_counts[l.0] += 1;
+        }
+    }
+
+    pub(crate) fn lane_traversed(&mut self, l: LaneID, dt: Duration) {
+        let (count, total) = &mut self.lane_times[l.0];
+        *count += 1;
+        *total += dt;
+    }
+
+    pub(crate) fn border_spawn_delayed(&mut self, i: IntersectionID, delay: Duration) {
+        let (count, total) = self
+            .border_spawn_delays
+            .entry(i)
+            .or_insert((0, Duration::ZERO));
+        *count += 1;
+        *total += delay;
+    }
+
+    pub fn lane_count(&self, l: LaneID) -> usize {
+        self.lane_counts[l.0]
+    }
+
+    pub fn road_count(&self, r: &Road) -> usize {
+        r.all_lanes().into_iter().map(|l| self.lane_count(l)).sum()
+    }
+
+    // Only lanes that somebody entered
+    pub fn all_lane_counts(&self) -> Vec<(LaneID, usize)> {
+        self.lane_counts
+            .iter()
+            .enumerate()
+            .filter(|(_, cnt)| **cnt > 0)
+            .map(|(idx, cnt)| (LaneID(idx), *cnt))
+            .collect()
+    }
+
+    // Only lanes that somebody crossed entirely. Returns the number of agents and their average
+    // time.
+    pub fn all_lane_travel_times(&self) -> HashMap<LaneID, (usize, Duration)> {
+        self.lane_times
+            .iter()
+            .enumerate()
+            .filter(|(_, (count, _))| *count > 0)
+            .map(|(idx, (count, total))| (LaneID(idx), (*count, *total * (1.0 / *count as f64))))
+            .collect()
+    }
+    // Only borders where some car had to wait to appear. Returns the number of cars that waited
+    // and the total vehicle-hours they spent waiting. Lots of delay means the demand at that
+    // border is more than its lanes can physically carry.
+    pub fn border_spawn_delays(&self) -> BTreeMap<IntersectionID, (usize, f64)> {
+        self.border_spawn_delays
+            .iter()
+            .map(|(i, (count, total))| (*i, (*count, *total / Duration::minutes(60))))
+            .collect()
+    }
+}
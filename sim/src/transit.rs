@@ -1,13 +1,34 @@
-use crate::{CarID, Event, PedestrianID, Router, Scheduler, TripManager, WalkingSimState};
+use crate::{
+    CarID, Event, PedestrianID, Router, Scheduler, TripManager, WalkingSimState, BUS_LENGTH,
+};
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use geom::{Distance, Duration};
-use map_model::{BusRoute, BusRouteID, BusStopID, Map, Path, PathRequest, Position};
+use map_model::{BusRoute, BusRouteID, BusStopID, LaneType, Map, Path, PathRequest, Position};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 // These index stops along a route, not stops along a single sidewalk.
 type StopIdx = usize;
 
+// Pulling in, opening and closing the doors, and pulling out
+const BASE_DWELL_TIME: Duration = Duration::const_seconds(5.0);
+// Everybody pays at the front door, one at a time
+const TIME_PER_BOARDING: Duration = Duration::const_seconds(3.0);
+// Riders can leave through any of the doors
+const TIME_PER_ALIGHTING: Duration = Duration::const_seconds(2.0);
+const DOORS_FOR_ALIGHTING: usize = 2;
+
+// Per route, only the most recent headways are kept, so long simulations don't grow without bound.
+const MAX_HEADWAYS_PER_ROUTE: usize = 1000;
+
+// How long a bus occupies a stop, given how many riders got on and off there.
+pub fn bus_dwell_time(boarded: usize, alighted: usize) -> Duration {
+    let alighting_waves = (alighted + DOORS_FOR_ALIGHTING - 1) / DOORS_FOR_ALIGHTING;
+    BASE_DWELL_TIME
+        + (boarded as f64) * TIME_PER_BOARDING
+        + (alighting_waves as f64) * TIME_PER_ALIGHTING
+}
+
 #[derive(Serialize, Deserialize, PartialEq)]
 struct StopForRoute {
     id: BusStopID,
@@ -20,6 +41,11 @@ struct StopForRoute {
 struct Route {
     stops: Vec<StopForRoute>,
     buses: Vec<CarID>,
+    // Indexed by StopIdx: when a bus of this route last arrived there
+    last_arrivals: Vec<Option<Duration>>,
+    // Gaps between consecutive buses arriving at the same stop, in the order they happened. Small
+    // gaps mean buses are bunching up. Capped at MAX_HEADWAYS_PER_ROUTE, dropping the oldest.
+    headways: VecDeque<Duration>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq)]
@@ -29,6 +55,9 @@ struct Bus {
     // Where does each passenger want to deboard?
     passengers: Vec<(PedestrianID, BusStopID)>,
     state: BusState,
+    // How many riders got on and off at the current stop. Reset when arriving at the next one.
+    boarded: usize,
+    alighted: usize,
 }
 
 #[derive(Serialize, Deserialize, PartialEq)]
@@ -67,7 +96,8 @@ impl TransitSimState {
     }
 
     // Returns (next stop, start distance on the driving lane, first path, end distance for next
-    // stop) for all of the stops in the route.
+    // stop) for all of the stops in the route, plus one starting at the border if the route was
+    // truncated by the map boundary.
     pub fn create_empty_route(
         &mut self,
         bus_route: &BusRoute,
@@ -77,6 +107,8 @@ impl TransitSimState {
 
         let route = Route {
             buses: Vec::new(),
+            last_arrivals: vec![None; bus_route.stops.len()],
+            headways: VecDeque::new(),
             stops: bus_route
                 .stops
                 .iter()
@@ -94,6 +126,9 @@ impl TransitSimState {
                             end: map.get_bs(bus_route.stops[stop2_idx]).driving_pos,
                             can_use_bike_lanes: false,
                             can_use_bus_lanes: true,
+                            can_use_hov_lanes: true,
+                            can_use_midblock_crossings: false,
+                            avoid_lanes: BTreeSet::new(),
                         })
                         .expect(&format!(
                             "No route between bus stops {:?} and {:?}",
@@ -109,7 +144,7 @@ impl TransitSimState {
                 .collect(),
         };
 
-        let stops = route
+        let mut stops: Vec<(StopIdx, Distance, Path, Distance)> = route
             .stops
             .iter()
             .map(|s| {
@@ -121,6 +156,28 @@ impl TransitSimState {
                 )
             })
             .collect();
+        // Truncated routes also get a bus entering from the border, like other border traffic.
+        if let Some(border) = bus_route.start_border {
+            if let Some(lane) = map
+                .get_i(border)
+                .get_outgoing_lanes(map, LaneType::Driving)
+                .into_iter()
+                .find(|l| map.get_l(*l).length() > BUS_LENGTH)
+            {
+                let first_stop = &route.stops[0];
+                if let Some(path) = map.pathfind(PathRequest {
+                    start: Position::new(lane, BUS_LENGTH),
+                    end: first_stop.driving_pos,
+                    can_use_bike_lanes: false,
+                    can_use_bus_lanes: true,
+                    can_use_hov_lanes: true,
+                    can_use_midblock_crossings: false,
+                    avoid_lanes: BTreeSet::new(),
+                }) {
+                    stops.push((0, BUS_LENGTH, path, first_stop.driving_pos.dist_along()));
+                }
+            }
+        }
         self.routes.insert(bus_route.id, route);
         stops
     }
@@ -134,10 +191,13 @@ impl TransitSimState {
                 route,
                 passengers: Vec::new(),
                 state: BusState::DrivingToStop(next_stop_idx),
+                boarded: 0,
+                alighted: 0,
             },
         );
     }
 
+    // Returns how long the bus should wait at the stop.
     pub fn bus_arrived_at_stop(
         &mut self,
         time: Duration,
@@ -146,13 +206,30 @@ impl TransitSimState {
         walking: &mut WalkingSimState,
         scheduler: &mut Scheduler,
         map: &Map,
-    ) {
+    ) -> Duration {
         let mut bus = self.buses.get_mut(&id).unwrap();
         match bus.state {
             BusState::DrivingToStop(stop_idx) => {
                 bus.state = BusState::AtStop(stop_idx);
-                let stop = self.routes[&bus.route].stops[stop_idx].id;
-                self.events.push(Event::BusArrivedAtStop(id, stop));
+                let route = self.routes.get_mut(&bus.route).unwrap();
+                let stop = route.stops[stop_idx].id;
+                let headway = route.last_arrivals[stop_idx].map(|last| time - last);
+                route.last_arrivals[stop_idx] = Some(time);
+                if let Some(dt) = headway {
+                    if route.headways.len() == MAX_HEADWAYS_PER_ROUTE {
+                        route.headways.pop_front();
+                    }
+                    route.headways.push_back(dt);
+                }
+                self.events.push(Event::BusArrivedAtStop(
+                    id,
+                    bus.route,
+                    stop,
+                    headway,
+                    bus.passengers.len(),
+                ));
+                bus.boarded = 0;
+                bus.alighted = 0;
 
                 // Deboard existing passengers.
                 let mut still_riding = Vec::new();
@@ -160,6 +237,7 @@ impl TransitSimState {
                     if stop == stop2 {
                         self.events.push(Event::PedLeavesBus(ped, id));
                         trips.ped_left_bus(time, ped, map, scheduler);
+                        bus.alighted += 1;
                     } else {
                         still_riding.push((ped, stop2));
                     }
@@ -172,15 +250,25 @@ impl TransitSimState {
                     if stop == stop1 && bus.route == route {
                         bus.passengers.push((ped, stop2));
                         self.events.push(Event::PedEntersBus(ped, id));
-                        trips.ped_boarded_bus(ped, walking);
+                        trips.ped_boarded_bus(time, ped, map, walking);
+                        bus.boarded += 1;
                     } else {
                         still_waiting.push((ped, stop1, route, stop2));
                     }
                 }
                 self.peds_waiting = still_waiting;
+
+                bus_dwell_time(bus.boarded, bus.alighted)
             }
             BusState::AtStop(_) => unreachable!(),
-        };
+        }
+    }
+
+    // Riders can keep boarding while the bus waits, so this may grow past what
+    // bus_arrived_at_stop originally returned.
+    pub fn dwell_time(&self, id: CarID) -> Duration {
+        let bus = &self.buses[&id];
+        bus_dwell_time(bus.boarded, bus.alighted)
     }
 
     pub fn bus_departed_from_stop(&mut self, id: CarID) -> Router {
@@ -192,7 +280,12 @@ impl TransitSimState {
                 let stop = &route.stops[stop_idx];
 
                 bus.state = BusState::DrivingToStop(stop.next_stop_idx);
-                self.events.push(Event::BusDepartedFromStop(id, stop.id));
+                self.events.push(Event::BusDepartedFromStop(
+                    id,
+                    stop.id,
+                    bus.boarded,
+                    bus.alighted,
+                ));
                 Router::follow_bus_route(
                     stop.path_to_next_stop.clone(),
                     route.stops[stop.next_stop_idx].driving_pos.dist_along(),
@@ -214,13 +307,11 @@ impl TransitSimState {
         for bus in &route.buses {
             if let BusState::AtStop(idx) = self.buses[bus].state {
                 if route.stops[idx].id == stop1 {
-                    self.buses
-                        .get_mut(bus)
-                        .unwrap()
-                        .passengers
-                        .push((ped, stop2));
+                    let bus = self.buses.get_mut(bus).unwrap();
+                    bus.passengers.push((ped, stop2));
+                    bus.boarded += 1;
                     // TODO shift trips
-                    self.events.push(Event::PedEntersBus(ped, *bus));
+                    self.events.push(Event::PedEntersBus(ped, bus.car));
                     return true;
                 }
             }
@@ -241,4 +332,12 @@ impl TransitSimState {
     pub fn bus_route(&self, bus: CarID) -> BusRouteID {
         self.buses[&bus].route
     }
+
+    // The most recent headways, oldest first. Empty for routes without any buses yet.
+    pub fn route_headways(&self, route: BusRouteID) -> Vec<Duration> {
+        self.routes
+            .get(&route)
+            .map(|r| r.headways.iter().cloned().collect())
+            .unwrap_or_else(Vec::new)
+    }
 }
@@ -1,13 +1,15 @@
 use crate::{
-    AgentID, CarID, Command, CreateCar, CreatePedestrian, DrivingGoal, Event, ParkingSimState,
-    ParkingSpot, PedestrianID, Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripID,
-    Vehicle, WalkingSimState,
+    AgentID, BikeParkingState, CarID, Command, CreateCar, CreatePedestrian, DrivingGoal, Event,
+    ParkingSimState, ParkingSpot, PedestrianID, Router, Scheduler, SidewalkPOI, SidewalkSpot,
+    TransitSimState, TripID, Vehicle, VehicleType, WalkingSimState,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::{Duration, Speed};
-use map_model::{BuildingID, BusRouteID, BusStopID, IntersectionID, Map, PathRequest};
+use geom::{Distance, Duration, DurationHistogram, Speed};
+use map_model::{
+    BikeRackID, BuildingID, BusRouteID, BusStopID, IntersectionID, Map, Path, PathRequest,
+};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct TripManager {
@@ -20,6 +22,13 @@ pub struct TripManager {
     active_trip_mode: BTreeMap<AgentID, TripID>,
     num_bus_trips: usize,
     unfinished_trips: usize,
+    // Let pedestrians cut across quiet roads mid-block
+    pub(crate) use_midblock_crossings: bool,
+    // Remember the path and timing of every leg, even after it's done. Costs memory.
+    pub(crate) record_trip_details: bool,
+    // Trips that departed before this were placed partway along, so their times are meaningless.
+    pub(crate) warm_start: Option<Duration>,
+    bike_parking: BikeParkingState,
 
     events: Vec<Event>,
 }
@@ -31,11 +40,20 @@ impl TripManager {
             active_trip_mode: BTreeMap::new(),
             num_bus_trips: 0,
             unfinished_trips: 0,
+            use_midblock_crossings: false,
+            record_trip_details: false,
+            warm_start: None,
+            bike_parking: BikeParkingState::new(),
             events: Vec::new(),
         }
     }
 
-    pub fn new_trip(&mut self, spawned_at: Duration, legs: Vec<TripLeg>) -> TripID {
+    pub fn new_trip(
+        &mut self,
+        spawned_at: Duration,
+        legs: Vec<TripLeg>,
+        metadata: Option<TripMetadata>,
+    ) -> TripID {
         assert!(!legs.is_empty());
         // TODO Make sure the legs constitute a valid state machine.
 
@@ -63,6 +81,8 @@ impl TripManager {
             finished_at: None,
             mode,
             legs: VecDeque::from(legs),
+            finished_legs: Vec::new(),
+            metadata,
         };
         if !trip.is_bus_trip() {
             self.unfinished_trips += 1;
@@ -81,6 +101,40 @@ impl TripManager {
         }
     }
 
+    // The agent for the trip's current leg just spawned, following this path.
+    pub fn record_leg_start(
+        &mut self,
+        time: Duration,
+        trip: TripID,
+        path: &Path,
+        start_dist: Distance,
+    ) {
+        if !self.record_trip_details {
+            return;
+        }
+        let trip = &mut self.trips[trip.0];
+        let mode = match trip.legs[0] {
+            TripLeg::Walk(_, _, _) => TripMode::Walk,
+            TripLeg::Drive(ref vehicle, _) => {
+                if vehicle.vehicle_type == VehicleType::Bike {
+                    TripMode::Bike
+                } else {
+                    TripMode::Drive
+                }
+            }
+            // Bus legs start in ped_boarded_bus, and buses themselves aren't interesting.
+            TripLeg::RideBus(_, _, _) | TripLeg::ServeBusRoute(_, _) => {
+                return;
+            }
+        };
+        trip.finished_legs.push(LegDetails {
+            mode,
+            started_at: time,
+            finished_at: None,
+            path: Some((path.clone(), start_dist)),
+        });
+    }
+
     pub fn car_reached_parking_spot(
         &mut self,
         time: Duration,
@@ -97,12 +151,14 @@ impl TripManager {
             Some(TripLeg::Drive(vehicle, DrivingGoal::ParkNear(_))) => assert_eq!(car, vehicle.id),
             _ => unreachable!(),
         };
+        trip.leg_finished(time);
 
         if !trip.spawn_ped(
             time,
             SidewalkSpot::parking_spot(spot, map, parking),
             map,
             scheduler,
+            self.use_midblock_crossings,
         ) {
             self.unfinished_trips -= 1;
         }
@@ -124,7 +180,7 @@ impl TripManager {
             .unwrap()
             .0];
 
-        trip.assert_walking_leg(ped, SidewalkSpot::parking_spot(spot, map, parking));
+        trip.assert_walking_leg(time, ped, SidewalkSpot::parking_spot(spot, map, parking));
         let (car, drive_to) = match trip.legs[0] {
             TripLeg::Drive(ref vehicle, ref to) => (vehicle.id, to.clone()),
             _ => unreachable!(),
@@ -138,7 +194,10 @@ impl TripManager {
             start,
             end,
             can_use_bus_lanes: false,
+            can_use_hov_lanes: parked_car.vehicle.can_use_hov_lanes(),
             can_use_bike_lanes: false,
+            can_use_midblock_crossings: false,
+            avoid_lanes: BTreeSet::new(),
         }) {
             p
         } else {
@@ -174,7 +233,7 @@ impl TripManager {
             .unwrap()
             .0];
 
-        trip.assert_walking_leg(ped, spot.clone());
+        trip.assert_walking_leg(time, ped, spot.clone());
         let (vehicle, drive_to) = match trip.legs[0] {
             TripLeg::Drive(ref vehicle, ref to) => (vehicle.clone(), to.clone()),
             _ => unreachable!(),
@@ -184,12 +243,24 @@ impl TripManager {
             _ => unreachable!(),
         };
 
-        let end = drive_to.goal_pos(map);
+        // Head to a rack with room near the destination. When there isn't one, or they're all
+        // full, just stop nearby.
+        let rack = match drive_to {
+            DrivingGoal::ParkNear(b) => self.bike_parking.reserve_rack_near(vehicle.id, b, map),
+            _ => None,
+        };
+        let end = match rack {
+            Some(r) => map.get_bike_rack(r).driving_pos(map).unwrap(),
+            None => drive_to.goal_pos(map),
+        };
         let path = if let Some(p) = map.pathfind(PathRequest {
             start: driving_pos,
             end,
             can_use_bus_lanes: false,
+            can_use_hov_lanes: false,
             can_use_bike_lanes: true,
+            can_use_midblock_crossings: false,
+            avoid_lanes: BTreeSet::new(),
         }) {
             p
         } else {
@@ -197,11 +268,15 @@ impl TripManager {
                 "Aborting a trip because no path for the bike portion! {:?} to {:?}",
                 driving_pos, end
             );
+            self.bike_parking.cancel_reservation(vehicle.id);
             self.unfinished_trips -= 1;
             return;
         };
 
-        let router = drive_to.make_router(path, map, vehicle.vehicle_type);
+        let router = match rack {
+            Some(r) => Router::bike_to_rack(path, end.dist_along(), r),
+            None => drive_to.make_router(path, map, vehicle.vehicle_type),
+        };
         scheduler.push(
             time,
             Command::SpawnCar(
@@ -216,6 +291,7 @@ impl TripManager {
         time: Duration,
         bike: CarID,
         bike_rack: SidewalkSpot,
+        rack_id: Option<BikeRackID>,
         map: &Map,
         scheduler: &mut Scheduler,
     ) {
@@ -226,15 +302,81 @@ impl TripManager {
         let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(bike)).unwrap().0];
 
         match trip.legs.pop_front() {
-            Some(TripLeg::Drive(vehicle, DrivingGoal::ParkNear(_))) => assert_eq!(vehicle.id, bike),
+            Some(TripLeg::Drive(vehicle, DrivingGoal::ParkNear(b))) => {
+                assert_eq!(vehicle.id, bike);
+                if rack_id.is_some() {
+                    self.bike_parking.bike_left_near(bike, b, vehicle.owner);
+                } else {
+                    // The bike couldn't stop at its rack after all.
+                    self.bike_parking.cancel_reservation(bike);
+                }
+            }
             _ => unreachable!(),
         };
+        trip.leg_finished(time);
 
-        if !trip.spawn_ped(time, bike_rack, map, scheduler) {
+        if !trip.spawn_ped(time, bike_rack, map, scheduler, self.use_midblock_crossings) {
             self.unfinished_trips -= 1;
         }
     }
 
+    // Someone heading back home from a building where they left their bike earlier walks back to
+    // that rack, instead of wherever the trip planned to start biking.
+    pub fn walk_to_parked_bike(&mut self, create_ped: &mut CreatePedestrian, map: &Map) {
+        let bldg = match (&create_ped.start.connection, &create_ped.goal.connection) {
+            (SidewalkPOI::Building(b), SidewalkPOI::BikeRack(_)) => *b,
+            _ => {
+                return;
+            }
+        };
+        let home = match self.trips[create_ped.trip.0].legs.get(1) {
+            Some(TripLeg::Drive(_, DrivingGoal::ParkNear(home))) => *home,
+            _ => {
+                return;
+            }
+        };
+        let spot = match self
+            .bike_parking
+            .parked_bike_near(bldg, home)
+            .and_then(|r| SidewalkSpot::at_bike_rack(r, map).ok())
+        {
+            Some(spot) => spot,
+            None => {
+                return;
+            }
+        };
+        let path = match map.pathfind(PathRequest {
+            start: create_ped.start.sidewalk_pos,
+            end: spot.sidewalk_pos,
+            can_use_bus_lanes: false,
+            can_use_hov_lanes: false,
+            can_use_bike_lanes: false,
+            can_use_midblock_crossings: self.use_midblock_crossings,
+            avoid_lanes: BTreeSet::new(),
+        }) {
+            Some(p) => p,
+            None => {
+                return;
+            }
+        };
+
+        self.bike_parking.retrieve_bike_near(bldg, home);
+        let trip = &mut self.trips[create_ped.trip.0];
+        match trip.legs[0] {
+            TripLeg::Walk(ped, _, ref mut walk_to) => {
+                assert_eq!(ped, create_ped.id);
+                *walk_to = spot.clone();
+            }
+            _ => unreachable!(),
+        }
+        create_ped.goal = spot;
+        create_ped.path = path;
+    }
+
+    pub fn bike_rack_occupancy(&self, rack: BikeRackID) -> usize {
+        self.bike_parking.get_occupancy(rack)
+    }
+
     pub fn ped_reached_building(
         &mut self,
         time: Duration,
@@ -248,7 +390,7 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
-        trip.assert_walking_leg(ped, SidewalkSpot::building(bldg, map));
+        trip.assert_walking_leg(time, ped, SidewalkSpot::building(bldg, map));
         assert!(trip.legs.is_empty());
         assert!(!trip.finished_at.is_some());
         trip.finished_at = Some(time);
@@ -258,6 +400,7 @@ impl TripManager {
     // If true, the pedestrian boarded a bus immediately.
     pub fn ped_reached_bus_stop(
         &mut self,
+        time: Duration,
         ped: PedestrianID,
         stop: BusStopID,
         map: &Map,
@@ -272,10 +415,15 @@ impl TripManager {
             }
             _ => unreachable!(),
         }
+        // Waiting for the bus doesn't count as part of any leg.
+        trip.leg_finished(time);
         match trip.legs[1] {
             TripLeg::RideBus(_, route, stop2) => {
                 if transit.ped_waiting_for_bus(ped, stop, route, stop2) {
                     trip.legs.pop_front();
+                    if self.record_trip_details {
+                        trip.start_riding_bus(time, stop, map);
+                    }
                     true
                 } else {
                     false
@@ -285,10 +433,25 @@ impl TripManager {
         }
     }
 
-    pub fn ped_boarded_bus(&mut self, ped: PedestrianID, walking: &mut WalkingSimState) {
+    pub fn ped_boarded_bus(
+        &mut self,
+        time: Duration,
+        ped: PedestrianID,
+        map: &Map,
+        walking: &mut WalkingSimState,
+    ) {
         // TODO Make sure canonical pt is the bus while the ped is riding it
         let trip = &mut self.trips[self.active_trip_mode[&AgentID::Pedestrian(ped)].0];
-        trip.legs.pop_front();
+        let stop = match trip.legs.pop_front() {
+            Some(TripLeg::Walk(_, _, spot)) => match spot.connection {
+                SidewalkPOI::BusStop(stop) => stop,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        if self.record_trip_details {
+            trip.start_riding_bus(time, stop, map);
+        }
         walking.ped_boarded_bus(ped);
     }
 
@@ -308,8 +471,9 @@ impl TripManager {
             TripLeg::RideBus(_, _, stop) => SidewalkSpot::bus_stop(stop, map),
             _ => unreachable!(),
         };
+        trip.leg_finished(time);
 
-        if !trip.spawn_ped(time, start, map, scheduler) {
+        if !trip.spawn_ped(time, start, map, scheduler, self.use_midblock_crossings) {
             self.unfinished_trips -= 1;
         }
     }
@@ -327,7 +491,7 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
-        trip.assert_walking_leg(ped, SidewalkSpot::end_at_border(i, map).unwrap());
+        trip.assert_walking_leg(time, ped, SidewalkSpot::end_at_border(i, map).unwrap());
         assert!(trip.legs.is_empty());
         assert!(!trip.finished_at.is_some());
         trip.finished_at = Some(time);
@@ -337,6 +501,7 @@ impl TripManager {
     pub fn car_or_bike_reached_border(&mut self, time: Duration, car: CarID, i: IntersectionID) {
         self.events.push(Event::CarOrBikeReachedBorder(car, i));
         let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        trip.leg_finished(time);
         match trip.legs.pop_front().unwrap() {
             TripLeg::Drive(_, DrivingGoal::Border(int, _)) => assert_eq!(i, int),
             _ => {
@@ -345,6 +510,7 @@ impl TripManager {
                     "Aborting trip {}, because {} couldn't find parking and got stuck",
                     trip.id, car
                 );
+                self.bike_parking.cancel_reservation(car);
                 self.unfinished_trips -= 1;
                 return;
             }
@@ -355,6 +521,18 @@ impl TripManager {
         self.unfinished_trips -= 1;
     }
 
+    // The car was removed before it could finish this leg, so give up on the rest of the trip.
+    pub fn abort_trip_for_car(&mut self, time: Duration, car: CarID) {
+        let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        trip.leg_finished(time);
+        println!(
+            "Aborting trip {}, because {} was stuck in gridlock",
+            trip.id, car
+        );
+        self.bike_parking.cancel_reservation(car);
+        self.unfinished_trips -= 1;
+    }
+
     pub fn active_agents(&self) -> Vec<AgentID> {
         self.active_trip_mode.keys().cloned().collect()
     }
@@ -401,17 +579,106 @@ impl TripManager {
         let mut result = FinishedTrips {
             unfinished_trips: self.unfinished_trips,
             finished_trips: Vec::new(),
+            metadata: BTreeMap::new(),
         };
         for t in &self.trips {
+            if self.warmed_up(t) {
+                continue;
+            }
             if let Some(end) = t.finished_at {
                 result
                     .finished_trips
                     .push((t.id, t.mode, end - t.spawned_at));
+                if let Some(ref m) = t.metadata {
+                    result.metadata.insert(t.id, m.clone());
+                }
             }
         }
         result
     }
 
+    // Trips placed by a warm start don't count towards finished trip stats.
+    fn warmed_up(&self, t: &Trip) -> bool {
+        match self.warm_start {
+            Some(at) => t.spawned_at < at,
+            None => false,
+        }
+    }
+
+    // Empty unless record_trip_details was on while the trip happened.
+    pub fn get_trip_details(&self, id: TripID) -> &Vec<LegDetails> {
+        &self.trips[id.0].finished_legs
+    }
+
+    pub fn get_trip_metadata(&self, id: TripID) -> Option<&TripMetadata> {
+        self.trips[id.0].metadata.as_ref()
+    }
+
+    // One row per finished trip, or per leg of each finished trip when details are recorded. The
+    // metadata columns only appear when some trip has metadata. Times are all in seconds. A leg's
+    // distance is along the path planned when it started, and is blank if there wasn't one.
+    pub fn finished_trips_csv(&self, map: &Map) -> String {
+        let with_metadata = self.trips.iter().any(|t| t.metadata.is_some());
+        let mut header = "trip,mode,spawn_time,finish_time,total_time".to_string();
+        if with_metadata {
+            header.push_str(",purpose_from,purpose_to,from_parcel,to_parcel");
+        }
+        if self.record_trip_details {
+            header.push_str(",leg,leg_mode,leg_start,leg_time,leg_dist");
+        }
+        let mut lines = vec![header];
+        for t in self.trips.iter().filter(|t| !self.warmed_up(t)) {
+            let end = match t.finished_at {
+                Some(end) => end,
+                None => {
+                    continue;
+                }
+            };
+            let mut prefix = format!(
+                "{},{:?},{},{},{}",
+                t.id.0,
+                t.mode,
+                t.spawned_at.inner_seconds(),
+                end.inner_seconds(),
+                (end - t.spawned_at).inner_seconds()
+            );
+            if with_metadata {
+                prefix.push_str(&match t.metadata {
+                    Some(ref m) => format!(
+                        ",{:?},{:?},{},{}",
+                        m.purpose.0,
+                        m.purpose.1,
+                        m.from_parcel.map(|id| id.to_string()).unwrap_or_default(),
+                        m.to_parcel.map(|id| id.to_string()).unwrap_or_default()
+                    ),
+                    None => ",,,,".to_string(),
+                });
+            }
+            if !self.record_trip_details {
+                lines.push(prefix);
+                continue;
+            }
+            for (idx, leg) in t.finished_legs.iter().enumerate() {
+                let dist = leg
+                    .path
+                    .as_ref()
+                    .and_then(|(path, start_dist)| path.trace(map, *start_dist, None))
+                    .map(|pl| pl.length().inner_meters().to_string())
+                    .unwrap_or_default();
+                lines.push(format!(
+                    "{},{},{:?},{},{},{}",
+                    prefix,
+                    idx,
+                    leg.mode,
+                    leg.started_at.inner_seconds(),
+                    (leg.finished_at.unwrap() - leg.started_at).inner_seconds(),
+                    dist
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+
     pub fn is_done(&self) -> bool {
         self.unfinished_trips == 0
     }
@@ -428,6 +695,9 @@ struct Trip {
     finished_at: Option<Duration>,
     legs: VecDeque<TripLeg>,
     mode: TripMode,
+    finished_legs: Vec<LegDetails>,
+    // Only for analysis; the simulation never looks at this.
+    metadata: Option<TripMetadata>,
 }
 
 impl Trip {
@@ -446,6 +716,7 @@ impl Trip {
         start: SidewalkSpot,
         map: &Map,
         scheduler: &mut Scheduler,
+        use_midblock_crossings: bool,
     ) -> bool {
         let (ped, speed, walk_to) = match self.legs[0] {
             TripLeg::Walk(ped, speed, ref to) => (ped, speed, to.clone()),
@@ -456,7 +727,10 @@ impl Trip {
             start: start.sidewalk_pos,
             end: walk_to.sidewalk_pos,
             can_use_bus_lanes: false,
+            can_use_hov_lanes: false,
             can_use_bike_lanes: false,
+            can_use_midblock_crossings: use_midblock_crossings,
+            avoid_lanes: BTreeSet::new(),
         }) {
             p
         } else {
@@ -481,7 +755,7 @@ impl Trip {
         true
     }
 
-    fn assert_walking_leg(&mut self, ped: PedestrianID, goal: SidewalkSpot) {
+    fn assert_walking_leg(&mut self, time: Duration, ped: PedestrianID, goal: SidewalkSpot) {
         match self.legs.pop_front() {
             Some(TripLeg::Walk(p, _, spot)) => {
                 assert_eq!(ped, p);
@@ -489,6 +763,42 @@ impl Trip {
             }
             _ => unreachable!(),
         }
+        self.leg_finished(time);
+    }
+
+    // No-op unless record_leg_start or start_riding_bus recorded the leg.
+    fn leg_finished(&mut self, time: Duration) {
+        if let Some(leg) = self.finished_legs.last_mut() {
+            if leg.finished_at.is_none() {
+                leg.finished_at = Some(time);
+            }
+        }
+    }
+
+    // Called after the walking leg to stop1 is popped.
+    fn start_riding_bus(&mut self, time: Duration, stop1: BusStopID, map: &Map) {
+        let stop2 = match self.legs[0] {
+            TripLeg::RideBus(_, _, stop2) => stop2,
+            _ => unreachable!(),
+        };
+        // The bus follows its route, which might stop elsewhere on the way, but this is close
+        // enough for showing the leg.
+        let start = map.get_bs(stop1).driving_pos;
+        let path = map.pathfind(PathRequest {
+            start,
+            end: map.get_bs(stop2).driving_pos,
+            can_use_bus_lanes: true,
+            can_use_hov_lanes: true,
+            can_use_bike_lanes: false,
+            can_use_midblock_crossings: false,
+            avoid_lanes: BTreeSet::new(),
+        });
+        self.finished_legs.push(LegDetails {
+            mode: TripMode::Transit,
+            started_at: time,
+            finished_at: None,
+            path: path.map(|p| (p, start.dist_along())),
+        });
     }
 }
 
@@ -502,11 +812,93 @@ pub enum TripLeg {
     ServeBusRoute(CarID, BusRouteID),
 }
 
+// Only kept when TripManager::record_trip_details is on. A leg is recorded when it starts, so
+// the last one might not be finished yet.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct LegDetails {
+    // Riding the bus is Transit
+    pub mode: TripMode,
+    pub started_at: Duration,
+    pub finished_at: Option<Duration>,
+    // The path planned when the leg started, and the distance along its first step where the leg
+    // began. Drivers looking for parking might wander off of this.
+    pub path: Option<(Path, Distance)>,
+}
+
 // As of a moment in time, not necessarily the end of the simulation
 pub struct FinishedTrips {
     pub unfinished_trips: usize,
     // (..., ..., time to complete trip)
     pub finished_trips: Vec<(TripID, TripMode, Duration)>,
+    // Only for the finished trips that have any
+    pub metadata: BTreeMap<TripID, TripMetadata>,
+}
+
+impl FinishedTrips {
+    // The distribution of trip times, grouped by mode.
+    pub fn per_mode(&self) -> BTreeMap<TripMode, DurationHistogram> {
+        let mut results: BTreeMap<TripMode, DurationHistogram> = BTreeMap::new();
+        for (_, mode, dt) in &self.finished_trips {
+            results
+                .entry(*mode)
+                .or_insert_with(DurationHistogram::default)
+                .add(*dt);
+        }
+        results
+    }
+
+    // The distribution of trip times, grouped by purpose. Trips without metadata are left out.
+    pub fn per_purpose(&self) -> BTreeMap<TripPurpose, DurationHistogram> {
+        let mut results: BTreeMap<TripPurpose, DurationHistogram> = BTreeMap::new();
+        for (id, _, dt) in &self.finished_trips {
+            if let Some(m) = self.metadata.get(id) {
+                results
+                    .entry(m.main_purpose())
+                    .or_insert_with(DurationHistogram::default)
+                    .add(*dt);
+            }
+        }
+        results
+    }
+}
+
+// Why somebody makes a trip, following the activity types in travel surveys
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, PartialOrd, Ord)]
+pub enum TripPurpose {
+    Home,
+    Work,
+    School,
+    Escort,
+    PersonalBusiness,
+    Shopping,
+    Meal,
+    Social,
+    Recreation,
+    Medical,
+    ParkAndRideTransfer,
+}
+
+// Extra information about trips imported from a travel demand model
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct TripMetadata {
+    // The activity at the origin and destination
+    pub purpose: (TripPurpose, TripPurpose),
+    // IDs of the PSRC parcels where the trip starts and ends
+    pub from_parcel: Option<i64>,
+    pub to_parcel: Option<i64>,
+    // The model said the trip departs somewhere in this range
+    pub departure_window: (Duration, Duration),
+}
+
+impl TripMetadata {
+    // Heading home says little about the trip, so use whatever happened at the other end.
+    pub fn main_purpose(&self) -> TripPurpose {
+        if self.purpose.1 == TripPurpose::Home {
+            self.purpose.0
+        } else {
+            self.purpose.1
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, PartialOrd, Ord)]
@@ -140,6 +140,7 @@ impl Road {
         match lt {
             LaneType::Driving => Color::BLACK,
             LaneType::Bus => Color::rgb(190, 74, 76),
+            LaneType::HOV => Color::rgb(120, 80, 160),
             LaneType::Parking => Color::grey(0.2),
             LaneType::Sidewalk => Color::grey(0.8),
             LaneType::Biking => Color::rgb(15, 125, 75),
@@ -336,10 +337,14 @@ impl Model {
                         pt(self.intersections[&r.i1].center),
                         pt(self.intersections[&r.i2].center),
                     ],
+                    osm_node_ids: Vec::new(),
                     osm_tags,
                     osm_way_id: id.0 as i64,
                     parking_lane_fwd: r.lanes.fwd.contains(&LaneType::Parking),
                     parking_lane_back: r.lanes.back.contains(&LaneType::Parking),
+                    parking_hours_fwd: None,
+                    parking_hours_back: None,
+                    speed_limit: None,
                 },
             );
         }
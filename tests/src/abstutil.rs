@@ -0,0 +1,172 @@
+use crate::runner::TestRunner;
+use abstutil::{list_dir_objects, FileWatcher, Timer, TimerRecordKind};
+
+pub fn run(t: &mut TestRunner) {
+    t.run_fast("file_watcher_notices_changes", |_| {
+        let path = std::env::temp_dir().join(format!("file_watcher_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "a").unwrap();
+
+        let mut watcher = FileWatcher::new(path);
+        assert!(!watcher.changed());
+
+        std::fs::write(path, "bb").unwrap();
+        assert!(watcher.changed());
+        // Only reported once
+        assert!(!watcher.changed());
+
+        std::fs::remove_file(path).unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+    });
+
+    t.run_fast("list_dir_objects_sorted_and_deduped", |_| {
+        let dir = std::env::temp_dir().join(format!("list_dir_objects_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in vec!["c.json", "a.bin", "b.bin", "a.json", ".a.json.swp"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+
+        let results = list_dir_objects(dir.to_str().unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            results,
+            vec![
+                ("a".to_string(), "a.json".to_string()),
+                ("b".to_string(), "b.bin".to_string()),
+                ("c".to_string(), "c.json".to_string()),
+            ]
+        );
+    });
+
+    t.run_fast("timer_records_nest_like_the_stack", |_| {
+        let mut timer = Timer::new("outermost");
+        timer.record_timings();
+
+        timer.start_iter("first loop", 2);
+        timer.next();
+        timer.next();
+        timer.start("middle");
+        timer.note("inside middle".to_string());
+        timer.start_iter("inner loop", 3);
+        for _ in 0..3 {
+            timer.next();
+        }
+        timer.start("empty");
+        timer.stop("empty");
+        timer.stop("middle");
+        // Loops with no items don't show up at all.
+        timer.start_iter("nothing to do", 0);
+        timer.start_iter("last loop", 1);
+        timer.next();
+        timer.warn("at the top".to_string());
+
+        let records = timer.timing_records();
+        assert_eq!(records.len(), 1);
+        let root = &records[0];
+        assert_eq!(root.name, "outermost");
+        assert_eq!(root.depth, 0);
+        assert_eq!(root.warnings, vec!["at the top".to_string()]);
+        let names: Vec<&str> = root.children.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["first loop", "middle", "last loop"]);
+
+        let first = &root.children[0];
+        assert_eq!(first.kind, TimerRecordKind::Loop);
+        assert_eq!((first.depth, first.count), (1, 2));
+        assert!(first.children.is_empty());
+
+        let middle = &root.children[1];
+        assert_eq!(middle.kind, TimerRecordKind::Span);
+        assert_eq!((middle.depth, middle.count), (1, 1));
+        assert_eq!(middle.notes, vec!["inside middle".to_string()]);
+        let names: Vec<&str> = middle.children.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["inner loop", "empty"]);
+        assert_eq!(middle.children[0].depth, 2);
+        assert_eq!(middle.children[0].count, 3);
+        assert!(middle.seconds >= middle.children.iter().map(|r| r.seconds).sum::<f64>());
+
+        assert_eq!(timer.find_timing("empty").unwrap().depth, 2);
+        assert!(timer.find_timing("nothing to do").is_none());
+        assert!(timer.done_json().contains("\"inner loop\""));
+    });
+
+    t.run_fast("timer_records_start_from_whatever_is_in_progress", |_| {
+        let mut timer = Timer::new("outermost");
+        timer.start("already going");
+        timer.start_iter("loop", 2);
+        timer.next();
+        timer.record_timings();
+        timer.next();
+        timer.stop("already going");
+
+        let root = &timer.timing_records()[0];
+        assert_eq!(root.children.len(), 1);
+        let span = &root.children[0];
+        assert_eq!(span.name, "already going");
+        assert_eq!(span.children[0].name, "loop");
+        assert_eq!(span.children[0].depth, 2);
+    });
+
+    t.run_fast("timer_run_pair_looks_the_same_in_parallel", |_| {
+        let mut summaries = Vec::new();
+        for parallel in vec![false, true] {
+            let mut timer = Timer::new("outermost");
+            timer.record_timings();
+            timer.collect_warnings();
+            timer.start("pair");
+            let (a, b) = timer.run_pair(
+                parallel,
+                "first",
+                |timer| {
+                    timer.start_iter("loop", 2);
+                    timer.next();
+                    timer.next();
+                    timer.warn("from first".to_string());
+                    1
+                },
+                "second",
+                |timer| {
+                    timer.note("from second".to_string());
+                    2
+                },
+            );
+            timer.stop("pair");
+            assert_eq!((a, b), (1, 2));
+
+            let pair = timer.find_timing("pair").unwrap();
+            let children: Vec<(String, usize, usize)> = pair
+                .children
+                .iter()
+                .map(|r| (r.name.clone(), r.depth, r.children.len()))
+                .collect();
+            assert_eq!(timer.find_timing("loop").unwrap().depth, 3);
+            assert_eq!(
+                timer.find_timing("second").unwrap().notes,
+                vec!["from second".to_string()]
+            );
+            let warnings: Vec<Vec<String>> = timer
+                .take_warnings()
+                .into_iter()
+                .map(|w| w.context)
+                .collect();
+            summaries.push((children, warnings));
+        }
+        assert_eq!(summaries[0], summaries[1]);
+        assert_eq!(
+            summaries[0].1,
+            vec![vec![
+                "outermost".to_string(),
+                "pair".to_string(),
+                "first".to_string()
+            ]]
+        );
+    });
+
+    t.run_fast("timer_records_nothing_unless_asked", |_| {
+        let mut timer = Timer::new("outermost");
+        timer.start("span");
+        timer.stop("span");
+        assert!(timer.timing_records().is_empty());
+        assert_eq!(timer.done_json(), "[]");
+    });
+}
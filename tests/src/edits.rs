@@ -0,0 +1,262 @@
+use crate::fixtures::{build_map, pt, raw_map, road, road_by_osm_id, TmpFile};
+use crate::runner::TestRunner;
+use abstutil::Timer;
+use map_model::{
+    DiffType, IntersectionType, LaneID, LaneType, Map, MapEdits, PathRequest, PathStep, Position,
+    RoadDirectionChange, RoadID,
+};
+use std::collections::BTreeSet;
+
+pub fn run(t: &mut TestRunner) {
+    t.run_fast("edits_diff", |_| {
+        let mut edits1 = MapEdits::new("fake_map".to_string());
+        edits1.edits_name = "edits1".to_string();
+        let mut edits2 = edits1.clone();
+        edits2.edits_name = "edits2".to_string();
+
+        // Only in the first, in both, in both but different, and only in the second
+        edits1.lane_overrides.insert(LaneID(1), LaneType::Bus);
+        edits1.lane_overrides.insert(LaneID(2), LaneType::Bus);
+        edits2.lane_overrides.insert(LaneID(2), LaneType::Bus);
+        edits1.lane_overrides.insert(LaneID(3), LaneType::Bus);
+        edits2.lane_overrides.insert(LaneID(3), LaneType::Biking);
+        edits2.lane_overrides.insert(LaneID(4), LaneType::Biking);
+
+        let diff = edits1.diff(&edits2);
+        assert_eq!(
+            diff.lanes.into_iter().collect::<Vec<_>>(),
+            vec![
+                (LaneID(1), DiffType::Removed),
+                (LaneID(3), DiffType::Changed),
+                (LaneID(4), DiffType::Added),
+            ]
+        );
+        assert!(diff.intersections.is_empty());
+        assert!(edits1.diff(&edits1).is_empty());
+    });
+
+    t.run_fast("flip_road_direction", |_| {
+        // One block of a grid, with a street coming in from the west at the northwest corner and
+        // leaving east from the northeast corner. Normally the way across is along the north side
+        // of the block.
+        let (west, east) = (pt(0.0, 0.002), pt(0.006, 0.002));
+        let (nw, ne, sw, se) = (
+            pt(0.002, 0.002),
+            pt(0.004, 0.002),
+            pt(0.002, 0.0),
+            pt(0.004, 0.0),
+        );
+        let roads = vec![
+            road(1, "residential", vec![west, nw]),
+            road(2, "residential", vec![nw, ne]),
+            road(3, "residential", vec![ne, east]),
+            road(4, "residential", vec![nw, sw]),
+            road(5, "residential", vec![sw, se]),
+            road(6, "residential", vec![se, ne]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        for i in raw.intersections.values_mut() {
+            i.intersection_type = if i.point == west || i.point == east {
+                IntersectionType::Border
+            } else {
+                IntersectionType::StopSign
+            };
+        }
+        let mut map = build_map(
+            "flip_road_direction",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.007, 0.003),
+        );
+
+        let fwd_driving_lane = |map: &Map, osm_way_id: i64| {
+            road_by_osm_id(map, osm_way_id)
+                .children_forwards
+                .iter()
+                .find(|(_, lt)| *lt == LaneType::Driving)
+                .unwrap()
+                .0
+        };
+        let (start, end) = (fwd_driving_lane(&map, 1), fwd_driving_lane(&map, 3));
+        let roads_on_route = |map: &Map| -> BTreeSet<RoadID> {
+            map.pathfind(PathRequest {
+                start: Position::new(start, map.get_l(start).length() / 2.0),
+                end: Position::new(end, map.get_l(end).length() / 2.0),
+                can_use_bike_lanes: false,
+                can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: false,
+                avoid_lanes: BTreeSet::new(),
+            })
+            .unwrap()
+            .get_steps()
+            .iter()
+            .filter_map(|step| match step {
+                PathStep::Lane(l) => Some(map.get_l(*l).parent),
+                _ => None,
+            })
+            .collect()
+        };
+        let (north, south) = (road_by_osm_id(&map, 2).id, road_by_osm_id(&map, 5).id);
+        assert!(roads_on_route(&map).contains(&north));
+        assert!(!roads_on_route(&map).contains(&south));
+
+        // Make the north side of the block one-way heading west, so cars have to go around.
+        assert!(map.can_change_direction(north, RoadDirectionChange::MakeOneWayBackward));
+        let mut edits = map.get_edits().clone();
+        edits
+            .direction_overrides
+            .insert(north, RoadDirectionChange::MakeOneWayBackward);
+        map.apply_edits(edits, &mut Timer::throwaway());
+        assert!(!map
+            .get_r(north)
+            .children_forwards
+            .iter()
+            .any(|(_, lt)| lt.is_for_moving_vehicles()));
+        assert!(!map.can_change_direction(north, RoadDirectionChange::MakeOneWayBackward));
+        for l in map.get_r(north).all_lanes() {
+            let lane = map.get_l(l);
+            assert!(map.get_i(lane.src_i).outgoing_lanes.contains(&l));
+            assert!(map.get_i(lane.dst_i).incoming_lanes.contains(&l));
+        }
+        assert!(!roads_on_route(&map).contains(&north));
+        assert!(roads_on_route(&map).contains(&south));
+
+        // And back again
+        let edits = MapEdits::new(map.get_name().to_string());
+        map.apply_edits(edits, &mut Timer::throwaway());
+        assert!(roads_on_route(&map).contains(&north));
+        assert!(!roads_on_route(&map).contains(&south));
+
+        // Edits from an older version of the map might not fit the road anymore. Those get skipped
+        // instead of crashing.
+        let mut edits = map.get_edits().clone();
+        edits
+            .direction_overrides
+            .insert(north, RoadDirectionChange::MakeTwoWay(5));
+        edits
+            .direction_overrides
+            .insert(south, RoadDirectionChange::MakeOneWayForward);
+        let mut timer = Timer::new("flip_road_direction");
+        timer.collect_warnings();
+        map.apply_edits(edits, &mut timer);
+        assert_eq!(timer.take_warnings().len(), 1);
+        assert_eq!(
+            map.get_edits()
+                .direction_overrides
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![south]
+        );
+        assert!(roads_on_route(&map).contains(&north));
+    });
+
+    t.run_fast("midblock_crossings_follow_edits", |_| {
+        // One quiet street, about 300m long
+        let mut map = build_map(
+            "midblock_crossings_follow_edits",
+            raw_map(
+                vec![road(1, "residential", vec![pt(0.0, 0.0), pt(0.004, 0.0)])],
+                Vec::new(),
+            ),
+            pt(-0.001, -0.001),
+            pt(0.005, 0.001),
+        );
+        let check_crossings = |map: &Map, num_crossed: usize| {
+            assert!(!map.all_midblock_crossings().is_empty());
+            for c in map.all_midblock_crossings() {
+                assert_eq!(c.crossed_lanes.len(), num_crossed);
+                for pos in &c.crossed_lanes {
+                    assert!(map.get_l(pos.lane()).lane_type.is_for_moving_vehicles());
+                }
+                assert!(map.get_l(c.src.lane()).midblock_crossings.contains(&c.id));
+            }
+            let sidewalks: Vec<LaneID> = map
+                .all_lanes()
+                .iter()
+                .filter(|l| l.is_sidewalk())
+                .map(|l| l.id)
+                .collect();
+            let path = map
+                .pathfind(PathRequest {
+                    start: Position::new(sidewalks[0], map.get_l(sidewalks[0]).length() / 2.0),
+                    end: Position::new(sidewalks[1], map.get_l(sidewalks[1]).length() / 2.0),
+                    can_use_bike_lanes: false,
+                    can_use_bus_lanes: false,
+                    can_use_hov_lanes: false,
+                    can_use_midblock_crossings: true,
+                    avoid_lanes: BTreeSet::new(),
+                })
+                .unwrap();
+            assert!(path.get_steps().iter().any(|step| match step {
+                PathStep::MidblockCrossing(c) => {
+                    map.get_mc(*c).crossed_lanes.len() == num_crossed
+                }
+                _ => false,
+            }));
+        };
+        check_crossings(&map, 2);
+
+        // Crossings cut across parking lanes without worrying about them.
+        let driving = map
+            .all_lanes()
+            .iter()
+            .find(|l| l.lane_type == LaneType::Driving)
+            .unwrap()
+            .id;
+        let mut edits = map.get_edits().clone();
+        edits.lane_overrides.insert(driving, LaneType::Parking);
+        map.apply_edits(edits, &mut Timer::throwaway());
+        check_crossings(&map, 1);
+
+        map.apply_edits(
+            MapEdits::new(map.get_name().to_string()),
+            &mut Timer::throwaway(),
+        );
+        check_crossings(&map, 2);
+    });
+
+    t.run_slow("apply_edits_tool", |_| {
+        let mut timer = Timer::throwaway();
+        let map_path = "../data/maps/montlake.bin";
+        let edits_name = "apply_edits_tool";
+
+        let mut expected: Map = abstutil::read_binary(map_path, &mut timer).unwrap();
+        let lane = expected
+            .all_lanes()
+            .iter()
+            .find(|l| l.lane_type == LaneType::Driving)
+            .unwrap()
+            .id;
+        let mut edits = expected.get_edits().clone();
+        edits.edits_name = edits_name.to_string();
+        edits.lane_overrides.insert(lane, LaneType::Bus);
+        // Round-trip the edits through a file too, instead of saving them with the real edits.
+        let edits_file = TmpFile::new("apply_edits_tool.json");
+        abstutil::write_json(edits_file.path(), &edits).unwrap();
+        let saved_edits: MapEdits = abstutil::read_json(edits_file.path()).unwrap();
+        expected.apply_edits(edits, &mut timer);
+        expected.simplify_edits(&mut timer);
+
+        let edited = apply_edits::apply_to_map(
+            abstutil::read_binary(map_path, &mut timer).unwrap(),
+            saved_edits,
+            &mut timer,
+        );
+        let output = TmpFile::new("apply_edits_tool.bin");
+        abstutil::write_binary(output.path(), &edited).unwrap();
+        let reloaded: Map = abstutil::read_binary(output.path(), &mut timer).unwrap();
+
+        assert_eq!(
+            reloaded.get_edits().lane_overrides.get(&lane),
+            Some(&LaneType::Bus)
+        );
+        if abstutil::to_json(&reloaded) != abstutil::to_json(&expected) {
+            panic!(
+                "Applying {} with the tool and in-process differs",
+                edits_name
+            );
+        }
+    });
+}
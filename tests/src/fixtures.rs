@@ -0,0 +1,47 @@
+// The tiny map builders are shared with tools outside the tests, so they live in map_fixtures.
+// Vehicles and scratch files are only for tests.
+
+use geom::Distance;
+pub use map_fixtures::{
+    build_map, building, driving_lane, oneway, pt, raw_map, rectangle, road, road_by_osm_id,
+    set_boundary,
+};
+use sim::{VehicleClass, VehicleSpec, VehicleType};
+
+// An ordinary car, the same every time
+pub fn car() -> VehicleSpec {
+    VehicleSpec {
+        vehicle_type: VehicleType::Car,
+        class: VehicleClass::Car,
+        length: Distance::meters(5.0),
+        max_speed: None,
+        occupancy: 1,
+    }
+}
+
+// A scratch file in the system's temporary directory. It's removed when this goes out of scope, even
+// if the test fails first. Nothing gets created until the test writes to the path.
+pub struct TmpFile {
+    path: String,
+}
+
+impl TmpFile {
+    pub fn new(name: &str) -> TmpFile {
+        // Include the process, so two test runs at the same time don't clobber each other.
+        let path = std::env::temp_dir().join(format!("abst_tests_{}_{}", std::process::id(), name));
+        TmpFile {
+            path: path.to_str().unwrap().to_string(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for TmpFile {
+    fn drop(&mut self) {
+        // The test might've failed before writing anything
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::runner::TestRunner;
-use geom::{Duration, Line, PolyLine, Pt2D};
+use geom::{Distance, Duration, Line, LonLat, PolyLine, Polygon, Pt2D, Ring, EPSILON_DIST};
+use map_model::Neighborhood;
 
 #[allow(clippy::unreadable_literal)]
 pub fn run(t: &mut TestRunner) {
@@ -40,6 +41,62 @@ pub fn run(t: &mut TestRunner) {
         pl.get_slice_ending_at(pt);
     });
 
+    t.run_fast("split_polyline", |_| {
+        let pl = PolyLine::new(vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(10.0, 0.0),
+            Pt2D::new(10.0, 10.0),
+            Pt2D::new(20.0, 10.0),
+        ]);
+
+        // At the boundary between the first and second segments
+        let (first, second) = pl.split_at_dist(Distance::meters(10.0));
+        assert!((first.length() + second.length()).epsilon_eq(pl.length()));
+        assert_eq!(first.points().len(), 2);
+        assert_eq!(second.points().len(), 3);
+        assert!(first.last_pt().epsilon_eq(Pt2D::new(10.0, 0.0)));
+
+        // Halfway through the second segment
+        let (first, second) = pl.split_at_dist(Distance::meters(15.0));
+        assert!((first.length() + second.length()).epsilon_eq(pl.length()));
+        assert!(first.length().epsilon_eq(Distance::meters(15.0)));
+        assert!(first.last_pt().epsilon_eq(second.first_pt()));
+
+        // Off the polyline, so this gets projected onto the same spot
+        let (first, second) = pl.split_at_pt(Pt2D::new(12.0, 5.0)).unwrap();
+        assert!((first.length() + second.length()).epsilon_eq(pl.length()));
+        assert!(first.last_pt().epsilon_eq(Pt2D::new(10.0, 5.0)));
+
+        // One half would be empty
+        assert!(pl.split_at_pt(Pt2D::new(-1.0, 0.0)).is_none());
+        assert!(pl.split_at_pt(Pt2D::new(20.0, 10.0)).is_none());
+    });
+
+    t.run_fast("reverse_polyline_twice", |_| {
+        let polylines = vec![
+            PolyLine::new(vec![Pt2D::new(0.0, 0.0), Pt2D::new(10.0, 0.0)]),
+            PolyLine::new(vec![
+                Pt2D::new(1725.295220788561, 1414.2752785686052),
+                Pt2D::new(1724.6291929910137, 1414.8246144364846),
+                Pt2D::new(1723.888820814687, 1415.6240169312443),
+                Pt2D::new(1723.276510998312, 1416.4750455089877),
+            ]),
+            PolyLine::new(vec![
+                Pt2D::new(147.17832753158294, 1651.034235433578),
+                Pt2D::new(185.9754103560146, 1651.0342354335778),
+                Pt2D::new(185.9754103560146, 1700.5),
+                Pt2D::new(0.25, 1700.75),
+            ]),
+        ];
+        for pl in polylines {
+            let round_trip = pl.reversed().reversed();
+            assert!(round_trip.approx_eq(&pl, Distance::ZERO));
+            assert_eq!(round_trip, pl);
+            assert!(pl.reversed().length().epsilon_eq(pl.length()));
+            assert!(!pl.reversed().approx_eq(&pl, EPSILON_DIST));
+        }
+    });
+
     t.run_fast("time_parsing", |_| {
         assert_eq!(Duration::parse("2.3"), Some(Duration::seconds(2.3)));
         assert_eq!(Duration::parse("02.3"), Some(Duration::seconds(2.3)));
@@ -54,6 +111,221 @@ pub fn run(t: &mut TestRunner) {
             Some(Duration::seconds(3723.5))
         );
     });
+
+    t.run_fast("duration_hms", |_| {
+        for (secs, hms) in vec![
+            (0.0, "0s"),
+            (2.3, "2.3s"),
+            (45.0, "45s"),
+            (60.0, "1m 0s"),
+            (123.5, "2m 3.5s"),
+            (3599.0, "59m 59s"),
+            (3600.0, "1h 0m 0s"),
+            (5025.0, "1h 23m 45s"),
+            (3.0 * 3600.0 + 5.1, "3h 0m 5.1s"),
+            (30.0 * 3600.0 + 60.0, "30h 1m 0s"),
+            (-90.0, "-1m 30s"),
+        ] {
+            let d = Duration::seconds(secs);
+            assert_eq!(d.to_string_hms(), hms);
+            assert_eq!(Duration::parse_hms(hms), Some(d));
+        }
+
+        // Leading units can be left off, but the order is fixed.
+        assert_eq!(
+            Duration::parse_hms("1h 5s"),
+            Some(Duration::seconds(3605.0))
+        );
+        assert_eq!(Duration::parse_hms("5s 1h"), None);
+        assert_eq!(Duration::parse_hms("1m 1m"), None);
+        assert_eq!(Duration::parse_hms("12"), None);
+        assert_eq!(Duration::parse_hms(""), None);
+
+        // Display agrees with the same rounding.
+        assert_eq!(Duration::seconds(5025.3).to_string(), "01:23:45.3");
+        assert_eq!(Duration::seconds(-2.3).to_string(), "-00:00:02.3");
+    });
+
+    t.run_fast("thick_hairpin", |_| {
+        let width = Distance::meters(4.0);
+        let hairpin = PolyLine::new(vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(40.0, 0.0),
+            Pt2D::new(0.0, 8.0),
+        ]);
+        let polygon = hairpin.make_polygons(width);
+        assert!(polygon.area() > 0.0);
+        assert!(!polygon.is_self_intersecting());
+        // The bend is beveled, instead of a miter spiking far past the turn.
+        assert!(polygon.get_bounds().max_x < 40.0 + width.inner_meters());
+        // The first leg keeps its full width until the inner edges meet, instead of tapering all
+        // the way from its start.
+        assert!(polygon.contains_pt(Pt2D::new(20.0, 1.5)));
+
+        // Gentle bends still get a plain miter.
+        let bend = PolyLine::new(vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(40.0, 0.0),
+            Pt2D::new(80.0, 10.0),
+        ]);
+        let polygon = bend.make_polygons(width);
+        assert!(!polygon.is_self_intersecting());
+        assert_eq!(polygon.points().len(), 6);
+    });
+
+    t.run_fast("polygon_area", |_| {
+        let square = Polygon::rectangle_topleft(
+            Pt2D::new(0.0, 0.0),
+            Distance::meters(1.0),
+            Distance::meters(1.0),
+        );
+        assert!((square.area() - 1.0).abs() < 1e-9);
+        assert_eq!(square.triangles().len(), 2);
+
+        // Clockwise or not shouldn't matter
+        for pts in vec![
+            vec![
+                Pt2D::new(0.0, 0.0),
+                Pt2D::new(3.0, 0.0),
+                Pt2D::new(0.0, 4.0),
+            ],
+            vec![
+                Pt2D::new(0.0, 0.0),
+                Pt2D::new(0.0, 4.0),
+                Pt2D::new(3.0, 0.0),
+            ],
+        ] {
+            let triangle = Polygon::new(&pts);
+            assert!((triangle.area() - 6.0).abs() < 1e-9);
+            assert_eq!(triangle.triangles().len(), 1);
+        }
+    });
+
+    t.run_fast("haversine_and_bearing", |_| {
+        // Reference values from https://www.movable-type.co.uk/scripts/latlong.html
+        let close = |d: Distance, meters: f64| (d.inner_meters() - meters).abs() < 1.0;
+
+        // Space Needle to the Fremont Troll
+        let needle = LonLat::new(-122.3493, 47.6205);
+        let troll = LonLat::new(-122.3473, 47.6510);
+        assert!(close(needle.haversine_dist(troll), 3395.0));
+        assert!(close(troll.haversine_dist(needle), 3395.0));
+        assert!((needle.bearing(troll).normalized_degrees() - 2.5).abs() < 0.1);
+
+        // One degree of longitude along the equator, heading due east
+        let origin = LonLat::new(0.0, 0.0);
+        let east = LonLat::new(1.0, 0.0);
+        assert!(close(origin.haversine_dist(east), 111_195.0));
+        assert!((origin.bearing(east).normalized_degrees() - 90.0).abs() < 1e-6);
+        assert!((east.bearing(origin).normalized_degrees() - 270.0).abs() < 1e-6);
+
+        // Due south
+        assert!((origin.bearing(LonLat::new(0.0, -1.0)).normalized_degrees() - 180.0).abs() < 1e-6);
+        assert_eq!(origin.haversine_dist(origin), Distance::ZERO);
+    });
+
+    t.run_fast("neighborhood_containing", |_| {
+        let square = |name: &str, x: f64| Neighborhood {
+            map_name: "test".to_string(),
+            name: name.to_string(),
+            polygon: Polygon::rectangle_topleft(
+                Pt2D::new(x, 0.0),
+                Distance::meters(10.0),
+                Distance::meters(10.0),
+            ),
+        };
+        // The second and third overlap between x=15 and x=20
+        let neighborhoods = vec![
+            square("west", 0.0),
+            square("middle", 10.0),
+            square("east", 15.0),
+        ];
+
+        let lookup =
+            |x: f64, y: f64| Neighborhood::neighborhood_containing(Pt2D::new(x, y), &neighborhoods);
+        assert_eq!(lookup(5.0, 5.0), Some("west".to_string()));
+        assert_eq!(lookup(12.0, 5.0), Some("middle".to_string()));
+        assert_eq!(lookup(17.0, 5.0), Some("middle".to_string()));
+        assert_eq!(lookup(22.0, 5.0), Some("east".to_string()));
+        assert_eq!(lookup(5.0, 15.0), None);
+        assert_eq!(lookup(30.0, 5.0), None);
+    });
+
+    t.run_fast("ring_closes_off", |_| {
+        let square = vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(10.0, 0.0),
+            Pt2D::new(10.0, 10.0),
+            Pt2D::new(0.0, 10.0),
+        ];
+        let ring = Ring::new(square.clone()).unwrap();
+        assert_eq!(ring.points().len(), 5);
+        assert_eq!(ring.points()[0], ring.points()[4]);
+
+        // Already repeating the first point, maybe a few times, is fine
+        let mut repeated = square.clone();
+        repeated.push(square[0]);
+        repeated.push(Pt2D::new(0.0, 0.05));
+        assert_eq!(Ring::new(repeated).unwrap(), ring);
+
+        // But there have to be at least 3 distinct points
+        assert!(Ring::new(vec![square[0], square[1], square[0]]).is_err());
+    });
+
+    t.run_fast("ring_fixes_orientation", |_| {
+        // Clockwise as drawn, since y points down
+        let clockwise = vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(10.0, 0.0),
+            Pt2D::new(10.0, 10.0),
+            Pt2D::new(0.0, 10.0),
+        ];
+        assert_eq!(
+            Ring::new(clockwise.clone()).unwrap().points(),
+            &vec![
+                Pt2D::new(0.0, 0.0),
+                Pt2D::new(10.0, 0.0),
+                Pt2D::new(10.0, 10.0),
+                Pt2D::new(0.0, 10.0),
+                Pt2D::new(0.0, 0.0),
+            ]
+        );
+
+        let mut counter_clockwise = clockwise.clone();
+        counter_clockwise.reverse();
+        assert_eq!(
+            Ring::new(counter_clockwise).unwrap().points(),
+            &vec![
+                Pt2D::new(0.0, 10.0),
+                Pt2D::new(0.0, 0.0),
+                Pt2D::new(10.0, 0.0),
+                Pt2D::new(10.0, 10.0),
+                Pt2D::new(0.0, 10.0),
+            ]
+        );
+    });
+
+    t.run_fast("ring_rejects_self_intersection", |_| {
+        // A bowtie
+        assert!(Ring::new(vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(10.0, 10.0),
+            Pt2D::new(10.0, 0.0),
+            Pt2D::new(0.0, 10.0),
+        ])
+        .is_err());
+
+        // Touching itself at one point
+        assert!(Ring::new(vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(10.0, 0.0),
+            Pt2D::new(5.0, 5.0),
+            Pt2D::new(10.0, 10.0),
+            Pt2D::new(0.0, 10.0),
+            Pt2D::new(5.0, 5.0),
+        ])
+        .is_err());
+    });
 }
 
 // TODO test that shifting lines and polylines is a reversible operation
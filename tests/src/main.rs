@@ -1,24 +1,33 @@
+mod abstutil;
+mod edits;
+mod fixtures;
 mod geom;
 mod map_conversion;
 mod parking;
 mod runner;
 mod sim_completion;
 mod sim_determinism;
+mod traffic_signals;
 mod transit;
 mod trips;
+mod ui;
 
 use structopt::StructOpt;
 
 fn main() {
     let mut t = runner::TestRunner::new(runner::Flags::from_args());
 
+    abstutil::run(t.suite("abstutil"));
+    edits::run(t.suite("edits"));
     geom::run(t.suite("geom"));
     map_conversion::run(t.suite("map_conversion"));
     parking::run(t.suite("parking"));
     sim_completion::run(t.suite("sim_completion"));
     sim_determinism::run(t.suite("sim_determinism"));
+    traffic_signals::run(t.suite("traffic_signals"));
     transit::run(t.suite("transit"));
     trips::run(t.suite("trips"));
+    ui::run(t.suite("ui"));
 
     t.done();
 }
@@ -1,9 +1,615 @@
+use crate::fixtures::{
+    build_map, driving_lane, oneway, pt, raw_map, road, road_by_osm_id, set_boundary, TmpFile,
+};
 use crate::runner::TestRunner;
 use abstutil;
 use convert_osm;
-use map_model;
+use geojson::{GeoJson, Value};
+use geom::{Distance, LonLat, Polygon, Pt2D, Speed, EPSILON_DIST};
+use map_model::{
+    find_mentioned_ids, raw_data, BuildingID, BuildingType, IntersectionID, IntersectionType,
+    LaneID, LaneType, MentionedID, PathRequest, PathStep, Position, RoadID, Traversable, TurnGraph,
+    TurnID, TurnPriority, TurnType, LANE_THICKNESS,
+};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 pub fn run(t: &mut TestRunner) {
+    t.run_fast("find_mentioned_ids", |_| {
+        let r1 = raw_data::StableRoadID(12);
+        let r2 = raw_data::StableRoadID(345);
+        // From make/initial/geometry.rs
+        assert_eq!(
+            find_mentioned_ids(&format!(
+                "{} and {} hit, but the perpendicular never hit the original center line, or \
+                 the trimmed thing is empty",
+                r1, r2
+            )),
+            vec![MentionedID::StableRoad(r1), MentionedID::StableRoad(r2)]
+        );
+        assert_eq!(
+            find_mentioned_ids(&format!(
+                "main polygon endpoints from {}: polyline has 1 point",
+                r2
+            )),
+            vec![MentionedID::StableRoad(r2)]
+        );
+
+        // From make/turns.rs and make/bus_stops.rs
+        let (l1, l2) = (LaneID(7), LaneID(8));
+        assert_eq!(
+            find_mentioned_ids(&format!(
+                "SharedSidewalkCorner between {} and {} has weird duplicate geometry, so just \
+                 doing straight line",
+                l1, l2
+            )),
+            vec![MentionedID::Lane(l1), MentionedID::Lane(l2)]
+        );
+        assert_eq!(
+            find_mentioned_ids(&format!(
+                "Can't find driving lane next to {}: {:?} and {:?}",
+                l1,
+                vec![(l2, LaneType::Sidewalk)],
+                Vec::<(LaneID, LaneType)>::new()
+            )),
+            vec![MentionedID::Lane(l1), MentionedID::Lane(l2)]
+        );
+
+        // From traffic_signals.rs and make/half_map.rs
+        let i = IntersectionID(3);
+        assert_eq!(
+            find_mentioned_ids(&format!("Falling back to greedy_assignment for {}", i)),
+            vec![MentionedID::Intersection(i)]
+        );
+        let r = RoadID(42);
+        assert_eq!(
+            find_mentioned_ids(&format!("{} has no name. Tags: {{}}", r)),
+            vec![MentionedID::Road(r)]
+        );
+        let b = BuildingID(5);
+        assert_eq!(
+            find_mentioned_ids(&format!("{} can't be reached", b)),
+            vec![MentionedID::Building(b)]
+        );
+
+        // Things that look a bit like IDs, but aren't
+        assert_eq!(find_mentioned_ids("u1234 is a very short turn"), Vec::new());
+        assert_eq!(
+            find_mentioned_ids("Skipping route 43 since it only has 1 stop"),
+            Vec::new()
+        );
+        assert_eq!(find_mentioned_ids("BusStopID(3) and RoadID(x)"), Vec::new());
+    });
+
+    t.run_fast("timer_collects_warnings", |_| {
+        let mut timer = abstutil::Timer::new("timer_collects_warnings");
+        timer.warn("ignored".to_string());
+        timer.collect_warnings();
+        timer.start("outer");
+        timer.start_iter("loop", 1);
+        timer.next();
+        timer.warn("first".to_string());
+        timer.stop("outer");
+        timer.warn("second".to_string());
+
+        let warnings = timer.take_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].message, "first");
+        assert_eq!(
+            warnings[0].context,
+            vec!["timer_collects_warnings".to_string(), "outer".to_string()]
+        );
+        assert_eq!(warnings[1].message, "second");
+        assert_eq!(
+            warnings[1].context,
+            vec!["timer_collects_warnings".to_string()]
+        );
+        assert!(timer.take_warnings().is_empty());
+    });
+
+    t.run_fast("parse_maxspeed", |_| {
+        let tmp = TmpFile::new("parse_maxspeed.osm");
+        let path = tmp.path();
+        std::fs::write(
+            path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" visible="true" version="1" lat="47.0" lon="-122.0"/>
+  <node id="2" visible="true" version="1" lat="47.001" lon="-122.0"/>
+  <way id="100" visible="true" version="1">
+    <nd ref="1"/><nd ref="2"/>
+    <tag k="highway" v="residential"/>
+    <tag k="maxspeed" v="30 mph"/>
+  </way>
+  <way id="101" visible="true" version="1">
+    <nd ref="1"/><nd ref="2"/>
+    <tag k="highway" v="residential"/>
+    <tag k="maxspeed" v="50"/>
+  </way>
+  <way id="102" visible="true" version="1">
+    <nd ref="1"/><nd ref="2"/>
+    <tag k="highway" v="residential"/>
+    <tag k="maxspeed" v="very fast"/>
+  </way>
+</osm>"#,
+        )
+        .unwrap();
+        let (roads, _, _, _, _) =
+            convert_osm::osm_to_raw_roads(path, false, &mut abstutil::Timer::throwaway());
+
+        let limit = |id: i64| {
+            roads
+                .iter()
+                .find(|r| r.osm_way_id == id)
+                .unwrap()
+                .speed_limit
+        };
+        assert_eq!(limit(100), Some(Speed::miles_per_hour(30.0)));
+        assert_eq!(limit(101), Some(Speed::km_per_hour(50.0)));
+        // Falls back to a default based on the road type when the map is built
+        assert_eq!(limit(102), None);
+    });
+
+    t.run_fast("bike_avoids_steps", |_| {
+        // Straight north from node 5 to 6, with steps as a shortcut between 1 and 2, and a
+        // residential detour out east through 4 and 3.
+        let tmp = TmpFile::new("bike_avoids_steps.osm");
+        let path = tmp.path();
+        std::fs::write(
+            path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" visible="true" version="1" lat="47.0" lon="-122.0"/>
+  <node id="2" visible="true" version="1" lat="47.0003" lon="-122.0"/>
+  <node id="3" visible="true" version="1" lat="47.0003" lon="-121.999"/>
+  <node id="4" visible="true" version="1" lat="47.0" lon="-121.999"/>
+  <node id="5" visible="true" version="1" lat="46.9997" lon="-122.0"/>
+  <node id="6" visible="true" version="1" lat="47.0006" lon="-122.0"/>
+  <way id="100" visible="true" version="1">
+    <nd ref="5"/><nd ref="1"/>
+    <tag k="highway" v="residential"/>
+  </way>
+  <way id="101" visible="true" version="1">
+    <nd ref="1"/><nd ref="4"/><nd ref="3"/><nd ref="2"/>
+    <tag k="highway" v="residential"/>
+  </way>
+  <way id="102" visible="true" version="1">
+    <nd ref="2"/><nd ref="6"/>
+    <tag k="highway" v="residential"/>
+  </way>
+  <way id="200" visible="true" version="1">
+    <nd ref="1"/><nd ref="2"/>
+    <tag k="highway" v="steps"/>
+  </way>
+</osm>"#,
+        )
+        .unwrap();
+        let (roads, bldgs, areas, signals, _) =
+            convert_osm::osm_to_raw_roads(path, true, &mut abstutil::Timer::throwaway());
+        let raw = convert_osm::split_up_roads(
+            (roads, bldgs, areas, signals),
+            false,
+            &mut abstutil::Timer::throwaway(),
+        );
+        let map = build_map(
+            "bike_avoids_steps",
+            raw,
+            LonLat::new(-122.001, 46.9996),
+            LonLat::new(-121.998, 47.0007),
+        );
+
+        let steps = road_by_osm_id(&map, 200);
+        assert!(steps
+            .all_lanes()
+            .into_iter()
+            .all(|l| map.get_l(l).is_sidewalk() && !map.is_bikeable(l)));
+
+        let start = driving_lane(&map, 100);
+        let end = driving_lane(&map, 102);
+        assert!(map.is_bikeable(start) && map.is_bikeable(end));
+        let route = map
+            .pathfind(PathRequest {
+                start: Position::new(start, Distance::ZERO),
+                end: Position::new(end, map.get_l(end).length()),
+                can_use_bike_lanes: true,
+                can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: false,
+                avoid_lanes: BTreeSet::new(),
+            })
+            .expect("No bike route around the steps");
+        let roads: Vec<i64> = route
+            .get_steps()
+            .iter()
+            .filter_map(|step| match step.as_traversable() {
+                Traversable::Lane(l) => Some(map.get_parent(l).osm_way_id),
+                Traversable::Turn(_) | Traversable::MidblockCrossing(_) => None,
+            })
+            .collect();
+        assert!(roads.contains(&101));
+        assert!(!roads.contains(&200));
+    });
+
+    t.run_fast("traffic_signal_node_tags", |_| {
+        // A 4-way intersection at node 1 tagged as a signal, and a plain T-intersection at node 6.
+        // Node 7 is a signal only for a mid-block pedestrian crossing.
+        let tmp = TmpFile::new("traffic_signal_node_tags.osm");
+        let path = tmp.path();
+        std::fs::write(
+            path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" visible="true" version="1" lat="47.0" lon="-122.0">
+    <tag k="highway" v="traffic_signals"/>
+  </node>
+  <node id="2" visible="true" version="1" lat="47.001" lon="-122.0"/>
+  <node id="3" visible="true" version="1" lat="46.999" lon="-122.0"/>
+  <node id="4" visible="true" version="1" lat="47.0" lon="-122.001"/>
+  <node id="5" visible="true" version="1" lat="47.0" lon="-121.998"/>
+  <node id="6" visible="true" version="1" lat="47.0" lon="-121.999"/>
+  <node id="7" visible="true" version="1" lat="47.0" lon="-121.9995">
+    <tag k="highway" v="traffic_signals"/>
+    <tag k="crossing" v="traffic_signals"/>
+  </node>
+  <node id="8" visible="true" version="1" lat="47.001" lon="-121.999"/>
+  <way id="100" visible="true" version="1">
+    <nd ref="3"/><nd ref="1"/><nd ref="2"/>
+    <tag k="highway" v="residential"/>
+  </way>
+  <way id="101" visible="true" version="1">
+    <nd ref="4"/><nd ref="1"/><nd ref="7"/><nd ref="6"/><nd ref="5"/>
+    <tag k="highway" v="residential"/>
+  </way>
+  <way id="102" visible="true" version="1">
+    <nd ref="6"/><nd ref="8"/>
+    <tag k="highway" v="residential"/>
+  </way>
+</osm>"#,
+        )
+        .unwrap();
+        let (roads, bldgs, areas, signals, _) =
+            convert_osm::osm_to_raw_roads(path, false, &mut abstutil::Timer::throwaway());
+        assert_eq!(signals.len(), 1);
+        let raw = convert_osm::split_up_roads(
+            (roads, bldgs, areas, signals),
+            false,
+            &mut abstutil::Timer::throwaway(),
+        );
+
+        // Node IDs survive splitting, lined up with the points.
+        for r in raw.roads.values() {
+            assert_eq!(r.osm_node_ids.len(), r.points.len());
+        }
+        let piece = raw
+            .roads
+            .values()
+            .find(|r| r.osm_way_id == 101 && r.osm_node_ids.contains(&7))
+            .unwrap();
+        assert_eq!(piece.osm_node_ids, vec![1, 7, 6]);
+
+        let intersection_at = |node: i64| {
+            let r = raw
+                .roads
+                .values()
+                .find(|r| r.osm_node_ids[0] == node)
+                .unwrap();
+            raw.intersections[&r.i1].intersection_type
+        };
+        assert_eq!(intersection_at(1), IntersectionType::TrafficSignal);
+        assert_eq!(intersection_at(6), IntersectionType::StopSign);
+    });
+
+    t.run_fast("synthesize_buildings_from_permits", |_| {
+        // One road running east-west, with no buildings. A permit sits just north of it.
+        let (osm, clip, permits) = (
+            TmpFile::new("synthesize_buildings.osm"),
+            TmpFile::new("synthesize_buildings.poly"),
+            TmpFile::new("synthesize_buildings.kml"),
+        );
+        let (osm_path, clip_path, permits_path) = (osm.path(), clip.path(), permits.path());
+        std::fs::write(
+            osm_path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" visible="true" version="1" lat="47.0" lon="-122.001"/>
+  <node id="2" visible="true" version="1" lat="47.0" lon="-121.999"/>
+  <way id="100" visible="true" version="1">
+    <nd ref="1"/><nd ref="2"/>
+    <tag k="highway" v="residential"/>
+  </way>
+</osm>"#,
+        )
+        .unwrap();
+        std::fs::write(
+            clip_path,
+            "synthesize_buildings
+1
+    -122.002    46.999
+    -121.998    46.999
+    -121.998    47.001
+    -122.002    47.001
+    -122.002    46.999
+END
+END
+",
+        )
+        .unwrap();
+        std::fs::write(
+            permits_path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2">
+<Document>
+<Placemark>
+<ExtendedData><SchemaData schemaUrl="permits">
+<SimpleData name="net_units">12</SimpleData>
+</SchemaData></ExtendedData>
+<Point><coordinates>-122.0,47.0003</coordinates></Point>
+</Placemark>
+</Document>
+</kml>"#,
+        )
+        .unwrap();
+        let flags = convert_osm::Flags {
+            osm: osm_path.to_string(),
+            traffic_signals: "".to_string(),
+            residential_buildings: permits_path.to_string(),
+            parking_shapes: "".to_string(),
+            gtfs: "".to_string(),
+            neighborhoods: "".to_string(),
+            clip: clip_path.to_string(),
+            output: "synthesize_buildings.bin".to_string(),
+            fast_dev: false,
+            circulating_roundabouts: false,
+            import_steps: false,
+            synthesize_buildings: true,
+            normalize_road_names: false,
+        };
+        let raw = convert_osm::convert(&flags, &mut abstutil::Timer::throwaway());
+        assert_eq!(raw.buildings.len(), 1);
+
+        let map = map_model::Map::create_from_raw(
+            "synthesize_buildings".to_string(),
+            raw,
+            &mut abstutil::Timer::throwaway(),
+        );
+        assert_eq!(map.all_buildings().len(), 1);
+        let b = &map.all_buildings()[0];
+        assert_eq!(b.osm_tags.get("synthetic"), Some(&"true".to_string()));
+        assert_eq!(b.num_residential_units, Some(12));
+        assert_eq!(b.building_type, BuildingType::Residence);
+        // Placed next to the permit, but not on top of the road
+        let road = map
+            .building_to_road(b.id)
+            .center_pts
+            .make_polygons(LANE_THICKNESS * 4.0);
+        assert!(!b.polygon.points().iter().any(|pt| road.contains_pt(*pt)));
+    });
+
+    t.run_fast("classify_building_types", |_| {
+        let classify = |units: Option<usize>, tags: Vec<(&str, &str)>| {
+            let tags: BTreeMap<String, String> = tags
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            BuildingType::classify(units, &tags)
+        };
+        assert_eq!(
+            classify(None, vec![("building", "house")]),
+            BuildingType::Residence
+        );
+        assert_eq!(
+            classify(None, vec![("building", "yes"), ("shop", "bakery")]),
+            BuildingType::Retail
+        );
+        assert_eq!(
+            classify(None, vec![("building", "yes"), ("office", "company")]),
+            BuildingType::Office
+        );
+        assert_eq!(
+            classify(None, vec![("building", "commercial")]),
+            BuildingType::Office
+        );
+        assert_eq!(
+            classify(None, vec![("building", "warehouse")]),
+            BuildingType::Industrial
+        );
+        // The school tag beats the generic building one
+        assert_eq!(
+            classify(
+                None,
+                vec![("amenity", "school"), ("building", "residential")]
+            ),
+            BuildingType::School
+        );
+        // Shops on the ground floor of apartments
+        assert_eq!(
+            classify(
+                None,
+                vec![("building", "apartments"), ("shop", "convenience")]
+            ),
+            BuildingType::Residence
+        );
+        // Permits say people live there, whatever OSM thinks
+        assert_eq!(
+            classify(Some(3), vec![("building", "retail")]),
+            BuildingType::Residence
+        );
+        assert_eq!(
+            classify(None, vec![("building", "yes")]),
+            BuildingType::Unknown
+        );
+    });
+
+    t.run_fast("corner_building_front_paths", |_| {
+        // Main Street runs east-west, and Oak Street heads north from the middle of it. Building
+        // 300 is long and thin, sitting northeast of the corner with its long wall along Oak, but
+        // its center is slightly closer to Main. Building 301 is northwest of the corner, much
+        // closer to Main, but addressed on Oak. Building 302 is southeast, addressed on a street
+        // that isn't nearby.
+        let tmp = TmpFile::new("corner_building_front_paths.osm");
+        let path = tmp.path();
+        std::fs::write(
+            path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" visible="true" version="1" lat="47.0" lon="-122.001"/>
+  <node id="2" visible="true" version="1" lat="47.0" lon="-122.0"/>
+  <node id="3" visible="true" version="1" lat="47.0" lon="-121.999"/>
+  <node id="4" visible="true" version="1" lat="47.001" lon="-122.0"/>
+  <node id="10" visible="true" version="1" lat="47.000171" lon="-121.999605"/>
+  <node id="11" visible="true" version="1" lat="47.000171" lon="-121.999394"/>
+  <node id="12" visible="true" version="1" lat="47.000441" lon="-121.999394"/>
+  <node id="13" visible="true" version="1" lat="47.000441" lon="-121.999605"/>
+  <node id="14" visible="true" version="1" lat="47.000135" lon="-122.000659"/>
+  <node id="15" visible="true" version="1" lat="47.000135" lon="-122.000329"/>
+  <node id="16" visible="true" version="1" lat="47.000225" lon="-122.000329"/>
+  <node id="17" visible="true" version="1" lat="47.000225" lon="-122.000659"/>
+  <node id="18" visible="true" version="1" lat="46.999775" lon="-121.999737"/>
+  <node id="19" visible="true" version="1" lat="46.999775" lon="-121.999605"/>
+  <node id="20" visible="true" version="1" lat="46.999865" lon="-121.999605"/>
+  <node id="21" visible="true" version="1" lat="46.999865" lon="-121.999737"/>
+  <way id="100" visible="true" version="1">
+    <nd ref="1"/><nd ref="2"/><nd ref="3"/>
+    <tag k="highway" v="residential"/>
+    <tag k="name" v="Main Street"/>
+  </way>
+  <way id="101" visible="true" version="1">
+    <nd ref="2"/><nd ref="4"/>
+    <tag k="highway" v="residential"/>
+    <tag k="name" v="Oak Street"/>
+  </way>
+  <way id="300" visible="true" version="1">
+    <nd ref="10"/><nd ref="11"/><nd ref="12"/><nd ref="13"/><nd ref="10"/>
+    <tag k="building" v="yes"/>
+  </way>
+  <way id="301" visible="true" version="1">
+    <nd ref="14"/><nd ref="15"/><nd ref="16"/><nd ref="17"/><nd ref="14"/>
+    <tag k="building" v="yes"/>
+    <tag k="addr:street" v="oak  street"/>
+  </way>
+  <way id="302" visible="true" version="1">
+    <nd ref="18"/><nd ref="19"/><nd ref="20"/><nd ref="21"/><nd ref="18"/>
+    <tag k="building" v="yes"/>
+    <tag k="addr:street" v="Elm Street"/>
+  </way>
+</osm>"#,
+        )
+        .unwrap();
+        let (roads, bldgs, areas, signals, _) =
+            convert_osm::osm_to_raw_roads(path, false, &mut abstutil::Timer::throwaway());
+        let raw = convert_osm::split_up_roads(
+            (roads, bldgs, areas, signals),
+            false,
+            &mut abstutil::Timer::throwaway(),
+        );
+        let map = build_map(
+            "corner_building_front_paths",
+            raw,
+            LonLat::new(-122.0015, 46.9995),
+            LonLat::new(-121.9985, 47.0015),
+        );
+        assert_eq!(map.all_buildings().len(), 3);
+
+        let bldg = |id: i64| {
+            map.all_buildings()
+                .iter()
+                .find(|b| b.osm_way_id == id)
+                .unwrap()
+        };
+        let sidewalk_road = |id: i64| map.building_to_road(bldg(id).id).osm_way_id;
+
+        // The long wall faces Oak, even though Main is closer.
+        assert_eq!(sidewalk_road(300), 101);
+        // The address wins over distance.
+        assert_eq!(sidewalk_road(301), 101);
+        // An address that doesn't match anything nearby falls back to the closest sidewalk.
+        assert_eq!(sidewalk_road(302), 100);
+    });
+
+    t.run_fast("blockface_under_bridge", |_| {
+        // A street running east-west, with a bridge passing just north of it that comes down to
+        // meet the street at the east end. The blockface on the street's north side is closer to
+        // the bridge deck in 2D.
+        let (osm, clip, blockface) = (
+            TmpFile::new("blockface_under_bridge.osm"),
+            TmpFile::new("blockface_under_bridge.poly"),
+            TmpFile::new("blockface_under_bridge.bin"),
+        );
+        let (osm_path, clip_path, blockface_path) = (osm.path(), clip.path(), blockface.path());
+        std::fs::write(
+            osm_path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" visible="true" version="1" lat="47.0" lon="-122.001"/>
+  <node id="2" visible="true" version="1" lat="47.0" lon="-121.999"/>
+  <node id="3" visible="true" version="1" lat="47.000005" lon="-122.001"/>
+  <node id="4" visible="true" version="1" lat="47.000005" lon="-121.9995"/>
+  <way id="100" visible="true" version="1">
+    <nd ref="1"/><nd ref="2"/>
+    <tag k="highway" v="residential"/>
+  </way>
+  <way id="200" visible="true" version="1">
+    <nd ref="3"/><nd ref="4"/><nd ref="2"/>
+    <tag k="highway" v="primary"/>
+    <tag k="bridge" v="yes"/>
+  </way>
+</osm>"#,
+        )
+        .unwrap();
+        std::fs::write(
+            clip_path,
+            "blockface_under_bridge
+1
+    -122.002    46.999
+    -121.998    46.999
+    -121.998    47.001
+    -122.002    47.001
+    -122.002    46.999
+END
+END
+",
+        )
+        .unwrap();
+        let mut attributes = BTreeMap::new();
+        attributes.insert(
+            "PARKING_CATEGORY".to_string(),
+            "Unrestricted Parking".to_string(),
+        );
+        abstutil::write_binary(
+            blockface_path,
+            &kml::ExtraShapes {
+                shapes: vec![kml::ExtraShape {
+                    points: vec![
+                        LonLat::new(-122.0005, 47.000027),
+                        LonLat::new(-121.9995, 47.000027),
+                    ],
+                    attributes,
+                }],
+            },
+        )
+        .unwrap();
+        let flags = convert_osm::Flags {
+            osm: osm_path.to_string(),
+            traffic_signals: "".to_string(),
+            residential_buildings: "".to_string(),
+            parking_shapes: blockface_path.to_string(),
+            gtfs: "".to_string(),
+            neighborhoods: "".to_string(),
+            clip: clip_path.to_string(),
+            output: "blockface_under_bridge.bin".to_string(),
+            fast_dev: false,
+            circulating_roundabouts: false,
+            import_steps: false,
+            synthesize_buildings: false,
+            normalize_road_names: false,
+        };
+        let raw = convert_osm::convert(&flags, &mut abstutil::Timer::throwaway());
+
+        let road = |id: i64| raw.roads.values().find(|r| r.osm_way_id == id).unwrap();
+        assert_eq!(road(200).get_zorder(), 1);
+        assert!(road(100).parking_lane_back);
+        assert!(!road(200).parking_lane_fwd && !road(200).parking_lane_back);
+    });
+
     t.run_slow("convert_osm_twice", |_| {
         let flags = convert_osm::Flags {
             osm: "../data/input/montlake.osm".to_string(),
@@ -15,6 +621,10 @@ pub fn run(t: &mut TestRunner) {
             clip: "../data/polygons/montlake.poly".to_string(),
             output: "convert_osm_twice.bin".to_string(),
             fast_dev: false,
+            circulating_roundabouts: false,
+            import_steps: false,
+            synthesize_buildings: false,
+            normalize_road_names: false,
         };
 
         let map1 = convert_osm::convert(&flags, &mut abstutil::Timer::throwaway());
@@ -48,6 +658,46 @@ pub fn run(t: &mut TestRunner) {
         }
     });
 
+    t.run_slow("raw_to_map_in_parallel", |_| {
+        let serial = map_model::Map::new_maybe_parallel(
+            "../data/raw_maps/montlake.bin",
+            false,
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+        let parallel = map_model::Map::new_maybe_parallel(
+            "../data/raw_maps/montlake.bin",
+            true,
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+
+        if abstutil::to_json(&serial) != abstutil::to_json(&parallel) {
+            // Kept around to diff, so not a TmpFile
+            let dir = std::env::temp_dir();
+            let serial_path = dir.join("serial_map.json");
+            let parallel_path = dir.join("parallel_map.json");
+            abstutil::write_json(serial_path.to_str().unwrap(), &serial).unwrap();
+            abstutil::write_json(parallel_path.to_str().unwrap(), &parallel).unwrap();
+            panic!(
+                "{} and {} differ",
+                serial_path.display(),
+                parallel_path.display()
+            );
+        }
+    });
+
+    t.run_slow("turn_lengths_cached", |_| {
+        let map = map_model::Map::new(
+            "../data/raw_maps/montlake.bin",
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+        for t in map.all_turns().values() {
+            assert_eq!(t.length, t.geom.length(), "{} has a stale length", t.id);
+        }
+    });
+
     t.run_slow("bigger_map_loads", |_| {
         map_model::Map::new(
             "../data/raw_maps/23rd.bin",
@@ -63,4 +713,867 @@ pub fn run(t: &mut TestRunner) {
         )
         .expect("huge_seattle broke");
     });
+
+    t.run_slow("export_geojson", |_| {
+        let map = map_model::Map::new(
+            "../data/maps/montlake.bin",
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+        let tmp = TmpFile::new("export_geojson.geojson");
+        let path = tmp.path();
+        map.export_geojson(path).unwrap();
+
+        let (mut roads, mut intersections, mut buildings) = (0, 0, 0);
+        let document: GeoJson = abstutil::read_json(path).unwrap();
+        match document {
+            GeoJson::FeatureCollection(c) => {
+                for f in c.features {
+                    match f.geometry.unwrap().value {
+                        Value::LineString(_) => roads += 1,
+                        Value::Point(_) => intersections += 1,
+                        Value::Polygon(_) => buildings += 1,
+                        x => panic!("Unexpected GeoJson value {:?}", x),
+                    }
+                }
+            }
+            x => panic!("Unexpected GeoJson root {:?}", x),
+        }
+
+        assert_eq!(roads, map.all_roads().len());
+        assert_eq!(intersections, map.all_intersections().len());
+        assert_eq!(buildings, map.all_buildings().len());
+    });
+
+    t.run_slow("export_turn_graph", |_| {
+        let map = map_model::Map::new(
+            "../data/maps/montlake.bin",
+            &mut abstutil::Timer::throwaway(),
+        )
+        .unwrap();
+        let tmp = TmpFile::new("export_turn_graph.json");
+        let path = tmp.path();
+        map.export_turn_graph(path).unwrap();
+        let graph: TurnGraph = abstutil::read_json(path).unwrap();
+
+        let allowed: Vec<&TurnID> = map
+            .all_turns()
+            .keys()
+            .filter(|t| map.is_turn_allowed(**t))
+            .collect();
+        assert_eq!(graph.nodes.len(), map.all_lanes().len());
+        assert_eq!(graph.links.len(), allowed.len());
+        // Some turns are banned, so the filtering actually did something.
+        assert!(allowed.len() < map.all_turns().len());
+        for link in &graph.links {
+            assert!(link.source < graph.nodes.len() && link.target < graph.nodes.len());
+        }
+    });
+
+    t.run_fast("sidewalk_corners_stay_off_other_lanes", |_| {
+        // Three intersections where roads meet at sharp angles, which used to get corners drawn
+        // over their lanes: a Y, a diagonal crossing, and a lopsided four-way.
+        let roads = vec![
+            road(1, "residential", vec![pt(0.0, 0.001), pt(0.002, 0.001)]),
+            road(2, "residential", vec![pt(0.002, 0.001), pt(0.004, 0.0017)]),
+            road(3, "residential", vec![pt(0.002, 0.001), pt(0.004, 0.0003)]),
+            road(4, "residential", vec![pt(0.006, 0.001), pt(0.008, 0.001)]),
+            road(5, "residential", vec![pt(0.008, 0.001), pt(0.010, 0.001)]),
+            road(6, "residential", vec![pt(0.0065, 0.0001), pt(0.008, 0.001)]),
+            road(7, "residential", vec![pt(0.008, 0.001), pt(0.0095, 0.0019)]),
+            road(8, "residential", vec![pt(0.012, 0.001), pt(0.014, 0.001)]),
+            road(9, "residential", vec![pt(0.014, 0.001), pt(0.016, 0.0016)]),
+            road(
+                10,
+                "residential",
+                vec![pt(0.014, 0.001), pt(0.0145, 0.0025)],
+            ),
+            road(11, "residential", vec![pt(0.014, 0.001), pt(0.0155, 0.0)]),
+        ];
+        let map = build_map(
+            "sidewalk_corners_stay_off_other_lanes",
+            raw_map(roads, Vec::new()),
+            pt(-0.001, -0.001),
+            pt(0.017, 0.003),
+        );
+        // Clipping leaves slivers along the edges it cuts. Allow about one EPSILON_DIST along the
+        // width of a lane.
+        let max_overlap = EPSILON_DIST.inner_meters() * LANE_THICKNESS.inner_meters();
+
+        for center in vec![pt(0.002, 0.001), pt(0.008, 0.001), pt(0.014, 0.001)] {
+            let center = Pt2D::from_gps(center, map.get_gps_bounds()).unwrap();
+            let i = map
+                .all_intersections()
+                .iter()
+                .find(|i| i.polygon.contains_pt(center))
+                .unwrap();
+            let mut timer = abstutil::Timer::throwaway();
+            timer.collect_warnings();
+            let corners = editor::calculate_corners(i, &map, &mut timer);
+            // Falling back draws the old corners, which cover lanes here.
+            let warnings: Vec<String> = timer
+                .take_warnings()
+                .into_iter()
+                .map(|w| w.message)
+                .collect();
+            assert!(
+                warnings.is_empty(),
+                "Corners at {} fell back: {:?}",
+                i.id,
+                warnings
+            );
+            assert!(!corners.is_empty());
+            for l in i.incoming_lanes.iter().chain(i.outgoing_lanes.iter()) {
+                let lane = Polygon::new(
+                    &map.get_l(*l)
+                        .lane_center_pts
+                        .to_thick_boundary_pts(LANE_THICKNESS),
+                );
+                let overlap: f64 = corners
+                    .iter()
+                    .flat_map(|corner| corner.intersection(&lane))
+                    .map(|piece| piece.area())
+                    .sum();
+                assert!(
+                    overlap < max_overlap,
+                    "Sidewalk corners at {} cover {} square meters of {}",
+                    i.id,
+                    overlap,
+                    l
+                );
+            }
+        }
+    });
+
+    t.run_fast("connectivity_audit_flags_orphaned_lane", |_| {
+        // A T intersection with dead-ends at the other end of each arm
+        let roads = vec![
+            road(1, "residential", vec![pt(0.0, 0.001), pt(0.002, 0.001)]),
+            road(2, "residential", vec![pt(0.002, 0.001), pt(0.004, 0.001)]),
+            road(3, "residential", vec![pt(0.002, 0.001), pt(0.002, 0.003)]),
+        ];
+        let mut map = build_map(
+            "connectivity_audit_flags_orphaned_lane",
+            raw_map(roads, Vec::new()),
+            pt(-0.001, 0.0),
+            pt(0.005, 0.004),
+        );
+
+        let before = map.connectivity_audit();
+        assert!(before.no_incoming_turns.is_empty());
+        assert!(before.no_outgoing_turns.is_empty());
+
+        // Ban every turn into the eastbound lane leaving the T.
+        let center = map
+            .all_intersections()
+            .iter()
+            .find(|i| i.roads.len() == 3)
+            .unwrap()
+            .id;
+        let lane = driving_lane(&map, 2);
+        assert_eq!(map.get_l(lane).src_i, center);
+        let mut ss = map.get_stop_sign(center).clone();
+        for t in &map.get_i(center).turns {
+            if t.dst == lane {
+                ss.change(*t, TurnPriority::Banned, &map);
+            }
+        }
+        let mut edits = map.get_edits().clone();
+        edits.stop_sign_overrides.insert(center, ss);
+        map.apply_edits(edits, &mut abstutil::Timer::throwaway());
+
+        let after = map.connectivity_audit();
+        assert_eq!(after.no_incoming_turns, vec![lane]);
+        // The U-turn at the dead-end still leads out of it.
+        assert!(after.no_outgoing_turns.is_empty());
+        assert!(after.problems().contains(&(
+            format!("No allowed turns lead into {}", lane),
+            MentionedID::Lane(lane)
+        )));
+    });
+
+    t.run_fast("half_map_reports_orphaned_lanes", |_| {
+        // Two one-way streets that both lead into the same intersection, with no way out
+        let roads = vec![
+            oneway(1, vec![pt(0.0, 0.0), pt(0.002, 0.0)]),
+            oneway(2, vec![pt(0.004, 0.0), pt(0.002, 0.0)]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        set_boundary(&mut raw, pt(-0.001, -0.001), pt(0.005, 0.001));
+        let mut timer = abstutil::Timer::new("half_map_reports_orphaned_lanes");
+        timer.collect_warnings();
+        let map = map_model::Map::create_from_raw(
+            "half_map_reports_orphaned_lanes".to_string(),
+            raw,
+            &mut timer,
+        );
+        let warnings: Vec<String> = timer
+            .take_warnings()
+            .into_iter()
+            .map(|w| w.message)
+            .collect();
+
+        let trapped: Vec<LaneID> = map
+            .all_lanes()
+            .iter()
+            .filter(|l| l.lane_type == LaneType::Driving)
+            .map(|l| l.id)
+            .collect();
+        assert_eq!(trapped.len(), 2);
+        for l in &trapped {
+            assert!(
+                warnings.contains(&format!("No turns lead out of {}", l)),
+                "{} wasn't reported. Warnings: {:?}",
+                l,
+                warnings
+            );
+        }
+        // Both start at borders, so only the way out is missing.
+        assert!(!warnings.iter().any(|w| w.starts_with("No turns lead into")));
+        // And the audit on the finished map agrees.
+        assert_eq!(map.connectivity_audit().no_outgoing_turns, trapped);
+    });
+
+    t.run_fast("circulating_roundabout", |_| {
+        // A roundabout about 80m across, with an approach at every other point around the circle
+        let center = LonLat::new(-122.3, 47.6);
+        let ring_pt = |idx: usize, scale: f64| {
+            let angle = (idx as f64) * std::f64::consts::PI / 4.0;
+            LonLat::new(
+                center.longitude + scale * 0.00053 * angle.cos(),
+                center.latitude + scale * 0.00036 * angle.sin(),
+            )
+        };
+        let mut circle = road(
+            1,
+            "residential",
+            (0..=8).map(|idx| ring_pt(idx, 1.0)).collect(),
+        );
+        circle
+            .osm_tags
+            .insert("junction".to_string(), "roundabout".to_string());
+        let mut roads = vec![circle];
+        for idx in &[0, 2, 4, 6] {
+            roads.push(road(
+                10 + *idx as i64,
+                "residential",
+                vec![ring_pt(*idx, 4.0), ring_pt(*idx, 1.0)],
+            ));
+        }
+
+        let mut raw = convert_osm::split_up_roads(
+            (roads, Vec::new(), Vec::new(), HashSet::new()),
+            true,
+            &mut abstutil::Timer::throwaway(),
+        );
+
+        // The circle is split into 4 roads, each starting where the previous one ends.
+        let ring: Vec<&raw_data::Road> = raw.roads.values().filter(|r| r.osm_way_id == 1).collect();
+        assert_eq!(ring.len(), 4);
+        for r1 in &ring {
+            assert_eq!(ring.iter().filter(|r2| r2.i1 == r1.i2).count(), 1);
+        }
+
+        raw.boundary_polygon = vec![
+            ring_pt(0, 5.0),
+            ring_pt(2, 5.0),
+            ring_pt(4, 5.0),
+            ring_pt(6, 5.0),
+            ring_pt(0, 5.0),
+        ];
+        raw.compute_gps_bounds();
+        let map = map_model::Map::create_from_raw(
+            "circulating_roundabout".to_string(),
+            raw,
+            &mut abstutil::Timer::throwaway(),
+        );
+
+        let mut entries = 0;
+        for i in map.all_intersections() {
+            if !i.roads.iter().any(|r| map.get_r(*r).is_roundabout()) {
+                continue;
+            }
+            let ss = map.get_stop_sign(i.id);
+            for t in &i.turns {
+                if map.get_l(t.src).lane_type != LaneType::Driving {
+                    continue;
+                }
+                if map.get_parent(t.src).is_roundabout() {
+                    assert_eq!(ss.get_priority(*t), TurnPriority::Priority);
+                } else {
+                    assert_eq!(ss.get_priority(*t), TurnPriority::Yield);
+                    entries += 1;
+                }
+            }
+
+            // Circulating traffic can keep going around.
+            for l in &i.incoming_lanes {
+                if map.get_parent(*l).is_roundabout()
+                    && map.get_l(*l).lane_type == LaneType::Driving
+                {
+                    assert!(map
+                        .get_turns_from_lane(*l)
+                        .iter()
+                        .any(|t| map.get_parent(t.id.dst).is_roundabout()));
+                }
+            }
+        }
+        // Every approach can enter the circle.
+        assert!(entries >= 4);
+
+        // Entering from the east and leaving to the south goes three quarters of the way around,
+        // instead of cutting through the middle.
+        let approach_lane = |osm_way_id: i64, towards_ring: bool| {
+            let r = road_by_osm_id(&map, osm_way_id);
+            let lanes = if towards_ring {
+                &r.children_forwards
+            } else {
+                &r.children_backwards
+            };
+            lanes
+                .iter()
+                .find(|(_, lt)| *lt == LaneType::Driving)
+                .unwrap()
+                .0
+        };
+        let start = approach_lane(10, true);
+        let end = approach_lane(16, false);
+        let path = map
+            .pathfind(PathRequest {
+                start: Position::new(start, Distance::ZERO),
+                end: Position::new(end, map.get_l(end).length()),
+                can_use_bike_lanes: false,
+                can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: false,
+                avoid_lanes: BTreeSet::new(),
+            })
+            .unwrap();
+        let ring_steps = path
+            .get_steps()
+            .iter()
+            .filter(|step| match step {
+                PathStep::Lane(l) => map.get_parent(*l).is_roundabout(),
+                _ => false,
+            })
+            .count();
+        assert_eq!(ring_steps, 3);
+    });
+
+    t.run_fast("intersection_polygon_jitter", |_| {
+        // A skewed 5-way intersection, plus a few more around it. Offsets are in meters. The road
+        // from the west is exactly level, so jitter moves its angle across 0 degrees.
+        let nodes = vec![
+            (0.0, 0.0),
+            (100.0, 0.0),
+            (0.0, 100.0),
+            (-100.0, 0.0),
+            (-3.0, -100.0),
+            (70.0, 70.5),
+            (100.0, 100.0),
+        ];
+        let ways = vec![
+            vec![0, 1],
+            vec![0, 2],
+            vec![0, 3],
+            vec![0, 4],
+            vec![0, 5],
+            vec![2, 6],
+            vec![1, 6],
+        ];
+        let to_gps = |(x, y): (f64, f64)| LonLat::new(-122.3 + x / 75_000.0, 47.6 + y / 111_000.0);
+
+        // Nudge every node by a fraction of a millimeter, in a different direction each trial. This
+        // has to be bigger than the resolution of Pt2D, or it'd just be rounded away.
+        let jitter = 3e-4;
+        let polygons = |trial: usize| -> Vec<Vec<Pt2D>> {
+            let jittered: Vec<LonLat> = nodes
+                .iter()
+                .enumerate()
+                .map(|(idx, (x, y))| {
+                    let dx = if (idx + trial) % 2 == 0 {
+                        jitter
+                    } else {
+                        -jitter
+                    };
+                    let dy = if (idx + trial / 2) % 2 == 0 {
+                        jitter
+                    } else {
+                        -jitter
+                    };
+                    to_gps((x + dx, y + dy))
+                })
+                .collect();
+            let roads = ways
+                .iter()
+                .enumerate()
+                .map(|(idx, way)| {
+                    road(
+                        idx as i64,
+                        "residential",
+                        way.iter().map(|n| jittered[*n]).collect(),
+                    )
+                })
+                .collect();
+            let map = build_map(
+                "intersection_polygon_jitter",
+                raw_map(roads, Vec::new()),
+                to_gps((-200.0, -200.0)),
+                to_gps((200.0, 200.0)),
+            );
+            map.all_intersections()
+                .iter()
+                .map(|i| i.polygon.points().clone())
+                .collect()
+        };
+
+        // The jitter moves corners by much less than a millimeter, and rounding to the meter only
+        // hides that. Any change in the order or number of points still shows up.
+        let quantized = |trial: usize| -> Vec<Vec<(i64, i64)>> {
+            polygons(trial)
+                .into_iter()
+                .map(|pts| {
+                    pts.into_iter()
+                        .map(|pt| (pt.x().round() as i64, pt.y().round() as i64))
+                        .collect()
+                })
+                .collect()
+        };
+        let expected = quantized(0);
+        for trial in 1..4 {
+            assert_eq!(quantized(trial), expected, "trial {} differs", trial);
+        }
+    });
+
+    t.run_fast("undo_hints_incrementally", |_| {
+        // A 4-way intersection, connected to a T intersection to the east
+        let roads = vec![
+            road(1, "residential", vec![pt(0.0, 0.002), pt(0.002, 0.002)]),
+            road(2, "residential", vec![pt(0.002, 0.002), pt(0.004, 0.002)]),
+            road(3, "residential", vec![pt(0.002, 0.002), pt(0.002, 0.004)]),
+            road(4, "residential", vec![pt(0.002, 0.0), pt(0.002, 0.002)]),
+            road(5, "residential", vec![pt(0.004, 0.002), pt(0.006, 0.002)]),
+            road(6, "residential", vec![pt(0.004, 0.002), pt(0.004, 0.004)]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        raw.compute_gps_bounds();
+        let orig_road = |id: i64| {
+            raw.roads
+                .values()
+                .find(|r| r.osm_way_id == id)
+                .unwrap()
+                .orig_id()
+        };
+        let east_t = raw
+            .intersections
+            .values()
+            .find(|i| i.point == pt(0.004, 0.002))
+            .unwrap()
+            .orig_id();
+        // Deleting the northern arm of the T leaves a degenerate intersection behind.
+        let all_hints = vec![
+            raw_data::Hint::DeleteRoad(orig_road(6)),
+            raw_data::Hint::MergeDegenerateIntersection(east_t),
+            raw_data::Hint::MergeRoad(orig_road(3)),
+        ];
+
+        let build = |hints: &[raw_data::Hint]| {
+            let mut timer = abstutil::Timer::throwaway();
+            let mut initial = raw_data::InitialMap::new(
+                "undo_hints_incrementally".to_string(),
+                &raw,
+                &raw.gps_bounds,
+                &raw.gps_bounds.to_bounds(),
+                &mut timer,
+            );
+            initial.apply_hints(
+                &raw_data::Hints {
+                    hints: hints.to_vec(),
+                },
+                &raw,
+                &mut timer,
+            );
+            initial
+        };
+
+        let mut incremental = build(&all_hints);
+        for num_hints in (0..all_hints.len()).rev() {
+            assert!(incremental.undo_last_hint().is_some());
+            assert_eq!(
+                abstutil::to_json(&incremental),
+                abstutil::to_json(&build(&all_hints[0..num_hints])),
+                "undoing down to {} hints",
+                num_hints
+            );
+        }
+        assert!(incremental.undo_last_hint().is_none());
+    });
+
+    t.run_fast("merge_dual_carriageway_median", |_| {
+        // A two-lane northbound and southbound carriageway about 20m apart, crossed by a two-way
+        // street. The short piece of the cross street in the median gets merged.
+        let carriageway = |id: i64, points: Vec<LonLat>| {
+            let mut r = oneway(id, points);
+            r.osm_tags.insert("lanes".to_string(), "2".to_string());
+            r
+        };
+        let roads = vec![
+            carriageway(1, vec![pt(0.002, 0.0), pt(0.002, 0.002), pt(0.002, 0.004)]),
+            carriageway(
+                2,
+                vec![pt(0.0023, 0.004), pt(0.0023, 0.002), pt(0.0023, 0.0)],
+            ),
+            road(3, "residential", vec![pt(0.0, 0.002), pt(0.002, 0.002)]),
+            road(4, "residential", vec![pt(0.002, 0.002), pt(0.0023, 0.002)]),
+            road(5, "residential", vec![pt(0.0023, 0.002), pt(0.0043, 0.002)]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        set_boundary(&mut raw, pt(-0.001, -0.001), pt(0.0053, 0.005));
+        let median = raw
+            .roads
+            .values()
+            .find(|r| r.osm_way_id == 4)
+            .unwrap()
+            .orig_id();
+        let mut timer = abstutil::Timer::throwaway();
+        timer.collect_warnings();
+        let map = map_model::Map::create_from_raw_with_hints(
+            "merge_dual_carriageway_median".to_string(),
+            raw,
+            &raw_data::Hints {
+                hints: vec![raw_data::Hint::MergeRoad(median)],
+            },
+            false,
+            &mut timer,
+        );
+        // Any movement through the median that got lost is reported while building the map.
+        let lost: Vec<String> = timer
+            .take_warnings()
+            .into_iter()
+            .map(|w| w.message)
+            .filter(|msg| msg.contains("lost the movement"))
+            .collect();
+        assert!(lost.is_empty(), "{:?}", lost);
+        assert!(map.connectivity_audit().lost_movements.is_empty());
+
+        let i = map
+            .all_intersections()
+            .iter()
+            .find(|i| i.roads.len() == 6)
+            .unwrap();
+        // Roads that used to end at either side of the median
+        let find = |osm_way_id: i64, ends_here: bool| {
+            map.all_roads()
+                .iter()
+                .find(|r| r.osm_way_id == osm_way_id && (r.dst_i == i.id) == ends_here)
+                .unwrap()
+                .id
+        };
+        let (northbound_in, northbound_out) = (find(1, true), find(1, false));
+        let (southbound_in, southbound_out) = (find(2, true), find(2, false));
+        let (west, east) = (find(3, true), find(5, false));
+
+        let mut expected = vec![
+            (west, east),
+            (west, southbound_out),
+            (northbound_in, east),
+            (northbound_in, southbound_out),
+            (east, west),
+            (east, northbound_out),
+            (southbound_in, west),
+            (southbound_in, northbound_out),
+        ];
+        expected.sort();
+        let mut actual = i.merged_movements.clone();
+        actual.sort();
+        assert_eq!(actual, expected);
+
+        // Every incoming lane of the absorbed roads can reach the other carriageway, as if the
+        // median were still part of the intersection.
+        for (r1, r2) in vec![
+            (northbound_in, southbound_out),
+            (southbound_in, northbound_out),
+        ] {
+            for (l, lt) in map.get_r(r1).incoming_lanes(i.id) {
+                if *lt != LaneType::Driving {
+                    continue;
+                }
+                assert!(
+                    i.turns
+                        .iter()
+                        .any(|t| t.src == *l && map.get_l(t.dst).parent == r2),
+                    "No turn from {} onto {}",
+                    l,
+                    r2
+                );
+            }
+        }
+    });
+
+    t.run_fast("normalize_road_names", |_| {
+        assert_eq!(convert_osm::normalize_name("Main St"), "Main Street");
+        assert_eq!(convert_osm::normalize_name(" Main  St. "), "Main Street");
+        assert_eq!(
+            convert_osm::normalize_name("NE 45th St"),
+            "Northeast 45th Street"
+        );
+        assert_eq!(
+            convert_osm::normalize_name("St Johns Ave"),
+            "St Johns Avenue"
+        );
+
+        // Main St and Main Street are two segments of one street, crossed by Elm.
+        let named = |id: i64, name: &str, points: Vec<LonLat>| {
+            let mut r = road(id, "residential", points);
+            r.osm_tags.insert("name".to_string(), name.to_string());
+            r
+        };
+        let roads = vec![
+            named(1, "Main St", vec![pt(0.0, 0.001), pt(0.002, 0.001)]),
+            named(2, "Main Street", vec![pt(0.002, 0.001), pt(0.004, 0.001)]),
+            named(3, "Elm Ave.", vec![pt(0.002, 0.001), pt(0.002, 0.003)]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        convert_osm::normalize_road_names(&mut raw, &mut abstutil::Timer::throwaway());
+        let map = build_map(
+            "normalize_road_names",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.005, 0.004),
+        );
+
+        let find = |osm_way_id: i64| road_by_osm_id(&map, osm_way_id);
+        let (main1, main2, elm) = (find(1), find(2), find(3));
+        assert_eq!(main1.get_name(), "Main Street");
+        assert_eq!(main2.get_name(), "Main Street");
+        assert_eq!(elm.get_name(), "Elm Avenue");
+        // The original spelling is still around.
+        assert_eq!(
+            main1.osm_tags.get(convert_osm::RAW_NAME_TAG),
+            Some(&"Main St".to_string())
+        );
+        assert_eq!(main2.osm_tags.get(convert_osm::RAW_NAME_TAG), None);
+
+        // Editing one segment reaches the other too.
+        let edits = editor::bulk_edit_lanes(main1.id, LaneType::Driving, LaneType::Bus, &map);
+        for r in vec![main1, main2, elm] {
+            for l in r.all_lanes() {
+                assert_eq!(
+                    edits.lane_overrides.contains_key(&l),
+                    r.id != elm.id && map.get_l(l).lane_type == LaneType::Driving,
+                    "{} on {}",
+                    l,
+                    r.get_name()
+                );
+            }
+        }
+    });
+
+    t.run_fast("group_roads_by_name", |_| {
+        // Main Street gets split into three roads where it crosses 1st and 2nd Avenue.
+        let named = |id: i64, name: &str, points: Vec<LonLat>| {
+            let mut r = road(id, "residential", points);
+            r.osm_tags.insert("name".to_string(), name.to_string());
+            r
+        };
+        let roads = vec![
+            named(
+                1,
+                "Main Street",
+                vec![
+                    pt(0.0, 0.002),
+                    pt(0.002, 0.002),
+                    pt(0.004, 0.002),
+                    pt(0.006, 0.002),
+                ],
+            ),
+            named(2, "1st Avenue", vec![pt(0.002, 0.0), pt(0.002, 0.002)]),
+            named(3, "1st Avenue", vec![pt(0.002, 0.002), pt(0.002, 0.004)]),
+            named(4, "2nd Avenue", vec![pt(0.004, 0.002), pt(0.004, 0.004)]),
+        ];
+        let map = build_map(
+            "group_roads_by_name",
+            raw_map(roads, Vec::new()),
+            pt(-0.001, -0.001),
+            pt(0.007, 0.005),
+        );
+
+        let groups = map.group_roads_by_name();
+        assert_eq!(groups.len(), 3);
+        let sizes: BTreeMap<String, usize> = groups
+            .iter()
+            .map(|(name, roads)| (name.clone(), roads.len()))
+            .collect();
+        assert_eq!(sizes["Main Street"], 3);
+        assert_eq!(sizes["1st Avenue"], 2);
+        assert_eq!(sizes["2nd Avenue"], 1);
+        for (name, roads) in &groups {
+            assert_eq!(&map.roads_with_name(name), roads);
+            for r in roads {
+                assert_eq!(&map.get_r(*r).get_name(), name);
+            }
+        }
+        assert!(map.roads_with_name("3rd Avenue").is_empty());
+    });
+
+    t.run_fast("bulk_edit_unnamed_road", |_| {
+        // Three service roads without names meet at one point.
+        let roads = vec![
+            road(1, "service", vec![pt(0.0, 0.001), pt(0.002, 0.001)]),
+            road(2, "service", vec![pt(0.002, 0.001), pt(0.004, 0.001)]),
+            road(3, "service", vec![pt(0.002, 0.001), pt(0.002, 0.003)]),
+        ];
+        let map = build_map(
+            "bulk_edit_unnamed_road",
+            raw_map(roads, Vec::new()),
+            pt(-0.001, -0.001),
+            pt(0.005, 0.004),
+        );
+
+        let find = |osm_way_id: i64| road_by_osm_id(&map, osm_way_id);
+        let (r1, r2, r3) = (find(1), find(2), find(3));
+        assert_eq!(r1.get_osm_name(), None);
+        assert_eq!(r1.get_name(), "service road #1");
+        assert_eq!(r2.get_name(), "service road #2");
+        assert_eq!(map.street_roads(r1.id), vec![r1.id]);
+
+        // Only the one road changes, not every other unnamed road.
+        let edits = editor::bulk_edit_lanes(r1.id, LaneType::Driving, LaneType::Bus, &map);
+        assert!(!edits.lane_overrides.is_empty());
+        for r in vec![r1, r2, r3] {
+            for l in r.all_lanes() {
+                assert_eq!(
+                    edits.lane_overrides.contains_key(&l),
+                    r.id == r1.id && map.get_l(l).lane_type == LaneType::Driving,
+                    "{} on {}",
+                    l,
+                    r.get_name()
+                );
+            }
+        }
+    });
+
+    t.run_fast("street_roads_stay_connected", |_| {
+        // Main Street is split into two OSM ways, crossed by 1st Avenue. Another Main Street
+        // elsewhere doesn't touch it.
+        let named = |id: i64, name: &str, points| {
+            let mut r = road(id, "residential", points);
+            r.osm_tags.insert("name".to_string(), name.to_string());
+            r
+        };
+        let roads = vec![
+            named(1, "Main Street", vec![pt(0.0, 0.0), pt(0.002, 0.0)]),
+            named(2, "Main Street", vec![pt(0.002, 0.0), pt(0.004, 0.0)]),
+            named(3, "Main Street", vec![pt(0.0, 0.003), pt(0.004, 0.003)]),
+            named(4, "1st Avenue", vec![pt(0.002, 0.0), pt(0.002, 0.002)]),
+        ];
+        let map = build_map(
+            "street_roads_stay_connected",
+            raw_map(roads, Vec::new()),
+            pt(-0.001, -0.001),
+            pt(0.005, 0.004),
+        );
+
+        let find = |osm_way_id: i64| road_by_osm_id(&map, osm_way_id).id;
+        let (r1, r2, r3, r4) = (find(1), find(2), find(3), find(4));
+        assert_eq!(map.roads_with_name("Main Street").len(), 3);
+        let mut expected = vec![r1, r2];
+        expected.sort();
+        assert_eq!(map.street_roads(r1), expected);
+        assert_eq!(map.street_roads(r2), expected);
+        assert_eq!(map.street_roads(r3), vec![r3]);
+        assert_eq!(map.street_roads(r4), vec![r4]);
+
+        let edits = editor::bulk_edit_lanes(r1, LaneType::Driving, LaneType::Bus, &map);
+        for r in vec![r3, r4] {
+            for l in map.get_r(r).all_lanes() {
+                assert!(!edits.lane_overrides.contains_key(&l));
+            }
+        }
+    });
+
+    t.run_fast("divided_road_avoids_uturn", |_| {
+        // A divided road whose carriageways meet at Q, with a block of two-way streets to the
+        // northeast of Q. P is a border.
+        //
+        //        R ---- U
+        //        |      |
+        //  P === Q ---- T
+        let (p, q, t, u, r) = (
+            pt(0.0, 0.0),
+            pt(0.002, 0.0),
+            pt(0.004, 0.0),
+            pt(0.004, 0.002),
+            pt(0.002, 0.002),
+        );
+        let roads = vec![
+            // Eastbound and westbound carriageways
+            oneway(1, vec![p, pt(0.001, -0.0001), q]),
+            oneway(2, vec![q, pt(0.001, 0.0001), p]),
+            road(3, "residential", vec![q, t]),
+            road(4, "residential", vec![t, u]),
+            road(5, "residential", vec![u, r]),
+            road(6, "residential", vec![r, q]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        for i in raw.intersections.values_mut() {
+            i.intersection_type = if i.point == p {
+                IntersectionType::Border
+            } else {
+                IntersectionType::StopSign
+            };
+        }
+        let mut map = build_map(
+            "divided_road_avoids_uturn",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.005, 0.003),
+        );
+
+        let (eastbound, westbound) = (driving_lane(&map, 1), driving_lane(&map, 2));
+        let q = map.get_l(eastbound).dst_i;
+        let uturn = TurnID {
+            parent: q,
+            src: eastbound,
+            dst: westbound,
+        };
+        assert_eq!(map.get_t(uturn).turn_type, TurnType::UTurn);
+        assert!(!map.is_turn_allowed(uturn));
+
+        let route = |map: &map_model::Map| -> (Vec<i64>, bool) {
+            let path = map
+                .pathfind(PathRequest {
+                    start: Position::new(eastbound, Distance::ZERO),
+                    end: Position::new(westbound, map.get_l(westbound).length()),
+                    can_use_bike_lanes: false,
+                    can_use_bus_lanes: false,
+                    can_use_hov_lanes: false,
+                    can_use_midblock_crossings: false,
+                    avoid_lanes: BTreeSet::new(),
+                })
+                .unwrap();
+            let mut roads = Vec::new();
+            let mut any_uturns = false;
+            for step in path.get_steps() {
+                match step.as_traversable() {
+                    Traversable::Lane(l) => roads.push(map.get_parent(l).osm_way_id),
+                    Traversable::Turn(t) => {
+                        any_uturns |= map.get_t(t).turn_type == TurnType::UTurn;
+                    }
+                    Traversable::MidblockCrossing(_) => unreachable!(),
+                }
+            }
+            (roads, any_uturns)
+        };
+
+        // The shortest way back is the U-turn, but it's banned, so go around the block.
+        assert_eq!(route(&map), (vec![1, 3, 4, 5, 6, 2], false));
+
+        // Unless it's explicitly allowed
+        let mut edits = map.get_edits().clone();
+        edits.allow_uturns.insert(q);
+        map.apply_edits(edits, &mut abstutil::Timer::throwaway());
+        assert!(map.is_turn_allowed(uturn));
+        assert_eq!(route(&map), (vec![1, 2], true));
+    });
 }
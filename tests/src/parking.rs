@@ -1,9 +1,103 @@
+use crate::fixtures::{build_map, building, pt, raw_map, rectangle, road};
 use crate::runner::TestRunner;
 use abstutil::Timer;
-use geom::Duration;
-use sim::{DrivingGoal, Event, ParkingSpot, Scenario, SidewalkSpot, SimFlags, TripSpec};
+use geom::{Distance, Duration, PolyLine, Pt2D};
+use map_model::{
+    raw_data, IntersectionID, IntersectionType, Lane, LaneID, LaneType, ParkingLotID, Position,
+    RoadID, PARKING_SPOT_LENGTH,
+};
+use sim::{DrivingGoal, Event, ParkingSpot, Scenario, SidewalkSpot, Sim, SimFlags, TripSpec};
 
 pub fn run(t: &mut TestRunner) {
+    t.run_fast("parking_spots_awkward_lengths", |_| {
+        for (length, expected_spots) in vec![
+            (0.5, 0),
+            (23.9, 0),
+            (24.0, 1),
+            (30.0, 1),
+            (31.99, 1),
+            (32.0, 2),
+            (100.3, 10),
+        ] {
+            let lane = parking_lane(Distance::meters(length));
+            let spots = lane.parking_spots();
+            assert_eq!(spots.len(), expected_spots, "lane of length {}", length);
+            assert_eq!(lane.number_parking_spots(), expected_spots);
+
+            for (idx, (start, end)) in spots.iter().enumerate() {
+                // Every spot is full-sized and lies entirely on the lane, with no room for a
+                // partial spot at the end.
+                assert!((*end - *start).epsilon_eq(PARKING_SPOT_LENGTH));
+                assert!(*start > Distance::ZERO);
+                assert!(*end < lane.length());
+                if idx > 0 {
+                    assert_eq!(spots[idx - 1].1, *start);
+                }
+            }
+            if let Some((_, last_end)) = spots.last() {
+                assert!(lane.length() - *last_end < PARKING_SPOT_LENGTH * 2.0);
+            }
+        }
+    });
+
+    t.run_fast("parking_hours", |_| {
+        let mut roads = vec![
+            road(1, "residential", vec![pt(0.0, 0.0), pt(0.002, 0.0)]),
+            road(2, "residential", vec![pt(0.002, 0.0), pt(0.004, 0.0)]),
+        ];
+        roads[0].parking_lane_fwd = true;
+        for r in roads.iter_mut() {
+            // Only during the first minute of the day
+            r.parking_hours_fwd = Some(vec![(Duration::ZERO, Duration::minutes(1))]);
+        }
+        let map = build_map(
+            "parking_hours",
+            raw_map(roads, Vec::new()),
+            pt(-0.001, -0.001),
+            pt(0.005, 0.001),
+        );
+
+        let lane = map
+            .all_lanes()
+            .iter()
+            .find(|l| l.lane_type == LaneType::Parking)
+            .unwrap();
+        assert!(lane.parking_allowed_at(Duration::seconds(59.0)));
+        assert!(!lane.parking_allowed_at(Duration::minutes(1)));
+        // Restrictions repeat every day
+        assert!(lane.parking_allowed_at(Duration::minutes(24 * 60)));
+        assert_eq!(
+            lane.next_parking_hours_change(Duration::ZERO),
+            Some(Duration::minutes(1))
+        );
+        assert_eq!(
+            lane.next_parking_hours_change(Duration::minutes(1)),
+            Some(Duration::minutes(24 * 60))
+        );
+
+        let mut sim = Sim::new(&map, "parking_hours".to_string(), None);
+        let mut rng = SimFlags::for_test("parking_hours").make_rng();
+        let spot = ParkingSpot::Onstreet(lane.id, 0);
+        let car = sim.seed_parked_car(Scenario::rand_car(&mut rng), spot, None);
+        let num_spots = lane.number_parking_spots();
+        assert_eq!(sim.get_free_spots(lane.id).len(), num_spots - 1);
+
+        sim.step(&map, Duration::seconds(59.0));
+        assert_eq!(sim.get_free_spots(lane.id).len(), num_spots - 1);
+        assert!(sim.get_events_since_last_step().is_empty());
+
+        sim.step(&map, Duration::seconds(1.0));
+        assert!(sim.get_free_spots(lane.id).is_empty());
+        assert!(sim
+            .get_parking_spot_occupancy(lane.id)
+            .into_iter()
+            .all(|(_, unavailable)| unavailable));
+        assert_eq!(
+            sim.get_events_since_last_step(),
+            &vec![Event::CarParkedIllegally(car, spot)]
+        );
+    });
+
     // TODO Lots of boilerplate between these two. Can we do better?
 
     t.run_slow("park_on_goal_st", |h| {
@@ -36,7 +130,7 @@ pub fn run(t: &mut TestRunner) {
             &map,
             vec![Event::CarReachedParkingSpot(
                 car,
-                ParkingSpot::new(north_parking, 4),
+                ParkingSpot::Onstreet(north_parking, 4),
             )],
             Duration::minutes(6),
         );
@@ -74,10 +168,132 @@ pub fn run(t: &mut TestRunner) {
             &map,
             vec![Event::CarReachedParkingSpot(
                 car,
-                ParkingSpot::new(south_parking, 0),
+                ParkingSpot::Onstreet(south_parking, 0),
             )],
             Duration::minutes(6),
         );
         sim.just_run_until_done(&map, Some(Duration::minutes(1)));
     });
+
+    t.run_slow("parking_spot_occupancy_overlay", |h| {
+        let (map, mut sim, mut rng) =
+            SimFlags::synthetic_test("parking_test", "parking_spot_occupancy_overlay")
+                .load(None, &mut Timer::throwaway());
+        let lane = map.parking_lane("north", 23);
+        h.seed_parked_cars(&mut sim, &mut rng, lane.id, None, vec![5]);
+
+        let polygons = editor::parking_spot_polygons(lane);
+        let spots = editor::parking_spot_occupancy(&polygons, lane.id, &sim);
+        assert_eq!(spots.len(), 23);
+        assert_eq!(spots.iter().filter(|(_, occupied)| *occupied).count(), 1);
+        assert!(spots[5].1);
+    });
+
+    t.run_fast("park_in_and_leave_lot", |_| {
+        // No parking lanes anywhere, just a small lot on the south side of the block, next to
+        // the east building.
+        let roads = vec![
+            road(1, "residential", vec![pt(0.0, 0.0), pt(0.009, 0.0)]),
+            road(
+                2,
+                "residential",
+                vec![pt(0.0, -0.0005), pt(0.0, 0.0), pt(0.0, 0.0005)],
+            ),
+            road(
+                3,
+                "residential",
+                vec![pt(0.009, -0.0005), pt(0.009, 0.0), pt(0.009, 0.0005)],
+            ),
+        ];
+        let bldgs = vec![building(4, 0.0015, -0.0003), building(5, 0.0075, -0.0003)];
+
+        let mut raw = raw_map(roads, bldgs);
+        raw.parking_lots = vec![raw_data::ParkingLot {
+            points: rectangle(pt(0.0069, -0.00035), pt(0.0071, -0.00025)),
+            capacity: Some(2),
+            osm_id: 6,
+        }];
+        for i in raw.intersections.values_mut() {
+            if i.point == pt(0.009, 0.0005) {
+                i.intersection_type = IntersectionType::Border;
+            }
+        }
+        let map = build_map(
+            "park_in_and_leave_lot",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.01, 0.001),
+        );
+        assert_eq!(map.all_parking_lots().len(), 1);
+        let lot = ParkingLotID(0);
+        assert_eq!(map.get_parking_lot(lot).capacity, 2);
+        let east = map.all_buildings()[1].id;
+        let eastbound = map.get_parking_lot(lot).driving_pos.lane();
+        let border = map
+            .all_intersections()
+            .iter()
+            .find(|i| i.intersection_type == IntersectionType::Border)
+            .unwrap()
+            .id;
+
+        let mut sim = Sim::new(&map, "park_in_and_leave_lot".to_string(), None);
+        let mut rng = SimFlags::for_test("park_in_and_leave_lot").make_rng();
+        let parked_spot = ParkingSpot::Offstreet(lot, 0);
+        sim.seed_parked_car(Scenario::rand_car(&mut rng), parked_spot, Some(east));
+        assert_eq!(sim.get_parking_lot_occupancy(lot), 1);
+
+        // One car drives in from the west end of the block and takes the other spot, while the
+        // car already there leaves.
+        let (_, arriving) = sim.schedule_trip(
+            Duration::ZERO,
+            TripSpec::CarAppearing {
+                start_pos: Position::new(eastbound, Distance::meters(20.0)),
+                goal: DrivingGoal::ParkNear(east),
+                vehicle_spec: Scenario::rand_car(&mut rng),
+                ped_speed: Scenario::rand_ped_speed(&mut rng),
+            },
+            &map,
+        );
+        let (leaving, _) = sim.schedule_trip(
+            Duration::ZERO,
+            TripSpec::UsingParkedCar {
+                start: SidewalkSpot::building(east, &map),
+                spot: parked_spot,
+                goal: DrivingGoal::end_at_border(border, vec![LaneType::Driving], &map).unwrap(),
+                ped_speed: Scenario::rand_ped_speed(&mut rng),
+            },
+            &map,
+        );
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+
+        sim.run_until_expectations_met(
+            &map,
+            vec![
+                Event::PedReachedParkingSpot(leaving.unwrap(), parked_spot),
+                Event::CarReachedParkingSpot(arriving.unwrap(), ParkingSpot::Offstreet(lot, 1)),
+            ],
+            Duration::minutes(3),
+        );
+        sim.just_run_until_done(&map, Some(Duration::minutes(5)));
+        assert_eq!(sim.get_parking_lot_occupancy(lot), 1);
+        assert_eq!(sim.get_free_lot_spots(lot), vec![parked_spot]);
+    });
+}
+
+fn parking_lane(length: Distance) -> Lane {
+    Lane {
+        id: LaneID(0),
+        parent: RoadID(0),
+        lane_type: LaneType::Parking,
+        lane_center_pts: PolyLine::new(vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(length.inner_meters(), 0.0),
+        ]),
+        src_i: IntersectionID(0),
+        dst_i: IntersectionID(1),
+        building_paths: Vec::new(),
+        bus_stops: Vec::new(),
+        midblock_crossings: Vec::new(),
+        parking_hours: None,
+    }
 }
@@ -1,7 +1,12 @@
+use crate::fixtures::{build_map, car, driving_lane, oneway, pt, raw_map, road_by_osm_id};
 use crate::runner::TestRunner;
 use abstutil::Timer;
-use geom::Duration;
-use sim::{Scenario, SimFlags};
+use geom::{Distance, Duration, Speed};
+use map_model::{ControlTrafficSignal, Cycle, IntersectionType, LaneType, Position, SignalControl};
+use sim::{
+    Conditions, DrivingGoal, Event, Scenario, Sim, SimFlags, TripMode, TripSpec, VehicleClass,
+    VehicleSpec, VehicleType,
+};
 
 pub fn run(t: &mut TestRunner) {
     t.run_slow("small_spawn_completes", |h| {
@@ -11,4 +16,370 @@ pub fn run(t: &mut TestRunner) {
         h.setup_done(&sim);
         sim.just_run_until_done(&map, Some(Duration::minutes(70)));
     });
+
+    t.run_slow("headless_run_until_deadline", |h| {
+        let (map, mut sim, mut rng) = SimFlags::for_test("headless_run_until_deadline")
+            .load(None, &mut Timer::throwaway());
+        Scenario::small_run(&map).instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway());
+        h.setup_done(&sim);
+
+        assert!(sim.run_until_done_or_deadline(&map, Duration::minutes(70)));
+        let trips = sim.get_finished_trips();
+        assert_eq!(trips.unfinished_trips, 0);
+        assert!(!trips.finished_trips.is_empty());
+    });
+
+    t.run_slow("rain_is_slower", |_| {
+        let total_trip_time = |conditions: Conditions| {
+            let mut flags = SimFlags::for_test("rain_is_slower");
+            flags.conditions = conditions;
+            let (map, mut sim, mut rng) = flags.load(None, &mut Timer::throwaway());
+            Scenario::small_run(&map).instantiate(
+                &mut sim,
+                &map,
+                &mut rng,
+                &mut Timer::throwaway(),
+            );
+            assert!(sim.run_until_done_or_deadline(&map, Duration::minutes(90)));
+            sim.get_finished_trips()
+                .finished_trips
+                .into_iter()
+                .fold(Duration::ZERO, |sum, (_, _, dt)| sum + dt)
+        };
+
+        let dry = total_trip_time(Conditions::Dry);
+        let rain = total_trip_time(Conditions::Rain);
+        // Every trip walks some, and walking alone is about 18% slower.
+        assert!(
+            rain > dry * 1.03 && rain < dry * 1.5,
+            "Dry trips took {} total, rainy ones {}",
+            dry,
+            rain
+        );
+    });
+
+    t.run_slow("fewer_bikes_in_bad_weather", |_| {
+        let bike_trips = |conditions: Conditions| {
+            let mut flags = SimFlags::for_test("fewer_bikes_in_bad_weather");
+            flags.conditions = conditions;
+            let (map, mut sim, mut rng) = flags.load(None, &mut Timer::throwaway());
+            Scenario::small_run(&map).instantiate(
+                &mut sim,
+                &map,
+                &mut rng,
+                &mut Timer::throwaway(),
+            );
+            assert!(sim.run_until_done_or_deadline(&map, Duration::minutes(90)));
+            sim.get_finished_trips()
+                .finished_trips
+                .into_iter()
+                .filter(|(_, mode, _)| *mode == TripMode::Bike)
+                .count()
+        };
+
+        let dry = bike_trips(Conditions::Dry);
+        let snow = bike_trips(Conditions::Snow);
+        assert!(dry > 0);
+        assert!(
+            snow < dry,
+            "{} bike trips when it's dry, {} in the snow",
+            dry,
+            snow
+        );
+    });
+
+    t.run_fast("trucks_are_slower", |_| {
+        // One straight one-way street, broken up by two stop signs.
+        let (west, east) = (pt(0.0, 0.0), pt(0.006, 0.0));
+        let roads = vec![
+            oneway(1, vec![west, pt(0.002, 0.0)]),
+            oneway(2, vec![pt(0.002, 0.0), pt(0.004, 0.0)]),
+            oneway(3, vec![pt(0.004, 0.0), east]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        for i in raw.intersections.values_mut() {
+            i.intersection_type = if i.point == west || i.point == east {
+                IntersectionType::Border
+            } else {
+                IntersectionType::StopSign
+            };
+        }
+        let map = build_map(
+            "trucks_are_slower",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.007, 0.001),
+        );
+
+        let (start, end) = (driving_lane(&map, 1), driving_lane(&map, 3));
+
+        // Identical besides the class, so only the acceleration differs.
+        let trip_time = |class: VehicleClass| {
+            let mut sim = Sim::new(&map, "trucks_are_slower".to_string(), None);
+            sim.schedule_trip(
+                Duration::ZERO,
+                TripSpec::CarAppearing {
+                    start_pos: Position::new(start, Distance::meters(10.0)),
+                    goal: DrivingGoal::end_at_border(
+                        map.get_l(end).dst_i,
+                        vec![LaneType::Driving],
+                        &map,
+                    )
+                    .unwrap(),
+                    vehicle_spec: VehicleSpec {
+                        vehicle_type: VehicleType::Car,
+                        class,
+                        length: Distance::meters(7.0),
+                        max_speed: None,
+                        occupancy: 1,
+                    },
+                    ped_speed: Speed::miles_per_hour(3.0),
+                },
+                &map,
+            );
+            sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+            sim.just_run_until_done(&map, Some(Duration::minutes(10)));
+            let trips = sim.get_finished_trips().finished_trips;
+            assert_eq!(trips.len(), 1);
+            trips[0].2
+        };
+
+        let car = trip_time(VehicleClass::Car);
+        let truck = trip_time(VehicleClass::Truck);
+        // Starting from rest on each of the 3 lanes and 2 turns costs the truck several seconds.
+        assert!(
+            truck > car + Duration::seconds(10.0),
+            "Car took {}, truck took {}",
+            car,
+            truck
+        );
+    });
+
+    t.run_fast("gridlock_detected_and_aborted", |_| {
+        // Two one-way roads form a loop between stop signs at A and B. Cars on each road want to
+        // go around to the far side of the other. Both roads are packed bumper to bumper, so
+        // nobody can start a turn onto the other one.
+        let a = pt(0.002, 0.002);
+        let b = pt(0.004, 0.002);
+        let roads = vec![
+            // A to B
+            oneway(1, vec![a, b]),
+            // B back to A, looping around to the north
+            oneway(2, vec![b, pt(0.005, 0.004), pt(0.001, 0.004), a]),
+            // Exits from A and B
+            oneway(3, vec![a, pt(0.0, 0.002)]),
+            oneway(4, vec![b, pt(0.006, 0.002)]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        for i in raw.intersections.values_mut() {
+            i.intersection_type = if i.point == a || i.point == b {
+                IntersectionType::StopSign
+            } else {
+                IntersectionType::Border
+            };
+        }
+        let map = build_map(
+            "gridlock_detected_and_aborted",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.007, 0.005),
+        );
+
+        let (a_to_b, b_to_a) = (driving_lane(&map, 1), driving_lane(&map, 2));
+        let west = road_by_osm_id(&map, 3).dst_i;
+        let east = road_by_osm_id(&map, 4).dst_i;
+
+        let mut sim = Sim::new(&map, "gridlock_detected_and_aborted".to_string(), None);
+        sim.set_gridlock_window(Duration::minutes(1));
+        sim.abort_gridlocked_trips(true);
+        let vehicle = car();
+        // Bumper to bumper (just over the length of a car plus the following distance apart) from
+        // the end of each lane back to its start. There isn't room for another car.
+        let spacing = vehicle.length + Distance::meters(1.001);
+        let mut num_cars = 0;
+        for (lane, goal) in vec![(a_to_b, west), (b_to_a, east)] {
+            let mut front = map.get_l(lane).length();
+            while front >= vehicle.length {
+                sim.schedule_trip(
+                    Duration::ZERO,
+                    TripSpec::CarAppearing {
+                        start_pos: Position::new(lane, front),
+                        goal: DrivingGoal::end_at_border(goal, vec![LaneType::Driving], &map)
+                            .unwrap(),
+                        vehicle_spec: vehicle.clone(),
+                        ped_speed: Speed::miles_per_hour(3.0),
+                    },
+                    &map,
+                );
+                num_cars += 1;
+                front -= spacing;
+            }
+        }
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+
+        let mut gridlock = None;
+        while gridlock.is_none() && sim.time() < Duration::minutes(10) {
+            sim.step(&map, Duration::seconds(30.0));
+            for ev in sim.get_events_since_last_step() {
+                if let Event::GridlockDetected(agents) = ev {
+                    gridlock = Some(agents.clone());
+                }
+            }
+        }
+        let agents = gridlock.expect("Gridlock wasn't detected in 10 minutes");
+        // Every car is part of the loop.
+        assert_eq!(agents.len(), num_cars);
+        // And all of their trips were aborted.
+        assert!(sim.is_done());
+        assert_eq!(sim.get_finished_trips().finished_trips.len(), 0);
+    });
+
+    t.run_fast("red_light_is_not_gridlock", |_| {
+        // One one-way street through a signal that's red for cars for an hour. Nobody can move,
+        // but they're just waiting for the light, not for each other.
+        let roads = vec![
+            oneway(1, vec![pt(0.0, 0.0), pt(0.002, 0.0)]),
+            oneway(2, vec![pt(0.002, 0.0), pt(0.004, 0.0)]),
+            oneway(3, vec![pt(0.002, 0.0), pt(0.002, 0.002)]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        for i in raw.intersections.values_mut() {
+            i.intersection_type = if i.point == pt(0.002, 0.0) {
+                IntersectionType::TrafficSignal
+            } else {
+                IntersectionType::Border
+            };
+        }
+        let mut map = build_map(
+            "red_light_is_not_gridlock",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.005, 0.003),
+        );
+        let lane = driving_lane(&map, 1);
+        let exit = road_by_osm_id(&map, 2).dst_i;
+        let signal = map.get_l(lane).dst_i;
+
+        let mut edits = map.get_edits().clone();
+        let mut red = Cycle::new(signal, 0);
+        let mut green = Cycle::new(signal, 1);
+        for t in map.get_turns_in_intersection(signal) {
+            if t.between_sidewalks() {
+                red.priority_turns.insert(t.id);
+                green.priority_turns.insert(t.id);
+            } else {
+                green.yield_turns.insert(t.id);
+            }
+        }
+        red.min_green = Duration::minutes(60);
+        red.max_green = Duration::minutes(60);
+        edits.traffic_signal_overrides.insert(
+            signal,
+            ControlTrafficSignal {
+                id: signal,
+                cycles: vec![red, green],
+                control: SignalControl::default_actuated(),
+                all_red: Duration::ZERO,
+            },
+        );
+        map.apply_edits(edits, &mut Timer::throwaway());
+
+        let mut sim = Sim::new(&map, "red_light_is_not_gridlock".to_string(), None);
+        sim.set_gridlock_window(Duration::minutes(1));
+        sim.abort_gridlocked_trips(true);
+        for idx in 0..3 {
+            sim.schedule_trip(
+                Duration::ZERO,
+                TripSpec::CarAppearing {
+                    start_pos: Position::new(lane, Distance::meters(10.0 + 20.0 * (idx as f64))),
+                    goal: DrivingGoal::end_at_border(exit, vec![LaneType::Driving], &map).unwrap(),
+                    vehicle_spec: car(),
+                    ped_speed: Speed::miles_per_hour(3.0),
+                },
+                &map,
+            );
+        }
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+
+        sim.step(&map, Duration::minutes(10));
+        for ev in sim.get_events_since_last_step() {
+            if let Event::GridlockDetected(agents) = ev {
+                panic!("Waiting at a red light counted as gridlock: {:?}", agents);
+            }
+        }
+        assert!(!sim.is_done());
+    });
+
+    t.run_fast("overloaded_border_queues_cars", |_| {
+        // One short one-way street with a single lane, between two borders.
+        let mut raw = raw_map(
+            vec![oneway(1, vec![pt(0.0, 0.0), pt(0.002, 0.0)])],
+            Vec::new(),
+        );
+        for i in raw.intersections.values_mut() {
+            i.intersection_type = IntersectionType::Border;
+        }
+        let map = build_map(
+            "overloaded_border_queues_cars",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.003, 0.001),
+        );
+        let lanes: Vec<_> = map
+            .all_lanes()
+            .iter()
+            .filter(|l| l.lane_type == LaneType::Driving)
+            .collect();
+        assert_eq!(lanes.len(), 1);
+        let (lane, border) = (lanes[0].id, lanes[0].src_i);
+
+        // Everybody shows up at once, but only one car fits at the start of the lane at a time.
+        let num_cars = 20;
+        let mut sim = Sim::new(&map, "overloaded_border_queues_cars".to_string(), None);
+        let spawn = |sim: &mut Sim, dist: Distance| {
+            sim.schedule_trip(
+                Duration::ZERO,
+                TripSpec::CarAppearing {
+                    start_pos: Position::new(lane, dist),
+                    goal: DrivingGoal::end_at_border(
+                        map.get_l(lane).dst_i,
+                        vec![LaneType::Driving],
+                        &map,
+                    )
+                    .unwrap(),
+                    vehicle_spec: car(),
+                    ped_speed: Speed::miles_per_hour(3.0),
+                },
+                &map,
+            )
+            .1
+            .unwrap()
+        };
+        for _ in 0..num_cars {
+            spawn(&mut sim, Distance::meters(5.0));
+        }
+        // Somebody appearing further along the lane isn't stuck behind the queue at the border.
+        let midblock = spawn(&mut sim, map.get_l(lane).length() / 2.0);
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+
+        let mut delayed = 0;
+        while !sim.is_done() && sim.time() < Duration::minutes(30) {
+            sim.step(&map, Duration::seconds(30.0));
+            for ev in sim.get_events_since_last_step() {
+                if let Event::BorderSpawnDelayed(car, i, delay) = ev {
+                    assert_ne!(*car, midblock);
+                    assert_eq!(*i, border);
+                    assert!(*delay > Duration::ZERO);
+                    delayed += 1;
+                }
+            }
+        }
+        // Nobody was dropped; all but the first car had to wait.
+        assert!(sim.is_done());
+        assert_eq!(sim.get_finished_trips().finished_trips.len(), num_cars + 1);
+        assert_eq!(delayed, num_cars - 1);
+        let (count, vehicle_hours) = sim.get_travel_stats().border_spawn_delays()[&border];
+        assert_eq!(count, num_cars - 1);
+        assert!(vehicle_hours > 0.0);
+    });
 }
@@ -1,7 +1,8 @@
+use crate::fixtures::{build_map, pt, raw_map, road, TmpFile};
 use crate::runner::TestRunner;
 use abstutil::Timer;
 use geom::Duration;
-use sim::{Scenario, Sim, SimFlags};
+use sim::{Replay, ReplayLog, Scenario, Sim, SimFlags};
 
 pub fn run(t: &mut TestRunner) {
     t.run_slow("serialization", |_| {
@@ -101,4 +102,106 @@ pub fn run(t: &mut TestRunner) {
 
         std::fs::remove_file(sim1_save).unwrap();
     });
+    t.run_slow("same_seed_same_results", |_| {
+        let run = |name: &str| {
+            let flags = SimFlags::for_test(name).with_seed(1234);
+            let (map, mut sim, mut rng) = flags.load(None, &mut Timer::throwaway());
+            assert_eq!(sim.rng_seed(), Some(1234));
+            Scenario::small_run(&map).instantiate(
+                &mut sim,
+                &map,
+                &mut rng,
+                &mut Timer::throwaway(),
+            );
+            sim.step(&map, Duration::minutes(10));
+            let trips = sim.get_finished_trips();
+            (trips.finished_trips.len(), trips.unfinished_trips)
+        };
+        let (finished, unfinished) = run("same_seed_same_results_1");
+        assert!(finished > 0);
+        assert_eq!(run("same_seed_same_results_2"), (finished, unfinished));
+    });
+
+    t.run_slow("replay_matches_live_run", |_| {
+        let flags = SimFlags {
+            record_replay: Some(5.0),
+            ..SimFlags::for_test("replay_matches_live_run")
+        };
+        let (map, mut sim, mut rng) = flags.load(None, &mut Timer::throwaway());
+        Scenario::small_run(&map).instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway());
+
+        // Step exactly to each sample time, so the live positions are what got recorded.
+        let mut live = Vec::new();
+        for _ in 0..60 {
+            sim.step(&map, Duration::seconds(5.0));
+            live.push((sim.time(), sim.get_agent_positions(&map)));
+        }
+        assert!(live.iter().any(|(_, positions)| !positions.is_empty()));
+
+        let tmp = TmpFile::new("replay_matches_live_run.bin");
+        let path = tmp.path();
+        abstutil::write_binary(path, sim.get_replay_log().unwrap()).unwrap();
+        let log: ReplayLog = abstutil::read_binary(path, &mut Timer::throwaway()).unwrap();
+        let replay = Replay::new(log, &map);
+
+        for (time, expected) in live {
+            let actual = replay.positions_at(time);
+            assert_eq!(
+                actual.keys().collect::<Vec<_>>(),
+                expected.keys().collect::<Vec<_>>(),
+                "Different agents at {}",
+                time
+            );
+            for (agent, (on, dist)) in expected {
+                let (replay_on, replay_dist) = actual[&agent];
+                assert_eq!(on, replay_on, "{} at {}", agent, time);
+                // The log only keeps centimeters
+                assert!(
+                    (dist.inner_meters() - replay_dist.inner_meters()).abs() <= 0.01,
+                    "{} at {} is {} along {}, but replays at {}",
+                    agent,
+                    time,
+                    dist,
+                    on,
+                    replay_dist
+                );
+            }
+        }
+    });
+    t.run_fast("toggle_replay_recording", |_| {
+        let map = build_map(
+            "toggle_replay_recording",
+            raw_map(
+                vec![road(1, "residential", vec![pt(0.0, 0.0), pt(0.002, 0.0)])],
+                Vec::new(),
+            ),
+            pt(-0.001, -0.001),
+            pt(0.003, 0.001),
+        );
+        let mut sim = Sim::new(&map, "toggle_replay_recording".to_string(), None);
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+
+        // Turning recording off and back on before the first sample doesn't start a second chain
+        // of samples.
+        sim.record_replay(Some(Duration::seconds(5.0)));
+        sim.record_replay(None);
+        sim.record_replay(Some(Duration::seconds(5.0)));
+        sim.step(&map, Duration::seconds(12.0));
+        let log = sim.get_replay_log().unwrap();
+        assert_eq!(log.start, Duration::ZERO);
+        assert_eq!(log.end(), Duration::seconds(10.0));
+
+        // Switching the interval starts a fresh log, sampled only at the new interval.
+        sim.record_replay(None);
+        sim.record_replay(Some(Duration::seconds(10.0)));
+        sim.step(&map, Duration::seconds(50.0));
+        let log = sim.get_replay_log().unwrap();
+        assert_eq!(log.interval, Duration::seconds(10.0));
+        assert_eq!(log.start, Duration::seconds(12.0));
+        assert_eq!(log.end(), Duration::seconds(62.0));
+
+        sim.record_replay(None);
+        sim.step(&map, Duration::seconds(30.0));
+        assert!(sim.get_replay_log().is_none());
+    });
 }
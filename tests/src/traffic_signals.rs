@@ -0,0 +1,333 @@
+use crate::fixtures::{build_map, car, pt, raw_map, road, road_by_osm_id};
+use crate::runner::TestRunner;
+use abstutil::Timer;
+use geom::{Distance, Duration, Speed};
+use map_model::{
+    ControlTrafficSignal, Cycle, IntersectionID, IntersectionType, LaneType, Map, Position,
+    SignalControl, TurnType,
+};
+use sim::{DrivingGoal, Sim, TripSpec, VehicleSpec};
+use std::collections::HashSet;
+
+pub fn run(t: &mut TestRunner) {
+    t.run_slow("actuated_signal_reduces_delay", |_| {
+        // A signalized four-way. A steady stream of cars heads east along the main road, and just
+        // two cars cross it from the north.
+        let mut map = four_way("actuated_signal_reduces_delay");
+
+        let (west, center) = road_ends(&map, 1);
+        let east = road_ends(&map, 2).1;
+        let north = road_ends(&map, 3).0;
+        let south = road_ends(&map, 4).1;
+
+        let mut trips = Vec::new();
+        for idx in 0..40 {
+            trips.push((Duration::seconds(4.0 * (idx as f64)), west, east));
+        }
+        trips.push((Duration::seconds(20.0), north, south));
+        trips.push((Duration::seconds(100.0), north, south));
+
+        let total_trip_time = |map: &Map| {
+            let mut sim = Sim::new(map, "actuated_signal_reduces_delay".to_string(), None);
+            let vehicle = car();
+            for (time, from, to) in &trips {
+                let lane = *map
+                    .get_i(*from)
+                    .outgoing_lanes
+                    .iter()
+                    .find(|l| map.get_l(**l).lane_type == LaneType::Driving)
+                    .unwrap();
+                sim.schedule_trip(
+                    *time,
+                    TripSpec::CarAppearing {
+                        start_pos: Position::new(lane, vehicle.length),
+                        goal: DrivingGoal::end_at_border(*to, vec![LaneType::Driving], map)
+                            .unwrap(),
+                        vehicle_spec: vehicle.clone(),
+                        ped_speed: Speed::miles_per_hour(3.0),
+                    },
+                    map,
+                );
+            }
+            sim.spawn_all_trips(map, &mut Timer::throwaway(), false);
+            assert!(sim.run_until_done_or_deadline(map, Duration::minutes(30)));
+
+            let finished = sim.get_finished_trips();
+            assert_eq!(finished.finished_trips.len(), trips.len());
+            finished
+                .finished_trips
+                .into_iter()
+                .fold(Duration::ZERO, |sum, (_, _, dt)| sum + dt)
+        };
+
+        assert_eq!(
+            map.get_traffic_signal(center).control,
+            SignalControl::FixedTime
+        );
+        let fixed = total_trip_time(&map);
+
+        let mut signal = map.get_traffic_signal(center).clone();
+        signal.control = SignalControl::default_actuated();
+        let mut edits = map.get_edits().clone();
+        edits.traffic_signal_overrides.insert(center, signal);
+        map.apply_edits(edits, &mut Timer::throwaway());
+        let actuated = total_trip_time(&map);
+
+        assert!(
+            actuated < fixed,
+            "Trips took {} total with a fixed-time signal, but {} with an actuated one",
+            fixed.to_string_hms(),
+            actuated.to_string_hms()
+        );
+    });
+
+    t.run_fast("right_turn_on_red", |_| {
+        // The same four-way. The signal starts red for the north road, while cars from the east
+        // have a green.
+        let mut map = four_way("right_turn_on_red");
+
+        let (west, center) = road_ends(&map, 1);
+        let east = road_ends(&map, 2).1;
+        let north = road_ends(&map, 3).0;
+        let driving_lane_from = |map: &Map, i| {
+            *map.get_i(i)
+                .outgoing_lanes
+                .iter()
+                .find(|l| map.get_l(**l).lane_type == LaneType::Driving)
+                .unwrap()
+        };
+        let southbound = driving_lane_from(&map, north);
+        let westbound = driving_lane_from(&map, east);
+
+        // For the first 30 seconds, westbound traffic going straight has priority, and nothing
+        // from the north may go. Then everybody can yield their way through.
+        let mut red = Cycle::new(center, 0);
+        let mut green = Cycle::new(center, 1);
+        for t in map.get_turns_in_intersection(center) {
+            if t.between_sidewalks() {
+                red.priority_turns.insert(t.id);
+                green.priority_turns.insert(t.id);
+                continue;
+            }
+            if t.id.src == westbound && t.turn_type == TurnType::Straight {
+                red.priority_turns.insert(t.id);
+            } else if t.id.src != southbound {
+                red.yield_turns.insert(t.id);
+            }
+            green.yield_turns.insert(t.id);
+        }
+        let mut edits = map.get_edits().clone();
+        edits.traffic_signal_overrides.insert(
+            center,
+            ControlTrafficSignal {
+                id: center,
+                cycles: vec![red, green],
+                control: SignalControl::FixedTime,
+                all_red: Duration::ZERO,
+            },
+        );
+        map.apply_edits(edits, &mut Timer::throwaway());
+
+        // How long the car from the north takes to turn right and leave to the west, optionally
+        // with a queue of westbound cars crossing its path.
+        let right_turn_time = |map: &Map, right_turn_on_red: bool, cross_traffic: bool| {
+            let mut sim = Sim::new(map, "right_turn_on_red".to_string(), None);
+            sim.allow_right_turn_on_red(right_turn_on_red);
+            let vehicle = car();
+            let mut starts = vec![(
+                Position::new(
+                    southbound,
+                    map.get_l(southbound).length() - Distance::meters(30.0),
+                ),
+                west,
+            )];
+            if cross_traffic {
+                for idx in 0..8 {
+                    starts.push((
+                        Position::new(
+                            westbound,
+                            map.get_l(westbound).length()
+                                - Distance::meters(1.0 + 8.0 * (idx as f64)),
+                        ),
+                        west,
+                    ));
+                }
+            }
+            for (start_pos, to) in starts {
+                sim.schedule_trip(
+                    Duration::ZERO,
+                    TripSpec::CarAppearing {
+                        start_pos,
+                        goal: DrivingGoal::end_at_border(to, vec![LaneType::Driving], map).unwrap(),
+                        vehicle_spec: vehicle.clone(),
+                        ped_speed: Speed::miles_per_hour(3.0),
+                    },
+                    map,
+                );
+            }
+            sim.spawn_all_trips(map, &mut Timer::throwaway(), false);
+            assert!(sim.run_until_done_or_deadline(map, Duration::minutes(5)));
+
+            // The right-turner was scheduled first.
+            let finished = sim.get_finished_trips();
+            finished
+                .finished_trips
+                .into_iter()
+                .min_by_key(|(id, _, _)| *id)
+                .unwrap()
+                .2
+        };
+
+        let waiting_for_green = right_turn_time(&map, false, false);
+        let clear = right_turn_time(&map, true, false);
+        let busy = right_turn_time(&map, true, true);
+        // The light doesn't turn green until 30 seconds in, and the turn is clear long before.
+        assert!(
+            clear + Duration::seconds(10.0) < waiting_for_green,
+            "Turning right on red took {}, but waiting for the green took {}",
+            clear,
+            waiting_for_green
+        );
+        // Westbound cars going straight have priority and cross the right turn's path.
+        assert!(
+            busy > clear + Duration::seconds(2.0),
+            "Turning right on red took {} with priority cross traffic, and {} without",
+            busy,
+            clear
+        );
+    });
+
+    t.run_fast("all_red_clearance", |_| {
+        // The same four-way. Westbound traffic going straight has priority in both cycles, but
+        // there's an all-red clearance between them.
+        let mut map = four_way("all_red_clearance");
+
+        let (west, center) = road_ends(&map, 1);
+        let east = road_ends(&map, 2).1;
+        let westbound = *map
+            .get_i(east)
+            .outgoing_lanes
+            .iter()
+            .find(|l| map.get_l(**l).lane_type == LaneType::Driving)
+            .unwrap();
+
+        let mut cycles = vec![Cycle::new(center, 0), Cycle::new(center, 1)];
+        for t in map.get_turns_in_intersection(center) {
+            for cycle in cycles.iter_mut() {
+                if t.id.src == westbound && t.turn_type == TurnType::Straight {
+                    cycle.priority_turns.insert(t.id);
+                } else {
+                    cycle.yield_turns.insert(t.id);
+                }
+            }
+        }
+        let mut edits = map.get_edits().clone();
+        edits.traffic_signal_overrides.insert(
+            center,
+            ControlTrafficSignal {
+                id: center,
+                cycles,
+                control: SignalControl::FixedTime,
+                all_red: Duration::seconds(20.0),
+            },
+        );
+        map.apply_edits(edits, &mut Timer::throwaway());
+
+        // A queue of slow westbound cars, so somebody's always partway through the intersection.
+        let mut sim = Sim::new(&map, "all_red_clearance".to_string(), None);
+        let vehicle = VehicleSpec {
+            max_speed: Some(Speed::meters_per_second(2.0)),
+            ..car()
+        };
+        let num_cars = 10;
+        for idx in 0..num_cars {
+            sim.schedule_trip(
+                Duration::ZERO,
+                TripSpec::CarAppearing {
+                    start_pos: Position::new(
+                        westbound,
+                        map.get_l(westbound).length() - Distance::meters(1.0 + 8.0 * (idx as f64)),
+                    ),
+                    goal: DrivingGoal::end_at_border(west, vec![LaneType::Driving], &map).unwrap(),
+                    vehicle_spec: vehicle.clone(),
+                    ped_speed: Speed::miles_per_hour(3.0),
+                },
+                &map,
+            );
+        }
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+
+        // The first cycle is green for 30 seconds, then everything's red for 20.
+        let green_ends = Duration::seconds(30.0);
+        let next_green = Duration::seconds(50.0);
+        let dt = Duration::seconds(0.5);
+        let mut ever_accepted = HashSet::new();
+        let mut started_turns_at = Vec::new();
+        let mut in_intersection_when_cleared = HashSet::new();
+        while sim.time() < Duration::minutes(5) && !sim.is_done() {
+            sim.step(&map, dt);
+            let now = sim.time();
+            let accepted = sim.get_accepted_agents(center);
+            for agent in &accepted {
+                if ever_accepted.insert(*agent) {
+                    // Somewhere between the last step and now
+                    started_turns_at.push(now);
+                }
+            }
+
+            if now == green_ends {
+                in_intersection_when_cleared = accepted;
+            } else if now == Duration::seconds(40.0) {
+                assert!(sim
+                    .current_signal_cycle(map.get_traffic_signal(center))
+                    .0
+                    .is_none());
+            } else if now == next_green - dt {
+                for agent in &in_intersection_when_cleared {
+                    assert!(
+                        !accepted.contains(agent),
+                        "{} is still in the intersection at the end of the clearance",
+                        agent
+                    );
+                }
+            }
+        }
+        assert_eq!(sim.get_finished_trips().finished_trips.len(), num_cars);
+
+        assert!(!in_intersection_when_cleared.is_empty());
+        for t in &started_turns_at {
+            assert!(
+                *t - dt < green_ends || *t >= next_green,
+                "Somebody started a turn around {}, during the all-red clearance",
+                t
+            );
+        }
+        assert!(started_turns_at.iter().any(|t| *t < green_ends));
+        assert!(started_turns_at.iter().any(|t| *t >= next_green));
+    });
+}
+
+// A signalized four-way, with borders at the end of each road. Roads 1 and 2 go west to east, and
+// 3 and 4 go north to south.
+fn four_way(name: &str) -> Map {
+    let roads = vec![
+        road(1, "residential", vec![pt(0.0, 0.002), pt(0.003, 0.002)]),
+        road(2, "residential", vec![pt(0.003, 0.002), pt(0.006, 0.002)]),
+        road(3, "residential", vec![pt(0.003, 0.004), pt(0.003, 0.002)]),
+        road(4, "residential", vec![pt(0.003, 0.002), pt(0.003, 0.0)]),
+    ];
+    let mut raw = raw_map(roads, Vec::new());
+    for i in raw.intersections.values_mut() {
+        i.intersection_type = if i.point == pt(0.003, 0.002) {
+            IntersectionType::TrafficSignal
+        } else {
+            IntersectionType::Border
+        };
+    }
+    build_map(name, raw, pt(-0.001, -0.001), pt(0.007, 0.005))
+}
+
+fn road_ends(map: &Map, osm_way_id: i64) -> (IntersectionID, IntersectionID) {
+    let r = road_by_osm_id(map, osm_way_id);
+    (r.src_i, r.dst_i)
+}
@@ -1,9 +1,185 @@
+use crate::fixtures::{build_map, pt, raw_map, road};
 use crate::runner::TestRunner;
 use abstutil::Timer;
-use geom::Duration;
-use sim::{Event, Scenario, SidewalkSpot, SimFlags, TripSpec};
+use geom::{Distance, Duration, LonLat, Pt2D};
+use gtfs::{ClippedRoute, Route};
+use map_model::{BusRouteProblem, LaneID, Map};
+use sim::{bus_dwell_time, Event, Scenario, SidewalkSpot, Sim, SimFlags, TripSpec};
 
 pub fn run(t: &mut TestRunner) {
+    t.run_fast("clip_route_dipping_out_of_bounds", |_| {
+        // Only stops with positive longitude are in bounds. The route leaves the map after its
+        // second stop and comes back for three more.
+        let route = Route {
+            name: "fixture".to_string(),
+            stops: vec![
+                LonLat::new(1.0, 0.0),
+                LonLat::new(2.0, 0.0),
+                LonLat::new(-1.0, 0.0),
+                LonLat::new(-2.0, 0.0),
+                LonLat::new(3.0, 0.0),
+                LonLat::new(4.0, 0.0),
+                LonLat::new(5.0, 0.0),
+            ],
+        };
+        let in_bounds = |pt: LonLat| pt.longitude > 0.0;
+        assert_eq!(
+            route.clip(in_bounds),
+            Some(ClippedRoute {
+                stops: vec![
+                    LonLat::new(3.0, 0.0),
+                    LonLat::new(4.0, 0.0),
+                    LonLat::new(5.0, 0.0),
+                ],
+                enters_from_border: true,
+                exits_to_border: false,
+            })
+        );
+
+        // Not enough contiguous stops left
+        let short = Route {
+            name: "short".to_string(),
+            stops: vec![
+                LonLat::new(1.0, 0.0),
+                LonLat::new(-1.0, 0.0),
+                LonLat::new(2.0, 0.0),
+            ],
+        };
+        assert_eq!(short.clip(in_bounds), None);
+    });
+
+    t.run_fast("bus_route_problems_reported", |_| {
+        // A residential road with steps heading north from the middle. The middle stop is only
+        // next to the steps, where buses can't go.
+        let roads = vec![
+            road(
+                1,
+                "residential",
+                vec![pt(0.0, 0.0), pt(0.002, 0.0), pt(0.004, 0.0)],
+            ),
+            road(2, "steps", vec![pt(0.002, 0.0), pt(0.002, 0.002)]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        raw.bus_routes = vec![Route {
+            name: "fixture".to_string(),
+            stops: vec![pt(0.001, -0.00005), pt(0.00205, 0.001), pt(0.003, -0.00005)],
+        }];
+        let map = build_map(
+            "bus_route_problems_reported",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.005, 0.003),
+        );
+
+        let report = map
+            .get_bus_route_reports()
+            .iter()
+            .find(|r| r.route_name == "fixture")
+            .expect("No report for the route with a stop next to the steps");
+        let bad_sidewalks: Vec<LaneID> = report
+            .problems
+            .iter()
+            .filter_map(|p| match p {
+                BusRouteProblem::StopNotOnTransitLane(l) => Some(*l),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(bad_sidewalks.len(), 1);
+        assert_eq!(map.get_parent(bad_sidewalks[0]).osm_way_id, 2);
+        assert!(map.get_l(bad_sidewalks[0]).is_sidewalk());
+    });
+
+    t.run_fast("bus_stop_far_from_sidewalk_dropped", |_| {
+        let mut raw = raw_map(
+            vec![road(1, "residential", vec![pt(0.0, 0.0), pt(0.004, 0.0)])],
+            Vec::new(),
+        );
+        // The middle stop is about 100m north of the only road.
+        let far_stop = pt(0.002, 0.001);
+        raw.bus_routes = vec![Route {
+            name: "fixture".to_string(),
+            stops: vec![pt(0.001, -0.00005), far_stop, pt(0.003, -0.00005)],
+        }];
+        let map = build_map(
+            "bus_stop_far_from_sidewalk_dropped",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.005, 0.002),
+        );
+        let far_pt = Pt2D::from_gps(far_stop, map.get_gps_bounds()).unwrap();
+
+        let dropped = map.get_dropped_bus_stops();
+        assert_eq!(dropped.len(), 1);
+        assert!(dropped[0].pt.approx_eq(far_pt, Distance::meters(0.1)));
+        assert_eq!(
+            dropped[0].problem,
+            BusRouteProblem::StopFarFromSidewalk(dropped[0].pt)
+        );
+        let report = map
+            .get_bus_route_reports()
+            .iter()
+            .find(|r| r.route_name == "fixture")
+            .expect("No report for the route with a far stop");
+        assert!(report.problems.contains(&dropped[0].problem));
+
+        // The other stops are placed, and nothing got snapped toward the far one.
+        assert_eq!(map.all_bus_stops().len(), 2);
+        for bs in map.all_bus_stops().values() {
+            assert!(map.get_l(bs.sidewalk_pos.lane()).is_sidewalk());
+            assert!(bs.sidewalk_pos.pt(&map).dist_to(far_pt) > Distance::meters(50.0));
+        }
+    });
+
+    t.run_fast("bus_headways_recorded", |_| {
+        // One square block. The route has a stop on the south and north roads, so it gets two
+        // buses that loop counter-clockwise and both serve each stop.
+        let roads = vec![
+            road(1, "residential", vec![pt(0.0, 0.0), pt(0.003, 0.0)]),
+            road(2, "residential", vec![pt(0.003, 0.0), pt(0.003, 0.002)]),
+            road(3, "residential", vec![pt(0.003, 0.002), pt(0.0, 0.002)]),
+            road(4, "residential", vec![pt(0.0, 0.002), pt(0.0, 0.0)]),
+        ];
+        let mut raw = raw_map(roads, Vec::new());
+        raw.bus_routes = vec![Route {
+            name: "loop".to_string(),
+            stops: vec![pt(0.0015, -0.00005), pt(0.0015, 0.00205)],
+        }];
+        let map = build_map(
+            "bus_headways_recorded",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.004, 0.003),
+        );
+        let route = map.get_bus_route("loop").unwrap();
+
+        let mut sim = Sim::new(&map, "bus_headways_recorded".to_string(), None);
+        let buses = sim.seed_bus_route(route, &map, &mut Timer::throwaway());
+        assert_eq!(buses.len(), 2);
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+
+        let mut arrivals = Vec::new();
+        for _ in 0..60 {
+            sim.step(&map, Duration::seconds(10.0));
+            for ev in sim.get_events_since_last_step() {
+                if let Event::BusArrivedAtStop(bus, r, stop, headway, _) = ev {
+                    assert_eq!(*r, route.id);
+                    arrivals.push((*bus, *stop, *headway));
+                }
+            }
+        }
+
+        // The first bus to reach a stop has nothing to compare against. The next one reports the
+        // gap since then.
+        let stop = route.stops[1];
+        let at_stop: Vec<_> = arrivals.iter().filter(|(_, s, _)| *s == stop).collect();
+        assert!(at_stop.len() >= 2, "Not enough arrivals: {:?}", arrivals);
+        assert_eq!(at_stop[0].2, None);
+        let headway = at_stop[1].2.expect("Second arrival has no headway");
+        assert_ne!(at_stop[0].0, at_stop[1].0);
+        assert!(headway > Duration::ZERO);
+        assert!(sim.get_route_headways(route.id).contains(&headway));
+    });
+
     t.run_slow("bus_reaches_stops", |h| {
         let (map, mut sim, _) = SimFlags::for_test("bus_reaches_stops")
             .load(Some(Duration::seconds(30.0)), &mut Timer::throwaway());
@@ -15,11 +191,11 @@ pub fn run(t: &mut TestRunner) {
         let mut expectations: Vec<Event> = Vec::new();
         // TODO assert stuff about other buses as well, although the timing is a little unclear
         for stop in route.stops.iter().skip(1) {
-            expectations.push(Event::BusArrivedAtStop(bus, *stop));
-            expectations.push(Event::BusDepartedFromStop(bus, *stop));
+            expectations.push(Event::BusArrivedAtStop(bus, route.id, *stop, None, 0));
+            expectations.push(Event::BusDepartedFromStop(bus, *stop, 0, 0));
         }
 
-        sim.run_until_expectations_met(&map, expectations, Duration::minutes(10));
+        run_until_expectations_met(&mut sim, &map, expectations, Duration::minutes(10));
         // Make sure buses don't block a sim from being considered done
         sim.just_run_until_done(&map, Some(Duration::minutes(11)));
     });
@@ -61,20 +237,129 @@ pub fn run(t: &mut TestRunner) {
         sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
         h.setup_done(&sim);
 
-        sim.run_until_expectations_met(
+        run_until_expectations_met(
+            &mut sim,
             &map,
             vec![
                 Event::PedReachedBusStop(ped, ped_stop1),
-                Event::BusArrivedAtStop(bus, ped_stop1),
+                Event::BusArrivedAtStop(bus, route.id, ped_stop1, None, 0),
                 Event::PedEntersBus(ped, bus),
-                Event::BusDepartedFromStop(bus, ped_stop1),
-                Event::BusArrivedAtStop(bus, ped_stop2),
+                Event::BusDepartedFromStop(bus, ped_stop1, 1, 0),
+                Event::BusArrivedAtStop(bus, route.id, ped_stop2, None, 1),
                 Event::PedLeavesBus(ped, bus),
                 Event::PedReachedBuilding(ped, goal_bldg),
-                Event::BusDepartedFromStop(bus, ped_stop2),
-                Event::BusArrivedAtStop(bus, route.stops[3]),
+                Event::BusDepartedFromStop(bus, ped_stop2, 0, 1),
+                Event::BusArrivedAtStop(bus, route.id, route.stops[3], None, 0),
             ],
             Duration::minutes(9),
         );
     });
+    t.run_slow("bus_dwell_time_at_stops", |h| {
+        let (map, mut sim, mut rng) = SimFlags::for_test("bus_dwell_time_at_stops")
+            .load(Some(Duration::seconds(30.0)), &mut Timer::throwaway());
+        let route = map.get_bus_route("49").unwrap();
+        let buses = sim.seed_bus_route(route, &map, &mut Timer::throwaway());
+        let bus = buses[0];
+        let ped_stop1 = route.stops[1];
+        let ped_stop2 = route.stops[2];
+        let start_bldg = *map
+            .get_l(map.get_bs(ped_stop1).sidewalk_pos.lane())
+            .building_paths
+            .last()
+            .unwrap();
+        let goal_bldg = map
+            .get_l(map.get_bs(ped_stop2).sidewalk_pos.lane())
+            .building_paths[0];
+        // Everybody walks together, so they all catch the same bus.
+        let num_riders = 3;
+        let ped_speed = Scenario::rand_ped_speed(&mut rng);
+        for _ in 0..num_riders {
+            sim.schedule_trip(
+                Duration::ZERO,
+                TripSpec::UsingTransit {
+                    start: SidewalkSpot::building(start_bldg, &map),
+                    route: route.id,
+                    stop1: ped_stop1,
+                    stop2: ped_stop2,
+                    goal: SidewalkSpot::building(goal_bldg, &map),
+                    ped_speed,
+                },
+                &map,
+            );
+        }
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+        h.setup_done(&sim);
+
+        // Step finely, since events don't record exactly when they happened.
+        let dt = Duration::seconds(0.1);
+        let mut arrived_at = None;
+        let mut dwells = Vec::new();
+        let mut departures = Vec::new();
+        while departures.len() < 2 && sim.time() < Duration::minutes(10) {
+            sim.step(&map, dt);
+            for ev in sim.get_events_since_last_step() {
+                match ev {
+                    Event::BusArrivedAtStop(b, _, stop, _, _)
+                        if *b == bus && (*stop == ped_stop1 || *stop == ped_stop2) =>
+                    {
+                        arrived_at = Some(sim.time());
+                    }
+                    Event::BusDepartedFromStop(b, stop, boarded, alighted)
+                        if *b == bus && (*stop == ped_stop1 || *stop == ped_stop2) =>
+                    {
+                        dwells.push(sim.time() - arrived_at.take().unwrap());
+                        departures.push((*stop, *boarded, *alighted));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        assert_eq!(
+            departures,
+            vec![(ped_stop1, num_riders, 0), (ped_stop2, 0, num_riders)]
+        );
+        // Each arrival and departure is only noticed at the end of a step.
+        let predicted = bus_dwell_time(num_riders, 0) + bus_dwell_time(0, num_riders);
+        let actual = dwells[0] + dwells[1];
+        assert!(
+            (actual.inner_seconds() - predicted.inner_seconds()).abs() <= 2.0 * dt.inner_seconds(),
+            "Buses dwelled for {}, but expected {}",
+            actual,
+            predicted
+        );
+    });
+}
+
+// Like Sim::run_until_expectations_met, but bus arrivals match regardless of their headway, which
+// depends on where the route's other buses were seeded.
+fn run_until_expectations_met(
+    sim: &mut Sim,
+    map: &Map,
+    all_expectations: Vec<Event>,
+    time_limit: Duration,
+) {
+    let mut expectations = all_expectations.into_iter().peekable();
+    sim.step(map, time_limit);
+    for ev in sim.get_events_since_last_step() {
+        let met = match (ev, expectations.peek()) {
+            (
+                Event::BusArrivedAtStop(bus1, route1, stop1, _, riders1),
+                Some(Event::BusArrivedAtStop(bus2, route2, stop2, _, riders2)),
+            ) => (bus1, route1, stop1, riders1) == (bus2, route2, stop2, riders2),
+            (ev, Some(expected)) => ev == expected,
+            (_, None) => break,
+        };
+        if met {
+            println!("At {}, met expectation {:?}", sim.time(), ev);
+            expectations.next();
+        }
+    }
+    let missing: Vec<Event> = expectations.collect();
+    if !missing.is_empty() {
+        panic!(
+            "Time limit {} hit, but some expectations never met: {:?}",
+            time_limit, missing
+        );
+    }
 }
@@ -1,8 +1,21 @@
+use crate::fixtures::{
+    build_map, building, car, driving_lane, oneway, pt, raw_map, rectangle, road, TmpFile,
+};
 use crate::runner::TestRunner;
-use abstutil::Timer;
-use geom::Duration;
-use map_model::{BuildingID, IntersectionID};
-use sim::{DrivingGoal, Event, Scenario, SidewalkSpot, SimFlags, TripSpec};
+use abstutil::{Timer, WeightedUsizeChoice};
+use geom::{Distance, Duration, LonLat, Speed};
+use map_model::{
+    raw_data, BikeRackID, BuildingID, ControlTrafficSignal, Cycle, IntersectionID,
+    IntersectionType, LaneID, LaneType, Map, Path, PathRequest, PathStep, Position, RoadID,
+    SignalControl, SpawnError, Traversable,
+};
+use sim::{
+    AgentID, CarID, DemandModel, DrivingGoal, Event, LaneClosure, ODMatrix, OriginDestination,
+    Scenario, SeedParkedCars, SidewalkPOI, SidewalkSpot, Sim, SimFlags, SpawnOverTime, SpawnTrip,
+    TripID, TripMetadata, TripMode, TripPurpose, TripSpec, VehicleClass, VehicleSpec, VehicleType,
+    BIKE_LENGTH, MAX_TRUCK_LENGTH,
+};
+use std::collections::{BTreeMap, BTreeSet};
 
 pub fn run(t: &mut TestRunner) {
     t.run_slow("bike_from_border", |h| {
@@ -36,4 +49,1183 @@ pub fn run(t: &mut TestRunner) {
         );
         sim.just_run_until_done(&map, Some(Duration::minutes(1)));
     });
+    t.run_slow("record_trip_details", |h| {
+        let (map, mut sim, mut rng) =
+            SimFlags::for_test("record_trip_details").load(None, &mut Timer::throwaway());
+        sim.record_trip_details(true);
+        sim.schedule_trip(
+            Duration::ZERO,
+            TripSpec::UsingBike {
+                start: SidewalkSpot::start_at_border(IntersectionID(186), &map).unwrap(),
+                vehicle: Scenario::rand_bike(&mut rng),
+                goal: DrivingGoal::ParkNear(BuildingID(319)),
+                ped_speed: Scenario::rand_ped_speed(&mut rng),
+            },
+            &map,
+        );
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+        h.setup_done(&sim);
+        sim.just_run_until_done(&map, Some(Duration::minutes(8)));
+
+        let finished = sim.get_finished_trips();
+        assert_eq!(finished.finished_trips.len(), 1);
+        let (trip, _, total_time) = finished.finished_trips[0];
+        let legs = sim.get_trip_details(trip);
+        assert_eq!(
+            legs.iter().map(|leg| leg.mode).collect::<Vec<_>>(),
+            vec![TripMode::Walk, TripMode::Bike, TripMode::Walk]
+        );
+        let mut legs_time = Duration::ZERO;
+        for leg in legs {
+            assert!(leg.path.is_some());
+            legs_time += leg.finished_at.unwrap() - leg.started_at;
+        }
+        assert!(legs_time <= total_time);
+        // A header, then one row per leg
+        assert_eq!(sim.finished_trips_csv(&map).lines().count(), 4);
+    });
+    t.run_slow("trip_metadata", |h| {
+        let (map, mut sim, mut rng) =
+            SimFlags::for_test("trip_metadata").load(None, &mut Timer::throwaway());
+        let metadata = TripMetadata {
+            purpose: (TripPurpose::Work, TripPurpose::Home),
+            from_parcel: None,
+            to_parcel: Some(751_392),
+            departure_window: (Duration::ZERO, Duration::minutes(1)),
+        };
+        for md in vec![Some(metadata.clone()), None] {
+            sim.schedule_trip_with_metadata(
+                Duration::ZERO,
+                TripSpec::UsingBike {
+                    start: SidewalkSpot::start_at_border(IntersectionID(186), &map).unwrap(),
+                    vehicle: Scenario::rand_bike(&mut rng),
+                    goal: DrivingGoal::ParkNear(BuildingID(319)),
+                    ped_speed: Scenario::rand_ped_speed(&mut rng),
+                },
+                md,
+                &map,
+            );
+        }
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+        h.setup_done(&sim);
+        sim.just_run_until_done(&map, Some(Duration::minutes(10)));
+
+        let finished = sim.get_finished_trips();
+        assert_eq!(finished.finished_trips.len(), 2);
+        assert_eq!(finished.metadata.len(), 1);
+        let (trip, md) = finished.metadata.iter().next().unwrap();
+        assert_eq!(md, &metadata);
+        assert_eq!(sim.get_trip_metadata(*trip), Some(&metadata));
+        // Heading home, so the purpose comes from the origin
+        assert_eq!(
+            finished.per_purpose().keys().cloned().collect::<Vec<_>>(),
+            vec![TripPurpose::Work]
+        );
+
+        let csv = sim.finished_trips_csv(&map);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "trip,mode,spawn_time,finish_time,total_time,purpose_from,purpose_to,from_parcel,to_parcel"
+        );
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines.iter().filter(|l| l.ends_with(",,,,")).count(), 1);
+    });
+    t.run_slow("trips_csv", |h| {
+        let (map, mut sim, mut rng) =
+            SimFlags::for_test("trips_csv").load(None, &mut Timer::throwaway());
+        Scenario::small_run(&map).instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway());
+        h.setup_done(&sim);
+        sim.run_until_done_or_deadline(&map, Duration::minutes(20));
+
+        let tmp = TmpFile::new("trips_csv.csv");
+        let path = tmp.path();
+        sim.save_trips_csv(path, &map).unwrap();
+        let csv = std::fs::read_to_string(path).unwrap();
+
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("trip,mode,spawn_time,finish_time,total_time")
+        );
+        let finished = sim.get_finished_trips().finished_trips;
+        assert!(!finished.is_empty());
+        let mut rows = 0;
+        for line in lines {
+            let cols: Vec<&str> = line.split(',').collect();
+            assert_eq!(cols.len(), 5);
+            let seconds = |idx: usize| cols[idx].parse::<f64>().unwrap();
+            assert!((seconds(3) - seconds(2) - seconds(4)).abs() < 1e-6);
+            let trip = TripID(cols[0].parse::<usize>().unwrap());
+            let (_, mode, total_time) = finished.iter().find(|(id, _, _)| *id == trip).unwrap();
+            assert_eq!(cols[1], format!("{:?}", mode));
+            assert!((seconds(4) - total_time.inner_seconds()).abs() < 1e-6);
+            rows += 1;
+        }
+        assert_eq!(rows, finished.len());
+    });
+    t.run_slow("warm_start", |h| {
+        let steady_demand = |map: &Map| Scenario {
+            scenario_name: "steady_demand".to_string(),
+            map_name: map.get_name().to_string(),
+            seed_parked_cars: vec![SeedParkedCars {
+                neighborhood: "_everywhere_".to_string(),
+                cars_per_building: WeightedUsizeChoice {
+                    weights: vec![5, 5],
+                },
+            }],
+            spawn_over_time: vec![SpawnOverTime {
+                num_agents: 600,
+                start_time: Duration::ZERO,
+                stop_time: Duration::minutes(60),
+                start_from_neighborhood: "_everywhere_".to_string(),
+                goal: OriginDestination::Neighborhood("_everywhere_".to_string()),
+                percent_biking: 0.0,
+                percent_use_transit: 0.0,
+                percent_trucks: 0.0,
+            }],
+            border_spawn_over_time: Vec::new(),
+            individ_trips: Vec::new(),
+            trip_metadata: BTreeMap::new(),
+            lane_closures: Vec::new(),
+        };
+        let warm_at = Duration::minutes(30);
+        // Right after the warm start, and once things have settled
+        let check_at = vec![Duration::minutes(1), Duration::minutes(5)];
+        // (cars, pedestrians) active
+        let by_mode = |sim: &Sim| {
+            let agents = sim.active_agents();
+            let cars = agents
+                .iter()
+                .filter(|a| match a {
+                    AgentID::Car(_) => true,
+                    _ => false,
+                })
+                .count();
+            (cars, agents.len() - cars)
+        };
+
+        let (map, mut cold, mut rng) =
+            SimFlags::for_test("warm_start_cold").load(None, &mut Timer::throwaway());
+        steady_demand(&map).instantiate(&mut cold, &map, &mut rng, &mut Timer::throwaway());
+        cold.timed_step(&map, warm_at, &mut Timer::throwaway());
+
+        let (map, mut warm, mut rng) =
+            SimFlags::for_test("warm_start_warm").load(None, &mut Timer::throwaway());
+        warm.set_warm_start(Some(warm_at));
+        steady_demand(&map).instantiate(&mut warm, &map, &mut rng, &mut Timer::throwaway());
+        h.setup_done(&warm);
+        warm.timed_step(&map, warm_at, &mut Timer::throwaway());
+
+        // The network should be about as full of each mode as if it'd been running the whole
+        // time. Pedestrians whose walk to their car finished before the warm start shouldn't all
+        // pour out of buildings at once.
+        let mut elapsed = Duration::ZERO;
+        for t in check_at {
+            cold.timed_step(&map, t - elapsed, &mut Timer::throwaway());
+            warm.timed_step(&map, t - elapsed, &mut Timer::throwaway());
+            elapsed = t;
+
+            let (cold_cars, cold_peds) = by_mode(&cold);
+            let (warm_cars, warm_peds) = by_mode(&warm);
+            for (mode, warm_count, cold_count) in vec![
+                ("cars", warm_cars, cold_cars),
+                ("pedestrians", warm_peds, cold_peds),
+            ] {
+                let ratio = (warm_count as f64) / (cold_count as f64);
+                assert!(
+                    ratio > 0.5 && ratio < 1.5,
+                    "{} after the warm start: {} {} active, but {} without",
+                    t,
+                    warm_count,
+                    mode,
+                    cold_count
+                );
+            }
+        }
+        // Trips that departed before the warm start don't count.
+        let csv = warm.finished_trips_csv(&map);
+        for line in csv.lines().skip(1) {
+            let spawn_time = line.split(',').nth(2).unwrap().parse::<f64>().unwrap();
+            assert!(spawn_time >= warm_at.inner_seconds());
+        }
+        assert_eq!(
+            csv.lines().count() - 1,
+            warm.get_finished_trips().finished_trips.len()
+        );
+    });
+    t.run_fast("clone_scenario", |_| {
+        let original = Scenario {
+            scenario_name: "weekday".to_string(),
+            map_name: "montlake".to_string(),
+            seed_parked_cars: vec![SeedParkedCars {
+                neighborhood: "_everywhere_".to_string(),
+                cars_per_building: WeightedUsizeChoice {
+                    weights: vec![5, 5],
+                },
+            }],
+            spawn_over_time: vec![SpawnOverTime {
+                num_agents: 100,
+                start_time: Duration::ZERO,
+                stop_time: Duration::minutes(10),
+                start_from_neighborhood: "_everywhere_".to_string(),
+                goal: OriginDestination::Neighborhood("_everywhere_".to_string()),
+                percent_biking: 0.1,
+                percent_use_transit: 0.2,
+                percent_trucks: 0.0,
+            }],
+            border_spawn_over_time: Vec::new(),
+            individ_trips: Vec::new(),
+            trip_metadata: BTreeMap::new(),
+            lane_closures: Vec::new(),
+        };
+        let copy = original.clone_as("weekday_variant".to_string());
+        assert_eq!(copy.scenario_name, "weekday_variant");
+        assert_eq!(original.scenario_name, "weekday");
+
+        let mut renamed_back = copy.clone();
+        renamed_back.scenario_name = original.scenario_name.clone();
+        assert_eq!(
+            abstutil::to_json(&renamed_back),
+            abstutil::to_json(&original)
+        );
+    });
+    t.run_fast("od_matrix_import", |_| {
+        // One street with two buildings on each half. Buildings 2 and 4 should attract most trips:
+        // one has lots of housing, the other is an office.
+        let mut bldgs = vec![
+            building(2, 0.0005, -0.0003),
+            building(3, 0.0015, -0.0003),
+            building(4, 0.0025, -0.0003),
+            building(5, 0.0035, -0.0003),
+        ];
+        bldgs[0].num_residential_units = Some(50);
+        bldgs[2]
+            .osm_tags
+            .insert("building".to_string(), "office".to_string());
+        let mut raw = raw_map(
+            vec![road(1, "residential", vec![pt(0.0, 0.0), pt(0.004, 0.0)])],
+            bldgs,
+        );
+        for i in raw.intersections.values_mut() {
+            if i.point == pt(0.004, 0.0) {
+                i.intersection_type = IntersectionType::Border;
+            }
+        }
+        let map = build_map("od_matrix_import", raw, pt(-0.001, -0.001), pt(0.005, 0.001));
+        let mut rng = SimFlags::for_test("od_matrix_import").make_rng();
+
+        // Split the map into a west and east zone, and put a third zone off the map entirely, past
+        // the border.
+        let feature = |name: &str, pts: Vec<LonLat>| {
+            let coords: Vec<String> = pts
+                .iter()
+                .map(|pt| format!("[{}, {}]", pt.longitude, pt.latitude))
+                .collect();
+            format!(
+                r#"{{"type": "Feature", "properties": {{"name": "{}"}}, "geometry": {{"type": "Polygon", "coordinates": [[{}]]}}}}"#,
+                name,
+                coords.join(", ")
+            )
+        };
+        let (zones, csv) = (
+            TmpFile::new("od_matrix_import.geojson"),
+            TmpFile::new("od_matrix_import.csv"),
+        );
+        let (zones_path, csv_path) = (zones.path(), csv.path());
+        std::fs::write(
+            zones_path,
+            format!(
+                r#"{{"type": "FeatureCollection", "features": [{}, {}, {}]}}"#,
+                feature("west", rectangle(pt(-0.001, -0.001), pt(0.002, 0.001))),
+                feature("east", rectangle(pt(0.002, -0.001), pt(0.005, 0.001))),
+                feature("offmap", rectangle(pt(0.006, -0.001), pt(0.008, 0.001)))
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            csv_path,
+            "origin,destination,hour,mode,count\n\
+             west,east,7,walk,30\n\
+             east,west,8,walk,30\n\
+             offmap,west,9,walk,4\n",
+        )
+        .unwrap();
+        let matrix = ODMatrix::load(csv_path, zones_path);
+        std::fs::write(
+            csv_path,
+            "origin,destination,hour,mode,count\nwest,nowhere,7,walk,1\n",
+        )
+        .unwrap();
+        let unknown_zone = ODMatrix::load(csv_path, zones_path);
+        assert!(unknown_zone.is_err());
+        std::fs::write(
+            zones_path,
+            r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "properties": {"name": "west"}, "geometry": {"type": "Polygon", "coordinates": []}}]}"#,
+        )
+        .unwrap();
+        let no_rings = ODMatrix::load(csv_path, zones_path);
+        assert_eq!(
+            no_rings.err().map(|err| err.kind()),
+            Some(std::io::ErrorKind::InvalidData)
+        );
+
+        let scenario = matrix.unwrap().to_scenario(
+            "od".to_string(),
+            &map,
+            &mut rng,
+            &mut Timer::throwaway(),
+        );
+        assert_eq!(scenario.individ_trips.len(), 64);
+        let bldg = |spot: &SidewalkSpot| match spot.connection {
+            SidewalkPOI::Building(b) => Some(map.get_b(b).osm_way_id),
+            _ => None,
+        };
+        let mut from_busy_bldgs = 0;
+        for trip in &scenario.individ_trips {
+            match trip {
+                SpawnTrip::JustWalking(depart, from, to) => {
+                    let hour = (depart.inner_seconds() / 3600.0).floor() as usize;
+                    match (hour, bldg(from), bldg(to)) {
+                        (7, Some(2), Some(4)) | (7, Some(2), Some(5)) => from_busy_bldgs += 1,
+                        (7, Some(3), Some(4)) | (7, Some(3), Some(5)) => {}
+                        (8, Some(4), Some(2)) | (8, Some(4), Some(3)) => from_busy_bldgs += 1,
+                        (8, Some(5), Some(2)) | (8, Some(5), Some(3)) => {}
+                        // Clipped to the border
+                        (9, None, Some(2)) | (9, None, Some(3)) => match from.connection {
+                            SidewalkPOI::Border(_) => {}
+                            ref x => panic!("Trip from off the map starts at {:?}", x),
+                        },
+                        x => panic!("Unexpected trip (hour, from, to) {:?}", x),
+                    }
+                }
+                x => panic!("Walking trip became {:?}", x),
+            }
+        }
+        // 50 units and an office outweigh a plain building by far.
+        assert!(from_busy_bldgs >= 45, "{} of 60", from_busy_bldgs);
+    });
+    t.run_slow("agents_on_road", |h| {
+        let (map, mut sim, mut rng) =
+            SimFlags::for_test("agents_on_road").load(None, &mut Timer::throwaway());
+        let long_lane = |r: RoadID, lane_type: LaneType| {
+            map.get_r(r).all_lanes().into_iter().find(|l| {
+                let lane = map.get_l(*l);
+                lane.lane_type == lane_type && lane.length() > Distance::meters(50.0)
+            })
+        };
+        // A road with room for a car and a pedestrian, next to another road with room for a car
+        let (road, driving, sidewalk, next_road, next_driving) = map
+            .all_roads()
+            .iter()
+            .filter_map(|r| {
+                let driving = long_lane(r.id, LaneType::Driving)?;
+                let sidewalk = long_lane(r.id, LaneType::Sidewalk)?;
+                let (next_road, next_driving) = map
+                    .get_next_roads(r.id)
+                    .into_iter()
+                    .filter(|next| *next != r.id)
+                    .find_map(|next| long_lane(next, LaneType::Driving).map(|l| (next, l)))?;
+                Some((r.id, driving, sidewalk, next_road, next_driving))
+            })
+            .next()
+            .unwrap();
+
+        let goal = map.all_buildings()[0].id;
+        let mut spawn_car = |lane: LaneID| {
+            sim.schedule_trip(
+                Duration::ZERO,
+                TripSpec::CarAppearing {
+                    start_pos: Position::new(lane, Distance::meters(20.0)),
+                    goal: DrivingGoal::ParkNear(goal),
+                    vehicle_spec: Scenario::rand_car(&mut rng),
+                    ped_speed: Scenario::rand_ped_speed(&mut rng),
+                },
+                &map,
+            )
+            .1
+            .unwrap()
+        };
+        let car = spawn_car(driving);
+        let next_car = spawn_car(next_driving);
+        let ped = sim
+            .schedule_trip(
+                Duration::ZERO,
+                TripSpec::JustWalking {
+                    start: SidewalkSpot::suddenly_appear(sidewalk, Distance::meters(20.0), &map),
+                    goal: SidewalkSpot::building(goal, &map),
+                    ped_speed: Scenario::rand_ped_speed(&mut rng),
+                },
+                &map,
+            )
+            .0
+            .unwrap();
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+        h.setup_done(&sim);
+        sim.step(&map, Duration::seconds(1.0));
+
+        let on_road = sim.agents_on_road(road, &map);
+        assert!(on_road.contains(&AgentID::Car(car)));
+        assert!(on_road.contains(&AgentID::Pedestrian(ped)));
+        assert!(!on_road.contains(&AgentID::Car(next_car)));
+        assert!(sim
+            .agents_on_road(next_road, &map)
+            .contains(&AgentID::Car(next_car)));
+    });
+    t.run_slow("constant_demand", |h| {
+        let (map, mut sim, _) =
+            SimFlags::for_test("constant_demand").load(None, &mut Timer::throwaway());
+        sim.set_demand(Some(DemandModel::Constant(10)));
+        h.setup_done(&sim);
+        // Stop right before the sixth minute's trips are spawned
+        sim.timed_step(
+            &map,
+            Duration::minutes(5) - Duration::seconds(1.0),
+            &mut Timer::throwaway(),
+        );
+
+        let trips = sim.get_finished_trips();
+        let num_trips = trips.finished_trips.len() + trips.unfinished_trips;
+        // A few trips might not find a path and never get created.
+        assert!(
+            num_trips >= 40 && num_trips <= 50,
+            "{} trips spawned over 5 minutes",
+            num_trips
+        );
+    });
+    t.run_fast("demand_keeps_sim_running", |_| {
+        let map = build_map(
+            "demand_keeps_sim_running",
+            raw_map(
+                vec![road(1, "residential", vec![pt(0.0, 0.0), pt(0.002, 0.0)])],
+                Vec::new(),
+            ),
+            pt(-0.001, -0.001),
+            pt(0.003, 0.001),
+        );
+        let mut sim = Sim::new(&map, "demand_keeps_sim_running".to_string(), None);
+        assert!(sim.is_done());
+        sim.set_demand(Some(DemandModel::Constant(10)));
+        assert!(!sim.is_done());
+        sim.set_demand(None);
+        assert!(sim.is_done());
+    });
+    t.run_fast("peak_hours_demand", |_| {
+        let model = DemandModel::PeakHours {
+            off_peak: 2,
+            peak: 20,
+        };
+        assert_eq!(model.trips_per_minute(Duration::minutes(8 * 60)), 20);
+        assert_eq!(model.trips_per_minute(Duration::minutes(17 * 60)), 20);
+        assert_eq!(model.trips_per_minute(Duration::minutes(12 * 60 + 30)), 2);
+        assert!(model.trips_per_minute(Duration::minutes(10 * 60)) > 2);
+        assert!(model.trips_per_minute(Duration::minutes(10 * 60)) < 20);
+        // Night stays at the floor, instead of cycling back up to another peak.
+        for hour in vec![0, 1, 2, 3, 22, 23, 24 + 2] {
+            assert_eq!(
+                model.trips_per_minute(Duration::minutes(hour * 60)),
+                2,
+                "at hour {}",
+                hour
+            );
+        }
+        // The next morning looks like the first.
+        assert_eq!(model.trips_per_minute(Duration::minutes((24 + 8) * 60)), 20);
+    });
+    t.run_slow("hov_lane_needs_carpool", |_| {
+        let mut map = Map::new("../data/maps/montlake.bin", &mut Timer::throwaway()).unwrap();
+        // Find a turn into the only driving lane on its side of the road, so turning that lane
+        // into an HOV lane leaves single-occupant cars no way to reach it.
+        let (start, hov_lane) = map
+            .all_turns()
+            .values()
+            .map(|t| (t.id.src, t.id.dst))
+            .find(|(src, dst)| {
+                let r = map.get_parent(*dst);
+                let side = if r.is_forwards(*dst) {
+                    &r.children_forwards
+                } else {
+                    &r.children_backwards
+                };
+                map.get_l(*src).is_driving()
+                    && map.get_l(*dst).is_driving()
+                    && map.get_l(*src).parent != r.id
+                    && side
+                        .iter()
+                        .filter(|(_, lt)| *lt == LaneType::Driving || *lt == LaneType::Bus)
+                        .count()
+                        == 1
+            })
+            .unwrap();
+        let mut edits = map.get_edits().clone();
+        edits.edits_name = "hov_lane_needs_carpool".to_string();
+        edits.lane_overrides.insert(hov_lane, LaneType::HOV);
+        map.apply_edits(edits, &mut Timer::throwaway());
+
+        let mut rng = SimFlags::for_test("hov_lane_needs_carpool").make_rng();
+        let solo = Scenario::rand_car(&mut rng);
+        let carpool = VehicleSpec {
+            occupancy: 2,
+            ..Scenario::rand_car(&mut rng)
+        };
+        let req = |vehicle: &VehicleSpec| PathRequest {
+            start: Position::new(start, Distance::ZERO),
+            end: Position::new(hov_lane, map.get_l(hov_lane).length() / 2.0),
+            can_use_bike_lanes: false,
+            can_use_bus_lanes: false,
+            can_use_hov_lanes: vehicle.can_use_hov_lanes(),
+            can_use_midblock_crossings: false,
+            avoid_lanes: BTreeSet::new(),
+        };
+        assert!(map.pathfind(req(&solo)).is_none());
+        assert!(map.pathfind(req(&carpool)).is_some());
+    });
+    t.run_slow("travel_stats_count_path", |h| {
+        let (map, mut sim, mut rng) =
+            SimFlags::for_test("travel_stats_count_path").load(None, &mut Timer::throwaway());
+        let start = SidewalkSpot::building(BuildingID(319), &map);
+        let goal = map
+            .all_buildings()
+            .iter()
+            .map(|b| SidewalkSpot::building(b.id, &map))
+            .find(|spot| spot.sidewalk_pos.lane() != start.sidewalk_pos.lane())
+            .unwrap();
+        // The same request the spawner makes
+        let path = map
+            .pathfind(PathRequest {
+                start: start.sidewalk_pos,
+                end: goal.sidewalk_pos,
+                can_use_bike_lanes: false,
+                can_use_bus_lanes: false,
+                can_use_hov_lanes: false,
+                can_use_midblock_crossings: false,
+                avoid_lanes: BTreeSet::new(),
+            })
+            .unwrap();
+        let mut expected: BTreeMap<LaneID, usize> = BTreeMap::new();
+        for step in path.get_steps() {
+            if let Traversable::Lane(l) = step.as_traversable() {
+                *expected.entry(l).or_insert(0) += 1;
+            }
+        }
+
+        sim.schedule_trip(
+            Duration::ZERO,
+            TripSpec::JustWalking {
+                start,
+                goal,
+                ped_speed: Scenario::rand_ped_speed(&mut rng),
+            },
+            &map,
+        );
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+        h.setup_done(&sim);
+        sim.just_run_until_done(&map, Some(Duration::minutes(60)));
+
+        let actual: BTreeMap<LaneID, usize> = sim
+            .get_travel_stats()
+            .all_lane_counts()
+            .into_iter()
+            .collect();
+        assert_eq!(actual, expected);
+    });
+    t.run_fast("lane_travel_times", |_| {
+        // Two identical one-way streets, each with a signal in the middle. On the north street,
+        // the signal is red for two minutes before letting anybody through.
+        let mut roads = Vec::new();
+        let mut signals = Vec::new();
+        for (idx, lat) in vec![0.001, 0.003].into_iter().enumerate() {
+            let id = 10 * (idx as i64);
+            let (west, mid1, mid2, east) =
+                (pt(0.0, lat), pt(0.002, lat), pt(0.004, lat), pt(0.006, lat));
+            roads.push(oneway(id + 1, vec![west, mid1]));
+            roads.push(oneway(id + 2, vec![mid1, mid2]));
+            roads.push(oneway(id + 3, vec![mid2, east]));
+            signals.push(mid1);
+            signals.push(mid2);
+        }
+        let mut raw = raw_map(roads, Vec::new());
+        for i in raw.intersections.values_mut() {
+            i.intersection_type = if signals.contains(&i.point) {
+                IntersectionType::TrafficSignal
+            } else {
+                IntersectionType::Border
+            };
+        }
+        let mut map = build_map("lane_travel_times", raw, pt(-0.001, 0.0), pt(0.007, 0.004));
+
+        let (free_start, free_middle, free_end) = (
+            driving_lane(&map, 1),
+            driving_lane(&map, 2),
+            driving_lane(&map, 3),
+        );
+        let (slow_start, slow_middle, slow_end) = (
+            driving_lane(&map, 11),
+            driving_lane(&map, 12),
+            driving_lane(&map, 13),
+        );
+
+        let i = map.get_l(slow_middle).dst_i;
+        let mut red = Cycle::new(i, 0);
+        let mut green = Cycle::new(i, 1);
+        for t in map.get_turns_in_intersection(i) {
+            if t.between_sidewalks() {
+                red.priority_turns.insert(t.id);
+                green.priority_turns.insert(t.id);
+            } else {
+                green.yield_turns.insert(t.id);
+            }
+        }
+        red.min_green = Duration::minutes(2);
+        red.max_green = Duration::minutes(2);
+        let mut edits = map.get_edits().clone();
+        edits.traffic_signal_overrides.insert(
+            i,
+            ControlTrafficSignal {
+                id: i,
+                cycles: vec![red, green],
+                control: SignalControl::default_actuated(),
+                all_red: Duration::ZERO,
+            },
+        );
+        map.apply_edits(edits, &mut Timer::throwaway());
+
+        let mut sim = Sim::new(&map, "lane_travel_times".to_string(), None);
+        for (start, end) in vec![(free_start, free_end), (slow_start, slow_end)] {
+            for idx in 0..3 {
+                sim.schedule_trip(
+                    Duration::ZERO,
+                    TripSpec::CarAppearing {
+                        start_pos: Position::new(
+                            start,
+                            Distance::meters(10.0 + 20.0 * (idx as f64)),
+                        ),
+                        goal: DrivingGoal::end_at_border(
+                            map.get_l(end).dst_i,
+                            vec![LaneType::Driving],
+                            &map,
+                        )
+                        .unwrap(),
+                        vehicle_spec: car(),
+                        ped_speed: Speed::miles_per_hour(3.0),
+                    },
+                    &map,
+                );
+            }
+        }
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+        sim.just_run_until_done(&map, Some(Duration::minutes(10)));
+
+        let times = sim.lane_travel_times();
+        // Nobody crossed the first or last lanes entirely
+        assert!(!times.contains_key(&free_start) && !times.contains_key(&free_end));
+        let (free_count, free_avg) = times[&free_middle];
+        let (slow_count, slow_avg) = times[&slow_middle];
+        assert_eq!((free_count, slow_count), (3, 3));
+        assert!(
+            slow_avg > free_avg + Duration::minutes(1),
+            "{} on the red street, {} on the free one",
+            slow_avg,
+            free_avg
+        );
+    });
+    t.run_fast("spawn_errors", |_| {
+        let roads = vec![
+            // A motorway with no sidewalks, ending at a border
+            road(1, "motorway", vec![pt(0.0, 0.0), pt(0.001, 0.0)]),
+            // Steps in between two residential roads. The building is too far from the residential
+            // sidewalks to connect to them.
+            road(2, "residential", vec![pt(0.0, 0.002), pt(0.0, 0.003)]),
+            road(3, "steps", vec![pt(0.0, 0.003), pt(0.0, 0.005)]),
+            road(4, "residential", vec![pt(0.0, 0.005), pt(0.0, 0.006)]),
+        ];
+        let bldg = raw_data::Building {
+            points: rectangle(pt(0.0002, 0.0039), pt(0.0004, 0.0041)),
+            osm_tags: BTreeMap::new(),
+            osm_way_id: 5,
+            num_residential_units: None,
+        };
+
+        let mut raw = raw_map(roads, vec![bldg]);
+        let border = raw.roads.values().find(|r| r.osm_way_id == 1).unwrap().i1;
+        raw.intersections
+            .get_mut(&border)
+            .unwrap()
+            .intersection_type = IntersectionType::Border;
+        let map = build_map("spawn_errors", raw, pt(-0.001, -0.001), pt(0.002, 0.007));
+
+        let border = map
+            .all_intersections()
+            .iter()
+            .find(|i| i.intersection_type == IntersectionType::Border)
+            .unwrap()
+            .id;
+        assert_eq!(
+            SidewalkSpot::start_at_border(border, &map),
+            Err(SpawnError::NoSidewalkAtBorder(border))
+        );
+        assert_eq!(
+            SidewalkSpot::end_at_border(border, &map),
+            Err(SpawnError::NoSidewalkAtBorder(border))
+        );
+        assert_eq!(
+            DrivingGoal::end_at_border(border, vec![LaneType::Biking], &map),
+            Err(SpawnError::NoLanesAtBorder(border, vec![LaneType::Biking]))
+        );
+        assert!(DrivingGoal::end_at_border(border, vec![LaneType::Driving], &map).is_ok());
+
+        let b = map.all_buildings()[0].id;
+        assert_eq!(map.building_to_road(b).osm_way_id, 3);
+        assert_eq!(
+            Position::bldg_via_driving(b, &map),
+            Err(SpawnError::NoDrivingAccess(b))
+        );
+    });
+    t.run_fast("midblock_crossing", |_| {
+        // A block about 300m long, with cross streets at both ends, and a building directly across
+        // the road from another in the middle.
+        let roads = vec![
+            road(1, "residential", vec![pt(0.0, 0.0), pt(0.004, 0.0)]),
+            road(
+                2,
+                "residential",
+                vec![pt(0.0, -0.0005), pt(0.0, 0.0), pt(0.0, 0.0005)],
+            ),
+            road(
+                3,
+                "residential",
+                vec![pt(0.004, -0.0005), pt(0.004, 0.0), pt(0.004, 0.0005)],
+            ),
+        ];
+        let bldgs = vec![building(4, 0.002, 0.0003), building(5, 0.002, -0.0003)];
+
+        let map = build_map(
+            "midblock_crossing",
+            raw_map(roads, bldgs),
+            pt(-0.001, -0.001),
+            pt(0.005, 0.001),
+        );
+        let (b1, b2) = (map.all_buildings()[0].id, map.all_buildings()[1].id);
+        assert_ne!(
+            map.get_b(b1).front_path.sidewalk.lane(),
+            map.get_b(b2).front_path.sidewalk.lane()
+        );
+
+        let req = |can_use_midblock_crossings: bool| PathRequest {
+            start: map.get_b(b1).front_path.sidewalk,
+            end: map.get_b(b2).front_path.sidewalk,
+            can_use_bike_lanes: false,
+            can_use_bus_lanes: false,
+            can_use_hov_lanes: false,
+            can_use_midblock_crossings,
+            avoid_lanes: BTreeSet::new(),
+        };
+        let crosses_midblock = |path: &Path| {
+            path.get_steps().iter().any(|step| match step {
+                PathStep::MidblockCrossing(_) => true,
+                _ => false,
+            })
+        };
+        let walking_dist = |path: &Path| {
+            path.trace(&map, map.get_b(b1).front_path.sidewalk.dist_along(), None)
+                .unwrap()
+                .length()
+        };
+        let direct = map.pathfind(req(true)).unwrap();
+        let detour = map.pathfind(req(false)).unwrap();
+        assert!(crosses_midblock(&direct));
+        assert!(!crosses_midblock(&detour));
+        assert!(walking_dist(&direct) * 4.0 < walking_dist(&detour));
+
+        // Walking around the block takes minutes; with no traffic, cutting across doesn't.
+        let mut sim = Sim::new(&map, "midblock_crossing".to_string(), None);
+        sim.use_midblock_crossings(true);
+        let (ped, _) = sim.schedule_trip(
+            Duration::ZERO,
+            TripSpec::JustWalking {
+                start: SidewalkSpot::building(b1, &map),
+                goal: SidewalkSpot::building(b2, &map),
+                ped_speed: Speed::miles_per_hour(3.0),
+            },
+            &map,
+        );
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+        sim.run_until_expectations_met(
+            &map,
+            vec![Event::PedReachedBuilding(ped.unwrap(), b2)],
+            Duration::minutes(2),
+        );
+    });
+    t.run_fast("bike_parks_at_rack", |_| {
+        let (map, west, east) = one_rack_map("bike_parks_at_rack");
+        let rack = BikeRackID(0);
+        let mut sim = Sim::new(&map, "bike_parks_at_rack".to_string(), None);
+        let (ped, bike) = sim.schedule_trip(
+            Duration::ZERO,
+            TripSpec::UsingBike {
+                start: SidewalkSpot::building(west, &map),
+                goal: DrivingGoal::ParkNear(east),
+                vehicle: bike_spec(),
+                ped_speed: Speed::miles_per_hour(3.0),
+            },
+            &map,
+        );
+        // Going back home later should take the bike from the rack.
+        sim.schedule_trip(
+            Duration::minutes(10),
+            TripSpec::UsingBike {
+                start: SidewalkSpot::building(east, &map),
+                goal: DrivingGoal::ParkNear(west),
+                vehicle: bike_spec(),
+                ped_speed: Speed::miles_per_hour(3.0),
+            },
+            &map,
+        );
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+
+        sim.run_until_expectations_met(
+            &map,
+            vec![
+                Event::BikeStoppedAtSidewalk(
+                    bike.unwrap(),
+                    map.get_bike_rack(rack).sidewalk_pos.lane(),
+                ),
+                Event::PedReachedBuilding(ped.unwrap(), east),
+            ],
+            Duration::minutes(5),
+        );
+        assert_eq!(sim.get_bike_rack_occupancy(rack), 1);
+
+        // There's no rack near the west building, so that bike just stops wherever.
+        sim.just_run_until_done(&map, Some(Duration::minutes(15)));
+        assert_eq!(sim.get_bike_rack_occupancy(rack), 0);
+    });
+
+    t.run_fast("bike_stops_nearby_when_racks_are_full", |_| {
+        let (map, west, east) = one_rack_map("bike_stops_nearby_when_racks_are_full");
+        let rack = BikeRackID(0);
+        let mut sim = Sim::new(
+            &map,
+            "bike_stops_nearby_when_racks_are_full".to_string(),
+            None,
+        );
+        // The second cyclist finds the only rack taken, so they stop near the building instead.
+        for depart in vec![Duration::ZERO, Duration::minutes(1)] {
+            sim.schedule_trip(
+                depart,
+                TripSpec::UsingBike {
+                    start: SidewalkSpot::building(west, &map),
+                    goal: DrivingGoal::ParkNear(east),
+                    vehicle: bike_spec(),
+                    ped_speed: Speed::miles_per_hour(3.0),
+                },
+                &map,
+            );
+        }
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+
+        sim.just_run_until_done(&map, Some(Duration::minutes(15)));
+        assert_eq!(sim.get_finished_trips().finished_trips.len(), 2);
+        assert_eq!(sim.get_bike_rack_occupancy(rack), 1);
+    });
+    t.run_fast("pinned_vehicle_spec", |_| {
+        // A single one-way street between two borders
+        let mut raw = raw_map(
+            vec![oneway(1, vec![pt(0.0, 0.0), pt(0.002, 0.0)])],
+            Vec::new(),
+        );
+        for i in raw.intersections.values_mut() {
+            i.intersection_type = IntersectionType::Border;
+        }
+        let map = build_map(
+            "pinned_vehicle_spec",
+            raw,
+            pt(-0.001, -0.001),
+            pt(0.003, 0.001),
+        );
+        let lane = map
+            .all_lanes()
+            .iter()
+            .find(|l| l.lane_type == LaneType::Driving)
+            .unwrap();
+
+        // Longer and slower than anything rand_car would pick
+        let truck = VehicleSpec {
+            vehicle_type: VehicleType::Car,
+            class: VehicleClass::Truck,
+            length: MAX_TRUCK_LENGTH,
+            max_speed: Some(Speed::miles_per_hour(20.0)),
+            occupancy: 1,
+        };
+        // Too long to be a car, so this trip gets skipped
+        let limo = VehicleSpec {
+            vehicle_type: VehicleType::Car,
+            class: VehicleClass::Car,
+            length: Distance::meters(25.0),
+            max_speed: None,
+            occupancy: 1,
+        };
+        let goal = DrivingGoal::end_at_border(lane.dst_i, vec![LaneType::Driving], &map).unwrap();
+        let scenario = Scenario {
+            scenario_name: "pinned_vehicle_spec".to_string(),
+            map_name: map.get_name().to_string(),
+            seed_parked_cars: Vec::new(),
+            spawn_over_time: Vec::new(),
+            border_spawn_over_time: Vec::new(),
+            individ_trips: vec![
+                SpawnTrip::CarAppearing {
+                    depart: Duration::ZERO,
+                    start: Position::new(lane.id, Distance::meters(30.0)),
+                    goal: goal.clone(),
+                    is_bike: false,
+                    occupancy: 1,
+                    vehicle_spec: Some(truck.clone()),
+                },
+                SpawnTrip::CarAppearing {
+                    depart: Duration::ZERO,
+                    start: Position::new(lane.id, Distance::meters(60.0)),
+                    goal,
+                    is_bike: false,
+                    occupancy: 1,
+                    vehicle_spec: Some(limo),
+                },
+            ],
+            trip_metadata: BTreeMap::new(),
+            lane_closures: Vec::new(),
+        };
+        let mut sim = Sim::new(&map, "pinned_vehicle_spec".to_string(), None);
+        let mut rng = SimFlags::for_test("pinned_vehicle_spec").make_rng();
+        let mut timer = Timer::new("instantiate pinned_vehicle_spec");
+        timer.collect_warnings();
+        scenario.instantiate(&mut sim, &map, &mut rng, &mut timer);
+        let warnings = timer.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("Skipping trip 1"));
+        sim.step(&map, Duration::seconds(1.0));
+
+        let cars: Vec<_> = sim
+            .active_agents()
+            .into_iter()
+            .filter_map(|a| match a {
+                AgentID::Car(c) => Some(c),
+                AgentID::Pedestrian(_) => None,
+            })
+            .collect();
+        assert_eq!(cars.len(), 1);
+        let vehicle = sim.get_vehicle(cars[0]).unwrap();
+        assert_eq!(vehicle.length, truck.length);
+        assert_eq!(vehicle.class, truck.class);
+        assert_eq!(vehicle.max_speed, truck.max_speed);
+    });
+
+    t.run_fast("closing_lane_reroutes", |_| {
+        let (map, start, closed, end) = detour_map("closing_lane_reroutes");
+
+        let mut sim = Sim::new(&map, "closing_lane_reroutes".to_string(), None);
+        sim.schedule_trip(
+            Duration::ZERO,
+            TripSpec::CarAppearing {
+                start_pos: Position::new(start, Distance::meters(10.0)),
+                goal: DrivingGoal::end_at_border(
+                    map.get_l(end).dst_i,
+                    vec![LaneType::Driving],
+                    &map,
+                )
+                .unwrap(),
+                vehicle_spec: car(),
+                ped_speed: Speed::miles_per_hour(3.0),
+            },
+            &map,
+        );
+        sim.spawn_all_trips(&map, &mut Timer::throwaway(), false);
+        sim.step(&map, Duration::seconds(1.0));
+
+        let car = match sim.active_agents()[0] {
+            AgentID::Car(c) => c,
+            AgentID::Pedestrian(_) => unreachable!(),
+        };
+        let roads_on_path = |sim: &Sim| -> Vec<i64> {
+            sim.get_path(AgentID::Car(car))
+                .unwrap()
+                .get_steps()
+                .iter()
+                .filter_map(|step| match step {
+                    PathStep::Lane(l) => Some(map.get_parent(*l).osm_way_id),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(roads_on_path(&sim), vec![1, 2, 3, 4]);
+
+        assert_eq!(sim.close_lanes(vec![closed], &map), vec![car]);
+        assert_eq!(roads_on_path(&sim), vec![1, 2, 5, 6, 7, 4]);
+        // Nothing changes if the lane is closed again.
+        assert!(sim.close_lanes(vec![closed], &map).is_empty());
+
+        sim.just_run_until_done(&map, Some(Duration::minutes(10)));
+        assert_eq!(sim.get_finished_trips().finished_trips.len(), 1);
+        let times = sim.lane_travel_times();
+        assert!(times.contains_key(&driving_lane(&map, 6)));
+        assert!(!times.contains_key(&closed));
+    });
+
+    t.run_fast("scheduled_lane_closure", |_| {
+        // C -> D closes a minute in, so a car leaving later has to take the detour.
+        let (map, start, closed, end) = detour_map("scheduled_lane_closure");
+
+        let trip = |depart: Duration| SpawnTrip::CarAppearing {
+            depart,
+            start: Position::new(start, Distance::meters(10.0)),
+            goal: DrivingGoal::end_at_border(map.get_l(end).dst_i, vec![LaneType::Driving], &map)
+                .unwrap(),
+            is_bike: false,
+            occupancy: 1,
+            vehicle_spec: None,
+        };
+        let scenario = Scenario {
+            scenario_name: "scheduled_lane_closure".to_string(),
+            map_name: map.get_name().to_string(),
+            seed_parked_cars: Vec::new(),
+            spawn_over_time: Vec::new(),
+            border_spawn_over_time: Vec::new(),
+            individ_trips: vec![trip(Duration::ZERO), trip(Duration::minutes(2))],
+            trip_metadata: BTreeMap::new(),
+            lane_closures: vec![
+                LaneClosure {
+                    lane: closed,
+                    start_time: Duration::minutes(1),
+                    end_time: None,
+                },
+                // Reopens before it closes, so it's skipped
+                LaneClosure {
+                    lane: driving_lane(&map, 6),
+                    start_time: Duration::minutes(5),
+                    end_time: Some(Duration::minutes(3)),
+                },
+            ],
+        };
+        let mut sim = Sim::new(&map, "scheduled_lane_closure".to_string(), None);
+        let mut rng = SimFlags::for_test("scheduled_lane_closure").make_rng();
+        let mut timer = Timer::new("instantiate scheduled_lane_closure");
+        timer.collect_warnings();
+        scenario.instantiate(&mut sim, &map, &mut rng, &mut timer);
+        let warnings = timer.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("Skipping a lane closure"));
+
+        // Remember the route each car planned when it started.
+        let mut routes: BTreeMap<CarID, Vec<i64>> = BTreeMap::new();
+        while sim.time() < Duration::minutes(10) {
+            sim.step(&map, Duration::seconds(1.0));
+            assert_eq!(
+                sim.get_closed_lanes().contains(&closed),
+                sim.time() >= Duration::minutes(1)
+            );
+            for agent in sim.active_agents() {
+                if let AgentID::Car(car) = agent {
+                    routes.entry(car).or_insert_with(|| {
+                        sim.get_path(agent)
+                            .unwrap()
+                            .get_steps()
+                            .iter()
+                            .filter_map(|step| match step {
+                                PathStep::Lane(l) => Some(map.get_parent(*l).osm_way_id),
+                                _ => None,
+                            })
+                            .collect()
+                    });
+                }
+            }
+        }
+        assert_eq!(sim.get_finished_trips().finished_trips.len(), 2);
+        assert_eq!(
+            routes.into_iter().map(|(_, r)| r).collect::<Vec<_>>(),
+            vec![vec![1, 2, 3, 4], vec![1, 2, 5, 6, 7, 4]]
+        );
+    });
+}
+
+// One-way streets from A to G, with a detour around C-D through E and F. Both A and G are borders.
+//
+//  A -> B -> C -> D -> G
+//            |    ^
+//            v    |
+//            E -> F
+//
+// Returns the map and the driving lanes on A -> B, C -> D, and D -> G.
+fn detour_map(name: &str) -> (Map, LaneID, LaneID, LaneID) {
+    let (a, b, c, d, g, e, f) = (
+        pt(0.0, 0.0),
+        pt(0.002, 0.0),
+        pt(0.004, 0.0),
+        pt(0.006, 0.0),
+        pt(0.008, 0.0),
+        pt(0.004, -0.002),
+        pt(0.006, -0.002),
+    );
+    let roads = vec![
+        oneway(1, vec![a, b]),
+        oneway(2, vec![b, c]),
+        oneway(3, vec![c, d]),
+        oneway(4, vec![d, g]),
+        oneway(5, vec![c, e]),
+        oneway(6, vec![e, f]),
+        oneway(7, vec![f, d]),
+    ];
+    let mut raw = raw_map(roads, Vec::new());
+    for i in raw.intersections.values_mut() {
+        i.intersection_type = if i.point == a || i.point == g {
+            IntersectionType::Border
+        } else {
+            IntersectionType::StopSign
+        };
+    }
+    let map = build_map(name, raw, pt(-0.001, -0.003), pt(0.009, 0.001));
+    let (start, closed, end) = (
+        driving_lane(&map, 1),
+        driving_lane(&map, 3),
+        driving_lane(&map, 4),
+    );
+    (map, start, closed, end)
+}
+
+// A block about 650m long with a building at each end. The one rack, with room for one bike, is
+// near the east building, too far from the west one to be used there. Returns the map and the
+// west and east buildings.
+fn one_rack_map(name: &str) -> (Map, BuildingID, BuildingID) {
+    let roads = vec![
+        road(1, "residential", vec![pt(0.0, 0.0), pt(0.009, 0.0)]),
+        road(
+            2,
+            "residential",
+            vec![pt(0.0, -0.0005), pt(0.0, 0.0), pt(0.0, 0.0005)],
+        ),
+        road(
+            3,
+            "residential",
+            vec![pt(0.009, -0.0005), pt(0.009, 0.0), pt(0.009, 0.0005)],
+        ),
+    ];
+    let bldgs = vec![building(4, 0.0015, 0.0003), building(5, 0.0075, 0.0003)];
+
+    let mut raw = raw_map(roads, bldgs);
+    raw.bike_racks = vec![raw_data::BikeRack {
+        point: pt(0.0073, 0.00006),
+        capacity: 1,
+        osm_node_id: 6,
+    }];
+    let map = build_map(name, raw, pt(-0.001, -0.001), pt(0.01, 0.001));
+    assert_eq!(map.all_bike_racks().len(), 1);
+    let (west, east) = (map.all_buildings()[0].id, map.all_buildings()[1].id);
+    (map, west, east)
+}
+
+fn bike_spec() -> VehicleSpec {
+    VehicleSpec {
+        vehicle_type: VehicleType::Bike,
+        class: VehicleClass::Bike,
+        length: BIKE_LENGTH,
+        max_speed: Some(Speed::miles_per_hour(9.0)),
+        occupancy: 1,
+    }
 }
@@ -0,0 +1,899 @@
+use crate::fixtures::TmpFile;
+use crate::runner::TestRunner;
+use abstutil::Timer;
+use editor::{
+    editable_lane_types, next_type, select_within, Annotation, Annotations, ColorScheme, Flags,
+    GameState, LaneMarkingsCache, ObjectQuadtree, Palette, Session, SessionMode, Throughput, Trip,
+    TripEndpt, ID,
+};
+use ezgui::{
+    hotkey, Color, Event, EventCtx, EventLoopMode, GeomBatch, GfxCtx, Headless, Key, Minimap,
+    ModalMenu, MultiKey, ScreenPt, Slider, Wizard, WrappedWizard, GUI,
+};
+use geom::{Bounds, Distance, Duration, LonLat, PolyLine, Pt2D};
+use map_model::{IntersectionID, Lane, LaneID, LaneType, Map, RoadID, LANE_THICKNESS};
+use popdat::psrc::{Mode, Purpose};
+use sim::SimFlags;
+use std::collections::BTreeSet;
+
+pub fn run(t: &mut TestRunner) {
+    t.run_slow("toggle_lane_type", |_| {
+        let mut gui = start("toggle_lane_type");
+        gui.press_lctrl(Key::E);
+        assert_eq!(gui.gui().describe_state()[0], "Map Edit Mode");
+
+        let (lane, pt) = {
+            let map = &gui.gui().ui.primary.map;
+            let l = map
+                .all_lanes()
+                .iter()
+                .find(|l| l.lane_type == LaneType::Driving && l.bus_stops.is_empty())
+                .unwrap();
+            (l.id, l.dist_along(l.length() / 2.0).0)
+        };
+        gui.canvas_mut().center_on_map_pt(pt);
+        gui.hover(pt);
+        assert_eq!(gui.gui().ui.primary.current_selection, Some(ID::Lane(lane)));
+        assert!(gui
+            .gui()
+            .describe_state()
+            .contains(&format!("Selected {:?}", ID::Lane(lane))));
+
+        gui.press_key(Key::Space);
+        let map = &gui.gui().ui.primary.map;
+        let new_type = map.get_edits().lane_overrides[&lane];
+        assert_ne!(new_type, LaneType::Driving);
+        assert_eq!(map.get_l(lane).lane_type, new_type);
+        assert!(gui.gui().describe_state().contains(&"1 lanes".to_string()));
+    });
+
+    t.run_slow("compare_edits_side_by_side", |_| {
+        let mut gui = start("compare_edits_side_by_side");
+        gui.press_lctrl(Key::E);
+
+        let pt = {
+            let map = &gui.gui().ui.primary.map;
+            let l = map
+                .all_lanes()
+                .iter()
+                .find(|l| l.lane_type == LaneType::Driving && l.bus_stops.is_empty())
+                .unwrap();
+            l.dist_along(l.length() / 2.0).0
+        };
+        gui.canvas_mut().center_on_map_pt(pt);
+        gui.hover(pt);
+        gui.press_key(Key::Space);
+        assert_eq!(gui.gui().ui.primary.map.get_edits().lane_overrides.len(), 1);
+
+        gui.press_key(Key::V);
+        assert_eq!(gui.gui().describe_state()[0], "Compare Edits");
+
+        let (width, height) = (gui.canvas().window_width, gui.canvas().window_height);
+        gui.event(Event::MouseMovedTo(ScreenPt::new(
+            width / 4.0,
+            height / 2.0,
+        )));
+        assert!(gui
+            .gui()
+            .describe_state()
+            .contains(&"Hovering over the original map".to_string()));
+        assert!(gui
+            .gui()
+            .ui
+            .primary
+            .map
+            .get_edits()
+            .lane_overrides
+            .is_empty());
+
+        gui.event(Event::MouseMovedTo(ScreenPt::new(
+            3.0 * width / 4.0,
+            height / 2.0,
+        )));
+        assert!(gui
+            .gui()
+            .describe_state()
+            .contains(&"Hovering over the edited map".to_string()));
+
+        // Leaving from the original side still ends up editing the edited map
+        gui.event(Event::MouseMovedTo(ScreenPt::new(
+            width / 4.0,
+            height / 2.0,
+        )));
+        gui.press_key(Key::Escape);
+        assert_eq!(gui.gui().describe_state()[0], "Map Edit Mode");
+        assert_eq!(gui.gui().ui.primary.map.get_edits().lane_overrides.len(), 1);
+    });
+
+    t.run_slow("spawn_pedestrian", |_| {
+        let mut gui = start("spawn_pedestrian");
+        assert_eq!(gui.gui().describe_state()[0], "Sandbox Mode");
+
+        let (from, to) = {
+            let map = &gui.gui().ui.primary.map;
+            let mut bldgs = map
+                .all_buildings()
+                .iter()
+                .filter(|b| b.polygon.contains_pt(b.polygon.center()));
+            let from = bldgs.next().unwrap();
+            let to = bldgs
+                .find(|b| b.front_path.sidewalk.lane() != from.front_path.sidewalk.lane())
+                .unwrap();
+            (
+                (from.id, from.polygon.center()),
+                (to.id, to.polygon.center()),
+            )
+        };
+
+        gui.canvas_mut().center_on_map_pt(from.1);
+        gui.hover(from.1);
+        gui.press_key(Key::F3);
+        assert!(gui
+            .gui()
+            .describe_state()
+            .contains(&format!("Spawning a pedestrian from {}", from.0)));
+
+        gui.canvas_mut().center_on_map_pt(to.1);
+        gui.hover(to.1);
+        assert!(gui
+            .gui()
+            .describe_state()
+            .contains(&format!("Ending at {}", to.0)));
+
+        gui.press_key(Key::F3);
+        let state = gui.gui().describe_state();
+        assert_eq!(state[0], "Sandbox Mode");
+        let ui = &gui.gui().ui;
+        assert!(state.contains(&ui.primary.sim.summary()));
+        let (cars, bikes, buses, peds) = ui.primary.sim.get_unzoomed_agents(&ui.primary.map);
+        assert_eq!(
+            (cars.len(), bikes.len(), buses.len(), peds.len()),
+            (0, 0, 0, 1)
+        );
+    });
+
+    t.run_fast("session_round_trip", |_| {
+        let tmp = TmpFile::new("session_round_trip.json");
+        let path = tmp.path();
+        let session = Session {
+            map_name: "montlake".to_string(),
+            edits_name: "no_edits".to_string(),
+            mode: SessionMode::Edit,
+            cam_x: 123.0,
+            cam_y: 456.0,
+            cam_zoom: 2.5,
+        };
+        session.save(path);
+        let loaded = Session::load(path);
+        assert_eq!(loaded, Some(session));
+
+        assert_eq!(Session::load("no_such_session.json"), None);
+    });
+
+    t.run_fast("stale_session", |_| {
+        let tmp = TmpFile::new("stale_session.json");
+        let path = tmp.path();
+        let mut session = Session {
+            map_name: "no_such_map".to_string(),
+            edits_name: "no_edits".to_string(),
+            mode: SessionMode::Sandbox,
+            cam_x: 0.0,
+            cam_y: 0.0,
+            cam_zoom: 1.0,
+        };
+        session.save(path);
+        assert_eq!(Session::load(path), None);
+
+        // The map's fine, but the edits are gone
+        session.map_name = "montlake".to_string();
+        session.edits_name = "no_such_edits".to_string();
+        session.save(path);
+        assert_eq!(Session::load(path), None);
+
+        std::fs::write(path, "{ not json").unwrap();
+        assert_eq!(Session::load(path), None);
+    });
+
+    t.run_fast("lane_type_cycle_covers_editable_types", |_| {
+        let all = vec![
+            LaneType::Driving,
+            LaneType::Parking,
+            LaneType::Sidewalk,
+            LaneType::Biking,
+            LaneType::Bus,
+            LaneType::HOV,
+        ];
+        // Forces this test to be updated when a new LaneType is added.
+        for lt in &all {
+            match lt {
+                LaneType::Driving
+                | LaneType::Parking
+                | LaneType::Sidewalk
+                | LaneType::Biking
+                | LaneType::Bus
+                | LaneType::HOV => {}
+            }
+        }
+
+        assert_eq!(next_type(LaneType::Sidewalk), None);
+        let start = LaneType::Driving;
+        let mut seen = vec![start];
+        let mut lt = next_type(start).unwrap();
+        while lt != start {
+            assert!(!seen.contains(&lt), "{:?} appears twice in the cycle", lt);
+            seen.push(lt);
+            lt = next_type(lt).unwrap();
+        }
+        for lt in all {
+            assert_eq!(seen.contains(&lt), lt != LaneType::Sidewalk, "{:?}", lt);
+        }
+
+        let mut keys: Vec<Key> = editable_lane_types()
+            .into_iter()
+            .map(|(_, _, k)| k)
+            .collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), seen.len());
+    });
+
+    t.run_slow("drag_to_select_lanes", |_| {
+        let mut gui = start("drag_to_select_lanes");
+        gui.press_lctrl(Key::E);
+        // Far enough out to fit a whole lane onscreen
+        gui.canvas_mut().cam_zoom = 2.0;
+
+        let (lane, bounds) = {
+            let map = &gui.gui().ui.primary.map;
+            let l = map
+                .all_lanes()
+                .iter()
+                .find(|l| l.lane_type == LaneType::Driving && l.length().inner_meters() > 20.0)
+                .unwrap();
+            (
+                l.id,
+                l.lane_center_pts.make_polygons(LANE_THICKNESS).get_bounds(),
+            )
+        };
+        let pad = 5.0;
+        let from = Pt2D::new(bounds.min_x - pad, bounds.min_y - pad);
+        let to = Pt2D::new(bounds.max_x + pad, bounds.max_y + pad);
+        gui.canvas_mut()
+            .center_on_map_pt(Pt2D::center(&vec![from, to]));
+
+        // Without control held, dragging would just pan.
+        gui.event(Event::KeyPress(Key::LeftControl));
+        gui.hover(from);
+        gui.event(Event::LeftMouseButtonDown);
+        gui.hover(to);
+        gui.event(Event::LeftMouseButtonUp);
+        gui.event(Event::KeyRelease(Key::LeftControl));
+        let num_selected = gui
+            .gui()
+            .describe_state()
+            .into_iter()
+            .find(|line| line.ends_with("objects selected"))
+            .expect("Nothing selected");
+
+        // The hovered lane's own actions use the same keys, but the selection takes precedence.
+        gui.press_key(Key::H);
+        let edits = gui.gui().ui.primary.map.get_edits();
+        assert_eq!(edits.lane_overrides.get(&lane), Some(&LaneType::HOV));
+        assert!(edits.lane_overrides.values().all(|lt| *lt == LaneType::HOV));
+        assert!(!gui.gui().describe_state().contains(&num_selected));
+    });
+
+    t.run_fast("rectangle_selects_two_parallel_lanes", |_| {
+        let lane = |id: usize, y1: f64, y2: f64| {
+            let pl = PolyLine::new(vec![Pt2D::new(10.0, y1), Pt2D::new(50.0, y2)]);
+            (
+                ID::Lane(LaneID(id)),
+                pl.make_polygons(LANE_THICKNESS).get_bounds(),
+            )
+        };
+        let next_to = 20.0 + LANE_THICKNESS.inner_meters();
+        let candidates = vec![
+            lane(0, 20.0, 20.0),
+            lane(1, next_to, next_to),
+            // Starts inside the rectangle, but doesn't end there
+            lane(2, 25.0, 80.0),
+            // Entirely outside
+            lane(3, 100.0, 100.0),
+        ];
+
+        let mut rect = Bounds::new();
+        rect.update(Pt2D::new(5.0, 10.0));
+        rect.update(Pt2D::new(55.0, 30.0));
+        assert_eq!(
+            select_within(&rect, candidates),
+            vec![ID::Lane(LaneID(0)), ID::Lane(LaneID(1))]
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+        );
+    });
+
+    t.run_slow("trip_route_longer_than_straight_line", |_| {
+        let map = Map::new("../data/maps/montlake.bin", &mut Timer::throwaway()).unwrap();
+        let bldgs = map.all_buildings();
+        let trip = Trip {
+            from: TripEndpt::Building(bldgs[0].id),
+            to: TripEndpt::Building(bldgs[bldgs.len() - 1].id),
+            depart_at: Duration::ZERO,
+            purpose: (Purpose::Home, Purpose::Work),
+            parcels: (1, 2),
+            mode: Mode::Walk,
+            trip_time: Duration::minutes(30),
+            trip_dist: Distance::ZERO,
+            route: None,
+        };
+        let route = trip.calculate_route(&map).unwrap();
+        let straight_line = trip
+            .from
+            .polygon(&map)
+            .center()
+            .dist_to(trip.to.polygon(&map).center());
+        assert!(route.length() > straight_line);
+    });
+
+    t.run_fast("lane_markings_cache_keeps_colors", |_| {
+        for lane_type in vec![LaneType::Sidewalk, LaneType::Parking] {
+            // Curvy, so every marking is rotated differently
+            let lane = Lane {
+                id: LaneID(0),
+                parent: RoadID(0),
+                lane_type,
+                lane_center_pts: PolyLine::new(vec![
+                    Pt2D::new(10.0, 10.0),
+                    Pt2D::new(60.0, 25.0),
+                    Pt2D::new(90.0, 90.0),
+                    Pt2D::new(40.0, 130.0),
+                ]),
+                src_i: IntersectionID(0),
+                dst_i: IntersectionID(1),
+                building_paths: Vec::new(),
+                bus_stops: Vec::new(),
+                midblock_crossings: Vec::new(),
+                parking_hours: None,
+            };
+
+            let mut fresh = GeomBatch::new();
+            LaneMarkingsCache::new().draw(&mut fresh, &lane, Color::WHITE);
+            let fresh = fresh.consume();
+            assert!(fresh.len() > 5);
+            // Every marking lands on the lane.
+            let around_lane = lane.lane_center_pts.make_polygons(LANE_THICKNESS * 2.0);
+            for (_, p) in &fresh {
+                assert!(around_lane.contains_pt(p.center()));
+            }
+
+            // A cache warmed up in another color still uses the new one.
+            let mut cache = LaneMarkingsCache::new();
+            cache.draw(&mut GeomBatch::new(), &lane, Color::RED);
+            let mut reused = GeomBatch::new();
+            cache.draw(&mut reused, &lane, Color::WHITE);
+            let reused = reused.consume();
+            assert_eq!(reused.len(), fresh.len());
+            for ((color1, p1), (color2, p2)) in fresh.iter().zip(reused.iter()) {
+                assert_eq!(*color1, Color::WHITE);
+                assert_eq!(*color2, Color::WHITE);
+                assert_eq!(p1.raw_for_rendering(), p2.raw_for_rendering());
+            }
+        }
+    });
+
+    t.run_fast("all_menu_choices_reachable_on_short_canvas", |_| {
+        // Too short for all of the choices
+        let mut gui = Headless::new(800.0, 400.0, |_| Chooser::new());
+        let pages = gui
+            .gui()
+            .wizard
+            .describe_state()
+            .into_iter()
+            .find(|line| line.starts_with("Page 1/"))
+            .expect("Menu isn't paginated");
+        assert_ne!(pages, "Page 1/1 (Page Up/Page Down for more)");
+
+        // The keyboard can reach every choice, flipping pages along the way.
+        for _ in 0..99 {
+            gui.press_key(Key::DownArrow);
+        }
+        let state = gui.gui().wizard.describe_state();
+        assert!(state.contains(&"> choice 99".to_string()));
+        let last_page = state.last().unwrap().clone();
+        assert!(last_page.starts_with("Page "));
+        assert_ne!(last_page, pages);
+        gui.press_key(Key::PageUp);
+        assert!(!gui.gui().wizard.describe_state().contains(&last_page));
+        gui.press_key(Key::PageDown);
+        assert!(gui.gui().wizard.describe_state().contains(&last_page));
+        gui.press_key(Key::Enter);
+        assert_eq!(gui.gui().chosen, Some("choice 99".to_string()));
+
+        // Hotkeys work no matter what page is showing.
+        let mut gui = Headless::new(800.0, 400.0, |_| Chooser::new());
+        gui.press_key(Key::F);
+        assert_eq!(gui.gui().chosen, Some("choice 95".to_string()));
+    });
+
+    t.run_fast("modal_menu_tooltip_follows_mouse", |_| {
+        let mut gui = Headless::new(800.0, 400.0, |ctx| Helped::new(ctx));
+        // Sweep down the right edge of the screen, where the menu lives.
+        let mut seen: Vec<Option<String>> = Vec::new();
+        for y in 0..200 {
+            gui.event(Event::MouseMovedTo(ScreenPt::new(795.0, f64::from(y))));
+            let tooltip = gui.gui().menu.current_tooltip().map(|s| s.to_string());
+            if seen.last() != Some(&tooltip) {
+                seen.push(tooltip);
+            }
+        }
+        // The prompt, then each entry, then past the menu
+        assert_eq!(
+            seen,
+            vec![
+                None,
+                Some("Leave this mode".to_string()),
+                None,
+                Some("Change every lane of this street".to_string()),
+                None,
+            ]
+        );
+
+        // Away from the menu entirely
+        gui.event(Event::MouseMovedTo(ScreenPt::new(10.0, 50.0)));
+        assert_eq!(gui.gui().menu.current_tooltip(), None);
+    });
+
+    t.run_fast("minimap_click_and_drag_moves_camera", |_| {
+        let mut bounds = Bounds::new();
+        bounds.update(Pt2D::new(0.0, 0.0));
+        bounds.update(Pt2D::new(5000.0, 2500.0));
+        let mut gui = Headless::new(1000.0, 800.0, |_| Overview {
+            minimap: Minimap::new(),
+            bounds,
+        });
+        let camera = |gui: &Headless<Overview>| {
+            let pt = gui.canvas().center_to_map_pt();
+            (pt.x(), pt.y())
+        };
+        let close = |(x1, y1): (f64, f64), (x2, y2): (f64, f64)| {
+            (x1 - x2).abs() < 1e-6 && (y1 - y2).abs() < 1e-6
+        };
+        let start = camera(&gui);
+
+        // The panel is 250 pixels in the bottom-right corner, above the OSD. The wider dimension
+        // fills it, so 1 pixel is 20 meters.
+        let (x1, y1, x2) = (1000.0 - 10.0 - 250.0, 800.0 - 60.0 - 250.0, 1000.0 - 10.0);
+        gui.event(Event::MouseMovedTo(ScreenPt::new(
+            x1 + 1234.0 / 20.0,
+            y1 + 567.0 / 20.0,
+        )));
+        assert!(close(camera(&gui), start));
+        gui.event(Event::LeftMouseButtonDown);
+        assert!(close(camera(&gui), (1234.0, 567.0)));
+
+        // Dragging off the panel pins to the edge of the map
+        gui.event(Event::MouseMovedTo(ScreenPt::new(x2 + 50.0, y1 - 50.0)));
+        assert!(close(camera(&gui), (5000.0, 0.0)));
+
+        // After letting go, moving the mouse doesn't touch the camera
+        gui.event(Event::LeftMouseButtonUp);
+        gui.event(Event::MouseMovedTo(ScreenPt::new(x1 + 10.0, y1 + 10.0)));
+        assert!(close(camera(&gui), (5000.0, 0.0)));
+
+        // Clicking outside of the panel doesn't jump anywhere
+        gui.event(Event::MouseMovedTo(ScreenPt::new(100.0, 100.0)));
+        gui.event(Event::LeftMouseButtonDown);
+        assert!(close(camera(&gui), (5000.0, 0.0)));
+    });
+
+    t.run_fast("slider_scales_with_hidpi", |_| {
+        // The bar starts 60 logical pixels in from the slider's top-left corner and is 300 wide.
+        let click = |hidpi_factor: f64, x: f64| {
+            let mut gui = Headless::new(1000.0, 800.0, |_| Sliding {
+                slider: Slider::new(Some(ScreenPt::new(10.0, 20.0))),
+            });
+            gui.canvas_mut().set_hidpi_factor(hidpi_factor);
+            gui.event(Event::MouseMovedTo(ScreenPt::new(
+                x,
+                20.0 + hidpi_factor * 70.0,
+            )));
+            gui.event(Event::LeftMouseButtonDown);
+            let percent = gui.gui().slider.get_percent();
+
+            // Dragging past the end pins to it
+            gui.event(Event::MouseMovedTo(ScreenPt::new(10_000.0, 0.0)));
+            assert_eq!(gui.gui().slider.get_percent(), 1.0);
+            percent
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+
+        // The same spot along the bar means the same thing either way
+        assert!(close(click(1.0, 10.0 + 60.0 + 0.3 * 300.0), 0.3));
+        assert!(close(click(2.0, 10.0 + 2.0 * (60.0 + 0.3 * 300.0)), 0.3));
+        // The same physical spot is further along the smaller bar
+        assert!(close(click(1.0, 10.0 + 120.0), 0.2));
+        assert!(close(click(2.0, 10.0 + 120.0), 0.0));
+    });
+
+    t.run_fast("wizard_step_back_and_forward_again", |_| {
+        let mut gui = Headless::new(1800.0, 800.0, |_| Flow::new());
+        type_line(&mut gui, "alice");
+        type_line(&mut gui, "red");
+        assert_eq!(
+            gui.gui().wizard.breadcrumbs(),
+            vec![("Name?", "alice"), ("Color?", "red")]
+        );
+        // Dismiss the acknowledgement
+        gui.press_key(Key::Enter);
+        assert_eq!(gui.gui().wizard.describe_state(), vec!["Size?", ""]);
+
+        // Escape at the last question skips back past the acknowledgement to the second, offering
+        // the old answer again.
+        gui.press_key(Key::Escape);
+        assert!(!gui.gui().wizard.aborted());
+        assert_eq!(gui.gui().wizard.breadcrumbs(), vec![("Name?", "alice")]);
+        assert_eq!(gui.gui().wizard.describe_state(), vec!["Color?", "red"]);
+
+        // Replace the old answer
+        for _ in 0..3 {
+            gui.press_key(Key::Backspace);
+        }
+        type_line(&mut gui, "blue");
+        gui.press_key(Key::Enter);
+        type_line(&mut gui, "big");
+        assert_eq!(
+            gui.gui().answers,
+            Some(vec![
+                "alice".to_string(),
+                "blue".to_string(),
+                "big".to_string()
+            ])
+        );
+        assert_eq!(
+            gui.gui().wizard.breadcrumbs(),
+            vec![("Name?", "alice"), ("Color?", "blue"), ("Size?", "big")]
+        );
+    });
+
+    t.run_fast("wizard_escape_at_first_question_aborts", |_| {
+        let mut gui = Headless::new(1800.0, 800.0, |_| Flow::new());
+        type_line(&mut gui, "alice");
+        // Back to the first question...
+        gui.press_key(Key::Escape);
+        assert!(!gui.gui().wizard.aborted());
+        assert!(gui.gui().wizard.breadcrumbs().is_empty());
+        assert_eq!(gui.gui().wizard.describe_state(), vec!["Name?", "alice"]);
+        // ...and only a second Escape there aborts.
+        gui.press_key(Key::Escape);
+        assert!(gui.gui().wizard.aborted());
+        assert_eq!(gui.gui().answers, None);
+    });
+
+    t.run_fast("quadtree_finds_moved_objects", |_| {
+        let bounds = |x1, y1, x2, y2| Bounds::from(&vec![Pt2D::new(x1, y1), Pt2D::new(x2, y2)]);
+        let mut tree = ObjectQuadtree::new(&bounds(0.0, 0.0, 1000.0, 1000.0));
+        let road = ID::Road(RoadID(0));
+        let lane = ID::Lane(LaneID(0));
+        let intersection = ID::Intersection(IntersectionID(0));
+        tree.insert(road, bounds(10.0, 10.0, 100.0, 20.0));
+        tree.insert(lane, bounds(10.0, 10.0, 100.0, 15.0));
+        tree.insert(intersection, bounds(500.0, 500.0, 520.0, 520.0));
+
+        let near_start = bounds(0.0, 0.0, 50.0, 50.0);
+        let mut found = tree.query(&near_start);
+        found.sort();
+        assert_eq!(found, vec![road, lane]);
+
+        // An edit widens the road and moves it across the map.
+        tree.insert(road, bounds(600.0, 600.0, 900.0, 700.0));
+        assert_eq!(tree.query(&near_start), vec![lane]);
+        let mut found = tree.query(&bounds(510.0, 510.0, 650.0, 650.0));
+        found.sort();
+        assert_eq!(found, vec![road, intersection]);
+        assert!(tree.query(&bounds(200.0, 200.0, 300.0, 300.0)).is_empty());
+    });
+
+    t.run_fast("throughput_sums_to_total_finished", |_| {
+        let mut throughput = Throughput::new();
+        // (full minutes elapsed, total finished trips), as a sim might report them. Sometimes a
+        // big step skips several minutes at once.
+        for (minutes, finished) in vec![(0, 0), (1, 3), (2, 3), (3, 10), (7, 12), (8, 20)] {
+            throughput.record(minutes, finished);
+        }
+        assert_eq!(throughput.per_minute(), &vec![3, 0, 7, 0, 0, 0, 2, 8]);
+        assert_eq!(throughput.per_minute().iter().sum::<usize>(), 20);
+    });
+
+    t.run_fast("text_box_recalls_submitted_lines", |_| {
+        let mut gui = Headless::new(1800.0, 800.0, |_| Naming::new());
+        let current_line = |gui: &Headless<Naming>| gui.gui().wizard.describe_state()[1].clone();
+        type_line(&mut gui, "ab");
+
+        // The next time the same question is asked, Up recalls the answer.
+        gui.press_key(Key::C);
+        gui.press_key(Key::UpArrow);
+        assert_eq!(current_line(&gui), "ab");
+        // Stops at the oldest entry
+        gui.press_key(Key::UpArrow);
+        assert_eq!(current_line(&gui), "ab");
+        // Back to what was being typed
+        gui.press_key(Key::DownArrow);
+        assert_eq!(current_line(&gui), "c");
+        gui.press_key(Key::Enter);
+
+        // Resubmitting the most recent entry doesn't duplicate it, and empty lines aren't kept.
+        gui.press_key(Key::UpArrow);
+        gui.press_key(Key::Enter);
+        gui.press_key(Key::Enter);
+        gui.press_key(Key::UpArrow);
+        assert_eq!(current_line(&gui), "c");
+        gui.press_key(Key::UpArrow);
+        assert_eq!(current_line(&gui), "ab");
+        gui.press_key(Key::UpArrow);
+        assert_eq!(current_line(&gui), "ab");
+        assert_eq!(gui.gui().answers, vec!["ab", "c", "c", ""]);
+    });
+
+    t.run_fast("palette_remaps_without_changing_defaults", |_| {
+        let mut cs = ColorScheme::load().unwrap();
+        let name = "occupied parking spot";
+        assert_eq!(cs.get_modified(name), None);
+        let registered = cs.get(name);
+        let parking_lane = cs.get("parking lane");
+
+        cs.set_palette(Palette::Colorblind);
+        assert_ne!(cs.get(name), registered);
+        assert_eq!(cs.get_def(name, Color::RED), cs.get(name));
+        // Colors without a colorblind variant are untouched.
+        assert_eq!(cs.get("parking lane"), parking_lane);
+
+        // A color somebody picked by hand wins over the palette.
+        cs.override_color(name, Color::PURPLE);
+        assert_eq!(cs.get(name), Color::PURPLE);
+
+        // Going back to the standard palette and forgetting the override restores the default.
+        cs.set_palette(Palette::Standard);
+        cs.reset_modified(name, None);
+        assert_eq!(cs.get(name), registered);
+    });
+
+    t.run_fast("annotations_report_and_search", |_| {
+        let annotations = Annotations {
+            map_name: "montlake".to_string(),
+            annotations: vec![
+                Annotation {
+                    pt: LonLat::new(-122.3, 47.6),
+                    note: "OSM geometry bad".to_string(),
+                },
+                Annotation {
+                    pt: LonLat::new(-122.301234, 47.612345),
+                    note: "Signal timing looks wrong here".to_string(),
+                },
+            ],
+        };
+        assert_eq!(
+            annotations.report(),
+            "2 notes on montlake\n\
+             -122.300000, 47.600000: OSM geometry bad\n\
+             -122.301234, 47.612345: Signal timing looks wrong here"
+        );
+        assert_eq!(
+            annotations.search("signal TIMING").unwrap().pt,
+            LonLat::new(-122.301234, 47.612345)
+        );
+        assert!(annotations.search("parking").is_none());
+    });
+}
+
+// Asks a few questions with an acknowledgement in the middle, like the editor's wizards do.
+struct Flow {
+    wizard: Wizard,
+    answers: Option<Vec<String>>,
+}
+
+impl Flow {
+    fn new() -> Flow {
+        Flow {
+            wizard: Wizard::new(),
+            answers: None,
+        }
+    }
+}
+
+impl GUI for Flow {
+    fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
+        if self.answers.is_none() && !self.wizard.aborted() {
+            self.answers = ask(&mut self.wizard.wrap(ctx));
+        }
+        EventLoopMode::InputOnly
+    }
+
+    fn draw(&self, _: &mut GfxCtx) {}
+}
+
+// Asks the same question over and over
+struct Naming {
+    wizard: Wizard,
+    answers: Vec<String>,
+}
+
+impl Naming {
+    fn new() -> Naming {
+        Naming {
+            wizard: Wizard::new(),
+            answers: Vec::new(),
+        }
+    }
+}
+
+impl GUI for Naming {
+    fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
+        // Don't share history with questions other tests ask
+        if let Some(name) = self.wizard.wrap(ctx).input_string("Name this run?") {
+            self.answers.push(name);
+            self.wizard = Wizard::new();
+        }
+        EventLoopMode::InputOnly
+    }
+
+    fn draw(&self, _: &mut GfxCtx) {}
+}
+
+// A modal menu where only some entries have help text
+struct Helped {
+    menu: ModalMenu,
+}
+
+impl Helped {
+    fn new(ctx: &EventCtx) -> Helped {
+        Helped {
+            menu: ModalMenu::new_with_help(
+                "Helpful",
+                vec![
+                    (hotkey(Key::Escape), "quit", Some("Leave this mode")),
+                    (hotkey(Key::S), "save", None),
+                    (
+                        hotkey(Key::B),
+                        "bulk edit",
+                        Some("Change every lane of this street"),
+                    ),
+                ],
+                ctx,
+            ),
+        }
+    }
+}
+
+impl GUI for Helped {
+    fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
+        self.menu.handle_event(ctx, None);
+        for action in &["quit", "save", "bulk edit"] {
+            self.menu.action(action);
+        }
+        EventLoopMode::InputOnly
+    }
+
+    fn draw(&self, _: &mut GfxCtx) {}
+}
+
+struct Sliding {
+    slider: Slider,
+}
+
+impl GUI for Sliding {
+    fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
+        ctx.canvas.handle_event(ctx.input);
+        self.slider.event(ctx);
+        EventLoopMode::InputOnly
+    }
+
+    fn draw(&self, _: &mut GfxCtx) {}
+}
+
+// Just a minimap over an empty map
+struct Overview {
+    minimap: Minimap,
+    bounds: Bounds,
+}
+
+impl GUI for Overview {
+    fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
+        ctx.canvas.handle_event(ctx.input);
+        self.minimap.event(ctx, &self.bounds);
+        EventLoopMode::InputOnly
+    }
+
+    fn draw(&self, _: &mut GfxCtx) {}
+}
+
+// Picks from a long menu
+struct Chooser {
+    wizard: Wizard,
+    chosen: Option<String>,
+}
+
+impl Chooser {
+    fn new() -> Chooser {
+        Chooser {
+            wizard: Wizard::new(),
+            chosen: None,
+        }
+    }
+}
+
+impl GUI for Chooser {
+    fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
+        if self.chosen.is_none() && !self.wizard.aborted() {
+            self.chosen = self
+                .wizard
+                .wrap(ctx)
+                .choose_something("Pick one", Box::new(hundred_choices))
+                .map(|(name, _)| name);
+        }
+        EventLoopMode::InputOnly
+    }
+
+    fn draw(&self, _: &mut GfxCtx) {}
+}
+
+// Only a few have hotkeys, one of them near the end.
+fn hundred_choices() -> Vec<(Option<MultiKey>, String, ())> {
+    (0..100)
+        .map(|i| {
+            let key = match i {
+                0 => hotkey(Key::A),
+                1 => hotkey(Key::B),
+                95 => hotkey(Key::F),
+                _ => None,
+            };
+            (key, format!("choice {}", i), ())
+        })
+        .collect()
+}
+
+fn ask(wizard: &mut WrappedWizard) -> Option<Vec<String>> {
+    let name = wizard.input_string("Name?")?;
+    let color = wizard.input_string("Color?")?;
+    if !wizard.acknowledge("Noted", vec!["Just one more question"]) {
+        return None;
+    }
+    let size = wizard.input_string("Size?")?;
+    Some(vec![name, color, size])
+}
+
+// Types the lowercase word and presses Enter
+fn type_line<G: GUI>(gui: &mut Headless<G>, word: &str) {
+    for c in word.chars() {
+        gui.press_key(match c {
+            'a' => Key::A,
+            'b' => Key::B,
+            'c' => Key::C,
+            'd' => Key::D,
+            'e' => Key::E,
+            'g' => Key::G,
+            'i' => Key::I,
+            'l' => Key::L,
+            'r' => Key::R,
+            'u' => Key::U,
+            _ => panic!("type_line doesn't know how to type {}", c),
+        });
+    }
+    gui.press_key(Key::Enter);
+}
+
+fn start(run_name: &str) -> Headless<GameState> {
+    let flags = Flags {
+        sim_flags: SimFlags::for_test(run_name),
+        kml: None,
+        dont_draw_lane_markings: false,
+        enable_profiler: false,
+        num_agents: None,
+        no_splash: true,
+        secondary_window: false,
+        ignore_hidpi: false,
+    };
+    let mut gui = Headless::new(1800.0, 800.0, |ctx| GameState::new(flags, ctx));
+    // Close enough to mouseover individual objects
+    gui.canvas_mut().cam_zoom = 10.0;
+    gui
+}
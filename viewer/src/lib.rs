@@ -1,4 +1,4 @@
-use aabb_quadtree::QuadTree;
+use aabb_quadtree::{ItemId, QuadTree};
 use ezgui::{Color, Drawable, EventCtx, GfxCtx, Prerender, Text};
 use geom::{Bounds, Circle, Distance, Polygon, Pt2D};
 use std::collections::{HashMap, HashSet};
@@ -14,6 +14,7 @@ struct Object {
     polygon: Polygon,
     draw: Drawable,
     info: Text,
+    quadtree_id: Option<ItemId>,
 }
 
 pub struct World<ID: ObjectID> {
@@ -81,7 +82,8 @@ impl<ID: ObjectID> World<ID> {
         color: Color,
         info: Text,
     ) {
-        self.quadtree
+        let quadtree_id = self
+            .quadtree
             .insert_with_box(id, polygon.get_bounds().as_bbox());
         let draw = prerender.upload_borrowed(vec![(color, &polygon)]);
         self.objects.insert(
@@ -90,10 +92,20 @@ impl<ID: ObjectID> World<ID> {
                 polygon,
                 draw,
                 info,
+                quadtree_id,
             },
         );
     }
 
+    // Does nothing if the object doesn't exist.
+    pub fn delete_obj(&mut self, id: ID) {
+        if let Some(obj) = self.objects.remove(&id) {
+            if let Some(quadtree_id) = obj.quadtree_id {
+                self.quadtree.remove(quadtree_id);
+            }
+        }
+    }
+
     pub fn get_center(&self, id: ID) -> Pt2D {
         self.objects[&id].polygon.center()
     }